@@ -1076,6 +1076,7 @@ pub enum AcpThreadEvent {
     ToolAuthorizationRequested(acp::ToolCallId),
     ToolAuthorizationReceived(acp::ToolCallId),
     Retry(RetryStatus),
+    Warning(SharedString),
     SubagentSpawned(acp::SessionId),
     Stopped(acp::StopReason),
     Error,
@@ -1708,6 +1709,10 @@ impl AcpThread {
         cx.emit(AcpThreadEvent::Retry(status));
     }
 
+    pub fn show_warning(&mut self, warning: SharedString, cx: &mut Context<Self>) {
+        cx.emit(AcpThreadEvent::Warning(warning));
+    }
+
     pub fn update_tool_call(
         &mut self,
         update: impl Into<ToolCallUpdate>,
@@ -2586,6 +2591,9 @@ impl AcpThread {
             }
 
             let format_on_save = cx.update(|cx| {
+                project.update(cx, |project, cx| {
+                    project.agent_will_edit_buffer(&buffer, cx)
+                });
                 action_log.update(cx, |action_log, cx| {
                     action_log.buffer_read(buffer.clone(), cx);
                 });
@@ -2673,6 +2681,7 @@ impl AcpThread {
                 let (task_command, task_args) =
                     ShellBuilder::new(&Shell::Program(shell), is_windows)
                         .redirect_stdin_to_dev_null()
+                        .report_command_marks()
                         .build(Some(command.clone()), &args);
                 let terminal = project
                     .update(cx, |project, cx| {
@@ -3156,6 +3165,8 @@ mod tests {
                     vec![],
                     0,
                     false,
+                    false,
+                    false,
                     0,
                     Some(completion_tx),
                     cx,
@@ -1,5 +1,7 @@
+mod budget;
 mod db;
 mod edit_agent;
+mod hooks;
 mod legacy_thread;
 mod native_agent_server;
 pub mod outline;
@@ -13,6 +15,7 @@ mod tool_permissions;
 mod tools;
 
 use context_server::ContextServerId;
+pub use budget::*;
 pub use db::*;
 use itertools::Itertools;
 pub use native_agent_server::NativeAgentServer;
@@ -29,6 +32,7 @@ use acp_thread::{
     AgentSessionListResponse, TokenUsageRatio, UserMessageId,
 };
 use agent_client_protocol as acp;
+use agent_settings::{AgentSettings, TerminalSandboxSettings};
 use anyhow::{Context as _, Result, anyhow};
 use chrono::{DateTime, Utc};
 use collections::{HashMap, HashSet, IndexMap};
@@ -47,7 +51,7 @@ use prompt_store::{
     WorktreeContext,
 };
 use serde::{Deserialize, Serialize};
-use settings::{LanguageModelSelection, update_settings_file};
+use settings::{LanguageModelSelection, Settings as _, update_settings_file};
 use std::any::Any;
 use std::path::PathBuf;
 use std::rc::Rc;
@@ -1244,6 +1248,11 @@ impl NativeAgentConnection {
                                     thread.update_retry_status(status, cx)
                                 })?;
                             }
+                            ThreadEvent::Warning(warning) => {
+                                acp_thread.update(cx, |thread, cx| {
+                                    thread.show_warning(warning, cx)
+                                })?;
+                            }
                             ThreadEvent::Stop(stop_reason) => {
                                 log::debug!("Assistant message complete: {:?}", stop_reason);
                                 return Ok(acp::PromptResponse::new(stop_reason));
@@ -1749,7 +1758,11 @@ impl acp_thread::AgentSessionRetry for NativeAgentSessionRetry {
     fn run(&self, cx: &mut App) -> Task<Result<acp::PromptResponse>> {
         self.connection
             .run_turn(self.session_id.clone(), cx, |thread, cx| {
-                thread.update(cx, |thread, cx| thread.resume(cx))
+                thread.update(cx, |thread, cx| {
+                    // Retrying after a budget pause is how the user chooses to continue.
+                    thread.continue_past_budget_pause();
+                    thread.resume(cx)
+                })
             })
     }
 }
@@ -1908,6 +1921,16 @@ impl ThreadEnvironment for NativeThreadEnvironment {
         self.create_subagent_thread(label, cx)
     }
 
+    fn terminal_sandbox(&self, cx: &App) -> Option<TerminalSandboxSettings> {
+        let thread = self.thread.upgrade()?;
+        let profile_id = thread.read(cx).profile();
+        AgentSettings::get_global(cx)
+            .profiles
+            .get(profile_id)?
+            .terminal_sandbox
+            .clone()
+    }
+
     fn resume_subagent(
         &self,
         session_id: acp::SessionId,
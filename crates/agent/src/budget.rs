@@ -0,0 +1,491 @@
+//! Token and cost budgets for the native agent.
+//!
+//! Spend is recorded per request in the threads database, keyed by local date,
+//! thread, profile, and model. Before each request, the thread compares what
+//! has been spent against the thread, profile, and daily budgets from
+//! [`AgentSettings`], and either warns, pauses until the user chooses to
+//! continue, or stops.
+
+use crate::db::ThreadsDatabase;
+use agent_settings::{AgentBudget, AgentProfileId, AgentSettings};
+use anyhow::{Result, anyhow};
+use chrono::{Local, NaiveDate};
+use collections::{HashMap, HashSet};
+use gpui::{App, Task};
+use language_model::{LanguageModel, LanguageModelCostInfo, TokenUsage};
+use settings::{AgentBudgetAction, LanguageModelPricing, Settings as _};
+use std::{fmt, ops::Add, sync::Arc};
+
+/// Tokens used and their estimated cost, in US dollars.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Spend {
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+impl Add for Spend {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            tokens: self.tokens + other.tokens,
+            cost: self.cost + other.cost,
+        }
+    }
+}
+
+impl Spend {
+    fn saturating_sub(self, other: Self) -> Self {
+        Self {
+            tokens: self.tokens.saturating_sub(other.tokens),
+            cost: (self.cost - other.cost).max(0.),
+        }
+    }
+}
+
+/// What has been spent in each budget's scope.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct SpendTotals {
+    pub thread: Spend,
+    /// Spent today by threads using the current profile.
+    pub profile: Spend,
+    /// Spent today by all threads.
+    pub daily: Spend,
+}
+
+impl SpendTotals {
+    fn get(&self, scope: &BudgetScope) -> Spend {
+        match scope {
+            BudgetScope::Thread => self.thread,
+            BudgetScope::Profile(_) => self.profile,
+            BudgetScope::Daily => self.daily,
+        }
+    }
+}
+
+/// One request's worth of spend, as stored in the database.
+#[derive(Clone, Debug)]
+pub struct SpendRecord {
+    pub date: NaiveDate,
+    pub thread_id: Arc<str>,
+    pub profile_id: AgentProfileId,
+    pub provider: String,
+    pub model: String,
+    pub usage: TokenUsage,
+    pub cost: f64,
+}
+
+/// Spend aggregated for a single model.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ModelSpend {
+    pub provider: String,
+    pub model: String,
+    pub usage: TokenUsage,
+    pub cost: f64,
+}
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+/// Estimates the cost of `usage` in US dollars, using `agent.model_pricing`
+/// or, failing that, the prices reported by the model's provider.
+pub fn estimate_cost(usage: &TokenUsage, model: &Arc<dyn LanguageModel>, cx: &App) -> f64 {
+    if let Some(pricing) = AgentSettings::pricing_for_model(model, cx) {
+        return cost_from_pricing(usage, &pricing);
+    }
+    match model.model_cost_info() {
+        Some(LanguageModelCostInfo::TokenCost {
+            input_token_cost_per_1m,
+            output_token_cost_per_1m,
+        }) => cost_from_pricing(
+            usage,
+            &LanguageModelPricing {
+                input_cost_per_million: Some(input_token_cost_per_1m),
+                output_cost_per_million: Some(output_token_cost_per_1m),
+                ..Default::default()
+            },
+        ),
+        // Per-request costs are multipliers of a subscription's request
+        // allowance rather than currency, so they can't be estimated.
+        Some(LanguageModelCostInfo::RequestCost { .. }) | None => 0.,
+    }
+}
+
+fn cost_from_pricing(usage: &TokenUsage, pricing: &LanguageModelPricing) -> f64 {
+    let input = pricing.input_cost_per_million.unwrap_or_default();
+    let output = pricing.output_cost_per_million.unwrap_or_default();
+    let cache_read = pricing.cache_read_cost_per_million.unwrap_or(input);
+    let cache_write = pricing.cache_write_cost_per_million.unwrap_or(input);
+    (usage.input_tokens as f64 * input
+        + usage.output_tokens as f64 * output
+        + usage.cache_read_input_tokens as f64 * cache_read
+        + usage.cache_creation_input_tokens as f64 * cache_write)
+        / 1_000_000.
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum BudgetScope {
+    Thread,
+    Profile(AgentProfileId),
+    Daily,
+}
+
+impl fmt::Display for BudgetScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetScope::Thread => write!(f, "thread budget"),
+            BudgetScope::Profile(profile_id) => {
+                write!(f, "daily budget for the `{profile_id}` profile")
+            }
+            BudgetScope::Daily => write!(f, "daily budget"),
+        }
+    }
+}
+
+/// Returns the budgets that apply to a thread using the given profile.
+pub fn active_budgets(profile_id: &AgentProfileId, cx: &App) -> Vec<(BudgetScope, AgentBudget)> {
+    let settings = AgentSettings::get_global(cx);
+    let profile_budget = settings
+        .profiles
+        .get(profile_id)
+        .and_then(|profile| profile.budget);
+    [
+        settings
+            .budgets
+            .thread
+            .map(|budget| (BudgetScope::Thread, budget)),
+        profile_budget.map(|budget| (BudgetScope::Profile(profile_id.clone()), budget)),
+        settings
+            .budgets
+            .daily
+            .map(|budget| (BudgetScope::Daily, budget)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+/// Returned from a turn when a budget with the `pause` or `stop` action is used up.
+#[derive(Clone, Debug, PartialEq, thiserror::Error)]
+#[error("{message}")]
+pub struct BudgetExceededError {
+    pub scope: BudgetScope,
+    /// Whether the user may continue past the budget, which grants the thread
+    /// another budget's worth of spend.
+    pub can_continue: bool,
+    pub message: String,
+}
+
+/// Tracks which budget warnings a thread has shown, and which budgets the user
+/// has chosen to continue past.
+#[derive(Debug, Default)]
+pub struct BudgetState {
+    warned: HashSet<BudgetScope>,
+    warned_exhausted: HashSet<BudgetScope>,
+    paused: Option<(BudgetScope, Spend)>,
+    allowances: HashMap<BudgetScope, Spend>,
+}
+
+impl BudgetState {
+    /// Checks `totals` against `budgets`, returning any new warnings to show,
+    /// or an error if the thread must not make another request.
+    pub fn check(
+        &mut self,
+        budgets: &[(BudgetScope, AgentBudget)],
+        totals: &SpendTotals,
+    ) -> Result<Vec<String>, BudgetExceededError> {
+        let mut warnings = Vec::new();
+        for (scope, budget) in budgets {
+            let total = totals.get(scope);
+            let spent = self
+                .allowances
+                .get(scope)
+                .map_or(total, |allowance| total.saturating_sub(*allowance));
+            let fraction = budget_fraction(budget, spent);
+
+            if fraction >= 1. {
+                let message = format!(
+                    "The {scope} of {} has been used up ({} spent).",
+                    describe_limit(budget),
+                    describe_spend(budget, spent)
+                );
+                match budget.action {
+                    AgentBudgetAction::Warn => {
+                        if self.warned_exhausted.insert(scope.clone()) {
+                            warnings.push(message);
+                        }
+                    }
+                    AgentBudgetAction::Pause => {
+                        self.paused = Some((scope.clone(), total));
+                        return Err(BudgetExceededError {
+                            scope: scope.clone(),
+                            can_continue: true,
+                            message,
+                        });
+                    }
+                    AgentBudgetAction::Stop => {
+                        return Err(BudgetExceededError {
+                            scope: scope.clone(),
+                            can_continue: false,
+                            message,
+                        });
+                    }
+                }
+            } else if fraction >= budget.warn_at as f64 && self.warned.insert(scope.clone()) {
+                warnings.push(format!(
+                    "{:.0}% of the {scope} of {} has been used ({} spent).",
+                    fraction * 100.,
+                    describe_limit(budget),
+                    describe_spend(budget, spent)
+                ));
+            }
+        }
+        Ok(warnings)
+    }
+
+    /// Lets the thread continue past the budget it last paused for, until it
+    /// has spent another budget's worth.
+    pub fn continue_past_pause(&mut self) {
+        if let Some((scope, spent)) = self.paused.take() {
+            self.warned.remove(&scope);
+            self.warned_exhausted.remove(&scope);
+            self.allowances.insert(scope, spent);
+        }
+    }
+}
+
+fn budget_fraction(budget: &AgentBudget, spent: Spend) -> f64 {
+    let tokens = budget.max_tokens.map_or(0., |max_tokens| {
+        spent.tokens as f64 / max_tokens.max(1) as f64
+    });
+    let cost = budget.max_cost.map_or(0., |max_cost| {
+        if max_cost > 0. {
+            spent.cost / max_cost
+        } else {
+            f64::INFINITY
+        }
+    });
+    tokens.max(cost)
+}
+
+fn describe_limit(budget: &AgentBudget) -> String {
+    match (budget.max_tokens, budget.max_cost) {
+        (Some(max_tokens), Some(max_cost)) => {
+            format!(
+                "{} tokens or {}",
+                format_tokens(max_tokens),
+                format_cost(max_cost)
+            )
+        }
+        (Some(max_tokens), None) => format!("{} tokens", format_tokens(max_tokens)),
+        (None, Some(max_cost)) => format_cost(max_cost),
+        (None, None) => "nothing".to_string(),
+    }
+}
+
+fn describe_spend(budget: &AgentBudget, spent: Spend) -> String {
+    match (budget.max_tokens, budget.max_cost) {
+        (Some(_), Some(_)) => format!(
+            "{} tokens, {}",
+            format_tokens(spent.tokens),
+            format_cost(spent.cost)
+        ),
+        (None, Some(_)) => format_cost(spent.cost),
+        _ => format!("{} tokens", format_tokens(spent.tokens)),
+    }
+}
+
+fn format_tokens(tokens: u64) -> String {
+    let digits = tokens.to_string();
+    let mut formatted = String::with_capacity(digits.len() + digits.len() / 3);
+    for (ix, digit) in digits.chars().enumerate() {
+        if ix > 0 && (digits.len() - ix) % 3 == 0 {
+            formatted.push(',');
+        }
+        formatted.push(digit);
+    }
+    formatted
+}
+
+fn format_cost(cost: f64) -> String {
+    format!("${cost:.2}")
+}
+
+/// Renders the spend recorded today and over all time, per model, as Markdown.
+pub fn spend_report(cx: &mut App) -> Task<Result<String>> {
+    let db = ThreadsDatabase::connect(cx);
+    cx.background_spawn(async move {
+        let db = db.await.map_err(|error| anyhow!(error))?;
+        let today_spend = db.spend_by_model(Some(today())).await?;
+        let all_time_spend = db.spend_by_model(None).await?;
+
+        let mut report = String::from("# Agent Spend\n\n");
+        report.push_str(
+            "Costs are estimates based on `agent.model_pricing` and the prices reported by each provider.\n",
+        );
+        for (title, spend) in [("Today", today_spend), ("All Time", all_time_spend)] {
+            report.push_str(&format!("\n## {title}\n\n"));
+            if spend.is_empty() {
+                report.push_str("No usage recorded.\n");
+                continue;
+            }
+            report.push_str(
+                "| Provider | Model | Input | Output | Cache Write | Cache Read | Est. Cost |\n",
+            );
+            report.push_str("|---|---|---:|---:|---:|---:|---:|\n");
+            let mut total = ModelSpend {
+                provider: String::new(),
+                model: String::new(),
+                usage: TokenUsage::default(),
+                cost: 0.,
+            };
+            for row in &spend {
+                report.push_str(&model_spend_row(&row.provider, &row.model, row));
+                total.usage = total.usage + row.usage;
+                total.cost += row.cost;
+            }
+            report.push_str(&model_spend_row("**Total**", "", &total));
+        }
+        Ok(report)
+    })
+}
+
+fn model_spend_row(provider: &str, model: &str, spend: &ModelSpend) -> String {
+    format!(
+        "| {provider} | {model} | {} | {} | {} | {} | {} |\n",
+        format_tokens(spend.usage.input_tokens),
+        format_tokens(spend.usage.output_tokens),
+        format_tokens(spend.usage.cache_creation_input_tokens),
+        format_tokens(spend.usage.cache_read_input_tokens),
+        format_cost(spend.cost),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn budget(
+        max_tokens: Option<u64>,
+        max_cost: Option<f64>,
+        action: AgentBudgetAction,
+    ) -> AgentBudget {
+        AgentBudget {
+            max_tokens,
+            max_cost,
+            warn_at: AgentBudget::DEFAULT_WARN_AT,
+            action,
+        }
+    }
+
+    fn thread_spend(tokens: u64, cost: f64) -> SpendTotals {
+        SpendTotals {
+            thread: Spend { tokens, cost },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_cost_from_pricing() {
+        let usage = TokenUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 500_000,
+            cache_creation_input_tokens: 0,
+            cache_read_input_tokens: 2_000_000,
+        };
+        let pricing = LanguageModelPricing {
+            input_cost_per_million: Some(3.),
+            output_cost_per_million: Some(15.),
+            cache_read_cost_per_million: Some(0.3),
+            ..Default::default()
+        };
+        assert_eq!(cost_from_pricing(&usage, &pricing), 3. + 7.5 + 0.6);
+
+        let pricing = LanguageModelPricing {
+            input_cost_per_million: Some(1.),
+            ..Default::default()
+        };
+        assert_eq!(cost_from_pricing(&usage, &pricing), 3.);
+    }
+
+    #[test]
+    fn test_budget_warns_once() {
+        let budgets = [(
+            BudgetScope::Thread,
+            budget(Some(1000), None, AgentBudgetAction::Stop),
+        )];
+        let mut state = BudgetState::default();
+
+        assert_eq!(state.check(&budgets, &thread_spend(500, 0.)), Ok(vec![]));
+        assert_eq!(
+            state.check(&budgets, &thread_spend(850, 0.)),
+            Ok(vec![
+                "85% of the thread budget of 1,000 tokens has been used (850 tokens spent)."
+                    .to_string()
+            ])
+        );
+        assert_eq!(state.check(&budgets, &thread_spend(900, 0.)), Ok(vec![]));
+
+        let error = state.check(&budgets, &thread_spend(1200, 0.)).unwrap_err();
+        assert!(!error.can_continue);
+        assert_eq!(
+            error.message,
+            "The thread budget of 1,000 tokens has been used up (1,200 tokens spent)."
+        );
+    }
+
+    #[test]
+    fn test_continue_past_paused_budget() {
+        let budgets = [(
+            BudgetScope::Daily,
+            budget(None, Some(2.), AgentBudgetAction::Pause),
+        )];
+        let totals = |cost| SpendTotals {
+            daily: Spend { tokens: 0, cost },
+            ..Default::default()
+        };
+        let mut state = BudgetState::default();
+
+        let error = state.check(&budgets, &totals(2.5)).unwrap_err();
+        assert!(error.can_continue);
+        assert_eq!(error.scope, BudgetScope::Daily);
+        assert!(state.check(&budgets, &totals(2.5)).is_err());
+
+        state.continue_past_pause();
+        assert_eq!(state.check(&budgets, &totals(3.)), Ok(vec![]));
+        assert!(state.check(&budgets, &totals(4.5)).is_err());
+    }
+
+    #[test]
+    fn test_warn_action_keeps_going() {
+        let budgets = [(
+            BudgetScope::Profile(AgentProfileId("ask".into())),
+            budget(Some(100), Some(1.), AgentBudgetAction::Warn),
+        )];
+        let totals = SpendTotals {
+            profile: Spend {
+                tokens: 50,
+                cost: 1.25,
+            },
+            ..Default::default()
+        };
+        let mut state = BudgetState::default();
+
+        assert_eq!(
+            state.check(&budgets, &totals),
+            Ok(vec![
+                "The daily budget for the `ask` profile of 100 tokens or $1.00 has been used up (50 tokens, $1.25 spent).".to_string()
+            ])
+        );
+        assert_eq!(state.check(&budgets, &totals), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_format_tokens() {
+        assert_eq!(format_tokens(0), "0");
+        assert_eq!(format_tokens(999), "999");
+        assert_eq!(format_tokens(1000), "1,000");
+        assert_eq!(format_tokens(12_345_678), "12,345,678");
+    }
+}
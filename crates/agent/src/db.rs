@@ -1,14 +1,17 @@
-use crate::{AgentMessage, AgentMessageContent, UserMessage, UserMessageContent};
+use crate::{
+    AgentMessage, AgentMessageContent, ModelSpend, Spend, SpendRecord, SpendTotals, UserMessage,
+    UserMessageContent,
+};
 use acp_thread::UserMessageId;
 use agent_client_protocol as acp;
 use agent_settings::AgentProfileId;
 use anyhow::{Result, anyhow};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use collections::{HashMap, IndexMap};
 use futures::{FutureExt, future::Shared};
 use gpui::{BackgroundExecutor, Global, Task};
 use indoc::indoc;
-use language_model::Speed;
+use language_model::{Speed, TokenUsage};
 use parking_lot::Mutex;
 use serde::{Deserialize, Serialize};
 use sqlez::{
@@ -432,6 +435,23 @@ impl ThreadsDatabase {
             }
         }
 
+        connection.exec(indoc! {"
+            CREATE TABLE IF NOT EXISTS spend (
+                date TEXT NOT NULL,
+                thread_id TEXT NOT NULL,
+                profile_id TEXT NOT NULL,
+                provider TEXT NOT NULL,
+                model TEXT NOT NULL,
+                input_tokens INTEGER NOT NULL,
+                output_tokens INTEGER NOT NULL,
+                cache_creation_input_tokens INTEGER NOT NULL,
+                cache_read_input_tokens INTEGER NOT NULL,
+                cost REAL NOT NULL,
+                PRIMARY KEY (date, thread_id, profile_id, provider, model)
+            )
+        "})?()
+        .map_err(|e| anyhow!("Failed to create spend table: {}", e))?;
+
         let db = Self {
             executor,
             connection: Arc::new(Mutex::new(connection)),
@@ -612,6 +632,123 @@ impl ThreadsDatabase {
         })
     }
 
+    pub fn record_spend(&self, record: SpendRecord) -> Task<Result<()>> {
+        let connection = self.connection.clone();
+
+        self.executor.spawn(async move {
+            let connection = connection.lock();
+
+            let mut insert = connection.exec_bound::<(String, Arc<str>, Arc<str>, String, String, u64, u64, u64, u64, f64)>(indoc! {"
+                INSERT INTO spend (date, thread_id, profile_id, provider, model, input_tokens, output_tokens, cache_creation_input_tokens, cache_read_input_tokens, cost)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+                ON CONFLICT(date, thread_id, profile_id, provider, model) DO UPDATE SET
+                    input_tokens = input_tokens + excluded.input_tokens,
+                    output_tokens = output_tokens + excluded.output_tokens,
+                    cache_creation_input_tokens = cache_creation_input_tokens + excluded.cache_creation_input_tokens,
+                    cache_read_input_tokens = cache_read_input_tokens + excluded.cache_read_input_tokens,
+                    cost = cost + excluded.cost
+            "})?;
+
+            insert((
+                record.date.to_string(),
+                record.thread_id,
+                record.profile_id.0,
+                record.provider,
+                record.model,
+                record.usage.input_tokens,
+                record.usage.output_tokens,
+                record.usage.cache_creation_input_tokens,
+                record.usage.cache_read_input_tokens,
+                record.cost,
+            ))?;
+
+            Ok(())
+        })
+    }
+
+    /// Returns what the given thread has spent, and what has been spent on `date`
+    /// by the given profile and by all threads.
+    pub fn spend_totals(
+        &self,
+        thread_id: Arc<str>,
+        profile_id: AgentProfileId,
+        date: NaiveDate,
+    ) -> Task<Result<SpendTotals>> {
+        const SPEND: &str = "COALESCE(SUM(input_tokens + output_tokens + cache_creation_input_tokens + cache_read_input_tokens), 0), COALESCE(SUM(cost), 0.0)";
+        let connection = self.connection.clone();
+
+        self.executor.spawn(async move {
+            let connection = connection.lock();
+            let date = date.to_string();
+
+            let mut select_thread = connection.select_row_bound::<Arc<str>, (u64, f64)>(
+                &format!("SELECT {SPEND} FROM spend WHERE thread_id = ?"),
+            )?;
+            let mut select_profile = connection
+                .select_row_bound::<(String, Arc<str>), (u64, f64)>(&format!(
+                    "SELECT {SPEND} FROM spend WHERE date = ? AND profile_id = ?"
+                ))?;
+            let mut select_daily = connection.select_row_bound::<String, (u64, f64)>(&format!(
+                "SELECT {SPEND} FROM spend WHERE date = ?"
+            ))?;
+
+            let spend = |row: Option<(u64, f64)>| {
+                let (tokens, cost) = row.unwrap_or_default();
+                Spend { tokens, cost }
+            };
+            Ok(SpendTotals {
+                thread: spend(select_thread(thread_id)?),
+                profile: spend(select_profile((date.clone(), profile_id.0))?),
+                daily: spend(select_daily(date)?),
+            })
+        })
+    }
+
+    /// Returns spend aggregated per model, since `since` or over all time.
+    pub fn spend_by_model(&self, since: Option<NaiveDate>) -> Task<Result<Vec<ModelSpend>>> {
+        let connection = self.connection.clone();
+
+        self.executor.spawn(async move {
+            let connection = connection.lock();
+
+            let mut select = connection
+                .select_bound::<String, (String, String, u64, u64, u64, u64, f64)>(indoc! {"
+                SELECT provider, model, SUM(input_tokens), SUM(output_tokens), SUM(cache_creation_input_tokens), SUM(cache_read_input_tokens), SUM(cost)
+                FROM spend
+                WHERE date >= ?
+                GROUP BY provider, model
+                ORDER BY SUM(cost) DESC, provider, model
+            "})?;
+
+            let since = since.map(|date| date.to_string()).unwrap_or_default();
+            let rows = select(since)?;
+            Ok(rows
+                .into_iter()
+                .map(
+                    |(
+                        provider,
+                        model,
+                        input_tokens,
+                        output_tokens,
+                        cache_creation_input_tokens,
+                        cache_read_input_tokens,
+                        cost,
+                    )| ModelSpend {
+                        provider,
+                        model,
+                        usage: TokenUsage {
+                            input_tokens,
+                            output_tokens,
+                            cache_creation_input_tokens,
+                            cache_read_input_tokens,
+                        },
+                        cost,
+                    },
+                )
+                .collect())
+        })
+    }
+
     pub fn delete_threads(&self) -> Task<Result<()>> {
         let connection = self.connection.clone();
 
@@ -154,6 +154,9 @@ impl EditAgent {
         let (parse_task, parse_rx) = Self::parse_create_file_chunks(edit_chunks, cx);
         let this = self.clone();
         let task = cx.spawn(async move |cx| {
+            this.project.update(cx, |project, cx| {
+                project.agent_will_edit_buffer(&buffer, cx)
+            });
             this.action_log
                 .update(cx, |log, cx| log.buffer_created(buffer.clone(), cx));
             this.overwrite_with_chunks_internal(buffer, parse_rx, output_events_tx, cx)
@@ -286,6 +289,9 @@ impl EditAgent {
         output_events: mpsc::UnboundedSender<EditAgentOutputEvent>,
         cx: &mut AsyncApp,
     ) -> Result<EditAgentOutput> {
+        self.project.update(cx, |project, cx| {
+            project.agent_will_edit_buffer(&buffer, cx)
+        });
         self.action_log
             .update(cx, |log, cx| log.buffer_read(buffer.clone(), cx));
 
@@ -0,0 +1,361 @@
+//! Lifecycle hooks that run user-configured commands around agent activity.
+//!
+//! Each hook receives a [`HookRequest`] as JSON on stdin. A hook that exits
+//! with status 0 may print a [`HookResponse`] as JSON on stdout; any other
+//! non-empty output is treated as additional context for the model. Exiting
+//! with [`BLOCKING_EXIT_CODE`] denies the action, using stderr as the reason.
+//! A `pre_tool_use` hook that fails in any other way denies the tool call, as
+//! it may be guarding against it. Failures of other hooks are logged and
+//! otherwise ignored, so a broken hook can't wedge the agent.
+
+use agent_settings::AgentHook;
+use anyhow::{Context as _, Result};
+use futures::{AsyncReadExt as _, AsyncWriteExt as _, FutureExt as _};
+use gpui::BackgroundExecutor;
+use serde::{Deserialize, Serialize};
+use std::{io, path::PathBuf, sync::Arc};
+use util::command::Stdio;
+
+/// Exit code a hook uses to deny the action it was run for.
+pub const BLOCKING_EXIT_CODE: i32 = 2;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HookRequest {
+    pub session_id: String,
+    /// The root of the first visible worktree, which hooks are run from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+    #[serde(flatten)]
+    pub event: HookEvent,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum HookEvent {
+    PreToolUse {
+        tool_name: Arc<str>,
+        tool_input: serde_json::Value,
+    },
+    PostToolUse {
+        tool_name: Arc<str>,
+        tool_input: serde_json::Value,
+        tool_output: serde_json::Value,
+        is_error: bool,
+    },
+    Stop {
+        stop_reason: String,
+    },
+    UserPromptSubmit {
+        prompt: String,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookDecision {
+    Allow,
+    Deny,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct HookResponse {
+    pub decision: Option<HookDecision>,
+    pub reason: Option<String>,
+    /// Replacement input for the tool call. Only honored by `pre_tool_use` hooks.
+    pub updated_input: Option<serde_json::Value>,
+    pub additional_context: Option<String>,
+}
+
+/// The combined result of running every hook registered for an event.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HookOutcome {
+    /// Set when a hook denied the action, with the reason it gave.
+    pub denied: Option<String>,
+    pub updated_input: Option<serde_json::Value>,
+    pub additional_context: Vec<String>,
+}
+
+impl HookOutcome {
+    /// Appends any context added by hooks to `text`.
+    pub fn append_context_to(&self, text: &mut String) {
+        for context in &self.additional_context {
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+            text.push_str(context);
+        }
+    }
+}
+
+/// Runs `hooks` one after another for `request`.
+///
+/// When a `pre_tool_use` hook rewrites the tool input, later hooks see the
+/// rewritten input. Evaluation stops at the first hook that denies the action.
+pub async fn run_hooks(
+    hooks: Vec<AgentHook>,
+    mut request: HookRequest,
+    executor: BackgroundExecutor,
+) -> HookOutcome {
+    let mut outcome = HookOutcome::default();
+    for hook in hooks {
+        let response = match run_hook(&hook, &request, &executor).await {
+            Ok(response) => response,
+            Err(error) => {
+                log::error!("Agent hook `{}` failed: {error:#}", hook.command);
+                match failed_hook_response(&hook, &request.event, &error) {
+                    Some(response) => response,
+                    None => continue,
+                }
+            }
+        };
+
+        if let Some(context) = response
+            .additional_context
+            .filter(|context| !context.trim().is_empty())
+        {
+            outcome.additional_context.push(context);
+        }
+
+        if response.decision == Some(HookDecision::Deny) {
+            outcome.denied = Some(
+                response
+                    .reason
+                    .filter(|reason| !reason.trim().is_empty())
+                    .unwrap_or_else(|| format!("Denied by hook `{}`", hook.command)),
+            );
+            break;
+        }
+
+        if let Some(updated_input) = response.updated_input
+            && let HookEvent::PreToolUse { tool_input, .. } = &mut request.event
+        {
+            *tool_input = updated_input.clone();
+            outcome.updated_input = Some(updated_input);
+        }
+    }
+    outcome
+}
+
+/// The response standing in for a hook that failed to run, if it shouldn't be ignored.
+fn failed_hook_response(
+    hook: &AgentHook,
+    event: &HookEvent,
+    error: &anyhow::Error,
+) -> Option<HookResponse> {
+    match event {
+        HookEvent::PreToolUse { .. } => Some(HookResponse {
+            decision: Some(HookDecision::Deny),
+            reason: Some(format!("Hook `{}` failed: {error:#}", hook.command)),
+            ..Default::default()
+        }),
+        HookEvent::PostToolUse { .. }
+        | HookEvent::Stop { .. }
+        | HookEvent::UserPromptSubmit { .. } => None,
+    }
+}
+
+async fn run_hook(
+    hook: &AgentHook,
+    request: &HookRequest,
+    executor: &BackgroundExecutor,
+) -> Result<HookResponse> {
+    let mut command = util::command::new_command(&hook.command);
+    command
+        .args(&hook.args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    if let Some(cwd) = &request.cwd {
+        command.current_dir(cwd);
+    }
+
+    let mut child = command.spawn().context("failed to spawn hook command")?;
+    let payload = serde_json::to_vec(request)?;
+    let mut stdin = child.stdin.take().context("failed to acquire stdin")?;
+    let mut stdout = child.stdout.take().context("failed to acquire stdout")?;
+    let mut stderr = child.stderr.take().context("failed to acquire stderr")?;
+    // Writing the payload and reading the output happen at once and are timed as well,
+    // as a hook that never reads its stdin, or fills its stdout, would otherwise block forever.
+    let output = async move {
+        let write_stdin = async move {
+            stdin.write_all(&payload).await?;
+            stdin.flush().await
+        };
+        let mut stdout_bytes = Vec::new();
+        let mut stderr_bytes = Vec::new();
+        let (write_result, stdout_result, stderr_result) = futures::join!(
+            write_stdin,
+            stdout.read_to_end(&mut stdout_bytes),
+            stderr.read_to_end(&mut stderr_bytes),
+        );
+        // The exit status decides, even if the hook exited without reading its input,
+        // as it does when denying an action right away.
+        let status = child.status().await?;
+        match write_result {
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => {}
+            result => result.context("failed to write the hook request")?,
+        }
+        stdout_result.context("failed to read the hook stdout")?;
+        stderr_result.context("failed to read the hook stderr")?;
+        anyhow::Ok((status, stdout_bytes, stderr_bytes))
+    };
+
+    let (status, stdout, stderr) = futures::select_biased! {
+        output = output.fuse() => output?,
+        _ = executor.timer(hook.timeout).fuse() => {
+            anyhow::bail!("timed out after {:?}", hook.timeout);
+        }
+    };
+
+    parse_hook_output(
+        status.code(),
+        &String::from_utf8_lossy(&stdout),
+        &String::from_utf8_lossy(&stderr),
+    )
+}
+
+fn parse_hook_output(exit_code: Option<i32>, stdout: &str, stderr: &str) -> Result<HookResponse> {
+    match exit_code {
+        Some(0) => {
+            let stdout = stdout.trim();
+            if stdout.is_empty() {
+                return Ok(HookResponse::default());
+            }
+            if stdout.starts_with('{') {
+                return serde_json::from_str(stdout).context("invalid JSON response");
+            }
+            Ok(HookResponse {
+                additional_context: Some(stdout.to_string()),
+                ..Default::default()
+            })
+        }
+        Some(BLOCKING_EXIT_CODE) => Ok(HookResponse {
+            decision: Some(HookDecision::Deny),
+            reason: Some(stderr.trim().to_string()),
+            ..Default::default()
+        }),
+        code => anyhow::bail!(
+            "exited with status {}: {}",
+            code.map_or_else(|| "unknown".to_string(), |code| code.to_string()),
+            stderr.trim()
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_hook_request_serialization() {
+        let request = HookRequest {
+            session_id: "abc".into(),
+            cwd: Some(PathBuf::from("/project")),
+            event: HookEvent::PreToolUse {
+                tool_name: "edit_file".into(),
+                tool_input: json!({ "path": "src/main.rs" }),
+            },
+        };
+        assert_eq!(
+            serde_json::to_value(&request).unwrap(),
+            json!({
+                "session_id": "abc",
+                "cwd": "/project",
+                "event": "pre_tool_use",
+                "tool_name": "edit_file",
+                "tool_input": { "path": "src/main.rs" },
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_hook_output() {
+        assert_eq!(
+            parse_hook_output(Some(0), "", "").unwrap(),
+            HookResponse::default()
+        );
+        assert_eq!(
+            parse_hook_output(
+                Some(0),
+                r#"{"decision": "deny", "reason": "generated file"}"#,
+                ""
+            )
+            .unwrap(),
+            HookResponse {
+                decision: Some(HookDecision::Deny),
+                reason: Some("generated file".into()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            parse_hook_output(Some(0), r#"{"updated_input": {"path": "b"}}"#, "").unwrap(),
+            HookResponse {
+                updated_input: Some(json!({ "path": "b" })),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            parse_hook_output(Some(0), "warning: unused import\n", "").unwrap(),
+            HookResponse {
+                additional_context: Some("warning: unused import".into()),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            parse_hook_output(Some(BLOCKING_EXIT_CODE), "", "do not touch\n").unwrap(),
+            HookResponse {
+                decision: Some(HookDecision::Deny),
+                reason: Some("do not touch".into()),
+                ..Default::default()
+            }
+        );
+        assert!(parse_hook_output(Some(1), "", "boom").is_err());
+        assert!(parse_hook_output(None, "", "").is_err());
+        assert!(parse_hook_output(Some(0), "{not json", "").is_err());
+    }
+
+    #[test]
+    fn test_failed_hook_response() {
+        let hook = AgentHook {
+            command: "guard".into(),
+            args: Vec::new(),
+            matcher: None,
+            timeout: agent_settings::AgentHooks::DEFAULT_TIMEOUT,
+        };
+        let error = anyhow::anyhow!("exited with status 1: boom");
+        let pre_tool_use = HookEvent::PreToolUse {
+            tool_name: "edit_file".into(),
+            tool_input: json!({}),
+        };
+        assert_eq!(
+            failed_hook_response(&hook, &pre_tool_use, &error),
+            Some(HookResponse {
+                decision: Some(HookDecision::Deny),
+                reason: Some("Hook `guard` failed: exited with status 1: boom".into()),
+                ..Default::default()
+            })
+        );
+        let stop = HookEvent::Stop {
+            stop_reason: "end_turn".into(),
+        };
+        assert_eq!(failed_hook_response(&hook, &stop, &error), None);
+    }
+
+    #[test]
+    fn test_append_context() {
+        let outcome = HookOutcome {
+            additional_context: vec!["first".into(), "second".into()],
+            ..Default::default()
+        };
+        let mut text = "output".to_string();
+        outcome.append_context_to(&mut text);
+        assert_eq!(text, "output\n\nfirst\n\nsecond");
+
+        let mut empty = String::new();
+        outcome.append_context_to(&mut empty);
+        assert_eq!(empty, "first\n\nsecond");
+    }
+}
@@ -0,0 +1,286 @@
+//! Tests that hooks configured in `agent.hooks` are applied to threads. Hooks
+//! are run as `sh` scripts, so these only run on Unix.
+
+use super::*;
+use gpui::SharedString;
+
+/// A tool that echoes its input like [`EchoTool`], counting how many times it
+/// has run. It's registered under [`EchoTool`]'s name, which the test profile
+/// enables.
+struct CountingEchoTool {
+    runs: Arc<AtomicUsize>,
+}
+
+impl AgentTool for CountingEchoTool {
+    type Input = EchoToolInput;
+    type Output = String;
+
+    const NAME: &'static str = EchoTool::NAME;
+
+    fn kind() -> acp::ToolKind {
+        acp::ToolKind::Other
+    }
+
+    fn initial_title(
+        &self,
+        _input: Result<Self::Input, serde_json::Value>,
+        _cx: &mut App,
+    ) -> SharedString {
+        "Echo".into()
+    }
+
+    fn run(
+        self: Arc<Self>,
+        input: ToolInput<Self::Input>,
+        _event_stream: ToolCallEventStream,
+        cx: &mut App,
+    ) -> Task<Result<String, String>> {
+        self.runs.fetch_add(1, Ordering::SeqCst);
+        cx.spawn(async move |_cx| {
+            let input = input
+                .recv()
+                .await
+                .map_err(|e| format!("Failed to receive tool input: {e}"))?;
+            Ok(input.text)
+        })
+    }
+}
+
+#[gpui::test]
+async fn test_pre_tool_use_hook_denies_tool_call(cx: &mut TestAppContext) {
+    let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+    cx.update(|cx| {
+        let mut settings = agent_settings::AgentSettings::get_global(cx).clone();
+        // Denies right away, without reading the request from its stdin.
+        settings.hooks.pre_tool_use = vec![agent_settings::AgentHook {
+            command: "sh".into(),
+            args: vec!["-c".into(), "echo 'protected' >&2; exit 2".into()],
+            matcher: None,
+            timeout: agent_settings::AgentHooks::DEFAULT_TIMEOUT,
+        }];
+        agent_settings::AgentSettings::override_global(settings, cx);
+    });
+
+    let mut events = thread
+        .update(cx, |thread, cx| {
+            thread.add_tool(EchoTool);
+            thread.send(UserMessageId::new(), ["Echo a long text"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    // Larger than a pipe buffer, so writing it fails once the hook exits.
+    let text = "x".repeat(1024 * 1024);
+    fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::ToolUse(
+        LanguageModelToolUse {
+            id: "tool_1".into(),
+            name: EchoTool::NAME.into(),
+            raw_input: json!({ "text": text }).to_string(),
+            input: json!({ "text": text }),
+            is_input_complete: true,
+            thought_signature: None,
+        },
+    ));
+    fake_model.end_last_completion_stream();
+
+    loop {
+        let event = events
+            .next()
+            .await
+            .expect("no tool call result received")
+            .unwrap();
+        if let ThreadEvent::ToolCallUpdate(acp_thread::ToolCallUpdate::UpdateFields(update)) = event
+            && let Some(status) = update.fields.status
+            && status != acp::ToolCallStatus::InProgress
+        {
+            assert_eq!(status, acp::ToolCallStatus::Failed);
+            break;
+        }
+    }
+    cx.run_until_parked();
+
+    let completion = fake_model.pending_completions().pop().unwrap();
+    let message = completion.messages.last().unwrap();
+    let MessageContent::ToolResult(tool_result) = &message.content[0] else {
+        panic!("expected a tool result, got {message:?}");
+    };
+    assert!(tool_result.is_error);
+    assert_eq!(
+        tool_result.content,
+        "Tool call blocked by hook: protected".into()
+    );
+}
+
+#[gpui::test]
+async fn test_pre_tool_use_hook_deny_skips_tool(cx: &mut TestAppContext) {
+    let echo = echo_with_hooks(
+        agent_settings::AgentHooks {
+            pre_tool_use: vec![sh_hook("cat > /dev/null; echo 'protected' >&2; exit 2")],
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+    assert_eq!(echo.runs, 0);
+    assert!(echo.tool_result.is_error);
+    assert_eq!(
+        echo.tool_result.content,
+        "Tool call blocked by hook: protected".into()
+    );
+}
+
+#[gpui::test]
+async fn test_pre_tool_use_hook_updates_input(cx: &mut TestAppContext) {
+    let echo = echo_with_hooks(
+        agent_settings::AgentHooks {
+            pre_tool_use: vec![sh_hook(
+                r#"cat > /dev/null; echo '{"updated_input": {"text": "rewritten"}}'"#,
+            )],
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+    assert_eq!(echo.runs, 1);
+    assert!(!echo.tool_result.is_error);
+    assert_eq!(echo.tool_result.content, "rewritten".into());
+}
+
+#[gpui::test]
+async fn test_failing_pre_tool_use_hook_denies_tool_call(cx: &mut TestAppContext) {
+    let echo = echo_with_hooks(
+        agent_settings::AgentHooks {
+            pre_tool_use: vec![sh_hook("cat > /dev/null; echo 'boom' >&2; exit 1")],
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+    assert_eq!(echo.runs, 0);
+    assert!(echo.tool_result.is_error);
+    assert_eq!(
+        echo.tool_result.content,
+        "Tool call blocked by hook: Hook `sh` failed: exited with status 1: boom".into()
+    );
+}
+
+#[gpui::test]
+async fn test_timed_out_pre_tool_use_hook_denies_tool_call(cx: &mut TestAppContext) {
+    let echo = echo_with_hooks(
+        agent_settings::AgentHooks {
+            pre_tool_use: vec![agent_settings::AgentHook {
+                timeout: Duration::from_millis(100),
+                ..sh_hook("sleep 10")
+            }],
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+    assert_eq!(echo.runs, 0);
+    assert!(echo.tool_result.is_error);
+    assert_eq!(
+        echo.tool_result.content,
+        "Tool call blocked by hook: Hook `sh` failed: timed out after 100ms".into()
+    );
+}
+
+#[gpui::test]
+async fn test_hook_context_is_added_to_requests(cx: &mut TestAppContext) {
+    let echo = echo_with_hooks(
+        agent_settings::AgentHooks {
+            user_prompt_submit: vec![sh_hook("cat > /dev/null; echo 'prompt context'")],
+            post_tool_use: vec![sh_hook("cat > /dev/null; echo 'tool context'")],
+            ..Default::default()
+        },
+        cx,
+    )
+    .await;
+    let user_message = echo.first_request.messages.last().unwrap();
+    assert_eq!(user_message.role, Role::User);
+    assert!(
+        user_message.string_contents().contains("prompt context"),
+        "got: {user_message:?}"
+    );
+    assert_eq!(echo.runs, 1);
+    assert_eq!(echo.tool_result.content, "hello\n\ntool context".into());
+}
+
+/// A hook that runs `script` with `sh`.
+fn sh_hook(script: &str) -> agent_settings::AgentHook {
+    agent_settings::AgentHook {
+        command: "sh".into(),
+        args: vec!["-c".into(), script.into()],
+        matcher: None,
+        timeout: agent_settings::AgentHooks::DEFAULT_TIMEOUT,
+    }
+}
+
+/// What happened when the model called [`CountingEchoTool`] with hooks configured.
+struct HookedEcho {
+    /// The request that started the turn.
+    first_request: LanguageModelRequest,
+    /// The tool result sent to the model.
+    tool_result: LanguageModelToolResult,
+    /// How many times the tool ran.
+    runs: usize,
+}
+
+/// Sends a message with `hooks` configured, and has the model respond by
+/// calling [`CountingEchoTool`] with "hello".
+async fn echo_with_hooks(hooks: agent_settings::AgentHooks, cx: &mut TestAppContext) -> HookedEcho {
+    let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+    cx.update(|cx| {
+        let mut settings = agent_settings::AgentSettings::get_global(cx).clone();
+        settings.hooks = hooks;
+        agent_settings::AgentSettings::override_global(settings, cx);
+    });
+
+    let runs = Arc::new(AtomicUsize::new(0));
+    let tool = CountingEchoTool { runs: runs.clone() };
+    let _events = thread
+        .update(cx, |thread, cx| {
+            thread.add_tool(tool);
+            thread.send(UserMessageId::new(), ["Echo hello"], cx)
+        })
+        .unwrap();
+    let first_request = next_completion(fake_model, cx).await;
+    fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::ToolUse(
+        LanguageModelToolUse {
+            id: "tool_1".into(),
+            name: CountingEchoTool::NAME.into(),
+            raw_input: json!({ "text": "hello" }).to_string(),
+            input: json!({ "text": "hello" }),
+            is_input_complete: true,
+            thought_signature: None,
+        },
+    ));
+    fake_model.end_last_completion_stream();
+
+    let request = next_completion(fake_model, cx).await;
+    let message = request.messages.last().unwrap();
+    let MessageContent::ToolResult(tool_result) = &message.content[0] else {
+        panic!("expected a tool result, got {message:?}");
+    };
+    HookedEcho {
+        first_request,
+        tool_result: tool_result.clone(),
+        runs: runs.load(Ordering::SeqCst),
+    }
+}
+
+/// Waits for the model to receive a request, letting real time pass for hooks
+/// to run, as they're separate processes.
+async fn next_completion(
+    fake_model: &FakeLanguageModel,
+    cx: &mut TestAppContext,
+) -> LanguageModelRequest {
+    loop {
+        cx.run_until_parked();
+        if let Some(request) = fake_model.pending_completions().pop() {
+            return request;
+        }
+        cx.executor().timer(Duration::from_millis(10)).await;
+    }
+}
@@ -54,6 +54,8 @@ use std::{
 use util::path;
 
 mod edit_file_thread_test;
+#[cfg(unix)]
+mod hook_tests;
 mod test_tools;
 use test_tools::*;
 
@@ -2873,6 +2875,69 @@ async fn test_refusal(cx: &mut TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_spend_recorded_when_request_fails_or_is_cancelled(cx: &mut TestAppContext) {
+    let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
+    let fake_model = model.as_fake();
+
+    // A refusal ends the turn with an error.
+    let events = thread
+        .update(cx, |thread, cx| {
+            thread.send(UserMessageId::new(), ["Hello"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::UsageUpdate(
+        TokenUsage {
+            input_tokens: 100,
+            output_tokens: 10,
+            ..Default::default()
+        },
+    ));
+    fake_model
+        .send_last_completion_stream_event(LanguageModelCompletionEvent::Stop(StopReason::Refusal));
+    let events = events.collect::<Vec<_>>().await;
+    assert_eq!(stop_events(events), vec![acp::StopReason::Refusal]);
+    cx.run_until_parked();
+    assert_eq!(spent_tokens(&thread, cx).await, 110);
+
+    // Cancelling the turn in the middle of the request.
+    thread
+        .update(cx, |thread, cx| {
+            thread.send(UserMessageId::new(), ["Hello again"], cx)
+        })
+        .unwrap();
+    cx.run_until_parked();
+    fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::UsageUpdate(
+        TokenUsage {
+            input_tokens: 200,
+            output_tokens: 20,
+            ..Default::default()
+        },
+    ));
+    cx.run_until_parked();
+    thread.update(cx, |thread, cx| thread.cancel(cx)).await;
+    cx.run_until_parked();
+    assert_eq!(spent_tokens(&thread, cx).await, 330);
+}
+
+/// Returns the tokens recorded as spent by `thread`.
+async fn spent_tokens(thread: &Entity<Thread>, cx: &mut TestAppContext) -> u64 {
+    let (thread_id, profile_id, db) = thread.update(cx, |thread, cx| {
+        (
+            thread.id().0.clone(),
+            thread.profile().clone(),
+            ThreadsDatabase::connect(cx),
+        )
+    });
+    let db = db.await.unwrap();
+    db.spend_totals(thread_id, profile_id, budget::today())
+        .await
+        .unwrap()
+        .thread
+        .tokens
+}
+
 #[gpui::test]
 async fn test_truncate_first_message(cx: &mut TestAppContext) {
     let ThreadTest { model, thread, .. } = setup(cx, TestModel::Fake).await;
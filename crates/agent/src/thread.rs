@@ -1,10 +1,11 @@
+use crate::hooks::{self, HookEvent, HookOutcome, HookRequest};
 use crate::{
-    ContextServerRegistry, CopyPathTool, CreateDirectoryTool, DbLanguageModel, DbThread,
-    DeletePathTool, DiagnosticsTool, EditFileTool, FetchTool, FindPathTool, GrepTool,
+    BudgetState, ContextServerRegistry, CopyPathTool, CreateDirectoryTool, DbLanguageModel,
+    DbThread, DeletePathTool, DiagnosticsTool, EditFileTool, FetchTool, FindPathTool, GrepTool,
     ListDirectoryTool, MovePathTool, NowTool, OpenTool, ProjectSnapshot, ReadFileTool,
-    RestoreFileFromDiskTool, SaveFileTool, SpawnAgentTool, StreamingEditFileTool,
-    SystemPromptTemplate, Template, Templates, TerminalTool, ToolPermissionDecision,
-    UpdatePlanTool, WebSearchTool, decide_permission_from_settings,
+    RestoreFileFromDiskTool, SaveFileTool, SpawnAgentTool, SpendRecord, StreamingEditFileTool,
+    SystemPromptTemplate, Template, Templates, TerminalTool, ThreadsDatabase,
+    ToolPermissionDecision, UpdatePlanTool, WebSearchTool, budget, decide_permission_from_settings,
 };
 use acp_thread::{MentionUri, UserMessageId};
 use action_log::ActionLog;
@@ -15,6 +16,7 @@ use feature_flags::{
 use agent_client_protocol as acp;
 use agent_settings::{
     AgentProfileId, AgentSettings, SUMMARIZE_THREAD_DETAILED_PROMPT, SUMMARIZE_THREAD_PROMPT,
+    TerminalSandboxSettings,
 };
 use anyhow::{Context as _, Result, anyhow};
 use chrono::{DateTime, Utc};
@@ -49,6 +51,7 @@ use serde::{Deserialize, Serialize};
 use settings::{LanguageModelSelection, Settings, ToolPermissionMode, update_settings_file};
 use smol::stream::StreamExt;
 use std::{
+    cell::Cell,
     collections::BTreeMap,
     marker::PhantomData,
     ops::RangeInclusive,
@@ -644,6 +647,12 @@ pub trait ThreadEnvironment {
 
     fn create_subagent(&self, label: String, cx: &mut App) -> Result<Rc<dyn SubagentHandle>>;
 
+    /// The sandbox that terminal commands should run in, if the thread's
+    /// profile enables one.
+    fn terminal_sandbox(&self, _cx: &App) -> Option<TerminalSandboxSettings> {
+        None
+    }
+
     fn resume_subagent(
         &self,
         _session_id: acp::SessionId,
@@ -666,6 +675,7 @@ pub enum ThreadEvent {
     ToolCallAuthorization(ToolCallAuthorization),
     SubagentSpawned(acp::SessionId),
     Retry(acp_thread::RetryStatus),
+    Warning(SharedString),
     Stop(acp::StopReason),
 }
 
@@ -945,6 +955,10 @@ pub struct Thread {
     pending_message: Option<AgentMessage>,
     pub(crate) tools: BTreeMap<SharedString, Arc<dyn AnyAgentTool>>,
     request_token_usage: HashMap<UserMessageId, language_model::TokenUsage>,
+    /// Usage reported for the in-flight completion request, which is recorded
+    /// as spend once the request ends. Shared with the request's [`PendingSpend`].
+    pending_request_usage: Rc<Cell<Option<TokenUsage>>>,
+    budget_state: BudgetState,
     #[allow(unused)]
     cumulative_token_usage: TokenUsage,
     #[allow(unused)]
@@ -1061,6 +1075,8 @@ impl Thread {
             pending_message: None,
             tools: BTreeMap::default(),
             request_token_usage: HashMap::default(),
+            pending_request_usage: Rc::default(),
+            budget_state: BudgetState::default(),
             cumulative_token_usage: TokenUsage::default(),
             initial_project_snapshot: {
                 let project_snapshot = Self::project_snapshot(project.clone(), cx);
@@ -1281,6 +1297,8 @@ impl Thread {
             pending_message: None,
             tools: BTreeMap::default(),
             request_token_usage: db_thread.request_token_usage.clone(),
+            pending_request_usage: Rc::default(),
+            budget_state: BudgetState::default(),
             cumulative_token_usage: db_thread.cumulative_token_usage,
             initial_project_snapshot: Task::ready(db_thread.initial_project_snapshot).shared(),
             context_server_registry,
@@ -1618,6 +1636,7 @@ impl Thread {
     }
 
     fn update_token_usage(&mut self, update: language_model::TokenUsage, cx: &mut Context<Self>) {
+        self.pending_request_usage.set(Some(update));
         let Some(last_user_message) = self.last_user_message() else {
             return;
         };
@@ -1826,6 +1845,18 @@ impl Thread {
         let event_stream = ThreadEventStream(events_tx);
         let message_ix = self.messages.len().saturating_sub(1);
         self.clear_summary();
+        let hooks = AgentSettings::get_global(cx).hooks.clone();
+        let prompt_submit = match self.messages.last() {
+            Some(Message::User(message)) if !hooks.user_prompt_submit.is_empty() => {
+                Some(self.hook_request(
+                    HookEvent::UserPromptSubmit {
+                        prompt: message.to_markdown(),
+                    },
+                    cx,
+                ))
+            }
+            _ => None,
+        };
         let (cancellation_tx, mut cancellation_rx) = watch::channel(false);
         self.running_turn = Some(RunningTurn {
             event_stream: event_stream.clone(),
@@ -1835,6 +1866,32 @@ impl Thread {
             _task: cx.spawn(async move |this, cx| {
                 log::debug!("Starting agent turn execution");
 
+                if let Some(request) = prompt_submit {
+                    let outcome = hooks::run_hooks(
+                        hooks.user_prompt_submit.clone(),
+                        request,
+                        cx.background_executor().clone(),
+                    )
+                    .await;
+                    if let Some(reason) = outcome.denied {
+                        _ = this.update(cx, |this, _| {
+                            this.messages.truncate(message_ix);
+                            this.running_turn.take();
+                        });
+                        event_stream.send_error(anyhow!("Message blocked by hook: {reason}"));
+                        return;
+                    }
+                    if !outcome.additional_context.is_empty() {
+                        _ = this.update(cx, |this, _| {
+                            if let Some(Message::User(message)) = this.messages.last_mut() {
+                                let mut context = String::new();
+                                outcome.append_context_to(&mut context);
+                                message.content.push(UserMessageContent::Text(context));
+                            }
+                        });
+                    }
+                }
+
                 let turn_result =
                     Self::run_turn_internal(&this, &event_stream, cancellation_rx.clone(), cx)
                         .await;
@@ -1849,6 +1906,23 @@ impl Thread {
 
                 _ = this.update(cx, |this, cx| this.flush_pending_message(cx));
 
+                if !hooks.stop.is_empty()
+                    && let Ok(request) = this.update(cx, |this, cx| {
+                        let stop_reason = match &turn_result {
+                            Ok(()) => "end_turn".to_string(),
+                            Err(error) => format!("error: {error}"),
+                        };
+                        this.hook_request(HookEvent::Stop { stop_reason }, cx)
+                    })
+                {
+                    hooks::run_hooks(
+                        hooks.stop.clone(),
+                        request,
+                        cx.background_executor().clone(),
+                    )
+                    .await;
+                }
+
                 match turn_result {
                     Ok(()) => {
                         log::debug!("Turn execution completed");
@@ -1886,6 +1960,8 @@ impl Thread {
         let mut attempt = 0;
         let mut intent = CompletionIntent::UserPrompt;
         loop {
+            Self::check_budgets(this, event_stream, cx).await?;
+
             // Re-read the model and refresh tools on each iteration so that
             // mid-turn changes (e.g. the user switches model, toggles tools,
             // or changes profile) take effect between tool-call rounds.
@@ -1895,6 +1971,14 @@ impl Thread {
                 let request = this.build_completion_request(intent, cx)?;
                 anyhow::Ok((model, request))
             })??;
+            let spend_cx = cx.clone();
+            let mut spend = this.read_with(cx, |this, _| PendingSpend {
+                usage: this.pending_request_usage.clone(),
+                thread_id: this.id.0.clone(),
+                profile_id: this.profile_id.clone(),
+                model: model.clone(),
+                cx: spend_cx,
+            })?;
 
             telemetry::event!(
                 "Agent Thread Completion",
@@ -2011,6 +2095,8 @@ impl Thread {
             // that need their own permits.
             drop(events);
 
+            spend.record().await;
+
             // Drop streaming tool input senders that never received their final input.
             // This prevents deadlock when the LLM stream ends (e.g. because of an error)
             // before sending a tool use with `is_input_complete: true`.
@@ -2084,6 +2170,41 @@ impl Thread {
         }
     }
 
+    /// Returns an error if a budget that applies to this thread has been used
+    /// up, and sends warnings for budgets that are running out.
+    async fn check_budgets(
+        this: &WeakEntity<Self>,
+        event_stream: &ThreadEventStream,
+        cx: &mut AsyncApp,
+    ) -> Result<()> {
+        let (budgets, thread_id, profile_id, db) = this.update(cx, |this, cx| {
+            (
+                budget::active_budgets(&this.profile_id, cx),
+                this.id.0.clone(),
+                this.profile_id.clone(),
+                ThreadsDatabase::connect(cx),
+            )
+        })?;
+        if budgets.is_empty() {
+            return Ok(());
+        }
+
+        let db = db.await.map_err(|error| anyhow!(error))?;
+        let totals = db
+            .spend_totals(thread_id, profile_id, budget::today())
+            .await?;
+        let warnings = this.update(cx, |this, _| this.budget_state.check(&budgets, &totals))??;
+        for warning in warnings {
+            event_stream.send_warning(warning);
+        }
+        Ok(())
+    }
+
+    /// Lets the next turn continue past the budget that last paused this thread.
+    pub fn continue_past_budget_pause(&mut self) {
+        self.budget_state.continue_past_pause();
+    }
+
     fn process_tool_result(
         this: &WeakEntity<Thread>,
         event_stream: &ThreadEventStream,
@@ -2313,7 +2434,13 @@ impl Thread {
         };
 
         if !tool_use.is_input_complete {
-            if tool.supports_input_streaming() {
+            // Hooks need the complete input, so tools they apply to only run
+            // once the model has finished streaming it.
+            if tool.supports_input_streaming()
+                && !AgentSettings::get_global(cx)
+                    .hooks
+                    .has_tool_hooks(&tool_use.name)
+            {
                 let running_turn = self.running_turn.as_mut()?;
                 if let Some(sender) = running_turn.streaming_tool_inputs.get(&tool_use.id) {
                     sender.send_partial(tool_use.input);
@@ -2386,7 +2513,20 @@ impl Thread {
             acp::ToolCallUpdateFields::new().status(acp::ToolCallStatus::InProgress),
         );
         let supports_images = self.model().is_some_and(|model| model.supports_images());
-        let tool_result = tool.run(tool_input, tool_event_stream, cx);
+        let hooks = AgentSettings::get_global(cx).hooks.clone();
+        let tool_result = if hooks.has_tool_hooks(&tool_name) {
+            self.run_tool_with_hooks(
+                tool,
+                tool_input,
+                tool_name.clone(),
+                tool_event_stream,
+                hooks.pre_tool_use_for(&tool_name),
+                hooks.post_tool_use_for(&tool_name),
+                cx,
+            )
+        } else {
+            tool.run(tool_input, tool_event_stream, cx)
+        };
         cx.foreground_executor().spawn(async move {
             let (is_error, output) = match tool_result.await {
                 Ok(mut output) => {
@@ -2414,6 +2554,112 @@ impl Thread {
         })
     }
 
+    fn run_tool_with_hooks(
+        &self,
+        tool: Arc<dyn AnyAgentTool>,
+        tool_input: ToolInput<serde_json::Value>,
+        tool_name: Arc<str>,
+        tool_event_stream: ToolCallEventStream,
+        pre_tool_hooks: Vec<agent_settings::AgentHook>,
+        post_tool_hooks: Vec<agent_settings::AgentHook>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<AgentToolOutput, AgentToolOutput>> {
+        let request = self.hook_request(
+            HookEvent::PreToolUse {
+                tool_name: tool_name.clone(),
+                tool_input: serde_json::Value::Null,
+            },
+            cx,
+        );
+        cx.spawn(async move |_this, cx| {
+            let mut input = tool_input
+                .recv()
+                .await
+                .map_err(|error| AgentToolOutput::from_error(error.to_string()))?;
+
+            let mut pre_tool_outcome = HookOutcome::default();
+            if !pre_tool_hooks.is_empty() {
+                let mut request = request.clone();
+                request.event = HookEvent::PreToolUse {
+                    tool_name: tool_name.clone(),
+                    tool_input: input.clone(),
+                };
+                pre_tool_outcome =
+                    hooks::run_hooks(pre_tool_hooks, request, cx.background_executor().clone())
+                        .await;
+                if let Some(reason) = pre_tool_outcome.denied.take() {
+                    return Err(AgentToolOutput::from_error(format!(
+                        "Tool call blocked by hook: {reason}"
+                    )));
+                }
+                if let Some(updated_input) = pre_tool_outcome.updated_input.take() {
+                    tool_event_stream.update_fields(
+                        acp::ToolCallUpdateFields::new().raw_input(updated_input.clone()),
+                    );
+                    input = updated_input;
+                }
+            }
+
+            let result = cx
+                .update(|cx| tool.run(ToolInput::ready(input.clone()), tool_event_stream, cx))
+                .await;
+
+            let mut post_tool_outcome = HookOutcome::default();
+            if !post_tool_hooks.is_empty() {
+                let (is_error, output) = match &result {
+                    Ok(output) => (false, output),
+                    Err(output) => (true, output),
+                };
+                let mut request = request;
+                request.event = HookEvent::PostToolUse {
+                    tool_name,
+                    tool_input: input,
+                    tool_output: output.raw_output.clone(),
+                    is_error,
+                };
+                post_tool_outcome =
+                    hooks::run_hooks(post_tool_hooks, request, cx.background_executor().clone())
+                        .await;
+            }
+
+            let mut context = pre_tool_outcome;
+            context
+                .additional_context
+                .extend(post_tool_outcome.additional_context);
+            let append_context = |mut output: AgentToolOutput| {
+                if context.additional_context.is_empty() {
+                    return output;
+                }
+                if let LanguageModelToolResultContent::Text(text) = &output.llm_output {
+                    let mut text = text.to_string();
+                    context.append_context_to(&mut text);
+                    output.llm_output = LanguageModelToolResultContent::Text(text.into());
+                } else {
+                    log::warn!("Dropping hook context for non-text tool output");
+                }
+                output
+            };
+            result.map(append_context).map_err(append_context)
+        })
+    }
+
+    fn hook_request(&self, event: HookEvent, cx: &App) -> HookRequest {
+        let project = self.project.read(cx);
+        let cwd = if project.is_local() {
+            project
+                .visible_worktrees(cx)
+                .next()
+                .map(|worktree| worktree.read(cx).abs_path().to_path_buf())
+        } else {
+            None
+        };
+        HookRequest {
+            session_id: self.id.to_string(),
+            cwd,
+            event,
+        }
+    }
+
     fn handle_tool_use_json_parse_error_event(
         &mut self,
         tool_use_id: LanguageModelToolUseId,
@@ -3111,6 +3357,57 @@ impl RunningTurn {
     }
 }
 
+/// The spend of an in-flight completion request.
+///
+/// The usage is recorded when the request ends and, failing that, when this is
+/// dropped, so that it counts against budgets even when the request fails or
+/// the turn is cancelled.
+struct PendingSpend {
+    usage: Rc<Cell<Option<TokenUsage>>>,
+    thread_id: Arc<str>,
+    profile_id: AgentProfileId,
+    model: Arc<dyn LanguageModel>,
+    cx: AsyncApp,
+}
+
+impl PendingSpend {
+    /// Records the usage reported so far, resolving once it has been written.
+    fn record(&mut self) -> Task<()> {
+        let Some(usage) = self.usage.take() else {
+            return Task::ready(());
+        };
+        let thread_id = self.thread_id.clone();
+        let profile_id = self.profile_id.clone();
+        let model = self.model.clone();
+        self.cx.spawn(async move |cx| {
+            let (record, db) = cx.update(|cx| {
+                let record = SpendRecord {
+                    date: budget::today(),
+                    thread_id,
+                    profile_id,
+                    provider: model.provider_id().0.to_string(),
+                    model: model.id().0.to_string(),
+                    cost: budget::estimate_cost(&usage, &model, cx),
+                    usage,
+                };
+                (record, ThreadsDatabase::connect(cx))
+            });
+            match db.await {
+                Ok(db) => {
+                    db.record_spend(record).await.log_err();
+                }
+                Err(error) => log::error!("Failed to record agent spend: {error:#}"),
+            }
+        })
+    }
+}
+
+impl Drop for PendingSpend {
+    fn drop(&mut self) {
+        self.record().detach();
+    }
+}
+
 pub struct TokenUsageUpdated(pub Option<acp_thread::TokenUsage>);
 
 impl EventEmitter<TokenUsageUpdated> for Thread {}
@@ -3496,6 +3793,12 @@ impl ThreadEventStream {
         self.0.unbounded_send(Ok(ThreadEvent::Retry(status))).ok();
     }
 
+    fn send_warning(&self, warning: String) {
+        self.0
+            .unbounded_send(Ok(ThreadEvent::Warning(warning.into())))
+            .ok();
+    }
+
     fn send_stop(&self, reason: acp::StopReason) {
         self.0.unbounded_send(Ok(ThreadEvent::Stop(reason))).ok();
     }
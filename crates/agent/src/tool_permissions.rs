@@ -594,6 +594,9 @@ mod tests {
             use_modifier_to_send: true,
             message_editor_min_lines: 1,
             tool_permissions,
+            hooks: Default::default(),
+            budgets: Default::default(),
+            model_pricing: Vec::new(),
             show_turn_stats: false,
             new_thread_location: Default::default(),
         }
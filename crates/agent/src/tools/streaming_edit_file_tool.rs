@@ -562,6 +562,9 @@ impl EditSession {
             }
         }) as Box<dyn FnOnce()>);
 
+        tool.project.update(cx, |project, cx| {
+            project.agent_will_edit_buffer(&buffer, cx)
+        });
         tool.action_log.update(cx, |log, cx| match mode {
             StreamingEditFileMode::Write => log.buffer_created(buffer.clone(), cx),
             StreamingEditFileMode::Edit => log.buffer_read(buffer.clone(), cx),
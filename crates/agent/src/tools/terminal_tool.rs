@@ -1,9 +1,10 @@
 use agent_client_protocol as acp;
-use agent_settings::AgentSettings;
-use anyhow::Result;
+use agent_settings::{AgentSettings, TerminalSandboxSettings};
+use anyhow::{Context as _, Result};
 use futures::FutureExt as _;
 use gpui::{App, Entity, SharedString, Task};
 use project::Project;
+use sandbox::{SandboxPolicy, SandboxReport};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::Settings;
@@ -13,6 +14,8 @@ use std::{
     sync::Arc,
     time::Duration,
 };
+use util::{ResultExt as _, get_default_system_shell_preferring_bash, shell::ShellKind};
+use uuid::Uuid;
 
 use crate::{
     AgentTool, ThreadEnvironment, ToolCallEventStream, ToolInput, ToolPermissionDecision,
@@ -97,9 +100,18 @@ impl AgentTool for TerminalTool {
                 .await
                 .map_err(|e| format!("Failed to receive tool input: {e}"))?;
 
-            let (working_dir, authorize) = cx.update(|cx| {
+            let (working_dir, sandbox, authorize) = cx.update(|cx| {
                 let working_dir =
                     working_dir(&input, &self.project, cx).map_err(|err| err.to_string())?;
+                let sandbox = self
+                    .environment
+                    .terminal_sandbox(cx)
+                    .map(|settings| {
+                        let policy = sandbox_policy(&settings, &self.project, cx)?;
+                        SandboxedCommand::new(policy, &input.command)
+                    })
+                    .transpose()
+                    .map_err(|err| format!("Failed to sandbox command: {err:#}"))?;
 
                 let decision = decide_permission_from_settings(
                     Self::NAME,
@@ -124,7 +136,7 @@ impl AgentTool for TerminalTool {
                         ))
                     }
                 };
-                Ok((working_dir, authorize))
+                Ok((working_dir, sandbox, authorize))
             })?;
             if let Some(authorize) = authorize {
                 authorize.await.map_err(|e| e.to_string())?;
@@ -133,7 +145,9 @@ impl AgentTool for TerminalTool {
             let terminal = self
                 .environment
                 .create_terminal(
-                    input.command.clone(),
+                    sandbox
+                        .as_ref()
+                        .map_or_else(|| input.command.clone(), |sandbox| sandbox.command.clone()),
                     working_dir,
                     Some(COMMAND_OUTPUT_LIMIT),
                     cx,
@@ -193,15 +207,137 @@ impl AgentTool for TerminalTool {
             let user_stopped = user_stopped_via_signal || user_stopped_via_terminal;
 
             let output = terminal.current_output(cx).map_err(|e| e.to_string())?;
+            let succeeded = output
+                .exit_status
+                .as_ref()
+                .is_some_and(|status| status.exit_code == Some(0));
+
+            let mut content = process_content(output, &input.command, timed_out, user_stopped);
+            if let Some(sandbox) = sandbox {
+                let report = sandbox.finish();
+                if let Some(summary) = sandbox_summary(&sandbox.policy, &report, succeeded) {
+                    content.push_str("\n\n");
+                    content.push_str(&summary);
+                }
+            }
+            Ok(content)
+        })
+    }
+}
+
+/// A terminal tool command rewritten to run through the sandbox helper.
+struct SandboxedCommand {
+    policy: SandboxPolicy,
+    report_path: PathBuf,
+    command: String,
+}
 
-            Ok(process_content(
-                output,
-                &input.command,
-                timed_out,
-                user_stopped,
-            ))
+impl SandboxedCommand {
+    fn new(policy: SandboxPolicy, command: &str) -> Result<Self> {
+        let helper = std::env::current_exe().context("failed to locate the Zed executable")?;
+        let shell = get_default_system_shell_preferring_bash();
+        let shell_kind = ShellKind::new(&shell, false);
+        // The report must live where the sandboxed command can't forge or remove it.
+        let report_dir = paths::data_dir().join("sandbox_reports");
+        std::fs::create_dir_all(&report_dir)
+            .with_context(|| format!("failed to create {report_dir:?}"))?;
+        anyhow::ensure!(
+            !policy.is_writable(&report_dir),
+            "the sandbox report directory {report_dir:?} is writable by sandboxed commands"
+        );
+        let report_path = report_dir.join(format!("{}.log", Uuid::new_v4()));
+
+        let helper = helper.to_string_lossy();
+        let helper_args = policy.helper_args(
+            &report_path,
+            &shell,
+            &["-c".to_string(), command.to_string()],
+        );
+        let mut wrapped = String::new();
+        for arg in std::iter::once(helper.as_ref()).chain(helper_args.iter().map(String::as_str)) {
+            let quoted = shell_kind
+                .try_quote(arg)
+                .with_context(|| format!("failed to quote {arg:?}"))?;
+            if !wrapped.is_empty() {
+                wrapped.push(' ');
+            }
+            wrapped.push_str(&quoted);
+        }
+
+        Ok(Self {
+            policy,
+            report_path,
+            command: wrapped,
         })
     }
+
+    /// Reads the operations the sandbox blocked and cleans up the report.
+    fn finish(&self) -> SandboxReport {
+        let report = SandboxReport::read(&self.report_path)
+            .log_err()
+            .unwrap_or_default();
+        std::fs::remove_file(&self.report_path).ok();
+        report
+    }
+}
+
+fn sandbox_policy(
+    settings: &TerminalSandboxSettings,
+    project: &Entity<Project>,
+    cx: &App,
+) -> Result<SandboxPolicy> {
+    anyhow::ensure!(
+        sandbox::is_supported(),
+        "the active profile enables the terminal sandbox, but sandboxing isn't supported on this system"
+    );
+    let project = project.read(cx);
+    anyhow::ensure!(
+        project.is_local(),
+        "the active profile enables the terminal sandbox, which is only supported in local projects"
+    );
+
+    Ok(SandboxPolicy {
+        writable_paths: sandbox_writable_paths(settings, project, cx),
+        allow_network: settings.allow_network,
+    })
+}
+
+/// Returns the paths sandboxed commands may write to. Invisible worktrees are
+/// left out, since they're files Zed opened from outside the project, such as
+/// the user's settings, which a command could otherwise rewrite.
+fn sandbox_writable_paths(
+    settings: &TerminalSandboxSettings,
+    project: &Project,
+    cx: &App,
+) -> Vec<PathBuf> {
+    let mut writable_paths = project
+        .visible_worktrees(cx)
+        .map(|worktree| worktree.read(cx).abs_path().to_path_buf())
+        .collect::<Vec<_>>();
+    writable_paths.push(std::env::temp_dir());
+    writable_paths.extend(settings.additional_writable_paths.iter().cloned());
+    writable_paths
+}
+
+/// Explains the sandbox to the model when it may have caused the command to fail.
+fn sandbox_summary(
+    policy: &SandboxPolicy,
+    report: &SandboxReport,
+    succeeded: bool,
+) -> Option<String> {
+    if succeeded && report.blocked.is_empty() {
+        return None;
+    }
+
+    let mut summary = policy.describe();
+    if !report.blocked.is_empty() {
+        summary.push_str("\n\nThe sandbox blocked the following operations:");
+        for operation in &report.blocked {
+            summary.push_str("\n- ");
+            summary.push_str(&operation.to_string());
+        }
+    }
+    Some(summary)
 }
 
 fn process_content(
@@ -493,6 +629,77 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sandbox_summary() {
+        let policy = SandboxPolicy {
+            writable_paths: vec![PathBuf::from("/project")],
+            allow_network: false,
+        };
+
+        assert_eq!(
+            sandbox_summary(&policy, &SandboxReport::default(), true),
+            None
+        );
+
+        let summary = sandbox_summary(&policy, &SandboxReport::default(), false).unwrap();
+        assert!(summary.contains("/project"), "got: {summary}");
+        assert!(
+            summary.contains("Network access is blocked"),
+            "got: {summary}"
+        );
+
+        let report = SandboxReport {
+            blocked: vec![sandbox::BlockedOperation {
+                pid: 7,
+                process: Some("curl".into()),
+                operation: "network socket (AF_INET)".into(),
+            }],
+        };
+        let summary = sandbox_summary(&policy, &report, true).unwrap();
+        assert!(
+            summary.contains("- network socket (AF_INET) (`curl`, pid 7)"),
+            "got: {summary}"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_sandbox_writable_paths_exclude_invisible_worktrees(
+        cx: &mut gpui::TestAppContext,
+    ) {
+        crate::tests::init_test(cx);
+
+        let fs = fs::FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/root",
+            serde_json::json!({
+                "project": {},
+                "config": { "settings.json": "{}" },
+            }),
+        )
+        .await;
+        let project = project::Project::test(fs, ["/root/project".as_ref()], cx).await;
+        project
+            .update(cx, |project, cx| {
+                project.find_or_create_worktree("/root/config/settings.json", false, cx)
+            })
+            .await
+            .unwrap();
+
+        let settings = TerminalSandboxSettings {
+            allow_network: false,
+            additional_writable_paths: vec![PathBuf::from("/root/cache")],
+        };
+        let writable_paths = cx.read(|cx| sandbox_writable_paths(&settings, project.read(cx), cx));
+        assert!(writable_paths.contains(&PathBuf::from("/root/project")));
+        assert!(writable_paths.contains(&PathBuf::from("/root/cache")));
+        assert!(
+            !writable_paths
+                .iter()
+                .any(|path| path.starts_with("/root/config")),
+            "invisible worktrees must not be writable, got: {writable_paths:?}"
+        );
+    }
+
     #[test]
     fn test_process_content_timed_out() {
         let output = acp::TerminalOutputResponse::new("build output here".to_string(), false);
@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use anyhow::{Result, bail};
@@ -7,11 +8,11 @@ use fs::Fs;
 use gpui::{App, SharedString};
 use settings::{
     AgentProfileContent, ContextServerPresetContent, LanguageModelSelection, Settings as _,
-    SettingsContent, update_settings_file,
+    SettingsContent, TerminalSandboxContent, update_settings_file,
 };
 use util::ResultExt as _;
 
-use crate::{AgentProfileId, AgentSettings};
+use crate::{AgentBudget, AgentProfileId, AgentSettings};
 
 pub mod builtin_profiles {
     use super::AgentProfileId;
@@ -70,6 +71,10 @@ impl AgentProfile {
         let default_model = base_profile
             .as_ref()
             .and_then(|profile| profile.default_model.clone());
+        let terminal_sandbox = base_profile
+            .as_ref()
+            .and_then(|profile| profile.terminal_sandbox.clone());
+        let budget = base_profile.as_ref().and_then(|profile| profile.budget);
 
         let profile_settings = AgentProfileSettings {
             name: name.into(),
@@ -77,6 +82,8 @@ impl AgentProfile {
             enable_all_context_servers,
             context_servers,
             default_model,
+            terminal_sandbox,
+            budget,
         };
 
         update_settings_file(fs, cx, {
@@ -109,6 +116,10 @@ pub struct AgentProfileSettings {
     pub context_servers: IndexMap<Arc<str>, ContextServerPreset>,
     /// Default language model to apply when this profile becomes active.
     pub default_model: Option<LanguageModelSelection>,
+    /// Sandbox for terminal tool commands. `None` when sandboxing is disabled.
+    pub terminal_sandbox: Option<TerminalSandboxSettings>,
+    /// Budget shared by all threads using this profile, reset daily.
+    pub budget: Option<AgentBudget>,
 }
 
 impl AgentProfileSettings {
@@ -158,6 +169,8 @@ impl AgentProfileSettings {
                     })
                     .collect(),
                 default_model: self.default_model.clone(),
+                terminal_sandbox: self.terminal_sandbox.clone().map(Into::into),
+                budget: self.budget.map(Into::into),
             },
         );
 
@@ -173,6 +186,8 @@ impl From<AgentProfileContent> for AgentProfileSettings {
             enable_all_context_servers,
             context_servers,
             default_model,
+            terminal_sandbox,
+            budget,
         } = content;
 
         Self {
@@ -184,6 +199,35 @@ impl From<AgentProfileContent> for AgentProfileSettings {
                 .map(|(server_id, preset)| (server_id, preset.into()))
                 .collect(),
             default_model,
+            terminal_sandbox: terminal_sandbox
+                .filter(|sandbox| sandbox.enabled == Some(true))
+                .map(Into::into),
+            budget: budget.and_then(AgentBudget::from_content),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TerminalSandboxSettings {
+    pub allow_network: bool,
+    pub additional_writable_paths: Vec<PathBuf>,
+}
+
+impl From<TerminalSandboxContent> for TerminalSandboxSettings {
+    fn from(content: TerminalSandboxContent) -> Self {
+        Self {
+            allow_network: content.allow_network.unwrap_or_default(),
+            additional_writable_paths: content.additional_writable_paths.unwrap_or_default(),
+        }
+    }
+}
+
+impl From<TerminalSandboxSettings> for TerminalSandboxContent {
+    fn from(settings: TerminalSandboxSettings) -> Self {
+        Self {
+            enabled: Some(true),
+            allow_network: Some(settings.allow_network),
+            additional_writable_paths: Some(settings.additional_writable_paths),
         }
     }
 }
@@ -2,6 +2,7 @@ mod agent_profile;
 
 use std::path::{Component, Path};
 use std::sync::{Arc, LazyLock};
+use std::time::Duration;
 
 use agent_client_protocol::ModelId;
 use collections::{HashSet, IndexMap};
@@ -11,8 +12,9 @@ use project::DisableAiSettings;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::{
-    DefaultAgentView, DockPosition, LanguageModelParameters, LanguageModelSelection,
-    NewThreadLocation, NotifyWhenAgentWaiting, RegisterSetting, Settings, ToolPermissionMode,
+    AgentBudgetAction, AgentBudgetContent, DefaultAgentView, DockPosition, LanguageModelParameters,
+    LanguageModelPricing, LanguageModelSelection, NewThreadLocation, NotifyWhenAgentWaiting,
+    RegisterSetting, Settings, ToolPermissionMode,
 };
 
 pub use crate::agent_profile::*;
@@ -51,6 +53,9 @@ pub struct AgentSettings {
     pub message_editor_min_lines: usize,
     pub show_turn_stats: bool,
     pub tool_permissions: ToolPermissions,
+    pub hooks: AgentHooks,
+    pub budgets: AgentBudgets,
+    pub model_pricing: Vec<LanguageModelPricing>,
     pub new_thread_location: NewThreadLocation,
 }
 
@@ -77,6 +82,29 @@ impl AgentSettings {
         return None;
     }
 
+    /// Returns the configured prices for the given model, if any.
+    pub fn pricing_for_model(
+        model: &Arc<dyn LanguageModel>,
+        cx: &App,
+    ) -> Option<LanguageModelPricing> {
+        let settings = Self::get_global(cx);
+        settings
+            .model_pricing
+            .iter()
+            .rev()
+            .find(|pricing| {
+                pricing
+                    .provider
+                    .as_ref()
+                    .is_none_or(|provider| provider.0 == model.provider_id().0)
+                    && pricing
+                        .model
+                        .as_ref()
+                        .is_none_or(|pricing_model| *pricing_model == model.id().0)
+            })
+            .cloned()
+    }
+
     pub fn set_message_editor_max_lines(&self) -> usize {
         self.message_editor_min_lines * 2
     }
@@ -192,6 +220,110 @@ impl CompiledRegex {
     }
 }
 
+/// User commands to run at points in the agent's lifecycle.
+#[derive(Clone, Debug, Default)]
+pub struct AgentHooks {
+    pub pre_tool_use: Vec<AgentHook>,
+    pub post_tool_use: Vec<AgentHook>,
+    pub stop: Vec<AgentHook>,
+    pub user_prompt_submit: Vec<AgentHook>,
+}
+
+impl AgentHooks {
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
+
+    /// Returns the `pre_tool_use` hooks that apply to the given tool.
+    pub fn pre_tool_use_for(&self, tool_name: &str) -> Vec<AgentHook> {
+        Self::matching(&self.pre_tool_use, tool_name)
+    }
+
+    /// Returns the `post_tool_use` hooks that apply to the given tool.
+    pub fn post_tool_use_for(&self, tool_name: &str) -> Vec<AgentHook> {
+        Self::matching(&self.post_tool_use, tool_name)
+    }
+
+    /// Returns true if any `pre_tool_use` or `post_tool_use` hook applies to the given tool.
+    pub fn has_tool_hooks(&self, tool_name: &str) -> bool {
+        self.pre_tool_use
+            .iter()
+            .chain(self.post_tool_use.iter())
+            .any(|hook| hook.matches_tool(tool_name))
+    }
+
+    fn matching(hooks: &[AgentHook], tool_name: &str) -> Vec<AgentHook> {
+        hooks
+            .iter()
+            .filter(|hook| hook.matches_tool(tool_name))
+            .cloned()
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AgentHook {
+    pub command: String,
+    pub args: Vec<String>,
+    /// Matched against the tool name. `None` matches every tool.
+    pub matcher: Option<CompiledRegex>,
+    pub timeout: Duration,
+}
+
+impl AgentHook {
+    pub fn matches_tool(&self, tool_name: &str) -> bool {
+        self.matcher
+            .as_ref()
+            .is_none_or(|matcher| matcher.is_match(tool_name))
+    }
+}
+
+/// Limits on what the native agent may spend.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AgentBudgets {
+    pub thread: Option<AgentBudget>,
+    pub daily: Option<AgentBudget>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AgentBudget {
+    pub max_tokens: Option<u64>,
+    /// Maximum estimated cost, in US dollars.
+    pub max_cost: Option<f64>,
+    /// Fraction of the budget at which to warn that it is running out.
+    pub warn_at: f32,
+    pub action: AgentBudgetAction,
+}
+
+impl AgentBudget {
+    pub const DEFAULT_WARN_AT: f32 = 0.8;
+
+    /// Returns `None` when the content doesn't limit tokens or cost.
+    pub fn from_content(content: AgentBudgetContent) -> Option<Self> {
+        if content.max_tokens.is_none() && content.max_cost.is_none() {
+            return None;
+        }
+        Some(Self {
+            max_tokens: content.max_tokens,
+            max_cost: content.max_cost,
+            warn_at: content
+                .warn_at
+                .unwrap_or(Self::DEFAULT_WARN_AT)
+                .clamp(0., 1.),
+            action: content.action.unwrap_or_default(),
+        })
+    }
+}
+
+impl From<AgentBudget> for AgentBudgetContent {
+    fn from(budget: AgentBudget) -> Self {
+        Self {
+            max_tokens: budget.max_tokens,
+            max_cost: budget.max_cost,
+            warn_at: Some(budget.warn_at),
+            action: Some(budget.action),
+        }
+    }
+}
+
 pub const HARDCODED_SECURITY_DENIAL_MESSAGE: &str = "Blocked by built-in security rule. This operation is considered too \
      harmful to be allowed, and cannot be overridden by settings.";
 
@@ -439,6 +571,15 @@ impl Settings for AgentSettings {
             message_editor_min_lines: agent.message_editor_min_lines.unwrap(),
             show_turn_stats: agent.show_turn_stats.unwrap(),
             tool_permissions: compile_tool_permissions(agent.tool_permissions),
+            hooks: compile_hooks(agent.hooks),
+            budgets: agent
+                .budgets
+                .map(|budgets| AgentBudgets {
+                    thread: budgets.thread.and_then(AgentBudget::from_content),
+                    daily: budgets.daily.and_then(AgentBudget::from_content),
+                })
+                .unwrap_or_default(),
+            model_pricing: agent.model_pricing,
             new_thread_location: agent.new_thread_location.unwrap_or_default(),
         }
     }
@@ -506,6 +647,57 @@ fn compile_tool_permissions(content: Option<settings::ToolPermissionsContent>) -
     }
 }
 
+fn compile_hooks(content: Option<settings::AgentHooksContent>) -> AgentHooks {
+    let Some(content) = content else {
+        return AgentHooks::default();
+    };
+
+    AgentHooks {
+        pre_tool_use: compile_hook_list(content.pre_tool_use, "pre_tool_use"),
+        post_tool_use: compile_hook_list(content.post_tool_use, "post_tool_use"),
+        stop: compile_hook_list(content.stop, "stop"),
+        user_prompt_submit: compile_hook_list(content.user_prompt_submit, "user_prompt_submit"),
+    }
+}
+
+fn compile_hook_list(
+    hooks: Option<Vec<settings::AgentHookContent>>,
+    event: &str,
+) -> Vec<AgentHook> {
+    hooks
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|hook| {
+            if hook.command.trim().is_empty() {
+                log::error!("Ignoring agent `{event}` hook with an empty command");
+                return None;
+            }
+            let matcher = match hook.matcher.filter(|matcher| !matcher.is_empty()) {
+                Some(pattern) => match CompiledRegex::try_new(&pattern, true) {
+                    Ok(regex) => Some(regex),
+                    Err(error) => {
+                        log::error!(
+                            "Ignoring agent `{event}` hook `{}` with invalid matcher '{pattern}' - {error}",
+                            hook.command,
+                        );
+                        return None;
+                    }
+                },
+                None => None,
+            };
+            Some(AgentHook {
+                command: hook.command,
+                args: hook.args,
+                matcher,
+                timeout: hook
+                    .timeout
+                    .map(Duration::from_secs)
+                    .unwrap_or(AgentHooks::DEFAULT_TIMEOUT),
+            })
+        })
+        .collect()
+}
+
 fn compile_regex_rules(
     rules: Vec<settings::ToolRegexRule>,
     rule_type: &str,
@@ -593,6 +785,37 @@ mod tests {
         assert!(terminal_rules.always_allow[0].is_match("git status"));
     }
 
+    #[test]
+    fn test_hooks_parsing() {
+        let json = json!({
+            "pre_tool_use": [
+                { "command": "./scripts/check-write.sh", "matcher": "^(edit_file|delete_path)$" },
+                { "command": "", "matcher": "terminal" },
+                { "command": "lint", "matcher": "[invalid(regex" }
+            ],
+            "post_tool_use": [
+                { "command": "cargo", "args": ["fmt"], "timeout": 5 }
+            ]
+        });
+
+        let content: settings::AgentHooksContent = serde_json::from_value(json).unwrap();
+        let hooks = compile_hooks(Some(content));
+
+        assert_eq!(hooks.pre_tool_use.len(), 1);
+        assert_eq!(hooks.pre_tool_use[0].command, "./scripts/check-write.sh");
+        assert_eq!(hooks.pre_tool_use[0].timeout, AgentHooks::DEFAULT_TIMEOUT);
+        assert_eq!(hooks.pre_tool_use_for("edit_file").len(), 1);
+        assert!(hooks.pre_tool_use_for("read_file").is_empty());
+
+        assert_eq!(hooks.post_tool_use[0].args, vec!["fmt".to_string()]);
+        assert_eq!(hooks.post_tool_use[0].timeout, Duration::from_secs(5));
+        assert_eq!(hooks.post_tool_use_for("read_file").len(), 1);
+        assert!(hooks.has_tool_hooks("read_file"));
+
+        assert!(hooks.stop.is_empty());
+        assert!(!compile_hooks(None).has_tool_hooks("edit_file"));
+    }
+
     #[test]
     fn test_tool_rules_default() {
         let json = json!({
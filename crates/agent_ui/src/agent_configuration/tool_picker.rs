@@ -310,6 +310,8 @@ impl PickerDelegate for ToolPickerDelegate {
                             })
                             .collect(),
                         default_model: default_profile.default_model.clone(),
+                        terminal_sandbox: default_profile.terminal_sandbox.map(Into::into),
+                        budget: default_profile.budget.map(Into::into),
                     });
 
                 if let Some(server_id) = server_id {
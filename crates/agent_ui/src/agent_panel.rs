@@ -29,8 +29,8 @@ use zed_actions::agent::{
 use crate::{
     AddContextServer, AgentDiffPane, ConversationView, CopyThreadToClipboard, CycleStartThreadIn,
     Follow, InlineAssistant, LoadThreadFromClipboard, NewTextThread, NewThread,
-    OpenActiveThreadAsMarkdown, OpenAgentDiff, OpenHistory, ResetTrialEndUpsell, ResetTrialUpsell,
-    StartThreadIn, ToggleNavigationMenu, ToggleNewThreadMenu, ToggleOptionsMenu,
+    OpenActiveThreadAsMarkdown, OpenAgentDiff, OpenHistory, OpenSpendReport, ResetTrialEndUpsell,
+    ResetTrialUpsell, StartThreadIn, ToggleNavigationMenu, ToggleNewThreadMenu, ToggleOptionsMenu,
     agent_configuration::{AgentConfiguration, AssistantConfigurationEvent},
     conversation_view::{AcpThreadViewEvent, ThreadView},
     slash_command::SlashCommandCompletionProvider,
@@ -298,6 +298,9 @@ pub fn init(cx: &mut App) {
                         });
                     }
                 })
+                .register_action(|workspace, _: &OpenSpendReport, window, cx| {
+                    open_spend_report(workspace, window, cx).detach_and_log_err(cx);
+                })
                 .register_action(|workspace, _: &LoadThreadFromClipboard, window, cx| {
                     if let Some(panel) = workspace.panel::<AgentPanel>(cx) {
                         workspace.focus_panel::<AgentPanel>(window, cx);
@@ -424,6 +427,52 @@ pub fn init(cx: &mut App) {
     .detach();
 }
 
+fn open_spend_report(
+    workspace: &mut Workspace,
+    window: &mut Window,
+    cx: &mut Context<Workspace>,
+) -> Task<Result<()>> {
+    let markdown_language_task = workspace
+        .app_state()
+        .languages
+        .language_for_name("Markdown");
+    let report_task = agent::spend_report(cx);
+    let project = workspace.project().clone();
+
+    cx.spawn_in(window, async move |workspace, cx| {
+        let markdown_language = markdown_language_task.await?;
+        let report = report_task.await?;
+
+        let buffer = project
+            .update(cx, |project, cx| {
+                project.create_buffer(Some(markdown_language), false, cx)
+            })
+            .await?;
+        buffer.update(cx, |buffer, cx| {
+            buffer.set_text(report, cx);
+            buffer.set_capability(language::Capability::ReadOnly, cx);
+        });
+
+        workspace.update_in(cx, |workspace, window, cx| {
+            let title = "Agent Spend";
+            let buffer =
+                cx.new(|cx| MultiBuffer::singleton(buffer, cx).with_title(title.to_string()));
+            workspace.add_item_to_active_pane(
+                Box::new(cx.new(|cx| {
+                    let mut editor =
+                        Editor::for_multibuffer(buffer, Some(project.clone()), window, cx);
+                    editor.set_breadcrumb_header(title.to_string());
+                    editor
+                })),
+                None,
+                true,
+                window,
+                cx,
+            );
+        })
+    })
+}
+
 fn conflict_resource_block(conflict: &ConflictContent) -> acp::ContentBlock {
     let mention_uri = MentionUri::MergeConflict {
         file_path: conflict.file_path.clone(),
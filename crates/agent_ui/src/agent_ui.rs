@@ -129,6 +129,8 @@ actions!(
         OpenActiveThreadAsMarkdown,
         /// Opens the agent diff view to review changes.
         OpenAgentDiff,
+        /// Opens a report of the agent's token usage and estimated cost per model.
+        OpenSpendReport,
         /// Copies the current thread to the clipboard as JSON for debugging.
         CopyThreadToClipboard,
         /// Loads a thread from the clipboard JSON for debugging.
@@ -660,6 +662,9 @@ mod tests {
             use_modifier_to_send: true,
             message_editor_min_lines: 1,
             tool_permissions: Default::default(),
+            hooks: Default::default(),
+            budgets: Default::default(),
+            model_pricing: Vec::new(),
             show_turn_stats: false,
             new_thread_location: Default::default(),
         };
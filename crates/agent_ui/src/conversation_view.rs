@@ -83,7 +83,7 @@ use crate::{
     Agent, AgentDiffPane, AgentInitialContent, AgentPanel, AllowAlways, AllowOnce,
     AuthorizeToolCall, ClearMessageQueue, CycleFavoriteModels, CycleModeSelector,
     CycleThinkingEffort, EditFirstQueuedMessage, ExpandMessageEditor, Follow, KeepAll, NewThread,
-    OpenAddContextMenu, OpenAgentDiff, OpenHistory, RejectAll, RejectOnce,
+    OpenAddContextMenu, OpenAgentDiff, OpenHistory, OpenSpendReport, RejectAll, RejectOnce,
     RemoveFirstQueuedMessage, SendImmediately, SendNextQueuedMessage, ToggleFastMode,
     ToggleProfileSelector, ToggleThinkingEffortMenu, ToggleThinkingMode, UndoLastReject,
 };
@@ -110,6 +110,10 @@ pub(crate) enum ThreadError {
     PaymentRequired,
     Refusal,
     AuthenticationRequired(SharedString),
+    BudgetExceeded {
+        message: SharedString,
+        can_continue: bool,
+    },
     Other {
         message: SharedString,
         acp_error_code: Option<SharedString>,
@@ -124,6 +128,11 @@ impl From<anyhow::Error> for ThreadError {
             && acp_error.code == acp::ErrorCode::AuthRequired
         {
             Self::AuthenticationRequired(acp_error.message.clone().into())
+        } else if let Some(budget_error) = error.downcast_ref::<agent::BudgetExceededError>() {
+            Self::BudgetExceeded {
+                message: budget_error.message.clone().into(),
+                can_continue: budget_error.can_continue,
+            }
         } else {
             let message: SharedString = format!("{:#}", error).into();
 
@@ -193,6 +202,7 @@ impl Conversation {
                 | AcpThreadEvent::EntryUpdated(_)
                 | AcpThreadEvent::EntriesRemoved(_)
                 | AcpThreadEvent::Retry(_)
+                | AcpThreadEvent::Warning(_)
                 | AcpThreadEvent::SubagentSpawned(_)
                 | AcpThreadEvent::Stopped(_)
                 | AcpThreadEvent::Error
@@ -1253,6 +1263,14 @@ impl ConversationView {
                     });
                 }
             }
+            AcpThreadEvent::Warning(warning) => {
+                if let Some(active) = self.thread_view(&thread_id) {
+                    active.update(cx, |active, cx| {
+                        active.thread_warning = Some(warning.clone());
+                        cx.notify();
+                    });
+                }
+            }
             AcpThreadEvent::Stopped(stop_reason) => {
                 if let Some(active) = self.thread_view(&thread_id) {
                     active.update(cx, |active, _cx| {
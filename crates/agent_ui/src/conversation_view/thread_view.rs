@@ -236,6 +236,7 @@ pub struct ThreadView {
     pub profile_selector: Option<Entity<ProfileSelector>>,
     pub permission_dropdown_handle: PopoverMenuHandle<ContextMenu>,
     pub thread_retry_status: Option<RetryStatus>,
+    pub thread_warning: Option<SharedString>,
     pub(super) thread_error: Option<ThreadError>,
     pub thread_error_markdown: Option<Entity<Markdown>>,
     pub token_limit_callout_dismissed: bool,
@@ -481,6 +482,7 @@ impl ThreadView {
             _subscriptions: subscriptions,
             permission_dropdown_handle: PopoverMenuHandle::default(),
             thread_retry_status: None,
+            thread_warning: None,
             thread_error: None,
             thread_error_markdown: None,
             token_limit_callout_dismissed: false,
@@ -975,6 +977,7 @@ impl ThreadView {
         let contents = self.resolve_message_contents(&message_editor, cx);
 
         self.thread_error.take();
+        self.thread_warning.take();
         self.thread_feedback.clear();
         self.editing_message.take();
 
@@ -1211,6 +1214,9 @@ impl ThreadView {
                 ThreadError::AuthenticationRequired(message) => {
                     ("authentication_required", None, message.clone())
                 }
+                ThreadError::BudgetExceeded { message, .. } => {
+                    ("budget_exceeded", None, message.clone())
+                }
                 ThreadError::Other {
                     acp_error_code,
                     message,
@@ -7559,6 +7565,10 @@ impl ThreadView {
                 self.render_authentication_required_error(error.clone(), cx)
             }
             ThreadError::PaymentRequired => self.render_payment_required_error(cx),
+            ThreadError::BudgetExceeded {
+                message,
+                can_continue,
+            } => self.render_budget_exceeded_error(message.clone(), *can_continue, cx),
         };
 
         Some(div().child(content))
@@ -7619,6 +7629,70 @@ impl ThreadView {
             .dismiss_action(self.dismiss_error_button(cx))
     }
 
+    fn render_budget_exceeded_error(
+        &self,
+        message: SharedString,
+        can_continue: bool,
+        cx: &mut Context<Self>,
+    ) -> Callout {
+        let description = if can_continue {
+            format!("{message} Continue to allow this thread to spend another budget's worth.")
+        } else {
+            format!("{message} The agent won't make further requests until the budget resets.")
+        };
+
+        Callout::new()
+            .severity(Severity::Warning)
+            .icon(IconName::Warning)
+            .title("Budget Reached")
+            .description(description)
+            .actions_slot(
+                h_flex()
+                    .gap_0p5()
+                    .when(can_continue, |this| {
+                        this.child(
+                            Button::new("continue-past-budget", "Continue")
+                                .label_size(LabelSize::Small)
+                                .style(ButtonStyle::Filled)
+                                .on_click(cx.listener(|this, _, _window, cx| {
+                                    this.retry_generation(cx);
+                                })),
+                        )
+                    })
+                    .child(self.open_spend_report_button(cx)),
+            )
+            .dismiss_action(self.dismiss_error_button(cx))
+    }
+
+    fn open_spend_report_button(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        Button::new("open-spend-report", "View Spend")
+            .label_size(LabelSize::Small)
+            .on_click(cx.listener(|_this, _, window, cx| {
+                window.dispatch_action(OpenSpendReport.boxed_clone(), cx);
+            }))
+    }
+
+    fn render_thread_warning(&self, cx: &mut Context<Self>) -> Option<Callout> {
+        let warning = self.thread_warning.clone()?;
+        Some(
+            Callout::new()
+                .severity(Severity::Warning)
+                .icon(IconName::Warning)
+                .title("Budget Warning")
+                .description(warning)
+                .actions_slot(self.open_spend_report_button(cx))
+                .dismiss_action(
+                    IconButton::new("dismiss-thread-warning", IconName::Close)
+                        .icon_size(IconSize::Small)
+                        .tooltip(Tooltip::text("Dismiss"))
+                        .on_click(cx.listener(|this, _, _, cx| {
+                            this.thread_warning.take();
+                            cx.notify();
+                        })),
+                ),
+        )
+    }
+
     fn upgrade_button(&self, cx: &mut Context<Self>) -> impl IntoElement {
         Button::new("upgrade", "Upgrade")
             .label_size(LabelSize::Small)
@@ -8303,6 +8377,7 @@ impl Render for ThreadView {
                 this.child(self.render_codex_windows_warning(cx))
             })
             .children(self.render_thread_retry_status_callout())
+            .children(self.render_thread_warning(cx))
             .children(self.render_thread_error(window, cx))
             .when_some(
                 match has_messages {
@@ -0,0 +1,373 @@
+use std::sync::Arc;
+
+use editor::{Editor, SelectionEffects, scroll::Autoscroll};
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    App, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Render, Task,
+    WeakEntity, Window, actions, rems,
+};
+use picker::{Picker, PickerDelegate};
+use project::Project;
+use text::{Point, ToPoint as _};
+use theme::ActiveTheme;
+use ui::{HighlightedLabel, ListItem, ListItemSpacing, prelude::*};
+use workspace::{ModalView, Workspace};
+
+actions!(
+    bookmarks,
+    [
+        /// Opens a picker listing the bookmarks across the project.
+        Toggle,
+        /// Edits the label of the bookmark on the current line, bookmarking the line if needed.
+        EditLabel,
+        /// Removes every bookmark in the project.
+        ClearAll,
+    ]
+);
+
+pub fn init(cx: &mut App) {
+    cx.observe_new(
+        |workspace: &mut Workspace, _window, _: &mut Context<Workspace>| {
+            workspace
+                .register_action(|workspace, _: &Toggle, window, cx| {
+                    let project = workspace.project().clone();
+                    let handle = cx.entity().downgrade();
+                    workspace.toggle_modal(window, cx, move |window, cx| {
+                        let delegate = BookmarksDelegate::new(handle, project, cx);
+                        Picker::uniform_list(delegate, window, cx).width(rems(34.))
+                    });
+                })
+                .register_action(|workspace, _: &EditLabel, window, cx| {
+                    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+                        return;
+                    };
+                    workspace.toggle_modal(window, cx, move |window, cx| {
+                        BookmarkLabelModal::new(editor, window, cx)
+                    });
+                })
+                .register_action(|workspace, _: &ClearAll, _, cx| {
+                    workspace.project().read(cx).bookmark_store().update(
+                        cx,
+                        |bookmark_store, cx| {
+                            bookmark_store.clear_bookmarks(cx);
+                        },
+                    );
+                });
+        },
+    )
+    .detach();
+}
+
+struct BookmarkEntry {
+    buffer: Entity<language::Buffer>,
+    row: u32,
+    label: Option<Arc<str>>,
+    path: SharedString,
+    preview: SharedString,
+}
+
+pub struct BookmarksDelegate {
+    workspace: WeakEntity<Workspace>,
+    entries: Vec<BookmarkEntry>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl BookmarksDelegate {
+    fn new(workspace: WeakEntity<Workspace>, project: Entity<Project>, cx: &App) -> Self {
+        let path_style = project.read(cx).path_style(cx);
+        let bookmark_store = project.read(cx).bookmark_store();
+        let mut entries = Vec::new();
+        for (abs_path, buffer, bookmarks) in bookmark_store.read(cx).all_bookmarks() {
+            let buffer_ref = buffer.read(cx);
+            let snapshot = buffer_ref.snapshot();
+            let path: SharedString = match buffer_ref.file() {
+                Some(file) => file.path().display(path_style).into_owned().into(),
+                None => abs_path.to_string_lossy().into_owned().into(),
+            };
+            for bookmark in bookmarks {
+                if !snapshot.can_resolve(&bookmark.position) {
+                    continue;
+                }
+                let row = bookmark.position.to_point(&snapshot).row;
+                let line = snapshot
+                    .text_for_range(Point::new(row, 0)..Point::new(row, snapshot.line_len(row)))
+                    .collect::<String>();
+                entries.push(BookmarkEntry {
+                    buffer: buffer.clone(),
+                    row,
+                    label: bookmark.label.clone(),
+                    path: path.clone(),
+                    preview: line.trim().to_string().into(),
+                });
+            }
+        }
+
+        let matches = (0..entries.len())
+            .map(|candidate_id| StringMatch {
+                candidate_id,
+                score: 0.,
+                positions: Vec::new(),
+                string: String::new(),
+            })
+            .collect();
+        Self {
+            workspace,
+            entries,
+            matches,
+            selected_index: 0,
+        }
+    }
+
+    fn match_text(entry: &BookmarkEntry) -> String {
+        format!(
+            "{} {}",
+            entry.label.as_deref().unwrap_or(&entry.preview),
+            entry.path
+        )
+    }
+}
+
+impl PickerDelegate for BookmarksDelegate {
+    type ListItem = ListItem;
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+    }
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        "Search bookmarks…".into()
+    }
+
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        Some(if self.entries.is_empty() {
+            "No bookmarks in this project".into()
+        } else {
+            "No matching bookmarks".into()
+        })
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        let candidates = self
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(id, entry)| StringMatchCandidate::new(id, &Self::match_text(entry)))
+            .collect::<Vec<_>>();
+        cx.spawn_in(window, async move |picker, cx| {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        score: 0.,
+                        positions: Vec::new(),
+                        string: candidate.string,
+                    })
+                    .collect()
+            } else {
+                fuzzy::match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    true,
+                    100,
+                    &Default::default(),
+                    cx.background_executor().clone(),
+                )
+                .await
+            };
+            picker
+                .update(cx, |picker, cx| {
+                    let delegate = &mut picker.delegate;
+                    delegate.matches = matches;
+                    delegate.selected_index = delegate
+                        .selected_index
+                        .min(delegate.matches.len().saturating_sub(1));
+                    cx.notify();
+                })
+                .ok();
+        })
+    }
+
+    fn confirm(&mut self, secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(entry) = self
+            .matches
+            .get(self.selected_index)
+            .and_then(|mat| self.entries.get(mat.candidate_id))
+        else {
+            return;
+        };
+        let buffer = entry.buffer.clone();
+        let position = Point::new(entry.row, 0);
+        self.workspace
+            .update(cx, |workspace, cx| {
+                let pane = if secondary {
+                    workspace.adjacent_pane(window, cx)
+                } else {
+                    workspace.active_pane().clone()
+                };
+                let editor = workspace
+                    .open_project_item::<Editor>(pane, buffer, true, true, true, true, window, cx);
+                editor.update(cx, |editor, cx| {
+                    editor.change_selections(
+                        SelectionEffects::scroll(Autoscroll::center()),
+                        window,
+                        cx,
+                        |s| s.select_ranges([position..position]),
+                    );
+                });
+            })
+            .ok();
+        cx.emit(DismissEvent);
+    }
+
+    fn dismissed(&mut self, _window: &mut Window, _cx: &mut Context<Picker<Self>>) {}
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let string_match = self.matches.get(ix)?;
+        let entry = self.entries.get(string_match.candidate_id)?;
+        let title: SharedString = entry
+            .label
+            .as_ref()
+            .map(|label| label.to_string().into())
+            .unwrap_or_else(|| entry.preview.clone());
+        let title_positions = string_match
+            .positions
+            .iter()
+            .copied()
+            .filter(|position| *position < title.len())
+            .collect();
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .toggle_state(selected)
+                .start_slot(Icon::new(IconName::Bookmark).color(Color::Accent))
+                .child(
+                    v_flex()
+                        .child(HighlightedLabel::new(title, title_positions))
+                        .child(
+                            h_flex()
+                                .gap_1()
+                                .child(
+                                    Label::new(format!("{}:{}", entry.path, entry.row + 1))
+                                        .size(LabelSize::Small)
+                                        .color(Color::Muted),
+                                )
+                                .when(entry.label.is_some(), |this| {
+                                    this.child(
+                                        Label::new(entry.preview.clone())
+                                            .size(LabelSize::Small)
+                                            .color(Color::Muted)
+                                            .truncate(),
+                                    )
+                                }),
+                        ),
+                ),
+        )
+    }
+}
+
+pub struct BookmarkLabelModal {
+    label_editor: Entity<Editor>,
+    active_editor: Entity<Editor>,
+}
+
+impl ModalView for BookmarkLabelModal {}
+
+impl EventEmitter<DismissEvent> for BookmarkLabelModal {}
+
+impl Focusable for BookmarkLabelModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.label_editor.focus_handle(cx)
+    }
+}
+
+impl BookmarkLabelModal {
+    fn new(active_editor: Entity<Editor>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let current_label = active_editor
+            .update(cx, |editor, cx| editor.bookmark_at_cursor(window, cx))
+            .flatten();
+        let label_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Bookmark label", window, cx);
+            if let Some(label) = current_label {
+                editor.set_text(label.as_ref(), window, cx);
+                editor.select_all(&editor::actions::SelectAll, window, cx);
+            }
+            editor
+        });
+        Self {
+            label_editor,
+            active_editor,
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let label = self.label_editor.read(cx).text(cx);
+        let label = Some(label.trim())
+            .filter(|label| !label.is_empty())
+            .map(Arc::from);
+        self.active_editor.update(cx, |editor, cx| {
+            editor.set_bookmark_label_at_cursor(label, window, cx);
+            editor.focus_handle(cx).focus(window, cx);
+        });
+        cx.emit(DismissEvent);
+    }
+}
+
+impl Render for BookmarkLabelModal {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .w(rems(24.))
+            .elevation_2(cx)
+            .key_context("BookmarkLabel")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                div()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .px_2()
+                    .py_1()
+                    .child(self.label_editor.clone()),
+            )
+            .child(
+                h_flex().px_2().py_1().child(
+                    Label::new("Leave empty to remove the label")
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                ),
+            )
+    }
+}
@@ -38,3 +38,44 @@ pub enum CliResponse {
 /// Note that in the main zed binary, this variable is unset after it's read for the first time,
 /// therefore it should always be accessed through the `FORCE_CLI_MODE` static.
 pub const FORCE_CLI_MODE_ENV_VAR_NAME: &str = "ZED_FORCE_CLI_MODE";
+
+/// How `zed --agent` answers tool calls that would otherwise ask for confirmation.
+///
+/// Tool permission rules from settings are applied first; this policy only
+/// decides the calls those rules leave up to the user.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum AgentPermissionPolicy {
+    /// Allow the tool call.
+    Allow,
+    /// Reject the tool call, letting the agent continue without it.
+    #[default]
+    Deny,
+}
+
+/// What `zed --agent` prints once the agent has finished.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum AgentOutputFormat {
+    /// A unified diff of the files the agent changed.
+    #[default]
+    Diff,
+    /// A JSON transcript of the thread, including the diff.
+    Json,
+}
+
+impl AgentPermissionPolicy {
+    pub fn as_arg(&self) -> &'static str {
+        match self {
+            Self::Allow => "allow",
+            Self::Deny => "deny",
+        }
+    }
+}
+
+impl AgentOutputFormat {
+    pub fn as_arg(&self) -> &'static str {
+        match self {
+            Self::Diff => "diff",
+            Self::Json => "json",
+        }
+    }
+}
@@ -9,12 +9,15 @@
 
 use anyhow::{Context as _, Result};
 use clap::Parser;
-use cli::{CliRequest, CliResponse, IpcHandshake, ipc::IpcOneShotServer};
+use cli::{
+    AgentOutputFormat, AgentPermissionPolicy, CliRequest, CliResponse, IpcHandshake,
+    ipc::IpcOneShotServer,
+};
 use parking_lot::Mutex;
 use std::{
     collections::{BTreeMap, BTreeSet},
     env,
-    ffi::OsStr,
+    ffi::{OsStr, OsString},
     fs, io,
     path::{Path, PathBuf},
     process::ExitStatus,
@@ -58,7 +61,9 @@ Examples:
     `zed path-to-your-project`
           Open your project in Zed
     `zed -n path-to-file `
-          Open file/folder in a new window",
+          Open file/folder in a new window
+    `zed --agent \"fix the failing test\" path-to-your-project`
+          Run the agent without opening a window and print its changes as a diff",
     after_help = "To read from stdin, append '-', e.g. 'ps axf | zed -'"
 )]
 struct Args {
@@ -122,6 +127,24 @@ struct Args {
     /// When directories are provided, recurses into them and shows all changed files in a single multi-diff view.
     #[arg(long, action = clap::ArgAction::Append, num_args = 2, value_names = ["OLD_PATH", "NEW_PATH"])]
     diff: Vec<String>,
+    /// Run the agent with this prompt against the given paths without opening a window.
+    ///
+    /// Tool calls are streamed to stdout, followed by the changes the agent made.
+    #[arg(long, value_name = "PROMPT", allow_hyphen_values = true)]
+    agent: Option<String>,
+    /// The agent profile to use with `--agent`. Defaults to the `agent.default_profile` setting.
+    #[arg(long, requires = "agent", value_name = "PROFILE")]
+    agent_profile: Option<String>,
+    /// The language model to use with `--agent`, in `provider/model` format.
+    /// Defaults to the `agent.default_model` setting.
+    #[arg(long, requires = "agent", value_name = "PROVIDER/MODEL")]
+    agent_model: Option<String>,
+    /// How to answer tool calls that need confirmation when running with `--agent`.
+    #[arg(long, requires = "agent", value_enum, default_value_t)]
+    agent_permissions: AgentPermissionPolicy,
+    /// What to print when the agent finishes when running with `--agent`.
+    #[arg(long, requires = "agent", value_enum, default_value_t)]
+    agent_output: AgentOutputFormat,
     /// Uninstall Zed from user system
     #[cfg(all(
         any(target_os = "linux", target_os = "macos"),
@@ -136,6 +159,44 @@ struct Args {
     askpass: Option<String>,
 }
 
+/// Runs the Zed binary headlessly in `--agent` mode, forwarding its output and exit status.
+fn run_agent(app: &impl InstalledApp, prompt: &str, args: &Args) -> Result<()> {
+    let status = std::process::Command::new(app.path())
+        .args(agent_args(prompt, args)?)
+        .status()
+        .with_context(|| format!("failed to run {}", app.path().display()))?;
+    std::process::exit(status.code().unwrap_or(1));
+}
+
+/// Returns the arguments that run the Zed binary in `--agent` mode with `prompt`.
+fn agent_args(prompt: &str, args: &Args) -> Result<Vec<OsString>> {
+    // Attached with `=`, so that a prompt starting with `-` isn't parsed as a flag.
+    let mut agent_args = vec![
+        OsString::from(format!("--agent={prompt}")),
+        "--agent-permissions".into(),
+        args.agent_permissions.as_arg().into(),
+        "--agent-output".into(),
+        args.agent_output.as_arg().into(),
+    ];
+    if let Some(profile) = &args.agent_profile {
+        agent_args.push("--agent-profile".into());
+        agent_args.push(profile.into());
+    }
+    if let Some(model) = &args.agent_model {
+        agent_args.push("--agent-model".into());
+        agent_args.push(model.into());
+    }
+    if let Some(dir) = &args.user_data_dir {
+        agent_args.push("--user-data-dir".into());
+        agent_args.push(dir.into());
+    }
+    for path in &args.paths_with_position {
+        let path = fs::canonicalize(path).with_context(|| format!("invalid path {path:?}"))?;
+        agent_args.push(path.into());
+    }
+    Ok(agent_args)
+}
+
 /// Parses a path containing a position (e.g. `path:line:column`)
 /// and returns its canonicalized string representation.
 ///
@@ -313,6 +374,18 @@ mod tests {
         result
     }
 
+    #[test]
+    fn test_agent_args_with_hyphenated_prompt() {
+        let args = Args::try_parse_from(["zed", "--agent", "-v crashes on startup"]).unwrap();
+        assert_eq!(args.agent.as_deref(), Some("-v crashes on startup"));
+
+        let agent_args = agent_args(args.agent.as_deref().unwrap(), &args).unwrap();
+        let forwarded =
+            Args::try_parse_from(std::iter::once(OsString::from("zed")).chain(agent_args)).unwrap();
+        assert_eq!(forwarded.agent.as_deref(), Some("-v crashes on startup"));
+        assert!(forwarded.paths_with_position.is_empty());
+    }
+
     #[test]
     fn test_parse_non_existing_path() {
         // Absolute path
@@ -503,6 +576,10 @@ fn main() -> Result<()> {
         anyhow::bail!(msg.join("\n"));
     }
 
+    if let Some(prompt) = &args.agent {
+        return run_agent(&app, prompt, &args);
+    }
+
     #[cfg(all(
         any(target_os = "linux", target_os = "macos"),
         not(feature = "no-bundled-uninstall")
@@ -201,6 +201,31 @@ CREATE UNIQUE INDEX "index_project_collaborators_on_project_id_connection_id_and
     "connection_server_id"
 );
 
+CREATE TABLE "review_threads" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+    "project_id" INTEGER NOT NULL REFERENCES projects (id) ON DELETE CASCADE,
+    "worktree_id" INTEGER NOT NULL,
+    "path" VARCHAR NOT NULL,
+    "buffer_id" INTEGER NOT NULL,
+    "start_anchor" BLOB NOT NULL,
+    "end_anchor" BLOB NOT NULL,
+    "original_text" TEXT NOT NULL,
+    "resolved" BOOLEAN NOT NULL DEFAULT FALSE,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX "index_review_threads_on_project_id" ON "review_threads" ("project_id");
+
+CREATE TABLE "review_comments" (
+    "id" INTEGER PRIMARY KEY AUTOINCREMENT,
+    "thread_id" INTEGER NOT NULL REFERENCES review_threads (id) ON DELETE CASCADE,
+    "user_id" INTEGER NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    "body" TEXT NOT NULL,
+    "created_at" TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX "index_review_comments_on_thread_id" ON "review_comments" ("thread_id");
+
 CREATE TABLE "room_participants" (
     "id" INTEGER PRIMARY KEY AUTOINCREMENT,
     "room_id" INTEGER NOT NULL REFERENCES rooms (id),
@@ -345,6 +345,47 @@ CREATE SEQUENCE public.projects_id_seq
 
 ALTER SEQUENCE public.projects_id_seq OWNED BY public.projects.id;
 
+CREATE TABLE public.review_comments (
+    id integer NOT NULL,
+    thread_id integer NOT NULL,
+    user_id integer NOT NULL,
+    body text NOT NULL,
+    created_at timestamp without time zone DEFAULT now() NOT NULL
+);
+
+CREATE SEQUENCE public.review_comments_id_seq
+    AS integer
+    START WITH 1
+    INCREMENT BY 1
+    NO MINVALUE
+    NO MAXVALUE
+    CACHE 1;
+
+ALTER SEQUENCE public.review_comments_id_seq OWNED BY public.review_comments.id;
+
+CREATE TABLE public.review_threads (
+    id integer NOT NULL,
+    project_id integer NOT NULL,
+    worktree_id bigint NOT NULL,
+    path text NOT NULL,
+    buffer_id bigint NOT NULL,
+    start_anchor bytea NOT NULL,
+    end_anchor bytea NOT NULL,
+    original_text text NOT NULL,
+    resolved boolean DEFAULT false NOT NULL,
+    created_at timestamp without time zone DEFAULT now() NOT NULL
+);
+
+CREATE SEQUENCE public.review_threads_id_seq
+    AS integer
+    START WITH 1
+    INCREMENT BY 1
+    NO MINVALUE
+    NO MAXVALUE
+    CACHE 1;
+
+ALTER SEQUENCE public.review_threads_id_seq OWNED BY public.review_threads.id;
+
 CREATE TABLE public.room_participants (
     id integer NOT NULL,
     room_id integer NOT NULL,
@@ -512,6 +553,10 @@ ALTER TABLE ONLY public.project_collaborators ALTER COLUMN id SET DEFAULT nextva
 
 ALTER TABLE ONLY public.projects ALTER COLUMN id SET DEFAULT nextval('public.projects_id_seq'::regclass);
 
+ALTER TABLE ONLY public.review_comments ALTER COLUMN id SET DEFAULT nextval('public.review_comments_id_seq'::regclass);
+
+ALTER TABLE ONLY public.review_threads ALTER COLUMN id SET DEFAULT nextval('public.review_threads_id_seq'::regclass);
+
 ALTER TABLE ONLY public.room_participants ALTER COLUMN id SET DEFAULT nextval('public.room_participants_id_seq'::regclass);
 
 ALTER TABLE ONLY public.rooms ALTER COLUMN id SET DEFAULT nextval('public.rooms_id_seq'::regclass);
@@ -583,6 +628,12 @@ ALTER TABLE ONLY public.project_repository_statuses
 ALTER TABLE ONLY public.projects
     ADD CONSTRAINT projects_pkey PRIMARY KEY (id);
 
+ALTER TABLE ONLY public.review_comments
+    ADD CONSTRAINT review_comments_pkey PRIMARY KEY (id);
+
+ALTER TABLE ONLY public.review_threads
+    ADD CONSTRAINT review_threads_pkey PRIMARY KEY (id);
+
 ALTER TABLE ONLY public.room_participants
     ADD CONSTRAINT room_participants_pkey PRIMARY KEY (id);
 
@@ -674,6 +725,10 @@ CREATE INDEX index_projects_on_host_connection_id_and_host_connection_server ON
 
 CREATE INDEX index_projects_on_host_connection_server_id ON public.projects USING btree (host_connection_server_id);
 
+CREATE INDEX index_review_comments_on_thread_id ON public.review_comments USING btree (thread_id);
+
+CREATE INDEX index_review_threads_on_project_id ON public.review_threads USING btree (project_id);
+
 CREATE INDEX index_room_participants_on_answering_connection_id ON public.room_participants USING btree (answering_connection_id);
 
 CREATE UNIQUE INDEX index_room_participants_on_answering_connection_id_and_answerin ON public.room_participants USING btree (answering_connection_id, answering_connection_server_id);
@@ -812,6 +867,15 @@ ALTER TABLE ONLY public.projects
 ALTER TABLE ONLY public.projects
     ADD CONSTRAINT projects_room_id_fkey FOREIGN KEY (room_id) REFERENCES public.rooms(id) ON DELETE CASCADE;
 
+ALTER TABLE ONLY public.review_comments
+    ADD CONSTRAINT review_comments_thread_id_fkey FOREIGN KEY (thread_id) REFERENCES public.review_threads(id) ON DELETE CASCADE;
+
+ALTER TABLE ONLY public.review_comments
+    ADD CONSTRAINT review_comments_user_id_fkey FOREIGN KEY (user_id) REFERENCES public.users(id) ON DELETE CASCADE;
+
+ALTER TABLE ONLY public.review_threads
+    ADD CONSTRAINT review_threads_project_id_fkey FOREIGN KEY (project_id) REFERENCES public.projects(id) ON DELETE CASCADE;
+
 ALTER TABLE ONLY public.room_participants
     ADD CONSTRAINT room_participants_answering_connection_server_id_fkey FOREIGN KEY (answering_connection_server_id) REFERENCES public.servers(id) ON DELETE CASCADE;
 
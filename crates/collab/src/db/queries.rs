@@ -7,6 +7,7 @@ pub mod contributors;
 pub mod extensions;
 pub mod notifications;
 pub mod projects;
+pub mod review_threads;
 pub mod rooms;
 pub mod servers;
 pub mod shared_threads;
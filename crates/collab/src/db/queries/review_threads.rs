@@ -0,0 +1,213 @@
+use super::*;
+use anyhow::Context as _;
+use prost::Message;
+
+impl Database {
+    /// Starts a review thread on some code in a shared project, with its first comment.
+    ///
+    /// Returns the thread along with the connections of everyone in the project.
+    pub async fn create_review_thread(
+        &self,
+        request: &proto::CreateReviewThread,
+        connection: ConnectionId,
+        user_id: UserId,
+    ) -> Result<TransactionGuard<(proto::ReviewThread, Vec<ConnectionId>)>> {
+        let project_id = ProjectId::from_proto(request.project_id);
+        self.project_transaction(project_id, |tx| async move {
+            self.access_project(project_id, connection, Capability::ReadWrite, &tx)
+                .await?;
+            if request.body.trim().is_empty() {
+                return Err(anyhow!("comments can't be empty"))?;
+            }
+            let start = request.start.as_ref().context("invalid start anchor")?;
+            let end = request.end.as_ref().context("invalid end anchor")?;
+
+            let thread = review_thread::ActiveModel {
+                project_id: ActiveValue::Set(project_id),
+                worktree_id: ActiveValue::Set(request.worktree_id as i64),
+                path: ActiveValue::Set(request.path.clone()),
+                buffer_id: ActiveValue::Set(request.buffer_id as i64),
+                start_anchor: ActiveValue::Set(start.encode_to_vec()),
+                end_anchor: ActiveValue::Set(end.encode_to_vec()),
+                original_text: ActiveValue::Set(request.original_text.clone()),
+                ..Default::default()
+            }
+            .insert(&*tx)
+            .await?;
+            review_comment::ActiveModel {
+                thread_id: ActiveValue::Set(thread.id),
+                user_id: ActiveValue::Set(user_id),
+                body: ActiveValue::Set(request.body.clone()),
+                ..Default::default()
+            }
+            .insert(&*tx)
+            .await?;
+
+            self.review_thread_update(thread, &tx).await
+        })
+        .await
+    }
+
+    /// Adds a comment to a review thread.
+    ///
+    /// Returns the thread along with the connections of everyone in the project.
+    pub async fn reply_to_review_thread(
+        &self,
+        request: &proto::ReplyToReviewThread,
+        connection: ConnectionId,
+        user_id: UserId,
+    ) -> Result<TransactionGuard<(proto::ReviewThread, Vec<ConnectionId>)>> {
+        let project_id = ProjectId::from_proto(request.project_id);
+        self.project_transaction(project_id, |tx| async move {
+            self.access_project(project_id, connection, Capability::ReadWrite, &tx)
+                .await?;
+            if request.body.trim().is_empty() {
+                return Err(anyhow!("comments can't be empty"))?;
+            }
+            let thread = self
+                .project_review_thread(project_id, request.thread_id, &tx)
+                .await?;
+
+            review_comment::ActiveModel {
+                thread_id: ActiveValue::Set(thread.id),
+                user_id: ActiveValue::Set(user_id),
+                body: ActiveValue::Set(request.body.clone()),
+                ..Default::default()
+            }
+            .insert(&*tx)
+            .await?;
+
+            self.review_thread_update(thread, &tx).await
+        })
+        .await
+    }
+
+    /// Resolves or reopens a review thread.
+    ///
+    /// Returns the thread along with the connections of everyone in the project.
+    pub async fn resolve_review_thread(
+        &self,
+        request: &proto::ResolveReviewThread,
+        connection: ConnectionId,
+    ) -> Result<TransactionGuard<(proto::ReviewThread, Vec<ConnectionId>)>> {
+        let project_id = ProjectId::from_proto(request.project_id);
+        self.project_transaction(project_id, |tx| async move {
+            self.access_project(project_id, connection, Capability::ReadWrite, &tx)
+                .await?;
+            let thread = self
+                .project_review_thread(project_id, request.thread_id, &tx)
+                .await?;
+
+            let mut thread: review_thread::ActiveModel = thread.into();
+            thread.resolved = ActiveValue::Set(request.resolved);
+            let thread = thread.update(&*tx).await?;
+
+            self.review_thread_update(thread, &tx).await
+        })
+        .await
+    }
+
+    /// Returns the review threads of a shared project, oldest first.
+    pub async fn get_review_threads(
+        &self,
+        project_id: ProjectId,
+        connection: ConnectionId,
+    ) -> Result<Vec<proto::ReviewThread>> {
+        self.project_transaction(project_id, |tx| async move {
+            self.access_project(project_id, connection, Capability::ReadOnly, &tx)
+                .await?;
+
+            let threads = review_thread::Entity::find()
+                .filter(review_thread::Column::ProjectId.eq(project_id))
+                .order_by_asc(review_thread::Column::Id)
+                .all(&*tx)
+                .await?;
+            let mut comments_by_thread = HashMap::<ReviewThreadId, Vec<_>>::default();
+            for comment in review_comment::Entity::find()
+                .filter(review_comment::Column::ThreadId.is_in(threads.iter().map(|t| t.id)))
+                .order_by_asc(review_comment::Column::Id)
+                .all(&*tx)
+                .await?
+            {
+                comments_by_thread
+                    .entry(comment.thread_id)
+                    .or_default()
+                    .push(comment);
+            }
+
+            threads
+                .into_iter()
+                .map(|thread| {
+                    let comments = comments_by_thread.remove(&thread.id).unwrap_or_default();
+                    review_thread_to_proto(thread, comments)
+                })
+                .collect()
+        })
+        .await
+        .map(|guard| guard.into_inner())
+    }
+
+    async fn project_review_thread(
+        &self,
+        project_id: ProjectId,
+        thread_id: u64,
+        tx: &DatabaseTransaction,
+    ) -> Result<review_thread::Model> {
+        Ok(
+            review_thread::Entity::find_by_id(ReviewThreadId::from_proto(thread_id))
+                .filter(review_thread::Column::ProjectId.eq(project_id))
+                .one(tx)
+                .await?
+                .context("no such review thread")?,
+        )
+    }
+
+    async fn review_thread_update(
+        &self,
+        thread: review_thread::Model,
+        tx: &DatabaseTransaction,
+    ) -> Result<(proto::ReviewThread, Vec<ConnectionId>)> {
+        let comments = review_comment::Entity::find()
+            .filter(review_comment::Column::ThreadId.eq(thread.id))
+            .order_by_asc(review_comment::Column::Id)
+            .all(tx)
+            .await?;
+        let connection_ids = project_collaborator::Entity::find()
+            .filter(project_collaborator::Column::ProjectId.eq(thread.project_id))
+            .all(tx)
+            .await?
+            .into_iter()
+            .map(|collaborator| collaborator.connection())
+            .collect();
+        Ok((review_thread_to_proto(thread, comments)?, connection_ids))
+    }
+}
+
+fn review_thread_to_proto(
+    thread: review_thread::Model,
+    comments: Vec<review_comment::Model>,
+) -> Result<proto::ReviewThread> {
+    let start = proto::Anchor::decode(thread.start_anchor.as_slice())
+        .map_err(|error| anyhow!("{error}"))?;
+    let end =
+        proto::Anchor::decode(thread.end_anchor.as_slice()).map_err(|error| anyhow!("{error}"))?;
+    Ok(proto::ReviewThread {
+        id: thread.id.to_proto(),
+        worktree_id: thread.worktree_id as u64,
+        path: thread.path,
+        buffer_id: thread.buffer_id as u64,
+        start: Some(start),
+        end: Some(end),
+        original_text: thread.original_text,
+        resolved: thread.resolved,
+        comments: comments
+            .into_iter()
+            .map(|comment| proto::ReviewComment {
+                id: comment.id.to_proto(),
+                user_id: comment.user_id.to_proto(),
+                body: comment.body,
+                timestamp: comment.created_at.assume_utc().unix_timestamp() as u64,
+            })
+            .collect(),
+    })
+}
@@ -18,6 +18,8 @@ pub mod project;
 pub mod project_collaborator;
 pub mod project_repository;
 pub mod project_repository_statuses;
+pub mod review_comment;
+pub mod review_thread;
 pub mod room;
 pub mod room_participant;
 pub mod server;
@@ -0,0 +1,48 @@
+use crate::db::{ProjectId, ReviewThreadId};
+use sea_orm::entity::prelude::*;
+use time::PrimitiveDateTime;
+
+#[derive(Clone, Debug, PartialEq, Eq, DeriveEntityModel)]
+#[sea_orm(table_name = "review_threads")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: ReviewThreadId,
+    pub project_id: ProjectId,
+    pub worktree_id: i64,
+    pub path: String,
+    pub buffer_id: i64,
+    /// The encoded `proto::Anchor` where the commented code starts.
+    pub start_anchor: Vec<u8>,
+    /// The encoded `proto::Anchor` where the commented code ends.
+    pub end_anchor: Vec<u8>,
+    /// The commented code when the thread was started.
+    pub original_text: String,
+    pub resolved: bool,
+    pub created_at: PrimitiveDateTime,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::project::Entity",
+        from = "Column::ProjectId",
+        to = "super::project::Column::Id"
+    )]
+    Project,
+    #[sea_orm(has_many = "super::review_comment::Entity")]
+    Comments,
+}
+
+impl Related<super::project::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Project.def()
+    }
+}
+
+impl Related<super::review_comment::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Comments.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
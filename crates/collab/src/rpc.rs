@@ -429,6 +429,15 @@ impl Server {
             .add_request_handler(forward_mutating_project_request::<proto::SetIndexText>)
             .add_request_handler(forward_mutating_project_request::<proto::ToggleBreakpoint>)
             .add_message_handler(broadcast_project_message_from_host::<proto::BreakpointsForFile>)
+            .add_request_handler(forward_mutating_project_request::<proto::EditBookmark>)
+            .add_message_handler(broadcast_project_message_from_host::<proto::BookmarksForFile>)
+            .add_message_handler(broadcast_project_message_from_host::<proto::UpdateSharedTerminal>)
+            .add_message_handler(broadcast_project_message_from_host::<proto::UnshareTerminal>)
+            .add_request_handler(forward_mutating_project_request::<proto::SharedTerminalInput>)
+            .add_request_handler(create_review_thread)
+            .add_request_handler(reply_to_review_thread)
+            .add_request_handler(resolve_review_thread)
+            .add_request_handler(get_review_threads)
             .add_request_handler(forward_mutating_project_request::<proto::OpenCommitMessageBuffer>)
             .add_request_handler(forward_mutating_project_request::<proto::GitDiff>)
             .add_request_handler(forward_mutating_project_request::<proto::GetTreeDiff>)
@@ -2452,6 +2461,103 @@ async fn broadcast_project_message_from_host<T: EntityMessage<Entity = ShareProj
     Ok(())
 }
 
+/// Start a review thread on some code in a shared project.
+async fn create_review_thread(
+    request: proto::CreateReviewThread,
+    response: Response<proto::CreateReviewThread>,
+    session: MessageContext,
+) -> Result<()> {
+    let guard = session
+        .db()
+        .await
+        .create_review_thread(&request, session.connection_id, session.user_id())
+        .await?;
+    let (thread, connection_ids) = &*guard;
+    broadcast_review_thread(request.project_id, thread, connection_ids, &session);
+    response.send(proto::ReviewThreadResponse {
+        thread: Some(thread.clone()),
+    })?;
+    Ok(())
+}
+
+/// Add a comment to a review thread.
+async fn reply_to_review_thread(
+    request: proto::ReplyToReviewThread,
+    response: Response<proto::ReplyToReviewThread>,
+    session: MessageContext,
+) -> Result<()> {
+    let guard = session
+        .db()
+        .await
+        .reply_to_review_thread(&request, session.connection_id, session.user_id())
+        .await?;
+    let (thread, connection_ids) = &*guard;
+    broadcast_review_thread(request.project_id, thread, connection_ids, &session);
+    response.send(proto::ReviewThreadResponse {
+        thread: Some(thread.clone()),
+    })?;
+    Ok(())
+}
+
+/// Resolve or reopen a review thread.
+async fn resolve_review_thread(
+    request: proto::ResolveReviewThread,
+    response: Response<proto::ResolveReviewThread>,
+    session: MessageContext,
+) -> Result<()> {
+    let guard = session
+        .db()
+        .await
+        .resolve_review_thread(&request, session.connection_id)
+        .await?;
+    let (thread, connection_ids) = &*guard;
+    broadcast_review_thread(request.project_id, thread, connection_ids, &session);
+    response.send(proto::ReviewThreadResponse {
+        thread: Some(thread.clone()),
+    })?;
+    Ok(())
+}
+
+/// Notify everyone else in a project that one of its review threads changed.
+fn broadcast_review_thread(
+    project_id: u64,
+    thread: &proto::ReviewThread,
+    connection_ids: &[ConnectionId],
+    session: &MessageContext,
+) {
+    broadcast(
+        Some(session.connection_id),
+        connection_ids.iter().copied(),
+        |connection_id| {
+            session.peer.send(
+                connection_id,
+                proto::UpdateReviewThread {
+                    project_id,
+                    thread: Some(thread.clone()),
+                },
+            )
+        },
+    );
+}
+
+/// Get the review threads of a shared project.
+async fn get_review_threads(
+    request: proto::GetReviewThreads,
+    response: Response<proto::GetReviewThreads>,
+    session: MessageContext,
+) -> Result<()> {
+    let threads = session
+        .db()
+        .await
+        .get_review_threads(
+            ProjectId::from_proto(request.project_id),
+            session.connection_id,
+        )
+        .await?;
+    response.send(proto::GetReviewThreadsResponse { threads })?;
+    Ok(())
+}
+
 /// Start following another user in a call.
 async fn follow(
     request: proto::Follow,
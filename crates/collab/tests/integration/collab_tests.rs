@@ -16,6 +16,8 @@ mod random_channel_buffer_tests;
 mod random_project_collaboration_tests;
 mod randomized_test_helpers;
 mod remote_editing_collaboration_tests;
+mod review_thread_tests;
+mod terminal_sharing_tests;
 mod test_server;
 
 pub use randomized_test_helpers::{
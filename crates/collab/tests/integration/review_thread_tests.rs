@@ -0,0 +1,152 @@
+use call::ActiveCall;
+use gpui::{BackgroundExecutor, Entity, TestAppContext};
+use language::Point;
+use project::{Project, review_thread_store::ReviewThread};
+use serde_json::json;
+use util::rel_path::rel_path;
+
+use crate::TestServer;
+
+#[gpui::test]
+async fn test_review_threads(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+    cx_c: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    let client_c = server.create_client(cx_c, "user_c").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b), (&client_c, cx_c)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree("/a", json!({ "a.txt": "one\ntwo\nthree\n" }))
+        .await;
+    let (project_a, worktree_id) = client_a.build_local_project("/a", cx_a).await;
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.join_remote_project(project_id, cx_b).await;
+    executor.run_until_parked();
+
+    let buffer_a = project_a
+        .update(cx_a, |project, cx| {
+            project.open_buffer((worktree_id, rel_path("a.txt")), cx)
+        })
+        .await
+        .unwrap();
+    let buffer_b = project_b
+        .update(cx_b, |project, cx| {
+            project.open_buffer((worktree_id, rel_path("a.txt")), cx)
+        })
+        .await
+        .unwrap();
+
+    // A guest starts a thread on the second line, and the host sees it.
+    let range = buffer_b.read_with(cx_b, |buffer, _| {
+        buffer.anchor_before(Point::new(1, 0))..buffer.anchor_after(Point::new(1, 3))
+    });
+    let thread_id = project_b
+        .read_with(cx_b, |project, _| project.review_thread_store())
+        .update(cx_b, |store, cx| {
+            store.create_thread(&buffer_b, range, "Should this be a number?".into(), cx)
+        })
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    let thread = review_thread(&project_a, thread_id, cx_a).unwrap();
+    assert_eq!(thread.original_text, "two");
+    assert!(!thread.resolved);
+    assert_eq!(thread.comments.len(), 1);
+    assert_eq!(thread.comments[0].user_id, client_b.user_id().unwrap());
+    assert_eq!(thread.comments[0].body, "Should this be a number?");
+    assert!(!buffer_a.read_with(cx_a, |buffer, _| thread.is_outdated(buffer)));
+
+    // The host replies, and the guest sees the reply.
+    project_a
+        .read_with(cx_a, |project, _| project.review_thread_store())
+        .update(cx_a, |store, cx| {
+            store.reply(thread_id, "Good idea".into(), cx)
+        })
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    let thread = review_thread(&project_b, thread_id, cx_b).unwrap();
+    assert_eq!(
+        thread
+            .comments
+            .iter()
+            .map(|comment| (comment.user_id, comment.body.as_str()))
+            .collect::<Vec<_>>(),
+        [
+            (client_b.user_id().unwrap(), "Should this be a number?"),
+            (client_a.user_id().unwrap(), "Good idea"),
+        ]
+    );
+
+    // Once the commented code changes, the thread is outdated.
+    buffer_a.update(cx_a, |buffer, cx| {
+        buffer.edit([(Point::new(1, 0)..Point::new(1, 3), "2")], None, cx)
+    });
+    executor.run_until_parked();
+    assert!(buffer_b.read_with(cx_b, |buffer, _| thread.is_outdated(buffer)));
+
+    // Empty comments are rejected.
+    assert!(
+        project_b
+            .read_with(cx_b, |project, _| project.review_thread_store())
+            .update(cx_b, |store, cx| store.reply(thread_id, " ".into(), cx))
+            .await
+            .is_err()
+    );
+
+    // The guest resolves the thread, and the host can reopen it.
+    project_b
+        .read_with(cx_b, |project, _| project.review_thread_store())
+        .update(cx_b, |store, cx| store.set_resolved(thread_id, true, cx))
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    assert!(review_thread(&project_a, thread_id, cx_a).unwrap().resolved);
+    project_a
+        .read_with(cx_a, |project, _| project.review_thread_store())
+        .update(cx_a, |store, cx| store.set_resolved(thread_id, false, cx))
+        .await
+        .unwrap();
+    executor.run_until_parked();
+    assert!(!review_thread(&project_b, thread_id, cx_b).unwrap().resolved);
+
+    // Guests who join later see the threads that were already started.
+    let project_c = client_c.join_remote_project(project_id, cx_c).await;
+    executor.run_until_parked();
+    let thread = review_thread(&project_c, thread_id, cx_c).unwrap();
+    assert_eq!(thread.comments.len(), 2);
+    assert!(!thread.resolved);
+
+    // Unsharing the project removes its threads.
+    project_a
+        .update(cx_a, |project, cx| project.unshare(cx))
+        .unwrap();
+    executor.run_until_parked();
+    assert!(review_thread(&project_a, thread_id, cx_a).is_none());
+}
+
+fn review_thread(
+    project: &Entity<Project>,
+    thread_id: u64,
+    cx: &mut TestAppContext,
+) -> Option<ReviewThread> {
+    project.read_with(cx, |project, cx| {
+        project
+            .review_thread_store()
+            .read(cx)
+            .thread(thread_id)
+            .cloned()
+    })
+}
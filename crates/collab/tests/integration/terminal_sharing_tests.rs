@@ -0,0 +1,125 @@
+use call::ActiveCall;
+use gpui::{AppContext as _, BackgroundExecutor, Entity, TestAppContext};
+use project::{Project, shared_terminal_store::TerminalAccess};
+use serde_json::json;
+use std::time::Duration;
+use terminal::{
+    Terminal, TerminalBuilder,
+    terminal_settings::{AlternateScroll, CursorShape},
+};
+use util::paths::PathStyle;
+
+use crate::TestServer;
+
+#[gpui::test]
+async fn test_share_terminal(
+    executor: BackgroundExecutor,
+    cx_a: &mut TestAppContext,
+    cx_b: &mut TestAppContext,
+) {
+    let mut server = TestServer::start(executor.clone()).await;
+    let client_a = server.create_client(cx_a, "user_a").await;
+    let client_b = server.create_client(cx_b, "user_b").await;
+    server
+        .create_room(&mut [(&client_a, cx_a), (&client_b, cx_b)])
+        .await;
+    let active_call_a = cx_a.read(ActiveCall::global);
+
+    client_a
+        .fs()
+        .insert_tree("/a", json!({ "a.txt": "a-contents" }))
+        .await;
+    let (project_a, _) = client_a.build_local_project("/a", cx_a).await;
+    let project_id = active_call_a
+        .update(cx_a, |call, cx| call.share_project(project_a.clone(), cx))
+        .await
+        .unwrap();
+    let project_b = client_b.join_remote_project(project_id, cx_b).await;
+    executor.run_until_parked();
+
+    let terminal_a = cx_a.new(|cx| {
+        TerminalBuilder::new_display_only(
+            CursorShape::default(),
+            AlternateScroll::On,
+            None,
+            0,
+            cx.background_executor(),
+            PathStyle::local(),
+        )
+        .unwrap()
+        .subscribe(cx)
+    });
+    terminal_a.update(cx_a, |terminal, cx| {
+        terminal.write_output(b"$ echo hello\r\nhello\r\n$ ", cx)
+    });
+
+    // Guests see the host's screen once it's shared.
+    project_a
+        .read_with(cx_a, |project, _| project.shared_terminal_store())
+        .update(cx_a, |store, cx| {
+            store.share_terminal(&terminal_a, TerminalAccess::ReadOnly, cx)
+        })
+        .unwrap();
+    executor.run_until_parked();
+    let (terminal_b, access) = joined_terminal(&project_b, cx_b).unwrap();
+    assert_eq!(access, TerminalAccess::ReadOnly);
+    assert!(
+        terminal_b
+            .read_with(cx_b, |terminal, _| terminal.get_content())
+            .contains("$ echo hello")
+    );
+
+    // The host's output reaches guests.
+    terminal_a.update(cx_a, |terminal, cx| terminal.write_output(b"ls\r\n", cx));
+    executor.advance_clock(Duration::from_millis(100));
+    executor.run_until_parked();
+    assert!(
+        terminal_b
+            .read_with(cx_b, |terminal, _| terminal.get_content())
+            .contains("$ ls")
+    );
+
+    // Input to read-only terminals is dropped.
+    terminal_b.update(cx_b, |terminal, _| terminal.input(b"pwd\r".to_vec()));
+    executor.run_until_parked();
+    assert!(terminal_a.update(cx_a, |terminal, _| terminal.take_input_log().is_empty()));
+
+    // Once the terminal is read-write, the guest can type in it.
+    project_a
+        .read_with(cx_a, |project, _| project.shared_terminal_store())
+        .update(cx_a, |store, cx| {
+            store.share_terminal(&terminal_a, TerminalAccess::ReadWrite, cx)
+        })
+        .unwrap();
+    executor.run_until_parked();
+    let (same_terminal_b, access) = joined_terminal(&project_b, cx_b).unwrap();
+    assert_eq!(same_terminal_b, terminal_b);
+    assert_eq!(access, TerminalAccess::ReadWrite);
+    terminal_b.update(cx_b, |terminal, _| terminal.input(b"pwd\r".to_vec()));
+    executor.run_until_parked();
+    assert_eq!(
+        terminal_a.update(cx_a, |terminal, _| terminal.take_input_log()),
+        [b"pwd\r".to_vec()]
+    );
+
+    // Unsharing the terminal closes it for guests.
+    project_a
+        .read_with(cx_a, |project, _| project.shared_terminal_store())
+        .update(cx_a, |store, cx| store.unshare_terminal(&terminal_a, cx));
+    executor.run_until_parked();
+    assert!(joined_terminal(&project_b, cx_b).is_none());
+}
+
+fn joined_terminal(
+    project: &Entity<Project>,
+    cx: &mut TestAppContext,
+) -> Option<(Entity<Terminal>, TerminalAccess)> {
+    project.read_with(cx, |project, cx| {
+        project
+            .shared_terminal_store()
+            .read(cx)
+            .joined_terminals()
+            .next()
+            .map(|(terminal, access)| (terminal.clone(), access))
+    })
+}
@@ -0,0 +1,493 @@
+use std::{collections::BTreeMap, ffi::OsString, path::PathBuf};
+
+use anyhow::{Context as _, Result, anyhow};
+use async_trait::async_trait;
+use serde::Deserialize;
+
+/// Arguments for building an image from a Dockerfile.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct BuildOptions {
+    pub tag: String,
+    pub dockerfile: PathBuf,
+    pub context: PathBuf,
+    pub build_args: BTreeMap<String, String>,
+    pub target: Option<String>,
+    pub extra_args: Vec<String>,
+}
+
+/// Arguments for creating and starting a container.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct RunOptions {
+    pub image: String,
+    pub labels: Vec<(String, String)>,
+    pub env: BTreeMap<String, String>,
+    pub mounts: Vec<String>,
+    pub user: Option<String>,
+    /// Replaces the image's command with one that keeps the container alive.
+    pub override_command: bool,
+    pub extra_args: Vec<String>,
+}
+
+/// Identifies a Docker Compose project.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ComposeProject {
+    pub name: String,
+    pub files: Vec<PathBuf>,
+}
+
+/// Arguments for running a command inside a container.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ExecOptions {
+    pub container_id: String,
+    pub user: Option<String>,
+    pub env: BTreeMap<String, String>,
+    pub working_dir: Option<String>,
+    pub command: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ContainerDetails {
+    pub id: String,
+    pub running: bool,
+    /// The user the container runs as, if the image or container sets one.
+    pub user: Option<String>,
+}
+
+/// The container engine a dev container runs on.
+///
+/// [`DockerRuntime`] drives the `docker` or `podman` CLI directly, while tests
+/// use a fake that records what it was asked to do.
+#[async_trait]
+pub(crate) trait ContainerRuntime: Send + Sync {
+    async fn build_image(&self, options: &BuildOptions) -> Result<()>;
+    /// Returns the user configured in the image, if any, pulling the image
+    /// if it isn't available locally.
+    async fn image_user(&self, image: &str) -> Result<Option<String>>;
+    /// Finds a container, running or stopped, carrying all of the labels.
+    async fn find_container(&self, labels: &[(String, String)])
+    -> Result<Option<ContainerDetails>>;
+    /// Creates and starts a container, returning its id.
+    async fn run_container(&self, options: &RunOptions) -> Result<String>;
+    async fn start_container(&self, container_id: &str) -> Result<()>;
+    /// Returns the fully resolved Compose configuration.
+    async fn compose_config(&self, project: &ComposeProject) -> Result<serde_json::Value>;
+    async fn compose_build(&self, project: &ComposeProject, services: &[String]) -> Result<()>;
+    async fn compose_up(&self, project: &ComposeProject, services: &[String]) -> Result<()>;
+    async fn exec(&self, options: &ExecOptions) -> Result<()>;
+}
+
+pub(crate) struct DockerRuntime {
+    binary: &'static str,
+}
+
+impl DockerRuntime {
+    pub fn new(use_podman: bool) -> Self {
+        Self {
+            binary: if use_podman { "podman" } else { "docker" },
+        }
+    }
+
+    async fn run(&self, args: Vec<OsString>) -> Result<String> {
+        let mut command = util::command::new_command(self.binary);
+        command.args(&args);
+        log::info!("Running {} {:?}", self.binary, args);
+        let output = command
+            .output()
+            .await
+            .with_context(|| format!("running {}", self.binary))?;
+        if !output.status.success() {
+            return Err(anyhow!(
+                "{} {} failed with {}: {}",
+                self.binary,
+                args.first()
+                    .map(|arg| arg.to_string_lossy())
+                    .unwrap_or_default(),
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    async fn inspect_container(&self, container_id: &str) -> Result<ContainerDetails> {
+        #[derive(Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct Inspect {
+            id: String,
+            state: InspectState,
+            config: InspectConfig,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct InspectState {
+            running: bool,
+        }
+        #[derive(Deserialize)]
+        #[serde(rename_all = "PascalCase")]
+        struct InspectConfig {
+            user: Option<String>,
+        }
+
+        let output = self
+            .run(vec!["inspect".into(), container_id.into()])
+            .await?;
+        let inspect = serde_json::from_str::<Vec<Inspect>>(&output)
+            .context("parsing container details")?
+            .into_iter()
+            .next()
+            .context("container not found")?;
+        Ok(ContainerDetails {
+            id: inspect.id,
+            running: inspect.state.running,
+            user: inspect.config.user.filter(|user| !user.is_empty()),
+        })
+    }
+
+    fn compose_args(project: &ComposeProject) -> Vec<OsString> {
+        let mut args: Vec<OsString> =
+            vec!["compose".into(), "-p".into(), project.name.clone().into()];
+        for file in &project.files {
+            args.push("-f".into());
+            args.push(file.as_os_str().into());
+        }
+        args
+    }
+}
+
+#[async_trait]
+impl ContainerRuntime for DockerRuntime {
+    async fn build_image(&self, options: &BuildOptions) -> Result<()> {
+        let mut args: Vec<OsString> = vec![
+            "build".into(),
+            "-t".into(),
+            options.tag.clone().into(),
+            "-f".into(),
+            options.dockerfile.as_os_str().into(),
+        ];
+        for (name, value) in &options.build_args {
+            args.push("--build-arg".into());
+            args.push(format!("{name}={value}").into());
+        }
+        if let Some(target) = &options.target {
+            args.push("--target".into());
+            args.push(target.into());
+        }
+        args.extend(options.extra_args.iter().map(OsString::from));
+        args.push(options.context.as_os_str().into());
+        self.run(args).await?;
+        Ok(())
+    }
+
+    async fn image_user(&self, image: &str) -> Result<Option<String>> {
+        let inspect_args: Vec<OsString> = vec![
+            "image".into(),
+            "inspect".into(),
+            "--format".into(),
+            "{{.Config.User}}".into(),
+            image.into(),
+        ];
+        let output = match self.run(inspect_args.clone()).await {
+            Ok(output) => output,
+            Err(_) => {
+                self.run(vec!["pull".into(), image.into()])
+                    .await
+                    .with_context(|| format!("pulling {image}"))?;
+                self.run(inspect_args).await?
+            }
+        };
+        let user = output.trim();
+        Ok((!user.is_empty()).then(|| user.to_string()))
+    }
+
+    async fn find_container(
+        &self,
+        labels: &[(String, String)],
+    ) -> Result<Option<ContainerDetails>> {
+        let mut args: Vec<OsString> = vec!["ps".into(), "-a".into(), "-q".into()];
+        for (name, value) in labels {
+            args.push("--filter".into());
+            args.push(format!("label={name}={value}").into());
+        }
+        let output = self.run(args).await?;
+        let Some(container_id) = output.lines().map(str::trim).find(|id| !id.is_empty()) else {
+            return Ok(None);
+        };
+        self.inspect_container(container_id).await.map(Some)
+    }
+
+    async fn run_container(&self, options: &RunOptions) -> Result<String> {
+        let mut args: Vec<OsString> = vec!["run".into(), "-d".into()];
+        for (name, value) in &options.labels {
+            args.push("--label".into());
+            args.push(format!("{name}={value}").into());
+        }
+        for (name, value) in &options.env {
+            args.push("-e".into());
+            args.push(format!("{name}={value}").into());
+        }
+        for mount in &options.mounts {
+            args.push("--mount".into());
+            args.push(mount.into());
+        }
+        if let Some(user) = &options.user {
+            args.push("-u".into());
+            args.push(user.into());
+        }
+        args.extend(options.extra_args.iter().map(OsString::from));
+        if options.override_command {
+            args.push("--entrypoint".into());
+            args.push("/bin/sh".into());
+        }
+        args.push(options.image.clone().into());
+        if options.override_command {
+            args.push("-c".into());
+            args.push(KEEP_ALIVE_SCRIPT.into());
+        }
+        let output = self.run(args).await?;
+        Ok(output.trim().to_string())
+    }
+
+    async fn start_container(&self, container_id: &str) -> Result<()> {
+        self.run(vec!["start".into(), container_id.into()]).await?;
+        Ok(())
+    }
+
+    async fn compose_config(&self, project: &ComposeProject) -> Result<serde_json::Value> {
+        let mut args = Self::compose_args(project);
+        args.extend(["config", "--format", "json"].map(OsString::from));
+        let output = self.run(args).await?;
+        serde_json::from_str(&output).context("parsing compose configuration")
+    }
+
+    async fn compose_build(&self, project: &ComposeProject, services: &[String]) -> Result<()> {
+        let mut args = Self::compose_args(project);
+        args.push("build".into());
+        args.extend(services.iter().map(OsString::from));
+        self.run(args).await?;
+        Ok(())
+    }
+
+    async fn compose_up(&self, project: &ComposeProject, services: &[String]) -> Result<()> {
+        let mut args = Self::compose_args(project);
+        args.extend(["up", "-d", "--no-build"].map(OsString::from));
+        args.extend(services.iter().map(OsString::from));
+        self.run(args).await?;
+        Ok(())
+    }
+
+    async fn exec(&self, options: &ExecOptions) -> Result<()> {
+        let mut args: Vec<OsString> = vec!["exec".into()];
+        if let Some(user) = &options.user {
+            args.push("-u".into());
+            args.push(user.into());
+        }
+        if let Some(working_dir) = &options.working_dir {
+            args.push("-w".into());
+            args.push(working_dir.into());
+        }
+        for (name, value) in &options.env {
+            args.push("-e".into());
+            args.push(format!("{name}={value}").into());
+        }
+        args.push(options.container_id.clone().into());
+        args.extend(options.command.iter().map(OsString::from));
+        let output = self.run(args).await?;
+        if !output.is_empty() {
+            log::info!("{}", output.trim_end());
+        }
+        Ok(())
+    }
+}
+
+/// Keeps a container whose command was overridden running until it's stopped.
+/// This is the same script the reference implementation uses.
+pub(crate) const KEEP_ALIVE_SCRIPT: &str =
+    "echo Container started\ntrap \"exit 0\" 15\nwhile sleep 1 & wait $!; do :; done";
+
+/// Lowercases a name and drops the characters that aren't allowed in image
+/// and Compose project names.
+pub(crate) fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .filter_map(|c| match c {
+            'a'..='z' | '0'..='9' | '_' | '-' => Some(c),
+            'A'..='Z' => Some(c.to_ascii_lowercase()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod fake {
+    use std::collections::BTreeMap;
+
+    use anyhow::{Result, anyhow};
+    use async_trait::async_trait;
+    use parking_lot::Mutex;
+
+    use super::{
+        BuildOptions, ComposeProject, ContainerDetails, ContainerRuntime, ExecOptions, RunOptions,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    pub(crate) enum Call {
+        Build(BuildOptions),
+        Run(RunOptions),
+        Start(String),
+        ComposeBuild(ComposeProject, Vec<String>),
+        ComposeUp(ComposeProject, Vec<String>),
+        Exec(ExecOptions),
+    }
+
+    struct FakeContainer {
+        id: String,
+        labels: Vec<(String, String)>,
+        running: bool,
+        user: Option<String>,
+    }
+
+    /// A container runtime that records the calls made to it, without running
+    /// anything.
+    #[derive(Default)]
+    pub(crate) struct FakeContainerRuntime {
+        calls: Mutex<Vec<Call>>,
+        containers: Mutex<Vec<FakeContainer>>,
+        image_users: Mutex<BTreeMap<String, String>>,
+        compose_config: Mutex<serde_json::Value>,
+        failing_commands: Mutex<Vec<String>>,
+    }
+
+    impl FakeContainerRuntime {
+        pub fn calls(&self) -> Vec<Call> {
+            self.calls.lock().clone()
+        }
+
+        pub fn clear_calls(&self) {
+            self.calls.lock().clear();
+        }
+
+        pub fn set_image_user(&self, image: &str, user: &str) {
+            self.image_users
+                .lock()
+                .insert(image.to_string(), user.to_string());
+        }
+
+        pub fn set_compose_config(&self, config: serde_json::Value) {
+            *self.compose_config.lock() = config;
+        }
+
+        /// Makes `exec` fail for commands whose last argument is `command`.
+        pub fn fail_command(&self, command: &str) {
+            self.failing_commands.lock().push(command.to_string());
+        }
+
+        pub fn stop_all(&self) {
+            for container in self.containers.lock().iter_mut() {
+                container.running = false;
+            }
+        }
+
+        fn add_container(&self, labels: Vec<(String, String)>, user: Option<String>) -> String {
+            let mut containers = self.containers.lock();
+            let id = format!("container-{}", containers.len() + 1);
+            containers.push(FakeContainer {
+                id: id.clone(),
+                labels,
+                running: true,
+                user,
+            });
+            id
+        }
+    }
+
+    #[async_trait]
+    impl ContainerRuntime for FakeContainerRuntime {
+        async fn build_image(&self, options: &BuildOptions) -> Result<()> {
+            self.calls.lock().push(Call::Build(options.clone()));
+            Ok(())
+        }
+
+        async fn image_user(&self, image: &str) -> Result<Option<String>> {
+            Ok(self.image_users.lock().get(image).cloned())
+        }
+
+        async fn find_container(
+            &self,
+            labels: &[(String, String)],
+        ) -> Result<Option<ContainerDetails>> {
+            Ok(self
+                .containers
+                .lock()
+                .iter()
+                .find(|container| labels.iter().all(|label| container.labels.contains(label)))
+                .map(|container| ContainerDetails {
+                    id: container.id.clone(),
+                    running: container.running,
+                    user: container.user.clone(),
+                }))
+        }
+
+        async fn run_container(&self, options: &RunOptions) -> Result<String> {
+            self.calls.lock().push(Call::Run(options.clone()));
+            let user = options
+                .user
+                .clone()
+                .or_else(|| self.image_users.lock().get(&options.image).cloned());
+            Ok(self.add_container(options.labels.clone(), user))
+        }
+
+        async fn start_container(&self, container_id: &str) -> Result<()> {
+            self.calls
+                .lock()
+                .push(Call::Start(container_id.to_string()));
+            let mut containers = self.containers.lock();
+            let container = containers
+                .iter_mut()
+                .find(|container| container.id == container_id)
+                .ok_or_else(|| anyhow!("no such container {container_id}"))?;
+            container.running = true;
+            Ok(())
+        }
+
+        async fn compose_config(&self, _: &ComposeProject) -> Result<serde_json::Value> {
+            Ok(self.compose_config.lock().clone())
+        }
+
+        async fn compose_build(&self, project: &ComposeProject, services: &[String]) -> Result<()> {
+            self.calls
+                .lock()
+                .push(Call::ComposeBuild(project.clone(), services.to_vec()));
+            Ok(())
+        }
+
+        async fn compose_up(&self, project: &ComposeProject, services: &[String]) -> Result<()> {
+            self.calls
+                .lock()
+                .push(Call::ComposeUp(project.clone(), services.to_vec()));
+            for service in services {
+                self.add_container(
+                    vec![
+                        (
+                            "com.docker.compose.project".to_string(),
+                            project.name.clone(),
+                        ),
+                        ("com.docker.compose.service".to_string(), service.clone()),
+                    ],
+                    None,
+                );
+            }
+            Ok(())
+        }
+
+        async fn exec(&self, options: &ExecOptions) -> Result<()> {
+            self.calls.lock().push(Call::Exec(options.clone()));
+            if options
+                .command
+                .last()
+                .is_some_and(|command| self.failing_commands.lock().contains(command))
+            {
+                return Err(anyhow!("command exited with status 1"));
+            }
+            Ok(())
+        }
+    }
+}
@@ -13,7 +13,10 @@ use util::rel_path::RelPath;
 use workspace::Workspace;
 use worktree::Snapshot;
 
-use crate::{DevContainerContext, DevContainerFeature, DevContainerTemplate};
+use crate::{
+    DevContainerContext, DevContainerFeature, DevContainerTemplate,
+    container_runtime::DockerRuntime, engine::devcontainer_up,
+};
 
 /// Represents a discovered devcontainer configuration
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -40,32 +43,12 @@ impl DevContainerConfig {
     }
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-struct DevContainerUp {
-    _outcome: String,
-    container_id: String,
-    remote_user: String,
-    remote_workspace_folder: String,
-}
-
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct DevContainerApply {
     pub(crate) files: Vec<String>,
 }
 
-#[derive(Debug, Deserialize)]
-#[serde(rename_all = "camelCase")]
-pub(crate) struct DevContainerConfiguration {
-    name: Option<String>,
-}
-
-#[derive(Debug, Deserialize)]
-pub(crate) struct DevContainerConfigurationOutput {
-    configuration: DevContainerConfiguration,
-}
-
 pub(crate) struct DevContainerCli {
     pub path: PathBuf,
     node_runtime_path: Option<PathBuf>,
@@ -98,6 +81,7 @@ pub enum DevContainerError {
     DevContainerUpFailed(String),
     DevContainerNotFound,
     DevContainerParseFailed,
+    DevContainerConfigInvalid(String),
     NodeRuntimeNotAvailable,
     NotInValidProject,
 }
@@ -112,8 +96,8 @@ impl Display for DevContainerError {
                     "docker CLI not found on $PATH".to_string(),
                 DevContainerError::DevContainerCliNotAvailable =>
                     "devcontainer CLI not found on path".to_string(),
-                DevContainerError::DevContainerUpFailed(_) => {
-                    "DevContainer creation failed".to_string()
+                DevContainerError::DevContainerUpFailed(message) => {
+                    format!("DevContainer creation failed: {message}")
                 }
                 DevContainerError::DevContainerTemplateApplyFailed(_) => {
                     "DevContainer template apply failed".to_string()
@@ -122,6 +106,8 @@ impl Display for DevContainerError {
                     "No valid dev container definition found in project".to_string(),
                 DevContainerError::DevContainerParseFailed =>
                     "Failed to parse file .devcontainer/devcontainer.json".to_string(),
+                DevContainerError::DevContainerConfigInvalid(message) =>
+                    format!("Invalid dev container configuration: {message}"),
                 DevContainerError::NodeRuntimeNotAvailable =>
                     "Cannot find a valid node runtime".to_string(),
                 DevContainerError::NotInValidProject => "Not within a valid project".to_string(),
@@ -243,42 +229,55 @@ pub async fn start_dev_container_with_config(
     config: Option<DevContainerConfig>,
 ) -> Result<(DevContainerConnection, String), DevContainerError> {
     check_for_docker(context.use_podman).await?;
-    let cli = ensure_devcontainer_cli(&context.node_runtime).await?;
-    let config_path = config.map(|c| context.project_directory.join(&c.config_path));
-
-    match devcontainer_up(&context, &cli, config_path.as_deref()).await {
-        Ok(DevContainerUp {
-            container_id,
-            remote_workspace_folder,
-            remote_user,
-            ..
-        }) => {
-            let project_name =
-                match read_devcontainer_configuration(&context, &cli, config_path.as_deref()).await
-                {
-                    Ok(DevContainerConfigurationOutput {
-                        configuration:
-                            DevContainerConfiguration {
-                                name: Some(project_name),
-                            },
-                    }) => project_name,
-                    _ => get_backup_project_name(&remote_workspace_folder, &container_id),
-                };
-
-            let connection = DevContainerConnection {
-                name: project_name,
-                container_id,
-                use_podman: context.use_podman,
-                remote_user,
-            };
-
-            Ok((connection, remote_workspace_folder))
-        }
-        Err(err) => {
-            let message = format!("Failed with nested error: {}", err);
-            Err(DevContainerError::DevContainerUpFailed(message))
+    let config = match config {
+        Some(config) => config,
+        None => find_config_on_disk(&context)
+            .await
+            .ok_or(DevContainerError::DevContainerNotFound)?,
+    };
+    let config_path = context.project_directory.join(&config.config_path);
+
+    let runtime = DockerRuntime::new(context.use_podman);
+    let up = devcontainer_up(
+        &context.project_directory,
+        &config_path,
+        &runtime,
+        &context.fs,
+        &context.http_client,
+    )
+    .await?;
+
+    let project_name = up
+        .name
+        .unwrap_or_else(|| get_backup_project_name(&up.remote_workspace_folder, &up.container_id));
+    let connection = DevContainerConnection {
+        name: project_name,
+        container_id: up.container_id,
+        use_podman: context.use_podman,
+        remote_user: up.remote_user,
+    };
+
+    Ok((connection, up.remote_workspace_folder))
+}
+
+/// Returns the configuration that's used when none is picked explicitly,
+/// preferring `.devcontainer/devcontainer.json` over `.devcontainer.json`.
+pub(crate) async fn find_config_on_disk(
+    context: &DevContainerContext,
+) -> Option<DevContainerConfig> {
+    for config in [
+        DevContainerConfig::default_config(),
+        DevContainerConfig::root_config(),
+    ] {
+        if context
+            .fs
+            .is_file(&context.project_directory.join(&config.config_path))
+            .await
+        {
+            return Some(config);
         }
     }
+    None
 }
 
 #[cfg(not(target_os = "windows"))]
@@ -409,85 +408,6 @@ pub(crate) async fn ensure_devcontainer_cli(
     }
 }
 
-async fn devcontainer_up(
-    context: &DevContainerContext,
-    cli: &DevContainerCli,
-    config_path: Option<&Path>,
-) -> Result<DevContainerUp, DevContainerError> {
-    let mut command = cli.command(context.use_podman);
-    command.arg("up");
-    command.arg("--workspace-folder");
-    command.arg(context.project_directory.display().to_string());
-
-    if let Some(config) = config_path {
-        command.arg("--config");
-        command.arg(config.display().to_string());
-    }
-
-    log::info!("Running full devcontainer up command: {:?}", command);
-
-    match command.output().await {
-        Ok(output) => {
-            if output.status.success() {
-                let raw = String::from_utf8_lossy(&output.stdout);
-                parse_json_from_cli(&raw)
-            } else {
-                let message = format!(
-                    "Non-success status running devcontainer up for workspace: out: {}, err: {}",
-                    String::from_utf8_lossy(&output.stdout),
-                    String::from_utf8_lossy(&output.stderr)
-                );
-
-                log::error!("{}", &message);
-                Err(DevContainerError::DevContainerUpFailed(message))
-            }
-        }
-        Err(e) => {
-            let message = format!("Error running devcontainer up: {:?}", e);
-            log::error!("{}", &message);
-            Err(DevContainerError::DevContainerUpFailed(message))
-        }
-    }
-}
-
-pub(crate) async fn read_devcontainer_configuration(
-    context: &DevContainerContext,
-    cli: &DevContainerCli,
-    config_path: Option<&Path>,
-) -> Result<DevContainerConfigurationOutput, DevContainerError> {
-    let mut command = cli.command(context.use_podman);
-    command.arg("read-configuration");
-    command.arg("--workspace-folder");
-    command.arg(context.project_directory.display().to_string());
-
-    if let Some(config) = config_path {
-        command.arg("--config");
-        command.arg(config.display().to_string());
-    }
-
-    match command.output().await {
-        Ok(output) => {
-            if output.status.success() {
-                let raw = String::from_utf8_lossy(&output.stdout);
-                parse_json_from_cli(&raw)
-            } else {
-                let message = format!(
-                    "Non-success status running devcontainer read-configuration for workspace: out: {:?}, err: {:?}",
-                    String::from_utf8_lossy(&output.stdout),
-                    String::from_utf8_lossy(&output.stderr)
-                );
-                log::error!("{}", &message);
-                Err(DevContainerError::DevContainerNotFound)
-            }
-        }
-        Err(e) => {
-            let message = format!("Error running devcontainer read-configuration: {:?}", e);
-            log::error!("{}", &message);
-            Err(DevContainerError::DevContainerNotFound)
-        }
-    }
-}
-
 pub(crate) async fn apply_dev_container_template(
     template: &DevContainerTemplate,
     template_options: &HashMap<String, String>,
@@ -545,6 +465,7 @@ pub(crate) async fn apply_dev_container_template(
         }
     }
 }
+
 // Try to parse directly first (newer versions output pure JSON)
 // If that fails, look for JSON start (older versions have plaintext prefix)
 fn parse_json_from_cli<T: serde::de::DeserializeOwned>(raw: &str) -> Result<T, DevContainerError> {
@@ -605,7 +526,7 @@ mod tests {
     use std::path::PathBuf;
 
     use crate::devcontainer_api::{
-        DevContainerConfig, DevContainerUp, find_configs_in_snapshot, parse_json_from_cli,
+        DevContainerApply, DevContainerConfig, find_configs_in_snapshot, parse_json_from_cli,
     };
     use fs::FakeFs;
     use gpui::TestAppContext;
@@ -622,27 +543,15 @@ mod tests {
     }
 
     #[test]
-    fn should_parse_from_devcontainer_json() {
-        let json = r#"{"outcome":"success","containerId":"826abcac45afd412abff083ab30793daff2f3c8ce2c831df728baf39933cb37a","remoteUser":"vscode","remoteWorkspaceFolder":"/workspaces/zed"}"#;
-        let up: DevContainerUp = parse_json_from_cli(json).unwrap();
-        assert_eq!(up._outcome, "success");
-        assert_eq!(
-            up.container_id,
-            "826abcac45afd412abff083ab30793daff2f3c8ce2c831df728baf39933cb37a"
-        );
-        assert_eq!(up.remote_user, "vscode");
-        assert_eq!(up.remote_workspace_folder, "/workspaces/zed");
+    fn should_parse_from_devcontainer_cli_output() {
+        let json = r#"{"files":["./.devcontainer/devcontainer.json"]}"#;
+        let apply: DevContainerApply = parse_json_from_cli(json).unwrap();
+        assert_eq!(apply.files, ["./.devcontainer/devcontainer.json"]);
 
         let json_in_plaintext = r#"[2026-01-22T16:19:08.802Z] @devcontainers/cli 0.80.1. Node.js v22.21.1. darwin 24.6.0 arm64.
-            {"outcome":"success","containerId":"826abcac45afd412abff083ab30793daff2f3c8ce2c831df728baf39933cb37a","remoteUser":"vscode","remoteWorkspaceFolder":"/workspaces/zed"}"#;
-        let up: DevContainerUp = parse_json_from_cli(json_in_plaintext).unwrap();
-        assert_eq!(up._outcome, "success");
-        assert_eq!(
-            up.container_id,
-            "826abcac45afd412abff083ab30793daff2f3c8ce2c831df728baf39933cb37a"
-        );
-        assert_eq!(up.remote_user, "vscode");
-        assert_eq!(up.remote_workspace_folder, "/workspaces/zed");
+            {"files":["./.devcontainer/devcontainer.json"]}"#;
+        let apply: DevContainerApply = parse_json_from_cli(json_in_plaintext).unwrap();
+        assert_eq!(apply.files, ["./.devcontainer/devcontainer.json"]);
     }
 
     #[gpui::test]
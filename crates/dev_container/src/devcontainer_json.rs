@@ -0,0 +1,460 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::devcontainer_api::DevContainerError;
+
+/// The subset of the `devcontainer.json` schema that Zed understands.
+///
+/// See <https://containers.dev/implementors/json_reference/>.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DevContainerJson {
+    pub name: Option<String>,
+    pub image: Option<String>,
+    pub build: Option<BuildConfig>,
+    /// Deprecated top-level spelling of `build.dockerfile`.
+    #[serde(rename = "dockerFile")]
+    pub docker_file: Option<String>,
+    /// Deprecated top-level spelling of `build.context`.
+    pub context: Option<String>,
+    pub docker_compose_file: Option<OneOrMany>,
+    pub service: Option<String>,
+    #[serde(default)]
+    pub run_services: Vec<String>,
+    pub workspace_folder: Option<String>,
+    pub workspace_mount: Option<String>,
+    #[serde(default)]
+    pub features: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    pub override_feature_install_order: Vec<String>,
+    #[serde(default)]
+    pub container_env: BTreeMap<String, String>,
+    #[serde(default)]
+    pub remote_env: BTreeMap<String, Option<String>>,
+    pub container_user: Option<String>,
+    pub remote_user: Option<String>,
+    #[serde(default)]
+    pub run_args: Vec<String>,
+    #[serde(default)]
+    pub mounts: Vec<Mount>,
+    pub override_command: Option<bool>,
+    pub initialize_command: Option<LifecycleCommand>,
+    pub on_create_command: Option<LifecycleCommand>,
+    pub update_content_command: Option<LifecycleCommand>,
+    pub post_create_command: Option<LifecycleCommand>,
+    pub post_start_command: Option<LifecycleCommand>,
+    pub post_attach_command: Option<LifecycleCommand>,
+    /// The folder the project is opened at inside the container, with the
+    /// spec's default applied.
+    #[serde(skip)]
+    pub container_workspace_folder: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BuildConfig {
+    pub dockerfile: Option<String>,
+    pub context: Option<String>,
+    #[serde(default)]
+    pub args: BTreeMap<String, String>,
+    pub target: Option<String>,
+    #[serde(default)]
+    pub options: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum OneOrMany {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl OneOrMany {
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            OneOrMany::One(value) => vec![value.clone()],
+            OneOrMany::Many(values) => values.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum Mount {
+    /// A mount in `docker run --mount` syntax.
+    Spec(String),
+    Object {
+        #[serde(rename = "type")]
+        kind: String,
+        source: Option<String>,
+        target: String,
+    },
+}
+
+impl Mount {
+    /// Returns the mount in `docker run --mount` syntax.
+    pub fn to_mount_arg(&self) -> String {
+        match self {
+            Mount::Spec(spec) => spec.clone(),
+            Mount::Object {
+                kind,
+                source: Some(source),
+                target,
+            } => format!("type={kind},source={source},target={target}"),
+            Mount::Object {
+                kind,
+                source: None,
+                target,
+            } => format!("type={kind},target={target}"),
+        }
+    }
+}
+
+/// A lifecycle hook such as `postCreateCommand`.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub(crate) enum LifecycleCommand {
+    /// Runs through a shell.
+    Shell(String),
+    /// Runs the program directly, without a shell.
+    Exec(Vec<String>),
+    /// Runs each named command in parallel.
+    Parallel(BTreeMap<String, LifecycleCommand>),
+}
+
+impl LifecycleCommand {
+    /// Returns the commands to run in the container, as argument vectors.
+    pub fn commands(&self) -> Vec<Vec<String>> {
+        self.commands_with_shell(&["/bin/sh", "-c"])
+    }
+
+    /// Returns the commands to run on the host, like `initializeCommand`, as argument vectors.
+    /// Shell commands run through the host's shell, which is `cmd.exe` on Windows.
+    pub fn host_commands(&self) -> Vec<Vec<String>> {
+        if cfg!(windows) {
+            self.commands_with_shell(&["cmd.exe", "/C"])
+        } else {
+            self.commands()
+        }
+    }
+
+    fn commands_with_shell(&self, shell: &[&str]) -> Vec<Vec<String>> {
+        match self {
+            LifecycleCommand::Shell(script) => {
+                let mut command = shell.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+                command.push(script.clone());
+                vec![command]
+            }
+            LifecycleCommand::Exec(args) if args.is_empty() => Vec::new(),
+            LifecycleCommand::Exec(args) => vec![args.clone()],
+            LifecycleCommand::Parallel(commands) => commands
+                .values()
+                .flat_map(|command| command.commands_with_shell(shell))
+                .collect(),
+        }
+    }
+}
+
+/// How the container's image is produced.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum DevContainerSource {
+    Image(String),
+    Dockerfile {
+        dockerfile: PathBuf,
+        context: PathBuf,
+    },
+    Compose {
+        files: Vec<PathBuf>,
+        service: String,
+    },
+}
+
+impl DevContainerJson {
+    /// Works out whether the configuration uses an image, a Dockerfile or a
+    /// Docker Compose file. Relative paths are resolved against the folder
+    /// containing `devcontainer.json`.
+    pub fn source(&self, config_dir: &Path) -> Result<DevContainerSource, DevContainerError> {
+        if let Some(compose_files) = &self.docker_compose_file {
+            let Some(service) = self.service.clone() else {
+                return Err(DevContainerError::DevContainerConfigInvalid(
+                    "`dockerComposeFile` requires a `service`".into(),
+                ));
+            };
+            return Ok(DevContainerSource::Compose {
+                files: compose_files
+                    .to_vec()
+                    .iter()
+                    .map(|file| config_dir.join(file))
+                    .collect(),
+                service,
+            });
+        }
+
+        let dockerfile = self
+            .build
+            .as_ref()
+            .and_then(|build| build.dockerfile.as_ref())
+            .or(self.docker_file.as_ref());
+        if let Some(dockerfile) = dockerfile {
+            let context = self
+                .build
+                .as_ref()
+                .and_then(|build| build.context.as_ref())
+                .or(self.context.as_ref())
+                .map_or_else(
+                    || config_dir.to_path_buf(),
+                    |context| config_dir.join(context),
+                );
+            return Ok(DevContainerSource::Dockerfile {
+                dockerfile: config_dir.join(dockerfile),
+                context,
+            });
+        }
+
+        if let Some(image) = &self.image {
+            return Ok(DevContainerSource::Image(image.clone()));
+        }
+
+        Err(DevContainerError::DevContainerConfigInvalid(
+            "one of `image`, `build.dockerfile` or `dockerComposeFile` must be set".into(),
+        ))
+    }
+}
+
+/// Parses a `devcontainer.json` file, substituting `${...}` variables.
+pub(crate) fn parse_devcontainer_json(
+    contents: &str,
+    local_workspace_folder: &Path,
+    devcontainer_id: &str,
+) -> Result<DevContainerJson, DevContainerError> {
+    let mut value =
+        settings::parse_json_with_comments::<serde_json::Value>(contents).map_err(|e| {
+            log::error!("Failed to parse devcontainer.json: {e:?}");
+            DevContainerError::DevContainerParseFailed
+        })?;
+
+    let local_workspace_folder_basename = local_workspace_folder
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut variables = BTreeMap::from_iter([
+        (
+            "localWorkspaceFolder".to_string(),
+            local_workspace_folder.display().to_string(),
+        ),
+        (
+            "localWorkspaceFolderBasename".to_string(),
+            local_workspace_folder_basename.clone(),
+        ),
+        ("devcontainerId".to_string(), devcontainer_id.to_string()),
+    ]);
+    substitute_variables(&mut value, &variables);
+
+    // `workspaceFolder` may only refer to local variables, and the container
+    // variables are derived from it.
+    let container_workspace_folder = match value.get("workspaceFolder") {
+        Some(serde_json::Value::String(folder)) => folder.clone(),
+        _ if value.get("dockerComposeFile").is_some() => "/".to_string(),
+        _ => format!("/workspaces/{local_workspace_folder_basename}"),
+    };
+    let container_workspace_folder_basename = Path::new(&container_workspace_folder)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    variables.insert(
+        "containerWorkspaceFolder".to_string(),
+        container_workspace_folder.clone(),
+    );
+    variables.insert(
+        "containerWorkspaceFolderBasename".to_string(),
+        container_workspace_folder_basename,
+    );
+    substitute_variables(&mut value, &variables);
+
+    let mut config = serde_json::from_value::<DevContainerJson>(value).map_err(|e| {
+        log::error!("Invalid devcontainer.json: {e:?}");
+        DevContainerError::DevContainerParseFailed
+    })?;
+    config.container_workspace_folder = container_workspace_folder;
+    Ok(config)
+}
+
+fn substitute_variables(value: &mut serde_json::Value, variables: &BTreeMap<String, String>) {
+    match value {
+        serde_json::Value::String(string) => *string = substitute_in_str(string, variables),
+        serde_json::Value::Array(values) => {
+            for value in values {
+                substitute_variables(value, variables);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for value in map.values_mut() {
+                substitute_variables(value, variables);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replaces `${name}` and `${localEnv:NAME:default}` references in a string.
+/// References that can't be resolved yet, such as `${containerEnv:PATH}`, are
+/// left untouched.
+fn substitute_in_str(input: &str, variables: &BTreeMap<String, String>) -> String {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let Some(len) = rest[start..].find('}') else {
+            rest = &rest[start..];
+            break;
+        };
+        let reference = &rest[start + 2..start + len];
+        match resolve_variable(reference, variables) {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(&rest[start..=start + len]),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    output.push_str(rest);
+    output
+}
+
+fn resolve_variable(reference: &str, variables: &BTreeMap<String, String>) -> Option<String> {
+    if let Some(env) = reference
+        .strip_prefix("localEnv:")
+        .or_else(|| reference.strip_prefix("env:"))
+    {
+        let (name, default) = env.split_once(':').unwrap_or((env, ""));
+        return Some(std::env::var(name).unwrap_or_else(|_| default.to_string()));
+    }
+    variables.get(reference).cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{DevContainerSource, LifecycleCommand, Mount, parse_devcontainer_json};
+
+    #[test]
+    fn test_parse_image_config() {
+        let config = parse_devcontainer_json(
+            r#"{
+                // Comments are allowed
+                "name": "${localWorkspaceFolderBasename} dev",
+                "image": "mcr.microsoft.com/devcontainers/rust:1",
+                "containerEnv": {
+                    "PROJECT": "${containerWorkspaceFolder}",
+                    "UNSET": "${localEnv:ZED_DEVCONTAINER_TEST_UNSET:fallback}",
+                    "REMOTE_PATH": "${containerEnv:PATH}",
+                },
+                "mounts": [
+                    "source=cache,target=/cache,type=volume",
+                    { "type": "bind", "source": "${localWorkspaceFolder}/.cache", "target": "/root/.cache" }
+                ],
+                "postCreateCommand": "cargo build",
+                "postStartCommand": ["echo", "started"],
+                "postAttachCommand": { "a": "echo a", "b": ["echo", "b"] },
+            }"#,
+            Path::new("/home/me/project"),
+            "abc",
+        )
+        .unwrap();
+
+        assert_eq!(config.name.as_deref(), Some("project dev"));
+        assert_eq!(config.container_workspace_folder, "/workspaces/project");
+        assert_eq!(
+            config.source(Path::new("/home/me/project/.devcontainer")),
+            Ok(DevContainerSource::Image(
+                "mcr.microsoft.com/devcontainers/rust:1".into()
+            ))
+        );
+        assert_eq!(config.container_env["PROJECT"], "/workspaces/project");
+        assert_eq!(config.container_env["UNSET"], "fallback");
+        assert_eq!(config.container_env["REMOTE_PATH"], "${containerEnv:PATH}");
+        assert_eq!(
+            config
+                .mounts
+                .iter()
+                .map(Mount::to_mount_arg)
+                .collect::<Vec<_>>(),
+            [
+                "source=cache,target=/cache,type=volume",
+                "type=bind,source=/home/me/project/.cache,target=/root/.cache",
+            ]
+        );
+        assert_eq!(
+            config.post_create_command.unwrap().commands(),
+            [["/bin/sh", "-c", "cargo build"]]
+        );
+        assert_eq!(
+            config.post_start_command,
+            Some(LifecycleCommand::Exec(vec![
+                "echo".into(),
+                "started".into()
+            ]))
+        );
+        assert_eq!(
+            config.post_attach_command.unwrap().commands(),
+            [vec!["/bin/sh", "-c", "echo a"], vec!["echo", "b"],]
+        );
+
+        let initialize_command = LifecycleCommand::Shell("echo init".into());
+        let host_shell: &[&str] = if cfg!(windows) {
+            &["cmd.exe", "/C"]
+        } else {
+            &["/bin/sh", "-c"]
+        };
+        assert_eq!(
+            initialize_command.host_commands(),
+            [[host_shell, &["echo init"]].concat()]
+        );
+    }
+
+    #[test]
+    fn test_parse_dockerfile_and_compose_configs() {
+        let config_dir = Path::new("/project/.devcontainer");
+
+        let config = parse_devcontainer_json(
+            r#"{ "build": { "dockerfile": "Dockerfile", "context": "..", "args": { "VARIANT": "1" } } }"#,
+            Path::new("/project"),
+            "abc",
+        )
+        .unwrap();
+        assert_eq!(
+            config.source(config_dir),
+            Ok(DevContainerSource::Dockerfile {
+                dockerfile: PathBuf::from("/project/.devcontainer/Dockerfile"),
+                context: PathBuf::from("/project/.devcontainer/.."),
+            })
+        );
+        assert_eq!(config.build.unwrap().args["VARIANT"], "1");
+
+        let config = parse_devcontainer_json(
+            r#"{ "dockerComposeFile": ["../compose.yml", "compose.extend.yml"], "service": "app" }"#,
+            Path::new("/project"),
+            "abc",
+        )
+        .unwrap();
+        assert_eq!(config.container_workspace_folder, "/");
+        assert_eq!(
+            config.source(config_dir),
+            Ok(DevContainerSource::Compose {
+                files: vec![
+                    PathBuf::from("/project/.devcontainer/../compose.yml"),
+                    PathBuf::from("/project/.devcontainer/compose.extend.yml"),
+                ],
+                service: "app".into(),
+            })
+        );
+
+        let config =
+            parse_devcontainer_json(r#"{ "name": "nothing" }"#, Path::new("/project"), "abc")
+                .unwrap();
+        assert!(config.source(config_dir).is_err());
+    }
+}
@@ -0,0 +1,909 @@
+//! Brings up a dev container by driving the container runtime directly,
+//! following the dev container spec's lifecycle.
+//!
+//! See <https://containers.dev/implementors/spec/>.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context as _, Result, anyhow};
+use fs::Fs;
+use http_client::HttpClient;
+use sha2::{Digest as _, Sha256};
+
+use crate::{
+    container_runtime::{
+        BuildOptions, ComposeProject, ContainerDetails, ContainerRuntime, ExecOptions,
+        KEEP_ALIVE_SCRIPT, RunOptions, sanitize_name,
+    },
+    devcontainer_api::DevContainerError,
+    devcontainer_json::{DevContainerJson, DevContainerSource, LifecycleCommand, Mount},
+    features::{FeatureUsers, features_build_dir, features_dockerfile, resolve_features},
+};
+
+// These labels match the reference implementation, so containers created by
+// other tools are reused by Zed, and vice versa.
+const LOCAL_FOLDER_LABEL: &str = "devcontainer.local_folder";
+const CONFIG_FILE_LABEL: &str = "devcontainer.config_file";
+const COMPOSE_PROJECT_LABEL: &str = "com.docker.compose.project";
+const COMPOSE_SERVICE_LABEL: &str = "com.docker.compose.service";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct DevContainerUp {
+    pub name: Option<String>,
+    pub container_id: String,
+    pub remote_user: String,
+    pub remote_workspace_folder: String,
+}
+
+/// Creates or reuses the container described by `config_path` and runs its
+/// lifecycle hooks.
+///
+/// Containers are reused when one with matching labels already exists, in
+/// which case only `postStartCommand` (if it had to be started) and
+/// `postAttachCommand` run.
+pub(crate) async fn devcontainer_up(
+    project_directory: &Path,
+    config_path: &Path,
+    runtime: &dyn ContainerRuntime,
+    fs: &Arc<dyn Fs>,
+    http_client: &Arc<dyn HttpClient>,
+) -> Result<DevContainerUp, DevContainerError> {
+    let contents = fs.load(config_path).await.map_err(|e| {
+        log::error!("Failed to read {}: {e:?}", config_path.display());
+        DevContainerError::DevContainerNotFound
+    })?;
+    let devcontainer_id = devcontainer_id(project_directory, config_path);
+    let config = crate::devcontainer_json::parse_devcontainer_json(
+        &contents,
+        project_directory,
+        &devcontainer_id,
+    )?;
+    let config_dir = config_path.parent().unwrap_or(project_directory);
+    let source = config.source(config_dir)?;
+
+    let engine = Engine {
+        project_directory,
+        config_path,
+        config_dir,
+        devcontainer_id,
+        config: &config,
+        runtime,
+        fs,
+        http_client,
+    };
+    engine.up(source).await.map_err(|e| {
+        log::error!("Failed to start dev container: {e:?}");
+        DevContainerError::DevContainerUpFailed(format!("{e:#}"))
+    })
+}
+
+struct Engine<'a> {
+    project_directory: &'a Path,
+    config_path: &'a Path,
+    config_dir: &'a Path,
+    devcontainer_id: String,
+    config: &'a DevContainerJson,
+    runtime: &'a dyn ContainerRuntime,
+    fs: &'a Arc<dyn Fs>,
+    http_client: &'a Arc<dyn HttpClient>,
+}
+
+/// Whether a container had to be created or started to bring it up, which
+/// decides the lifecycle hooks that run.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ContainerState {
+    Created,
+    Started,
+    AlreadyRunning,
+}
+
+impl Engine<'_> {
+    async fn up(&self, source: DevContainerSource) -> Result<DevContainerUp> {
+        if let Some(command) = &self.config.initialize_command {
+            self.run_initialize_command(command).await?;
+        }
+
+        let (container, state) = match source {
+            DevContainerSource::Compose { files, service } => {
+                self.compose_up(files, service).await?
+            }
+            source => self.single_container_up(source).await?,
+        };
+
+        let remote_user = self
+            .config
+            .remote_user
+            .clone()
+            .or_else(|| self.config.container_user.clone())
+            .or_else(|| container.user.clone())
+            .unwrap_or_else(|| "root".to_string());
+
+        let mut hooks = Vec::new();
+        if state == ContainerState::Created {
+            hooks.extend([
+                ("onCreateCommand", &self.config.on_create_command),
+                ("updateContentCommand", &self.config.update_content_command),
+                ("postCreateCommand", &self.config.post_create_command),
+            ]);
+        }
+        if state != ContainerState::AlreadyRunning {
+            hooks.push(("postStartCommand", &self.config.post_start_command));
+        }
+        hooks.push(("postAttachCommand", &self.config.post_attach_command));
+        for (name, command) in hooks {
+            if let Some(command) = command {
+                self.run_lifecycle_command(name, command, &container.id, &remote_user)
+                    .await?;
+            }
+        }
+
+        Ok(DevContainerUp {
+            name: self.config.name.clone(),
+            container_id: container.id,
+            remote_user,
+            remote_workspace_folder: self.config.container_workspace_folder.clone(),
+        })
+    }
+
+    async fn single_container_up(
+        &self,
+        source: DevContainerSource,
+    ) -> Result<(ContainerDetails, ContainerState)> {
+        let labels = vec![
+            (
+                LOCAL_FOLDER_LABEL.to_string(),
+                self.project_directory.display().to_string(),
+            ),
+            (
+                CONFIG_FILE_LABEL.to_string(),
+                self.config_path.display().to_string(),
+            ),
+        ];
+        if let Some(container) = self.runtime.find_container(&labels).await? {
+            log::info!("Reusing dev container {}", container.id);
+            return self.ensure_started(container).await;
+        }
+
+        let base_image = match source {
+            DevContainerSource::Image(image) => image,
+            DevContainerSource::Dockerfile {
+                dockerfile,
+                context,
+            } => {
+                let tag = self.image_name();
+                let build = self.config.build.as_ref();
+                self.runtime
+                    .build_image(&BuildOptions {
+                        tag: tag.clone(),
+                        dockerfile,
+                        context,
+                        build_args: build.map(|build| build.args.clone()).unwrap_or_default(),
+                        target: build.and_then(|build| build.target.clone()),
+                        extra_args: build.map(|build| build.options.clone()).unwrap_or_default(),
+                    })
+                    .await
+                    .context("building the dev container image")?;
+                tag
+            }
+            DevContainerSource::Compose { .. } => unreachable!("handled by compose_up"),
+        };
+        let image_user = self.runtime.image_user(&base_image).await?;
+        let image = self
+            .with_features(&base_image, image_user.as_deref())
+            .await?;
+
+        let workspace_mount = match &self.config.workspace_mount {
+            Some(mount) if !mount.is_empty() => mount.clone(),
+            _ => format!(
+                "type=bind,source={},target=/workspaces/{},consistency=cached",
+                self.project_directory.display(),
+                self.project_directory
+                    .file_name()
+                    .map(|name| name.to_string_lossy())
+                    .unwrap_or_default()
+            ),
+        };
+        let mut mounts = vec![workspace_mount];
+        mounts.extend(self.config.mounts.iter().map(Mount::to_mount_arg));
+
+        let container_id = self
+            .runtime
+            .run_container(&RunOptions {
+                image,
+                labels,
+                env: self.config.container_env.clone(),
+                mounts,
+                user: self.config.container_user.clone(),
+                override_command: self.config.override_command.unwrap_or(true),
+                extra_args: self.config.run_args.clone(),
+            })
+            .await
+            .context("creating the dev container")?;
+        log::info!("Created dev container {container_id}");
+
+        Ok((
+            ContainerDetails {
+                id: container_id,
+                running: true,
+                user: self.config.container_user.clone().or(image_user),
+            },
+            ContainerState::Created,
+        ))
+    }
+
+    async fn compose_up(
+        &self,
+        files: Vec<PathBuf>,
+        service: String,
+    ) -> Result<(ContainerDetails, ContainerState)> {
+        let mut project = ComposeProject {
+            name: self.compose_project_name(&files),
+            files,
+        };
+        let labels = vec![
+            (COMPOSE_PROJECT_LABEL.to_string(), project.name.clone()),
+            (COMPOSE_SERVICE_LABEL.to_string(), service.clone()),
+        ];
+        if let Some(container) = self.runtime.find_container(&labels).await? {
+            log::info!("Reusing dev container {}", container.id);
+            return self.ensure_started(container).await;
+        }
+
+        let compose_config = self
+            .runtime
+            .compose_config(&project)
+            .await
+            .context("reading the Docker Compose configuration")?;
+        let service_config = compose_config
+            .get("services")
+            .and_then(|services| services.get(&service))
+            .ok_or_else(|| anyhow!("service `{service}` is not defined in the compose files"))?;
+
+        if service_config.get("build").is_some() {
+            self.runtime
+                .compose_build(&project, std::slice::from_ref(&service))
+                .await
+                .context("building the dev container image")?;
+        }
+        let base_image = service_config
+            .get("image")
+            .and_then(|image| image.as_str())
+            .map(ToString::to_string)
+            .unwrap_or_else(|| format!("{}-{}", project.name, service));
+        let image_user = match service_config.get("user").and_then(|user| user.as_str()) {
+            Some(user) => Some(user.to_string()),
+            None => self.runtime.image_user(&base_image).await?,
+        };
+        let image = self
+            .with_features(&base_image, image_user.as_deref())
+            .await?;
+
+        let mut service_override = serde_json::json!({
+            "labels": {
+                LOCAL_FOLDER_LABEL: self.project_directory.display().to_string(),
+                CONFIG_FILE_LABEL: self.config_path.display().to_string(),
+            },
+            "environment": self.config.container_env,
+        });
+        if image != base_image {
+            service_override["image"] = image.into();
+        }
+        if let Some(user) = &self.config.container_user {
+            service_override["user"] = user.clone().into();
+        }
+        if self.config.override_command.unwrap_or(false) {
+            service_override["entrypoint"] =
+                serde_json::json!(["/bin/sh", "-c", KEEP_ALIVE_SCRIPT]);
+            service_override["command"] = serde_json::json!([]);
+        }
+        if !self.config.mounts.is_empty() {
+            service_override["volumes"] = self
+                .config
+                .mounts
+                .iter()
+                .map(compose_volume)
+                .collect::<Vec<_>>()
+                .into();
+        }
+        let override_file = serde_json::json!({
+            "services": { service.clone(): service_override },
+        });
+
+        let build_dir = features_build_dir(&self.devcontainer_id);
+        self.fs.create_dir(&build_dir).await?;
+        let override_path = build_dir.join("docker-compose.devcontainer.json");
+        self.fs
+            .write(
+                &override_path,
+                serde_json::to_string_pretty(&override_file)?.as_bytes(),
+            )
+            .await?;
+        project.files.push(override_path);
+
+        let mut services = vec![service];
+        for run_service in &self.config.run_services {
+            if !services.contains(run_service) {
+                services.push(run_service.clone());
+            }
+        }
+        self.runtime
+            .compose_up(&project, &services)
+            .await
+            .context("starting the Docker Compose services")?;
+
+        let mut container =
+            self.runtime.find_container(&labels).await?.ok_or_else(|| {
+                anyhow!("Docker Compose did not start the `{}` service", services[0])
+            })?;
+        if container.user.is_none() {
+            container.user = self.config.container_user.clone().or(image_user);
+        }
+        Ok((container, ContainerState::Created))
+    }
+
+    async fn ensure_started(
+        &self,
+        container: ContainerDetails,
+    ) -> Result<(ContainerDetails, ContainerState)> {
+        if container.running {
+            return Ok((container, ContainerState::AlreadyRunning));
+        }
+        self.runtime
+            .start_container(&container.id)
+            .await
+            .context("starting the dev container")?;
+        Ok((container, ContainerState::Started))
+    }
+
+    /// Builds an image installing the configured Features on top of
+    /// `base_image`, returning `base_image` when there are none.
+    async fn with_features(&self, base_image: &str, image_user: Option<&str>) -> Result<String> {
+        if self.config.features.is_empty() {
+            return Ok(base_image.to_string());
+        }
+
+        let image_user = image_user.unwrap_or("root");
+        let container_user = self.config.container_user.as_deref().unwrap_or(image_user);
+        let remote_user = self.config.remote_user.as_deref().unwrap_or(container_user);
+
+        let build_dir = features_build_dir(&self.devcontainer_id);
+        self.fs.create_dir(&build_dir).await?;
+        let features = resolve_features(
+            self.config,
+            self.config_dir,
+            &build_dir,
+            &FeatureUsers {
+                container_user,
+                remote_user,
+            },
+            self.fs,
+            self.http_client,
+        )
+        .await?;
+        if features.is_empty() {
+            return Ok(base_image.to_string());
+        }
+
+        let dockerfile = build_dir.join("Dockerfile.features");
+        self.fs
+            .write(
+                &dockerfile,
+                features_dockerfile(base_image, image_user, &features).as_bytes(),
+            )
+            .await?;
+
+        let tag = format!("{}-features", self.image_name());
+        self.runtime
+            .build_image(&BuildOptions {
+                tag: tag.clone(),
+                dockerfile,
+                context: build_dir,
+                ..Default::default()
+            })
+            .await
+            .context("installing dev container features")?;
+        Ok(tag)
+    }
+
+    async fn run_initialize_command(&self, command: &LifecycleCommand) -> Result<()> {
+        for args in command.host_commands() {
+            let (program, args) = args.split_first().context("empty initializeCommand")?;
+            let mut command = util::command::new_command(program);
+            command.args(args).current_dir(self.project_directory);
+            let output = command
+                .output()
+                .await
+                .context("running initializeCommand")?;
+            anyhow::ensure!(
+                output.status.success(),
+                "initializeCommand failed with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    async fn run_lifecycle_command(
+        &self,
+        name: &str,
+        command: &LifecycleCommand,
+        container_id: &str,
+        remote_user: &str,
+    ) -> Result<()> {
+        log::info!("Running {name} in dev container {container_id}");
+        let env = self
+            .config
+            .remote_env
+            .iter()
+            .filter_map(|(name, value)| Some((name.clone(), value.clone()?)))
+            .collect::<BTreeMap<_, _>>();
+        let runs = command
+            .commands()
+            .into_iter()
+            .map(|command| ExecOptions {
+                container_id: container_id.to_string(),
+                user: Some(remote_user.to_string()),
+                env: env.clone(),
+                working_dir: Some(self.config.container_workspace_folder.clone()),
+                command,
+            })
+            .collect::<Vec<_>>();
+        futures::future::try_join_all(runs.iter().map(|options| self.runtime.exec(options)))
+            .await
+            .with_context(|| format!("{name} failed"))?;
+        Ok(())
+    }
+
+    fn image_name(&self) -> String {
+        let basename = self
+            .project_directory
+            .file_name()
+            .map(|name| sanitize_name(&name.to_string_lossy()))
+            .unwrap_or_default();
+        format!(
+            "zed-dev-container-{basename}-{}",
+            &self.devcontainer_id[..12]
+        )
+    }
+
+    fn compose_project_name(&self, files: &[PathBuf]) -> String {
+        let compose_dir = files
+            .first()
+            .and_then(|file| util::paths::normalize_lexically(file).ok())
+            .and_then(|file| Some(file.parent()?.to_path_buf()))
+            .unwrap_or_else(|| self.config_dir.to_path_buf());
+        let basename = compose_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        sanitize_name(&format!("{basename}_devcontainer"))
+    }
+}
+
+/// Identifies the container for a project folder and configuration file.
+fn devcontainer_id(project_directory: &Path, config_path: &Path) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(project_directory.display().to_string());
+    hasher.update([0u8]);
+    hasher.update(config_path.display().to_string());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Converts a `--mount` style mount into Compose's long volume syntax.
+fn compose_volume(mount: &Mount) -> serde_json::Value {
+    let mut volume = serde_json::Map::new();
+    for field in mount.to_mount_arg().split(',') {
+        let (key, value) = field.split_once('=').unwrap_or((field, "true"));
+        let key = match key {
+            "src" => "source",
+            "dst" | "destination" => "target",
+            "readonly" | "ro" => "read_only",
+            key => key,
+        };
+        let value = match value {
+            "true" => serde_json::Value::Bool(true),
+            "false" => serde_json::Value::Bool(false),
+            value => value.into(),
+        };
+        volume.insert(key.to_string(), value);
+    }
+    volume.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+    use fs::{FakeFs, Fs};
+    use gpui::TestAppContext;
+    use http_client::{FakeHttpClient, HttpClient};
+    use serde_json::json;
+    use util::path;
+
+    use super::{DevContainerUp, devcontainer_id, devcontainer_up};
+    use crate::{
+        container_runtime::{
+            BuildOptions, ComposeProject, ExecOptions, KEEP_ALIVE_SCRIPT, RunOptions,
+            fake::{Call, FakeContainerRuntime},
+        },
+        devcontainer_api::DevContainerError,
+        features::features_build_dir,
+    };
+
+    fn http_client() -> Arc<dyn HttpClient> {
+        FakeHttpClient::with_404_response()
+    }
+
+    fn exec(container_id: &str, user: &str, workdir: &str, command: &[&str]) -> Call {
+        Call::Exec(ExecOptions {
+            container_id: container_id.into(),
+            user: Some(user.into()),
+            env: BTreeMap::new(),
+            working_dir: Some(workdir.into()),
+            command: command.iter().map(|arg| arg.to_string()).collect(),
+        })
+    }
+
+    #[gpui::test]
+    async fn test_image_container_lifecycle(cx: &mut TestAppContext) {
+        let fs: Arc<dyn Fs> = FakeFs::new(cx.executor());
+        let fs_fake = fs.as_fake();
+        fs_fake
+            .insert_tree(
+                path!("/project"),
+                json!({
+                    ".devcontainer": {
+                        "devcontainer.json": r#"{
+                            "name": "Rust",
+                            "image": "rust:1",
+                            "containerEnv": { "CARGO_HOME": "/cargo" },
+                            "remoteUser": "dev",
+                            "runArgs": ["--cap-add=SYS_PTRACE"],
+                            "onCreateCommand": "echo create",
+                            "postCreateCommand": ["cargo", "fetch"],
+                            "postStartCommand": "echo start",
+                            "postAttachCommand": "echo attach",
+                        }"#,
+                    },
+                }),
+            )
+            .await;
+        let runtime = FakeContainerRuntime::default();
+        let project = Path::new(path!("/project"));
+        let config_path = Path::new(path!("/project/.devcontainer/devcontainer.json"));
+
+        let up = devcontainer_up(project, config_path, &runtime, &fs, &http_client())
+            .await
+            .unwrap();
+        assert_eq!(
+            up,
+            DevContainerUp {
+                name: Some("Rust".into()),
+                container_id: "container-1".into(),
+                remote_user: "dev".into(),
+                remote_workspace_folder: "/workspaces/project".into(),
+            }
+        );
+        let labels = vec![
+            (
+                "devcontainer.local_folder".to_string(),
+                project.display().to_string(),
+            ),
+            (
+                "devcontainer.config_file".to_string(),
+                config_path.display().to_string(),
+            ),
+        ];
+        assert_eq!(
+            runtime.calls(),
+            [
+                Call::Run(RunOptions {
+                    image: "rust:1".into(),
+                    labels,
+                    env: BTreeMap::from_iter([("CARGO_HOME".to_string(), "/cargo".to_string())]),
+                    mounts: vec![format!(
+                        "type=bind,source={},target=/workspaces/project,consistency=cached",
+                        project.display()
+                    )],
+                    user: None,
+                    override_command: true,
+                    extra_args: vec!["--cap-add=SYS_PTRACE".into()],
+                }),
+                exec(
+                    "container-1",
+                    "dev",
+                    "/workspaces/project",
+                    &["/bin/sh", "-c", "echo create"]
+                ),
+                exec(
+                    "container-1",
+                    "dev",
+                    "/workspaces/project",
+                    &["cargo", "fetch"]
+                ),
+                exec(
+                    "container-1",
+                    "dev",
+                    "/workspaces/project",
+                    &["/bin/sh", "-c", "echo start"]
+                ),
+                exec(
+                    "container-1",
+                    "dev",
+                    "/workspaces/project",
+                    &["/bin/sh", "-c", "echo attach"]
+                ),
+            ]
+        );
+
+        // A running container is reused, and only attach hooks run.
+        runtime.clear_calls();
+        let up = devcontainer_up(project, config_path, &runtime, &fs, &http_client())
+            .await
+            .unwrap();
+        assert_eq!(up.container_id, "container-1");
+        assert_eq!(
+            runtime.calls(),
+            [exec(
+                "container-1",
+                "dev",
+                "/workspaces/project",
+                &["/bin/sh", "-c", "echo attach"]
+            )]
+        );
+
+        // A stopped container is started again, running the start hooks.
+        runtime.stop_all();
+        runtime.clear_calls();
+        devcontainer_up(project, config_path, &runtime, &fs, &http_client())
+            .await
+            .unwrap();
+        assert_eq!(
+            runtime.calls(),
+            [
+                Call::Start("container-1".into()),
+                exec(
+                    "container-1",
+                    "dev",
+                    "/workspaces/project",
+                    &["/bin/sh", "-c", "echo start"]
+                ),
+                exec(
+                    "container-1",
+                    "dev",
+                    "/workspaces/project",
+                    &["/bin/sh", "-c", "echo attach"]
+                ),
+            ]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_dockerfile_with_local_feature(cx: &mut TestAppContext) {
+        let fs: Arc<dyn Fs> = FakeFs::new(cx.executor());
+        fs.as_fake()
+            .insert_tree(
+                path!("/project"),
+                json!({
+                    ".devcontainer": {
+                        "devcontainer.json": r#"{
+                            "build": {
+                                "dockerfile": "Dockerfile",
+                                "args": { "VARIANT": "bookworm" },
+                            },
+                            "features": {
+                                "./tools": { "flavor": "full" },
+                            },
+                        }"#,
+                        "Dockerfile": "FROM debian:${VARIANT}",
+                        "tools": {
+                            "devcontainer-feature.json": r#"{
+                                "id": "tools",
+                                "options": {
+                                    "flavor": { "type": "string", "default": "minimal" },
+                                },
+                                "containerEnv": { "TOOLS_HOME": "/opt/tools" },
+                            }"#,
+                            "install.sh": "#!/bin/sh\necho installing",
+                        },
+                    },
+                }),
+            )
+            .await;
+        let runtime = FakeContainerRuntime::default();
+        let project = Path::new(path!("/project"));
+        let config_path = Path::new(path!("/project/.devcontainer/devcontainer.json"));
+        let image = format!(
+            "zed-dev-container-project-{}",
+            &devcontainer_id(project, config_path)[..12]
+        );
+        runtime.set_image_user(&image, "vscode");
+
+        let up = devcontainer_up(project, config_path, &runtime, &fs, &http_client())
+            .await
+            .unwrap();
+        assert_eq!(up.remote_user, "vscode");
+
+        let build_dir = features_build_dir(&devcontainer_id(project, config_path));
+        let calls = runtime.calls();
+        assert_eq!(
+            calls[0],
+            Call::Build(BuildOptions {
+                tag: image.clone(),
+                dockerfile: project.join(".devcontainer/Dockerfile"),
+                context: project.join(".devcontainer"),
+                build_args: BTreeMap::from_iter([("VARIANT".to_string(), "bookworm".to_string())]),
+                target: None,
+                extra_args: Vec::new(),
+            })
+        );
+        assert_eq!(
+            calls[1],
+            Call::Build(BuildOptions {
+                tag: format!("{image}-features"),
+                dockerfile: build_dir.join("Dockerfile.features"),
+                context: build_dir.clone(),
+                ..Default::default()
+            })
+        );
+        let Call::Run(run) = &calls[2] else {
+            panic!("expected a container to be created, got {:?}", calls[2]);
+        };
+        assert_eq!(run.image, format!("{image}-features"));
+
+        let dockerfile = fs
+            .load(&build_dir.join("Dockerfile.features"))
+            .await
+            .unwrap();
+        assert!(dockerfile.starts_with(&format!("FROM {image}\n")));
+        assert!(dockerfile.contains("ENV TOOLS_HOME=\"/opt/tools\"\n"));
+        assert!(dockerfile.ends_with("USER vscode\n"));
+        assert_eq!(
+            fs.load(&build_dir.join("features/0/install.sh"))
+                .await
+                .unwrap(),
+            "#!/bin/sh\necho installing"
+        );
+        let env_file = fs
+            .load(&build_dir.join("features/0/devcontainer-features.env"))
+            .await
+            .unwrap();
+        assert!(env_file.contains("FLAVOR='full'\n"));
+        assert!(env_file.contains("_REMOTE_USER='vscode'\n"));
+    }
+
+    #[gpui::test]
+    async fn test_compose_container(cx: &mut TestAppContext) {
+        let fs: Arc<dyn Fs> = FakeFs::new(cx.executor());
+        fs.as_fake()
+            .insert_tree(
+                path!("/project"),
+                json!({
+                    ".devcontainer": {
+                        "devcontainer.json": r#"{
+                            "dockerComposeFile": "../compose.yml",
+                            "service": "app",
+                            "runServices": ["db"],
+                            "workspaceFolder": "/workspace",
+                            "overrideCommand": true,
+                            "postCreateCommand": "make setup",
+                        }"#,
+                    },
+                    "compose.yml": "",
+                }),
+            )
+            .await;
+        let runtime = FakeContainerRuntime::default();
+        runtime.set_compose_config(json!({
+            "services": {
+                "app": { "image": "node:20", "user": "node" },
+                "db": { "image": "postgres:16" },
+            },
+        }));
+        let project = Path::new(path!("/project"));
+        let config_path = Path::new(path!("/project/.devcontainer/devcontainer.json"));
+
+        let up = devcontainer_up(project, config_path, &runtime, &fs, &http_client())
+            .await
+            .unwrap();
+        assert_eq!(up.remote_user, "node");
+        assert_eq!(up.remote_workspace_folder, "/workspace");
+
+        let build_dir = features_build_dir(&devcontainer_id(project, config_path));
+        let override_path = build_dir.join("docker-compose.devcontainer.json");
+        assert_eq!(
+            runtime.calls(),
+            [
+                Call::ComposeUp(
+                    ComposeProject {
+                        name: "project_devcontainer".into(),
+                        files: vec![
+                            project.join(".devcontainer/../compose.yml"),
+                            override_path.clone(),
+                        ],
+                    },
+                    vec!["app".into(), "db".into()],
+                ),
+                exec(
+                    &up.container_id,
+                    "node",
+                    "/workspace",
+                    &["/bin/sh", "-c", "make setup"]
+                ),
+            ]
+        );
+        let override_file: serde_json::Value =
+            serde_json::from_str(&fs.load(&override_path).await.unwrap()).unwrap();
+        assert_eq!(
+            override_file,
+            json!({
+                "services": {
+                    "app": {
+                        "labels": {
+                            "devcontainer.local_folder": project.display().to_string(),
+                            "devcontainer.config_file": config_path.display().to_string(),
+                        },
+                        "environment": {},
+                        "entrypoint": ["/bin/sh", "-c", KEEP_ALIVE_SCRIPT],
+                        "command": [],
+                    },
+                },
+            })
+        );
+    }
+
+    #[gpui::test]
+    async fn test_failing_lifecycle_command(cx: &mut TestAppContext) {
+        let fs: Arc<dyn Fs> = FakeFs::new(cx.executor());
+        fs.as_fake()
+            .insert_tree(
+                path!("/project"),
+                json!({
+                    ".devcontainer.json": r#"{
+                        "image": "alpine",
+                        "postCreateCommand": "exit 1",
+                        "postStartCommand": "echo start",
+                    }"#,
+                }),
+            )
+            .await;
+        let runtime = FakeContainerRuntime::default();
+        runtime.fail_command("exit 1");
+        let project = Path::new(path!("/project"));
+
+        let result = devcontainer_up(
+            project,
+            &project.join(".devcontainer.json"),
+            &runtime,
+            &fs,
+            &http_client(),
+        )
+        .await;
+        let Err(DevContainerError::DevContainerUpFailed(message)) = result else {
+            panic!("expected the dev container to fail, got {result:?}");
+        };
+        assert!(message.contains("postCreateCommand failed"), "{message}");
+        assert!(
+            !runtime.calls().iter().any(|call| matches!(
+                call,
+                Call::Exec(options) if options.command.last().is_some_and(|arg| arg == "echo start")
+            )),
+            "hooks after a failed one should not run"
+        );
+
+        let result = devcontainer_up(
+            project,
+            &project.join("missing.json"),
+            &runtime,
+            &fs,
+            &http_client(),
+        )
+        .await;
+        assert_eq!(result, Err(DevContainerError::DevContainerNotFound));
+    }
+}
@@ -0,0 +1,778 @@
+//! Dev container Features: self-contained install scripts layered on top of a
+//! container's image.
+//!
+//! See <https://containers.dev/implementors/features/>.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{Context as _, Result, anyhow};
+use async_compression::futures::bufread::GzipDecoder;
+use async_tar::Archive;
+use fs::{CopyOptions, Fs};
+use futures::{AsyncRead, AsyncReadExt as _, io::BufReader};
+use http_client::{AsyncBody, HttpClient, HttpRequestExt as _, RedirectPolicy, Request};
+use serde::Deserialize;
+use sha2::{Digest as _, Sha256};
+
+use crate::devcontainer_json::DevContainerJson;
+
+/// Features are small install scripts, so downloads larger than this, compressed or not, are
+/// rejected rather than unpacked.
+const MAX_FEATURE_DOWNLOAD_SIZE: u64 = 100 * 1024 * 1024;
+
+/// The metadata a Feature ships in its `devcontainer-feature.json`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeatureMetadata {
+    id: String,
+    #[serde(default)]
+    options: BTreeMap<String, FeatureOption>,
+    #[serde(default)]
+    container_env: BTreeMap<String, String>,
+    #[serde(default)]
+    installs_after: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FeatureOption {
+    default: Option<serde_json::Value>,
+}
+
+/// A Feature that has been downloaded into the build context.
+#[derive(Debug)]
+pub(crate) struct ResolvedFeature {
+    /// The reference used in `devcontainer.json`.
+    pub reference: String,
+    /// The directory the Feature was unpacked into, relative to the
+    /// `features` directory of the build context.
+    pub dir_name: String,
+    pub container_env: BTreeMap<String, String>,
+    installs_after: Vec<String>,
+}
+
+/// The users Features are installed for, exposed to install scripts as
+/// `_REMOTE_USER` and `_CONTAINER_USER`.
+pub(crate) struct FeatureUsers<'a> {
+    pub container_user: &'a str,
+    pub remote_user: &'a str,
+}
+
+/// Downloads the Features referenced in `config` into `build_dir/features`,
+/// writes their options next to their install scripts and returns them in
+/// install order.
+pub(crate) async fn resolve_features(
+    config: &DevContainerJson,
+    config_dir: &Path,
+    build_dir: &Path,
+    users: &FeatureUsers<'_>,
+    fs: &Arc<dyn Fs>,
+    http_client: &Arc<dyn HttpClient>,
+) -> Result<Vec<ResolvedFeature>> {
+    let features_dir = build_dir.join("features");
+    if fs.is_dir(&features_dir).await {
+        fs.remove_dir(
+            &features_dir,
+            fs::RemoveOptions {
+                recursive: true,
+                ignore_if_not_exists: true,
+            },
+        )
+        .await?;
+    }
+
+    let mut features = Vec::new();
+    for (ix, (reference, options)) in config.features.iter().enumerate() {
+        if options == &serde_json::Value::Bool(false) {
+            continue;
+        }
+
+        let dir_name = ix.to_string();
+        let destination = features_dir.join(&dir_name);
+        fs.create_dir(&destination).await?;
+        fetch_feature(reference, config_dir, &destination, fs, http_client)
+            .await
+            .with_context(|| format!("fetching feature {reference}"))?;
+
+        let metadata_path = destination.join("devcontainer-feature.json");
+        let metadata = fs
+            .load(&metadata_path)
+            .await
+            .with_context(|| format!("{reference} has no devcontainer-feature.json"))?;
+        let metadata = settings::parse_json_with_comments::<FeatureMetadata>(&metadata)
+            .with_context(|| format!("parsing devcontainer-feature.json for {reference}"))?;
+
+        let env_file = feature_env_file(&metadata, options, users);
+        fs.write(
+            &destination.join("devcontainer-features.env"),
+            env_file.as_bytes(),
+        )
+        .await?;
+
+        log::info!("Resolved feature {} as {}", reference, metadata.id);
+        features.push(ResolvedFeature {
+            reference: reference.clone(),
+            dir_name,
+            container_env: metadata.container_env,
+            installs_after: metadata.installs_after,
+        });
+    }
+
+    Ok(install_order(
+        features,
+        &config.override_feature_install_order,
+    ))
+}
+
+async fn fetch_feature(
+    reference: &str,
+    config_dir: &Path,
+    destination: &Path,
+    fs: &Arc<dyn Fs>,
+    http_client: &Arc<dyn HttpClient>,
+) -> Result<()> {
+    if reference.starts_with("./") || reference.starts_with("../") {
+        let source = util::paths::normalize_lexically(&config_dir.join(reference))
+            .map_err(|_| anyhow!("{reference} is outside of the filesystem root"))?;
+        // Like the dev container CLI, only allow Features in the configuration's
+        // directory, so that a configuration can't copy arbitrary host directories
+        // into the container.
+        let config_dir = util::paths::normalize_lexically(config_dir)
+            .map_err(|_| anyhow!("{config_dir:?} is outside of the filesystem root"))?;
+        anyhow::ensure!(
+            source.starts_with(&config_dir),
+            "local Feature {reference} must be inside {config_dir:?}"
+        );
+        return fs::copy_recursive(
+            fs.as_ref(),
+            &source,
+            destination,
+            CopyOptions {
+                overwrite: true,
+                ignore_if_exists: false,
+            },
+        )
+        .await;
+    }
+
+    if reference.starts_with("https://") || reference.starts_with("http://") {
+        let response = http_client
+            .get(reference, AsyncBody::default(), true)
+            .await?;
+        anyhow::ensure!(
+            response.status().is_success(),
+            "downloading {reference} failed with {}",
+            response.status()
+        );
+        let compressed = read_limited(response.into_body(), reference).await?;
+        let tar =
+            read_limited(GzipDecoder::new(BufReader::new(&compressed[..])), reference).await?;
+        let body = &tar[..];
+        futures::pin_mut!(body);
+        return fs.extract_tar_file(destination, Archive::new(body)).await;
+    }
+
+    let oci_reference = OciReference::parse(reference)
+        .ok_or_else(|| anyhow!("unsupported feature reference {reference}"))?;
+    let token = oci_token(&oci_reference, http_client).await?;
+
+    #[derive(Deserialize)]
+    struct Manifest {
+        layers: Vec<Layer>,
+    }
+    #[derive(Deserialize)]
+    struct Layer {
+        digest: String,
+    }
+
+    let manifest_url = format!(
+        "https://{}/v2/{}/manifests/{}",
+        oci_reference.registry, oci_reference.repository, oci_reference.reference
+    );
+    let response = oci_get(&manifest_url, token.as_deref(), http_client).await?;
+    let manifest = read_limited(response.into_body(), reference).await?;
+    // Tags can't contain colons, so this is a pinned digest.
+    if oci_reference.reference.contains(':') {
+        verify_digest(&manifest, &oci_reference.reference)
+            .with_context(|| format!("verifying manifest for {reference}"))?;
+    }
+    let manifest = serde_json::from_slice::<Manifest>(&manifest)
+        .with_context(|| format!("parsing manifest for {reference}"))?;
+    let layer = manifest
+        .layers
+        .first()
+        .ok_or_else(|| anyhow!("manifest for {reference} has no layers"))?;
+
+    let blob_url = format!(
+        "https://{}/v2/{}/blobs/{}",
+        oci_reference.registry, oci_reference.repository, layer.digest
+    );
+    let response = oci_get(&blob_url, token.as_deref(), http_client).await?;
+    let tar = read_limited(response.into_body(), reference).await?;
+    verify_digest(&tar, &layer.digest).with_context(|| format!("verifying {reference}"))?;
+    let body = &tar[..];
+    futures::pin_mut!(body);
+    fs.extract_tar_file(destination, Archive::new(body)).await
+}
+
+/// Checks that `bytes` have the given OCI content digest, such as
+/// `sha256:e3b0c442...`, so that nothing tampered with is unpacked.
+fn verify_digest(bytes: &[u8], digest: &str) -> Result<()> {
+    let expected = digest
+        .strip_prefix("sha256:")
+        .ok_or_else(|| anyhow!("unsupported digest {digest}"))?;
+    let actual = format!("{:x}", Sha256::digest(bytes));
+    anyhow::ensure!(
+        actual.eq_ignore_ascii_case(expected),
+        "expected digest {digest}, but the download has digest sha256:{actual}"
+    );
+    Ok(())
+}
+
+/// Reads all of `reader`, failing if it's larger than [`MAX_FEATURE_DOWNLOAD_SIZE`].
+async fn read_limited(reader: impl AsyncRead, reference: &str) -> Result<Vec<u8>> {
+    futures::pin_mut!(reader);
+    let mut bytes = Vec::new();
+    reader
+        .take(MAX_FEATURE_DOWNLOAD_SIZE + 1)
+        .read_to_end(&mut bytes)
+        .await
+        .with_context(|| format!("downloading {reference}"))?;
+    anyhow::ensure!(
+        bytes.len() as u64 <= MAX_FEATURE_DOWNLOAD_SIZE,
+        "{reference} is larger than {} MiB",
+        MAX_FEATURE_DOWNLOAD_SIZE / 1024 / 1024
+    );
+    Ok(bytes)
+}
+
+async fn oci_token(
+    reference: &OciReference,
+    http_client: &Arc<dyn HttpClient>,
+) -> Result<Option<String>> {
+    #[derive(Deserialize)]
+    struct TokenResponse {
+        token: String,
+    }
+
+    let url = format!(
+        "https://{}/token?scope=repository:{}:pull&service={}",
+        reference.registry, reference.repository, reference.registry
+    );
+    let mut response = http_client.get(&url, AsyncBody::default(), true).await?;
+    if !response.status().is_success() {
+        // Not every registry hands out anonymous tokens; try without one.
+        return Ok(None);
+    }
+    let mut body = String::new();
+    response.body_mut().read_to_string(&mut body).await?;
+    Ok(serde_json::from_str::<TokenResponse>(&body)
+        .ok()
+        .map(|response| response.token))
+}
+
+async fn oci_get(
+    url: &str,
+    token: Option<&str>,
+    http_client: &Arc<dyn HttpClient>,
+) -> Result<http_client::Response<AsyncBody>> {
+    let mut request = Request::get(url)
+        .header("Accept", "application/vnd.oci.image.manifest.v1+json")
+        .follow_redirects(RedirectPolicy::FollowAll);
+    if let Some(token) = token {
+        request = request.header("Authorization", format!("Bearer {token}"));
+    }
+    let response = http_client
+        .send(request.body(AsyncBody::default())?)
+        .await?;
+    anyhow::ensure!(
+        response.status().is_success(),
+        "GET {url} failed with {}",
+        response.status()
+    );
+    Ok(response)
+}
+
+/// A Feature published to an OCI registry, such as
+/// `ghcr.io/devcontainers/features/node:1`.
+#[derive(Debug, PartialEq)]
+struct OciReference {
+    registry: String,
+    repository: String,
+    /// A tag or a digest.
+    reference: String,
+}
+
+impl OciReference {
+    fn parse(reference: &str) -> Option<Self> {
+        let (registry, path) = reference.split_once('/')?;
+        if !registry.contains('.') && !registry.contains(':') && registry != "localhost" {
+            return None;
+        }
+        let (repository, reference) = if let Some((repository, digest)) = path.split_once('@') {
+            (repository, digest)
+        } else {
+            match path.rsplit_once(':') {
+                Some((repository, tag)) if !tag.contains('/') => (repository, tag),
+                _ => (path, "latest"),
+            }
+        };
+        Some(Self {
+            registry: registry.to_string(),
+            repository: repository.to_string(),
+            reference: reference.to_string(),
+        })
+    }
+}
+
+/// Strips the tag or digest from a Feature reference, so that references to
+/// different versions of the same Feature compare equal.
+fn feature_id(reference: &str) -> &str {
+    let reference = reference.split('@').next().unwrap_or(reference);
+    match reference.rsplit_once(':') {
+        Some((id, tag)) if !tag.contains('/') => id,
+        _ => reference,
+    }
+}
+
+/// Sorts Features so that each is installed after the ones listed in its
+/// `installsAfter`, with `overrideFeatureInstallOrder` taking precedence.
+fn install_order(
+    mut features: Vec<ResolvedFeature>,
+    override_order: &[String],
+) -> Vec<ResolvedFeature> {
+    features.sort_by_key(|feature| {
+        override_order
+            .iter()
+            .position(|id| feature_id(id) == feature_id(&feature.reference))
+            .unwrap_or(usize::MAX)
+    });
+
+    let mut ordered = Vec::with_capacity(features.len());
+    while !features.is_empty() {
+        let is_pending = |id: &str| {
+            features
+                .iter()
+                .any(|feature| feature_id(&feature.reference) == feature_id(id))
+        };
+        // Fall back to the first remaining Feature if the dependencies form a
+        // cycle.
+        let ix = features
+            .iter()
+            .position(|feature| {
+                !feature
+                    .installs_after
+                    .iter()
+                    .any(|dependency| is_pending(dependency))
+            })
+            .unwrap_or(0);
+        ordered.push(features.remove(ix));
+    }
+    ordered
+}
+
+/// Converts a Feature option name into the environment variable its install
+/// script reads, following the spec's rules.
+fn option_env_name(name: &str) -> String {
+    let name = name
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>();
+    let trimmed = name.trim_start_matches(|c: char| c.is_ascii_digit() || c == '_');
+    let name = if trimmed.len() < name.len() {
+        format!("_{trimmed}")
+    } else {
+        name
+    };
+    name.to_ascii_uppercase()
+}
+
+fn feature_env_file(
+    metadata: &FeatureMetadata,
+    options: &serde_json::Value,
+    users: &FeatureUsers<'_>,
+) -> String {
+    let mut values = BTreeMap::new();
+    for (name, option) in &metadata.options {
+        if let Some(default) = &option.default {
+            values.insert(option_env_name(name), option_value(default));
+        }
+    }
+    match options {
+        // A bare string is shorthand for the `version` option.
+        serde_json::Value::String(version) => {
+            values.insert("VERSION".to_string(), version.clone());
+        }
+        serde_json::Value::Object(options) => {
+            for (name, value) in options {
+                values.insert(option_env_name(name), option_value(value));
+            }
+        }
+        _ => {}
+    }
+
+    values.insert("_CONTAINER_USER".to_string(), users.container_user.into());
+    values.insert(
+        "_CONTAINER_USER_HOME".to_string(),
+        user_home(users.container_user),
+    );
+    values.insert("_REMOTE_USER".to_string(), users.remote_user.into());
+    values.insert(
+        "_REMOTE_USER_HOME".to_string(),
+        user_home(users.remote_user),
+    );
+
+    let mut env_file = String::new();
+    for (name, value) in values {
+        env_file.push_str(&format!("{name}={}\n", shell_quote(&value)));
+    }
+    env_file
+}
+
+fn option_value(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn user_home(user: &str) -> String {
+    if user == "root" {
+        "/root".to_string()
+    } else {
+        format!("/home/{user}")
+    }
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Returns a Dockerfile that installs `features` on top of `base_image`.
+pub(crate) fn features_dockerfile(
+    base_image: &str,
+    image_user: &str,
+    features: &[ResolvedFeature],
+) -> String {
+    let mut dockerfile = format!(
+        "FROM {base_image}\n\
+         USER root\n\
+         COPY ./features /tmp/dev-container-features\n"
+    );
+    for feature in features {
+        dockerfile.push_str(&format!(
+            "RUN cd /tmp/dev-container-features/{} \\\n    \
+             && chmod +x ./install.sh \\\n    \
+             && set -a && . ./devcontainer-features.env && set +a \\\n    \
+             && ./install.sh\n",
+            feature.dir_name
+        ));
+        for (name, value) in &feature.container_env {
+            dockerfile.push_str(&format!(
+                "ENV {name}={}\n",
+                serde_json::Value::String(value.clone())
+            ));
+        }
+    }
+    dockerfile.push_str(&format!("USER {image_user}\n"));
+    dockerfile
+}
+
+/// The directory for files generated for a dev container, which is also the
+/// build context for its Features image.
+pub(crate) fn features_build_dir(devcontainer_id: &str) -> PathBuf {
+    paths::devcontainer_dir()
+        .join("builds")
+        .join(devcontainer_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, path::Path, sync::Arc};
+
+    use anyhow::Result;
+    use fs::{FakeFs, Fs};
+    use gpui::TestAppContext;
+    use http_client::{AsyncBody, FakeHttpClient, HttpClient, Response};
+    use serde_json::json;
+    use sha2::{Digest as _, Sha256};
+    use util::path;
+
+    use super::{
+        FeatureMetadata, FeatureUsers, OciReference, ResolvedFeature, feature_env_file,
+        features_dockerfile, fetch_feature, install_order, option_env_name,
+    };
+
+    /// Fetches `reference` from a registry that serves `manifest` and `blob`
+    /// for any manifest and blob of `owner/feature`.
+    async fn fetch_from_registry(
+        reference: &str,
+        manifest: &str,
+        blob: &[u8],
+        fs: &Arc<dyn Fs>,
+    ) -> Result<()> {
+        let manifest = manifest.to_string();
+        let blob = blob.to_vec();
+        let http_client: Arc<dyn HttpClient> = FakeHttpClient::create(move |request| {
+            let path = request.uri().path();
+            let body = if path.starts_with("/v2/owner/feature/manifests/") {
+                Some(AsyncBody::from(manifest.clone()))
+            } else if path.starts_with("/v2/owner/feature/blobs/") {
+                Some(AsyncBody::from(blob.clone()))
+            } else {
+                None
+            };
+            async move {
+                Ok(match body {
+                    Some(body) => Response::builder().status(200).body(body).unwrap(),
+                    None => Response::builder()
+                        .status(404)
+                        .body(AsyncBody::default())
+                        .unwrap(),
+                })
+            }
+        });
+        fetch_feature(
+            reference,
+            Path::new(path!("/project/.devcontainer")),
+            Path::new(path!("/features")),
+            fs,
+            &http_client,
+        )
+        .await
+    }
+
+    #[test]
+    fn test_parse_oci_reference() {
+        assert_eq!(
+            OciReference::parse("ghcr.io/devcontainers/features/node:1"),
+            Some(OciReference {
+                registry: "ghcr.io".into(),
+                repository: "devcontainers/features/node".into(),
+                reference: "1".into(),
+            })
+        );
+        assert_eq!(
+            OciReference::parse("localhost:5000/features/go"),
+            Some(OciReference {
+                registry: "localhost:5000".into(),
+                repository: "features/go".into(),
+                reference: "latest".into(),
+            })
+        );
+        assert_eq!(
+            OciReference::parse("ghcr.io/owner/feature@sha256:abc"),
+            Some(OciReference {
+                registry: "ghcr.io".into(),
+                repository: "owner/feature".into(),
+                reference: "sha256:abc".into(),
+            })
+        );
+        assert_eq!(OciReference::parse("node"), None);
+    }
+
+    #[gpui::test]
+    async fn test_fetch_feature_verifies_digests(cx: &mut TestAppContext) {
+        let fs: Arc<dyn Fs> = FakeFs::new(cx.executor());
+        fs.create_dir(Path::new(path!("/features"))).await.unwrap();
+
+        // An empty tar archive.
+        let blob = vec![0; 1024];
+        let blob_digest = format!("sha256:{:x}", Sha256::digest(&blob));
+        let manifest = json!({ "layers": [{ "digest": blob_digest }] }).to_string();
+        let manifest_digest = format!("sha256:{:x}", Sha256::digest(manifest.as_bytes()));
+        let pinned = format!("ghcr.io/owner/feature@{manifest_digest}");
+
+        fetch_from_registry("ghcr.io/owner/feature:1", &manifest, &blob, &fs)
+            .await
+            .unwrap();
+        fetch_from_registry(&pinned, &manifest, &blob, &fs)
+            .await
+            .unwrap();
+
+        let mut tampered_blob = blob.clone();
+        tampered_blob[0] = b'x';
+        let error = fetch_from_registry("ghcr.io/owner/feature:1", &manifest, &tampered_blob, &fs)
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{error:#}").contains(&format!("expected digest {blob_digest}")),
+            "unexpected error: {error:#}"
+        );
+
+        let tampered_manifest = json!({
+            "layers": [{ "digest": format!("sha256:{:x}", Sha256::digest(&tampered_blob)) }]
+        })
+        .to_string();
+        let error = fetch_from_registry(&pinned, &tampered_manifest, &tampered_blob, &fs)
+            .await
+            .unwrap_err();
+        assert!(
+            format!("{error:#}").contains(&format!("expected digest {manifest_digest}")),
+            "unexpected error: {error:#}"
+        );
+    }
+
+    #[gpui::test]
+    async fn test_fetch_local_feature(cx: &mut TestAppContext) {
+        let fake_fs = FakeFs::new(cx.executor());
+        fake_fs
+            .insert_tree(
+                path!("/project"),
+                json!({ ".devcontainer": { "local": { "install.sh": "" } } }),
+            )
+            .await;
+        fake_fs
+            .insert_tree(
+                path!("/home"),
+                json!({ "user": { ".ssh": { "id_ed25519": "" } } }),
+            )
+            .await;
+        let fs: Arc<dyn Fs> = fake_fs;
+        fs.create_dir(Path::new(path!("/features"))).await.unwrap();
+        let http_client: Arc<dyn HttpClient> = FakeHttpClient::with_404_response();
+        let config_dir = Path::new(path!("/project/.devcontainer"));
+
+        fetch_feature(
+            "./local",
+            config_dir,
+            Path::new(path!("/features/local")),
+            &fs,
+            &http_client,
+        )
+        .await
+        .unwrap();
+        assert!(
+            fs.is_file(Path::new(path!("/features/local/install.sh")))
+                .await
+        );
+
+        for reference in [
+            "../../home/user/.ssh",
+            "./local/../../../home/user/.ssh",
+            "../",
+        ] {
+            let error = fetch_feature(
+                reference,
+                config_dir,
+                Path::new(path!("/features/escaped")),
+                &fs,
+                &http_client,
+            )
+            .await
+            .unwrap_err();
+            assert!(
+                format!("{error:#}").contains("must be inside"),
+                "unexpected error for {reference}: {error:#}"
+            );
+        }
+        assert!(
+            !fs.is_file(Path::new(path!("/features/escaped/id_ed25519")))
+                .await
+        );
+    }
+
+    #[test]
+    fn test_option_env_name() {
+        assert_eq!(option_env_name("version"), "VERSION");
+        assert_eq!(option_env_name("install-tools"), "INSTALL_TOOLS");
+        assert_eq!(option_env_name("2fa"), "_FA");
+    }
+
+    #[test]
+    fn test_feature_env_file() {
+        let metadata = FeatureMetadata {
+            id: "node".into(),
+            options: serde_json::from_value(json!({
+                "version": { "type": "string", "default": "lts" },
+                "installYarn": { "type": "boolean", "default": true },
+            }))
+            .unwrap(),
+            container_env: BTreeMap::new(),
+            installs_after: Vec::new(),
+        };
+        let users = FeatureUsers {
+            container_user: "root",
+            remote_user: "node",
+        };
+        assert_eq!(
+            feature_env_file(&metadata, &json!({ "version": "it's 20" }), &users),
+            "INSTALLYARN='true'\n\
+             VERSION='it'\\''s 20'\n\
+             _CONTAINER_USER='root'\n\
+             _CONTAINER_USER_HOME='/root'\n\
+             _REMOTE_USER='node'\n\
+             _REMOTE_USER_HOME='/home/node'\n"
+        );
+    }
+
+    #[test]
+    fn test_install_order() {
+        let feature = |reference: &str, installs_after: &[&str]| ResolvedFeature {
+            reference: reference.into(),
+            dir_name: reference.into(),
+            container_env: BTreeMap::new(),
+            installs_after: installs_after.iter().map(|id| id.to_string()).collect(),
+        };
+        let order = |features, override_order: &[String]| {
+            install_order(features, override_order)
+                .into_iter()
+                .map(|feature| feature.reference)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            order(
+                vec![
+                    feature("ghcr.io/a/python:1", &["ghcr.io/a/common-utils"]),
+                    feature("ghcr.io/a/common-utils:2", &[]),
+                    feature("ghcr.io/a/node:1", &[]),
+                ],
+                &[]
+            ),
+            [
+                "ghcr.io/a/common-utils:2",
+                "ghcr.io/a/python:1",
+                "ghcr.io/a/node:1"
+            ]
+        );
+        assert_eq!(
+            order(
+                vec![
+                    feature("ghcr.io/a/python:1", &[]),
+                    feature("ghcr.io/a/node:1", &[]),
+                ],
+                &["ghcr.io/a/node".to_string()]
+            ),
+            ["ghcr.io/a/node:1", "ghcr.io/a/python:1"]
+        );
+    }
+
+    #[test]
+    fn test_features_dockerfile() {
+        let features = [ResolvedFeature {
+            reference: "ghcr.io/devcontainers/features/node:1".into(),
+            dir_name: "0".into(),
+            container_env: BTreeMap::from_iter([(
+                "NVM_DIR".to_string(),
+                "/usr/local/share/nvm".to_string(),
+            )]),
+            installs_after: Vec::new(),
+        }];
+        assert_eq!(
+            features_dockerfile("debian:12", "vscode", &features),
+            "FROM debian:12\n\
+             USER root\n\
+             COPY ./features /tmp/dev-container-features\n\
+             RUN cd /tmp/dev-container-features/0 \\\n    \
+             && chmod +x ./install.sh \\\n    \
+             && set -a && . ./devcontainer-features.env && set +a \\\n    \
+             && ./install.sh\n\
+             ENV NVM_DIR=\"/usr/local/share/nvm\"\n\
+             USER vscode\n"
+        );
+    }
+}
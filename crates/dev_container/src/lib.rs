@@ -37,14 +37,19 @@ use util::ResultExt;
 use util::rel_path::RelPath;
 use workspace::{ModalView, Workspace, with_active_or_new_workspace};
 
+use fs::Fs;
 use futures::AsyncReadExt;
 use http::Request;
 use http_client::{AsyncBody, HttpClient};
 
+mod container_runtime;
 mod devcontainer_api;
+mod devcontainer_json;
+mod engine;
+mod features;
 
 use devcontainer_api::ensure_devcontainer_cli;
-use devcontainer_api::read_devcontainer_configuration;
+use devcontainer_api::find_config_on_disk;
 
 use crate::devcontainer_api::DevContainerError;
 use crate::devcontainer_api::apply_dev_container_template;
@@ -58,6 +63,8 @@ pub struct DevContainerContext {
     pub project_directory: Arc<Path>,
     pub use_podman: bool,
     pub node_runtime: node_runtime::NodeRuntime,
+    pub fs: Arc<dyn Fs>,
+    pub http_client: Arc<dyn HttpClient>,
 }
 
 impl DevContainerContext {
@@ -65,10 +72,14 @@ impl DevContainerContext {
         let project_directory = workspace.project().read(cx).active_project_directory(cx)?;
         let use_podman = DevContainerSettings::get_global(cx).use_podman;
         let node_runtime = workspace.app_state().node_runtime.clone();
+        let fs = workspace.app_state().fs.clone();
+        let http_client = cx.http_client();
         Some(Self {
             project_directory,
             use_podman,
             node_runtime,
+            fs,
+            http_client,
         })
     }
 }
@@ -1495,11 +1506,7 @@ fn dispatch_apply_templates(
         };
 
         {
-            if check_for_existing
-                && read_devcontainer_configuration(&context, &cli, None)
-                    .await
-                    .is_ok()
-            {
+            if check_for_existing && find_config_on_disk(&context).await.is_some() {
                 this.update_in(cx, |this, window, cx| {
                     this.accept_message(
                         DevContainerMessage::NeedConfirmWriteDevContainer(template_entry),
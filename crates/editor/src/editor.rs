@@ -159,6 +159,7 @@ use project::{
     CompletionResponse, CompletionSource, DisableAiSettings, DocumentHighlight, InlayHint, InlayId,
     InvalidationStrategy, Location, LocationLink, LspAction, PrepareRenameResponse, Project,
     ProjectItem, ProjectPath, ProjectTransaction,
+    bookmark_store::BookmarkStore,
     debugger::{
         breakpoint_store::{
             Breakpoint, BreakpointEditAction, BreakpointSessionState, BreakpointState,
@@ -1295,6 +1296,7 @@ pub struct Editor {
     expect_bounds_change: Option<Bounds<Pixels>>,
     runnables: RunnableData,
     breakpoint_store: Option<Entity<BreakpointStore>>,
+    bookmark_store: Option<Entity<BookmarkStore>>,
     gutter_breakpoint_indicator: (Option<PhantomBreakpointIndicator>, Option<Task<()>>),
     pub(crate) gutter_diff_review_indicator: (Option<PhantomDiffReviewIndicator>, Option<Task<()>>),
     pub(crate) diff_review_drag_state: Option<DiffReviewDragState>,
@@ -2350,6 +2352,10 @@ impl Editor {
             (EditorMode::Full { .. }, Some(project)) => Some(project.read(cx).breakpoint_store()),
             _ => None,
         };
+        let bookmark_store = match (&mode, project.as_ref()) {
+            (EditorMode::Full { .. }, Some(project)) => Some(project.read(cx).bookmark_store()),
+            _ => None,
+        };
 
         let mut code_action_providers = Vec::new();
         let mut load_uncommitted_diff = None;
@@ -2522,6 +2528,7 @@ impl Editor {
             blame_subscription: None,
 
             breakpoint_store,
+            bookmark_store,
             gutter_breakpoint_indicator: (None, None),
             gutter_diff_review_indicator: (None, None),
             diff_review_drag_state: None,
@@ -2622,6 +2629,13 @@ impl Editor {
                     cx.notify();
                 }));
         }
+        if let Some(bookmarks) = editor.bookmark_store.as_ref() {
+            editor
+                ._subscriptions
+                .push(cx.observe(bookmarks, |_, _, cx| {
+                    cx.notify();
+                }));
+        }
         editor._subscriptions.extend(project_subscriptions);
 
         editor._subscriptions.push(cx.subscribe_in(
@@ -8995,6 +9009,71 @@ impl Editor {
         breakpoint_display_points
     }
 
+    /// Returns the bookmarks in the given rows, keyed by the display row they're on.
+    fn active_bookmarks(
+        &self,
+        range: Range<DisplayRow>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> HashMap<DisplayRow, (Anchor, Option<Arc<str>>)> {
+        let snapshot = self.snapshot(window, cx);
+        let range = snapshot.display_point_to_point(DisplayPoint::new(range.start, 0), Bias::Left)
+            ..snapshot.display_point_to_point(DisplayPoint::new(range.end, 0), Bias::Right);
+        self.bookmarks_in_range(range, snapshot.buffer_snapshot(), cx)
+            .into_iter()
+            .map(|(anchor, label)| {
+                let row = anchor
+                    .to_point(snapshot.buffer_snapshot())
+                    .to_display_point(&snapshot)
+                    .row();
+                (row, (anchor, label))
+            })
+            .collect()
+    }
+
+    fn bookmarks_in_range(
+        &self,
+        range: Range<Point>,
+        multi_buffer_snapshot: &MultiBufferSnapshot,
+        cx: &App,
+    ) -> Vec<(Anchor, Option<Arc<str>>)> {
+        let mut bookmarks = Vec::new();
+        let (Some(bookmark_store), Some(project)) = (&self.bookmark_store, self.project()) else {
+            return bookmarks;
+        };
+
+        for (buffer_snapshot, range, excerpt_id) in
+            multi_buffer_snapshot.range_to_buffer_ranges(range.start..=range.end)
+        {
+            let Some(buffer) = project
+                .read(cx)
+                .buffer_for_id(buffer_snapshot.remote_id(), cx)
+            else {
+                continue;
+            };
+            bookmarks.extend(
+                bookmark_store
+                    .read(cx)
+                    .bookmarks(
+                        &buffer,
+                        Some(
+                            buffer_snapshot.anchor_before(range.start)
+                                ..buffer_snapshot.anchor_after(range.end),
+                        ),
+                        buffer_snapshot,
+                        cx,
+                    )
+                    .map(|bookmark| {
+                        (
+                            Anchor::in_buffer(excerpt_id, bookmark.position),
+                            bookmark.label.clone(),
+                        )
+                    }),
+            );
+        }
+        bookmarks
+    }
+
     fn breakpoint_context_menu(
         &self,
         anchor: Anchor,
@@ -9283,6 +9362,37 @@ impl Editor {
             })
     }
 
+    fn render_bookmark(
+        &self,
+        position: Anchor,
+        row: DisplayRow,
+        label: Option<Arc<str>>,
+        cx: &mut Context<Self>,
+    ) -> IconButton {
+        let focus_handle = self.focus_handle.clone();
+        IconButton::new(("bookmark_indicator", row.0 as usize), IconName::Bookmark)
+            .icon_size(IconSize::XSmall)
+            .size(ui::ButtonSize::None)
+            .icon_color(Color::Accent)
+            .style(ButtonStyle::Transparent)
+            .on_click(cx.listener(move |editor, _, window, cx| {
+                window.focus(&editor.focus_handle(cx), cx);
+                editor.toggle_bookmark_at_anchor(position, cx);
+            }))
+            .tooltip(move |_window, cx| match &label {
+                Some(label) => Tooltip::with_meta_in(
+                    SharedString::from(label.to_string()),
+                    Some(&ToggleBookmark),
+                    "Click to remove bookmark",
+                    &focus_handle,
+                    cx,
+                ),
+                None => {
+                    Tooltip::for_action_in("Remove Bookmark", &ToggleBookmark, &focus_handle, cx)
+                }
+            })
+    }
+
     fn build_tasks_context(
         project: &Entity<Project>,
         buffer: &Entity<Buffer>,
@@ -12257,6 +12367,156 @@ impl Editor {
         self.breakpoint_store.clone()
     }
 
+    pub fn toggle_bookmark(
+        &mut self,
+        _: &crate::actions::ToggleBookmark,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.bookmark_store.is_none() {
+            return;
+        }
+
+        let snapshot = self.snapshot(window, cx);
+        let mut rows = HashSet::default();
+        for selection in self.selections.all::<Point>(&snapshot.display_snapshot) {
+            let row = selection.head().row;
+            if rows.insert(row) {
+                let anchor = snapshot.buffer_snapshot().anchor_after(Point::new(row, 0));
+                self.toggle_bookmark_at_anchor(anchor, cx);
+            }
+        }
+    }
+
+    fn toggle_bookmark_at_anchor(&mut self, position: Anchor, cx: &mut Context<Self>) {
+        let Some(bookmark_store) = &self.bookmark_store else {
+            return;
+        };
+        let Some(buffer) = self.buffer.read(cx).buffer_for_anchor(position, cx) else {
+            return;
+        };
+        bookmark_store.update(cx, |bookmark_store, cx| {
+            bookmark_store.toggle_bookmark(buffer, position.text_anchor, cx);
+        });
+        cx.notify();
+    }
+
+    /// Returns the label of the bookmark on the newest cursor's line, or
+    /// `None` when that line isn't bookmarked.
+    pub fn bookmark_at_cursor(
+        &self,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Option<Option<Arc<str>>> {
+        let snapshot = self.snapshot(window, cx);
+        let row = self
+            .selections
+            .newest::<Point>(&snapshot.display_snapshot)
+            .head()
+            .row;
+        let line = Point::new(row, 0)
+            ..Point::new(
+                row,
+                snapshot.buffer_snapshot().line_len(MultiBufferRow(row)),
+            );
+        self.bookmarks_in_range(line, snapshot.buffer_snapshot(), cx)
+            .into_iter()
+            .next()
+            .map(|(_, label)| label)
+    }
+
+    /// Labels the bookmark on the newest cursor's line, bookmarking the line
+    /// if it isn't already.
+    pub fn set_bookmark_label_at_cursor(
+        &mut self,
+        label: Option<Arc<str>>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(bookmark_store) = self.bookmark_store.clone() else {
+            return;
+        };
+        let snapshot = self.snapshot(window, cx);
+        let head = self
+            .selections
+            .newest::<Point>(&snapshot.display_snapshot)
+            .head();
+        let position = snapshot.buffer_snapshot().anchor_after(head);
+        let Some(buffer) = self.buffer.read(cx).buffer_for_anchor(position, cx) else {
+            return;
+        };
+        bookmark_store.update(cx, |bookmark_store, cx| {
+            bookmark_store.set_bookmark_label(buffer, position.text_anchor, label, cx);
+        });
+        cx.notify();
+    }
+
+    pub fn go_to_next_bookmark(
+        &mut self,
+        _: &GoToNextBookmark,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.go_to_bookmark(Direction::Next, window, cx);
+    }
+
+    pub fn go_to_previous_bookmark(
+        &mut self,
+        _: &GoToPreviousBookmark,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.go_to_bookmark(Direction::Prev, window, cx);
+    }
+
+    fn go_to_bookmark(
+        &mut self,
+        direction: Direction,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let snapshot = self.snapshot(window, cx);
+        let buffer = snapshot.buffer_snapshot();
+        let mut rows = self
+            .bookmarks_in_range(Point::zero()..buffer.max_point(), buffer, cx)
+            .into_iter()
+            .map(|(anchor, _)| anchor.to_point(buffer).row)
+            .collect::<Vec<_>>();
+        if rows.is_empty() {
+            return;
+        }
+        rows.sort_unstable();
+        rows.dedup();
+
+        let current_row = self
+            .selections
+            .newest::<Point>(&snapshot.display_snapshot)
+            .head()
+            .row;
+        // Navigation wraps around at either end of the buffer.
+        let target_row = match direction {
+            Direction::Next => rows.iter().find(|row| **row > current_row).or(rows.first()),
+            Direction::Prev => rows
+                .iter()
+                .rev()
+                .find(|row| **row < current_row)
+                .or(rows.last()),
+        };
+        let Some(&target_row) = target_row else {
+            return;
+        };
+
+        self.hide_mouse_cursor(HideMouseCursorOrigin::MovementAction, cx);
+        let destination = Point::new(target_row, 0);
+        self.unfold_ranges(&[destination..destination], false, false, cx);
+        self.change_selections(
+            SelectionEffects::scroll(Autoscroll::center()),
+            window,
+            cx,
+            |s| s.select_ranges([destination..destination]),
+        );
+    }
+
     pub fn prepare_restore_change(
         &self,
         revert_changes: &mut HashMap<BufferId, Vec<(Range<text::Anchor>, Rope)>>,
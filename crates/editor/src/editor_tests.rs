@@ -28,7 +28,8 @@ use language::{
     DiagnosticSourceKind, FakeLspAdapter, IndentGuideSettings, LanguageConfig,
     LanguageConfigOverride, LanguageMatcher, LanguageName, LanguageQueries, Override, Point,
     language_settings::{
-        CompletionSettingsContent, FormatterList, LanguageSettingsContent, LspInsertMode,
+        CompletionSettingsContent, FormatOnSave, FormatterList, LanguageSettingsContent,
+        LspInsertMode,
     },
     tree_sitter_python,
 };
@@ -14151,6 +14152,196 @@ async fn test_range_format_on_save_success(cx: &mut TestAppContext) {
     assert!(!cx.read(|cx| editor.is_dirty(cx)));
 }
 
+#[gpui::test]
+async fn test_format_on_save_modified_lines(cx: &mut TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.format_on_save = Some(FormatOnSave::Modified);
+        settings.defaults.remove_trailing_whitespace_on_save = Some(true);
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/dir"),
+        json!({
+            ".git": {},
+            "file.rs": "one  \ntwo\nthree  \n",
+        }),
+    )
+    .await;
+    fs.set_head_for_repo(
+        path!("/dir/.git").as_ref(),
+        &[("file.rs", "one  \ntwo\nthree  \n".into())],
+        "deadbeef",
+    );
+
+    let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                document_formatting_provider: Some(lsp::OneOf::Left(true)),
+                document_range_formatting_provider: Some(lsp::OneOf::Left(true)),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer(path!("/dir/file.rs"), cx)
+        })
+        .await
+        .unwrap();
+    let buffer = cx.new(|cx| MultiBuffer::singleton(buffer, cx));
+    let (editor, cx) = cx.add_window_view(|window, cx| {
+        build_editor_with_project(project.clone(), buffer, window, cx)
+    });
+    let fake_server = fake_servers.next().await.unwrap();
+    cx.run_until_parked();
+
+    editor.update_in(cx, |editor, window, cx| {
+        editor.set_text("one  \nTWO  \nthree  \n", window, cx)
+    });
+    cx.run_until_parked();
+
+    fake_server.set_request_handler::<lsp::request::Formatting, _, _>(move |_, _| async move {
+        panic!("the whole document should not be formatted");
+    });
+    let save = editor
+        .update_in(cx, |editor, window, cx| {
+            editor.save(
+                SaveOptions {
+                    format: true,
+                    autosave: false,
+                },
+                project.clone(),
+                window,
+                cx,
+            )
+        })
+        .unwrap();
+    fake_server
+        .set_request_handler::<lsp::request::RangeFormatting, _, _>(move |params, _| async move {
+            assert_eq!(
+                params.range,
+                lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(1, 3))
+            );
+            Ok(Some(vec![lsp::TextEdit::new(
+                lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(1, 3)),
+                "two".to_string(),
+            )]))
+        })
+        .next()
+        .await;
+    save.await;
+
+    // Only the modified line is formatted and has its trailing whitespace removed.
+    assert_eq!(
+        editor.update(cx, |editor, cx| editor.text(cx)),
+        "one  \ntwo\nthree  \n"
+    );
+}
+
+#[gpui::test]
+async fn test_format_on_save_modified_lines_skips_whole_file_formatters(cx: &mut TestAppContext) {
+    init_test(cx, |settings| {
+        settings.defaults.format_on_save = Some(FormatOnSave::Modified);
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/dir"),
+        json!({
+            ".git": {},
+            "file.rs": "one\ntwo\n",
+        }),
+    )
+    .await;
+    fs.set_head_for_repo(
+        path!("/dir/.git").as_ref(),
+        &[("file.rs", "one\ntwo\n".into())],
+        "deadbeef",
+    );
+
+    let project = Project::test(fs, [path!("/dir").as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+    let mut fake_servers = language_registry.register_fake_lsp(
+        "Rust",
+        FakeLspAdapter {
+            capabilities: lsp::ServerCapabilities {
+                document_formatting_provider: Some(lsp::OneOf::Left(true)),
+                ..lsp::ServerCapabilities::default()
+            },
+            ..FakeLspAdapter::default()
+        },
+    );
+
+    let toasts = Rc::new(RefCell::new(Vec::new()));
+    cx.update(|cx| {
+        let toasts = toasts.clone();
+        cx.subscribe(&project, move |_, event, _| {
+            if let project::Event::Toast { message, .. } = event {
+                toasts.borrow_mut().push(message.clone());
+            }
+        })
+        .detach();
+    });
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer(path!("/dir/file.rs"), cx)
+        })
+        .await
+        .unwrap();
+    let buffer = cx.new(|cx| MultiBuffer::singleton(buffer, cx));
+    let (editor, cx) = cx.add_window_view(|window, cx| {
+        build_editor_with_project(project.clone(), buffer, window, cx)
+    });
+    let fake_server = fake_servers.next().await.unwrap();
+    cx.run_until_parked();
+
+    editor.update_in(cx, |editor, window, cx| {
+        editor.set_text("one\nTWO\n", window, cx)
+    });
+    cx.run_until_parked();
+
+    fake_server.set_request_handler::<lsp::request::Formatting, _, _>(move |_, _| async move {
+        panic!("the whole document should not be formatted");
+    });
+    editor
+        .update_in(cx, |editor, window, cx| {
+            editor.save(
+                SaveOptions {
+                    format: true,
+                    autosave: false,
+                },
+                project.clone(),
+                window,
+                cx,
+            )
+        })
+        .unwrap()
+        .await
+        .unwrap();
+    cx.run_until_parked();
+
+    assert_eq!(
+        editor.update(cx, |editor, cx| editor.text(cx)),
+        "one\nTWO\n"
+    );
+    let toasts = toasts.borrow();
+    assert_eq!(toasts.len(), 1, "{toasts:?}");
+    assert!(
+        toasts[0].contains("language server `the-fake-language-server`"),
+        "{}",
+        toasts[0]
+    );
+}
+
 #[gpui::test]
 async fn test_range_format_on_save_timeout(cx: &mut TestAppContext) {
     let (project, editor, cx, fake_server) = setup_range_format_test(cx).await;
@@ -26614,6 +26805,111 @@ async fn test_breakpoint_toggling(cx: &mut TestAppContext) {
     assert_breakpoint(&breakpoints, &abs_path, vec![]);
 }
 
+#[gpui::test]
+async fn test_bookmark_toggling_and_navigation(cx: &mut TestAppContext) {
+    init_test(cx, |_| {});
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/a"),
+        json!({
+            "main.rs": "First line\nSecond line\nThird line\nFourth line",
+        }),
+    )
+    .await;
+    let project = Project::test(fs, [path!("/a").as_ref()], cx).await;
+    let worktree_id = project.update(cx, |project, cx| {
+        project.worktrees(cx).next().unwrap().read(cx).id()
+    });
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_buffer((worktree_id, rel_path("main.rs")), cx)
+        })
+        .await
+        .unwrap();
+
+    let (editor, cx) = cx.add_window_view(|window, cx| {
+        Editor::new(
+            EditorMode::full(),
+            MultiBuffer::build_from_buffer(buffer.clone(), cx),
+            Some(project.clone()),
+            window,
+            cx,
+        )
+    });
+    let bookmarked_rows = |cx: &mut VisualTestContext| {
+        project.update(cx, |project, cx| {
+            project
+                .bookmark_store()
+                .read(cx)
+                .all_serialized_bookmarks(cx)
+                .into_values()
+                .flatten()
+                .map(|bookmark| bookmark.row)
+                .collect::<Vec<_>>()
+        })
+    };
+    let cursor_row = |cx: &mut VisualTestContext| {
+        editor.update_in(cx, |editor, window, cx| {
+            let snapshot = editor.snapshot(window, cx);
+            editor
+                .selections
+                .newest::<Point>(&snapshot.display_snapshot)
+                .head()
+                .row
+        })
+    };
+
+    editor.update_in(cx, |editor, window, cx| {
+        editor.move_down(&MoveDown, window, cx);
+        editor.toggle_bookmark(&actions::ToggleBookmark, window, cx);
+        editor.move_to_end(&MoveToEnd, window, cx);
+        editor.toggle_bookmark(&actions::ToggleBookmark, window, cx);
+    });
+    assert_eq!(bookmarked_rows(cx), vec![1, 3]);
+
+    // Bookmarks follow their line when text is inserted above them.
+    buffer.update(cx, |buffer, cx| {
+        buffer.edit(
+            [(Point::new(0, 0)..Point::new(0, 0), "Zeroth line\n")],
+            None,
+            cx,
+        );
+    });
+    assert_eq!(bookmarked_rows(cx), vec![2, 4]);
+
+    // Navigation wraps around at either end of the buffer.
+    editor.update_in(cx, |editor, window, cx| {
+        editor.move_to_beginning(&MoveToBeginning, window, cx);
+        editor.go_to_next_bookmark(&actions::GoToNextBookmark, window, cx);
+    });
+    assert_eq!(cursor_row(cx), 2);
+    editor.update_in(cx, |editor, window, cx| {
+        editor.go_to_next_bookmark(&actions::GoToNextBookmark, window, cx);
+    });
+    assert_eq!(cursor_row(cx), 4);
+    editor.update_in(cx, |editor, window, cx| {
+        editor.go_to_next_bookmark(&actions::GoToNextBookmark, window, cx);
+    });
+    assert_eq!(cursor_row(cx), 2);
+    editor.update_in(cx, |editor, window, cx| {
+        editor.go_to_previous_bookmark(&actions::GoToPreviousBookmark, window, cx);
+    });
+    assert_eq!(cursor_row(cx), 4);
+
+    // Labeling keeps the bookmark, and toggling removes it.
+    editor.update_in(cx, |editor, window, cx| {
+        editor.set_bookmark_label_at_cursor(Some("end".into()), window, cx);
+        assert_eq!(
+            editor.bookmark_at_cursor(window, cx),
+            Some(Some(Arc::from("end")))
+        );
+        editor.toggle_bookmark(&actions::ToggleBookmark, window, cx);
+        assert_eq!(editor.bookmark_at_cursor(window, cx), None);
+    });
+    assert_eq!(bookmarked_rows(cx), vec![2]);
+}
+
 #[gpui::test]
 async fn test_log_breakpoint_editing(cx: &mut TestAppContext) {
     init_test(cx, |_| {});
@@ -337,6 +337,9 @@ impl ExampleContext {
                     ThreadEvent::Retry(status) => {
                         println!("{log_prefix} Got retry: {status:?}");
                     }
+                    ThreadEvent::Warning(warning) => {
+                        println!("{log_prefix} Got warning: {warning}");
+                    }
                     ThreadEvent::Stop(stop_reason) => match stop_reason {
                         acp::StopReason::EndTurn => {}
                         acp::StopReason::MaxTokens => {
@@ -19,6 +19,13 @@
 //!   - `thread.md`    — full conversation as markdown
 //!   - `thread.json`  — raw thread state as JSON
 //!
+//! ## Offline replay
+//!
+//! `--record <PATH>` saves every language model request and response to a
+//! cassette file, and `--replay <PATH>` serves those responses back without
+//! contacting the provider. A replayed request that doesn't match the
+//! recording fails the run with a description of where it diverged.
+//!
 //! ## Exit codes
 //!
 //! | Code | Meaning |
@@ -47,6 +54,9 @@ use feature_flags::FeatureFlagAppExt as _;
 
 use futures::{FutureExt, select_biased};
 use gpui::{AppContext as _, AsyncApp, Entity, UpdateGlobal};
+use language_model::cassette::{
+    CassetteLanguageModelProvider, CassettePlayer, CassetteRecorder, RequestNormalizer,
+};
 use language_model::{LanguageModelRegistry, SelectedModel};
 use project::Project;
 use settings::SettingsStore;
@@ -97,6 +107,14 @@ struct Args {
     /// Enable or disable extended thinking. Defaults to model auto-detection if omitted.
     #[arg(long)]
     thinking: Option<bool>,
+
+    /// Record every language model request and response to this cassette file.
+    #[arg(long, conflicts_with = "replay")]
+    record: Option<PathBuf>,
+
+    /// Replay language model responses from this cassette file instead of contacting the provider.
+    #[arg(long)]
+    replay: Option<PathBuf>,
 }
 
 enum AgentOutcome {
@@ -169,6 +187,39 @@ fn main() {
         let app_state = headless::init(cx);
         cx.set_staff(!args.no_staff);
 
+        // The worktree path differs between runs, so it's normalized out of recorded requests.
+        let normalizer =
+            RequestNormalizer::default().with_replacement(workdir.to_string_lossy(), "$WORKDIR");
+        let recorder = args
+            .record
+            .as_ref()
+            .map(|_| CassetteRecorder::new(normalizer.clone()));
+        let player = args.replay.as_ref().map(|path| {
+            CassettePlayer::load(path, normalizer.clone()).unwrap_or_else(|e| {
+                eprintln!("Error loading cassette: {e:#}");
+                process::exit(EXIT_ERROR);
+            })
+        });
+        LanguageModelRegistry::global(cx).update(cx, |registry, cx| {
+            if let Some(recorder) = &recorder {
+                for provider in registry.providers() {
+                    registry.register_provider(
+                        Arc::new(CassetteLanguageModelProvider::record(
+                            provider,
+                            recorder.clone(),
+                        )),
+                        cx,
+                    );
+                }
+            }
+            if let Some(player) = &player {
+                for provider in CassetteLanguageModelProvider::replay(player) {
+                    registry.register_provider(Arc::new(provider), cx);
+                }
+            }
+        });
+        let record_path = args.record.clone();
+
         let auth_tasks = LanguageModelRegistry::global(cx).update(cx, |registry, cx| {
             registry
                 .providers()
@@ -202,6 +253,21 @@ fn main() {
 
             let duration = start.elapsed();
 
+            if let (Some(recorder), Some(path)) = (&recorder, &record_path) {
+                match recorder.save(path) {
+                    Ok(()) => eprintln!("[eval-cli] recorded cassette to {}", path.display()),
+                    Err(e) => eprintln!("Error writing cassette: {e:#}"),
+                }
+            }
+            if let Some(player) = &player
+                && player.remaining() > 0
+            {
+                eprintln!(
+                    "[eval-cli] {} recorded interaction(s) were not replayed",
+                    player.remaining()
+                );
+            }
+
             let (status, error, exit_code) = match &outcome {
                 Ok(AgentOutcome::Completed) => ("completed".to_string(), None, EXIT_OK),
                 Ok(AgentOutcome::Timeout { seconds }) => {
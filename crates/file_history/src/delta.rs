@@ -0,0 +1,118 @@
+use std::{ops::Range, sync::Arc};
+
+use anyhow::{Context as _, Result, anyhow};
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// Compresses the full text of a snapshot.
+pub fn encode_text(text: &str) -> Result<Vec<u8>> {
+    Ok(zstd::encode_all(text.as_bytes(), COMPRESSION_LEVEL)?)
+}
+
+pub fn decode_text(content: &[u8]) -> Result<String> {
+    let bytes = zstd::decode_all(content)?;
+    String::from_utf8(bytes).context("file history snapshot is not valid UTF-8")
+}
+
+/// Compresses the edits that turn `old_text` into `new_text`.
+///
+/// Each edit is stored as the byte range it replaces in the old text followed
+/// by the replacement text, with lengths as little-endian `u32`s.
+pub fn encode_delta(old_text: &str, new_text: &str) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    for (range, replacement) in language::text_diff(old_text, new_text) {
+        for value in [range.start, range.end, replacement.len()] {
+            let value = u32::try_from(value).context("file is too large for file history")?;
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes.extend_from_slice(replacement.as_bytes());
+    }
+    Ok(zstd::encode_all(&bytes[..], COMPRESSION_LEVEL)?)
+}
+
+pub fn apply_delta(old_text: &str, content: &[u8]) -> Result<String> {
+    let edits = decode_edits(&zstd::decode_all(content)?)?;
+    let mut text = String::with_capacity(old_text.len());
+    let mut offset = 0;
+    for (range, replacement) in edits {
+        if range.start < offset || range.end < range.start || range.end > old_text.len() {
+            return Err(anyhow!(
+                "file history delta doesn't match its base snapshot"
+            ));
+        }
+        text.push_str(
+            old_text
+                .get(offset..range.start)
+                .context("file history delta splits a character")?,
+        );
+        text.push_str(&replacement);
+        offset = range.end;
+    }
+    text.push_str(
+        old_text
+            .get(offset..)
+            .context("file history delta splits a character")?,
+    );
+    Ok(text)
+}
+
+fn decode_edits(mut bytes: &[u8]) -> Result<Vec<(Range<usize>, Arc<str>)>> {
+    let mut edits = Vec::new();
+    while !bytes.is_empty() {
+        let start = read_u32(&mut bytes)? as usize;
+        let end = read_u32(&mut bytes)? as usize;
+        let len = read_u32(&mut bytes)? as usize;
+        let replacement = bytes
+            .get(..len)
+            .context("file history delta is truncated")?;
+        let replacement = std::str::from_utf8(replacement)?;
+        edits.push((start..end, Arc::from(replacement)));
+        bytes = &bytes[len..];
+    }
+    Ok(edits)
+}
+
+fn read_u32(bytes: &mut &[u8]) -> Result<u32> {
+    let (value, rest) = bytes
+        .split_first_chunk::<4>()
+        .context("file history delta is truncated")?;
+    *bytes = rest;
+    Ok(u32::from_le_bytes(*value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delta_round_trip() {
+        let cases = [
+            ("", "fn main() {}\n"),
+            ("fn main() {}\n", ""),
+            (
+                "fn main() {\n    println!(\"hello\");\n}\n",
+                "fn main() {\n    let name = \"wörld\";\n    println!(\"hello {name}\");\n}\n",
+            ),
+            ("unchanged\n", "unchanged\n"),
+            ("αβγ\nδεζ\n", "αβ\nγδεζη\n"),
+        ];
+        for (old_text, new_text) in cases {
+            let delta = encode_delta(old_text, new_text).unwrap();
+            assert_eq!(apply_delta(old_text, &delta).unwrap(), new_text);
+        }
+    }
+
+    #[test]
+    fn test_delta_against_wrong_base() {
+        let delta = encode_delta("one\ntwo\nthree\n", "one\n").unwrap();
+        assert!(apply_delta("one\n", &delta).is_err());
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let text = "line\n".repeat(1000);
+        let content = encode_text(&text).unwrap();
+        assert!(content.len() < text.len());
+        assert_eq!(decode_text(&content).unwrap(), text);
+    }
+}
@@ -0,0 +1,381 @@
+//! A local history of file contents, kept independently of version control.
+//!
+//! Files are snapshotted when they're saved, before they're reloaded after
+//! changing on disk, and before the agent edits them. Any snapshot can be
+//! compared with the current contents of the file and restored.
+
+mod delta;
+mod persistence;
+mod snapshot_diff_view;
+
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use collections::HashMap;
+use editor::Editor;
+use fuzzy::{StringMatch, StringMatchCandidate};
+use gpui::{
+    App, AppContext as _, Context, DismissEvent, Entity, Subscription, Task, WeakEntity, Window,
+    actions, rems,
+};
+use language::{Buffer, BufferEvent};
+use picker::{Picker, PickerDelegate};
+use project::{Project, buffer_store::BufferStoreEvent};
+use settings::{RegisterSetting, Settings};
+use text::BufferId;
+use time::{OffsetDateTime, UtcOffset};
+use ui::{HighlightedLabel, ListItem, ListItemSpacing, prelude::*};
+use util::ResultExt as _;
+use workspace::Workspace;
+
+pub use persistence::{FileHistoryDb, RetentionPolicy, Snapshot, SnapshotSource};
+pub use snapshot_diff_view::SnapshotDiffView;
+
+actions!(
+    file_history,
+    [
+        /// Shows the local history of the active file.
+        ShowHistory,
+        /// Replaces the contents of the file with the snapshot being compared.
+        RestoreSnapshot,
+    ]
+);
+
+/// Settings for the local history of file contents.
+#[derive(Clone, Debug, RegisterSetting)]
+pub struct FileHistorySettings {
+    pub enabled: bool,
+    pub max_entries_per_file: usize,
+    pub max_file_size_kb: u64,
+    pub max_age_days: u64,
+    pub max_total_size_mb: u64,
+}
+
+impl Settings for FileHistorySettings {
+    fn from_settings(content: &settings::SettingsContent) -> Self {
+        let file_history = content.file_history.clone().unwrap();
+
+        Self {
+            enabled: file_history.enabled.unwrap(),
+            max_entries_per_file: file_history.max_entries_per_file.unwrap(),
+            max_file_size_kb: file_history.max_file_size_kb.unwrap(),
+            max_age_days: file_history.max_age_days.unwrap(),
+            max_total_size_mb: file_history.max_total_size_mb.unwrap(),
+        }
+    }
+}
+
+impl FileHistorySettings {
+    fn retention_policy(&self) -> RetentionPolicy {
+        RetentionPolicy {
+            max_entries_per_file: self.max_entries_per_file,
+            max_age: Duration::from_secs(self.max_age_days * 24 * 60 * 60),
+            max_total_size: self.max_total_size_mb * 1024 * 1024,
+        }
+    }
+}
+
+pub fn init(cx: &mut App) {
+    cx.observe_new(|workspace: &mut Workspace, _window, cx| {
+        workspace.register_action(|workspace, _: &ShowHistory, window, cx| {
+            show_history(workspace, window, cx);
+        });
+
+        let project = workspace.project().clone();
+        if !project.read(cx).is_local() {
+            return;
+        }
+        let recorder = cx.new(|cx| FileHistoryRecorder::new(&project, cx));
+        cx.subscribe(&project, move |_, _, event, cx| {
+            if let project::Event::AgentWillEditBuffer(buffer) = event {
+                recorder.update(cx, |recorder, cx| recorder.agent_will_edit(buffer, cx));
+            }
+        })
+        .detach();
+    })
+    .detach();
+}
+
+/// Records snapshots of the files in a local project.
+struct FileHistoryRecorder {
+    buffer_subscriptions: HashMap<BufferId, Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl FileHistoryRecorder {
+    fn new(project: &Entity<Project>, cx: &mut Context<Self>) -> Self {
+        let buffer_store = project.read(cx).buffer_store().clone();
+        let subscriptions = vec![
+            cx.subscribe(&buffer_store, |this, _, event, cx| match event {
+                BufferStoreEvent::BufferAdded(buffer) => this.register_buffer(buffer, cx),
+                BufferStoreEvent::BufferDropped(buffer_id) => {
+                    this.buffer_subscriptions.remove(buffer_id);
+                }
+                BufferStoreEvent::BufferChangedFilePath { .. }
+                | BufferStoreEvent::SharedBufferClosed(..) => {}
+            }),
+        ];
+        let mut this = Self {
+            buffer_subscriptions: HashMap::default(),
+            _subscriptions: subscriptions,
+        };
+        for buffer in buffer_store.read(cx).buffers().collect::<Vec<_>>() {
+            this.register_buffer(&buffer, cx);
+        }
+        this
+    }
+
+    fn register_buffer(&mut self, buffer: &Entity<Buffer>, cx: &mut Context<Self>) {
+        let buffer_id = buffer.read(cx).remote_id();
+        let subscription = cx.subscribe(buffer, |_, buffer, event, cx| match event {
+            BufferEvent::Saved => record_snapshot(&buffer, SnapshotSource::Save, cx),
+            // The buffer still has the contents from before the file changed on disk.
+            BufferEvent::ReloadNeeded => record_snapshot(&buffer, SnapshotSource::Reload, cx),
+            _ => {}
+        });
+        self.buffer_subscriptions.insert(buffer_id, subscription);
+    }
+
+    /// Snapshots the buffer before each agent tool call that edits it, so that
+    /// contents the agent replaces can always be restored.
+    fn agent_will_edit(&mut self, buffer: &Entity<Buffer>, cx: &mut Context<Self>) {
+        record_snapshot(buffer, SnapshotSource::Agent, cx);
+    }
+}
+
+fn local_path(buffer: &Buffer, cx: &App) -> Option<PathBuf> {
+    Some(buffer.file()?.as_local()?.abs_path(cx))
+}
+
+fn record_snapshot(buffer: &Entity<Buffer>, source: SnapshotSource, cx: &mut App) {
+    let settings = FileHistorySettings::get_global(cx);
+    if !settings.enabled {
+        return;
+    }
+    let buffer = buffer.read(cx);
+    if buffer.len() as u64 > settings.max_file_size_kb * 1024 {
+        return;
+    }
+    let Some(path) = local_path(buffer, cx) else {
+        return;
+    };
+    let text = buffer.text();
+    let retention = settings.retention_policy();
+    let db = FileHistoryDb::global(cx);
+    cx.background_spawn(async move {
+        db.record_snapshot(path, text, source, OffsetDateTime::now_utc(), retention)
+            .await
+            .log_err();
+    })
+    .detach();
+}
+
+fn show_history(workspace: &mut Workspace, window: &mut Window, cx: &mut Context<Workspace>) {
+    let Some(buffer) = workspace
+        .active_item_as::<Editor>(cx)
+        .and_then(|editor| editor.read(cx).buffer().read(cx).as_singleton())
+    else {
+        return;
+    };
+    let Some(path) = local_path(buffer.read(cx), cx) else {
+        return;
+    };
+    let Some(snapshots) = FileHistoryDb::global(cx).snapshots(&path).log_err() else {
+        return;
+    };
+    let handle = cx.entity().downgrade();
+    workspace.toggle_modal(window, cx, move |window, cx| {
+        let delegate = FileHistoryDelegate::new(handle, buffer, snapshots);
+        Picker::uniform_list(delegate, window, cx).width(rems(34.))
+    });
+}
+
+pub struct FileHistoryDelegate {
+    workspace: WeakEntity<Workspace>,
+    buffer: Entity<Buffer>,
+    snapshots: Vec<Snapshot>,
+    /// The formatted time of each snapshot.
+    times: Vec<SharedString>,
+    matches: Vec<StringMatch>,
+    selected_index: usize,
+}
+
+impl FileHistoryDelegate {
+    fn new(
+        workspace: WeakEntity<Workspace>,
+        buffer: Entity<Buffer>,
+        snapshots: Vec<Snapshot>,
+    ) -> Self {
+        let timezone = UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC);
+        let now = OffsetDateTime::now_utc();
+        let times = snapshots
+            .iter()
+            .map(|snapshot| {
+                time_format::format_localized_timestamp(
+                    snapshot.timestamp,
+                    now,
+                    timezone,
+                    time_format::TimestampFormat::Relative,
+                )
+                .into()
+            })
+            .collect::<Vec<_>>();
+        let matches = (0..snapshots.len())
+            .map(|candidate_id| StringMatch {
+                candidate_id,
+                score: 0.,
+                positions: Vec::new(),
+                string: String::new(),
+            })
+            .collect();
+        Self {
+            workspace,
+            buffer,
+            snapshots,
+            times,
+            matches,
+            selected_index: 0,
+        }
+    }
+
+    fn match_text(&self, ix: usize) -> String {
+        format!("{} {}", self.times[ix], self.snapshots[ix].source.label())
+    }
+}
+
+impl PickerDelegate for FileHistoryDelegate {
+    type ListItem = ListItem;
+
+    fn match_count(&self) -> usize {
+        self.matches.len()
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(
+        &mut self,
+        ix: usize,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) {
+        self.selected_index = ix;
+    }
+
+    fn placeholder_text(&self, _window: &mut Window, _cx: &mut App) -> Arc<str> {
+        "Search file history…".into()
+    }
+
+    fn no_matches_text(&self, _window: &mut Window, _cx: &mut App) -> Option<SharedString> {
+        Some(if self.snapshots.is_empty() {
+            "No local history for this file".into()
+        } else {
+            "No matching snapshots".into()
+        })
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        let candidates = (0..self.snapshots.len())
+            .map(|id| StringMatchCandidate::new(id, &self.match_text(id)))
+            .collect::<Vec<_>>();
+        cx.spawn_in(window, async move |picker, cx| {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .map(|candidate| StringMatch {
+                        candidate_id: candidate.id,
+                        score: 0.,
+                        positions: Vec::new(),
+                        string: candidate.string,
+                    })
+                    .collect()
+            } else {
+                fuzzy::match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    true,
+                    100,
+                    &Default::default(),
+                    cx.background_executor().clone(),
+                )
+                .await
+            };
+            picker
+                .update(cx, |picker, cx| {
+                    let delegate = &mut picker.delegate;
+                    delegate.matches = matches;
+                    delegate.selected_index = delegate
+                        .selected_index
+                        .min(delegate.matches.len().saturating_sub(1));
+                    cx.notify();
+                })
+                .ok();
+        })
+    }
+
+    fn confirm(&mut self, _secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(ix) = self
+            .matches
+            .get(self.selected_index)
+            .map(|mat| mat.candidate_id)
+        else {
+            return;
+        };
+        let snapshot = self.snapshots[ix].clone();
+        let time = self.times[ix].clone();
+        SnapshotDiffView::open(
+            self.buffer.clone(),
+            snapshot,
+            time,
+            self.workspace.clone(),
+            window,
+            cx,
+        )
+        .detach_and_log_err(cx);
+        cx.emit(DismissEvent);
+    }
+
+    fn dismissed(&mut self, _window: &mut Window, _cx: &mut Context<Picker<Self>>) {}
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _window: &mut Window,
+        _cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let string_match = self.matches.get(ix)?;
+        let snapshot = self.snapshots.get(string_match.candidate_id)?;
+        let time = self.times.get(string_match.candidate_id)?.clone();
+        let time_positions = string_match
+            .positions
+            .iter()
+            .copied()
+            .filter(|position| *position < time.len())
+            .collect();
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .toggle_state(selected)
+                .start_slot(Icon::new(IconName::HistoryRerun).color(Color::Muted))
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .child(HighlightedLabel::new(time, time_positions))
+                        .child(
+                            Label::new(snapshot.source.label())
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        ),
+                ),
+        )
+    }
+}
@@ -0,0 +1,419 @@
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Result, anyhow};
+use collections::{HashMap, HashSet};
+use db::{
+    sqlez::{
+        bindable::{Bind, Column, StaticColumnCount},
+        connection::Connection,
+        domain::Domain,
+        statement::Statement,
+        thread_safe_connection::ThreadSafeConnection,
+    },
+    sqlez_macros::sql,
+};
+use time::OffsetDateTime;
+
+use crate::delta;
+
+/// Every this many snapshots of a file, the full text is stored instead of a
+/// delta, which bounds the work needed to reconstruct any snapshot.
+const KEYFRAME_INTERVAL: u32 = 10;
+
+/// What caused a snapshot to be recorded.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SnapshotSource {
+    /// The file was saved.
+    Save,
+    /// The file changed on disk, and this is its content before reloading.
+    Reload,
+    /// The agent was about to edit the file, and this is its content before.
+    Agent,
+    /// A snapshot was restored, and this is the content it replaced.
+    Restore,
+}
+
+impl SnapshotSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnapshotSource::Save => "save",
+            SnapshotSource::Reload => "reload",
+            SnapshotSource::Agent => "agent",
+            SnapshotSource::Restore => "restore",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SnapshotSource::Save => "Saved",
+            SnapshotSource::Reload => "Before reload from disk",
+            SnapshotSource::Agent => "Before agent edit",
+            SnapshotSource::Restore => "Before restore",
+        }
+    }
+}
+
+impl StaticColumnCount for SnapshotSource {}
+
+impl Bind for SnapshotSource {
+    fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
+        self.as_str().bind(statement, start_index)
+    }
+}
+
+impl Column for SnapshotSource {
+    fn column(statement: &mut Statement, start_index: i32) -> Result<(Self, i32)> {
+        let (source, next_index) = String::column(statement, start_index)?;
+        let source = match source.as_str() {
+            "save" => SnapshotSource::Save,
+            "reload" => SnapshotSource::Reload,
+            "agent" => SnapshotSource::Agent,
+            "restore" => SnapshotSource::Restore,
+            _ => return Err(anyhow!("unknown file history source {source:?}")),
+        };
+        Ok((source, next_index))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Snapshot {
+    pub id: i64,
+    pub timestamp: OffsetDateTime,
+    pub source: SnapshotSource,
+}
+
+impl Column for Snapshot {
+    fn column(statement: &mut Statement, start_index: i32) -> Result<(Self, i32)> {
+        let (id, next_index) = i64::column(statement, start_index)?;
+        let (timestamp, next_index) = i64::column(statement, next_index)?;
+        let (source, next_index) = SnapshotSource::column(statement, next_index)?;
+        let snapshot = Self {
+            id,
+            timestamp: OffsetDateTime::from_unix_timestamp(timestamp)?,
+            source,
+        };
+        Ok((snapshot, next_index))
+    }
+}
+
+/// Limits on how much history is kept.
+#[derive(Clone, Copy, Debug)]
+pub struct RetentionPolicy {
+    pub max_entries_per_file: usize,
+    pub max_age: Duration,
+    pub max_total_size: u64,
+}
+
+pub struct FileHistoryDb(ThreadSafeConnection);
+
+impl Domain for FileHistoryDb {
+    const NAME: &str = stringify!(FileHistoryDb);
+
+    const MIGRATIONS: &[&str] = &[sql!(
+        CREATE TABLE file_history(
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            path BLOB NOT NULL,
+            timestamp INTEGER NOT NULL,
+            source TEXT NOT NULL,
+            // The snapshot this one is a delta against, or NULL if `content`
+            // holds the full text.
+            base_id INTEGER,
+            depth INTEGER NOT NULL,
+            content BLOB NOT NULL,
+            size INTEGER NOT NULL
+        ) STRICT;
+        CREATE INDEX file_history_path ON file_history(path, id);
+    )];
+}
+
+db::static_connection!(FileHistoryDb, []);
+
+impl FileHistoryDb {
+    /// Records the text of the file at `path`, unless it is unchanged since
+    /// the last snapshot, and then applies the retention policy.
+    ///
+    /// Returns whether a snapshot was recorded.
+    pub async fn record_snapshot(
+        &self,
+        path: PathBuf,
+        text: String,
+        source: SnapshotSource,
+        timestamp: OffsetDateTime,
+        retention: RetentionPolicy,
+    ) -> Result<bool> {
+        self.write(move |conn| {
+            conn.with_savepoint("record_file_history_snapshot", || {
+                let latest: Option<(i64, u32)> = conn.select_row_bound(sql!(
+                    SELECT id, depth FROM file_history
+                    WHERE path = ?
+                    ORDER BY id DESC
+                    LIMIT 1
+                ))?(path.as_path())?;
+
+                let (base_id, depth, content) = match latest {
+                    Some((latest_id, latest_depth)) => {
+                        let latest_text = snapshot_text(conn, latest_id)?;
+                        if latest_text == text {
+                            return Ok(false);
+                        }
+                        if latest_depth + 1 >= KEYFRAME_INTERVAL {
+                            (None, 0, delta::encode_text(&text)?)
+                        } else {
+                            (
+                                Some(latest_id),
+                                latest_depth + 1,
+                                delta::encode_delta(&latest_text, &text)?,
+                            )
+                        }
+                    }
+                    None => (None, 0, delta::encode_text(&text)?),
+                };
+
+                conn.exec_bound(sql!(
+                    INSERT INTO file_history
+                        (path, timestamp, source, base_id, depth, content, size)
+                    VALUES (?, ?, ?, ?, ?, ?, ?)
+                ))?((
+                    path.as_path(),
+                    timestamp.unix_timestamp(),
+                    source,
+                    base_id,
+                    depth,
+                    content.as_slice(),
+                    content.len() as u64,
+                ))?;
+
+                prune(conn, timestamp, retention)?;
+                Ok(true)
+            })
+        })
+        .await
+    }
+
+    /// Returns the snapshots of the file at `path`, newest first.
+    pub fn snapshots(&self, path: &Path) -> Result<Vec<Snapshot>> {
+        self.select_bound(sql!(
+            SELECT id, timestamp, source FROM file_history
+            WHERE path = ?
+            ORDER BY id DESC
+        ))?(path)
+    }
+
+    pub fn snapshot_text(&self, id: i64) -> Result<String> {
+        snapshot_text(self, id)
+    }
+}
+
+/// Reconstructs the text of a snapshot by applying the deltas since the
+/// closest full snapshot.
+fn snapshot_text(conn: &Connection, id: i64) -> Result<String> {
+    let mut select = conn.select_row_bound::<i64, (Option<i64>, Vec<u8>)>(sql!(
+        SELECT base_id, content FROM file_history WHERE id = ?
+    ))?;
+    let mut deltas = Vec::new();
+    let mut next_id = id;
+    let text = loop {
+        let (base_id, content) =
+            select(next_id)?.ok_or_else(|| anyhow!("file history snapshot {next_id} not found"))?;
+        match base_id {
+            Some(base_id) => {
+                deltas.push(content);
+                next_id = base_id;
+            }
+            None => break delta::decode_text(&content)?,
+        }
+    };
+    deltas
+        .iter()
+        .rev()
+        .try_fold(text, |text, content| delta::apply_delta(&text, content))
+}
+
+/// Removes snapshots that are beyond the retention limits.
+///
+/// Snapshots are removed oldest first, so each file loses a prefix of its
+/// history. Its oldest remaining snapshot is rewritten in full if it was a
+/// delta against a removed one.
+fn prune(conn: &Connection, now: OffsetDateTime, retention: RetentionPolicy) -> Result<()> {
+    let min_timestamp = (now - retention.max_age).unix_timestamp();
+    let rows: Vec<(i64, PathBuf, i64, Option<i64>, u64)> = conn.select_bound(sql!(
+        SELECT id, path, timestamp, base_id, size FROM file_history ORDER BY id DESC
+    ))?(())?;
+
+    let mut entries_per_path = HashMap::<PathBuf, usize>::default();
+    let mut pruned_paths = HashSet::default();
+    let mut oldest_kept = HashMap::<PathBuf, (i64, Option<i64>)>::default();
+    let mut total_size = 0;
+    let mut pruned_ids = Vec::new();
+    for (id, path, timestamp, base_id, size) in rows {
+        let entries = entries_per_path.entry(path.clone()).or_default();
+        *entries += 1;
+        total_size += size;
+        if pruned_paths.contains(&path)
+            || *entries > retention.max_entries_per_file
+            || timestamp < min_timestamp
+            || total_size > retention.max_total_size
+        {
+            pruned_paths.insert(path);
+            pruned_ids.push(id);
+        } else {
+            oldest_kept.insert(path, (id, base_id));
+        }
+    }
+    if pruned_ids.is_empty() {
+        return Ok(());
+    }
+
+    for path in &pruned_paths {
+        if let Some(&(id, Some(_))) = oldest_kept.get(path) {
+            let content = delta::encode_text(&snapshot_text(conn, id)?)?;
+            conn.exec_bound(sql!(
+                UPDATE file_history
+                SET base_id = NULL, content = ?, size = ?
+                WHERE id = ?
+            ))?((content.as_slice(), content.len() as u64, id))?;
+        }
+    }
+
+    let mut delete = conn.exec_bound::<i64>(sql!(DELETE FROM file_history WHERE id = ?))?;
+    for id in pruned_ids {
+        delete(id)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RETAIN_ALL: RetentionPolicy = RetentionPolicy {
+        max_entries_per_file: usize::MAX,
+        max_age: Duration::from_secs(365 * 24 * 60 * 60),
+        max_total_size: u64::MAX,
+    };
+
+    fn timestamp(seconds: i64) -> OffsetDateTime {
+        OffsetDateTime::from_unix_timestamp(1_700_000_000 + seconds).unwrap()
+    }
+
+    fn texts(db: &FileHistoryDb, path: &Path) -> Vec<String> {
+        db.snapshots(path)
+            .unwrap()
+            .into_iter()
+            .map(|snapshot| db.snapshot_text(snapshot.id).unwrap())
+            .collect()
+    }
+
+    #[gpui::test]
+    async fn test_record_and_reconstruct_snapshots() {
+        let db = FileHistoryDb::open_test_db("test_record_and_reconstruct_snapshots").await;
+        let path = PathBuf::from("/project/main.rs");
+
+        let mut expected = Vec::new();
+        for ix in 0..(KEYFRAME_INTERVAL * 2 + 3) {
+            let text = (0..=ix)
+                .map(|line| format!("line {line}\n"))
+                .collect::<String>();
+            let recorded = db
+                .record_snapshot(
+                    path.clone(),
+                    text.clone(),
+                    SnapshotSource::Save,
+                    timestamp(ix as i64),
+                    RETAIN_ALL,
+                )
+                .await
+                .unwrap();
+            assert!(recorded);
+            expected.insert(0, text);
+        }
+        assert_eq!(texts(&db, &path), expected);
+
+        // Unchanged content isn't recorded again.
+        let recorded = db
+            .record_snapshot(
+                path.clone(),
+                expected[0].clone(),
+                SnapshotSource::Reload,
+                timestamp(100),
+                RETAIN_ALL,
+            )
+            .await
+            .unwrap();
+        assert!(!recorded);
+
+        let snapshots = db.snapshots(&path).unwrap();
+        assert_eq!(snapshots.len(), expected.len());
+        assert_eq!(snapshots[0].source, SnapshotSource::Save);
+        assert_eq!(
+            snapshots[0].timestamp,
+            timestamp(KEYFRAME_INTERVAL as i64 * 2 + 2)
+        );
+        assert!(
+            db.snapshots(Path::new("/project/other.rs"))
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[gpui::test]
+    async fn test_retention() {
+        let db = FileHistoryDb::open_test_db("test_retention").await;
+        let first_path = PathBuf::from("/project/first.txt");
+        let second_path = PathBuf::from("/project/second.txt");
+        let policy = RetentionPolicy {
+            max_entries_per_file: 3,
+            max_age: Duration::from_secs(60),
+            max_total_size: u64::MAX,
+        };
+
+        for ix in 0..5 {
+            db.record_snapshot(
+                first_path.clone(),
+                format!("first {ix}\n"),
+                SnapshotSource::Save,
+                timestamp(ix),
+                policy,
+            )
+            .await
+            .unwrap();
+        }
+        // Only the newest snapshots remain, and the oldest of them no longer
+        // depends on removed ones.
+        assert_eq!(
+            texts(&db, &first_path),
+            ["first 4\n", "first 3\n", "first 2\n"]
+        );
+
+        db.record_snapshot(
+            second_path.clone(),
+            "second\n".into(),
+            SnapshotSource::Agent,
+            timestamp(63),
+            policy,
+        )
+        .await
+        .unwrap();
+        assert_eq!(texts(&db, &first_path), ["first 4\n", "first 3\n"]);
+        assert_eq!(texts(&db, &second_path), ["second\n"]);
+
+        let size_limit = RetentionPolicy {
+            max_total_size: 0,
+            ..policy
+        };
+        db.record_snapshot(
+            second_path.clone(),
+            "second, changed\n".into(),
+            SnapshotSource::Save,
+            timestamp(64),
+            size_limit,
+        )
+        .await
+        .unwrap();
+        assert!(texts(&db, &first_path).is_empty());
+        assert!(texts(&db, &second_path).is_empty());
+    }
+}
@@ -0,0 +1,369 @@
+use std::{
+    any::{Any, TypeId},
+    pin::pin,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::Result;
+use buffer_diff::BufferDiff;
+use editor::{Editor, EditorEvent, EditorSettings, MultiBuffer, SplittableEditor};
+use futures::{FutureExt as _, select_biased};
+use gpui::{
+    AnyElement, App, AsyncApp, Entity, EventEmitter, FocusHandle, Focusable, Task, WeakEntity,
+};
+use language::{Buffer, Point};
+use project::Project;
+use settings::Settings as _;
+use ui::prelude::*;
+use util::paths::PathExt as _;
+use workspace::{
+    Item, ItemNavHistory, Workspace,
+    item::{ItemEvent, SaveOptions, TabContentParams},
+    searchable::SearchableItemHandle,
+};
+
+use crate::{FileHistoryDb, RestoreSnapshot, Snapshot, SnapshotSource, record_snapshot};
+
+const RECALCULATE_DIFF_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Shows how a file has changed since one of its snapshots, and restores it.
+pub struct SnapshotDiffView {
+    diff_editor: Entity<SplittableEditor>,
+    buffer: Entity<Buffer>,
+    snapshot: Snapshot,
+    snapshot_text: Arc<str>,
+    title: SharedString,
+    path: Option<SharedString>,
+    buffer_changes_tx: watch::Sender<()>,
+    _recalculate_diff_task: Task<Result<()>>,
+}
+
+impl SnapshotDiffView {
+    pub fn open(
+        buffer: Entity<Buffer>,
+        snapshot: Snapshot,
+        time: SharedString,
+        workspace: WeakEntity<Workspace>,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Entity<Self>>> {
+        let db = FileHistoryDb::global(cx);
+        let snapshot_id = snapshot.id;
+        let snapshot_text = cx.background_spawn(async move { db.snapshot_text(snapshot_id) });
+        let diff = cx.new(|cx| BufferDiff::new(&buffer.read(cx).snapshot().text, cx));
+
+        window.spawn(cx, async move |cx| {
+            let snapshot_text = Arc::<str>::from(snapshot_text.await?);
+            update_diff(&diff, &buffer, &snapshot_text, cx).await;
+
+            workspace.update_in(cx, |workspace, window, cx| {
+                let project = workspace.project().clone();
+                let workspace_entity = cx.entity();
+                let view = cx.new(|cx| {
+                    SnapshotDiffView::new(
+                        buffer,
+                        snapshot,
+                        time,
+                        snapshot_text,
+                        diff,
+                        project,
+                        workspace_entity,
+                        window,
+                        cx,
+                    )
+                });
+                workspace.active_pane().update(cx, |pane, cx| {
+                    pane.add_item(Box::new(view.clone()), true, true, None, window, cx);
+                });
+                view
+            })
+        })
+    }
+
+    fn new(
+        buffer: Entity<Buffer>,
+        snapshot: Snapshot,
+        time: SharedString,
+        snapshot_text: Arc<str>,
+        diff: Entity<BufferDiff>,
+        project: Entity<Project>,
+        workspace: Entity<Workspace>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let multibuffer = cx.new(|cx| {
+            let mut multibuffer = MultiBuffer::new(language::Capability::ReadWrite);
+            let max_point = buffer.read(cx).max_point();
+            multibuffer.set_excerpts_for_buffer(buffer.clone(), [Point::zero()..max_point], 0, cx);
+            multibuffer.add_diff(diff.clone(), cx);
+            multibuffer
+        });
+        let diff_editor = cx.new(|cx| {
+            let splittable = SplittableEditor::new(
+                EditorSettings::get_global(cx).diff_view_style,
+                multibuffer,
+                project,
+                workspace,
+                window,
+                cx,
+            );
+            splittable.set_render_diff_hunk_controls(
+                Arc::new(|_, _, _, _, _, _, _, _| gpui::Empty.into_any_element()),
+                cx,
+            );
+            splittable.rhs_editor().update(cx, |editor, cx| {
+                editor.start_temporary_diff_override();
+                editor.disable_diagnostics(cx);
+                editor.set_expand_all_diff_hunks(cx);
+            });
+            splittable
+        });
+
+        let (buffer_changes_tx, mut buffer_changes_rx) = watch::channel(());
+        cx.subscribe(&buffer, |this: &mut Self, _, event, _| match event {
+            language::BufferEvent::Edited { .. } | language::BufferEvent::LanguageChanged(_) => {
+                this.buffer_changes_tx.send(()).ok();
+            }
+            _ => {}
+        })
+        .detach();
+
+        let file_name = buffer
+            .read(cx)
+            .file()
+            .map(|file| file.file_name(cx).to_string())
+            .unwrap_or_else(|| "untitled".into());
+        let path = buffer
+            .read(cx)
+            .file()
+            .map(|file| file.full_path(cx).compact().to_string_lossy().into_owned());
+
+        let recalculate_diff_task = cx.spawn({
+            let buffer = buffer.clone();
+            let snapshot_text = snapshot_text.clone();
+            async move |_, cx| {
+                while buffer_changes_rx.recv().await.is_ok() {
+                    loop {
+                        let mut timer = cx
+                            .background_executor()
+                            .timer(RECALCULATE_DIFF_DEBOUNCE)
+                            .fuse();
+                        let mut recv = pin!(buffer_changes_rx.recv().fuse());
+                        select_biased! {
+                            _ = timer => break,
+                            _ = recv => continue,
+                        }
+                    }
+                    update_diff(&diff, &buffer, &snapshot_text, cx).await;
+                }
+                Ok(())
+            }
+        });
+
+        Self {
+            diff_editor,
+            buffer,
+            title: format!("{file_name} ({time})").into(),
+            path: path.map(|path| format!("{path} ({time})").into()),
+            snapshot,
+            snapshot_text,
+            buffer_changes_tx,
+            _recalculate_diff_task: recalculate_diff_task,
+        }
+    }
+
+    fn restore(&mut self, _: &RestoreSnapshot, window: &mut Window, cx: &mut Context<Self>) {
+        record_snapshot(&self.buffer, SnapshotSource::Restore, cx);
+        let buffer = self.buffer.clone();
+        let diff = buffer.read(cx).diff(self.snapshot_text.clone(), cx);
+        cx.spawn_in(window, async move |_, cx| {
+            let diff = diff.await;
+            buffer.update(cx, |buffer, cx| buffer.apply_diff(diff, cx));
+        })
+        .detach();
+    }
+}
+
+async fn update_diff(
+    diff: &Entity<BufferDiff>,
+    buffer: &Entity<Buffer>,
+    snapshot_text: &Arc<str>,
+    cx: &mut AsyncApp,
+) {
+    let buffer_snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot());
+    let language = buffer_snapshot.language().cloned();
+    let language_registry = buffer.read_with(cx, |buffer, _| buffer.language_registry());
+
+    let update = diff
+        .update(cx, |diff, cx| {
+            diff.update_diff(
+                buffer_snapshot.text.clone(),
+                Some(snapshot_text.clone()),
+                Some(true),
+                language.clone(),
+                cx,
+            )
+        })
+        .await;
+
+    diff.update(cx, |diff, cx| {
+        diff.language_changed(language, language_registry, cx);
+        diff.set_snapshot(update, &buffer_snapshot.text, cx)
+    })
+    .await;
+}
+
+impl EventEmitter<EditorEvent> for SnapshotDiffView {}
+
+impl Focusable for SnapshotDiffView {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.diff_editor.focus_handle(cx)
+    }
+}
+
+impl Item for SnapshotDiffView {
+    type Event = EditorEvent;
+
+    fn tab_icon(&self, _window: &Window, _cx: &App) -> Option<Icon> {
+        Some(Icon::new(IconName::HistoryRerun).color(Color::Muted))
+    }
+
+    fn tab_content(&self, params: TabContentParams, _window: &Window, cx: &App) -> AnyElement {
+        Label::new(self.tab_content_text(params.detail.unwrap_or_default(), cx))
+            .color(if params.selected {
+                Color::Default
+            } else {
+                Color::Muted
+            })
+            .into_any_element()
+    }
+
+    fn tab_content_text(&self, _detail: usize, _: &App) -> SharedString {
+        self.title.clone()
+    }
+
+    fn tab_tooltip_text(&self, _: &App) -> Option<SharedString> {
+        self.path.clone()
+    }
+
+    fn to_item_events(event: &EditorEvent, f: &mut dyn FnMut(ItemEvent)) {
+        Editor::to_item_events(event, f)
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("File History Snapshot Opened")
+    }
+
+    fn deactivated(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.diff_editor
+            .update(cx, |editor, cx| editor.deactivated(window, cx));
+    }
+
+    fn act_as_type<'a>(
+        &'a self,
+        type_id: TypeId,
+        self_handle: &'a Entity<Self>,
+        cx: &'a App,
+    ) -> Option<gpui::AnyEntity> {
+        if type_id == TypeId::of::<Self>() {
+            Some(self_handle.clone().into())
+        } else if type_id == TypeId::of::<SplittableEditor>() {
+            Some(self.diff_editor.clone().into())
+        } else if type_id == TypeId::of::<Editor>() {
+            Some(self.diff_editor.read(cx).rhs_editor().clone().into())
+        } else {
+            None
+        }
+    }
+
+    fn as_searchable(&self, _: &Entity<Self>, _: &App) -> Option<Box<dyn SearchableItemHandle>> {
+        Some(Box::new(self.diff_editor.clone()))
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &App,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::ProjectItem),
+    ) {
+        self.diff_editor.read(cx).for_each_project_item(cx, f)
+    }
+
+    fn set_nav_history(
+        &mut self,
+        nav_history: ItemNavHistory,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let rhs = self.diff_editor.read(cx).rhs_editor().clone();
+        rhs.update(cx, |editor, _| {
+            editor.set_nav_history(Some(nav_history));
+        });
+    }
+
+    fn navigate(
+        &mut self,
+        data: Arc<dyn Any + Send>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        self.diff_editor
+            .update(cx, |editor, cx| editor.navigate(data, window, cx))
+    }
+
+    fn added_to_workspace(
+        &mut self,
+        workspace: &mut Workspace,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.diff_editor.update(cx, |editor, cx| {
+            editor.added_to_workspace(workspace, window, cx)
+        });
+    }
+
+    fn can_save(&self, cx: &App) -> bool {
+        self.diff_editor.read(cx).can_save(cx)
+    }
+
+    fn save(
+        &mut self,
+        options: SaveOptions,
+        project: Entity<Project>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        self.diff_editor
+            .update(cx, |editor, cx| editor.save(options, project, window, cx))
+    }
+}
+
+impl Render for SnapshotDiffView {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("SnapshotDiffView")
+            .on_action(cx.listener(Self::restore))
+            .size_full()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .px_2()
+                    .py_1()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .child(
+                        Label::new(self.snapshot.source.label())
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Button::new("restore-snapshot", "Restore")
+                            .start_icon(Icon::new(IconName::HistoryRerun).size(IconSize::Small))
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.restore(&RestoreSnapshot, window, cx)
+                            })),
+                    ),
+            )
+            .child(self.diff_editor.clone())
+    }
+}
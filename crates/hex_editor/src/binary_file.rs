@@ -0,0 +1,473 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Seek as _, SeekFrom, Write as _},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use fs::{Fs, MTime};
+use futures::StreamExt as _;
+use gpui::{App, AppContext as _, Context, Entity, EventEmitter, Task};
+use project::{Project, ProjectEntryId, ProjectPath};
+use settings::Settings as _;
+use util::ResultExt as _;
+
+use crate::HexEditorSettings;
+
+/// How long to wait after the file changes on disk before checking it.
+const WATCH_LATENCY: Duration = Duration::from_millis(100);
+
+pub enum BinaryFileEvent {
+    Edited,
+    Saved,
+    Reloaded,
+    /// The file changed on disk, which may have made it conflict with the
+    /// bytes overwritten in the editor.
+    DiskStateChanged,
+}
+
+/// The contents of a [`BinaryFile`] at some point in time, which can be read
+/// from the background.
+#[derive(Clone, Default)]
+pub struct BinarySnapshot {
+    /// `None` for a default, empty snapshot.
+    file: Option<Arc<std::fs::File>>,
+    /// The length of the file as of when it was opened.
+    len: usize,
+    /// Bytes that were overwritten but not saved, by offset.
+    edits: Arc<BTreeMap<usize, u8>>,
+}
+
+impl BinarySnapshot {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn byte(&self, offset: usize) -> Option<u8> {
+        self.edits
+            .get(&offset)
+            .copied()
+            .or_else(|| self.byte_on_disk(offset))
+    }
+
+    /// Returns the bytes in the given range, which is clipped to the file.
+    ///
+    /// The bytes are read from the file rather than memory-mapped, so if
+    /// another process truncates it, fewer bytes are returned instead of the
+    /// read faulting.
+    pub fn read(&self, range: Range<usize>) -> Vec<u8> {
+        let Some(file) = self.file.as_ref() else {
+            return Vec::new();
+        };
+        let range = range.start.min(self.len)..range.end.min(self.len);
+        let mut bytes = vec![0; range.len()];
+        let read = read_full(file, &mut bytes, range.start)
+            .log_err()
+            .unwrap_or_default();
+        bytes.truncate(read);
+        for (offset, byte) in self.edits.range(range.start..range.start + read) {
+            bytes[offset - range.start] = *byte;
+        }
+        bytes
+    }
+
+    pub fn is_edited(&self, offset: usize) -> bool {
+        self.edits.contains_key(&offset)
+    }
+
+    fn byte_on_disk(&self, offset: usize) -> Option<u8> {
+        if offset >= self.len {
+            return None;
+        }
+        let mut byte = [0];
+        let read = read_full(self.file.as_ref()?, &mut byte, offset).log_err()?;
+        (read == 1).then_some(byte[0])
+    }
+
+    #[cfg(test)]
+    pub(crate) fn for_file(path: &Path) -> Self {
+        let (file, len) = open_file(path).unwrap();
+        Self {
+            file: Some(file),
+            len,
+            edits: Arc::default(),
+        }
+    }
+}
+
+/// The modification time and length of the file when it was last read or
+/// written, to tell whether another process has changed it since.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct DiskState {
+    mtime: MTime,
+    len: u64,
+}
+
+async fn disk_state(fs: &dyn Fs, path: &Path) -> Result<Option<DiskState>> {
+    Ok(fs.metadata(path).await?.map(|metadata| DiskState {
+        mtime: metadata.mtime,
+        len: metadata.len,
+    }))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct ByteEdit {
+    offset: usize,
+    old: u8,
+    new: u8,
+}
+
+/// A local file opened for viewing and overwriting as bytes.
+///
+/// Only the parts of the file that are viewed or searched are read. Edits
+/// overwrite bytes in place and are kept in memory until the file is saved.
+/// Like a buffer, the file is watched so that saving over changes made by
+/// another process asks the user first, and it's reloaded when it changes
+/// and there are no edits.
+pub struct BinaryFile {
+    project_path: ProjectPath,
+    abs_path: PathBuf,
+    entry_id: Option<ProjectEntryId>,
+    fs: Arc<dyn Fs>,
+    snapshot: BinarySnapshot,
+    /// The state of the file when it was last read or written.
+    saved_disk_state: Option<DiskState>,
+    /// The state of the file as of when it last changed on disk.
+    disk_state: Option<DiskState>,
+    undo_stack: Vec<Vec<ByteEdit>>,
+    redo_stack: Vec<Vec<ByteEdit>>,
+    _watch: Task<()>,
+}
+
+impl EventEmitter<BinaryFileEvent> for BinaryFile {}
+
+/// Whether the file at the given path should be opened in the hex editor
+/// rather than an editor, based on its extension.
+pub fn is_binary_file(project: &Entity<Project>, path: &ProjectPath, cx: &App) -> bool {
+    let Some(extension) = path.path.extension() else {
+        return false;
+    };
+    project.read(cx).is_local()
+        && HexEditorSettings::get_global(cx)
+            .file_types
+            .iter()
+            .any(|file_type| file_type.eq_ignore_ascii_case(extension))
+}
+
+impl BinaryFile {
+    pub fn open(
+        project: &Entity<Project>,
+        project_path: ProjectPath,
+        cx: &mut App,
+    ) -> Task<Result<Entity<Self>>> {
+        let project = project.read(cx);
+        if !project.is_local() {
+            return Task::ready(Err(anyhow::anyhow!(
+                "The hex editor is only available in local projects"
+            )));
+        }
+        let entry_id = project
+            .entry_for_path(&project_path, cx)
+            .map(|entry| entry.id);
+        let Some(abs_path) = project.absolute_path(&project_path, cx) else {
+            return Task::ready(Err(anyhow::anyhow!(
+                "{:?} is not in the project",
+                project_path.path
+            )));
+        };
+        let fs = project.fs().clone();
+        cx.spawn(async move |cx| {
+            let saved_disk_state = disk_state(fs.as_ref(), &abs_path).await?;
+            let (file, len) = cx
+                .background_spawn({
+                    let abs_path = abs_path.clone();
+                    async move { open_file(&abs_path) }
+                })
+                .await?;
+            Ok(cx.new(|cx| {
+                let watch = Self::watch(fs.clone(), abs_path.clone(), cx);
+                Self {
+                    project_path,
+                    abs_path,
+                    entry_id,
+                    fs,
+                    snapshot: BinarySnapshot {
+                        file: Some(file),
+                        len,
+                        edits: Arc::default(),
+                    },
+                    saved_disk_state,
+                    disk_state: saved_disk_state,
+                    undo_stack: Vec::new(),
+                    redo_stack: Vec::new(),
+                    _watch: watch,
+                }
+            }))
+        })
+    }
+
+    /// Keeps track of the state of the file on disk, reloading the file when
+    /// it changes and there are no edits to lose.
+    fn watch(fs: Arc<dyn Fs>, abs_path: PathBuf, cx: &mut Context<Self>) -> Task<()> {
+        cx.spawn(async move |this, cx| {
+            let (mut events, _watcher) = fs.watch(&abs_path, WATCH_LATENCY).await;
+            while events.next().await.is_some() {
+                let Some(disk_state) = disk_state(fs.as_ref(), &abs_path).await.log_err() else {
+                    continue;
+                };
+                let updated = this.update(cx, |this, cx| {
+                    if disk_state == this.disk_state {
+                        return;
+                    }
+                    this.disk_state = disk_state;
+                    if disk_state.is_some() && !this.is_dirty() {
+                        this.reload(cx).detach_and_log_err(cx);
+                    }
+                    cx.emit(BinaryFileEvent::DiskStateChanged);
+                });
+                if updated.is_err() {
+                    break;
+                }
+            }
+        })
+    }
+
+    pub fn abs_path(&self) -> &Path {
+        &self.abs_path
+    }
+
+    pub fn snapshot(&self) -> &BinarySnapshot {
+        &self.snapshot
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        !self.snapshot.edits.is_empty()
+    }
+
+    /// Whether there are edits and the file has changed on disk since it was
+    /// read, so saving would overwrite another process's changes.
+    pub fn has_conflict(&self) -> bool {
+        self.is_dirty() && self.disk_state != self.saved_disk_state
+    }
+
+    /// Overwrites the bytes starting at `offset`, as one undoable edit. Bytes
+    /// past the end of the file are ignored, as the file can't grow.
+    pub fn overwrite(&mut self, offset: usize, bytes: &[u8], cx: &mut Context<Self>) {
+        let transaction = bytes
+            .iter()
+            .enumerate()
+            .filter_map(|(ix, new)| {
+                let offset = offset + ix;
+                let old = self.snapshot.byte(offset)?;
+                Some(ByteEdit {
+                    offset,
+                    old,
+                    new: *new,
+                })
+            })
+            .filter(|edit| edit.old != edit.new)
+            .collect::<Vec<_>>();
+        if transaction.is_empty() {
+            return;
+        }
+        for edit in &transaction {
+            self.set_byte(edit.offset, edit.new);
+        }
+        self.undo_stack.push(transaction);
+        self.redo_stack.clear();
+        cx.emit(BinaryFileEvent::Edited);
+    }
+
+    /// Reverts the last edit, returning the offset of its first byte.
+    pub fn undo(&mut self, cx: &mut Context<Self>) -> Option<usize> {
+        let transaction = self.undo_stack.pop()?;
+        for edit in transaction.iter().rev() {
+            self.set_byte(edit.offset, edit.old);
+        }
+        let offset = transaction.first().map(|edit| edit.offset);
+        self.redo_stack.push(transaction);
+        cx.emit(BinaryFileEvent::Edited);
+        offset
+    }
+
+    /// Reapplies the last undone edit, returning the offset of its first byte.
+    pub fn redo(&mut self, cx: &mut Context<Self>) -> Option<usize> {
+        let transaction = self.redo_stack.pop()?;
+        for edit in &transaction {
+            self.set_byte(edit.offset, edit.new);
+        }
+        let offset = transaction.first().map(|edit| edit.offset);
+        self.undo_stack.push(transaction);
+        cx.emit(BinaryFileEvent::Edited);
+        offset
+    }
+
+    fn set_byte(&mut self, offset: usize, byte: u8) {
+        let on_disk = self.snapshot.byte_on_disk(offset);
+        let edits = Arc::make_mut(&mut self.snapshot.edits);
+        if on_disk == Some(byte) {
+            edits.remove(&offset);
+        } else {
+            edits.insert(offset, byte);
+        }
+    }
+
+    /// Writes the overwritten bytes to the file in place.
+    ///
+    /// The workspace asks the user before saving while [`Self::has_conflict`]
+    /// is true, so this writes even if the file has changed on disk.
+    pub fn save(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let edits = self.snapshot.edits.clone();
+        let abs_path = self.abs_path.clone();
+        let fs = self.fs.clone();
+        cx.spawn(async move |this, cx| {
+            let (file, len) = cx
+                .background_spawn({
+                    let abs_path = abs_path.clone();
+                    async move {
+                        write_edits(&abs_path, &edits)?;
+                        open_file(&abs_path)
+                    }
+                })
+                .await?;
+            let disk_state = disk_state(fs.as_ref(), &abs_path).await?;
+            this.update(cx, |this, cx| {
+                this.snapshot = BinarySnapshot {
+                    file: Some(file),
+                    len,
+                    edits: Arc::default(),
+                };
+                this.saved_disk_state = disk_state;
+                this.disk_state = disk_state;
+                cx.emit(BinaryFileEvent::Saved);
+            })
+        })
+    }
+
+    /// Discards the overwritten bytes and opens the file again.
+    pub fn reload(&mut self, cx: &mut Context<Self>) -> Task<Result<()>> {
+        let abs_path = self.abs_path.clone();
+        let fs = self.fs.clone();
+        cx.spawn(async move |this, cx| {
+            let disk_state = disk_state(fs.as_ref(), &abs_path).await?;
+            let (file, len) = cx
+                .background_spawn(async move { open_file(&abs_path) })
+                .await?;
+            this.update(cx, |this, cx| {
+                this.snapshot = BinarySnapshot {
+                    file: Some(file),
+                    len,
+                    edits: Arc::default(),
+                };
+                this.saved_disk_state = disk_state;
+                this.disk_state = disk_state;
+                this.undo_stack.clear();
+                this.redo_stack.clear();
+                cx.emit(BinaryFileEvent::Reloaded);
+            })
+        })
+    }
+}
+
+fn open_file(path: &Path) -> Result<(Arc<std::fs::File>, usize)> {
+    let file = std::fs::File::open(path).with_context(|| format!("opening {path:?}"))?;
+    let len = usize::try_from(file.metadata()?.len()).context("file is too large")?;
+    Ok((Arc::new(file), len))
+}
+
+/// Fills as much of `buf` as possible with the bytes at `offset`, returning
+/// how many were read, which is fewer than requested at the end of the file.
+fn read_full(file: &std::fs::File, buf: &mut [u8], offset: usize) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        let position = (offset + read) as u64;
+        #[cfg(unix)]
+        let result = std::os::unix::fs::FileExt::read_at(file, &mut buf[read..], position);
+        #[cfg(windows)]
+        let result = std::os::windows::fs::FileExt::seek_read(file, &mut buf[read..], position);
+        match result {
+            Ok(0) => break,
+            Ok(len) => read += len,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(read)
+}
+
+/// Writes each run of consecutive edited bytes at its offset in the file.
+fn write_edits(path: &Path, edits: &BTreeMap<usize, u8>) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .with_context(|| format!("opening {path:?} for writing"))?;
+    let mut edits = edits.iter().peekable();
+    while let Some((&start, &byte)) = edits.next() {
+        let mut run = vec![byte];
+        while let Some((&offset, &byte)) = edits.peek() {
+            if offset != start + run.len() {
+                break;
+            }
+            run.push(byte);
+            edits.next();
+        }
+        file.seek(SeekFrom::Start(start as u64))?;
+        file.write_all(&run)?;
+    }
+    file.flush()?;
+    Ok(())
+}
+
+impl project::ProjectItem for BinaryFile {
+    fn try_open(
+        project: &Entity<Project>,
+        path: &ProjectPath,
+        cx: &mut App,
+    ) -> Option<Task<Result<Entity<Self>>>> {
+        is_binary_file(project, path, cx).then(|| Self::open(project, path.clone(), cx))
+    }
+
+    fn entry_id(&self, _: &App) -> Option<ProjectEntryId> {
+        self.entry_id
+    }
+
+    fn project_path(&self, _: &App) -> Option<ProjectPath> {
+        Some(self.project_path.clone())
+    }
+
+    fn is_dirty(&self) -> bool {
+        BinaryFile::is_dirty(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::*;
+
+    #[test]
+    fn test_read_after_truncation() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(&[1, 2, 3, 4, 5, 6]).unwrap();
+        let mut snapshot = BinarySnapshot::for_file(file.path());
+        snapshot.edits = Arc::new(BTreeMap::from_iter([(1, 0xaa), (4, 0xbb)]));
+        assert_eq!(snapshot.read(0..10), [1, 0xaa, 3, 4, 0xbb, 6]);
+
+        // Reads past the new end return fewer bytes rather than faulting.
+        file.as_file().set_len(3).unwrap();
+        assert_eq!(snapshot.len(), 6);
+        assert_eq!(snapshot.read(0..6), [1, 0xaa, 3]);
+        assert_eq!(snapshot.read(4..6), Vec::<u8>::new());
+        assert_eq!(snapshot.byte(2), Some(3));
+        assert_eq!(snapshot.byte(5), None);
+        assert_eq!(snapshot.byte(4), Some(0xbb));
+    }
+}
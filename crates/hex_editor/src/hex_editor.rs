@@ -0,0 +1,874 @@
+//! A hex editor for binary files.
+//!
+//! Files are shown as rows of offsets, hex bytes, and their ASCII text, and
+//! can be edited by overwriting bytes. Only the rows in view are read, so even
+//! very large files open instantly.
+
+mod binary_file;
+mod interpretation;
+mod search;
+
+use std::{cell::Cell, ops::Range, rc::Rc};
+
+use editor::Editor;
+use file_icons::FileIcons;
+use gpui::{
+    AnyElement, App, Bounds, Context, Entity, EventEmitter, FocusHandle, Focusable, KeyDownEvent,
+    MouseButton, MouseDownEvent, Pixels, ScrollWheelEvent, Subscription, Task, Window, actions,
+    canvas,
+};
+use project::Project;
+use settings::{RegisterSetting, Settings};
+use theme::ThemeSettings;
+use ui::{Tooltip, prelude::*};
+use util::{ResultExt as _, paths::PathExt as _};
+use workspace::{
+    ItemSettings, Pane, SaveIntent, Workspace,
+    invalid_item_view::InvalidItemView,
+    item::{Item, ItemEvent, ProjectItem, SaveOptions, TabContentParams},
+    notifications::DetachAndPromptErr as _,
+};
+use zed_actions::hex_editor::OpenInHexEditor;
+
+pub use crate::binary_file::{BinaryFile, BinaryFileEvent, BinarySnapshot, is_binary_file};
+use crate::{interpretation::interpret, search::HexPattern};
+
+actions!(
+    hex_editor,
+    [
+        /// Moves the cursor one byte left.
+        MoveLeft,
+        /// Moves the cursor one byte right.
+        MoveRight,
+        /// Moves the cursor one row up.
+        MoveUp,
+        /// Moves the cursor one row down.
+        MoveDown,
+        /// Extends the selection one byte left.
+        SelectLeft,
+        /// Extends the selection one byte right.
+        SelectRight,
+        /// Extends the selection one row up.
+        SelectUp,
+        /// Extends the selection one row down.
+        SelectDown,
+        /// Moves the cursor one page up.
+        PageUp,
+        /// Moves the cursor one page down.
+        PageDown,
+        /// Moves the cursor to the first byte.
+        MoveToStart,
+        /// Moves the cursor to the last byte.
+        MoveToEnd,
+        /// Switches typing between the hex and text columns.
+        SwitchColumn,
+        /// Undoes the last edit.
+        Undo,
+        /// Redoes the last undone edit.
+        Redo,
+        /// Focuses the hex pattern search field.
+        FocusSearch,
+        /// Selects the next match of the hex pattern.
+        FindNext,
+        /// Selects the previous match of the hex pattern.
+        FindPrevious,
+    ]
+);
+
+const BYTES_PER_ROW: usize = 16;
+/// How many bytes at the cursor are interpreted as numbers.
+const INTERPRETED_LEN: usize = 8;
+
+#[derive(Clone, Debug, RegisterSetting)]
+pub struct HexEditorSettings {
+    pub file_types: Vec<String>,
+}
+
+impl Settings for HexEditorSettings {
+    fn from_settings(content: &settings::SettingsContent) -> Self {
+        Self {
+            file_types: content.hex_editor.clone().unwrap().file_types.unwrap(),
+        }
+    }
+}
+
+pub fn init(cx: &mut App) {
+    workspace::register_project_item::<HexEditor>(cx);
+    cx.observe_new(|workspace: &mut Workspace, _, _| {
+        workspace.register_action(|workspace, _: &OpenInHexEditor, window, cx| {
+            open_in_hex_editor(workspace, window, cx);
+        });
+    })
+    .detach();
+}
+
+/// Opens the active item's file in a hex editor. When the active item is a
+/// file that failed to open, such as a binary file, it's replaced.
+fn open_in_hex_editor(workspace: &mut Workspace, window: &mut Window, cx: &mut Context<Workspace>) {
+    let Some(item) = workspace.active_item(cx) else {
+        return;
+    };
+    let project = workspace.project().clone();
+    let invalid_item = item.downcast::<InvalidItemView>();
+    let project_path = match &invalid_item {
+        Some(invalid_item) => project
+            .read(cx)
+            .find_project_path(&invalid_item.read(cx).abs_path, cx),
+        None => item.project_path(cx),
+    };
+    let Some(project_path) = project_path else {
+        return;
+    };
+
+    let open = BinaryFile::open(&project, project_path, cx);
+    let pane = workspace.active_pane().clone();
+    cx.spawn_in(window, async move |_, cx| {
+        let file = open.await?;
+        pane.update_in(cx, |pane, window, cx| {
+            let hex_editor = cx.new(|cx| HexEditor::new(file, window, cx));
+            pane.add_item(Box::new(hex_editor), true, true, None, window, cx);
+            if let Some(invalid_item) = invalid_item {
+                pane.close_item_by_id(invalid_item.entity_id(), SaveIntent::Skip, window, cx)
+                    .detach_and_log_err(cx);
+            }
+        })
+    })
+    .detach_and_prompt_err("Failed to open the hex editor", window, cx, |_, _, _| None);
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Hex,
+    Text,
+}
+
+pub enum HexEditorEvent {
+    Edited,
+    TitleChanged,
+}
+
+pub struct HexEditor {
+    file: Entity<BinaryFile>,
+    focus_handle: FocusHandle,
+    cursor: usize,
+    /// The other end of the selection from the cursor, if bytes are selected.
+    selection_tail: Option<usize>,
+    /// Whether the high nibble of the byte at the cursor was just typed.
+    high_nibble_typed: bool,
+    column: Column,
+    /// The row at the top of the view, which can be fractional while scrolling
+    /// by pixels.
+    scroll_row: f64,
+    last_bounds: Rc<Cell<Option<Bounds<Pixels>>>>,
+    search_editor: Entity<Editor>,
+    search_status: Option<SharedString>,
+    search_task: Task<()>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl HexEditor {
+    pub fn new(file: Entity<BinaryFile>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let search_editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Find hex bytes, like DE AD ?? EF", window, cx);
+            editor
+        });
+        let subscriptions = vec![
+            cx.subscribe(&file, |_, _, event, cx| {
+                match event {
+                    BinaryFileEvent::Edited => cx.emit(HexEditorEvent::Edited),
+                    BinaryFileEvent::Saved
+                    | BinaryFileEvent::Reloaded
+                    | BinaryFileEvent::DiskStateChanged => cx.emit(HexEditorEvent::TitleChanged),
+                }
+                cx.notify();
+            }),
+            cx.subscribe(
+                &search_editor,
+                |this, _, event: &editor::EditorEvent, cx| {
+                    if let editor::EditorEvent::BufferEdited = event {
+                        this.search_status = None;
+                        cx.notify();
+                    }
+                },
+            ),
+        ];
+        Self {
+            file,
+            focus_handle: cx.focus_handle(),
+            cursor: 0,
+            selection_tail: None,
+            high_nibble_typed: false,
+            column: Column::Hex,
+            scroll_row: 0.,
+            last_bounds: Rc::default(),
+            search_editor,
+            search_status: None,
+            search_task: Task::ready(()),
+            _subscriptions: subscriptions,
+        }
+    }
+
+    fn len(&self, cx: &App) -> usize {
+        self.file.read(cx).snapshot().len()
+    }
+
+    /// The selected bytes, or the byte at the cursor if none are selected.
+    fn selection(&self) -> Range<usize> {
+        match self.selection_tail {
+            Some(tail) => tail.min(self.cursor)..tail.max(self.cursor) + 1,
+            None => self.cursor..self.cursor + 1,
+        }
+    }
+
+    fn row_count(&self, cx: &App) -> usize {
+        self.len(cx).div_ceil(BYTES_PER_ROW).max(1)
+    }
+
+    fn line_height(cx: &App) -> Pixels {
+        let settings = ThemeSettings::get_global(cx);
+        settings.buffer_font_size(cx) * settings.line_height()
+    }
+
+    fn page_rows(&self, cx: &App) -> usize {
+        let height = self
+            .last_bounds
+            .get()
+            .map_or(Pixels::ZERO, |bounds| bounds.size.height);
+        (f32::from(height / Self::line_height(cx)).floor() as usize).max(1)
+    }
+
+    fn scroll_to(&mut self, row: f64, cx: &mut Context<Self>) {
+        let max_row = self.row_count(cx).saturating_sub(self.page_rows(cx)) as f64;
+        self.scroll_row = row.clamp(0., max_row);
+        cx.notify();
+    }
+
+    fn autoscroll(&mut self, cx: &mut Context<Self>) {
+        let cursor_row = (self.cursor / BYTES_PER_ROW) as f64;
+        let page_rows = self.page_rows(cx) as f64;
+        if cursor_row < self.scroll_row {
+            self.scroll_to(cursor_row, cx);
+        } else if cursor_row >= self.scroll_row + page_rows {
+            self.scroll_to(cursor_row - page_rows + 1., cx);
+        }
+    }
+
+    fn move_cursor(&mut self, delta: isize, select: bool, cx: &mut Context<Self>) {
+        let len = self.len(cx);
+        if len == 0 {
+            return;
+        }
+        if select {
+            self.selection_tail.get_or_insert(self.cursor);
+        } else {
+            self.selection_tail = None;
+        }
+        self.cursor = self.cursor.saturating_add_signed(delta).min(len - 1);
+        self.high_nibble_typed = false;
+        self.autoscroll(cx);
+        cx.notify();
+    }
+
+    fn select_range(&mut self, range: Range<usize>, cx: &mut Context<Self>) {
+        self.selection_tail = (range.len() > 1).then_some(range.start);
+        self.cursor = range.end.saturating_sub(1).max(range.start);
+        self.high_nibble_typed = false;
+        self.autoscroll(cx);
+        cx.notify();
+    }
+
+    fn move_left(&mut self, _: &MoveLeft, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor(-1, false, cx);
+    }
+
+    fn move_right(&mut self, _: &MoveRight, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor(1, false, cx);
+    }
+
+    fn move_up(&mut self, _: &MoveUp, _: &mut Window, cx: &mut Context<Self>) {
+        if self.cursor >= BYTES_PER_ROW {
+            self.move_cursor(-(BYTES_PER_ROW as isize), false, cx);
+        }
+    }
+
+    fn move_down(&mut self, _: &MoveDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor(BYTES_PER_ROW as isize, false, cx);
+    }
+
+    fn select_left(&mut self, _: &SelectLeft, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor(-1, true, cx);
+    }
+
+    fn select_right(&mut self, _: &SelectRight, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor(1, true, cx);
+    }
+
+    fn select_up(&mut self, _: &SelectUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor(-(BYTES_PER_ROW as isize), true, cx);
+    }
+
+    fn select_down(&mut self, _: &SelectDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor(BYTES_PER_ROW as isize, true, cx);
+    }
+
+    fn page_up(&mut self, _: &PageUp, _: &mut Window, cx: &mut Context<Self>) {
+        let delta = self.page_rows(cx) * BYTES_PER_ROW;
+        self.move_cursor(-(delta as isize), false, cx);
+    }
+
+    fn page_down(&mut self, _: &PageDown, _: &mut Window, cx: &mut Context<Self>) {
+        let delta = self.page_rows(cx) * BYTES_PER_ROW;
+        self.move_cursor(delta as isize, false, cx);
+    }
+
+    fn move_to_start(&mut self, _: &MoveToStart, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor(isize::MIN, false, cx);
+    }
+
+    fn move_to_end(&mut self, _: &MoveToEnd, _: &mut Window, cx: &mut Context<Self>) {
+        self.move_cursor(isize::MAX, false, cx);
+    }
+
+    fn switch_column(&mut self, _: &SwitchColumn, _: &mut Window, cx: &mut Context<Self>) {
+        self.column = match self.column {
+            Column::Hex => Column::Text,
+            Column::Text => Column::Hex,
+        };
+        self.high_nibble_typed = false;
+        cx.notify();
+    }
+
+    fn undo(&mut self, _: &Undo, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(offset) = self.file.update(cx, |file, cx| file.undo(cx)) {
+            self.select_range(offset..offset + 1, cx);
+        }
+    }
+
+    fn redo(&mut self, _: &Redo, _: &mut Window, cx: &mut Context<Self>) {
+        if let Some(offset) = self.file.update(cx, |file, cx| file.redo(cx)) {
+            self.select_range(offset..offset + 1, cx);
+        }
+    }
+
+    fn handle_key_down(
+        &mut self,
+        event: &KeyDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if !self.focus_handle.is_focused(window) {
+            return;
+        }
+        let modifiers = &event.keystroke.modifiers;
+        if modifiers.control || modifiers.alt || modifiers.platform || modifiers.function {
+            return;
+        }
+        let Some(key_char) = event.keystroke.key_char.as_deref() else {
+            return;
+        };
+        let mut chars = key_char.chars();
+        let (Some(char), None) = (chars.next(), chars.next()) else {
+            return;
+        };
+
+        let Some(byte) = self.file.read(cx).snapshot().byte(self.cursor) else {
+            return;
+        };
+        let new_byte = match self.column {
+            Column::Hex => {
+                let Some(digit) = char.to_digit(16) else {
+                    return;
+                };
+                let digit = digit as u8;
+                if self.high_nibble_typed {
+                    (byte & 0xf0) | digit
+                } else {
+                    (digit << 4) | (byte & 0x0f)
+                }
+            }
+            Column::Text => {
+                if !char.is_ascii() || char.is_ascii_control() {
+                    return;
+                }
+                char as u8
+            }
+        };
+        cx.stop_propagation();
+
+        let offset = self.cursor;
+        self.file
+            .update(cx, |file, cx| file.overwrite(offset, &[new_byte], cx));
+        self.selection_tail = None;
+        if self.column == Column::Hex && !self.high_nibble_typed {
+            self.high_nibble_typed = true;
+            cx.notify();
+        } else {
+            self.move_cursor(1, false, cx);
+        }
+    }
+
+    fn handle_mouse_down(
+        &mut self,
+        offset: usize,
+        column: Column,
+        event: &MouseDownEvent,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        window.focus(&self.focus_handle, cx);
+        if event.modifiers.shift {
+            self.selection_tail.get_or_insert(self.cursor);
+        } else {
+            self.selection_tail = None;
+        }
+        self.cursor = offset;
+        self.column = column;
+        self.high_nibble_typed = false;
+        cx.notify();
+    }
+
+    fn handle_scroll_wheel(
+        &mut self,
+        event: &ScrollWheelEvent,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let line_height = Self::line_height(cx);
+        let delta = event.delta.pixel_delta(line_height);
+        self.scroll_to(self.scroll_row - f64::from(delta.y / line_height), cx);
+    }
+
+    fn focus_search(&mut self, _: &FocusSearch, window: &mut Window, cx: &mut Context<Self>) {
+        self.search_editor.update(cx, |editor, cx| {
+            editor.select_all(&editor::actions::SelectAll, window, cx);
+        });
+        window.focus(&self.search_editor.focus_handle(cx), cx);
+    }
+
+    fn confirm_search(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        self.search(true, window, cx);
+    }
+
+    fn cancel_search(&mut self, _: &menu::Cancel, window: &mut Window, cx: &mut Context<Self>) {
+        window.focus(&self.focus_handle, cx);
+    }
+
+    fn find_next(&mut self, _: &FindNext, window: &mut Window, cx: &mut Context<Self>) {
+        self.search(true, window, cx);
+    }
+
+    fn find_previous(&mut self, _: &FindPrevious, window: &mut Window, cx: &mut Context<Self>) {
+        self.search(false, window, cx);
+    }
+
+    /// Selects the next or previous match of the search pattern, wrapping
+    /// around the end of the file.
+    fn search(&mut self, forward: bool, _: &mut Window, cx: &mut Context<Self>) {
+        let query = self.search_editor.read(cx).text(cx);
+        let pattern = match HexPattern::parse(&query) {
+            Ok(pattern) => pattern,
+            Err(error) => {
+                self.search_status = Some(error.to_string().into());
+                cx.notify();
+                return;
+            }
+        };
+
+        let snapshot = self.file.read(cx).snapshot().clone();
+        let selection = self.selection();
+        self.search_status = Some("Searching…".into());
+        cx.notify();
+        self.search_task = cx.spawn(async move |this, cx| {
+            let found = cx
+                .background_spawn(async move {
+                    if forward {
+                        pattern
+                            .find_next(&snapshot, selection.start + 1)
+                            .or_else(|| pattern.find_next(&snapshot, 0))
+                    } else {
+                        pattern
+                            .find_previous(&snapshot, selection.start)
+                            .or_else(|| pattern.find_previous(&snapshot, snapshot.len()))
+                    }
+                    .map(|offset| offset..offset + pattern.len())
+                })
+                .await;
+            this.update(cx, |this, cx| match found {
+                Some(range) => {
+                    this.search_status = None;
+                    this.select_range(range, cx);
+                }
+                None => {
+                    this.search_status = Some("No matches".into());
+                    cx.notify();
+                }
+            })
+            .log_err();
+        });
+    }
+
+    fn render_header(&self, cx: &Context<Self>) -> impl IntoElement {
+        let snapshot = self.file.read(cx).snapshot();
+        let selection = self.selection();
+        let position = if self.selection_tail.is_some() {
+            format!(
+                "{} bytes selected at 0x{:X}",
+                selection.len(),
+                selection.start
+            )
+        } else {
+            format!("Offset 0x{:X}", self.cursor)
+        };
+        let size = format!("{} bytes", snapshot.len());
+
+        h_flex()
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_2()
+            .justify_between()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        div()
+                            .key_context("HexEditorSearch")
+                            .w(rems(18.))
+                            .px_1()
+                            .border_1()
+                            .rounded_sm()
+                            .border_color(cx.theme().colors().border)
+                            .on_action(cx.listener(Self::confirm_search))
+                            .on_action(cx.listener(Self::cancel_search))
+                            .child(self.search_editor.clone()),
+                    )
+                    .child(
+                        IconButton::new("find-previous", IconName::ChevronLeft)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|_window, cx| {
+                                Tooltip::for_action("Find Previous", &FindPrevious, cx)
+                            })
+                            .on_click(
+                                cx.listener(|this, _, window, cx| this.search(false, window, cx)),
+                            ),
+                    )
+                    .child(
+                        IconButton::new("find-next", IconName::ChevronRight)
+                            .icon_size(IconSize::Small)
+                            .tooltip(|_window, cx| Tooltip::for_action("Find Next", &FindNext, cx))
+                            .on_click(
+                                cx.listener(|this, _, window, cx| this.search(true, window, cx)),
+                            ),
+                    )
+                    .when_some(self.search_status.clone(), |this, status| {
+                        this.child(
+                            Label::new(status)
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    }),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(position).size(LabelSize::Small))
+                    .child(Label::new(size).size(LabelSize::Small).color(Color::Muted)),
+            )
+    }
+
+    fn render_rows(&self, window: &Window, cx: &Context<Self>) -> impl IntoElement {
+        let line_height = Self::line_height(cx);
+        let snapshot = self.file.read(cx).snapshot();
+        let colors = cx.theme().colors();
+        let focused = self.focus_handle.contains_focused(window, cx);
+        let selection = self.selection();
+        let offset_width = format!("{:X}", snapshot.len()).len().max(8);
+
+        let first_row = self.scroll_row.floor() as usize;
+        let scroll_offset = line_height * (self.scroll_row.fract() as f32);
+        let rows = first_row..(first_row + self.page_rows(cx) + 2).min(self.row_count(cx));
+        let bytes = snapshot.read(rows.start * BYTES_PER_ROW..rows.end * BYTES_PER_ROW);
+
+        let cell = |offset: usize, column: Column, text: String, cx: &Context<Self>| {
+            let is_selected = selection.contains(&offset);
+            let is_cursor = offset == self.cursor && column == self.column && focused;
+            div()
+                .px(px(1.))
+                .when(is_selected, |this| {
+                    this.bg(colors.editor_document_highlight_read_background)
+                })
+                .when(is_cursor, |this| this.bg(colors.element_selected))
+                .when(snapshot.is_edited(offset), |this| {
+                    this.text_color(Color::Modified.color(cx))
+                })
+                .on_mouse_down(
+                    MouseButton::Left,
+                    cx.listener(move |this, event, window, cx| {
+                        this.handle_mouse_down(offset, column, event, window, cx)
+                    }),
+                )
+                .child(text)
+        };
+
+        let last_bounds = self.last_bounds.clone();
+        div()
+            .id("hex-rows")
+            .relative()
+            .flex_1()
+            .size_full()
+            .overflow_hidden()
+            .on_scroll_wheel(cx.listener(Self::handle_scroll_wheel))
+            .child(
+                canvas(
+                    move |bounds, _, _| last_bounds.set(Some(bounds)),
+                    |_, _, _, _| {},
+                )
+                .absolute()
+                .size_full(),
+            )
+            .child(
+                v_flex()
+                    .absolute()
+                    .top(-scroll_offset)
+                    .left_0()
+                    .children(rows.map(|row| {
+                        let row_start = row * BYTES_PER_ROW;
+                        let row_bytes = bytes
+                            .chunks(BYTES_PER_ROW)
+                            .nth(row - first_row)
+                            .unwrap_or_default();
+                        h_flex()
+                            .h(line_height)
+                            .gap_4()
+                            .px_2()
+                            .child(
+                                div()
+                                    .text_color(colors.editor_line_number)
+                                    .child(format!("{row_start:0offset_width$X}")),
+                            )
+                            .child(h_flex().children(row_bytes.iter().enumerate().map(
+                                |(ix, byte)| {
+                                    cell(row_start + ix, Column::Hex, format!("{byte:02X}"), cx)
+                                        .when(ix % 8 == 0 && ix > 0, |this| this.ml_2())
+                                        .mr(px(4.))
+                                },
+                            )))
+                            .child(h_flex().children(row_bytes.iter().enumerate().map(
+                                |(ix, byte)| {
+                                    let char = if byte.is_ascii_graphic() || *byte == b' ' {
+                                        *byte as char
+                                    } else {
+                                        '.'
+                                    };
+                                    cell(row_start + ix, Column::Text, char.to_string(), cx)
+                                },
+                            )))
+                    })),
+            )
+    }
+
+    fn render_interpretations(&self, cx: &Context<Self>) -> impl IntoElement {
+        let selection = self.selection();
+        let len = if self.selection_tail.is_some() {
+            selection.len().min(INTERPRETED_LEN)
+        } else {
+            INTERPRETED_LEN
+        };
+        let bytes = self
+            .file
+            .read(cx)
+            .snapshot()
+            .read(selection.start..selection.start + len);
+
+        v_flex()
+            .w(rems(20.))
+            .h_full()
+            .p_2()
+            .gap_1()
+            .border_l_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        Label::new("Type")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Label::new("Little Endian / Big Endian")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .children(interpret(&bytes).into_iter().map(|interpretation| {
+                let value = match interpretation.big_endian {
+                    Some(big_endian) => format!("{} / {big_endian}", interpretation.little_endian),
+                    None => interpretation.little_endian,
+                };
+                h_flex()
+                    .gap_2()
+                    .child(
+                        div()
+                            .w(rems(2.))
+                            .child(Label::new(interpretation.label).size(LabelSize::Small)),
+                    )
+                    .child(Label::new(value).size(LabelSize::Small).truncate())
+            }))
+    }
+}
+
+impl EventEmitter<HexEditorEvent> for HexEditor {}
+
+impl Focusable for HexEditor {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for HexEditor {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let settings = ThemeSettings::get_global(cx);
+        v_flex()
+            .key_context("HexEditor")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::move_left))
+            .on_action(cx.listener(Self::move_right))
+            .on_action(cx.listener(Self::move_up))
+            .on_action(cx.listener(Self::move_down))
+            .on_action(cx.listener(Self::select_left))
+            .on_action(cx.listener(Self::select_right))
+            .on_action(cx.listener(Self::select_up))
+            .on_action(cx.listener(Self::select_down))
+            .on_action(cx.listener(Self::page_up))
+            .on_action(cx.listener(Self::page_down))
+            .on_action(cx.listener(Self::move_to_start))
+            .on_action(cx.listener(Self::move_to_end))
+            .on_action(cx.listener(Self::switch_column))
+            .on_action(cx.listener(Self::undo))
+            .on_action(cx.listener(Self::redo))
+            .on_action(cx.listener(Self::focus_search))
+            .on_action(cx.listener(Self::find_next))
+            .on_action(cx.listener(Self::find_previous))
+            .on_key_down(cx.listener(Self::handle_key_down))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(self.render_header(cx))
+            .child(
+                h_flex()
+                    .flex_1()
+                    .min_h_0()
+                    .items_start()
+                    .font(settings.buffer_font.clone())
+                    .text_size(settings.buffer_font_size(cx))
+                    .line_height(Self::line_height(cx))
+                    .child(self.render_rows(window, cx))
+                    .child(self.render_interpretations(cx)),
+            )
+    }
+}
+
+impl Item for HexEditor {
+    type Event = HexEditorEvent;
+
+    fn to_item_events(event: &Self::Event, f: &mut dyn FnMut(ItemEvent)) {
+        match event {
+            HexEditorEvent::Edited => {
+                f(ItemEvent::Edit);
+                f(ItemEvent::UpdateTab);
+            }
+            HexEditorEvent::TitleChanged => f(ItemEvent::UpdateTab),
+        }
+    }
+
+    fn for_each_project_item(
+        &self,
+        cx: &App,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::ProjectItem),
+    ) {
+        f(self.file.entity_id(), self.file.read(cx))
+    }
+
+    fn tab_tooltip_text(&self, cx: &App) -> Option<SharedString> {
+        let abs_path = self.file.read(cx).abs_path().compact();
+        Some(abs_path.to_string_lossy().into_owned().into())
+    }
+
+    fn tab_content(&self, params: TabContentParams, _: &Window, cx: &App) -> AnyElement {
+        Label::new(self.tab_content_text(params.detail.unwrap_or_default(), cx))
+            .single_line()
+            .color(params.text_color())
+            .when(params.preview, |this| this.italic())
+            .into_any_element()
+    }
+
+    fn tab_content_text(&self, _: usize, cx: &App) -> SharedString {
+        self.file
+            .read(cx)
+            .abs_path()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned().into())
+            .unwrap_or_default()
+    }
+
+    fn tab_icon(&self, _: &Window, cx: &App) -> Option<Icon> {
+        let path = self.file.read(cx).abs_path();
+        ItemSettings::get_global(cx)
+            .file_icons
+            .then(|| FileIcons::get_icon(path, cx))
+            .flatten()
+            .map(Icon::from_path)
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("Hex Editor Opened")
+    }
+
+    fn is_dirty(&self, cx: &App) -> bool {
+        self.file.read(cx).is_dirty()
+    }
+
+    fn has_conflict(&self, cx: &App) -> bool {
+        self.file.read(cx).has_conflict()
+    }
+
+    fn can_save(&self, _: &App) -> bool {
+        true
+    }
+
+    fn save(
+        &mut self,
+        _: SaveOptions,
+        _: Entity<Project>,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        self.file.update(cx, |file, cx| file.save(cx))
+    }
+
+    fn reload(
+        &mut self,
+        _: Entity<Project>,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<anyhow::Result<()>> {
+        self.file.update(cx, |file, cx| file.reload(cx))
+    }
+}
+
+impl ProjectItem for HexEditor {
+    type Item = BinaryFile;
+
+    fn for_project_item(
+        _: Entity<Project>,
+        _: Option<&Pane>,
+        item: Entity<Self::Item>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        Self::new(item, window, cx)
+    }
+}
@@ -0,0 +1,87 @@
+/// One way of reading the bytes at the cursor as a number.
+#[derive(Debug, PartialEq)]
+pub struct Interpretation {
+    pub label: &'static str,
+    pub little_endian: String,
+    /// `None` for single bytes, which have no byte order.
+    pub big_endian: Option<String>,
+}
+
+macro_rules! interpretation {
+    ($bytes:expr, $label:literal, $ty:ty, $format:literal) => {{
+        const LEN: usize = std::mem::size_of::<$ty>();
+        $bytes.first_chunk::<LEN>().map(|chunk| Interpretation {
+            label: $label,
+            little_endian: format!($format, <$ty>::from_le_bytes(*chunk)),
+            big_endian: (LEN > 1).then(|| format!($format, <$ty>::from_be_bytes(*chunk))),
+        })
+    }};
+}
+
+/// Reads the start of `bytes` as each integer and float type that fits.
+/// Floats are debug-formatted, which uses scientific notation for very large
+/// and very small values.
+pub fn interpret(bytes: &[u8]) -> Vec<Interpretation> {
+    [
+        interpretation!(bytes, "u8", u8, "{}"),
+        interpretation!(bytes, "i8", i8, "{}"),
+        interpretation!(bytes, "u16", u16, "{}"),
+        interpretation!(bytes, "i16", i16, "{}"),
+        interpretation!(bytes, "u32", u32, "{}"),
+        interpretation!(bytes, "i32", i32, "{}"),
+        interpretation!(bytes, "u64", u64, "{}"),
+        interpretation!(bytes, "i64", i64, "{}"),
+        interpretation!(bytes, "f32", f32, "{:?}"),
+        interpretation!(bytes, "f64", f64, "{:?}"),
+    ]
+    .into_iter()
+    .flatten()
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(bytes: &[u8]) -> Vec<(&'static str, String, Option<String>)> {
+        interpret(bytes)
+            .into_iter()
+            .map(|interpretation| {
+                (
+                    interpretation.label,
+                    interpretation.little_endian,
+                    interpretation.big_endian,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_interpret_integers() {
+        assert_eq!(
+            values(&[0xff, 0x00, 0x80]),
+            [
+                ("u8", "255".into(), None),
+                ("i8", "-1".into(), None),
+                ("u16", "255".into(), Some("65280".into())),
+                ("i16", "255".into(), Some("-256".into())),
+            ]
+        );
+        assert!(values(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_interpret_floats() {
+        let values = values(&[0x00, 0x00, 0x80, 0x3f, 0x00, 0x00, 0xf0, 0x3f]);
+        assert_eq!(values[8].0, "f32");
+        assert_eq!(values[8].1, "1.0");
+        assert_eq!(
+            values[9],
+            (
+                "f64",
+                "1.000000236555934".into(),
+                Some("6.96672438435674e-310".into())
+            )
+        );
+    }
+}
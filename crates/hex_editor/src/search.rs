@@ -0,0 +1,153 @@
+use anyhow::{Result, anyhow};
+
+use crate::binary_file::BinarySnapshot;
+
+/// How many bytes are read at a time while searching.
+const SEARCH_CHUNK_LEN: usize = 1024 * 1024;
+
+/// A sequence of bytes to search for, where `None` matches any byte.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HexPattern(Vec<Option<u8>>);
+
+impl HexPattern {
+    /// Parses pairs of hex digits, optionally separated by whitespace and
+    /// prefixed with `0x`. `??` matches any byte.
+    pub fn parse(query: &str) -> Result<Self> {
+        let mut digits = Vec::new();
+        for word in query.split_whitespace() {
+            let word = word
+                .strip_prefix("0x")
+                .or_else(|| word.strip_prefix("0X"))
+                .unwrap_or(word);
+            digits.extend(word.chars());
+        }
+        if digits.is_empty() {
+            return Err(anyhow!("Enter hex bytes to search for, like `DE AD ?? EF`"));
+        }
+        if digits.len() % 2 != 0 {
+            return Err(anyhow!("Hex bytes must have two digits each"));
+        }
+        digits
+            .chunks(2)
+            .map(|pair| match pair {
+                ['?', '?'] => Ok(None),
+                [high, low] => {
+                    let digit = |c: char| {
+                        c.to_digit(16)
+                            .ok_or_else(|| anyhow!("`{c}` is not a hex digit"))
+                    };
+                    Ok(Some((digit(*high)? * 16 + digit(*low)?) as u8))
+                }
+                _ => unreachable!(),
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Self)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    fn matches_at(&self, bytes: &[u8]) -> bool {
+        bytes.len() >= self.0.len()
+            && self
+                .0
+                .iter()
+                .zip(bytes)
+                .all(|(pattern, byte)| pattern.is_none_or(|pattern| pattern == *byte))
+    }
+
+    /// Finds the first match starting at or after `start`.
+    pub fn find_next(&self, snapshot: &BinarySnapshot, start: usize) -> Option<usize> {
+        let mut chunk_start = start;
+        while chunk_start < snapshot.len() {
+            let chunk = snapshot.read(chunk_start..chunk_start + SEARCH_CHUNK_LEN + self.len() - 1);
+            if let Some(ix) =
+                (0..chunk.len().min(SEARCH_CHUNK_LEN)).find(|ix| self.matches_at(&chunk[*ix..]))
+            {
+                return Some(chunk_start + ix);
+            }
+            chunk_start += SEARCH_CHUNK_LEN;
+        }
+        None
+    }
+
+    /// Finds the last match starting before `end`.
+    pub fn find_previous(&self, snapshot: &BinarySnapshot, end: usize) -> Option<usize> {
+        let mut chunk_end = end.min(snapshot.len());
+        while chunk_end > 0 {
+            let chunk_start = chunk_end.saturating_sub(SEARCH_CHUNK_LEN);
+            let chunk = snapshot.read(chunk_start..chunk_end + self.len() - 1);
+            if let Some(ix) = (0..chunk_end - chunk_start)
+                .rev()
+                .find(|ix| self.matches_at(&chunk[*ix..]))
+            {
+                return Some(chunk_start + ix);
+            }
+            chunk_end = chunk_start;
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex_pattern() {
+        assert_eq!(
+            HexPattern::parse("DE ad 0xBE ??ef").unwrap(),
+            HexPattern(vec![Some(0xde), Some(0xad), Some(0xbe), None, Some(0xef)])
+        );
+        assert_eq!(
+            HexPattern::parse("cafe").unwrap(),
+            HexPattern(vec![Some(0xca), Some(0xfe)])
+        );
+        assert!(HexPattern::parse("").is_err());
+        assert!(HexPattern::parse("abc").is_err());
+        assert!(HexPattern::parse("zz").is_err());
+    }
+
+    #[test]
+    fn test_matches_at() {
+        let pattern = HexPattern::parse("01 ?? 03").unwrap();
+        assert!(pattern.matches_at(&[1, 2, 3]));
+        assert!(pattern.matches_at(&[1, 0xff, 3, 4]));
+        assert!(!pattern.matches_at(&[1, 2, 4]));
+        assert!(!pattern.matches_at(&[1, 2]));
+    }
+
+    #[test]
+    fn test_find_across_chunks() {
+        let mut bytes = vec![0u8; SEARCH_CHUNK_LEN * 2 + 100];
+        let first_match = SEARCH_CHUNK_LEN - 2;
+        let second_match = SEARCH_CHUNK_LEN * 2 + 10;
+        bytes[first_match..first_match + 4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        bytes[second_match..second_match + 4].copy_from_slice(&[0xde, 0x00, 0xbe, 0xef]);
+        let file = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(file.path(), &bytes).unwrap();
+        let snapshot = BinarySnapshot::for_file(file.path());
+
+        let pattern = HexPattern::parse("de ?? be ef").unwrap();
+        assert_eq!(pattern.find_next(&snapshot, 0), Some(first_match));
+        assert_eq!(
+            pattern.find_next(&snapshot, first_match + 1),
+            Some(second_match)
+        );
+        assert_eq!(pattern.find_next(&snapshot, second_match + 1), None);
+        assert_eq!(
+            pattern.find_previous(&snapshot, bytes.len()),
+            Some(second_match)
+        );
+        assert_eq!(
+            pattern.find_previous(&snapshot, second_match),
+            Some(first_match)
+        );
+        assert_eq!(pattern.find_previous(&snapshot, first_match), None);
+    }
+}
@@ -52,6 +52,7 @@ pub enum IconName {
     BoltOutlined,
     Book,
     BookCopy,
+    Bookmark,
     Box,
     BoxOpen,
     CaseSensitive,
@@ -0,0 +1,425 @@
+//! Recording and replaying keyboard macros in any keymap.
+//!
+//! While recording, the actions dispatched by keystrokes and the text typed
+//! into editors are captured. A macro can be replayed any number of times,
+//! and when the editor has several selections it's replayed at each of them
+//! independently. Macros can be saved by name in the settings, and bound in
+//! the keymap with [`Run`].
+
+mod prompt;
+mod recording_indicator;
+mod replay;
+
+use std::{
+    ops::Range,
+    rc::{Rc, Weak},
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use collections::HashMap;
+use editor::{Editor, EditorEvent};
+use gpui::{Action, App, Context, Global, SharedString, Window, actions};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde_json::Value;
+use settings::{KeyboardMacroStep, KeymapFile, RegisterSetting, Settings};
+use workspace::Workspace;
+
+pub use recording_indicator::RecordingIndicator;
+
+use crate::prompt::{MacroPrompt, PromptKind};
+
+actions!(
+    keyboard_macros,
+    [
+        /// Starts recording a keyboard macro, or stops the recording in progress.
+        ToggleRecording,
+        /// Asks for a number of times to replay the last recorded keyboard macro.
+        ReplayLastMacroWithCount,
+        /// Saves the last recorded keyboard macro by name in the settings.
+        SaveLastMacro,
+    ]
+);
+
+/// Replays the last recorded keyboard macro.
+#[derive(PartialEq, Clone, Deserialize, JsonSchema, Action)]
+#[action(namespace = keyboard_macros)]
+#[serde(deny_unknown_fields)]
+pub struct ReplayLastMacro {
+    /// The number of times to replay the macro.
+    #[serde(default = "default_count")]
+    pub count: usize,
+}
+
+/// Runs a keyboard macro saved in the settings.
+#[derive(PartialEq, Clone, Deserialize, JsonSchema, Action)]
+#[action(namespace = keyboard_macros)]
+#[serde(deny_unknown_fields)]
+pub struct Run {
+    /// The name of the macro in the `keyboard_macros` setting.
+    pub name: String,
+    /// The number of times to run the macro.
+    #[serde(default = "default_count")]
+    pub count: usize,
+}
+
+fn default_count() -> usize {
+    1
+}
+
+/// The keyboard macros saved in the settings, by name.
+#[derive(Clone, Debug, RegisterSetting)]
+pub struct KeyboardMacroSettings {
+    pub macros: HashMap<String, Vec<KeyboardMacroStep>>,
+}
+
+impl Settings for KeyboardMacroSettings {
+    fn from_settings(content: &settings::SettingsContent) -> Self {
+        Self {
+            macros: content.keyboard_macros.clone().unwrap(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum MacroStep {
+    Action {
+        action: Box<dyn Action>,
+        /// The input of the keymap binding the action was dispatched with,
+        /// which is needed to save actions that take arguments.
+        input: Option<SharedString>,
+    },
+    Insertion {
+        text: Arc<str>,
+        utf16_range_to_replace: Option<Range<isize>>,
+    },
+}
+
+impl Clone for MacroStep {
+    fn clone(&self) -> Self {
+        match self {
+            Self::Action { action, input } => Self::Action {
+                action: action.boxed_clone(),
+                input: input.clone(),
+            },
+            Self::Insertion {
+                text,
+                utf16_range_to_replace,
+            } => Self::Insertion {
+                text: text.clone(),
+                utf16_range_to_replace: utf16_range_to_replace.clone(),
+            },
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct KeyboardMacros {
+    recording: Option<Vec<MacroStep>>,
+    last_macro: Option<Rc<[MacroStep]>>,
+    /// Alive while a macro is being replayed.
+    replaying: Weak<()>,
+}
+
+impl Global for KeyboardMacros {}
+
+impl KeyboardMacros {
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    fn is_replaying(&self) -> bool {
+        self.replaying.strong_count() > 0
+    }
+
+    fn record(&mut self, step: MacroStep) {
+        if self.is_replaying() {
+            return;
+        }
+        if let Some(recording) = self.recording.as_mut() {
+            recording.push(step);
+        }
+    }
+
+    fn toggle_recording(&mut self) {
+        match self.recording.take() {
+            Some(recording) => {
+                if !recording.is_empty() {
+                    self.last_macro = Some(recording.into());
+                }
+            }
+            None => self.recording = Some(Vec::new()),
+        }
+    }
+}
+
+pub fn init(cx: &mut App) {
+    cx.set_global(KeyboardMacros::default());
+
+    cx.observe_keystrokes(|event, window, cx| {
+        let Some(action) = event.action.as_ref() else {
+            return;
+        };
+        if action.name().starts_with("keyboard_macros::")
+            || !cx.global::<KeyboardMacros>().is_recording()
+        {
+            return;
+        }
+        let input = window
+            .bindings_for_action(action.as_ref())
+            .into_iter()
+            .find_map(|binding| binding.action_input());
+        cx.global_mut::<KeyboardMacros>().record(MacroStep::Action {
+            action: action.boxed_clone(),
+            input,
+        });
+    })
+    .detach();
+
+    cx.observe_new(|editor: &mut Editor, _, cx| {
+        if !editor.mode().is_full() {
+            return;
+        }
+        cx.subscribe_self(|_, event: &EditorEvent, cx| {
+            if let EditorEvent::InputHandled {
+                text,
+                utf16_range_to_replace,
+            } = event
+                && cx.global::<KeyboardMacros>().is_recording()
+            {
+                cx.global_mut::<KeyboardMacros>()
+                    .record(MacroStep::Insertion {
+                        text: text.clone(),
+                        utf16_range_to_replace: utf16_range_to_replace.clone(),
+                    });
+            }
+        })
+        .detach();
+    })
+    .detach();
+
+    cx.observe_new(|workspace: &mut Workspace, _, _| {
+        workspace
+            .register_action(|_, _: &ToggleRecording, _, cx| {
+                cx.global_mut::<KeyboardMacros>().toggle_recording();
+            })
+            .register_action(|workspace, action: &ReplayLastMacro, window, cx| {
+                replay_last_macro(workspace, action.count, window, cx);
+            })
+            .register_action(|workspace, _: &ReplayLastMacroWithCount, window, cx| {
+                let handle = cx.entity().downgrade();
+                workspace.toggle_modal(window, cx, move |window, cx| {
+                    MacroPrompt::new(PromptKind::Count, handle, window, cx)
+                });
+            })
+            .register_action(|workspace, _: &SaveLastMacro, window, cx| {
+                if cx.global::<KeyboardMacros>().last_macro.is_none() {
+                    workspace.show_error(&anyhow!("No keyboard macro has been recorded"), cx);
+                    return;
+                }
+                let handle = cx.entity().downgrade();
+                workspace.toggle_modal(window, cx, move |window, cx| {
+                    MacroPrompt::new(PromptKind::Name, handle, window, cx)
+                });
+            })
+            .register_action(|workspace, action: &Run, window, cx| {
+                let steps = KeyboardMacroSettings::get_global(cx)
+                    .macros
+                    .get(&action.name)
+                    .ok_or_else(|| anyhow!("No keyboard macro named {:?}", action.name))
+                    .and_then(|steps| steps_from_settings(steps, cx));
+                match steps {
+                    Ok(steps) => replay::replay(steps.into(), action.count, workspace, window, cx),
+                    Err(error) => workspace.show_error(&error, cx),
+                }
+            });
+    })
+    .detach();
+}
+
+fn replay_last_macro(
+    workspace: &mut Workspace,
+    count: usize,
+    window: &mut Window,
+    cx: &mut Context<Workspace>,
+) {
+    let Some(steps) = cx.global::<KeyboardMacros>().last_macro.clone() else {
+        return;
+    };
+    replay::replay(steps, count, workspace, window, cx);
+}
+
+fn save_last_macro(name: String, workspace: &mut Workspace, cx: &mut Context<Workspace>) {
+    let Some(steps) = cx.global::<KeyboardMacros>().last_macro.clone() else {
+        return;
+    };
+    let steps = match steps_to_settings(&steps, cx) {
+        Ok(steps) => steps,
+        Err(error) => {
+            workspace.show_error(&error, cx);
+            return;
+        }
+    };
+    let fs = workspace.app_state().fs.clone();
+    settings::update_settings_file(fs, cx, move |settings, _| {
+        settings
+            .keyboard_macros
+            .get_or_insert_default()
+            .insert(name, steps);
+    });
+}
+
+/// Converts recorded steps to the form they're saved in the settings.
+///
+/// Fails if an action takes arguments that can't be recovered from the
+/// keymap binding it was dispatched with.
+fn steps_to_settings(steps: &[MacroStep], cx: &App) -> anyhow::Result<Vec<KeyboardMacroStep>> {
+    steps
+        .iter()
+        .map(|step| match step {
+            MacroStep::Insertion { text, .. } => Ok(KeyboardMacroStep::Text {
+                text: text.to_string(),
+            }),
+            MacroStep::Action { action, input } => {
+                let name = action.name();
+                if let Some(input) = input {
+                    let input = serde_json::from_str::<Value>(input)?;
+                    return Ok(KeyboardMacroStep::Action(Value::Array(vec![
+                        name.into(),
+                        input,
+                    ])));
+                }
+                match cx.build_action(name, None) {
+                    Ok(built) if built.partial_eq(action.as_ref()) => {
+                        Ok(KeyboardMacroStep::Action(name.into()))
+                    }
+                    _ => Err(anyhow!(
+                        "Can't save the keyboard macro because the arguments of {name} are unknown"
+                    )),
+                }
+            }
+        })
+        .collect()
+}
+
+fn steps_from_settings(steps: &[KeyboardMacroStep], cx: &App) -> anyhow::Result<Vec<MacroStep>> {
+    steps
+        .iter()
+        .map(|step| match step {
+            KeyboardMacroStep::Text { text } => Ok(MacroStep::Insertion {
+                text: text.as_str().into(),
+                utf16_range_to_replace: None,
+            }),
+            KeyboardMacroStep::Action(action) => {
+                let action = KeymapFile::build_action(action, cx)
+                    .map_err(|error| anyhow!("Invalid keyboard macro step: {error}"))?;
+                Ok(MacroStep::Action {
+                    action,
+                    input: None,
+                })
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use editor::test::editor_test_context::EditorTestContext;
+    use gpui::TestAppContext;
+    use indoc::indoc;
+    use serde_json::json;
+    use settings::SettingsStore;
+
+    fn init_test(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let settings_store = SettingsStore::test(cx);
+            cx.set_global(settings_store);
+            theme::init(theme::LoadThemes::JustBase, cx);
+            editor::init(cx);
+            cx.set_global(KeyboardMacros::default());
+        });
+    }
+
+    #[gpui::test]
+    fn test_steps_round_trip_through_settings(cx: &mut TestAppContext) {
+        init_test(cx);
+        cx.update(|cx| {
+            let saved = vec![
+                KeyboardMacroStep::Action(json!("editor::MoveToBeginningOfLine")),
+                KeyboardMacroStep::Text { text: "> ".into() },
+                KeyboardMacroStep::Action(
+                    json!(["editor::SelectNext", { "replace_newest": true }]),
+                ),
+            ];
+            let steps = steps_from_settings(&saved, cx).unwrap();
+            assert!(matches!(
+                &steps[1],
+                MacroStep::Insertion { text, .. } if text.as_ref() == "> "
+            ));
+
+            // Arguments are only known for actions dispatched through a binding.
+            assert!(steps_to_settings(&steps, cx).is_err());
+            let steps = steps
+                .into_iter()
+                .map(|step| match step {
+                    MacroStep::Action { action, .. } if action.name() == "editor::SelectNext" => {
+                        MacroStep::Action {
+                            action,
+                            input: Some("{\"replace_newest\":true}".into()),
+                        }
+                    }
+                    step => step,
+                })
+                .collect::<Vec<_>>();
+            assert_eq!(steps_to_settings(&steps, cx).unwrap(), saved);
+
+            let invalid = [KeyboardMacroStep::Action(json!("editor::NoSuchAction"))];
+            assert!(steps_from_settings(&invalid, cx).is_err());
+        });
+    }
+
+    #[gpui::test]
+    async fn test_replay_at_each_selection(cx: &mut TestAppContext) {
+        init_test(cx);
+        let mut cx = EditorTestContext::new(cx).await;
+        cx.set_state(indoc! {"
+            oneˇ
+            twoˇ
+            three
+        "});
+
+        let editor = cx.editor.clone();
+        cx.update(|window, cx| {
+            let steps = steps_from_settings(
+                &[
+                    KeyboardMacroStep::Action(json!("editor::MoveToBeginningOfLine")),
+                    KeyboardMacroStep::Text { text: "- ".into() },
+                    KeyboardMacroStep::Action(json!("editor::MoveDown")),
+                ],
+                cx,
+            )
+            .unwrap();
+            replay::replay_in_editor(editor, steps.into(), 1, window, cx).unwrap();
+        });
+        cx.run_until_parked();
+
+        // Each selection moved down a line independently of the other.
+        cx.assert_editor_state(indoc! {"
+            - one
+            - twˇo
+            thˇree
+        "});
+        assert!(cx.update(|_, cx| !cx.global::<KeyboardMacros>().is_replaying()));
+
+        // Replays that would take too many steps are refused up front.
+        let editor = cx.editor.clone();
+        cx.update(|window, cx| {
+            let steps =
+                steps_from_settings(&[KeyboardMacroStep::Text { text: "x".into() }], cx).unwrap();
+            assert!(replay::replay_in_editor(editor, steps.into(), 20_000, window, cx).is_err());
+            assert!(!cx.global::<KeyboardMacros>().is_replaying());
+        });
+    }
+}
@@ -0,0 +1,117 @@
+use editor::Editor;
+use gpui::{App, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, WeakEntity};
+use ui::prelude::*;
+use workspace::{ModalView, Workspace};
+
+use crate::{replay_last_macro, save_last_macro};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PromptKind {
+    /// Asks how many times to replay the last macro.
+    Count,
+    /// Asks for the name to save the last macro under.
+    Name,
+}
+
+pub(crate) struct MacroPrompt {
+    kind: PromptKind,
+    editor: Entity<Editor>,
+    workspace: WeakEntity<Workspace>,
+    error: Option<SharedString>,
+}
+
+impl ModalView for MacroPrompt {}
+
+impl EventEmitter<DismissEvent> for MacroPrompt {}
+
+impl Focusable for MacroPrompt {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl MacroPrompt {
+    pub(crate) fn new(
+        kind: PromptKind,
+        workspace: WeakEntity<Workspace>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            let placeholder = match kind {
+                PromptKind::Count => "Number of times to replay the last macro",
+                PromptKind::Name => "Name to save the last macro as",
+            };
+            editor.set_placeholder_text(placeholder, window, cx);
+            editor
+        });
+        Self {
+            kind,
+            editor,
+            workspace,
+            error: None,
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let input = self.editor.read(cx).text(cx).trim().to_string();
+        match self.kind {
+            PromptKind::Count => {
+                let Some(count) = input.parse::<usize>().ok().filter(|count| *count > 0) else {
+                    self.error = Some("Enter a positive number".into());
+                    cx.notify();
+                    return;
+                };
+                cx.emit(DismissEvent);
+                self.workspace
+                    .update(cx, |workspace, cx| {
+                        replay_last_macro(workspace, count, window, cx)
+                    })
+                    .ok();
+            }
+            PromptKind::Name => {
+                if input.is_empty() {
+                    self.error = Some("Enter a name".into());
+                    cx.notify();
+                    return;
+                }
+                cx.emit(DismissEvent);
+                self.workspace
+                    .update(cx, |workspace, cx| save_last_macro(input, workspace, cx))
+                    .ok();
+            }
+        }
+    }
+}
+
+impl Render for MacroPrompt {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let hint = match self.kind {
+            PromptKind::Count => "The macro is replayed at each selection",
+            PromptKind::Name => "Bind it with keyboard_macros::Run",
+        };
+        v_flex()
+            .w(rems(24.))
+            .elevation_2(cx)
+            .key_context("KeyboardMacroPrompt")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                div()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .px_2()
+                    .py_1()
+                    .child(self.editor.clone()),
+            )
+            .child(h_flex().px_2().py_1().child(match self.error.clone() {
+                Some(error) => Label::new(error).size(LabelSize::Small).color(Color::Error),
+                None => Label::new(hint).size(LabelSize::Small).color(Color::Muted),
+            }))
+    }
+}
@@ -0,0 +1,190 @@
+use std::{collections::VecDeque, ops::Range, rc::Rc};
+
+use anyhow::Result;
+use editor::{Anchor, Editor, SelectionEffects};
+use gpui::{App, Context, Entity, WeakEntity, Window};
+use workspace::Workspace;
+
+use crate::{KeyboardMacros, MacroStep};
+
+/// Replays that would take more than this many steps are refused.
+const MAX_REPLAYED_STEPS: usize = 10_000;
+
+pub(crate) fn replay(
+    steps: Rc<[MacroStep]>,
+    count: usize,
+    workspace: &mut Workspace,
+    window: &mut Window,
+    cx: &mut Context<Workspace>,
+) {
+    let result = match workspace.active_item_as::<Editor>(cx) {
+        Some(editor) => replay_in_editor(editor, steps, count, window, cx),
+        None => Replay::new(None, steps, count).start(window, cx),
+    };
+    if let Err(error) = result {
+        workspace.show_error(&error, cx);
+    }
+}
+
+/// Replays the steps `count` times in the editor, independently at each of
+/// its selections.
+pub(crate) fn replay_in_editor(
+    editor: Entity<Editor>,
+    steps: Rc<[MacroStep]>,
+    count: usize,
+    window: &mut Window,
+    cx: &mut App,
+) -> Result<()> {
+    let mut replay = Replay::new(Some(editor.downgrade()), steps, count);
+    editor.update(cx, |editor, cx| {
+        let selections = editor.selections.disjoint_anchors();
+        if selections.len() > 1 {
+            replay.pending_selections = selections
+                .iter()
+                .map(|selection| selection.tail()..selection.head())
+                .collect();
+        }
+        editor
+            .buffer()
+            .update(cx, |buffer, cx| buffer.finalize_last_transaction(cx));
+    });
+    replay.start(window, cx)
+}
+
+struct Replay {
+    editor: Option<WeakEntity<Editor>>,
+    steps: Rc<[MacroStep]>,
+    count: usize,
+    /// When replaying at each of several selections, the selections that
+    /// haven't been replayed at yet.
+    pending_selections: VecDeque<Range<Anchor>>,
+    /// The selections resulting from replaying at each selection so far.
+    replayed_selections: Vec<Range<Anchor>>,
+    per_selection: bool,
+    iteration: usize,
+    ix: usize,
+    /// Marks the replay as in progress for as long as it's alive, so that it
+    /// ends even if it's dropped before finishing, e.g. when its window closes.
+    in_progress: Rc<()>,
+}
+
+impl Replay {
+    fn new(editor: Option<WeakEntity<Editor>>, steps: Rc<[MacroStep]>, count: usize) -> Self {
+        Self {
+            editor,
+            steps,
+            count,
+            pending_selections: VecDeque::new(),
+            replayed_selections: Vec::new(),
+            per_selection: false,
+            iteration: 0,
+            ix: 0,
+            in_progress: Rc::new(()),
+        }
+    }
+
+    fn start(mut self, window: &mut Window, cx: &mut App) -> Result<()> {
+        if self.steps.is_empty() || self.count == 0 {
+            return Ok(());
+        }
+        let total_steps = self
+            .steps
+            .len()
+            .saturating_mul(self.count)
+            .saturating_mul(self.pending_selections.len().max(1));
+        anyhow::ensure!(
+            total_steps <= MAX_REPLAYED_STEPS,
+            "Can't replay the keyboard macro: it would take {total_steps} steps, \
+             more than the limit of {MAX_REPLAYED_STEPS}"
+        );
+        let state = cx.global_mut::<KeyboardMacros>();
+        if state.is_replaying() {
+            return Ok(());
+        }
+        state.replaying = Rc::downgrade(&self.in_progress);
+        if !self.pending_selections.is_empty() {
+            self.per_selection = true;
+            self.select_next_pending(window, cx);
+        }
+        window.defer(cx, move |window, cx| self.next(window, cx));
+        Ok(())
+    }
+
+    fn next(mut self, window: &mut Window, cx: &mut App) {
+        if self.ix == self.steps.len() {
+            self.ix = 0;
+            self.iteration += 1;
+            if self.iteration == self.count {
+                self.iteration = 0;
+                if self.per_selection {
+                    self.record_selections(cx);
+                }
+                if !self.select_next_pending(window, cx) {
+                    self.finish(window, cx);
+                    return;
+                }
+            }
+        }
+
+        match &self.steps[self.ix] {
+            MacroStep::Action { action, .. } => window.dispatch_action(action.boxed_clone(), cx),
+            MacroStep::Insertion {
+                text,
+                utf16_range_to_replace,
+            } => {
+                if let Some(editor) = self.editor() {
+                    editor.update(cx, |editor, cx| {
+                        editor.replay_insert_event(text, utf16_range_to_replace.clone(), window, cx)
+                    });
+                }
+            }
+        }
+        self.ix += 1;
+        window.defer(cx, move |window, cx| self.next(window, cx));
+    }
+
+    fn editor(&self) -> Option<Entity<Editor>> {
+        self.editor.as_ref().and_then(|editor| editor.upgrade())
+    }
+
+    fn record_selections(&mut self, cx: &mut App) {
+        if let Some(editor) = self.editor() {
+            let editor = editor.read(cx);
+            self.replayed_selections.extend(
+                editor
+                    .selections
+                    .disjoint_anchors()
+                    .iter()
+                    .map(|selection| selection.tail()..selection.head()),
+            );
+        }
+    }
+
+    /// Selects the next selection to replay at, returning false if there are
+    /// none left.
+    fn select_next_pending(&mut self, window: &mut Window, cx: &mut App) -> bool {
+        let Some(selection) = self.pending_selections.pop_front() else {
+            return false;
+        };
+        if let Some(editor) = self.editor() {
+            editor.update(cx, |editor, cx| {
+                editor.change_selections(SelectionEffects::no_scroll(), window, cx, |s| {
+                    s.select_anchor_ranges([selection])
+                });
+            });
+        }
+        true
+    }
+
+    fn finish(self, window: &mut Window, cx: &mut App) {
+        if self.per_selection
+            && let Some(editor) = self.editor()
+        {
+            editor.update(cx, |editor, cx| {
+                editor.change_selections(SelectionEffects::default(), window, cx, |s| {
+                    s.select_anchor_ranges(self.replayed_selections)
+                });
+            });
+        }
+    }
+}
@@ -2,6 +2,7 @@ use std::{
     cell::RefCell,
     cmp::{self},
     ops::{Not as _, Range},
+    path::Path,
     rc::Rc,
     sync::Arc,
     time::{Duration, Instant},
@@ -38,7 +39,7 @@ use ui::{
 use ui_input::InputField;
 use util::ResultExt;
 use workspace::{
-    Item, ModalView, SerializableItem, Workspace, notifications::NotifyTaskExt as _,
+    Item, ModalView, OpenOptions, SerializableItem, Workspace, notifications::NotifyTaskExt as _,
     register_serializable_item, with_active_or_new_workspace,
 };
 
@@ -238,7 +239,7 @@ struct SourceFilters {
 impl SourceFilters {
     fn allows(&self, source: Option<KeybindSource>) -> bool {
         match source {
-            Some(KeybindSource::User) => self.user,
+            Some(KeybindSource::User | KeybindSource::Project) => self.user,
             Some(KeybindSource::Vim) => self.vim_defaults,
             Some(KeybindSource::Base | KeybindSource::Default | KeybindSource::Unknown) | None => {
                 self.zed_defaults
@@ -825,6 +826,9 @@ impl KeymapEditor {
                 .meta()
                 .map(KeybindSource::from_meta)
                 .unwrap_or(KeybindSource::Unknown);
+            let source_file = key_binding
+                .meta()
+                .and_then(|meta| KeybindSource::project_keymap_file(meta, cx));
 
             let keystroke_text = ui::text_for_keybinding_keystrokes(key_binding.keystrokes(), cx);
             let is_no_action = gpui::is_no_action(key_binding.action());
@@ -864,6 +868,7 @@ impl KeymapEditor {
                 binding,
                 context,
                 source,
+                source_file,
                 is_no_action,
                 is_unbound_by_unbind,
                 action_information,
@@ -1294,6 +1299,10 @@ impl KeymapEditor {
         if !create && keybind.is_unbound_by_unbind() {
             return;
         }
+        if !create && let Some(source_file) = keybind.source_file().cloned() {
+            self.open_source_file(source_file, window, cx);
+            return;
+        }
         let keybind = keybind.clone();
         let keymap_editor = cx.entity();
 
@@ -1343,6 +1352,28 @@ impl KeymapEditor {
             .log_err();
     }
 
+    /// Project bindings are shared with the rest of the project's users, so instead of editing
+    /// them here, their file is opened.
+    fn open_source_file(
+        &self,
+        source_file: Arc<Path>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.workspace
+            .update(cx, |workspace, cx| {
+                workspace
+                    .open_abs_path(
+                        source_file.to_path_buf(),
+                        OpenOptions::default(),
+                        window,
+                        cx,
+                    )
+                    .detach_and_log_err(cx);
+            })
+            .log_err();
+    }
+
     fn edit_binding(&mut self, _: &EditBinding, window: &mut Window, cx: &mut Context<Self>) {
         self.open_edit_keybinding_modal(false, window, cx);
     }
@@ -1403,6 +1434,10 @@ impl KeymapEditor {
         if to_remove.is_unbound_by_unbind() {
             return;
         }
+        if let Some(source_file) = to_remove.source_file().cloned() {
+            self.open_source_file(source_file, window, cx);
+            return;
+        }
 
         let std::result::Result::Ok(fs) = self
             .workspace
@@ -1732,6 +1767,8 @@ struct KeybindInformation {
     binding: KeyBinding,
     context: KeybindContextString,
     source: KeybindSource,
+    /// The project keymap file the binding comes from.
+    source_file: Option<Arc<Path>>,
     is_no_action: bool,
     is_unbound_by_unbind: bool,
 }
@@ -1784,6 +1821,7 @@ impl ProcessedBinding {
         binding: KeyBinding,
         context: KeybindContextString,
         source: KeybindSource,
+        source_file: Option<Arc<Path>>,
         is_no_action: bool,
         is_unbound_by_unbind: bool,
         action_information: ActionInformation,
@@ -1794,6 +1832,7 @@ impl ProcessedBinding {
                 binding,
                 context,
                 source,
+                source_file,
                 is_no_action,
                 is_unbound_by_unbind,
             },
@@ -1826,6 +1865,11 @@ impl ProcessedBinding {
         self.keybind_information().map(|keybind| keybind.source)
     }
 
+    fn source_file(&self) -> Option<&Arc<Path>> {
+        self.keybind_information()
+            .and_then(|keybind| keybind.source_file.as_ref())
+    }
+
     fn context(&self) -> Option<&KeybindContextString> {
         self.keybind_information().map(|keybind| &keybind.context)
     }
@@ -2224,11 +2268,27 @@ impl Render for KeymapEditor {
                                         },
                                     );
 
-                                    let source = binding
+                                    let source_name = binding
                                         .keybind_source()
                                         .map(|source| source.name())
-                                        .unwrap_or_default()
-                                        .into_any_element();
+                                        .unwrap_or_default();
+                                    let source = match binding.source_file() {
+                                        Some(source_file) => {
+                                            let source_file: SharedString =
+                                                source_file.to_string_lossy().into_owned().into();
+                                            div()
+                                                .id(("keymap source", index))
+                                                .child(source_name)
+                                                .when(
+                                                    !context_menu_deployed && this.show_hover_menus,
+                                                    |this| {
+                                                        this.tooltip(Tooltip::text(source_file))
+                                                    },
+                                                )
+                                                .into_any_element()
+                                        }
+                                        None => source_name.into_any_element(),
+                                    };
 
                                     Some(vec![
                                         icon.into_any_element(),
@@ -2309,9 +2369,13 @@ impl Render for KeymapEditor {
                                                 let overriding_binding = this.keybindings.get(conflict.index);
                                                 let context = overriding_binding.and_then(|binding| {
                                                     match conflict.override_source {
-                                                        KeybindSource::User  => Some("your keymap"),
-                                                        KeybindSource::Vim => Some("the vim keymap"),
-                                                        KeybindSource::Base => Some("your base keymap"),
+                                                        KeybindSource::User  => Some("your keymap".to_string()),
+                                                        KeybindSource::Vim => Some("the vim keymap".to_string()),
+                                                        KeybindSource::Base => Some("your base keymap".to_string()),
+                                                        KeybindSource::Project => Some(match binding.source_file() {
+                                                            Some(source_file) => format!("the project keymap {}", source_file.display()),
+                                                            None => "the project keymap".to_string(),
+                                                        }),
                                                         _ => {
                                                             log::error!("Unexpected override from the {} keymap", conflict.override_source.name());
                                                             None
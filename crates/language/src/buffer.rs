@@ -301,6 +301,8 @@ pub enum DiagnosticSourceKind {
     Pulled,
     Pushed,
     Other,
+    /// A misspelling found by the spell checker.
+    SpellCheck,
 }
 
 /// An operation used to synchronize this buffer with its other replicas.
@@ -16,8 +16,8 @@ use settings::{DocumentFoldingRanges, DocumentSymbols, IntoGpui, SemanticTokens}
 pub use settings::{
     AutoIndentMode, CompletionSettingsContent, EditPredictionPromptFormat, EditPredictionProvider,
     EditPredictionsMode, FormatOnSave, Formatter, FormatterList, InlayHintKind,
-    LanguageSettingsContent, LspInsertMode, RewrapBehavior, ShowWhitespaceSetting, SoftWrap,
-    WordsCompletionMode,
+    LanguageSettingsContent, Linter, LinterInput, LinterOutput, LinterTrigger, LspInsertMode,
+    RewrapBehavior, ShowWhitespaceSetting, SoftWrap, WordsCompletionMode,
 };
 use settings::{RegisterSetting, Settings, SettingsLocation, SettingsStore, merge_from::MergeFrom};
 use shellexpand;
@@ -84,6 +84,10 @@ pub struct LanguageSettings {
     pub ensure_final_newline_on_save: bool,
     /// How to perform a buffer format.
     pub formatter: settings::FormatterList,
+    /// External commands whose output is reported as diagnostics.
+    pub linters: Vec<Linter>,
+    /// Spell checking settings.
+    pub spell_check: SpellCheckSettings,
     /// Zed's Prettier integration settings.
     pub prettier: PrettierSettings,
     /// Whether to automatically close JSX tags.
@@ -219,6 +223,19 @@ pub struct IndentGuideSettings {
     pub background_coloring: settings::IndentGuideBackgroundColoring,
 }
 
+/// The settings for spell checking.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpellCheckSettings {
+    /// Whether to check the spelling of comments, strings, and prose.
+    ///
+    /// Default: false
+    pub enabled: bool,
+    /// The Hunspell dictionaries to check against.
+    ///
+    /// Default: ["en_US"]
+    pub dictionaries: Vec<String>,
+}
+
 impl IndentGuideSettings {
     /// Returns the clamped line width in pixels for an indent guide based on
     /// whether it is active, or `None` when line coloring is disabled.
@@ -663,6 +680,7 @@ impl settings::Settings for AllLanguageSettings {
             let completions = settings.completions.unwrap();
             let prettier = settings.prettier.unwrap();
             let indent_guides = settings.indent_guides.unwrap();
+            let spell_check = settings.spell_check.unwrap();
             let tasks = settings.tasks.unwrap();
             let whitespace_map = settings.whitespace_map.unwrap();
 
@@ -686,6 +704,11 @@ impl settings::Settings for AllLanguageSettings {
                     .unwrap(),
                 ensure_final_newline_on_save: settings.ensure_final_newline_on_save.unwrap(),
                 formatter: settings.formatter.unwrap(),
+                linters: settings.linters.unwrap(),
+                spell_check: SpellCheckSettings {
+                    enabled: spell_check.enabled.unwrap(),
+                    dictionaries: spell_check.dictionaries.unwrap(),
+                },
                 prettier: PrettierSettings {
                     allowed: prettier.allowed.unwrap(),
                     parser: prettier.parser.filter(|parser| !parser.is_empty()),
@@ -216,6 +216,7 @@ pub fn serialize_diagnostics<'a>(
                 DiagnosticSourceKind::Pulled => proto::diagnostic::SourceKind::Pulled,
                 DiagnosticSourceKind::Pushed => proto::diagnostic::SourceKind::Pushed,
                 DiagnosticSourceKind::Other => proto::diagnostic::SourceKind::Other,
+                DiagnosticSourceKind::SpellCheck => proto::diagnostic::SourceKind::SpellCheck,
             } as i32,
             start: Some(serialize_anchor(&entry.range.start)),
             end: Some(serialize_anchor(&entry.range.end)),
@@ -471,6 +472,9 @@ pub fn deserialize_diagnostics(
                         proto::diagnostic::SourceKind::Pulled => DiagnosticSourceKind::Pulled,
                         proto::diagnostic::SourceKind::Pushed => DiagnosticSourceKind::Pushed,
                         proto::diagnostic::SourceKind::Other => DiagnosticSourceKind::Other,
+                        proto::diagnostic::SourceKind::SpellCheck => {
+                            DiagnosticSourceKind::SpellCheck
+                        }
                     },
                     data,
                 },
@@ -0,0 +1,492 @@
+use crate::{
+    BufferSnapshot, Grammar, GrammarId,
+    syntax_map::{QueryCursorHandle, TextProvider},
+    with_parser,
+};
+use anyhow::{Result, anyhow};
+use collections::{BTreeMap, HashMap};
+use parking_lot::Mutex;
+use std::{fmt, ops::Range, sync::Arc};
+use streaming_iterator::StreamingIterator as _;
+use tree_sitter::{Node, Query, Tree};
+
+const METAVARIABLE_PLACEHOLDER_PREFIX: &str = "zed_metavar_";
+
+/// A pattern that is matched against the syntax tree of a buffer, rather than
+/// its text.
+///
+/// Patterns are written as code in the language being searched, where
+/// metavariables like `$A` or `$ARGS` match any single syntax node. A
+/// metavariable that appears more than once must match identical text each
+/// time, and `$_` matches any node without capturing it. For example,
+/// `foo($A, $A)` matches `foo(x, x)` but not `foo(x, y)`.
+///
+/// Patterns starting with `(` or `[` that are valid Tree-sitter queries for
+/// a language are run as queries instead, with their `@captures` acting as
+/// metavariables. The `@match` capture, if any, determines the matched range.
+pub struct StructuralPattern {
+    source: Arc<str>,
+    /// The pattern with each metavariable replaced by a placeholder
+    /// identifier, so that it can be parsed as code.
+    code: String,
+    /// Metavariable names, keyed by the placeholder they were replaced with.
+    metavariables: HashMap<String, String>,
+    literals: Vec<String>,
+    compiled: Mutex<HashMap<GrammarId, Option<Arc<CompiledPattern>>>>,
+}
+
+enum CompiledPattern {
+    Code {
+        tree: Tree,
+        /// The path of child indices from the root of `tree` to the node that
+        /// is matched against buffers.
+        root_path: Vec<usize>,
+    },
+    Query(Query),
+}
+
+/// A match of a [`StructuralPattern`] in a buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StructuralMatch {
+    pub range: Range<usize>,
+    /// The byte range in the buffer captured by each metavariable.
+    pub captures: BTreeMap<String, Range<usize>>,
+}
+
+impl fmt::Debug for StructuralPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StructuralPattern")
+            .field("source", &self.source)
+            .finish_non_exhaustive()
+    }
+}
+
+impl StructuralPattern {
+    pub fn new(source: &str) -> Result<Self> {
+        let trimmed = source.trim();
+        if trimmed.is_empty() {
+            return Err(anyhow!("structural search pattern is empty"));
+        }
+
+        let mut code = String::with_capacity(trimmed.len());
+        let mut metavariables = HashMap::default();
+        let mut placeholders = HashMap::<&str, String>::default();
+        let mut rest = trimmed;
+        while let Some(ix) = rest.find('$') {
+            code.push_str(&rest[..ix]);
+            rest = &rest[ix + 1..];
+            let name_len = metavariable_name_len(rest);
+            if name_len == 0 {
+                code.push('$');
+                continue;
+            }
+            let name = &rest[..name_len];
+            let placeholder_count = placeholders.len();
+            let placeholder = placeholders
+                .entry(name)
+                .or_insert_with(|| format!("{METAVARIABLE_PLACEHOLDER_PREFIX}{placeholder_count}"));
+            metavariables.insert(placeholder.clone(), name.to_string());
+            code.push_str(placeholder);
+            rest = &rest[name_len..];
+        }
+        code.push_str(rest);
+
+        let literals = if looks_like_query(trimmed) {
+            Vec::new()
+        } else {
+            let mut literals = Vec::new();
+            for word in code
+                .split(|c: char| !c.is_alphanumeric() && c != '_')
+                .filter(|word| !word.is_empty())
+                .filter(|word| !word.starts_with(METAVARIABLE_PLACEHOLDER_PREFIX))
+            {
+                if !literals.iter().any(|literal| literal == word) {
+                    literals.push(word.to_string());
+                }
+            }
+            literals
+        };
+
+        Ok(Self {
+            source: source.into(),
+            code,
+            metavariables,
+            literals,
+            compiled: Mutex::default(),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    /// Words that appear verbatim in every match of this pattern, which can
+    /// be used to rule out files without parsing them.
+    pub fn literals(&self) -> &[String] {
+        &self.literals
+    }
+
+    /// Whether this pattern can be matched against syntax trees produced by
+    /// the given grammar.
+    pub fn compiles_for(&self, grammar: &Grammar) -> bool {
+        self.compile(grammar).is_some()
+    }
+
+    /// Returns the non-overlapping matches of this pattern that lie within
+    /// `range` of the buffer, in order.
+    pub fn matches(&self, buffer: &BufferSnapshot, range: Range<usize>) -> Vec<StructuralMatch> {
+        let mut matches = Vec::new();
+        for layer in buffer.syntax_layers_for_range(range.clone(), true) {
+            let Some(grammar) = layer.language.grammar() else {
+                continue;
+            };
+            let Some(compiled) = self.compile(grammar) else {
+                continue;
+            };
+            match compiled.as_ref() {
+                CompiledPattern::Code { tree, root_path } => {
+                    let mut root = tree.root_node();
+                    for ix in root_path {
+                        let mut cursor = root.walk();
+                        root = root
+                            .children(&mut cursor)
+                            .nth(*ix)
+                            .expect("invalid root path");
+                    }
+                    self.code_matches(root, layer.node(), buffer, &range, &mut matches);
+                }
+                CompiledPattern::Query(query) => {
+                    query_matches(query, layer.node(), buffer, &range, &mut matches);
+                }
+            }
+        }
+
+        matches.sort_by_key(|mat| (mat.range.start, std::cmp::Reverse(mat.range.end)));
+        let mut last_end = 0;
+        matches.retain(|mat| {
+            let overlaps = mat.range.start < last_end;
+            if !overlaps {
+                last_end = mat.range.end;
+            }
+            !overlaps
+        });
+        matches
+    }
+
+    /// Returns the match of this pattern that spans exactly `range`, if any.
+    pub fn match_at(
+        &self,
+        buffer: &BufferSnapshot,
+        range: Range<usize>,
+    ) -> Option<StructuralMatch> {
+        self.matches(buffer, range.clone())
+            .into_iter()
+            .find(|mat| mat.range == range)
+    }
+
+    fn compile(&self, grammar: &Grammar) -> Option<Arc<CompiledPattern>> {
+        self.compiled
+            .lock()
+            .entry(grammar.id())
+            .or_insert_with(|| self.compile_for_grammar(grammar).map(Arc::new))
+            .clone()
+    }
+
+    fn compile_for_grammar(&self, grammar: &Grammar) -> Option<CompiledPattern> {
+        if looks_like_query(&self.source)
+            && let Ok(query) = Query::new(&grammar.ts_language, self.source.trim())
+        {
+            return Some(CompiledPattern::Query(query));
+        }
+
+        let tree = with_parser(|parser| {
+            parser.set_language(&grammar.ts_language).ok()?;
+            parser.parse(&self.code, None)
+        })?;
+
+        // Descend to the innermost node spanning the whole pattern, so that
+        // e.g. an expression pattern isn't required to be a statement.
+        let mut root = tree.root_node();
+        let mut root_path = Vec::new();
+        loop {
+            let mut cursor = root.walk();
+            let child = root
+                .children(&mut cursor)
+                .enumerate()
+                .filter(|(_, child)| !child.is_missing() && !child.is_extra())
+                .find(|(_, child)| child.byte_range() == (0..self.code.len()));
+            let Some((ix, child)) = child else {
+                break;
+            };
+            root = child;
+            root_path.push(ix);
+        }
+        if root.has_error() {
+            return None;
+        }
+
+        Some(CompiledPattern::Code { tree, root_path })
+    }
+
+    fn code_matches(
+        &self,
+        pattern: Node,
+        node: Node,
+        buffer: &BufferSnapshot,
+        range: &Range<usize>,
+        matches: &mut Vec<StructuralMatch>,
+    ) {
+        let mut cursor = node.walk();
+        loop {
+            let node = cursor.node();
+            let node_range = node.byte_range();
+            let mut visit_children = node_range.start < range.end && node_range.end > range.start;
+            if visit_children && node_range.start >= range.start && node_range.end <= range.end {
+                let mut captures = BTreeMap::default();
+                if self.match_node(pattern, node, buffer, &mut captures) {
+                    matches.push(StructuralMatch {
+                        range: node_range,
+                        captures,
+                    });
+                    visit_children = false;
+                }
+            }
+
+            if visit_children && cursor.goto_first_child() {
+                continue;
+            }
+            while !cursor.goto_next_sibling() {
+                if !cursor.goto_parent() {
+                    return;
+                }
+            }
+        }
+    }
+
+    fn match_node(
+        &self,
+        pattern: Node,
+        node: Node,
+        buffer: &BufferSnapshot,
+        captures: &mut BTreeMap<String, Range<usize>>,
+    ) -> bool {
+        let pattern_text = &self.code[pattern.byte_range()];
+        if let Some(name) = self.metavariables.get(pattern_text) {
+            if name == "_" {
+                return true;
+            }
+            return match captures.get(name) {
+                Some(captured) => {
+                    let captured_text = buffer.text_for_range(captured.clone()).collect::<String>();
+                    text_eq(buffer, node.byte_range(), &captured_text)
+                }
+                None => {
+                    captures.insert(name.clone(), node.byte_range());
+                    true
+                }
+            };
+        }
+
+        if pattern.kind_id() != node.kind_id() {
+            return false;
+        }
+        let pattern_children = significant_children(pattern);
+        if pattern_children.is_empty() {
+            return significant_children(node).is_empty()
+                && text_eq(buffer, node.byte_range(), pattern_text);
+        }
+        let children = significant_children(node);
+        pattern_children.len() == children.len()
+            && pattern_children
+                .into_iter()
+                .zip(children)
+                .all(|(pattern, node)| self.match_node(pattern, node, buffer, captures))
+    }
+}
+
+impl StructuralMatch {
+    /// Expands a replacement template, where `$NAME` or `${NAME}` refers to
+    /// the text captured by a metavariable. References to names that weren't
+    /// captured are kept as is.
+    pub fn expand_replacement(&self, template: &str, buffer: &BufferSnapshot) -> String {
+        let mut replacement = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(ix) = rest.find('$') {
+            replacement.push_str(&rest[..ix]);
+            rest = &rest[ix + 1..];
+            let (name, len) = if let Some(braced) = rest.strip_prefix('{')
+                && let Some(end) = braced.find('}')
+            {
+                (&braced[..end], end + 2)
+            } else {
+                let len = capture_name_len(rest);
+                (&rest[..len], len)
+            };
+            match self.captures.get(name) {
+                Some(range) => {
+                    replacement.extend(buffer.text_for_range(range.clone()));
+                    rest = &rest[len..];
+                }
+                None => replacement.push('$'),
+            }
+        }
+        replacement.push_str(rest);
+        replacement
+    }
+}
+
+fn query_matches(
+    query: &Query,
+    node: Node,
+    buffer: &BufferSnapshot,
+    range: &Range<usize>,
+    matches: &mut Vec<StructuralMatch>,
+) {
+    let capture_names = query.capture_names();
+    let mut cursor = QueryCursorHandle::new();
+    cursor.set_byte_range(range.clone());
+    let mut query_matches = cursor.matches(query, node, TextProvider(buffer.as_rope()));
+    while let Some(query_match) = query_matches.next() {
+        let mut captures = BTreeMap::default();
+        let mut match_range: Option<Range<usize>> = None;
+        for capture in query_match.captures {
+            let name = capture_names[capture.index as usize];
+            let capture_range = capture.node.byte_range();
+            if name == "match" {
+                match_range = Some(capture_range.clone());
+            }
+            captures
+                .entry(name.to_string())
+                .or_insert_with(|| capture_range.clone());
+        }
+        let match_range = match_range.or_else(|| {
+            let start = captures.values().map(|range| range.start).min()?;
+            let end = captures.values().map(|range| range.end).max()?;
+            Some(start..end)
+        });
+        if let Some(match_range) = match_range
+            && match_range.start >= range.start
+            && match_range.end <= range.end
+        {
+            matches.push(StructuralMatch {
+                range: match_range,
+                captures,
+            });
+        }
+    }
+}
+
+fn looks_like_query(source: &str) -> bool {
+    source.trim_start().starts_with(['(', '['])
+}
+
+/// Returns the length of the metavariable name at the start of `text`, which
+/// must start with an uppercase letter or `_`, so that e.g. `$foo` in PHP or
+/// shell scripts is searched for literally.
+fn metavariable_name_len(text: &str) -> usize {
+    if !text.starts_with(|c: char| c.is_ascii_uppercase() || c == '_') {
+        return 0;
+    }
+    capture_name_len(text)
+}
+
+fn capture_name_len(text: &str) -> usize {
+    text.find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+        .unwrap_or(text.len())
+}
+
+/// Returns the children of a node, ignoring comments and other extras.
+fn significant_children(node: Node) -> Vec<Node> {
+    let mut cursor = node.walk();
+    node.children(&mut cursor)
+        .filter(|child| !child.is_extra() && !child.is_missing())
+        .collect()
+}
+
+fn text_eq(buffer: &BufferSnapshot, range: Range<usize>, text: &str) -> bool {
+    if range.len() != text.len() {
+        return false;
+    }
+    let mut rest = text;
+    for chunk in buffer.text_for_range(range) {
+        let Some(suffix) = rest.strip_prefix(chunk) else {
+            return false;
+        };
+        rest = suffix;
+    }
+    rest.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Buffer, rust_lang};
+    use gpui::{AppContext as _, TestAppContext};
+
+    fn find(pattern: &str, text: &str, cx: &mut TestAppContext) -> Vec<String> {
+        let buffer = cx.new(|cx| Buffer::local(text, cx).with_language(rust_lang(), cx));
+        cx.run_until_parked();
+        let snapshot = buffer.read_with(cx, |buffer, _| buffer.snapshot());
+        let pattern = StructuralPattern::new(pattern).unwrap();
+        pattern
+            .matches(&snapshot, 0..snapshot.len())
+            .into_iter()
+            .map(|mat| {
+                let replacement = mat.expand_replacement("$B <- $A", &snapshot);
+                let text = snapshot.text_for_range(mat.range).collect::<String>();
+                format!("{text} => {replacement}")
+            })
+            .collect()
+    }
+
+    #[gpui::test]
+    fn test_code_pattern(cx: &mut TestAppContext) {
+        assert_eq!(
+            find(
+                "foo($A, $B)",
+                "fn main() { foo(1 + 2, bar); foo(x); baz(1, 2); foo(a, /* b */ c) }",
+                cx
+            ),
+            [
+                "foo(1 + 2, bar) => bar <- 1 + 2",
+                "foo(a, /* b */ c) => c <- a"
+            ]
+        );
+    }
+
+    #[gpui::test]
+    fn test_repeated_metavariable(cx: &mut TestAppContext) {
+        assert_eq!(
+            find("$A == $A", "fn f() { x == x; x == y; a.b == a.b; }", cx),
+            ["x == x => $B <- x", "a.b == a.b => $B <- a.b"]
+        );
+    }
+
+    #[gpui::test]
+    fn test_nested_matches(cx: &mut TestAppContext) {
+        assert_eq!(
+            find("foo($A)", "fn f() { foo(foo(1)); }", cx),
+            ["foo(foo(1)) => $B <- foo(1)"]
+        );
+    }
+
+    #[gpui::test]
+    fn test_query_pattern(cx: &mut TestAppContext) {
+        assert_eq!(
+            find(
+                "(call_expression function: (identifier) @A arguments: (arguments (_) @B)) @match",
+                "fn f() { foo(1); bar(x); baz(); }",
+                cx
+            ),
+            ["foo(1) => 1 <- foo", "bar(x) => x <- bar"]
+        );
+    }
+
+    #[test]
+    fn test_literals() {
+        let pattern = StructuralPattern::new("foo($A, $B.len())").unwrap();
+        assert_eq!(pattern.literals(), ["foo", "len"]);
+        let pattern = StructuralPattern::new("echo $foo").unwrap();
+        assert_eq!(pattern.literals(), ["echo", "foo"]);
+    }
+}
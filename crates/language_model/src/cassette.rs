@@ -0,0 +1,1015 @@
+//! Recording and replaying of language model interactions.
+//!
+//! A [`Cassette`] holds every completion request made to a set of models,
+//! along with the events that were streamed back in response. Wrapping a
+//! provider with [`CassetteLanguageModelProvider::record`] captures
+//! interactions with a real provider, and
+//! [`CassetteLanguageModelProvider::replay`] serves them back without any
+//! network access, so that agent runs can be reproduced deterministically.
+//!
+//! Requests are matched by their normalized content (see
+//! [`RequestNormalizer`]) rather than by position, so concurrent requests
+//! such as title generation don't need to happen in the recorded order. When
+//! a request doesn't match any recorded one, the stream fails with a
+//! [`CassetteMismatch`] that describes where it diverged.
+
+use crate::{
+    AuthenticateError, ConfigurationViewTargetAgent, IconOrSvg, LanguageModel,
+    LanguageModelCompletionError, LanguageModelCompletionEvent, LanguageModelId, LanguageModelName,
+    LanguageModelProvider, LanguageModelProviderId, LanguageModelProviderName,
+    LanguageModelProviderState, LanguageModelRequest, LanguageModelToolChoice,
+    LanguageModelToolSchemaFormat,
+};
+use anyhow::{Context as _, Result, anyhow};
+use futures::{FutureExt, StreamExt, future::BoxFuture, stream::BoxStream};
+use gpui::{AnyView, App, AppContext as _, AsyncApp, Entity, Task, Window};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::{self, Write as _};
+use std::path::Path;
+use std::sync::Arc;
+use thiserror::Error;
+
+const CASSETTE_VERSION: u32 = 1;
+const MAX_VALUE_PREVIEW_LEN: usize = 240;
+
+/// The recorded interactions with one or more language models.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub version: u32,
+    pub models: Vec<RecordedModel>,
+    pub interactions: Vec<RecordedInteraction>,
+}
+
+impl Default for Cassette {
+    fn default() -> Self {
+        Self {
+            version: CASSETTE_VERSION,
+            models: Vec::new(),
+            interactions: Vec::new(),
+        }
+    }
+}
+
+impl Cassette {
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("failed to read cassette {}", path.display()))?;
+        let cassette: Self = serde_json::from_str(&contents)
+            .with_context(|| format!("failed to parse cassette {}", path.display()))?;
+        anyhow::ensure!(
+            cassette.version == CASSETTE_VERSION,
+            "cassette {} has version {}, but only version {CASSETTE_VERSION} is supported",
+            path.display(),
+            cassette.version
+        );
+        Ok(cassette)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, contents)
+            .with_context(|| format!("failed to write cassette {}", path.display()))
+    }
+}
+
+/// The capabilities of a model, as observed when recording.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedModel {
+    pub provider_id: String,
+    pub provider_name: String,
+    pub id: String,
+    pub name: String,
+    pub max_token_count: u64,
+    #[serde(default)]
+    pub max_output_tokens: Option<u64>,
+    pub supports_images: bool,
+    pub supports_tools: bool,
+    #[serde(default)]
+    pub supported_tool_choices: Vec<LanguageModelToolChoice>,
+    #[serde(default)]
+    pub supports_thinking: bool,
+    #[serde(default)]
+    pub supports_streaming_tools: bool,
+    #[serde(default)]
+    pub tool_input_format: LanguageModelToolSchemaFormat,
+    /// Whether this was the provider's default model.
+    #[serde(default)]
+    pub is_default: bool,
+    /// Whether this was the provider's default fast model.
+    #[serde(default)]
+    pub is_default_fast: bool,
+}
+
+impl RecordedModel {
+    fn from_model(model: &dyn LanguageModel) -> Self {
+        Self {
+            provider_id: model.provider_id().0.to_string(),
+            provider_name: model.provider_name().0.to_string(),
+            id: model.id().0.to_string(),
+            name: model.name().0.to_string(),
+            max_token_count: model.max_token_count(),
+            max_output_tokens: model.max_output_tokens(),
+            supports_images: model.supports_images(),
+            supports_tools: model.supports_tools(),
+            supported_tool_choices: [
+                LanguageModelToolChoice::Auto,
+                LanguageModelToolChoice::Any,
+                LanguageModelToolChoice::None,
+            ]
+            .into_iter()
+            .filter(|choice| model.supports_tool_choice(choice.clone()))
+            .collect(),
+            supports_thinking: model.supports_thinking(),
+            supports_streaming_tools: model.supports_streaming_tools(),
+            tool_input_format: model.tool_input_format(),
+            is_default: false,
+            is_default_fast: false,
+        }
+    }
+}
+
+/// A completion request and the response that was streamed back.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedInteraction {
+    pub model_id: String,
+    /// The request, after normalization.
+    pub request: Value,
+    pub events: Vec<RecordedEvent>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedEvent {
+    Event(LanguageModelCompletionEvent),
+    /// An error, which is replayed as [`LanguageModelCompletionError::Other`].
+    Error(String),
+}
+
+/// Removes the parts of a request that vary between otherwise identical runs.
+///
+/// Thread and prompt IDs and cache markers are always removed. Additional
+/// replacements can be configured for strings such as the path of a
+/// temporary worktree.
+#[derive(Clone, Debug, Default)]
+pub struct RequestNormalizer {
+    replacements: Vec<(String, String)>,
+}
+
+impl RequestNormalizer {
+    /// Replaces every occurrence of `from` in the request's strings with `to`.
+    pub fn with_replacement(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        let from = from.into();
+        if !from.is_empty() {
+            self.replacements.push((from, to.into()));
+        }
+        self
+    }
+
+    pub fn normalize(&self, request: &LanguageModelRequest) -> Value {
+        let mut value = serde_json::to_value(request).unwrap_or(Value::Null);
+        if let Some(object) = value.as_object_mut() {
+            object.remove("thread_id");
+            object.remove("prompt_id");
+            if let Some(messages) = object.get_mut("messages").and_then(Value::as_array_mut) {
+                for message in messages {
+                    if let Some(message) = message.as_object_mut() {
+                        message.remove("cache");
+                    }
+                }
+            }
+        }
+        self.replace_strings(&mut value);
+        value
+    }
+
+    fn replace_strings(&self, value: &mut Value) {
+        match value {
+            Value::String(string) => {
+                for (from, to) in &self.replacements {
+                    if string.contains(from.as_str()) {
+                        *string = string.replace(from.as_str(), to);
+                    }
+                }
+            }
+            Value::Array(values) => {
+                for value in values {
+                    self.replace_strings(value);
+                }
+            }
+            Value::Object(object) => {
+                for value in object.values_mut() {
+                    self.replace_strings(value);
+                }
+            }
+            Value::Null | Value::Bool(_) | Value::Number(_) => {}
+        }
+    }
+}
+
+/// Returned when a replayed request doesn't match any recorded request.
+#[derive(Clone, Debug, Error, PartialEq)]
+pub struct CassetteMismatch {
+    pub model_id: String,
+    /// How many requests had already been made to the model.
+    pub request_index: usize,
+    /// Where the request diverged from the closest remaining recorded request,
+    /// if there is one.
+    pub difference: Option<RequestDifference>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RequestDifference {
+    /// A JSON pointer to the first differing value.
+    pub path: String,
+    pub recorded: Option<Value>,
+    pub actual: Option<Value>,
+}
+
+impl fmt::Display for CassetteMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "request #{} to {} doesn't match any recorded request",
+            self.request_index + 1,
+            self.model_id
+        )?;
+        match &self.difference {
+            Some(difference) => write!(
+                f,
+                "; it first differs from the next recorded request at `{}`:\n  recorded: {}\n  actual:   {}",
+                difference.path,
+                preview_value(difference.recorded.as_ref()),
+                preview_value(difference.actual.as_ref()),
+            ),
+            None => write!(f, "; no recorded requests remain for this model"),
+        }
+    }
+}
+
+fn preview_value(value: Option<&Value>) -> String {
+    let Some(value) = value else {
+        return "<missing>".to_string();
+    };
+    let mut preview = value.to_string();
+    if preview.len() > MAX_VALUE_PREVIEW_LEN {
+        let mut end = MAX_VALUE_PREVIEW_LEN;
+        while !preview.is_char_boundary(end) {
+            end -= 1;
+        }
+        preview.truncate(end);
+        preview.push('…');
+    }
+    preview
+}
+
+/// Returns the location of the first difference between two JSON values.
+fn first_difference(recorded: &Value, actual: &Value) -> Option<RequestDifference> {
+    fn visit(path: &mut String, recorded: &Value, actual: &Value) -> Option<RequestDifference> {
+        let difference = |path: &str, recorded: Option<&Value>, actual: Option<&Value>| {
+            Some(RequestDifference {
+                path: if path.is_empty() {
+                    "/".to_string()
+                } else {
+                    path.to_string()
+                },
+                recorded: recorded.cloned(),
+                actual: actual.cloned(),
+            })
+        };
+
+        match (recorded, actual) {
+            (Value::Object(recorded), Value::Object(actual)) => {
+                let mut keys = recorded.keys().chain(actual.keys()).collect::<Vec<_>>();
+                keys.sort();
+                keys.dedup();
+                for key in keys {
+                    let len = path.len();
+                    write!(path, "/{}", key.replace('~', "~0").replace('/', "~1")).ok();
+                    let result = match (recorded.get(key), actual.get(key)) {
+                        (Some(recorded), Some(actual)) => visit(path, recorded, actual),
+                        (recorded, actual) => difference(path, recorded, actual),
+                    };
+                    path.truncate(len);
+                    if result.is_some() {
+                        return result;
+                    }
+                }
+                None
+            }
+            (Value::Array(recorded), Value::Array(actual)) => {
+                for ix in 0..recorded.len().max(actual.len()) {
+                    let len = path.len();
+                    write!(path, "/{ix}").ok();
+                    let result = match (recorded.get(ix), actual.get(ix)) {
+                        (Some(recorded), Some(actual)) => visit(path, recorded, actual),
+                        (recorded, actual) => difference(path, recorded, actual),
+                    };
+                    path.truncate(len);
+                    if result.is_some() {
+                        return result;
+                    }
+                }
+                None
+            }
+            (recorded, actual) if recorded == actual => None,
+            (recorded, actual) => difference(path, Some(recorded), Some(actual)),
+        }
+    }
+
+    visit(&mut String::new(), recorded, actual)
+}
+
+/// A provider that records or replays interactions with language models.
+///
+/// The provider takes on the ID of the provider it was recorded from, so
+/// registering it in place of that provider routes all of its requests
+/// through the cassette.
+pub struct CassetteLanguageModelProvider {
+    id: LanguageModelProviderId,
+    name: LanguageModelProviderName,
+    mode: CassetteMode,
+}
+
+enum CassetteMode {
+    Record {
+        provider: Arc<dyn LanguageModelProvider>,
+        recorder: CassetteRecorder,
+    },
+    Replay {
+        player: CassettePlayer,
+        models: Vec<Arc<dyn LanguageModel>>,
+    },
+}
+
+impl CassetteLanguageModelProvider {
+    /// Records every interaction with `provider`'s models into `recorder`.
+    pub fn record(provider: Arc<dyn LanguageModelProvider>, recorder: CassetteRecorder) -> Self {
+        Self {
+            id: provider.id(),
+            name: provider.name(),
+            mode: CassetteMode::Record { provider, recorder },
+        }
+    }
+
+    /// Creates a provider for each provider that was recorded in `player`'s cassette.
+    pub fn replay(player: &CassettePlayer) -> Vec<Self> {
+        let mut providers: Vec<Self> = Vec::new();
+        for model in &player.state.cassette.models {
+            let replay_model: Arc<dyn LanguageModel> = Arc::new(ReplayLanguageModel {
+                model: model.clone(),
+                state: player.state.clone(),
+            });
+            let provider_id = LanguageModelProviderId::from(model.provider_id.clone());
+            if let Some(provider) = providers
+                .iter_mut()
+                .find(|provider| provider.id == provider_id)
+            {
+                if let CassetteMode::Replay { models, .. } = &mut provider.mode {
+                    models.push(replay_model);
+                }
+            } else {
+                providers.push(Self {
+                    id: provider_id,
+                    name: LanguageModelProviderName::from(model.provider_name.clone()),
+                    mode: CassetteMode::Replay {
+                        player: player.clone(),
+                        models: vec![replay_model],
+                    },
+                });
+            }
+        }
+        providers
+    }
+
+    /// Returns the replayed model whose recording satisfies `predicate`.
+    fn recorded_model(
+        &self,
+        models: &[Arc<dyn LanguageModel>],
+        player: &CassettePlayer,
+        predicate: impl Fn(&RecordedModel) -> bool,
+    ) -> Option<Arc<dyn LanguageModel>> {
+        let recorded = player
+            .state
+            .cassette
+            .models
+            .iter()
+            .find(|model| model.provider_id == self.id.0 && predicate(model))?;
+        models
+            .iter()
+            .find(|model| model.id().0 == recorded.id)
+            .cloned()
+    }
+}
+
+impl LanguageModelProviderState for CassetteLanguageModelProvider {
+    type ObservableEntity = ();
+
+    fn observable_entity(&self) -> Option<Entity<Self::ObservableEntity>> {
+        None
+    }
+}
+
+impl LanguageModelProvider for CassetteLanguageModelProvider {
+    fn id(&self) -> LanguageModelProviderId {
+        self.id.clone()
+    }
+
+    fn name(&self) -> LanguageModelProviderName {
+        self.name.clone()
+    }
+
+    fn icon(&self) -> IconOrSvg {
+        match &self.mode {
+            CassetteMode::Record { provider, .. } => provider.icon(),
+            CassetteMode::Replay { .. } => IconOrSvg::default(),
+        }
+    }
+
+    fn default_model(&self, cx: &App) -> Option<Arc<dyn LanguageModel>> {
+        match &self.mode {
+            CassetteMode::Record { provider, recorder } => {
+                let model = provider.default_model(cx)?;
+                recorder.record_model(model.as_ref(), |recorded| recorded.is_default = true);
+                Some(recorder.wrap(model))
+            }
+            CassetteMode::Replay { player, models } => self
+                .recorded_model(models, player, |model| model.is_default)
+                .or_else(|| models.first().cloned()),
+        }
+    }
+
+    fn default_fast_model(&self, cx: &App) -> Option<Arc<dyn LanguageModel>> {
+        match &self.mode {
+            CassetteMode::Record { provider, recorder } => {
+                let model = provider.default_fast_model(cx)?;
+                recorder.record_model(model.as_ref(), |recorded| recorded.is_default_fast = true);
+                Some(recorder.wrap(model))
+            }
+            CassetteMode::Replay { player, models } => {
+                self.recorded_model(models, player, |model| model.is_default_fast)
+            }
+        }
+    }
+
+    fn provided_models(&self, cx: &App) -> Vec<Arc<dyn LanguageModel>> {
+        match &self.mode {
+            CassetteMode::Record { provider, recorder } => provider
+                .provided_models(cx)
+                .into_iter()
+                .map(|model| recorder.wrap(model))
+                .collect(),
+            CassetteMode::Replay { models, .. } => models.clone(),
+        }
+    }
+
+    fn is_authenticated(&self, cx: &App) -> bool {
+        match &self.mode {
+            CassetteMode::Record { provider, .. } => provider.is_authenticated(cx),
+            CassetteMode::Replay { .. } => true,
+        }
+    }
+
+    fn authenticate(&self, cx: &mut App) -> Task<Result<(), AuthenticateError>> {
+        match &self.mode {
+            CassetteMode::Record { provider, .. } => provider.authenticate(cx),
+            CassetteMode::Replay { .. } => Task::ready(Ok(())),
+        }
+    }
+
+    fn configuration_view(
+        &self,
+        target_agent: ConfigurationViewTargetAgent,
+        window: &mut Window,
+        cx: &mut App,
+    ) -> AnyView {
+        match &self.mode {
+            CassetteMode::Record { provider, .. } => {
+                provider.configuration_view(target_agent, window, cx)
+            }
+            CassetteMode::Replay { .. } => cx.new(|_| gpui::EmptyView).into(),
+        }
+    }
+
+    fn reset_credentials(&self, cx: &mut App) -> Task<Result<()>> {
+        match &self.mode {
+            CassetteMode::Record { provider, .. } => provider.reset_credentials(cx),
+            CassetteMode::Replay { .. } => Task::ready(Ok(())),
+        }
+    }
+}
+
+/// Collects interactions with the models it wraps into a [`Cassette`].
+#[derive(Clone, Default)]
+pub struct CassetteRecorder {
+    cassette: Arc<Mutex<Cassette>>,
+    normalizer: RequestNormalizer,
+}
+
+impl CassetteRecorder {
+    pub fn new(normalizer: RequestNormalizer) -> Self {
+        Self {
+            cassette: Default::default(),
+            normalizer,
+        }
+    }
+
+    pub fn wrap(&self, model: Arc<dyn LanguageModel>) -> Arc<dyn LanguageModel> {
+        Arc::new(RecordingLanguageModel {
+            model,
+            recorder: self.clone(),
+        })
+    }
+
+    /// Returns the interactions recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().clone()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        self.cassette.lock().save(path)
+    }
+
+    fn start_interaction(
+        &self,
+        model: &dyn LanguageModel,
+        request: &LanguageModelRequest,
+    ) -> usize {
+        self.record_model(model, |_| {});
+        let mut cassette = self.cassette.lock();
+        cassette.interactions.push(RecordedInteraction {
+            model_id: model.id().0.to_string(),
+            request: self.normalizer.normalize(request),
+            events: Vec::new(),
+        });
+        cassette.interactions.len() - 1
+    }
+
+    fn record_model(&self, model: &dyn LanguageModel, update: impl FnOnce(&mut RecordedModel)) {
+        let mut cassette = self.cassette.lock();
+        let model_id = model.id().0;
+        let provider_id = model.provider_id().0;
+        let ix = match cassette
+            .models
+            .iter()
+            .position(|recorded| recorded.id == model_id && recorded.provider_id == provider_id)
+        {
+            Some(ix) => ix,
+            None => {
+                cassette.models.push(RecordedModel::from_model(model));
+                cassette.models.len() - 1
+            }
+        };
+        update(&mut cassette.models[ix]);
+    }
+
+    fn record_event(&self, interaction_ix: usize, event: RecordedEvent) {
+        if let Some(interaction) = self.cassette.lock().interactions.get_mut(interaction_ix) {
+            interaction.events.push(event);
+        }
+    }
+}
+
+struct RecordingLanguageModel {
+    model: Arc<dyn LanguageModel>,
+    recorder: CassetteRecorder,
+}
+
+impl LanguageModel for RecordingLanguageModel {
+    fn id(&self) -> LanguageModelId {
+        self.model.id()
+    }
+
+    fn name(&self) -> LanguageModelName {
+        self.model.name()
+    }
+
+    fn provider_id(&self) -> LanguageModelProviderId {
+        self.model.provider_id()
+    }
+
+    fn provider_name(&self) -> LanguageModelProviderName {
+        self.model.provider_name()
+    }
+
+    fn upstream_provider_id(&self) -> LanguageModelProviderId {
+        self.model.upstream_provider_id()
+    }
+
+    fn upstream_provider_name(&self) -> LanguageModelProviderName {
+        self.model.upstream_provider_name()
+    }
+
+    fn telemetry_id(&self) -> String {
+        self.model.telemetry_id()
+    }
+
+    fn api_key(&self, cx: &App) -> Option<String> {
+        self.model.api_key(cx)
+    }
+
+    fn model_cost_info(&self) -> Option<crate::LanguageModelCostInfo> {
+        self.model.model_cost_info()
+    }
+
+    fn supports_thinking(&self) -> bool {
+        self.model.supports_thinking()
+    }
+
+    fn supports_fast_mode(&self) -> bool {
+        self.model.supports_fast_mode()
+    }
+
+    fn supported_effort_levels(&self) -> Vec<crate::LanguageModelEffortLevel> {
+        self.model.supported_effort_levels()
+    }
+
+    fn supports_images(&self) -> bool {
+        self.model.supports_images()
+    }
+
+    fn supports_tools(&self) -> bool {
+        self.model.supports_tools()
+    }
+
+    fn supports_tool_choice(&self, choice: LanguageModelToolChoice) -> bool {
+        self.model.supports_tool_choice(choice)
+    }
+
+    fn supports_streaming_tools(&self) -> bool {
+        self.model.supports_streaming_tools()
+    }
+
+    fn supports_split_token_display(&self) -> bool {
+        self.model.supports_split_token_display()
+    }
+
+    fn tool_input_format(&self) -> LanguageModelToolSchemaFormat {
+        self.model.tool_input_format()
+    }
+
+    fn max_token_count(&self) -> u64 {
+        self.model.max_token_count()
+    }
+
+    fn max_output_tokens(&self) -> Option<u64> {
+        self.model.max_output_tokens()
+    }
+
+    fn count_tokens(
+        &self,
+        request: LanguageModelRequest,
+        cx: &App,
+    ) -> BoxFuture<'static, Result<u64>> {
+        self.model.count_tokens(request, cx)
+    }
+
+    fn stream_completion(
+        &self,
+        request: LanguageModelRequest,
+        cx: &AsyncApp,
+    ) -> BoxFuture<
+        'static,
+        Result<
+            BoxStream<'static, Result<LanguageModelCompletionEvent, LanguageModelCompletionError>>,
+            LanguageModelCompletionError,
+        >,
+    > {
+        let interaction_ix = self
+            .recorder
+            .start_interaction(self.model.as_ref(), &request);
+        let recorder = self.recorder.clone();
+        let future = self.model.stream_completion(request, cx);
+        async move {
+            match future.await {
+                Ok(events) => Ok(events
+                    .inspect(move |event| {
+                        let event = match event {
+                            Ok(event) => RecordedEvent::Event(event.clone()),
+                            Err(error) => RecordedEvent::Error(error.to_string()),
+                        };
+                        recorder.record_event(interaction_ix, event);
+                    })
+                    .boxed()),
+                Err(error) => {
+                    recorder.record_event(interaction_ix, RecordedEvent::Error(error.to_string()));
+                    Err(error)
+                }
+            }
+        }
+        .boxed()
+    }
+}
+
+/// Serves the interactions in a [`Cassette`] in place of the models that were recorded.
+#[derive(Clone)]
+pub struct CassettePlayer {
+    state: Arc<ReplayState>,
+}
+
+struct ReplayState {
+    cassette: Cassette,
+    normalizer: RequestNormalizer,
+    /// Whether each interaction has been replayed.
+    replayed: Mutex<Vec<bool>>,
+    /// The number of requests made to each model, for error reporting.
+    request_counts: Mutex<collections::HashMap<String, usize>>,
+}
+
+impl CassettePlayer {
+    pub fn new(cassette: Cassette, normalizer: RequestNormalizer) -> Self {
+        let replayed = vec![false; cassette.interactions.len()];
+        Self {
+            state: Arc::new(ReplayState {
+                cassette,
+                normalizer,
+                replayed: Mutex::new(replayed),
+                request_counts: Default::default(),
+            }),
+        }
+    }
+
+    pub fn load(path: &Path, normalizer: RequestNormalizer) -> Result<Self> {
+        Ok(Self::new(Cassette::load(path)?, normalizer))
+    }
+
+    /// Returns the number of recorded interactions that haven't been replayed.
+    pub fn remaining(&self) -> usize {
+        self.state
+            .replayed
+            .lock()
+            .iter()
+            .filter(|replayed| !**replayed)
+            .count()
+    }
+}
+
+impl ReplayState {
+    fn take_interaction(
+        &self,
+        model_id: &str,
+        request: &LanguageModelRequest,
+    ) -> Result<&RecordedInteraction, CassetteMismatch> {
+        let request_index = {
+            let mut request_counts = self.request_counts.lock();
+            let count = request_counts.entry(model_id.to_string()).or_default();
+            *count += 1;
+            *count - 1
+        };
+        let request = self.normalizer.normalize(request);
+        let mut replayed = self.replayed.lock();
+        let mut closest = None;
+        for (ix, interaction) in self.cassette.interactions.iter().enumerate() {
+            if replayed[ix] || interaction.model_id != model_id {
+                continue;
+            }
+            if interaction.request == request {
+                replayed[ix] = true;
+                return Ok(interaction);
+            }
+            closest.get_or_insert(interaction);
+        }
+
+        Err(CassetteMismatch {
+            model_id: model_id.to_string(),
+            request_index,
+            difference: closest
+                .and_then(|interaction| first_difference(&interaction.request, &request)),
+        })
+    }
+}
+
+struct ReplayLanguageModel {
+    model: RecordedModel,
+    state: Arc<ReplayState>,
+}
+
+impl LanguageModel for ReplayLanguageModel {
+    fn id(&self) -> LanguageModelId {
+        LanguageModelId::from(self.model.id.clone())
+    }
+
+    fn name(&self) -> LanguageModelName {
+        LanguageModelName::from(self.model.name.clone())
+    }
+
+    fn provider_id(&self) -> LanguageModelProviderId {
+        LanguageModelProviderId::from(self.model.provider_id.clone())
+    }
+
+    fn provider_name(&self) -> LanguageModelProviderName {
+        LanguageModelProviderName::from(self.model.provider_name.clone())
+    }
+
+    fn telemetry_id(&self) -> String {
+        format!("replay/{}", self.model.id)
+    }
+
+    fn supports_thinking(&self) -> bool {
+        self.model.supports_thinking
+    }
+
+    fn supports_images(&self) -> bool {
+        self.model.supports_images
+    }
+
+    fn supports_tools(&self) -> bool {
+        self.model.supports_tools
+    }
+
+    fn supports_tool_choice(&self, choice: LanguageModelToolChoice) -> bool {
+        self.model.supported_tool_choices.contains(&choice)
+    }
+
+    fn supports_streaming_tools(&self) -> bool {
+        self.model.supports_streaming_tools
+    }
+
+    fn tool_input_format(&self) -> LanguageModelToolSchemaFormat {
+        self.model.tool_input_format
+    }
+
+    fn max_token_count(&self) -> u64 {
+        self.model.max_token_count
+    }
+
+    fn max_output_tokens(&self) -> Option<u64> {
+        self.model.max_output_tokens
+    }
+
+    fn count_tokens(&self, _: LanguageModelRequest, _: &App) -> BoxFuture<'static, Result<u64>> {
+        futures::future::ready(Ok(0)).boxed()
+    }
+
+    fn stream_completion(
+        &self,
+        request: LanguageModelRequest,
+        _: &AsyncApp,
+    ) -> BoxFuture<
+        'static,
+        Result<
+            BoxStream<'static, Result<LanguageModelCompletionEvent, LanguageModelCompletionError>>,
+            LanguageModelCompletionError,
+        >,
+    > {
+        let result = match self.state.take_interaction(&self.model.id, &request) {
+            Ok(interaction) => {
+                let events = interaction
+                    .events
+                    .iter()
+                    .map(|event| match event {
+                        RecordedEvent::Event(event) => Ok(event.clone()),
+                        RecordedEvent::Error(message) => {
+                            Err(LanguageModelCompletionError::Other(anyhow!("{message}")))
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                Ok(futures::stream::iter(events).boxed())
+            }
+            Err(mismatch) => Err(LanguageModelCompletionError::Other(mismatch.into())),
+        };
+        futures::future::ready(result).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_provider::FakeLanguageModel;
+    use crate::{LanguageModelRequestMessage, MessageContent, Role, StopReason};
+    use gpui::TestAppContext;
+
+    fn request(text: &str, thread_id: &str) -> LanguageModelRequest {
+        LanguageModelRequest {
+            thread_id: Some(thread_id.to_string()),
+            messages: vec![LanguageModelRequestMessage {
+                role: Role::User,
+                content: vec![MessageContent::Text(text.to_string())],
+                cache: true,
+                reasoning_details: None,
+            }],
+            ..Default::default()
+        }
+    }
+
+    async fn collect_events(
+        model: &Arc<dyn LanguageModel>,
+        request: LanguageModelRequest,
+        cx: &TestAppContext,
+    ) -> Vec<Result<LanguageModelCompletionEvent, String>> {
+        let events = model.stream_completion(request, &cx.to_async()).await;
+        match events {
+            Ok(events) => {
+                events
+                    .map(|event| event.map_err(|error| error.to_string()))
+                    .collect()
+                    .await
+            }
+            Err(error) => vec![Err(error.to_string())],
+        }
+    }
+
+    #[gpui::test]
+    async fn test_record_and_replay(cx: &mut TestAppContext) {
+        let fake_model = Arc::new(FakeLanguageModel::default());
+        let normalizer = RequestNormalizer::default().with_replacement("/tmp/run-1", "$WORKDIR");
+        let recorder = CassetteRecorder::new(normalizer);
+        let model = recorder.wrap(fake_model.clone());
+
+        let events_task = cx.executor().spawn({
+            let model = model.clone();
+            let events =
+                model.stream_completion(request("Look at /tmp/run-1", "a"), &cx.to_async());
+            async move { events.await.unwrap().collect::<Vec<_>>().await }
+        });
+        cx.run_until_parked();
+        fake_model.send_last_completion_stream_text_chunk("Hello");
+        fake_model.send_last_completion_stream_event(LanguageModelCompletionEvent::Stop(
+            StopReason::EndTurn,
+        ));
+        fake_model.end_last_completion_stream();
+        assert_eq!(events_task.await.len(), 2);
+
+        let cassette = recorder.cassette();
+        assert_eq!(cassette.models.len(), 1);
+        assert_eq!(cassette.interactions.len(), 1);
+        assert_eq!(
+            cassette.interactions[0].events,
+            vec![
+                RecordedEvent::Event(LanguageModelCompletionEvent::Text("Hello".into())),
+                RecordedEvent::Event(LanguageModelCompletionEvent::Stop(StopReason::EndTurn)),
+            ]
+        );
+
+        let cassette: Cassette =
+            serde_json::from_str(&serde_json::to_string(&cassette).unwrap()).unwrap();
+        let normalizer = RequestNormalizer::default().with_replacement("/tmp/run-2", "$WORKDIR");
+        let player = CassettePlayer::new(cassette, normalizer);
+        let providers = CassetteLanguageModelProvider::replay(&player);
+        assert_eq!(providers.len(), 1);
+        let model = cx.update(|cx| providers[0].default_model(cx)).unwrap();
+        assert_eq!(model.id(), fake_model.id());
+
+        assert_eq!(
+            collect_events(&model, request("Look at /tmp/run-2", "b"), cx).await,
+            vec![
+                Ok(LanguageModelCompletionEvent::Text("Hello".into())),
+                Ok(LanguageModelCompletionEvent::Stop(StopReason::EndTurn)),
+            ]
+        );
+        assert_eq!(player.remaining(), 0);
+
+        let events = collect_events(&model, request("Look at /tmp/run-2", "b"), cx).await;
+        assert_eq!(
+            events,
+            vec![Err(
+                "request #2 to fake doesn't match any recorded request; no recorded requests remain for this model"
+                    .to_string()
+            )]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_replay_mismatch(cx: &mut TestAppContext) {
+        let cassette = Cassette {
+            models: vec![RecordedModel::from_model(&FakeLanguageModel::default())],
+            interactions: vec![RecordedInteraction {
+                model_id: "fake".into(),
+                request: RequestNormalizer::default().normalize(&request("Hello", "a")),
+                events: vec![RecordedEvent::Error("overloaded".into())],
+            }],
+            ..Default::default()
+        };
+        let player = CassettePlayer::new(cassette, RequestNormalizer::default());
+        let providers = CassetteLanguageModelProvider::replay(&player);
+        let model = cx.update(|cx| providers[0].default_model(cx)).unwrap();
+
+        let mismatch = model
+            .stream_completion(request("Goodbye", "a"), &cx.to_async())
+            .await
+            .err()
+            .unwrap();
+        let LanguageModelCompletionError::Other(error) = mismatch else {
+            panic!("expected a mismatch error");
+        };
+        assert_eq!(
+            error.downcast_ref::<CassetteMismatch>().unwrap().difference,
+            Some(RequestDifference {
+                path: "/messages/0/content/0/Text".into(),
+                recorded: Some("Hello".into()),
+                actual: Some("Goodbye".into()),
+            })
+        );
+        assert_eq!(player.remaining(), 1);
+
+        assert_eq!(
+            collect_events(&model, request("Hello", "b"), cx).await,
+            vec![Err("overloaded".to_string())]
+        );
+        assert_eq!(player.remaining(), 0);
+    }
+}
@@ -1,4 +1,5 @@
 mod api_key;
+pub mod cassette;
 mod model;
 mod rate_limiter;
 mod registry;
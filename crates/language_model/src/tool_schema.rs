@@ -4,12 +4,15 @@ use schemars::{
     generate::SchemaSettings,
     transform::{Transform, transform_subschemas},
 };
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
 /// Indicates the format used to define the input schema for a language model tool.
-#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum LanguageModelToolSchemaFormat {
     /// A JSON schema, see https://json-schema.org
+    #[default]
     JsonSchema,
     /// A subset of an OpenAPI 3.0 schema object supported by Google AI, see https://ai.google.dev/api/caching#Schema
     JsonSchemaSubset,
@@ -0,0 +1,270 @@
+use std::{
+    io,
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use fs::Fs;
+use futures::StreamExt as _;
+use gpui::{App, AppContext as _, Context, Entity, EventEmitter, Task};
+use project::{Project, ProjectEntryId, ProjectPath};
+use settings::Settings as _;
+
+use crate::{
+    LargeFileViewerSettings,
+    line_index::{Line, LineIndex},
+    reader,
+};
+
+/// How many bytes are indexed in the background between updates of the view.
+const INDEX_CHUNK_LEN: usize = 64 * 1024 * 1024;
+const WATCH_LATENCY: Duration = Duration::from_millis(100);
+
+pub enum LargeFileEvent {
+    /// More of the file was indexed, or its contents changed.
+    Changed,
+}
+
+/// A local file that's read in bounded ranges rather than loaded into a
+/// buffer, so that files of any size can be viewed.
+pub struct LargeFile {
+    project_path: ProjectPath,
+    abs_path: PathBuf,
+    entry_id: Option<ProjectEntryId>,
+    fs: Arc<dyn Fs>,
+    /// The open file. It's only read with positioned reads, which return fewer
+    /// bytes rather than faulting if the file shrinks while it's open.
+    file: Arc<std::fs::File>,
+    /// The length of the file as of when it was last opened.
+    len: usize,
+    index: LineIndex,
+    indexing: Task<()>,
+    watching: Option<Task<()>>,
+}
+
+impl EventEmitter<LargeFileEvent> for LargeFile {}
+
+/// Whether the file at the given path should be opened in the large file
+/// viewer rather than an editor.
+pub fn is_large_file(project: &Entity<Project>, path: &ProjectPath, cx: &App) -> bool {
+    let threshold_mb = LargeFileViewerSettings::get_global(cx).threshold_mb;
+    let project = project.read(cx);
+    threshold_mb > 0
+        && project.is_local()
+        && project.entry_for_path(path, cx).is_some_and(|entry| {
+            entry.is_file() && entry.size >= threshold_mb.saturating_mul(1024 * 1024)
+        })
+}
+
+impl LargeFile {
+    pub fn abs_path(&self) -> &Path {
+        &self.abs_path
+    }
+
+    /// The length of the file as of when it was last opened.
+    pub fn byte_len(&self) -> usize {
+        self.len
+    }
+
+    pub fn index(&self) -> &LineIndex {
+        &self.index
+    }
+
+    pub fn is_indexed(&self) -> bool {
+        self.index.indexed_len() >= self.len
+    }
+
+    /// Reads the given rows, with each cut off after `max_line_len` bytes.
+    pub fn lines(&self, rows: Range<usize>, max_line_len: usize) -> io::Result<Vec<Line>> {
+        self.index.lines(rows, max_line_len, self.file.as_ref())
+    }
+
+    /// Returns the row containing the byte at `offset`.
+    pub fn row_for_offset(&self, offset: usize) -> io::Result<usize> {
+        self.index.row_for_offset(offset, self.file.as_ref())
+    }
+
+    /// Searches the indexed part of the file for `query` in the background,
+    /// starting after `offset` when searching forward and before it when
+    /// searching backward, and wrapping around the end of the file.
+    pub fn search(
+        &self,
+        query: Vec<u8>,
+        offset: usize,
+        backward: bool,
+        cx: &App,
+    ) -> Task<io::Result<Option<Range<usize>>>> {
+        let file = self.file.clone();
+        let end = self.index.indexed_len();
+        cx.background_spawn(async move {
+            let file = file.as_ref();
+            let offset = offset.min(end);
+            let overlap = query.len().saturating_sub(1);
+            let start = if backward {
+                match reader::find_previous(file, &query, 0, offset)? {
+                    Some(start) => Some(start),
+                    None => {
+                        reader::find_previous(file, &query, offset.saturating_sub(overlap), end)?
+                    }
+                }
+            } else {
+                match reader::find_next(file, &query, offset, end)? {
+                    Some(start) => Some(start),
+                    None => reader::find_next(file, &query, 0, (offset + overlap).min(end))?,
+                }
+            };
+            Ok(start.map(|start| start..start + query.len()))
+        })
+    }
+
+    pub fn is_watching(&self) -> bool {
+        self.watching.is_some()
+    }
+
+    /// Starts or stops remapping the file whenever it changes on disk.
+    pub fn set_watching(&mut self, watching: bool, cx: &mut Context<Self>) {
+        if !watching {
+            self.watching = None;
+            return;
+        }
+        if self.watching.is_some() {
+            return;
+        }
+
+        let fs = self.fs.clone();
+        let abs_path = self.abs_path.clone();
+        self.watching = Some(cx.spawn(async move |this, cx| {
+            let (mut events, _watcher) = fs.watch(&abs_path, WATCH_LATENCY).await;
+            while events.next().await.is_some() {
+                let file = cx.background_spawn({
+                    let abs_path = abs_path.clone();
+                    async move { open_file(&abs_path) }
+                });
+                // The file may be briefly missing while a log is being rotated.
+                let Ok((file, len)) = file.await else {
+                    continue;
+                };
+                if this
+                    .update(cx, |this, cx| this.replace_file(file, len, cx))
+                    .is_err()
+                {
+                    break;
+                }
+            }
+        }));
+    }
+
+    fn replace_file(&mut self, file: Arc<std::fs::File>, len: usize, cx: &mut Context<Self>) {
+        if len < self.index.indexed_len() {
+            // The file was truncated, so the lines indexed so far may be gone.
+            self.index = LineIndex::default();
+        }
+        self.file = file;
+        self.len = len;
+        self.index_remaining(cx);
+        cx.emit(LargeFileEvent::Changed);
+    }
+
+    fn index_remaining(&mut self, cx: &mut Context<Self>) {
+        self.indexing = Task::ready(());
+        let file = self.file.clone();
+        let len = self.len;
+        let mut position = self.index.position();
+        if position.indexed_len >= len {
+            return;
+        }
+
+        self.indexing = cx.spawn(async move |this, cx| {
+            while position.indexed_len < len {
+                let chunk = cx
+                    .background_spawn({
+                        let file = file.clone();
+                        async move { position.scan(file.as_ref(), len, INDEX_CHUNK_LEN) }
+                    })
+                    .await;
+                let chunk = match chunk {
+                    Ok(chunk) => chunk,
+                    Err(error) => {
+                        log::error!("failed to index lines: {error}");
+                        break;
+                    }
+                };
+                // The file shrank since it was opened; it's reopened once the
+                // change is noticed.
+                let stalled = chunk.end() == position;
+                position = chunk.end();
+                let updated = this.update(cx, |this, cx| {
+                    this.index.push_chunk(chunk);
+                    cx.emit(LargeFileEvent::Changed);
+                });
+                if updated.is_err() || stalled {
+                    break;
+                }
+            }
+        });
+    }
+}
+
+fn open_file(path: &Path) -> Result<(Arc<std::fs::File>, usize)> {
+    let file = std::fs::File::open(path).with_context(|| format!("opening {path:?}"))?;
+    let len = usize::try_from(file.metadata()?.len()).context("file is too large")?;
+    Ok((Arc::new(file), len))
+}
+
+impl project::ProjectItem for LargeFile {
+    fn try_open(
+        project: &Entity<Project>,
+        path: &ProjectPath,
+        cx: &mut App,
+    ) -> Option<Task<Result<Entity<Self>>>> {
+        if !is_large_file(project, path, cx) {
+            return None;
+        }
+
+        let project_path = path.clone();
+        let project = project.read(cx);
+        let entry_id = project.entry_for_path(path, cx).map(|entry| entry.id);
+        let abs_path = project.absolute_path(path, cx)?;
+        let fs = project.fs().clone();
+        Some(cx.spawn(async move |cx| {
+            let (file, len) = cx
+                .background_spawn({
+                    let abs_path = abs_path.clone();
+                    async move { open_file(&abs_path) }
+                })
+                .await?;
+            let follow = cx.update(|cx| LargeFileViewerSettings::get_global(cx).follow);
+            Ok(cx.new(|cx| {
+                let mut this = Self {
+                    project_path,
+                    abs_path,
+                    entry_id,
+                    fs,
+                    file,
+                    len,
+                    index: LineIndex::default(),
+                    indexing: Task::ready(()),
+                    watching: None,
+                };
+                this.index_remaining(cx);
+                this.set_watching(follow, cx);
+                this
+            }))
+        }))
+    }
+
+    fn entry_id(&self, _: &App) -> Option<ProjectEntryId> {
+        self.entry_id
+    }
+
+    fn project_path(&self, _: &App) -> Option<ProjectPath> {
+        Some(self.project_path.clone())
+    }
+
+    fn is_dirty(&self) -> bool {
+        false
+    }
+}
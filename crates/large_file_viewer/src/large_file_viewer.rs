@@ -0,0 +1,630 @@
+//! Views files too large to open in an editor.
+//!
+//! Files over the configured threshold are read in bounded ranges instead of
+//! being decoded into a buffer, and their lines are indexed in the background.
+//! The view is read-only and plain text, without syntax highlighting, but it
+//! can search the file for text and go to a line.
+
+mod large_file;
+mod line_index;
+mod reader;
+
+use std::{cell::Cell, ops::Range, rc::Rc};
+
+use editor::Editor;
+use file_icons::FileIcons;
+use gpui::{
+    AnyElement, App, Bounds, Context, Entity, EventEmitter, FocusHandle, Focusable, Pixels,
+    ScrollWheelEvent, Subscription, Task, Window, actions, canvas,
+};
+use project::Project;
+use settings::{RegisterSetting, Settings};
+use theme::ThemeSettings;
+use ui::prelude::*;
+use util::{paths::PathExt as _, size::format_file_size};
+use workspace::{
+    ItemSettings, Pane,
+    item::{Item, ProjectItem, TabContentParams},
+};
+
+pub use crate::large_file::{LargeFile, LargeFileEvent, is_large_file};
+
+actions!(
+    large_file_viewer,
+    [
+        /// Scrolls up by one line.
+        LineUp,
+        /// Scrolls down by one line.
+        LineDown,
+        /// Scrolls up by one page.
+        PageUp,
+        /// Scrolls down by one page.
+        PageDown,
+        /// Scrolls to the start of the file.
+        ScrollToStart,
+        /// Scrolls to the end of the file.
+        ScrollToEnd,
+        /// Toggles following content appended to the file.
+        ToggleFollow,
+        /// Shows the search bar.
+        Find,
+        /// Scrolls to the next match of the search query.
+        SelectNextMatch,
+        /// Scrolls to the previous match of the search query.
+        SelectPreviousMatch,
+        /// Asks for a line to scroll to.
+        GoToLine,
+    ]
+);
+
+/// Lines longer than this many bytes are cut off when displayed.
+const MAX_DISPLAYED_LINE_LEN: usize = 4096;
+
+#[derive(Clone, Debug, RegisterSetting)]
+pub struct LargeFileViewerSettings {
+    pub threshold_mb: u64,
+    pub follow: bool,
+}
+
+impl Settings for LargeFileViewerSettings {
+    fn from_settings(content: &settings::SettingsContent) -> Self {
+        let content = content.large_file_viewer.clone().unwrap();
+        Self {
+            threshold_mb: content.threshold_mb.unwrap(),
+            follow: content.follow.unwrap(),
+        }
+    }
+}
+
+pub fn init(cx: &mut App) {
+    workspace::register_project_item::<LargeFileView>(cx);
+}
+
+pub struct LargeFileView {
+    file: Entity<LargeFile>,
+    focus_handle: FocusHandle,
+    /// The row at the top of the view, which can be fractional while scrolling
+    /// by pixels.
+    scroll_row: f64,
+    last_bounds: Rc<Cell<Option<Bounds<Pixels>>>>,
+    query_bar: Option<QueryBar>,
+    /// The byte range of the search match that was scrolled to last.
+    active_match: Option<Range<usize>>,
+    search: Task<()>,
+    _subscription: Subscription,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum QueryKind {
+    Search,
+    GoToLine,
+}
+
+/// The input shown above the lines when searching or going to a line.
+struct QueryBar {
+    kind: QueryKind,
+    editor: Entity<Editor>,
+    message: Option<SharedString>,
+}
+
+impl LargeFileView {
+    pub fn new(file: Entity<LargeFile>, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let subscription = cx.subscribe_in(&file, window, |this, file, event, _, cx| match event {
+            LargeFileEvent::Changed => {
+                if file.read(cx).is_watching() {
+                    this.scroll_to_end(cx);
+                }
+                cx.notify();
+            }
+        });
+        let mut this = Self {
+            file,
+            focus_handle: cx.focus_handle(),
+            scroll_row: 0.,
+            last_bounds: Rc::default(),
+            query_bar: None,
+            active_match: None,
+            search: Task::ready(()),
+            _subscription: subscription,
+        };
+        if this.is_following(cx) {
+            this.scroll_to_end(cx);
+        }
+        this
+    }
+
+    fn is_following(&self, cx: &App) -> bool {
+        self.file.read(cx).is_watching()
+    }
+
+    fn line_height(cx: &App) -> Pixels {
+        let settings = ThemeSettings::get_global(cx);
+        settings.buffer_font_size(cx) * settings.line_height()
+    }
+
+    fn page_rows(&self, line_height: Pixels) -> f64 {
+        let height = self
+            .last_bounds
+            .get()
+            .map_or(Pixels::ZERO, |bounds| bounds.size.height);
+        f64::from(height / line_height).floor().max(1.)
+    }
+
+    fn max_scroll_row(&self, line_height: Pixels, cx: &App) -> f64 {
+        let line_count = self.file.read(cx).index().line_count() as f64;
+        (line_count - self.page_rows(line_height)).max(0.)
+    }
+
+    fn scroll_by(&mut self, rows: f64, cx: &mut Context<Self>) {
+        let line_height = Self::line_height(cx);
+        let max_scroll_row = self.max_scroll_row(line_height, cx);
+        self.scroll_row = (self.scroll_row + rows).clamp(0., max_scroll_row);
+        if rows < 0. && self.is_following(cx) {
+            self.file
+                .update(cx, |file, cx| file.set_watching(false, cx));
+        }
+        cx.notify();
+    }
+
+    fn scroll_to_end(&mut self, cx: &mut Context<Self>) {
+        self.scroll_row = self.max_scroll_row(Self::line_height(cx), cx);
+        cx.notify();
+    }
+
+    fn line_up(&mut self, _: &LineUp, _: &mut Window, cx: &mut Context<Self>) {
+        self.scroll_by(-1., cx);
+    }
+
+    fn line_down(&mut self, _: &LineDown, _: &mut Window, cx: &mut Context<Self>) {
+        self.scroll_by(1., cx);
+    }
+
+    fn page_up(&mut self, _: &PageUp, _: &mut Window, cx: &mut Context<Self>) {
+        let page_rows = self.page_rows(Self::line_height(cx));
+        self.scroll_by(-page_rows, cx);
+    }
+
+    fn page_down(&mut self, _: &PageDown, _: &mut Window, cx: &mut Context<Self>) {
+        let page_rows = self.page_rows(Self::line_height(cx));
+        self.scroll_by(page_rows, cx);
+    }
+
+    fn scroll_to_start(&mut self, _: &ScrollToStart, _: &mut Window, cx: &mut Context<Self>) {
+        self.scroll_by(f64::NEG_INFINITY, cx);
+    }
+
+    fn scroll_to_end_action(&mut self, _: &ScrollToEnd, _: &mut Window, cx: &mut Context<Self>) {
+        self.scroll_by(f64::INFINITY, cx);
+    }
+
+    fn toggle_follow(&mut self, _: &ToggleFollow, _: &mut Window, cx: &mut Context<Self>) {
+        let following = !self.is_following(cx);
+        self.file
+            .update(cx, |file, cx| file.set_watching(following, cx));
+        if following {
+            self.scroll_to_end(cx);
+        }
+        cx.notify();
+    }
+
+    /// Scrolls `row` into view, near the top of the view, and stops following.
+    fn scroll_to_row(&mut self, row: usize, cx: &mut Context<Self>) {
+        if self.is_following(cx) {
+            self.file
+                .update(cx, |file, cx| file.set_watching(false, cx));
+        }
+        let line_height = Self::line_height(cx);
+        let context_rows = (self.page_rows(line_height) / 3.).floor();
+        let max_scroll_row = self.max_scroll_row(line_height, cx);
+        self.scroll_row = (row as f64 - context_rows).clamp(0., max_scroll_row);
+        cx.notify();
+    }
+
+    fn show_query_bar(&mut self, kind: QueryKind, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(query_bar) = self.query_bar.as_mut()
+            && query_bar.kind == kind
+        {
+            query_bar.message = None;
+        } else {
+            let editor = cx.new(|cx| {
+                let mut editor = Editor::single_line(window, cx);
+                let placeholder = match kind {
+                    QueryKind::Search => "Search the file…",
+                    QueryKind::GoToLine => "Line number",
+                };
+                editor.set_placeholder_text(placeholder, window, cx);
+                editor
+            });
+            self.query_bar = Some(QueryBar {
+                kind,
+                editor,
+                message: None,
+            });
+        }
+        if let Some(query_bar) = &self.query_bar {
+            let editor = query_bar.editor.clone();
+            editor.update(cx, |editor, cx| {
+                editor.select_all(&editor::actions::SelectAll, window, cx)
+            });
+            window.focus(&editor.focus_handle(cx), cx);
+        }
+        cx.notify();
+    }
+
+    fn find(&mut self, _: &Find, window: &mut Window, cx: &mut Context<Self>) {
+        self.show_query_bar(QueryKind::Search, window, cx);
+    }
+
+    fn go_to_line(&mut self, _: &GoToLine, window: &mut Window, cx: &mut Context<Self>) {
+        self.show_query_bar(QueryKind::GoToLine, window, cx);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(query_bar) = self.query_bar.as_mut() else {
+            return;
+        };
+        let input = query_bar.editor.read(cx).text(cx);
+        match query_bar.kind {
+            QueryKind::Search => self.select_match(false, cx),
+            QueryKind::GoToLine => {
+                let line_count = self.file.read(cx).index().line_count();
+                match input.trim().parse::<usize>() {
+                    Ok(line) if (1..=line_count).contains(&line) => {
+                        self.dismiss_query_bar(window, cx);
+                        self.scroll_to_row(line - 1, cx);
+                    }
+                    _ => {
+                        query_bar.message =
+                            Some(format!("Enter a line number from 1 to {line_count}").into());
+                        cx.notify();
+                    }
+                }
+            }
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, window: &mut Window, cx: &mut Context<Self>) {
+        self.dismiss_query_bar(window, cx);
+    }
+
+    fn dismiss_query_bar(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.query_bar = None;
+        self.active_match = None;
+        self.search = Task::ready(());
+        window.focus(&self.focus_handle, cx);
+        cx.notify();
+    }
+
+    fn select_next_match(&mut self, _: &SelectNextMatch, _: &mut Window, cx: &mut Context<Self>) {
+        self.select_match(false, cx);
+    }
+
+    fn select_previous_match(
+        &mut self,
+        _: &SelectPreviousMatch,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.select_match(true, cx);
+    }
+
+    /// Searches for the query from the active match, or from the top of the
+    /// view when there's none, and scrolls to the match that's found.
+    fn select_match(&mut self, backward: bool, cx: &mut Context<Self>) {
+        let Some(query_bar) = self
+            .query_bar
+            .as_mut()
+            .filter(|query_bar| query_bar.kind == QueryKind::Search)
+        else {
+            return;
+        };
+        let query = query_bar.editor.read(cx).text(cx);
+        if query.is_empty() {
+            return;
+        }
+        query_bar.message = Some("Searching…".into());
+        cx.notify();
+
+        let file = self.file.read(cx);
+        let offset = match &self.active_match {
+            Some(active_match) if backward => active_match.end.saturating_sub(1),
+            Some(active_match) => active_match.start + 1,
+            None => file
+                .lines(self.scroll_row as usize..self.scroll_row as usize + 1, 0)
+                .ok()
+                .and_then(|lines| Some(lines.first()?.start))
+                .unwrap_or_default(),
+        };
+        let search = file.search(query.into_bytes(), offset, backward, cx);
+        self.search = cx.spawn(async move |this, cx| {
+            let result = search.await;
+            this.update(cx, |this, cx| {
+                let message = match result {
+                    Ok(Some(range)) => {
+                        let row = this.file.read(cx).row_for_offset(range.start);
+                        match row {
+                            Ok(row) => this.scroll_to_row(row, cx),
+                            Err(error) => log::error!("failed to find the row of a match: {error}"),
+                        }
+                        this.active_match = Some(range);
+                        None
+                    }
+                    Ok(None) if this.file.read(cx).is_indexed() => Some("No matches".into()),
+                    Ok(None) => Some("No matches in the part of the file indexed so far".into()),
+                    Err(error) => Some(format!("Search failed: {error}").into()),
+                };
+                if let Some(query_bar) = this.query_bar.as_mut() {
+                    query_bar.message = message;
+                }
+                cx.notify();
+            })
+            .ok();
+        });
+    }
+
+    fn handle_scroll_wheel(
+        &mut self,
+        event: &ScrollWheelEvent,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let line_height = Self::line_height(cx);
+        let delta = event.delta.pixel_delta(line_height);
+        self.scroll_by(-f64::from(delta.y / line_height), cx);
+    }
+
+    fn render_header(&self, cx: &Context<Self>) -> impl IntoElement {
+        let file = self.file.read(cx);
+        let threshold_mb = LargeFileViewerSettings::get_global(cx).threshold_mb;
+        let size = format_file_size(file.byte_len() as u64, false);
+        let progress = if file.is_indexed() {
+            format!("{} lines", file.index().line_count())
+        } else {
+            let percent = file.index().indexed_len() as f64 / file.byte_len() as f64 * 100.;
+            format!("Indexing lines… {percent:.0}%")
+        };
+
+        h_flex()
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_2()
+            .justify_between()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(
+                h_flex()
+                    .gap_1()
+                    .child(
+                        Icon::new(IconName::Info)
+                            .size(IconSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Label::new(format!(
+                            "Read-only: this file is {size}, over the {threshold_mb} MB limit for \
+                             editing. Syntax highlighting is unavailable."
+                        ))
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                    ),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        Label::new(progress)
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    )
+                    .child(
+                        Button::new("toggle-follow", "Follow")
+                            .label_size(LabelSize::Small)
+                            .toggle_state(file.is_watching())
+                            .on_click(cx.listener(|this, _, window, cx| {
+                                this.toggle_follow(&ToggleFollow, window, cx)
+                            })),
+                    ),
+            )
+    }
+
+    fn render_lines(&self, cx: &Context<Self>) -> impl IntoElement {
+        let line_height = Self::line_height(cx);
+        let buffer_font = ThemeSettings::get_global(cx).buffer_font.clone();
+        let file = self.file.read(cx);
+
+        let first_row = self.scroll_row.floor() as usize;
+        let offset = line_height * (self.scroll_row.fract() as f32);
+        let row_count = self.page_rows(line_height) as usize + 2;
+        let lines = file
+            .lines(first_row..first_row + row_count, MAX_DISPLAYED_LINE_LEN)
+            .unwrap_or_else(|error| {
+                log::error!("failed to read lines: {error}");
+                Vec::new()
+            });
+        let gutter_width = file.index().line_count().to_string().len();
+        let active_match = self.active_match.clone();
+
+        let last_bounds = self.last_bounds.clone();
+        div()
+            .id("large-file-lines")
+            .relative()
+            .flex_1()
+            .size_full()
+            .overflow_hidden()
+            .font(buffer_font)
+            .text_size(ThemeSettings::get_global(cx).buffer_font_size(cx))
+            .line_height(line_height)
+            .on_scroll_wheel(cx.listener(Self::handle_scroll_wheel))
+            .child(
+                canvas(
+                    move |bounds, _, _| last_bounds.set(Some(bounds)),
+                    |_, _, _, _| {},
+                )
+                .absolute()
+                .size_full(),
+            )
+            .child(v_flex().absolute().top(-offset).left_0().w_full().children(
+                lines.into_iter().enumerate().map(|(ix, line)| {
+                    // The part of the line covered by the active match, if any.
+                    let highlight = active_match.as_ref().and_then(|active_match| {
+                        let start = active_match.start.saturating_sub(line.start);
+                        let end = active_match.end.saturating_sub(line.start);
+                        let end = end.min(line.text.len());
+                        (active_match.start < line.start + line.text.len() && start < end)
+                            .then_some(start..end)
+                    });
+                    let display =
+                        |bytes: &[u8]| String::from_utf8_lossy(bytes).replace('\t', "    ");
+                    let text = match highlight {
+                        Some(highlight) => h_flex()
+                            .child(display(&line.text[..highlight.start]))
+                            .child(
+                                div()
+                                    .bg(cx.theme().colors().search_match_background)
+                                    .child(display(&line.text[highlight.clone()])),
+                            )
+                            .child(display(&line.text[highlight.end..]))
+                            .into_any_element(),
+                        None => div().child(display(&line.text)).into_any_element(),
+                    };
+                    h_flex()
+                        .h(line_height)
+                        .gap_4()
+                        .whitespace_nowrap()
+                        .child(
+                            div()
+                                .pl_2()
+                                .text_color(cx.theme().colors().editor_line_number)
+                                .child(format!("{:>gutter_width$}", first_row + ix + 1)),
+                        )
+                        .child(
+                            div()
+                                .text_color(cx.theme().colors().editor_foreground)
+                                .child(text),
+                        )
+                }),
+            ))
+    }
+
+    fn render_query_bar(&self, query_bar: &QueryBar, cx: &Context<Self>) -> impl IntoElement {
+        let label = match query_bar.kind {
+            QueryKind::Search => "Find",
+            QueryKind::GoToLine => "Go to Line",
+        };
+        h_flex()
+            .key_context("LargeFileViewerQuery")
+            .on_action(cx.listener(Self::confirm))
+            .on_action(cx.listener(Self::cancel))
+            .w_full()
+            .px_2()
+            .py_1()
+            .gap_2()
+            .border_b_1()
+            .border_color(cx.theme().colors().border_variant)
+            .child(Label::new(label).size(LabelSize::Small).color(Color::Muted))
+            .child(div().flex_1().child(query_bar.editor.clone()))
+            .children(query_bar.message.clone().map(|message| {
+                Label::new(message)
+                    .size(LabelSize::Small)
+                    .color(Color::Muted)
+            }))
+    }
+}
+
+impl EventEmitter<()> for LargeFileView {}
+
+impl Focusable for LargeFileView {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl Render for LargeFileView {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .key_context("LargeFileViewer")
+            .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::line_up))
+            .on_action(cx.listener(Self::line_down))
+            .on_action(cx.listener(Self::page_up))
+            .on_action(cx.listener(Self::page_down))
+            .on_action(cx.listener(Self::scroll_to_start))
+            .on_action(cx.listener(Self::scroll_to_end_action))
+            .on_action(cx.listener(Self::toggle_follow))
+            .on_action(cx.listener(Self::find))
+            .on_action(cx.listener(Self::go_to_line))
+            .on_action(cx.listener(Self::select_next_match))
+            .on_action(cx.listener(Self::select_previous_match))
+            .size_full()
+            .bg(cx.theme().colors().editor_background)
+            .child(self.render_header(cx))
+            .children(
+                self.query_bar
+                    .as_ref()
+                    .map(|query_bar| self.render_query_bar(query_bar, cx)),
+            )
+            .child(self.render_lines(cx))
+    }
+}
+
+impl Item for LargeFileView {
+    type Event = ();
+
+    fn for_each_project_item(
+        &self,
+        cx: &App,
+        f: &mut dyn FnMut(gpui::EntityId, &dyn project::ProjectItem),
+    ) {
+        f(self.file.entity_id(), self.file.read(cx))
+    }
+
+    fn tab_tooltip_text(&self, cx: &App) -> Option<SharedString> {
+        let abs_path = self.file.read(cx).abs_path().compact();
+        Some(abs_path.to_string_lossy().into_owned().into())
+    }
+
+    fn tab_content(&self, params: TabContentParams, _: &Window, cx: &App) -> AnyElement {
+        Label::new(self.tab_content_text(params.detail.unwrap_or_default(), cx))
+            .single_line()
+            .color(params.text_color())
+            .when(params.preview, |this| this.italic())
+            .into_any_element()
+    }
+
+    fn tab_content_text(&self, _: usize, cx: &App) -> SharedString {
+        self.file
+            .read(cx)
+            .abs_path()
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned().into())
+            .unwrap_or_default()
+    }
+
+    fn tab_icon(&self, _: &Window, cx: &App) -> Option<Icon> {
+        let path = self.file.read(cx).abs_path();
+        ItemSettings::get_global(cx)
+            .file_icons
+            .then(|| FileIcons::get_icon(path, cx))
+            .flatten()
+            .map(Icon::from_path)
+    }
+
+    fn telemetry_event_text(&self) -> Option<&'static str> {
+        Some("Large File Viewer Opened")
+    }
+}
+
+impl ProjectItem for LargeFileView {
+    type Item = LargeFile;
+
+    fn for_project_item(
+        _: Entity<Project>,
+        _: Option<&Pane>,
+        item: Entity<Self::Item>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        Self::new(item, window, cx)
+    }
+}
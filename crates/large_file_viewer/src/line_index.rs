@@ -0,0 +1,330 @@
+use std::{io, mem, ops::Range};
+
+use crate::reader::{ReadAt, for_each_block, skip_lines};
+
+/// How many lines apart the line starts recorded by a [`LineIndex`] are.
+const LINES_PER_CHECKPOINT: usize = 256;
+
+/// A sparse index of line starts in a file, built incrementally.
+///
+/// Only the start of every [`LINES_PER_CHECKPOINT`]th line is recorded, so the
+/// index of a file with a hundred million lines takes a few megabytes. The
+/// remaining lines are found by scanning forward from the nearest checkpoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LineIndex {
+    checkpoints: Vec<usize>,
+    position: IndexPosition,
+}
+
+/// How far a [`LineIndex`] has been built.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IndexPosition {
+    /// The number of lines whose start has been found.
+    pub line_count: usize,
+    /// The number of bytes of the file that have been scanned.
+    pub indexed_len: usize,
+}
+
+/// The line starts found by scanning a chunk of a file, to be appended to a
+/// [`LineIndex`] with [`LineIndex::push_chunk`].
+#[derive(Debug)]
+pub struct IndexedChunk {
+    checkpoints: Vec<usize>,
+    end: IndexPosition,
+}
+
+impl Default for LineIndex {
+    fn default() -> Self {
+        Self {
+            checkpoints: vec![0],
+            position: IndexPosition {
+                line_count: 1,
+                indexed_len: 0,
+            },
+        }
+    }
+}
+
+impl LineIndex {
+    pub fn position(&self) -> IndexPosition {
+        self.position
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.position.line_count
+    }
+
+    pub fn indexed_len(&self) -> usize {
+        self.position.indexed_len
+    }
+
+    pub fn push_chunk(&mut self, chunk: IndexedChunk) {
+        debug_assert!(chunk.end.indexed_len >= self.position.indexed_len);
+        self.checkpoints.extend(chunk.checkpoints);
+        self.position = chunk.end;
+    }
+
+    /// Returns the row containing the byte at `offset`, which must have been
+    /// indexed.
+    pub fn row_for_offset(
+        &self,
+        offset: usize,
+        reader: &(impl ReadAt + ?Sized),
+    ) -> io::Result<usize> {
+        let offset = offset.min(self.indexed_len());
+        let checkpoint = self.checkpoints.partition_point(|start| *start <= offset) - 1;
+        let mut row = checkpoint * LINES_PER_CHECKPOINT;
+        for_each_block(reader, self.checkpoints[checkpoint]..offset, |_, block| {
+            row += memchr::memchr_iter(b'\n', block).count();
+            true
+        })?;
+        Ok(row)
+    }
+
+    /// Returns the given rows, excluding line endings, with each cut off after
+    /// `max_line_len` bytes. Rows past the indexed part of the file are omitted.
+    pub fn lines(
+        &self,
+        rows: Range<usize>,
+        max_line_len: usize,
+        reader: &(impl ReadAt + ?Sized),
+    ) -> io::Result<Vec<Line>> {
+        let rows = rows.start..rows.end.min(self.line_count());
+        let mut lines = Vec::with_capacity(rows.len());
+        if rows.is_empty() {
+            return Ok(lines);
+        }
+
+        let end = self.indexed_len();
+        let checkpoint = self.checkpoints[rows.start / LINES_PER_CHECKPOINT];
+        let Some(start) = skip_lines(reader, checkpoint, end, rows.start % LINES_PER_CHECKPOINT)?
+        else {
+            return Ok(lines);
+        };
+        let mut line = Line::new(start);
+        for_each_block(reader, start..end, |offset, block| {
+            let mut position = 0;
+            while position < block.len() {
+                let newline = memchr::memchr(b'\n', &block[position..]).map(|ix| position + ix);
+                line.push(
+                    &block[position..newline.unwrap_or(block.len())],
+                    max_line_len,
+                );
+                let Some(newline) = newline else {
+                    break;
+                };
+                position = newline + 1;
+                lines.push(mem::replace(&mut line, Line::new(offset + position)).finish());
+                if lines.len() == rows.len() {
+                    return false;
+                }
+            }
+            true
+        })?;
+        if lines.len() < rows.len() {
+            lines.push(line.finish());
+        }
+        Ok(lines)
+    }
+}
+
+/// A line of a file, as returned by [`LineIndex::lines`].
+#[derive(Debug)]
+pub struct Line {
+    /// The offset of the start of the line in the file.
+    pub start: usize,
+    /// The start of the line's text, excluding its line ending.
+    pub text: Vec<u8>,
+    /// The length of the line, including any carriage return, even if its
+    /// text was cut off.
+    len: usize,
+    last_byte: Option<u8>,
+}
+
+impl Line {
+    fn new(start: usize) -> Self {
+        Self {
+            start,
+            text: Vec::new(),
+            len: 0,
+            last_byte: None,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8], max_len: usize) {
+        let room = max_len.saturating_sub(self.text.len());
+        self.text.extend_from_slice(&bytes[..bytes.len().min(room)]);
+        self.len += bytes.len();
+        if let Some(last_byte) = bytes.last() {
+            self.last_byte = Some(*last_byte);
+        }
+    }
+
+    fn finish(mut self) -> Self {
+        if self.last_byte == Some(b'\r') {
+            self.len -= 1;
+            self.text.truncate(self.len);
+        }
+        self
+    }
+}
+
+impl IndexPosition {
+    /// Scans up to `max_len` bytes past this position, and before `len`, for
+    /// line starts.
+    pub fn scan(
+        self,
+        reader: &(impl ReadAt + ?Sized),
+        len: usize,
+        max_len: usize,
+    ) -> io::Result<IndexedChunk> {
+        let start = self.indexed_len.min(len);
+        let end = len.min(start.saturating_add(max_len));
+        let mut line_count = self.line_count;
+        let mut checkpoints = Vec::new();
+        let mut indexed_len = start;
+        for_each_block(reader, start..end, |offset, block| {
+            for newline in memchr::memchr_iter(b'\n', block) {
+                if line_count % LINES_PER_CHECKPOINT == 0 {
+                    checkpoints.push(offset + newline + 1);
+                }
+                line_count += 1;
+            }
+            indexed_len = offset + block.len();
+            true
+        })?;
+        Ok(IndexedChunk {
+            checkpoints,
+            end: IndexPosition {
+                line_count,
+                indexed_len,
+            },
+        })
+    }
+}
+
+impl IndexedChunk {
+    pub fn end(&self) -> IndexPosition {
+        self.end
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn build(text: &[u8], chunk_len: usize) -> LineIndex {
+        let mut index = LineIndex::default();
+        while index.indexed_len() < text.len() {
+            let chunk = index.position().scan(text, text.len(), chunk_len).unwrap();
+            index.push_chunk(chunk);
+        }
+        index
+    }
+
+    fn lines(
+        index: &LineIndex,
+        rows: Range<usize>,
+        max_line_len: usize,
+        text: &[u8],
+    ) -> Vec<String> {
+        index
+            .lines(rows, max_line_len, text)
+            .unwrap()
+            .into_iter()
+            .map(|line| String::from_utf8(line.text).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_lines() {
+        let text = (0..1000)
+            .map(|row| format!("line {row}\n"))
+            .collect::<String>();
+        let text = text.as_bytes();
+        for chunk_len in [1, 7, 4096, usize::MAX] {
+            let index = build(text, chunk_len);
+            assert_eq!(index.line_count(), 1001);
+            assert_eq!(
+                lines(&index, 254..258, usize::MAX, text),
+                ["line 254", "line 255", "line 256", "line 257"]
+            );
+            assert_eq!(
+                lines(&index, 998..1005, usize::MAX, text),
+                ["line 998", "line 999", ""]
+            );
+        }
+    }
+
+    #[test]
+    fn test_row_for_offset() {
+        let text = (0..1000)
+            .map(|row| format!("line {row}\n"))
+            .collect::<String>();
+        let index = build(text.as_bytes(), usize::MAX);
+        for row in [0, 255, 256, 300, 999] {
+            let start = index
+                .lines(row..row + 1, usize::MAX, text.as_bytes())
+                .unwrap()[0]
+                .start;
+            assert_eq!(start, text.find(&format!("line {row}\n")).unwrap());
+            assert_eq!(index.row_for_offset(start, text.as_bytes()).unwrap(), row);
+            assert_eq!(
+                index.row_for_offset(start + 5, text.as_bytes()).unwrap(),
+                row
+            );
+        }
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let text = b"one\r\ntwo\n\nthree";
+        let index = build(text, usize::MAX);
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(
+            lines(&index, 0..4, usize::MAX, text),
+            ["one", "two", "", "three"]
+        );
+
+        let index = build(b"", usize::MAX);
+        assert_eq!(index.line_count(), 1);
+        assert_eq!(lines(&index, 0..1, usize::MAX, b""), [""]);
+    }
+
+    #[test]
+    fn test_long_lines() {
+        let text = b"xxxxxxxxxx\r\nshort\r\n";
+        let index = build(text, usize::MAX);
+        assert_eq!(lines(&index, 0..3, 4, text), ["xxxx", "shor", ""]);
+        assert_eq!(lines(&index, 1..2, 5, text), ["short"]);
+        assert_eq!(lines(&index, 1..2, 6, text), ["short"]);
+    }
+
+    #[test]
+    fn test_appended_text() {
+        let mut text = b"first\nsec".to_vec();
+        let mut index = build(&text, usize::MAX);
+        assert_eq!(lines(&index, 0..2, usize::MAX, &text), ["first", "sec"]);
+
+        text.extend_from_slice(b"ond\nthird\n");
+        let chunk = index
+            .position()
+            .scan(text.as_slice(), text.len(), usize::MAX)
+            .unwrap();
+        index.push_chunk(chunk);
+        assert_eq!(index.line_count(), 4);
+        assert_eq!(
+            lines(&index, 0..4, usize::MAX, &text),
+            ["first", "second", "third", ""]
+        );
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        let text = b"one\ntwo\nthree\n";
+        let index = build(text, usize::MAX);
+        // Reading a file that shrank after it was indexed returns what's left.
+        assert_eq!(lines(&index, 0..4, usize::MAX, &text[..6]), ["one", "tw"]);
+        assert!(lines(&index, 2..4, usize::MAX, &text[..6]).is_empty());
+    }
+}
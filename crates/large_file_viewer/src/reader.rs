@@ -0,0 +1,216 @@
+use std::{io, ops::Range};
+
+use memchr::memmem;
+
+/// How many bytes are read at a time when scanning a file.
+pub(crate) const BLOCK_LEN: usize = 1024 * 1024;
+
+/// Positioned reads from a file, or from bytes in memory in tests.
+///
+/// Files are read in bounded ranges rather than memory-mapped, so a file that
+/// shrinks while it's open just returns fewer bytes instead of faulting.
+pub trait ReadAt {
+    /// Reads bytes starting at `offset` into `buf`, returning how many were
+    /// read. Fewer bytes than requested are read at the end of the file.
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> io::Result<usize>;
+}
+
+impl ReadAt for std::fs::File {
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> io::Result<usize> {
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::FileExt::read_at(self, buf, offset as u64)
+        }
+        #[cfg(windows)]
+        {
+            std::os::windows::fs::FileExt::seek_read(self, buf, offset as u64)
+        }
+    }
+}
+
+impl ReadAt for [u8] {
+    fn read_at(&self, buf: &mut [u8], offset: usize) -> io::Result<usize> {
+        let source = self.get(offset..).unwrap_or_default();
+        let len = source.len().min(buf.len());
+        buf[..len].copy_from_slice(&source[..len]);
+        Ok(len)
+    }
+}
+
+/// Fills as much of `buf` as possible from `offset`, returning how many bytes
+/// were read, which is fewer than requested only at the end of the file.
+pub(crate) fn read_full(
+    reader: &(impl ReadAt + ?Sized),
+    buf: &mut [u8],
+    offset: usize,
+) -> io::Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read_at(&mut buf[read..], offset + read) {
+            Ok(0) => break,
+            Ok(len) => read += len,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(read)
+}
+
+/// Calls `f` with consecutive blocks of the bytes in `range`, with the offset
+/// of each, until it returns `false` or the file ends.
+pub(crate) fn for_each_block(
+    reader: &(impl ReadAt + ?Sized),
+    range: Range<usize>,
+    mut f: impl FnMut(usize, &[u8]) -> bool,
+) -> io::Result<()> {
+    let mut buf = vec![0; BLOCK_LEN.min(range.len())];
+    let mut offset = range.start;
+    while offset < range.end {
+        let len = read_full(
+            reader,
+            &mut buf[..BLOCK_LEN.min(range.end - offset)],
+            offset,
+        )?;
+        if len == 0 || !f(offset, &buf[..len]) {
+            break;
+        }
+        offset += len;
+    }
+    Ok(())
+}
+
+/// Returns the offset just past the `count`th newline at or after `start`,
+/// or `None` if there are fewer than `count` newlines before `end`.
+pub(crate) fn skip_lines(
+    reader: &(impl ReadAt + ?Sized),
+    start: usize,
+    end: usize,
+    count: usize,
+) -> io::Result<Option<usize>> {
+    if count == 0 {
+        return Ok(Some(start));
+    }
+    let mut remaining = count;
+    let mut result = None;
+    for_each_block(reader, start..end, |offset, block| {
+        for newline in memchr::memchr_iter(b'\n', block) {
+            remaining -= 1;
+            if remaining == 0 {
+                result = Some(offset + newline + 1);
+                return false;
+            }
+        }
+        true
+    })?;
+    Ok(result)
+}
+
+/// Returns the offset of the first occurrence of `query` that starts at or
+/// after `start` and ends at or before `end`.
+pub(crate) fn find_next(
+    reader: &(impl ReadAt + ?Sized),
+    query: &[u8],
+    start: usize,
+    end: usize,
+) -> io::Result<Option<usize>> {
+    if query.is_empty() || end < start.saturating_add(query.len()) {
+        return Ok(None);
+    }
+    let finder = memmem::Finder::new(query);
+    let overlap = query.len() - 1;
+    let step = BLOCK_LEN.max(overlap + 1);
+    let mut buf = vec![0; (step + overlap).min(end - start)];
+    let mut offset = start;
+    while offset + query.len() <= end {
+        let len = (step + overlap).min(end - offset);
+        let read = read_full(reader, &mut buf[..len], offset)?;
+        if let Some(ix) = finder.find(&buf[..read]) {
+            return Ok(Some(offset + ix));
+        }
+        if read < len {
+            break;
+        }
+        offset += step;
+    }
+    Ok(None)
+}
+
+/// Returns the offset of the last occurrence of `query` that starts at or
+/// after `start` and ends at or before `end`.
+pub(crate) fn find_previous(
+    reader: &(impl ReadAt + ?Sized),
+    query: &[u8],
+    start: usize,
+    end: usize,
+) -> io::Result<Option<usize>> {
+    if query.is_empty() || end < start.saturating_add(query.len()) {
+        return Ok(None);
+    }
+    let finder = memmem::FinderRev::new(query);
+    let overlap = query.len() - 1;
+    let step = BLOCK_LEN.max(overlap + 1);
+    let mut buf = vec![0; (step + overlap).min(end - start)];
+    let mut block_end = end;
+    while block_end >= start + query.len() {
+        let offset = block_end.saturating_sub(step + overlap).max(start);
+        let read = read_full(reader, &mut buf[..block_end - offset], offset)?;
+        if let Some(ix) = finder.rfind(&buf[..read]) {
+            return Ok(Some(offset + ix));
+        }
+        if offset == start {
+            break;
+        }
+        block_end = offset + overlap;
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_across_blocks() {
+        let mut text = vec![b'x'; BLOCK_LEN * 3];
+        let needle = b"needle";
+        let positions = [10, BLOCK_LEN - 3, 2 * BLOCK_LEN + 1];
+        for position in positions {
+            text[position..position + needle.len()].copy_from_slice(needle);
+        }
+        let text = text.as_slice();
+
+        let mut found = Vec::new();
+        let mut start = 0;
+        while let Some(position) = find_next(text, needle, start, text.len()).unwrap() {
+            found.push(position);
+            start = position + 1;
+        }
+        assert_eq!(found, positions);
+
+        let mut found = Vec::new();
+        let mut end = text.len();
+        while let Some(position) = find_previous(text, needle, 0, end).unwrap() {
+            found.push(position);
+            end = position + needle.len() - 1;
+        }
+        found.reverse();
+        assert_eq!(found, positions);
+
+        // Matches must lie entirely within the range.
+        assert_eq!(find_next(text, needle, 11, BLOCK_LEN).unwrap(), None);
+        assert_eq!(
+            find_previous(text, needle, 0, BLOCK_LEN + 2).unwrap(),
+            Some(10)
+        );
+        assert_eq!(find_next(text, b"", 0, text.len()).unwrap(), None);
+    }
+
+    #[test]
+    fn test_skip_lines() {
+        let text = b"one\ntwo\nthree".as_slice();
+        assert_eq!(skip_lines(text, 0, text.len(), 0).unwrap(), Some(0));
+        assert_eq!(skip_lines(text, 0, text.len(), 2).unwrap(), Some(8));
+        assert_eq!(skip_lines(text, 4, text.len(), 1).unwrap(), Some(8));
+        assert_eq!(skip_lines(text, 0, text.len(), 3).unwrap(), None);
+    }
+}
@@ -0,0 +1,482 @@
+use std::sync::Arc;
+
+use anyhow::Result;
+use fuzzy::{StringMatch, StringMatchCandidate, match_strings};
+use gpui::{
+    App, Context, DismissEvent, Entity, EventEmitter, FocusHandle, Focusable, Render, Task,
+    WeakEntity, Window, actions,
+};
+use picker::{Picker, PickerDelegate};
+use ui::{HighlightedLabel, ListItem, ListItemSpacing, Tooltip, prelude::*};
+use util::ResultExt as _;
+use workspace::{LayoutEntry, ModalView, Workspace, notifications::DetachAndPromptErr as _};
+
+actions!(
+    layout_selector,
+    [
+        /// Toggles the layout selector, which switches between, saves, and deletes the named
+        /// layouts of the current project.
+        Toggle
+    ]
+);
+
+pub fn init(cx: &mut App) {
+    cx.observe_new(|workspace: &mut Workspace, _, _| {
+        workspace.register_action(toggle);
+    })
+    .detach();
+}
+
+fn toggle(workspace: &mut Workspace, _: &Toggle, window: &mut Window, cx: &mut Context<Workspace>) {
+    let layouts = match workspace.layouts(cx) {
+        Ok(layouts) => layouts,
+        Err(error) => {
+            workspace.show_error(&error, cx);
+            return;
+        }
+    };
+    let weak_workspace = cx.entity().downgrade();
+    workspace.toggle_modal(window, cx, |window, cx| {
+        let delegate =
+            LayoutSelectorDelegate::new(weak_workspace, cx.entity().downgrade(), layouts);
+        LayoutSelector::new(delegate, window, cx)
+    });
+}
+
+pub struct LayoutSelector {
+    picker: Entity<Picker<LayoutSelectorDelegate>>,
+}
+
+impl ModalView for LayoutSelector {}
+
+impl EventEmitter<DismissEvent> for LayoutSelector {}
+
+impl Focusable for LayoutSelector {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.picker.focus_handle(cx)
+    }
+}
+
+impl Render for LayoutSelector {
+    fn render(&mut self, _: &mut Window, _: &mut Context<Self>) -> impl IntoElement {
+        v_flex().w(rems(30.)).child(self.picker.clone())
+    }
+}
+
+impl LayoutSelector {
+    fn new(delegate: LayoutSelectorDelegate, window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let picker = cx.new(|cx| Picker::uniform_list(delegate, window, cx));
+        Self { picker }
+    }
+}
+
+pub struct LayoutSelectorDelegate {
+    workspace: WeakEntity<Workspace>,
+    selector: WeakEntity<LayoutSelector>,
+    layouts: Vec<LayoutEntry>,
+    matches: Vec<StringMatch>,
+    /// The name to save the current layout under, when the query doesn't name an existing
+    /// layout.
+    new_layout_name: Option<String>,
+    selected_index: usize,
+}
+
+impl LayoutSelectorDelegate {
+    fn new(
+        workspace: WeakEntity<Workspace>,
+        selector: WeakEntity<LayoutSelector>,
+        layouts: Vec<LayoutEntry>,
+    ) -> Self {
+        let matches = layouts
+            .iter()
+            .enumerate()
+            .map(|(ix, layout)| StringMatch {
+                candidate_id: ix,
+                score: 0.0,
+                positions: Vec::new(),
+                string: layout.name.clone(),
+            })
+            .collect();
+        Self {
+            workspace,
+            selector,
+            layouts,
+            matches,
+            new_layout_name: None,
+            selected_index: 0,
+        }
+    }
+
+    fn save_layout(&self, name: String, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        workspace
+            .read(cx)
+            .save_layout(name, window, cx)
+            .detach_and_prompt_err("Failed to save layout", window, cx, |_, _, _| None);
+    }
+
+    fn toggle_default(&self, ix: usize, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(layout) = self.layout_at(ix) else {
+            return;
+        };
+        let name = (!layout.is_default).then(|| layout.name.clone());
+        self.update_layouts(
+            |workspace, cx| workspace.set_default_layout(name, cx),
+            window,
+            cx,
+        );
+    }
+
+    fn delete_layout(&self, ix: usize, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        let Some(layout) = self.layout_at(ix) else {
+            return;
+        };
+        let name = layout.name.clone();
+        self.update_layouts(
+            |workspace, cx| workspace.delete_layout(name, cx),
+            window,
+            cx,
+        );
+    }
+
+    /// Runs a change to the workspace's layouts, then refreshes the list.
+    fn update_layouts(
+        &self,
+        update: impl FnOnce(&Workspace, &App) -> Task<Result<()>>,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) {
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let task = update(workspace.read(cx), cx);
+        cx.spawn_in(window, async move |picker, cx| {
+            task.await?;
+            picker.update_in(cx, |picker, window, cx| {
+                if let Some(layouts) = workspace.read(cx).layouts(cx).log_err() {
+                    picker.delegate.layouts = layouts;
+                }
+                picker.refresh(window, cx);
+            })
+        })
+        .detach_and_prompt_err("Failed to update layouts", window, cx, |_, _, _| None);
+    }
+
+    fn layout_at(&self, ix: usize) -> Option<&LayoutEntry> {
+        let mat = self.matches.get(ix)?;
+        self.layouts.get(mat.candidate_id)
+    }
+}
+
+impl PickerDelegate for LayoutSelectorDelegate {
+    type ListItem = ListItem;
+
+    fn placeholder_text(&self, _: &mut Window, _: &mut App) -> Arc<str> {
+        "Switch to a layout, or type a name to save the current one...".into()
+    }
+
+    fn match_count(&self) -> usize {
+        self.matches.len() + usize::from(self.new_layout_name.is_some())
+    }
+
+    fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    fn set_selected_index(&mut self, ix: usize, _: &mut Window, _: &mut Context<Picker<Self>>) {
+        self.selected_index = ix;
+    }
+
+    fn update_matches(
+        &mut self,
+        query: String,
+        window: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Task<()> {
+        let background = cx.background_executor().clone();
+        let candidates = self
+            .layouts
+            .iter()
+            .enumerate()
+            .map(|(id, layout)| StringMatchCandidate::new(id, &layout.name))
+            .collect::<Vec<_>>();
+        let name = query.trim().to_string();
+        let new_layout_name = (!name.is_empty()
+            && !self.layouts.iter().any(|layout| layout.name == name))
+        .then_some(name);
+
+        cx.spawn_in(window, async move |this, cx| {
+            let matches = if query.is_empty() {
+                candidates
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, candidate)| StringMatch {
+                        candidate_id: index,
+                        string: candidate.string,
+                        positions: Vec::new(),
+                        score: 0.0,
+                    })
+                    .collect()
+            } else {
+                match_strings(
+                    &candidates,
+                    &query,
+                    false,
+                    true,
+                    100,
+                    &Default::default(),
+                    background,
+                )
+                .await
+            };
+
+            this.update(cx, |this, cx| {
+                let delegate = &mut this.delegate;
+                delegate.matches = matches;
+                delegate.new_layout_name = new_layout_name;
+                delegate.selected_index = delegate
+                    .selected_index
+                    .min(delegate.match_count().saturating_sub(1));
+                cx.notify();
+            })
+            .log_err();
+        })
+    }
+
+    fn confirm(&mut self, secondary: bool, window: &mut Window, cx: &mut Context<Picker<Self>>) {
+        if let Some(layout) = self.layout_at(self.selected_index) {
+            let name = layout.name.clone();
+            if secondary {
+                self.save_layout(name, window, cx);
+            } else if let Some(workspace) = self.workspace.upgrade() {
+                workspace.update(cx, |workspace, cx| {
+                    workspace
+                        .switch_to_layout(&name, window, cx)
+                        .detach_and_prompt_err("Failed to switch layout", window, cx, |_, _, _| {
+                            None
+                        });
+                });
+            }
+        } else if let Some(name) = self.new_layout_name.clone() {
+            self.save_layout(name, window, cx);
+        } else {
+            return;
+        }
+        self.dismissed(window, cx);
+    }
+
+    fn dismissed(&mut self, _: &mut Window, cx: &mut Context<Picker<Self>>) {
+        self.selector.update(cx, |_, cx| cx.emit(DismissEvent)).ok();
+    }
+
+    fn no_matches_text(&self, _: &mut Window, _: &mut App) -> Option<SharedString> {
+        Some("No layouts saved for this project yet. Type a name to save one.".into())
+    }
+
+    fn render_match(
+        &self,
+        ix: usize,
+        selected: bool,
+        _: &mut Window,
+        cx: &mut Context<Picker<Self>>,
+    ) -> Option<Self::ListItem> {
+        let Some(mat) = self.matches.get(ix) else {
+            let name = self.new_layout_name.as_ref()?;
+            return Some(
+                ListItem::new(ix)
+                    .inset(true)
+                    .spacing(ListItemSpacing::Sparse)
+                    .toggle_state(selected)
+                    .start_slot(Icon::new(IconName::Plus).color(Color::Muted))
+                    .child(Label::new(format!(
+                        "Save Current Layout as \u{201c}{name}\u{201d}"
+                    ))),
+            );
+        };
+        let layout = self.layouts.get(mat.candidate_id)?;
+
+        let default_button = IconButton::new(
+            ("toggle-default", ix),
+            if layout.is_default {
+                IconName::StarFilled
+            } else {
+                IconName::Star
+            },
+        )
+        .icon_size(IconSize::Small)
+        .toggle_state(layout.is_default)
+        .tooltip(Tooltip::text(if layout.is_default {
+            "Stop Opening the Project with This Layout"
+        } else {
+            "Open the Project with This Layout"
+        }))
+        .on_click(cx.listener(move |picker, _, window, cx| {
+            cx.stop_propagation();
+            picker.delegate.toggle_default(ix, window, cx);
+        }));
+        let delete_button = IconButton::new(("delete", ix), IconName::Trash)
+            .icon_size(IconSize::Small)
+            .tooltip(Tooltip::text("Delete Layout"))
+            .on_click(cx.listener(move |picker, _, window, cx| {
+                cx.stop_propagation();
+                picker.delegate.delete_layout(ix, window, cx);
+            }));
+
+        Some(
+            ListItem::new(ix)
+                .inset(true)
+                .spacing(ListItemSpacing::Sparse)
+                .toggle_state(selected)
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .child(HighlightedLabel::new(
+                            layout.name.clone(),
+                            mat.positions.clone(),
+                        ))
+                        .when(layout.is_default, |this| {
+                            this.child(
+                                Label::new("Default")
+                                    .size(LabelSize::Small)
+                                    .color(Color::Muted),
+                            )
+                        }),
+                )
+                .end_hover_slot(h_flex().gap_1().child(default_button).child(delete_button)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::{TestAppContext, VisualTestContext};
+    use project::{FakeFs, Project};
+    use workspace::{AppState, MultiWorkspace};
+
+    async fn open_layout_selector(
+        layouts: Vec<LayoutEntry>,
+        cx: &mut TestAppContext,
+    ) -> (
+        Entity<Picker<LayoutSelectorDelegate>>,
+        &mut VisualTestContext,
+    ) {
+        cx.update(AppState::test);
+
+        let fs = FakeFs::new(cx.executor());
+        let project = Project::test(fs, [], cx).await;
+        let window = cx.add_window(|window, cx| MultiWorkspace::test_new(project, window, cx));
+        let cx = VisualTestContext::from_window(*window, cx).into_mut();
+        let workspace = window
+            .read_with(cx, |multi_workspace, _| multi_workspace.workspace().clone())
+            .unwrap();
+
+        let picker = workspace.update_in(cx, |workspace, window, cx| {
+            let weak_workspace = cx.entity().downgrade();
+            workspace.toggle_modal(window, cx, |window, cx| {
+                let delegate =
+                    LayoutSelectorDelegate::new(weak_workspace, cx.entity().downgrade(), layouts);
+                LayoutSelector::new(delegate, window, cx)
+            });
+            workspace
+                .active_modal::<LayoutSelector>(cx)
+                .expect("layout selector is not open")
+                .read(cx)
+                .picker
+                .clone()
+        });
+        (picker, cx)
+    }
+
+    fn layout(name: &str, is_default: bool) -> LayoutEntry {
+        LayoutEntry {
+            name: name.to_string(),
+            is_default,
+        }
+    }
+
+    async fn set_query(
+        picker: &Entity<Picker<LayoutSelectorDelegate>>,
+        query: &str,
+        cx: &mut VisualTestContext,
+    ) {
+        picker
+            .update_in(cx, |picker, window, cx| {
+                picker
+                    .delegate
+                    .update_matches(query.to_string(), window, cx)
+            })
+            .await;
+    }
+
+    fn matched_names(
+        picker: &Entity<Picker<LayoutSelectorDelegate>>,
+        cx: &mut VisualTestContext,
+    ) -> (Vec<String>, Option<String>) {
+        picker.read_with(cx, |picker, _| {
+            let delegate = &picker.delegate;
+            let names = delegate
+                .matches
+                .iter()
+                .map(|mat| mat.string.clone())
+                .collect();
+            (names, delegate.new_layout_name.clone())
+        })
+    }
+
+    #[gpui::test]
+    async fn test_layout_selector_offers_to_save_new_names(cx: &mut TestAppContext) {
+        let (picker, cx) =
+            open_layout_selector(vec![layout("Debugging", false), layout("Review", true)], cx)
+                .await;
+
+        assert_eq!(
+            matched_names(&picker, cx),
+            (vec!["Debugging".to_string(), "Review".to_string()], None)
+        );
+
+        // A query that doesn't name a saved layout can be saved as a new one.
+        set_query(&picker, "Debug", cx).await;
+        assert_eq!(
+            matched_names(&picker, cx),
+            (vec!["Debugging".to_string()], Some("Debug".to_string()))
+        );
+        picker.read_with(cx, |picker, _| assert_eq!(picker.delegate.match_count(), 2));
+
+        // Naming a saved layout exactly doesn't offer to save over it.
+        set_query(&picker, "Review", cx).await;
+        assert_eq!(
+            matched_names(&picker, cx),
+            (vec!["Review".to_string()], None)
+        );
+
+        set_query(&picker, "   ", cx).await;
+        picker.read_with(cx, |picker, _| {
+            assert_eq!(picker.delegate.new_layout_name, None);
+        });
+    }
+
+    #[gpui::test]
+    async fn test_layout_selector_clamps_selection(cx: &mut TestAppContext) {
+        let (picker, cx) = open_layout_selector(
+            vec![
+                layout("Debugging", false),
+                layout("Review", false),
+                layout("Writing", false),
+            ],
+            cx,
+        )
+        .await;
+
+        picker.update_in(cx, |picker, window, cx| {
+            picker.delegate.set_selected_index(2, window, cx);
+        });
+        set_query(&picker, "Review", cx).await;
+        picker.read_with(cx, |picker, _| {
+            assert_eq!(picker.delegate.selected_index(), 0);
+            assert_eq!(picker.delegate.layout_at(0), Some(&layout("Review", false)));
+        });
+    }
+}
@@ -310,6 +310,18 @@ pub fn snippets_dir() -> &'static PathBuf {
     SNIPPETS_DIR.get_or_init(|| config_dir().join("snippets"))
 }
 
+/// Returns the path to the directory searched first for Hunspell dictionaries.
+pub fn dictionaries_dir() -> &'static PathBuf {
+    static DICTIONARIES_DIR: OnceLock<PathBuf> = OnceLock::new();
+    DICTIONARIES_DIR.get_or_init(|| config_dir().join("dictionaries"))
+}
+
+/// Returns the path to the user's spell checking dictionary, with one word per line.
+pub fn user_dictionary_file() -> &'static PathBuf {
+    static USER_DICTIONARY_FILE: OnceLock<PathBuf> = OnceLock::new();
+    USER_DICTIONARY_FILE.get_or_init(|| config_dir().join("dictionary.txt"))
+}
+
 // Returns old path to contexts directory.
 // Fallback
 fn text_threads_dir_fallback() -> &'static PathBuf {
@@ -461,6 +473,20 @@ pub fn local_tasks_file_relative_path() -> &'static RelPath {
     *CACHED
 }
 
+/// Returns the relative path to a `keymap.json` file within a project.
+pub fn local_keymap_file_relative_path() -> &'static RelPath {
+    static CACHED: LazyLock<&'static RelPath> =
+        LazyLock::new(|| RelPath::unix(".zed/keymap.json").unwrap());
+    *CACHED
+}
+
+/// Returns the relative path to a project's spell checking dictionary.
+pub fn local_dictionary_file_relative_path() -> &'static RelPath {
+    static CACHED: LazyLock<&'static RelPath> =
+        LazyLock::new(|| RelPath::unix(".zed/dictionary.txt").unwrap());
+    *CACHED
+}
+
 /// Returns the relative path to a `.vscode/tasks.json` file within a project.
 pub fn local_vscode_tasks_file_relative_path() -> &'static RelPath {
     static CACHED: LazyLock<&'static RelPath> =
@@ -0,0 +1,483 @@
+mod ports_panel_settings;
+
+use anyhow::Result;
+use editor::Editor;
+use gpui::{
+    Action, App, AsyncWindowContext, Context, DismissEvent, Entity, EventEmitter, FocusHandle,
+    Focusable, Pixels, Render, Subscription, Task, WeakEntity, Window, actions, rems,
+};
+use project::{
+    Fs,
+    port_store::{PortForward, PortForwardSource, PortForwardStatus, PortStore},
+};
+use settings::{Settings, SettingsStore};
+use std::sync::Arc;
+use ui::{ListItem, ListItemSpacing, Tab, Tooltip, prelude::*};
+use workspace::{
+    ModalView, Workspace,
+    dock::{DockPosition, Panel, PanelEvent},
+};
+
+pub use ports_panel_settings::PortsPanelSettings;
+
+const PORTS_PANEL_KEY: &str = "PortsPanel";
+
+actions!(
+    ports_panel,
+    [
+        /// Toggles focus on the ports panel.
+        ToggleFocus,
+        /// Forwards a port on the remote host to the local machine.
+        ForwardPort,
+    ]
+);
+
+pub fn init(cx: &mut App) {
+    cx.observe_new(|workspace: &mut Workspace, _, _| {
+        workspace
+            .register_action(|workspace, _: &ToggleFocus, window, cx| {
+                workspace.toggle_panel_focus::<PortsPanel>(window, cx);
+            })
+            .register_action(|workspace, _: &ForwardPort, window, cx| {
+                let port_store = workspace.project().read(cx).port_store();
+                if !port_store.read(cx).is_remote() {
+                    return;
+                }
+                workspace.toggle_modal(window, cx, move |window, cx| {
+                    PortInputModal::new(port_store, PortInput::Forward, window, cx)
+                });
+            });
+    })
+    .detach();
+}
+
+pub struct PortsPanel {
+    workspace: WeakEntity<Workspace>,
+    port_store: Entity<PortStore>,
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl PortsPanel {
+    pub fn new(workspace: &mut Workspace, cx: &mut Context<Workspace>) -> Entity<Self> {
+        let fs = workspace.app_state().fs.clone();
+        let port_store = workspace.project().read(cx).port_store();
+        let workspace_handle = workspace.weak_handle();
+
+        cx.new(|cx| Self {
+            _subscriptions: vec![
+                cx.observe(&port_store, |_, _, cx| cx.notify()),
+                cx.observe_global::<SettingsStore>(|_, cx| cx.notify()),
+            ],
+            workspace: workspace_handle,
+            port_store,
+            fs,
+            focus_handle: cx.focus_handle(),
+        })
+    }
+
+    pub fn load(
+        workspace: WeakEntity<Workspace>,
+        cx: AsyncWindowContext,
+    ) -> Task<Result<Entity<Self>>> {
+        cx.spawn(async move |cx| workspace.update(cx, |workspace, cx| Self::new(workspace, cx)))
+    }
+
+    fn render_forward(&self, forward: &PortForward) -> impl IntoElement {
+        let remote_port = forward.remote_port;
+        let local_port = forward.local_port();
+        let title = forward
+            .label
+            .clone()
+            .unwrap_or_else(|| remote_port.to_string().into());
+        let (status, status_color): (SharedString, _) = match &forward.status {
+            PortForwardStatus::Starting => ("Forwarding…".into(), Color::Muted),
+            PortForwardStatus::Forwarded { local_port } => {
+                (format!("localhost:{local_port}").into(), Color::Accent)
+            }
+            PortForwardStatus::Failed(_) => ("Failed".into(), Color::Error),
+        };
+        let source = match forward.source {
+            PortForwardSource::Detected => "Detected on the remote host",
+            PortForwardSource::Configured => "Listed in the dev container configuration",
+            PortForwardSource::Manual => "Forwarded manually",
+        };
+        let tooltip = match &forward.status {
+            PortForwardStatus::Failed(error) => format!("{source}\n{error}"),
+            PortForwardStatus::Starting | PortForwardStatus::Forwarded { .. } => {
+                format!("{source}\nRemote port {remote_port}")
+            }
+        };
+
+        ListItem::new(("port-forward", remote_port as usize))
+            .spacing(ListItemSpacing::Sparse)
+            .start_slot(
+                Icon::new(IconName::Server)
+                    .size(IconSize::Small)
+                    .color(Color::Muted),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(title))
+                    .when(forward.label.is_some(), |this| {
+                        this.child(
+                            Label::new(remote_port.to_string())
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                        )
+                    })
+                    .child(
+                        Label::new(status)
+                            .size(LabelSize::Small)
+                            .color(status_color),
+                    ),
+            )
+            .tooltip(Tooltip::text(tooltip))
+            .when_some(local_port, |this, local_port| {
+                this.on_click(move |_, _, cx| {
+                    cx.open_url(&format!("http://localhost:{local_port}"))
+                })
+            })
+            .end_hover_slot(
+                h_flex()
+                    .gap_0p5()
+                    .when_some(local_port, |this, local_port| {
+                        this.child(
+                            IconButton::new(
+                                ("open-port", remote_port as usize),
+                                IconName::ArrowUpRight,
+                            )
+                            .icon_size(IconSize::Small)
+                            .tooltip(Tooltip::text("Open in Browser"))
+                            .on_click(move |_, _, cx| {
+                                cx.open_url(&format!("http://localhost:{local_port}"))
+                            }),
+                        )
+                    })
+                    .child(
+                        IconButton::new(("label-port", remote_port as usize), IconName::Pencil)
+                            .icon_size(IconSize::Small)
+                            .tooltip(Tooltip::text("Set Label"))
+                            .on_click({
+                                let port_store = self.port_store.clone();
+                                let workspace = self.workspace.clone();
+                                move |_, window, cx| {
+                                    let port_store = port_store.clone();
+                                    workspace
+                                        .update(cx, |workspace, cx| {
+                                            workspace.toggle_modal(window, cx, |window, cx| {
+                                                PortInputModal::new(
+                                                    port_store,
+                                                    PortInput::Label(remote_port),
+                                                    window,
+                                                    cx,
+                                                )
+                                            })
+                                        })
+                                        .ok();
+                                }
+                            }),
+                    )
+                    .child(
+                        IconButton::new(("stop-port", remote_port as usize), IconName::Close)
+                            .icon_size(IconSize::Small)
+                            .tooltip(Tooltip::text("Stop Forwarding"))
+                            .on_click({
+                                let port_store = self.port_store.clone();
+                                move |_, _, cx| {
+                                    port_store.update(cx, |port_store, cx| {
+                                        port_store.stop_forwarding(remote_port, cx)
+                                    })
+                                }
+                            }),
+                    ),
+            )
+    }
+
+    fn render_listening_port(&self, port: u16) -> impl IntoElement {
+        ListItem::new(("listening-port", port as usize))
+            .spacing(ListItemSpacing::Sparse)
+            .start_slot(
+                Icon::new(IconName::Server)
+                    .size(IconSize::Small)
+                    .color(Color::Disabled),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(Label::new(port.to_string()).color(Color::Muted))
+                    .child(
+                        Label::new("Not forwarded")
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                    ),
+            )
+            .end_hover_slot(
+                IconButton::new(("forward-port", port as usize), IconName::Plus)
+                    .icon_size(IconSize::Small)
+                    .tooltip(Tooltip::text("Forward Port"))
+                    .on_click({
+                        let port_store = self.port_store.clone();
+                        move |_, _, cx| {
+                            port_store
+                                .update(cx, |port_store, cx| port_store.forward_port(port, cx))
+                        }
+                    }),
+            )
+    }
+}
+
+impl Render for PortsPanel {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let port_store = self.port_store.read(cx);
+        let forwards = port_store
+            .forwards()
+            .map(|forward| self.render_forward(forward).into_any_element())
+            .collect::<Vec<_>>();
+        let listening_ports = port_store
+            .listening_ports()
+            .iter()
+            .filter(|port| port_store.forward(**port).is_none())
+            .map(|port| self.render_listening_port(*port).into_any_element())
+            .collect::<Vec<_>>();
+        let is_empty = forwards.is_empty() && listening_ports.is_empty();
+
+        v_flex()
+            .key_context("PortsPanel")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .px_2()
+                    .py_1()
+                    // Match the height of the tab bar so they line up.
+                    .h(Tab::container_height(cx))
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new("Ports"))
+                    .child(
+                        IconButton::new("forward-port", IconName::Plus)
+                            .icon_size(IconSize::Small)
+                            .tooltip(Tooltip::for_action_title("Forward a Port", &ForwardPort))
+                            .on_click(|_, window, cx| {
+                                window.dispatch_action(ForwardPort.boxed_clone(), cx)
+                            }),
+                    ),
+            )
+            .map(|this| {
+                if is_empty {
+                    this.child(
+                        v_flex()
+                            .gap_1()
+                            .p_4()
+                            .child(Label::new("No forwarded ports"))
+                            .child(
+                                Label::new(
+                                    "Ports are forwarded when a process on the remote host \
+                                     starts listening on them.",
+                                )
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                            ),
+                    )
+                } else {
+                    this.child(
+                        v_flex()
+                            .id("ports")
+                            .p_1()
+                            .overflow_y_scroll()
+                            .children(forwards)
+                            .children(listening_ports),
+                    )
+                }
+            })
+    }
+}
+
+impl Focusable for PortsPanel {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for PortsPanel {}
+
+impl Panel for PortsPanel {
+    fn persistent_name() -> &'static str {
+        "PortsPanel"
+    }
+
+    fn panel_key() -> &'static str {
+        PORTS_PANEL_KEY
+    }
+
+    fn position(&self, _: &Window, cx: &App) -> DockPosition {
+        PortsPanelSettings::get_global(cx).dock
+    }
+
+    fn position_is_valid(&self, _: DockPosition) -> bool {
+        true
+    }
+
+    fn set_position(&mut self, position: DockPosition, _: &mut Window, cx: &mut Context<Self>) {
+        settings::update_settings_file(self.fs.clone(), cx, move |settings, _| {
+            settings.ports_panel.get_or_insert_default().dock = Some(position.into())
+        });
+    }
+
+    fn default_size(&self, _: &Window, cx: &App) -> Pixels {
+        PortsPanelSettings::get_global(cx).default_width
+    }
+
+    fn icon(&self, _: &Window, cx: &App) -> Option<IconName> {
+        (PortsPanelSettings::get_global(cx).button && self.enabled(cx)).then_some(IconName::Server)
+    }
+
+    fn icon_tooltip(&self, _: &Window, _: &App) -> Option<&'static str> {
+        Some("Ports Panel")
+    }
+
+    fn icon_label(&self, _: &Window, cx: &App) -> Option<String> {
+        let count = self
+            .port_store
+            .read(cx)
+            .forwards()
+            .filter(|forward| forward.local_port().is_some())
+            .count();
+        (count > 0).then(|| count.to_string())
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+
+    fn activation_priority(&self) -> u32 {
+        7
+    }
+
+    fn enabled(&self, cx: &App) -> bool {
+        self.port_store.read(cx).is_remote()
+    }
+}
+
+enum PortInput {
+    Forward,
+    Label(u16),
+}
+
+struct PortInputModal {
+    port_store: Entity<PortStore>,
+    input: PortInput,
+    editor: Entity<Editor>,
+    error: Option<SharedString>,
+}
+
+impl ModalView for PortInputModal {}
+
+impl EventEmitter<DismissEvent> for PortInputModal {}
+
+impl Focusable for PortInputModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl PortInputModal {
+    fn new(
+        port_store: Entity<PortStore>,
+        input: PortInput,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let current_label = match input {
+            PortInput::Forward => None,
+            PortInput::Label(port) => port_store
+                .read(cx)
+                .forward(port)
+                .and_then(|forward| forward.label.clone()),
+        };
+        let placeholder = match input {
+            PortInput::Forward => "Port number on the remote host",
+            PortInput::Label(_) => "Port label",
+        };
+        let editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text(placeholder, window, cx);
+            if let Some(label) = current_label {
+                editor.set_text(label.to_string(), window, cx);
+                editor.select_all(&editor::actions::SelectAll, window, cx);
+            }
+            editor
+        });
+        Self {
+            port_store,
+            input,
+            editor,
+            error: None,
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, _: &mut Window, cx: &mut Context<Self>) {
+        let text = self.editor.read(cx).text(cx);
+        let text = text.trim();
+        match self.input {
+            PortInput::Forward => {
+                let Some(port) = text.parse::<u16>().ok().filter(|port| *port != 0) else {
+                    self.error = Some("Enter a port number between 1 and 65535".into());
+                    cx.notify();
+                    return;
+                };
+                self.port_store
+                    .update(cx, |port_store, cx| port_store.forward_port(port, cx));
+            }
+            PortInput::Label(port) => {
+                let label = SharedString::from(text.to_string());
+                self.port_store.update(cx, |port_store, cx| {
+                    port_store.set_label(port, Some(label), cx)
+                });
+            }
+        }
+        cx.emit(DismissEvent);
+    }
+}
+
+impl Render for PortInputModal {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let (hint, hint_color) = match (&self.error, &self.input) {
+            (Some(error), _) => (error.clone(), Color::Error),
+            (None, PortInput::Forward) => (
+                "Forward a port that a process on the remote host listens on".into(),
+                Color::Muted,
+            ),
+            (None, PortInput::Label(_)) => ("Leave empty to remove the label".into(), Color::Muted),
+        };
+
+        v_flex()
+            .w(rems(24.))
+            .elevation_2(cx)
+            .key_context("PortInput")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                div()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .px_2()
+                    .py_1()
+                    .child(self.editor.clone()),
+            )
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .child(Label::new(hint).size(LabelSize::Small).color(hint_color)),
+            )
+    }
+}
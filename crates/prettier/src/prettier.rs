@@ -3,13 +3,13 @@ use collections::{HashMap, HashSet};
 use fs::Fs;
 use gpui::{AsyncApp, Entity};
 use language::language_settings::{LanguageSettings, PrettierSettings};
-use language::{Buffer, Diff, Language};
+use language::{Anchor, Buffer, Diff, Language, ToOffsetUtf16 as _};
 use lsp::{LanguageServer, LanguageServerId};
 use node_runtime::NodeRuntime;
 use paths::default_prettier_dir;
 use serde::{Deserialize, Serialize};
 use std::{
-    ops::ControlFlow,
+    ops::{ControlFlow, Range},
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
@@ -343,11 +343,13 @@ impl Prettier {
         }))
     }
 
+    /// Formats the buffer, or only the given range of it if one is provided.
     pub async fn format(
         &self,
         buffer: &Entity<Buffer>,
         buffer_path: Option<PathBuf>,
         ignore_dir: Option<PathBuf>,
+        range: Option<Range<Anchor>>,
         request_timeout: Duration,
         cx: &mut AsyncApp,
     ) -> anyhow::Result<Diff> {
@@ -470,6 +472,15 @@ impl Prettier {
                             ignore_path,
                         );
 
+                        // Prettier's range offsets are JavaScript string indices, i.e. UTF-16 code units.
+                        let (range_start, range_end) = match &range {
+                            Some(range) => (
+                                Some(range.start.to_offset_utf16(buffer).0),
+                                Some(range.end.to_offset_utf16(buffer).0),
+                            ),
+                            None => (None, None),
+                        };
+
                         anyhow::Ok(FormatParams {
                             text: buffer.text(),
                             options: FormatOptions {
@@ -478,6 +489,8 @@ impl Prettier {
                                 plugins,
                                 prettier_options,
                                 ignore_path,
+                                range_start,
+                                range_end,
                             },
                         })
                 })
@@ -651,6 +664,10 @@ struct FormatOptions {
     path: Option<PathBuf>,
     prettier_options: Option<HashMap<String, serde_json::Value>>,
     ignore_path: Option<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range_start: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    range_end: Option<usize>,
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
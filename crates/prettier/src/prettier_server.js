@@ -206,6 +206,10 @@ async function handleMessage(message, prettier) {
       parser: params.options.parser,
       filepath: params.options.filepath,
     };
+    if (params.options.rangeStart != null && params.options.rangeEnd != null) {
+      options.rangeStart = params.options.rangeStart;
+      options.rangeEnd = params.options.rangeEnd;
+    }
     process.stderr.write(
       `Resolved config: ${JSON.stringify(resolvedConfig)}, will format file '${
         params.options.filepath || ""
@@ -0,0 +1,532 @@
+//! Module for managing bookmarks in a project.
+//!
+//! Bookmarks are anchored in their buffers, so they follow the text they were
+//! set on as the buffer is edited. They're shared with collaborators and
+//! persisted with the workspace.
+use anyhow::{Context as _, Result};
+use collections::BTreeMap;
+use gpui::{App, AppContext as _, AsyncApp, Context, Entity, EventEmitter, Subscription, Task};
+use language::{Buffer, BufferEvent, BufferSnapshot, proto::serialize_anchor};
+use rpc::{AnyProtoClient, TypedEnvelope, proto};
+use std::{ops::Range, path::Path, sync::Arc};
+use text::{Point, ToPoint as _};
+
+use crate::{ProjectPath, buffer_store::BufferStore, worktree_store::WorktreeStore};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bookmark {
+    pub position: text::Anchor,
+    pub label: Option<Arc<str>>,
+}
+
+impl Bookmark {
+    fn to_proto(&self) -> proto::Bookmark {
+        proto::Bookmark {
+            position: Some(serialize_anchor(&self.position)),
+            label: self.label.as_ref().map(|label| label.to_string()),
+        }
+    }
+
+    fn from_proto(bookmark: proto::Bookmark) -> Option<Self> {
+        Some(Self {
+            position: language::proto::deserialize_anchor(bookmark.position?)?,
+            label: bookmark.label.map(Arc::from),
+        })
+    }
+}
+
+/// A bookmark as it's stored in the workspace database.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SerializedBookmark {
+    pub row: u32,
+    pub label: Option<Arc<str>>,
+}
+
+/// A change to the bookmark on a row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BookmarkEdit {
+    /// Removes the row's bookmark, or adds one if there is none.
+    Toggle,
+    /// Sets the label of the row's bookmark, adding one if there is none.
+    SetLabel(Option<Arc<str>>),
+}
+
+struct BookmarksInFile {
+    buffer: Entity<Buffer>,
+    bookmarks: Vec<Bookmark>,
+    _subscription: Subscription,
+}
+
+impl BookmarksInFile {
+    fn new(buffer: Entity<Buffer>, cx: &mut Context<BookmarkStore>) -> Self {
+        let subscription = cx.subscribe(&buffer, |bookmark_store, buffer, event, cx| {
+            if !matches!(event, BufferEvent::FileHandleChanged) {
+                return;
+            }
+            let entity_id = buffer.entity_id();
+            let old_path = bookmark_store
+                .bookmarks
+                .iter()
+                .find(|(_, in_file)| in_file.buffer.entity_id() == entity_id)
+                .map(|(path, _)| path.clone());
+            let Some(old_path) = old_path else {
+                return;
+            };
+
+            if buffer
+                .read(cx)
+                .file()
+                .is_none_or(|file| file.disk_state().is_deleted())
+            {
+                bookmark_store.bookmarks.remove(&old_path);
+                cx.emit(BookmarkStoreEvent::BookmarksUpdated(old_path));
+                cx.notify();
+                return;
+            }
+
+            if let Some(new_path) = BookmarkStore::abs_path_from_buffer(&buffer, cx)
+                && new_path != old_path
+                && let Some(bookmarks_in_file) = bookmark_store.bookmarks.remove(&old_path)
+            {
+                bookmark_store
+                    .bookmarks
+                    .insert(new_path.clone(), bookmarks_in_file);
+                cx.emit(BookmarkStoreEvent::BookmarksUpdated(old_path));
+                cx.emit(BookmarkStoreEvent::BookmarksUpdated(new_path));
+                cx.notify();
+            }
+        });
+
+        Self {
+            buffer,
+            bookmarks: Vec::new(),
+            _subscription: subscription,
+        }
+    }
+}
+
+#[derive(Clone)]
+struct RemoteBookmarkStore {
+    upstream_client: AnyProtoClient,
+    upstream_project_id: u64,
+}
+
+#[derive(Clone)]
+enum BookmarkStoreMode {
+    Local,
+    Remote(RemoteBookmarkStore),
+}
+
+pub struct BookmarkStore {
+    buffer_store: Entity<BufferStore>,
+    worktree_store: Entity<WorktreeStore>,
+    bookmarks: BTreeMap<Arc<Path>, BookmarksInFile>,
+    downstream_client: Option<(AnyProtoClient, u64)>,
+    mode: BookmarkStoreMode,
+}
+
+pub enum BookmarkStoreEvent {
+    BookmarksUpdated(Arc<Path>),
+    BookmarksCleared(Vec<Arc<Path>>),
+}
+
+impl EventEmitter<BookmarkStoreEvent> for BookmarkStore {}
+
+impl BookmarkStore {
+    pub fn init(client: &AnyProtoClient) {
+        client.add_entity_request_handler(Self::handle_edit_bookmark);
+        client.add_entity_message_handler(Self::handle_bookmarks_for_file);
+    }
+
+    pub fn local(worktree_store: Entity<WorktreeStore>, buffer_store: Entity<BufferStore>) -> Self {
+        Self {
+            buffer_store,
+            worktree_store,
+            bookmarks: BTreeMap::default(),
+            downstream_client: None,
+            mode: BookmarkStoreMode::Local,
+        }
+    }
+
+    pub(crate) fn remote(
+        upstream_project_id: u64,
+        upstream_client: AnyProtoClient,
+        buffer_store: Entity<BufferStore>,
+        worktree_store: Entity<WorktreeStore>,
+    ) -> Self {
+        Self {
+            buffer_store,
+            worktree_store,
+            bookmarks: BTreeMap::default(),
+            downstream_client: None,
+            mode: BookmarkStoreMode::Remote(RemoteBookmarkStore {
+                upstream_client,
+                upstream_project_id,
+            }),
+        }
+    }
+
+    pub fn shared(&mut self, project_id: u64, downstream_client: AnyProtoClient) {
+        self.downstream_client = Some((downstream_client, project_id));
+    }
+
+    pub(crate) fn unshared(&mut self, cx: &mut Context<Self>) {
+        self.downstream_client.take();
+        cx.notify();
+    }
+
+    async fn handle_bookmarks_for_file(
+        this: Entity<Self>,
+        message: TypedEnvelope<proto::BookmarksForFile>,
+        mut cx: AsyncApp,
+    ) -> Result<()> {
+        let abs_path = Arc::<Path>::from(Path::new(&message.payload.path));
+        if message.payload.bookmarks.is_empty() {
+            this.update(&mut cx, |this, cx| {
+                if this.bookmarks.remove(&abs_path).is_some() {
+                    cx.emit(BookmarkStoreEvent::BookmarksUpdated(abs_path));
+                    cx.notify();
+                }
+            });
+            return Ok(());
+        }
+
+        let buffer = this
+            .update(&mut cx, |this, cx| {
+                let path = this
+                    .worktree_store
+                    .read(cx)
+                    .project_path_for_absolute_path(&abs_path, cx)?;
+                Some(
+                    this.buffer_store
+                        .update(cx, |this, cx| this.open_buffer(path, cx)),
+                )
+            })
+            .context("Invalid project path")?
+            .await?;
+
+        this.update(&mut cx, move |this, cx| {
+            let bookmarks_in_file = this
+                .bookmarks
+                .entry(abs_path.clone())
+                .or_insert_with(|| BookmarksInFile::new(buffer, cx));
+            bookmarks_in_file.bookmarks = message
+                .payload
+                .bookmarks
+                .into_iter()
+                .filter_map(Bookmark::from_proto)
+                .collect();
+            cx.emit(BookmarkStoreEvent::BookmarksUpdated(abs_path));
+            cx.notify();
+        });
+
+        Ok(())
+    }
+
+    async fn handle_edit_bookmark(
+        this: Entity<Self>,
+        message: TypedEnvelope<proto::EditBookmark>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let path = this
+            .update(&mut cx, |this, cx| {
+                this.worktree_store
+                    .read(cx)
+                    .project_path_for_absolute_path(Path::new(&message.payload.path), cx)
+            })
+            .context("Could not resolve provided abs path")?;
+        let buffer = this
+            .update(&mut cx, |this, cx| {
+                this.buffer_store.read(cx).get_by_path(&path)
+            })
+            .context("Could not find buffer for a given path")?;
+        let bookmark = message
+            .payload
+            .bookmark
+            .context("Bookmark not present in RPC payload")?;
+        let bookmark = Bookmark::from_proto(bookmark).context("Could not deserialize bookmark")?;
+        let edit = match proto::BookmarkEdit::from_i32(message.payload.edit) {
+            Some(proto::BookmarkEdit::SetLabel) => BookmarkEdit::SetLabel(bookmark.label),
+            Some(proto::BookmarkEdit::Toggle) | None => BookmarkEdit::Toggle,
+        };
+
+        this.update(&mut cx, |this, cx| {
+            this.edit_bookmark(buffer, bookmark.position, edit, cx);
+        });
+        Ok(proto::Ack {})
+    }
+
+    pub(crate) fn broadcast(&self) {
+        if let Some((client, project_id)) = &self.downstream_client {
+            for (path, bookmarks_in_file) in &self.bookmarks {
+                client
+                    .send(proto::BookmarksForFile {
+                        project_id: *project_id,
+                        path: path.to_string_lossy().into_owned(),
+                        bookmarks: bookmarks_in_file
+                            .bookmarks
+                            .iter()
+                            .map(Bookmark::to_proto)
+                            .collect(),
+                    })
+                    .ok();
+            }
+        }
+    }
+
+    pub fn abs_path_from_buffer(buffer: &Entity<Buffer>, cx: &App) -> Option<Arc<Path>> {
+        worktree::File::from_dyn(buffer.read(cx).file())
+            .map(|file| file.worktree.read(cx).absolutize(&file.path))
+            .map(Arc::<Path>::from)
+    }
+
+    /// Adds or removes the bookmark on the row containing `position`.
+    pub fn toggle_bookmark(
+        &mut self,
+        buffer: Entity<Buffer>,
+        position: text::Anchor,
+        cx: &mut Context<Self>,
+    ) {
+        self.edit_bookmark(buffer, position, BookmarkEdit::Toggle, cx);
+    }
+
+    /// Labels the bookmark on the row containing `position`, adding one if
+    /// the row has no bookmark.
+    pub fn set_bookmark_label(
+        &mut self,
+        buffer: Entity<Buffer>,
+        position: text::Anchor,
+        label: Option<Arc<str>>,
+        cx: &mut Context<Self>,
+    ) {
+        self.edit_bookmark(buffer, position, BookmarkEdit::SetLabel(label), cx);
+    }
+
+    pub fn edit_bookmark(
+        &mut self,
+        buffer: Entity<Buffer>,
+        position: text::Anchor,
+        edit: BookmarkEdit,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(abs_path) = Self::abs_path_from_buffer(&buffer, cx) else {
+            return;
+        };
+        let snapshot = buffer.read(cx).text_snapshot();
+        let row = position.to_point(&snapshot).row;
+        // Bookmarks mark whole lines, so they're anchored at the start of their row.
+        let position = snapshot.anchor_after(Point::new(row, 0));
+
+        let bookmarks_in_file = self
+            .bookmarks
+            .entry(abs_path.clone())
+            .or_insert_with(|| BookmarksInFile::new(buffer, cx));
+        let existing = bookmarks_in_file
+            .bookmarks
+            .iter()
+            .position(|bookmark| bookmark.position.to_point(&snapshot).row == row);
+        let label = match &edit {
+            BookmarkEdit::Toggle => {
+                if let Some(ix) = existing {
+                    bookmarks_in_file.bookmarks.remove(ix);
+                } else {
+                    bookmarks_in_file.bookmarks.push(Bookmark {
+                        position,
+                        label: None,
+                    });
+                }
+                None
+            }
+            BookmarkEdit::SetLabel(label) => {
+                let label = label.clone().filter(|label| !label.trim().is_empty());
+                if let Some(ix) = existing {
+                    bookmarks_in_file.bookmarks[ix].label = label.clone();
+                } else {
+                    bookmarks_in_file.bookmarks.push(Bookmark {
+                        position,
+                        label: label.clone(),
+                    });
+                }
+                label
+            }
+        };
+        bookmarks_in_file
+            .bookmarks
+            .sort_by_key(|bookmark| bookmark.position.to_point(&snapshot).row);
+        if bookmarks_in_file.bookmarks.is_empty() {
+            self.bookmarks.remove(&abs_path);
+        }
+
+        if let BookmarkStoreMode::Remote(remote) = &self.mode {
+            let edit = match edit {
+                BookmarkEdit::Toggle => proto::BookmarkEdit::Toggle,
+                BookmarkEdit::SetLabel(_) => proto::BookmarkEdit::SetLabel,
+            };
+            cx.background_spawn(remote.upstream_client.request(proto::EditBookmark {
+                project_id: remote.upstream_project_id,
+                path: abs_path.to_string_lossy().into_owned(),
+                bookmark: Some(Bookmark { position, label }.to_proto()),
+                edit: edit.into(),
+            }))
+            .detach();
+        } else {
+            self.send_bookmarks_for_file(&abs_path);
+        }
+
+        cx.emit(BookmarkStoreEvent::BookmarksUpdated(abs_path));
+        cx.notify();
+    }
+
+    fn send_bookmarks_for_file(&self, abs_path: &Arc<Path>) {
+        let Some((client, project_id)) = &self.downstream_client else {
+            return;
+        };
+        let bookmarks = self
+            .bookmarks
+            .get(abs_path)
+            .map(|bookmarks_in_file| {
+                bookmarks_in_file
+                    .bookmarks
+                    .iter()
+                    .map(Bookmark::to_proto)
+                    .collect()
+            })
+            .unwrap_or_default();
+        client
+            .send(proto::BookmarksForFile {
+                project_id: *project_id,
+                path: abs_path.to_string_lossy().into_owned(),
+                bookmarks,
+            })
+            .ok();
+    }
+
+    pub fn clear_bookmarks(&mut self, cx: &mut Context<Self>) {
+        let paths = self.bookmarks.keys().cloned().collect::<Vec<_>>();
+        self.bookmarks.clear();
+        if let BookmarkStoreMode::Local = &self.mode {
+            for path in &paths {
+                self.send_bookmarks_for_file(path);
+            }
+        }
+        cx.emit(BookmarkStoreEvent::BookmarksCleared(paths));
+        cx.notify();
+    }
+
+    /// Returns the buffer's bookmarks, in order, optionally limited to a range.
+    pub fn bookmarks<'a>(
+        &'a self,
+        buffer: &Entity<Buffer>,
+        range: Option<Range<text::Anchor>>,
+        buffer_snapshot: &'a BufferSnapshot,
+        cx: &App,
+    ) -> impl Iterator<Item = &'a Bookmark> + 'a {
+        Self::abs_path_from_buffer(buffer, cx)
+            .and_then(|path| self.bookmarks.get(&path))
+            .into_iter()
+            .flat_map(move |bookmarks_in_file| {
+                bookmarks_in_file.bookmarks.iter().filter({
+                    let range = range.clone();
+                    move |bookmark| {
+                        if !buffer_snapshot.can_resolve(&bookmark.position) {
+                            return false;
+                        }
+                        range.as_ref().is_none_or(|range| {
+                            bookmark.position.cmp(&range.start, buffer_snapshot).is_ge()
+                                && bookmark.position.cmp(&range.end, buffer_snapshot).is_le()
+                        })
+                    }
+                })
+            })
+    }
+
+    /// Returns every bookmark in the project, ordered by path.
+    pub fn all_bookmarks(
+        &self,
+    ) -> impl Iterator<Item = (&Arc<Path>, &Entity<Buffer>, &[Bookmark])> + '_ {
+        self.bookmarks.iter().map(|(path, bookmarks_in_file)| {
+            (
+                path,
+                &bookmarks_in_file.buffer,
+                bookmarks_in_file.bookmarks.as_slice(),
+            )
+        })
+    }
+
+    pub fn all_serialized_bookmarks(
+        &self,
+        cx: &App,
+    ) -> BTreeMap<Arc<Path>, Vec<SerializedBookmark>> {
+        self.bookmarks
+            .iter()
+            .map(|(path, bookmarks_in_file)| {
+                let snapshot = bookmarks_in_file.buffer.read(cx).text_snapshot();
+                let bookmarks = bookmarks_in_file
+                    .bookmarks
+                    .iter()
+                    .map(|bookmark| SerializedBookmark {
+                        row: bookmark.position.to_point(&snapshot).row,
+                        label: bookmark.label.clone(),
+                    })
+                    .collect();
+                (path.clone(), bookmarks)
+            })
+            .collect()
+    }
+
+    pub fn with_serialized_bookmarks(
+        &self,
+        bookmarks: BTreeMap<Arc<Path>, Vec<SerializedBookmark>>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let BookmarkStoreMode::Local = &self.mode else {
+            return Task::ready(Ok(()));
+        };
+        let worktree_store = self.worktree_store.downgrade();
+        let buffer_store = self.buffer_store.downgrade();
+        cx.spawn(async move |this, cx| {
+            let mut new_bookmarks = BTreeMap::default();
+            for (path, serialized) in bookmarks {
+                if serialized.is_empty() {
+                    continue;
+                }
+                let (worktree, relative_path) = worktree_store
+                    .update(cx, |this, cx| {
+                        this.find_or_create_worktree(&path, false, cx)
+                    })?
+                    .await?;
+                let buffer = buffer_store
+                    .update(cx, |this, cx| {
+                        let path = ProjectPath {
+                            worktree_id: worktree.read(cx).id(),
+                            path: relative_path,
+                        };
+                        this.open_buffer(path, cx)
+                    })?
+                    .await;
+                let Ok(buffer) = buffer else {
+                    log::debug!("Skipping bookmarks in {path:?}, which couldn't be opened");
+                    continue;
+                };
+                let snapshot = buffer.read_with(cx, |buffer, _| buffer.text_snapshot());
+                let mut bookmarks_in_file =
+                    this.update(cx, |_, cx| BookmarksInFile::new(buffer, cx))?;
+                for bookmark in serialized {
+                    if bookmark.row > snapshot.max_point().row {
+                        continue;
+                    }
+                    bookmarks_in_file.bookmarks.push(Bookmark {
+                        position: snapshot.anchor_after(Point::new(bookmark.row, 0)),
+                        label: bookmark.label,
+                    });
+                }
+                new_bookmarks.insert(path, bookmarks_in_file);
+            }
+            this.update(cx, |this, cx| {
+                this.bookmarks = new_bookmarks;
+                this.broadcast();
+                cx.notify();
+            })
+        })
+    }
+}
@@ -13,7 +13,10 @@ use context_server::{ContextServer, ContextServerCommand, ContextServerId};
 use credentials_provider::CredentialsProvider;
 use futures::future::Either;
 use futures::{FutureExt as _, StreamExt as _, future::join_all};
-use gpui::{App, AsyncApp, Context, Entity, EventEmitter, Subscription, Task, WeakEntity, actions};
+use gpui::{
+    App, AsyncApp, Context, Entity, EventEmitter, SharedString, Subscription, Task, WeakEntity,
+    actions,
+};
 use http_client::HttpClient;
 use itertools::Itertools;
 use rand::Rng as _;
@@ -26,6 +29,7 @@ use util::{ResultExt as _, rel_path::RelPath};
 use crate::{
     DisableAiSettings, Project,
     project_settings::{ContextServerSettings, ProjectSettings},
+    trusted_worktrees::{TrustCapability, TrustedWorktrees, TrustedWorktreesEvent},
     worktree_store::WorktreeStore,
 };
 
@@ -452,6 +456,23 @@ impl ContextServerStore {
                     this.available_context_servers_changed(cx);
                 }
             }));
+            // Servers, defined by the project, may start once the project is trusted or grants them,
+            // and stop once it loses the trust or the grants.
+            if let Some(trusted_worktrees) = TrustedWorktrees::try_get_global(cx) {
+                subscriptions.push(cx.observe(&trusted_worktrees, |this, _, cx| {
+                    if !DisableAiSettings::get_global(cx).disable_ai {
+                        this.available_context_servers_changed(cx);
+                    }
+                }));
+                subscriptions.push(cx.subscribe(
+                    &trusted_worktrees,
+                    |this, _, _: &TrustedWorktreesEvent, cx| {
+                        if !DisableAiSettings::get_global(cx).disable_ai {
+                            this.available_context_servers_changed(cx);
+                        }
+                    },
+                ));
+            }
         }
 
         let ai_disabled = DisableAiSettings::get_global(cx).disable_ai;
@@ -930,6 +951,67 @@ impl ContextServerStore {
         })
     }
 
+    /// Drops the servers that the project's own settings add or change,
+    /// unless the project is trusted or was granted to run them.
+    fn allowed_context_server_settings(
+        &self,
+        cx: &mut Context<Self>,
+    ) -> HashMap<Arc<str>, ContextServerSettings> {
+        let Some(trusted_worktrees) = TrustedWorktrees::try_get_global(cx) else {
+            return self.context_server_settings.clone();
+        };
+        // Remote projects' settings are checked by their hosts.
+        let worktree_ids = self
+            .worktree_store
+            .read(cx)
+            .visible_worktrees(cx)
+            .filter(|worktree| worktree.read(cx).is_local())
+            .map(|worktree| worktree.read(cx).id())
+            .collect::<Vec<_>>();
+        let Some(first_worktree_id) = worktree_ids.first().copied() else {
+            return self.context_server_settings.clone();
+        };
+
+        let user_servers = ProjectSettings::get_global(cx).context_servers.clone();
+        let project_servers = self
+            .context_server_settings
+            .iter()
+            .filter(|(id, settings)| user_servers.get(*id) != Some(*settings))
+            .map(|(id, _)| {
+                let server_name = SharedString::new(Arc::clone(id));
+                // Check the worktrees whose settings define the server, not just the one the settings are resolved for.
+                let mut source_worktrees = worktree_ids
+                    .iter()
+                    .copied()
+                    .filter(|worktree_id| {
+                        trusted_worktrees
+                            .read(cx)
+                            .context_servers(&self.worktree_store, *worktree_id)
+                            .contains(&server_name)
+                    })
+                    .collect::<Vec<_>>();
+                if source_worktrees.is_empty() {
+                    source_worktrees.push(first_worktree_id);
+                }
+                (id.clone(), server_name, source_worktrees)
+            })
+            .collect::<Vec<_>>();
+
+        let mut allowed_settings = self.context_server_settings.clone();
+        for (id, server_name, source_worktrees) in project_servers {
+            let allowed = trusted_worktrees.update(cx, |trusted_worktrees, cx| {
+                let capability = TrustCapability::ContextServer(server_name);
+                source_worktrees.into_iter().all(|worktree_id| {
+                    trusted_worktrees.can_use(&self.worktree_store, worktree_id, &capability, cx)
+                })
+            });
+            if !allowed {
+                allowed_settings.remove(&id);
+            }
+        }
+        allowed_settings
+    }
+
     fn resolve_project_settings<'a>(
         worktree_store: &'a Entity<WorktreeStore>,
         cx: &'a App,
@@ -1301,9 +1383,9 @@ impl ContextServerStore {
             return Ok(());
         }
 
-        let (mut configured_servers, registry, worktree_store) = this.update(cx, |this, _| {
+        let (mut configured_servers, registry, worktree_store) = this.update(cx, |this, cx| {
             (
-                this.context_server_settings.clone(),
+                this.allowed_context_server_settings(cx),
                 this.registry.clone(),
                 this.worktree_store.clone(),
             )
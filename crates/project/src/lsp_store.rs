@@ -16,6 +16,7 @@ mod document_symbols;
 mod folding_ranges;
 mod inlay_hints;
 pub mod json_language_server_ext;
+mod linters;
 pub mod log_store;
 pub mod lsp_ext_command;
 pub mod rust_analyzer_ext;
@@ -47,7 +48,9 @@ use crate::{
     prettier_store::{self, PrettierStore, PrettierStoreEvent},
     project_settings::{BinarySettings, LspSettings, ProjectSettings},
     toolchain_store::{LocalToolchainStore, ToolchainStoreEvent},
-    trusted_worktrees::{PathTrust, TrustedWorktrees, TrustedWorktreesEvent},
+    trusted_worktrees::{
+        PathTrust, TrustCapability, TrustedWorktrees, TrustedWorktreesEvent, TrustedWorktreesStore,
+    },
     worktree_store::{WorktreeStore, WorktreeStoreEvent},
     yarn::YarnPathStore,
 };
@@ -76,7 +79,8 @@ use language::{
     ManifestDelegate, ManifestName, ModelineSettings, Patch, PointUtf16, TextBufferSnapshot,
     ToOffset, ToPointUtf16, Toolchain, Transaction, Unclipped,
     language_settings::{
-        AllLanguageSettings, FormatOnSave, Formatter, LanguageSettings, all_language_settings,
+        AllLanguageSettings, FormatOnSave, Formatter, LanguageSettings, LinterTrigger,
+        all_language_settings,
     },
     modeline, point_to_lsp,
     proto::{
@@ -303,6 +307,7 @@ pub struct LocalLspStore {
     supplementary_language_servers:
         HashMap<LanguageServerId, (LanguageServerName, Arc<LanguageServer>)>,
     prettier_store: Entity<PrettierStore>,
+    linter_runs: linters::LinterRuns,
     next_diagnostic_group_id: usize,
     diagnostics: HashMap<
         WorktreeId,
@@ -424,7 +429,12 @@ impl LocalLspStore {
         let wait_until_worktree_trust =
             TrustedWorktrees::try_get_global(cx).and_then(|trusted_worktrees| {
                 let can_trust = trusted_worktrees.update(cx, |trusted_worktrees, cx| {
-                    trusted_worktrees.can_trust(&self.worktree_store, worktree_id, cx)
+                    trusted_worktrees.can_use(
+                        &self.worktree_store,
+                        worktree_id,
+                        &TrustCapability::LanguageServers,
+                        cx,
+                    )
                 });
                 if can_trust {
                     self.restricted_worktrees_tasks.remove(&worktree_id);
@@ -433,27 +443,51 @@ impl LocalLspStore {
                     match self.restricted_worktrees_tasks.entry(worktree_id) {
                         hash_map::Entry::Occupied(o) => Some(o.get().1.clone()),
                         hash_map::Entry::Vacant(v) => {
-                            let (mut tx, rx) = watch::channel::<bool>();
+                            let (tx, rx) = watch::channel::<bool>();
+                            let tx = Rc::new(RefCell::new(tx));
                             let lsp_store = self.weak.clone();
-                            let subscription = cx.subscribe(&trusted_worktrees, move |_, e, cx| {
-                                if let TrustedWorktreesEvent::Trusted(_, trusted_paths) = e {
-                                    if trusted_paths.contains(&PathTrust::Worktree(worktree_id)) {
-                                        tx.blocking_send(true).ok();
-                                        lsp_store
-                                            .update(cx, |lsp_store, _| {
-                                                if let Some(local_lsp_store) =
-                                                    lsp_store.as_local_mut()
-                                                {
-                                                    local_lsp_store
-                                                        .restricted_worktrees_tasks
-                                                        .remove(&worktree_id);
-                                                }
-                                            })
-                                            .ok();
+                            let allow_worktree = move |cx: &mut App| {
+                                tx.borrow_mut().blocking_send(true).ok();
+                                lsp_store
+                                    .update(cx, |lsp_store, _| {
+                                        if let Some(local_lsp_store) = lsp_store.as_local_mut() {
+                                            local_lsp_store
+                                                .restricted_worktrees_tasks
+                                                .remove(&worktree_id);
+                                        }
+                                    })
+                                    .ok();
+                            };
+                            let trust_subscription = cx.subscribe(&trusted_worktrees, {
+                                let allow_worktree = allow_worktree.clone();
+                                move |_, e, cx| {
+                                    if let TrustedWorktreesEvent::Trusted(_, trusted_paths) = e {
+                                        if trusted_paths.contains(&PathTrust::Worktree(worktree_id))
+                                        {
+                                            allow_worktree(cx);
+                                        }
                                     }
                                 }
                             });
-                            v.insert((subscription, rx.clone()));
+                            let worktree_store = self.worktree_store.downgrade();
+                            let grant_subscription =
+                                cx.observe(&trusted_worktrees, move |trusted_worktrees, cx| {
+                                    let Some(worktree_store) = worktree_store.upgrade() else {
+                                        return;
+                                    };
+                                    if trusted_worktrees.read(cx).is_granted(
+                                        &worktree_store,
+                                        worktree_id,
+                                        &TrustCapability::LanguageServers,
+                                        cx,
+                                    ) {
+                                        allow_worktree(cx);
+                                    }
+                                });
+                            v.insert((
+                                Subscription::join(trust_subscription, grant_subscription),
+                                rx.clone(),
+                            ));
                             Some(rx)
                         }
                     }
@@ -844,7 +878,9 @@ impl LocalLspStore {
                                     registration_id: None,
                                 }],
                                 |_, diagnostic, cx| match diagnostic.source_kind {
-                                    DiagnosticSourceKind::Other | DiagnosticSourceKind::Pushed => {
+                                    DiagnosticSourceKind::Other
+                                    | DiagnosticSourceKind::Pushed
+                                    | DiagnosticSourceKind::SpellCheck => {
                                         adapter.retain_old_diagnostic(diagnostic, cx)
                                     }
                                     DiagnosticSourceKind::Pulled => true,
@@ -1608,13 +1644,33 @@ impl LocalLspStore {
                 })
             })?;
 
+        // With `format_on_save: modified`, `Project::format` provides the ranges
+        // changed since the last commit, and only those ranges are formatted.
+        let modified_ranges = match (trigger, &settings.format_on_save) {
+            (FormatTrigger::Save, FormatOnSave::Modified) => buffer.ranges.as_deref(),
+            _ => None,
+        };
+
         // handle whitespace formatting
         if settings.remove_trailing_whitespace_on_save {
             zlog::trace!(logger => "removing trailing whitespace");
-            let diff = buffer
-                .handle
-                .read_with(cx, |buffer, cx| buffer.remove_trailing_whitespace(cx))
-                .await;
+            let (diff, modified_offsets) = buffer.handle.read_with(cx, |buffer, cx| {
+                let modified_offsets = modified_ranges.map(|ranges| {
+                    ranges
+                        .iter()
+                        .map(|range| range.to_offset(buffer))
+                        .collect::<Vec<_>>()
+                });
+                (buffer.remove_trailing_whitespace(cx), modified_offsets)
+            });
+            let mut diff = diff.await;
+            if let Some(modified_offsets) = modified_offsets {
+                diff.edits.retain(|(range, _)| {
+                    modified_offsets
+                        .iter()
+                        .any(|modified| modified.start <= range.start && range.end <= modified.end)
+                });
+            }
             extend_formatting_transaction(buffer, formatting_transaction_id, cx, |buffer, cx| {
                 buffer.apply_diff(diff, cx);
             })?;
@@ -1623,7 +1679,14 @@ impl LocalLspStore {
         if settings.ensure_final_newline_on_save {
             zlog::trace!(logger => "ensuring final newline");
             extend_formatting_transaction(buffer, formatting_transaction_id, cx, |buffer, cx| {
-                buffer.ensure_final_newline(cx);
+                let end_is_modified = modified_ranges.is_none_or(|ranges| {
+                    ranges
+                        .iter()
+                        .any(|range| range.end.to_offset(buffer) == buffer.len())
+                });
+                if end_is_modified {
+                    buffer.ensure_final_newline(cx);
+                }
             })?;
         }
 
@@ -1655,7 +1718,8 @@ impl LocalLspStore {
 
         let formatters = match (trigger, &settings.format_on_save) {
             (FormatTrigger::Save, FormatOnSave::Off) => &[],
-            (FormatTrigger::Manual, _) | (FormatTrigger::Save, FormatOnSave::On) => {
+            (FormatTrigger::Manual, _)
+            | (FormatTrigger::Save, FormatOnSave::On | FormatOnSave::Modified) => {
                 settings.formatter.as_ref()
             }
         };
@@ -1665,6 +1729,7 @@ impl LocalLspStore {
             .flatten()
             .chain(formatters);
 
+        let mut skipped_formatters = Vec::new();
         for formatter in formatters {
             let formatter = if formatter == &Formatter::Auto {
                 if settings.prettier.allowed {
@@ -1677,14 +1742,28 @@ impl LocalLspStore {
             } else {
                 formatter
             };
+            if modified_ranges.is_some() {
+                let skipped = match formatter {
+                    Formatter::External { command, .. } => Some(format!("`{command}`")),
+                    Formatter::CodeAction(action) => Some(format!("code action `{action}`")),
+                    _ => None,
+                };
+                if let Some(skipped) = skipped {
+                    zlog::warn!(logger => "Skipping formatter {formatter:?}, which cannot format only modified lines");
+                    skipped_formatters.push(skipped);
+                    continue;
+                }
+            }
             if let Err(err) = Self::apply_formatter(
                 formatter,
                 &lsp_store,
                 buffer,
+                modified_ranges,
                 formatting_transaction_id,
                 &adapters_and_servers,
                 &settings,
                 request_timeout,
+                &mut skipped_formatters,
                 logger,
                 cx,
             )
@@ -1694,6 +1773,22 @@ impl LocalLspStore {
             }
         }
 
+        if !skipped_formatters.is_empty() {
+            let file_name = buffer
+                .abs_path
+                .as_ref()
+                .and_then(|path| path.file_name())
+                .map(|name| format!(" in {}", name.to_string_lossy()))
+                .unwrap_or_default();
+            let message = format!(
+                "Only modified lines were formatted{file_name}. Skipped formatters that can only format whole files: {}.",
+                skipped_formatters.join(", ")
+            );
+            lsp_store
+                .update(cx, |_, cx| cx.emit(LspStoreEvent::Notification(message)))
+                .ok();
+        }
+
         Ok(())
     }
 
@@ -1701,10 +1796,12 @@ impl LocalLspStore {
         formatter: &Formatter,
         lsp_store: &WeakEntity<LspStore>,
         buffer: &FormattableBuffer,
+        modified_ranges: Option<&[Range<Anchor>]>,
         formatting_transaction_id: clock::Lamport,
         adapters_and_servers: &[(Arc<CachedLspAdapter>, Arc<LanguageServer>)],
         settings: &LanguageSettings,
         request_timeout: Duration,
+        skipped_formatters: &mut Vec<String>,
         logger: zlog::Logger,
         cx: &mut AsyncApp,
     ) -> anyhow::Result<()> {
@@ -1725,22 +1822,29 @@ impl LocalLspStore {
                 let prettier = lsp_store.read_with(cx, |lsp_store, _cx| {
                     lsp_store.prettier_store().unwrap().downgrade()
                 })?;
-                let diff = prettier_store::format_with_prettier(&prettier, &buffer.handle, cx)
-                    .await
-                    .transpose()?;
-                let Some(diff) = diff else {
-                    zlog::trace!(logger => "No changes");
-                    return Ok(());
+                let ranges = match modified_ranges {
+                    Some(ranges) => ranges.iter().cloned().map(Some).collect(),
+                    None => vec![None],
                 };
+                for range in ranges {
+                    let diff =
+                        prettier_store::format_with_prettier(&prettier, &buffer.handle, range, cx)
+                            .await
+                            .transpose()?;
+                    let Some(diff) = diff else {
+                        zlog::trace!(logger => "No changes");
+                        continue;
+                    };
 
-                extend_formatting_transaction(
-                    buffer,
-                    formatting_transaction_id,
-                    cx,
-                    |buffer, cx| {
-                        buffer.apply_diff(diff, cx);
-                    },
-                )?;
+                    extend_formatting_transaction(
+                        buffer,
+                        formatting_transaction_id,
+                        cx,
+                        |buffer, cx| {
+                            buffer.apply_diff(diff, cx);
+                        },
+                    )?;
+                }
             }
             Formatter::External { command, arguments } => {
                 let logger = zlog::scoped!(logger => "command");
@@ -1787,6 +1891,20 @@ impl LocalLspStore {
                             }
                         })
                     }
+                    // Formatting only modified lines needs a server that formats ranges.
+                    settings::LanguageServerFormatterSpecifier::Current
+                        if modified_ranges.is_some() =>
+                    {
+                        adapters_and_servers
+                            .iter()
+                            .find(|(_, server)| Self::server_supports_range_formatting(server))
+                            .or_else(|| {
+                                adapters_and_servers
+                                    .iter()
+                                    .find(|(_, server)| Self::server_supports_formatting(server))
+                            })
+                            .map(|(_, server)| server.clone())
+                    }
                     settings::LanguageServerFormatterSpecifier::Current => adapters_and_servers
                         .iter()
                         .find(|(_, server)| Self::server_supports_formatting(server))
@@ -1801,6 +1919,19 @@ impl LocalLspStore {
                     return Ok(());
                 };
 
+                if modified_ranges.is_some()
+                    && !Self::server_supports_range_formatting(&language_server)
+                {
+                    zlog::warn!(
+                        logger =>
+                        "Skipping language server '{}', which cannot format only modified lines",
+                        language_server.name()
+                    );
+                    skipped_formatters
+                        .push(format!("language server `{}`", language_server.name()));
+                    return Ok(());
+                }
+
                 zlog::trace!(
                     logger =>
                     "Formatting buffer '{:?}' using language server '{:?}'",
@@ -2297,6 +2428,12 @@ impl LocalLspStore {
             || matches!(range_formatting, Some(p) if *p != OneOf::Left(false))
     }
 
+    fn server_supports_range_formatting(server: &Arc<LanguageServer>) -> bool {
+        let capabilities = server.capabilities();
+        let range_formatting = capabilities.document_range_formatting_provider.as_ref();
+        matches!(range_formatting, Some(p) if *p != OneOf::Left(false))
+    }
+
     async fn format_via_lsp(
         this: &WeakEntity<LspStore>,
         buffer: &Entity<Buffer>,
@@ -4183,6 +4320,10 @@ impl LspStore {
             .detach();
         cx.observe_global::<SettingsStore>(Self::on_settings_changed)
             .detach();
+        if let Some(trusted_worktrees) = TrustedWorktrees::try_get_global(cx) {
+            cx.subscribe(&trusted_worktrees, Self::on_trusted_worktrees_event)
+                .detach();
+        }
         subscribe_to_binary_statuses(&languages, cx).detach();
 
         let _maintain_workspace_config = {
@@ -4209,6 +4350,7 @@ impl LspStore {
                 _background_diagnostics_worker: Task::ready(()).shared(),
                 buffer_snapshots: Default::default(),
                 prettier_store,
+                linter_runs: Default::default(),
                 environment,
                 http_client,
                 fs,
@@ -4346,10 +4488,90 @@ impl LspStore {
                     }
                 }
             }
+            BufferStoreEvent::BufferDropped(buffer_id) => {
+                if let Some(local) = self.as_local_mut() {
+                    local.linter_runs.forget_buffer(*buffer_id);
+                }
+            }
             _ => {}
         }
     }
 
+    /// Stops the language servers of the worktrees that lost their trust, unless they were granted to run them.
+    /// Their buffers are registered again, so the servers wait for the worktree to be trusted again.
+    fn on_trusted_worktrees_event(
+        &mut self,
+        trusted_worktrees: Entity<TrustedWorktreesStore>,
+        event: &TrustedWorktreesEvent,
+        cx: &mut Context<Self>,
+    ) {
+        let TrustedWorktreesEvent::Restricted(worktree_store, restricted_paths) = event else {
+            return;
+        };
+        if *worktree_store != self.worktree_store.downgrade() {
+            return;
+        }
+        let Some(local) = self.as_local() else {
+            return;
+        };
+        let revoked_worktrees = restricted_paths
+            .iter()
+            .filter_map(|restricted_path| match restricted_path {
+                PathTrust::Worktree(worktree_id) => Some(*worktree_id),
+                PathTrust::AbsPath(..) => None,
+            })
+            // Servers of the worktrees that were never trusted are still waiting for it.
+            .filter(|worktree_id| {
+                !local.restricted_worktrees_tasks.contains_key(worktree_id)
+                    && !trusted_worktrees.read(cx).is_granted(
+                        &self.worktree_store,
+                        *worktree_id,
+                        &TrustCapability::LanguageServers,
+                        cx,
+                    )
+            })
+            .collect::<HashSet<_>>();
+        let servers_to_stop = local
+            .language_server_ids
+            .iter()
+            .filter(|(seed, _)| revoked_worktrees.contains(&seed.worktree_id))
+            .map(|(_, server)| LanguageServerSelector::Id(server.id))
+            .collect::<HashSet<_>>();
+        if servers_to_stop.is_empty() {
+            return;
+        }
+
+        log::info!(
+            "Stopping language servers of the worktrees that lost their trust: {revoked_worktrees:?}"
+        );
+        let buffers = self
+            .buffer_store
+            .read(cx)
+            .buffers()
+            .filter(|buffer| {
+                buffer
+                    .read(cx)
+                    .file()
+                    .is_some_and(|file| revoked_worktrees.contains(&file.worktree_id(cx)))
+            })
+            .collect::<Vec<_>>();
+        let stop_task = self.stop_local_language_servers_for_buffers(&[], servers_to_stop, cx);
+        cx.spawn(async move |lsp_store, cx| {
+            stop_task.await;
+            lsp_store.update(cx, |lsp_store, cx| {
+                for buffer in buffers {
+                    lsp_store.register_buffer_with_language_servers(
+                        &buffer,
+                        HashSet::default(),
+                        true,
+                        cx,
+                    );
+                }
+            })
+        })
+        .detach();
+    }
+
     fn on_worktree_store_event(
         &mut self,
         _: Entity<WorktreeStore>,
@@ -4436,11 +4658,18 @@ impl LspStore {
     ) {
         match event {
             language::BufferEvent::Edited { .. } => {
-                self.on_buffer_edited(buffer, cx);
+                self.on_buffer_edited(buffer.clone(), cx);
+                self.run_linters(&buffer, LinterTrigger::Change, cx);
             }
 
             language::BufferEvent::Saved => {
-                self.on_buffer_saved(buffer, cx);
+                self.on_buffer_saved(buffer.clone(), cx);
+                self.run_linters(&buffer, LinterTrigger::Save, cx);
+            }
+
+            language::BufferEvent::LanguageChanged(_) => {
+                self.run_linters(&buffer, LinterTrigger::Save, cx);
+                self.run_linters(&buffer, LinterTrigger::Change, cx);
             }
 
             language::BufferEvent::Reloaded => {
@@ -7442,9 +7671,9 @@ impl LspStore {
                                                     unchanged_buffers.contains(&document_uri)
                                                 })
                                     }
-                                    DiagnosticSourceKind::Other | DiagnosticSourceKind::Pushed => {
-                                        true
-                                    }
+                                    DiagnosticSourceKind::Other
+                                    | DiagnosticSourceKind::Pushed
+                                    | DiagnosticSourceKind::SpellCheck => true,
                                 },
                                 cx,
                             )
@@ -12387,7 +12616,9 @@ impl LspStore {
                                         unchanged_buffers.contains(&document_uri)
                                     })
                         }
-                        DiagnosticSourceKind::Other | DiagnosticSourceKind::Pushed => true,
+                        DiagnosticSourceKind::Other
+                        | DiagnosticSourceKind::Pushed
+                        | DiagnosticSourceKind::SpellCheck => true,
                     },
                     cx,
                 )
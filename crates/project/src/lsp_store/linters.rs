@@ -0,0 +1,647 @@
+//! Runs external linters configured with the `linters` language setting and
+//! reports their output as diagnostics.
+//!
+//! Each linter's diagnostics are stored under a language server ID of its own,
+//! so they're summarized, synced to collaborators, and cleared like any other
+//! diagnostic source, without a language server process behind them.
+
+use std::{
+    io,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result};
+use collections::HashMap;
+use futures::{AsyncReadExt as _, AsyncWriteExt as _, FutureExt as _};
+use gpui::{AsyncApp, Context, Entity, Task};
+use language::{
+    Buffer, DiagnosticSourceKind, Rope,
+    language_settings::{LanguageSettings, Linter, LinterInput, LinterOutput, LinterTrigger},
+};
+use lsp::{DiagnosticSeverity, LanguageServerId, NumberOrString};
+use regex::Regex;
+use serde_json::Value;
+use text::BufferId;
+use util::ResultExt as _;
+
+use crate::{
+    File, LspStore, WorktreeId,
+    trusted_worktrees::{TrustCapability, TrustedWorktrees},
+    worktree_store::WorktreeStore,
+};
+
+#[derive(Default)]
+pub(super) struct LinterRuns {
+    /// The ID that each linter's diagnostics are stored under, by linter name.
+    server_ids: HashMap<String, LanguageServerId>,
+    /// The pending or running invocation of each linter for each buffer.
+    /// Replacing a run drops its task, which cancels it and kills its process.
+    runs: HashMap<(BufferId, String), Task<()>>,
+}
+
+impl LinterRuns {
+    pub(super) fn forget_buffer(&mut self, buffer_id: BufferId) {
+        self.runs.retain(|(id, _), _| *id != buffer_id);
+    }
+}
+
+/// A buffer's contents and location, as passed to a linter.
+struct LintTarget {
+    abs_path: PathBuf,
+    working_dir: PathBuf,
+    env: Option<HashMap<String, String>>,
+    text: Rope,
+}
+
+impl LspStore {
+    /// Runs the buffer's linters that are configured to run on `trigger`.
+    pub(super) fn run_linters(
+        &mut self,
+        buffer: &Entity<Buffer>,
+        trigger: LinterTrigger,
+        cx: &mut Context<Self>,
+    ) {
+        if self.as_local().is_none() {
+            return;
+        }
+        let buffer_ref = buffer.read(cx);
+        let Some(file) = File::from_dyn(buffer_ref.file()) else {
+            return;
+        };
+        let Some(local_file) = file.as_local() else {
+            return;
+        };
+        let linters = LanguageSettings::for_buffer(buffer_ref, cx)
+            .linters
+            .iter()
+            .filter(|linter| linter.run_on == trigger)
+            .cloned()
+            .collect::<Vec<_>>();
+        if linters.is_empty() {
+            return;
+        }
+
+        let buffer_id = buffer_ref.remote_id();
+        let abs_path = local_file.abs_path(cx);
+        let worktree_id = file.worktree_id(cx);
+        let working_dir = {
+            let worktree = file.worktree.read(cx);
+            let mut worktree_path = worktree.abs_path().to_path_buf();
+            if worktree.root_entry().is_some_and(|entry| entry.is_file()) {
+                worktree_path.pop();
+            }
+            worktree_path
+        };
+
+        let Some(local) = self.as_local_mut() else {
+            return;
+        };
+        // Linters run commands from settings, which may come from the project.
+        if !worktree_is_trusted(&local.worktree_store, worktree_id, cx) {
+            return;
+        }
+
+        for linter in linters {
+            let server_id = *local
+                .linter_runs
+                .server_ids
+                .entry(linter.name.clone())
+                .or_insert_with(|| local.languages.next_language_server_id());
+            let delay = match trigger {
+                LinterTrigger::Save => Duration::ZERO,
+                LinterTrigger::Change => Duration::from_millis(linter.debounce_ms),
+            };
+            let buffer = buffer.clone();
+            let abs_path = abs_path.clone();
+            let working_dir = working_dir.clone();
+            let name = linter.name.clone();
+            let task = cx.spawn(async move |lsp_store, cx| {
+                if !delay.is_zero() {
+                    cx.background_executor().timer(delay).await;
+                }
+                let Some(env) = lsp_store
+                    .update(cx, |lsp_store, cx| {
+                        lsp_store.environment_for_buffer(&buffer, cx)
+                    })
+                    .ok()
+                else {
+                    return;
+                };
+                let target = LintTarget {
+                    abs_path,
+                    working_dir,
+                    env: env.await,
+                    text: buffer.read_with(cx, |buffer, _| buffer.as_rope().clone()),
+                };
+                let diagnostics = match run_linter(&linter, &target, cx).await {
+                    Ok(diagnostics) => diagnostics,
+                    Err(error) => {
+                        log::error!("linter {:?} failed: {error:#}", linter.name);
+                        return;
+                    }
+                };
+                let Some(uri) = lsp::Uri::from_file_path(&target.abs_path).ok() else {
+                    return;
+                };
+                lsp_store
+                    .update(cx, |lsp_store, cx| {
+                        lsp_store.update_diagnostics(
+                            server_id,
+                            lsp::PublishDiagnosticsParams {
+                                uri,
+                                diagnostics,
+                                version: None,
+                            },
+                            None,
+                            DiagnosticSourceKind::Other,
+                            &[],
+                            cx,
+                        )
+                    })
+                    .log_err();
+            });
+            local.linter_runs.runs.insert((buffer_id, name), task);
+        }
+    }
+}
+
+fn worktree_is_trusted(
+    worktree_store: &Entity<WorktreeStore>,
+    worktree_id: WorktreeId,
+    cx: &mut Context<LspStore>,
+) -> bool {
+    TrustedWorktrees::try_get_global(cx).is_none_or(|trusted_worktrees| {
+        trusted_worktrees.update(cx, |trusted_worktrees, cx| {
+            trusted_worktrees.can_use(
+                worktree_store,
+                worktree_id,
+                &TrustCapability::LanguageServers,
+                cx,
+            )
+        })
+    })
+}
+
+async fn run_linter(
+    linter: &Linter,
+    target: &LintTarget,
+    cx: &mut AsyncApp,
+) -> Result<Vec<lsp::Diagnostic>> {
+    use util::command::Stdio;
+
+    let file_name = target
+        .abs_path
+        .file_name()
+        .context("buffer has no file name")?;
+    let temp_dir = match linter.input {
+        LinterInput::Stdin => None,
+        LinterInput::TempFile => Some(tempfile::tempdir()?),
+    };
+    let temp_path = temp_dir.as_ref().map(|dir| dir.path().join(file_name));
+    if let Some(temp_path) = &temp_path {
+        smol::fs::write(temp_path, target.text.to_string()).await?;
+    }
+
+    let mut command = util::command::new_command(&linter.command);
+    if let Some(env) = &target.env {
+        command.envs(env);
+    }
+    command.current_dir(&target.working_dir);
+    let mut has_temp_path_argument = false;
+    command.args(linter.arguments.iter().map(|argument| {
+        let mut argument = argument.replace("{buffer_path}", &target.abs_path.to_string_lossy());
+        if let Some(temp_path) = &temp_path
+            && argument.contains("{temp_path}")
+        {
+            has_temp_path_argument = true;
+            argument = argument.replace("{temp_path}", &temp_path.to_string_lossy());
+        }
+        argument
+    }));
+    if let Some(temp_path) = &temp_path
+        && !has_temp_path_argument
+    {
+        command.arg(temp_path);
+    }
+
+    let mut child = command
+        .stdin(if temp_path.is_some() {
+            Stdio::null()
+        } else {
+            Stdio::piped()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .with_context(|| format!("failed to spawn {:?}", linter.command))?;
+
+    let stdin = child.stdin.take();
+    let mut stdout = child.stdout.take().context("failed to acquire stdout")?;
+    let mut stderr = child.stderr.take().context("failed to acquire stderr")?;
+    // The buffer is written while the output is read, as a linter that reports
+    // problems before reading all of its input would otherwise block forever.
+    let output = async move {
+        let write_stdin = async move {
+            let Some(mut stdin) = stdin else {
+                return Ok(());
+            };
+            for chunk in target.text.chunks() {
+                stdin.write_all(chunk.as_bytes()).await?;
+            }
+            stdin.flush().await
+        };
+        let mut stdout_bytes = Vec::new();
+        let mut stderr_bytes = Vec::new();
+        let (write_result, stdout_result, stderr_result) = futures::join!(
+            write_stdin,
+            stdout.read_to_end(&mut stdout_bytes),
+            stderr.read_to_end(&mut stderr_bytes),
+        );
+        let status = child.status().await?;
+        // Linters may exit without reading their whole input.
+        match write_result {
+            Err(error) if error.kind() == io::ErrorKind::BrokenPipe => {}
+            result => result.context("failed to write the buffer to stdin")?,
+        }
+        stdout_result.context("failed to read stdout")?;
+        stderr_result.context("failed to read stderr")?;
+        anyhow::Ok((status, stdout_bytes, stderr_bytes))
+    };
+    let timeout = Duration::from_millis(linter.timeout_ms);
+    let (status, stdout, stderr) = futures::select_biased! {
+        output = output.fuse() => output?,
+        _ = cx.background_executor().timer(timeout).fuse() => {
+            anyhow::bail!("timed out after {timeout:?}");
+        }
+    };
+
+    let stdout = String::from_utf8_lossy(&stdout);
+    // Linters usually exit with an error when they find problems, so only treat
+    // a failure without any output as an error.
+    anyhow::ensure!(
+        status.success() || !stdout.trim().is_empty(),
+        "command failed with exit code {:?}:\nstderr: {}",
+        status.code(),
+        String::from_utf8_lossy(&stderr),
+    );
+    drop(temp_dir);
+
+    let diagnostics = cx
+        .background_spawn({
+            let output_format = linter.output.clone();
+            let stdout = stdout.into_owned();
+            let name = linter.name.clone();
+            let file_name = PathBuf::from(file_name);
+            async move { parse_linter_output(&output_format, &stdout, &name, &file_name) }
+        })
+        .await?;
+    Ok(diagnostics)
+}
+
+/// A problem reported by a linter, with 1-based lines and columns.
+#[derive(Debug, Default)]
+struct LinterDiagnostic {
+    line: u32,
+    column: Option<u32>,
+    end_line: Option<u32>,
+    end_column: Option<u32>,
+    severity: Option<DiagnosticSeverity>,
+    code: Option<String>,
+    message: String,
+}
+
+impl LinterDiagnostic {
+    fn into_lsp(self, source: &str) -> lsp::Diagnostic {
+        let start_line = self.line.saturating_sub(1);
+        let start = lsp::Position::new(start_line, self.column.unwrap_or(1).saturating_sub(1));
+        let end = match (self.end_line, self.end_column) {
+            (Some(end_line), Some(end_column)) => {
+                lsp::Position::new(end_line.saturating_sub(1), end_column.saturating_sub(1))
+            }
+            // Without an end, highlight the rest of the line. Positions past the
+            // end of the line are clipped.
+            (end_line, _) => lsp::Position::new(
+                end_line.map_or(start_line, |line| line.saturating_sub(1)),
+                u32::MAX,
+            ),
+        };
+        let end = if end < start {
+            lsp::Position::new(start.line, start.character + 1)
+        } else {
+            end
+        };
+        lsp::Diagnostic {
+            range: lsp::Range::new(start, end),
+            severity: Some(self.severity.unwrap_or(DiagnosticSeverity::WARNING)),
+            code: self.code.map(NumberOrString::String),
+            source: Some(source.to_string()),
+            message: self.message,
+            ..Default::default()
+        }
+    }
+}
+
+fn parse_linter_output(
+    format: &LinterOutput,
+    output: &str,
+    source: &str,
+    file_name: &Path,
+) -> Result<Vec<lsp::Diagnostic>> {
+    let diagnostics = match format {
+        // A clean run may print nothing at all, rather than an empty document.
+        LinterOutput::Json | LinterOutput::Sarif if output.trim().is_empty() => Vec::new(),
+        LinterOutput::Json => parse_json(output, file_name)?,
+        LinterOutput::Sarif => parse_sarif(output, file_name)?,
+        LinterOutput::Regex(pattern) => parse_regex(output, pattern)?,
+    };
+    Ok(diagnostics
+        .into_iter()
+        .map(|diagnostic| diagnostic.into_lsp(source))
+        .collect())
+}
+
+fn parse_severity(severity: &str) -> Option<DiagnosticSeverity> {
+    match severity.to_ascii_lowercase().as_str() {
+        "error" | "err" | "fatal" | "critical" => Some(DiagnosticSeverity::ERROR),
+        "warning" | "warn" => Some(DiagnosticSeverity::WARNING),
+        "info" | "information" | "note" => Some(DiagnosticSeverity::INFORMATION),
+        "hint" | "style" | "none" => Some(DiagnosticSeverity::HINT),
+        _ => None,
+    }
+}
+
+/// Whether a path reported by a linter could refer to the linted file. Output
+/// for stdin is often reported as `-` or `<stdin>`, so only paths naming some
+/// other file are rejected.
+fn is_linted_file(reported_path: Option<&str>, file_name: &Path) -> bool {
+    let Some(reported_path) = reported_path else {
+        return true;
+    };
+    let reported_path = reported_path
+        .strip_prefix("file://")
+        .unwrap_or(reported_path);
+    match Path::new(reported_path).file_name() {
+        Some(reported_name) => {
+            reported_name == file_name.as_os_str()
+                || reported_path == "-"
+                || reported_path == "<stdin>"
+        }
+        None => true,
+    }
+}
+
+fn parse_json(output: &str, file_name: &Path) -> Result<Vec<LinterDiagnostic>> {
+    let value: Value = serde_json::from_str(output).context("parsing linter output as JSON")?;
+    let items = match &value {
+        Value::Array(items) => items,
+        Value::Object(object) => object
+            .get("diagnostics")
+            .or_else(|| object.get("comments"))
+            .and_then(Value::as_array)
+            .context("expected a `diagnostics` or `comments` array")?,
+        _ => anyhow::bail!("expected a JSON array or object"),
+    };
+
+    fn field<'a>(item: &'a Value, names: &[&str]) -> Option<&'a Value> {
+        names.iter().find_map(|name| item.get(*name))
+    }
+    fn number(item: &Value, names: &[&str]) -> Option<u32> {
+        field(item, names)?
+            .as_u64()
+            .and_then(|n| u32::try_from(n).ok())
+    }
+
+    Ok(items
+        .iter()
+        .filter(|item| {
+            is_linted_file(
+                field(item, &["file", "path", "filename"]).and_then(Value::as_str),
+                file_name,
+            )
+        })
+        .filter_map(|item| {
+            Some(LinterDiagnostic {
+                line: number(item, &["line", "start_line", "startLine"])?,
+                column: number(item, &["column", "start_column", "startColumn"]),
+                end_line: number(item, &["end_line", "endLine"]),
+                end_column: number(item, &["end_column", "endColumn"]),
+                severity: field(item, &["severity", "level"])
+                    .and_then(Value::as_str)
+                    .and_then(parse_severity),
+                code: field(item, &["code", "rule"]).and_then(|code| match code {
+                    Value::String(code) => Some(code.clone()),
+                    Value::Number(code) => Some(code.to_string()),
+                    _ => None,
+                }),
+                message: field(item, &["message"])?.as_str()?.to_string(),
+            })
+        })
+        .collect())
+}
+
+fn parse_sarif(output: &str, file_name: &Path) -> Result<Vec<LinterDiagnostic>> {
+    let value: Value = serde_json::from_str(output).context("parsing linter output as SARIF")?;
+    let runs = value
+        .get("runs")
+        .and_then(Value::as_array)
+        .context("expected a `runs` array")?;
+
+    let mut diagnostics = Vec::new();
+    for result in runs
+        .iter()
+        .filter_map(|run| run.get("results")?.as_array())
+        .flatten()
+    {
+        let Some(location) = result.pointer("/locations/0/physicalLocation") else {
+            continue;
+        };
+        let uri = location
+            .pointer("/artifactLocation/uri")
+            .and_then(Value::as_str);
+        if !is_linted_file(uri, file_name) {
+            continue;
+        }
+        let region = location.get("region");
+        let number = |name: &str| {
+            region?
+                .get(name)?
+                .as_u64()
+                .and_then(|n| u32::try_from(n).ok())
+        };
+        let Some(line) = number("startLine") else {
+            continue;
+        };
+        let Some(message) = result.pointer("/message/text").and_then(Value::as_str) else {
+            continue;
+        };
+        diagnostics.push(LinterDiagnostic {
+            line,
+            column: number("startColumn"),
+            end_line: number("endLine"),
+            end_column: number("endColumn"),
+            // SARIF results are warnings unless stated otherwise.
+            severity: result
+                .get("level")
+                .and_then(Value::as_str)
+                .and_then(parse_severity),
+            code: result
+                .get("ruleId")
+                .and_then(Value::as_str)
+                .map(ToString::to_string),
+            message: message.to_string(),
+        });
+    }
+    Ok(diagnostics)
+}
+
+fn parse_regex(output: &str, pattern: &str) -> Result<Vec<LinterDiagnostic>> {
+    let regex = Regex::new(pattern).context("invalid linter output regex")?;
+    anyhow::ensure!(
+        regex.capture_names().flatten().any(|name| name == "line")
+            && regex
+                .capture_names()
+                .flatten()
+                .any(|name| name == "message"),
+        "linter output regex must have `line` and `message` capture groups"
+    );
+
+    Ok(output
+        .lines()
+        .filter_map(|line| {
+            let captures = regex.captures(line)?;
+            let number = |name: &str| captures.name(name)?.as_str().parse::<u32>().ok();
+            Some(LinterDiagnostic {
+                line: number("line")?,
+                column: number("column"),
+                end_line: number("end_line"),
+                end_column: number("end_column"),
+                severity: captures
+                    .name("severity")
+                    .and_then(|severity| parse_severity(severity.as_str())),
+                code: captures.name("code").map(|code| code.as_str().to_string()),
+                message: captures.name("message")?.as_str().to_string(),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(format: LinterOutput, output: &str) -> Vec<lsp::Diagnostic> {
+        parse_linter_output(&format, output, "lint", Path::new("script.sh")).unwrap()
+    }
+
+    #[test]
+    fn test_parse_json_output() {
+        let diagnostics = parse(
+            LinterOutput::Json,
+            r#"{"comments": [
+                {"file": "-", "line": 3, "endLine": 3, "column": 5, "endColumn": 9, "level": "warning", "code": 2086, "message": "Double quote"},
+                {"file": "other.sh", "line": 1, "column": 1, "level": "error", "message": "Elsewhere"},
+                {"line": 7, "level": "style", "message": "Whole line"}
+            ]}"#,
+        );
+        assert_eq!(diagnostics.len(), 2);
+
+        assert_eq!(
+            diagnostics[0].range,
+            lsp::Range::new(lsp::Position::new(2, 4), lsp::Position::new(2, 8))
+        );
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::WARNING));
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("2086".into()))
+        );
+        assert_eq!(diagnostics[0].source.as_deref(), Some("lint"));
+        assert_eq!(diagnostics[0].message, "Double quote");
+
+        assert_eq!(
+            diagnostics[1].range,
+            lsp::Range::new(lsp::Position::new(6, 0), lsp::Position::new(6, u32::MAX))
+        );
+        assert_eq!(diagnostics[1].severity, Some(DiagnosticSeverity::HINT));
+    }
+
+    #[test]
+    fn test_parse_sarif_output() {
+        let diagnostics = parse(
+            LinterOutput::Sarif,
+            r#"{"version": "2.1.0", "runs": [{"results": [
+                {
+                    "ruleId": "DL3008",
+                    "level": "error",
+                    "message": {"text": "Pin versions"},
+                    "locations": [{"physicalLocation": {
+                        "artifactLocation": {"uri": "file:///project/script.sh"},
+                        "region": {"startLine": 2, "startColumn": 1, "endLine": 2, "endColumn": 4}
+                    }}]
+                },
+                {
+                    "ruleId": "DL3009",
+                    "message": {"text": "Elsewhere"},
+                    "locations": [{"physicalLocation": {
+                        "artifactLocation": {"uri": "file:///project/Dockerfile"},
+                        "region": {"startLine": 1}
+                    }}]
+                }
+            ]}]}"#,
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].range,
+            lsp::Range::new(lsp::Position::new(1, 0), lsp::Position::new(1, 3))
+        );
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(
+            diagnostics[0].code,
+            Some(NumberOrString::String("DL3008".into()))
+        );
+        assert_eq!(diagnostics[0].message, "Pin versions");
+    }
+
+    #[test]
+    fn test_parse_empty_output() {
+        assert!(parse(LinterOutput::Json, "").is_empty());
+        assert!(parse(LinterOutput::Sarif, " \n").is_empty());
+        assert!(
+            parse(
+                LinterOutput::Regex(r"^(?P<line>\d+): (?P<message>.*)$".into()),
+                ""
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_parse_regex_output() {
+        let diagnostics = parse(
+            LinterOutput::Regex(
+                r"^-:(?P<line>\d+):(?P<column>\d+): (?P<severity>\w+) (?P<code>\w+): (?P<message>.*)$"
+                    .into(),
+            ),
+            "-:4:2: error E001: Bad thing\nsummary: 1 problem\n-:5:1: note N002: Consider this\n",
+        );
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].range.start, lsp::Position::new(3, 1));
+        assert_eq!(diagnostics[0].severity, Some(DiagnosticSeverity::ERROR));
+        assert_eq!(diagnostics[0].message, "Bad thing");
+        assert_eq!(
+            diagnostics[1].severity,
+            Some(DiagnosticSeverity::INFORMATION)
+        );
+
+        assert!(
+            parse_linter_output(
+                &LinterOutput::Regex(r"^(?P<line>\d+)$".into()),
+                "",
+                "lint",
+                Path::new("script.sh"),
+            )
+            .is_err()
+        );
+    }
+}
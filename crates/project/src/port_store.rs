@@ -0,0 +1,732 @@
+//! Module for tracking the ports that processes on a remote host listen on,
+//! and forwarding them to the local machine.
+//!
+//! The remote server detects listening TCP ports by reading `/proc/net/tcp`,
+//! and reports them along with the `forwardPorts` of the project's dev
+//! container configuration. The client forwards them over its connection.
+//! Ports that are already listened on when the first scan is reported are
+//! treated as a baseline of long-running services, and only ports that start
+//! being listened on after it are forwarded automatically.
+use anyhow::Result;
+use collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use fs::Fs;
+use gpui::{AsyncApp, Context, Entity, SharedString, Subscription, Task};
+use remote::{ForwardedPort, RemoteClient};
+use rpc::{AnyProtoClient, TypedEnvelope, proto};
+use serde::Deserialize;
+use settings::{RegisterSetting, Settings, SettingsStore};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use util::ResultExt as _;
+
+use crate::worktree_store::{WorktreeStore, WorktreeStoreEvent};
+
+const PORT_DETECTION_INTERVAL: Duration = Duration::from_secs(2);
+const DEV_CONTAINER_CONFIG_PATHS: &[&str] =
+    &[".devcontainer/devcontainer.json", ".devcontainer.json"];
+
+#[derive(Clone, Debug, RegisterSetting)]
+pub struct PortForwardingSettings {
+    pub auto_forward_ports: bool,
+}
+
+impl Settings for PortForwardingSettings {
+    fn from_settings(content: &settings::SettingsContent) -> Self {
+        Self {
+            auto_forward_ports: content.remote.auto_forward_ports.unwrap(),
+        }
+    }
+}
+
+/// A port listed in `forwardPorts` of a dev container configuration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConfiguredPort {
+    pub port: u16,
+    pub label: Option<SharedString>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PortForwardSource {
+    /// A process on the remote host started listening on the port.
+    Detected,
+    /// The port is listed in `forwardPorts` of the dev container configuration.
+    Configured,
+    /// The user forwarded the port.
+    Manual,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PortForwardStatus {
+    Starting,
+    Forwarded { local_port: u16 },
+    Failed(SharedString),
+}
+
+pub struct PortForward {
+    pub remote_port: u16,
+    pub label: Option<SharedString>,
+    pub source: PortForwardSource,
+    pub status: PortForwardStatus,
+    _connection: Option<ForwardedPort>,
+    _task: Task<()>,
+}
+
+impl PortForward {
+    pub fn local_port(&self) -> Option<u16> {
+        match self.status {
+            PortForwardStatus::Forwarded { local_port } => Some(local_port),
+            PortForwardStatus::Starting | PortForwardStatus::Failed(_) => None,
+        }
+    }
+}
+
+struct LocalPortStore {
+    fs: Arc<dyn Fs>,
+    worktree_store: Entity<WorktreeStore>,
+    detection_task: Option<Task<()>>,
+    _worktree_store_subscription: Option<Subscription>,
+}
+
+struct RemotePortStore {
+    remote_client: Entity<RemoteClient>,
+    forwards: BTreeMap<u16, PortForward>,
+    /// Ports the user stopped forwarding, which aren't forwarded automatically
+    /// again until they stop being listened on.
+    stopped_ports: HashSet<u16>,
+    /// Ports that were listened on in the first scan, which aren't forwarded
+    /// automatically until they stop being listened on and start again.
+    baseline_ports: BTreeSet<u16>,
+    _settings_subscription: Subscription,
+}
+
+enum PortStoreMode {
+    Local(LocalPortStore),
+    Remote(RemotePortStore),
+}
+
+pub struct PortStore {
+    mode: PortStoreMode,
+    /// Whether the listening ports have been scanned, or reported by the
+    /// remote server, at least once.
+    ports_detected: bool,
+    listening_ports: BTreeSet<u16>,
+    configured_ports: Vec<ConfiguredPort>,
+    downstream_client: Option<(AnyProtoClient, u64)>,
+}
+
+impl PortStore {
+    pub fn init(client: &AnyProtoClient) {
+        client.add_entity_message_handler(Self::handle_update_listening_ports);
+    }
+
+    pub fn local(fs: Arc<dyn Fs>, worktree_store: Entity<WorktreeStore>) -> Self {
+        Self {
+            mode: PortStoreMode::Local(LocalPortStore {
+                fs,
+                worktree_store,
+                detection_task: None,
+                _worktree_store_subscription: None,
+            }),
+            ports_detected: false,
+            listening_ports: BTreeSet::default(),
+            configured_ports: Vec::new(),
+            downstream_client: None,
+        }
+    }
+
+    pub(crate) fn remote(remote_client: Entity<RemoteClient>, cx: &mut Context<Self>) -> Self {
+        Self {
+            mode: PortStoreMode::Remote(RemotePortStore {
+                remote_client,
+                forwards: BTreeMap::default(),
+                stopped_ports: HashSet::default(),
+                baseline_ports: BTreeSet::default(),
+                _settings_subscription: cx
+                    .observe_global::<SettingsStore>(|this, cx| this.update_forwards(cx)),
+            }),
+            ports_detected: false,
+            listening_ports: BTreeSet::default(),
+            configured_ports: Vec::new(),
+            downstream_client: None,
+        }
+    }
+
+    /// Starts detecting the ports that are listened on, and reporting them to
+    /// the downstream client.
+    pub fn shared(
+        &mut self,
+        project_id: u64,
+        downstream_client: AnyProtoClient,
+        cx: &mut Context<Self>,
+    ) {
+        self.downstream_client = Some((downstream_client, project_id));
+        if let PortStoreMode::Local(local) = &mut self.mode
+            && local.detection_task.is_none()
+        {
+            local.detection_task = Some(Self::detect_ports(
+                local.fs.clone(),
+                local.worktree_store.clone(),
+                cx,
+            ));
+            // Worktrees are added once the downstream client is ready to
+            // receive updates, so resend the ports it may have missed.
+            local._worktree_store_subscription =
+                Some(cx.subscribe(&local.worktree_store, |this, _, event, _| {
+                    if let WorktreeStoreEvent::WorktreeAdded(_) = event {
+                        this.send_update();
+                    }
+                }));
+        }
+        self.send_update();
+    }
+
+    pub fn is_remote(&self) -> bool {
+        matches!(self.mode, PortStoreMode::Remote(_))
+    }
+
+    pub fn listening_ports(&self) -> &BTreeSet<u16> {
+        &self.listening_ports
+    }
+
+    pub fn configured_ports(&self) -> &[ConfiguredPort] {
+        &self.configured_ports
+    }
+
+    pub fn forwards(&self) -> impl Iterator<Item = &PortForward> {
+        match &self.mode {
+            PortStoreMode::Remote(remote) => Some(remote.forwards.values()),
+            PortStoreMode::Local(_) => None,
+        }
+        .into_iter()
+        .flatten()
+    }
+
+    pub fn forward(&self, remote_port: u16) -> Option<&PortForward> {
+        match &self.mode {
+            PortStoreMode::Remote(remote) => remote.forwards.get(&remote_port),
+            PortStoreMode::Local(_) => None,
+        }
+    }
+
+    pub fn forward_port(&mut self, remote_port: u16, cx: &mut Context<Self>) {
+        let PortStoreMode::Remote(remote) = &mut self.mode else {
+            return;
+        };
+        remote.stopped_ports.remove(&remote_port);
+        if !remote.forwards.contains_key(&remote_port) {
+            self.start_forwarding(remote_port, None, PortForwardSource::Manual, cx);
+        }
+    }
+
+    pub fn stop_forwarding(&mut self, remote_port: u16, cx: &mut Context<Self>) {
+        let PortStoreMode::Remote(remote) = &mut self.mode else {
+            return;
+        };
+        if remote.forwards.remove(&remote_port).is_some() {
+            remote.stopped_ports.insert(remote_port);
+            cx.notify();
+        }
+    }
+
+    pub fn set_label(
+        &mut self,
+        remote_port: u16,
+        label: Option<SharedString>,
+        cx: &mut Context<Self>,
+    ) {
+        let PortStoreMode::Remote(remote) = &mut self.mode else {
+            return;
+        };
+        if let Some(forward) = remote.forwards.get_mut(&remote_port) {
+            forward.label = label.filter(|label| !label.is_empty());
+            cx.notify();
+        }
+    }
+
+    fn detect_ports(
+        fs: Arc<dyn Fs>,
+        worktree_store: Entity<WorktreeStore>,
+        cx: &mut Context<Self>,
+    ) -> Task<()> {
+        cx.spawn(async move |this, cx| {
+            loop {
+                let config_paths = worktree_store.read_with(cx, |worktree_store, cx| {
+                    worktree_store
+                        .visible_worktrees(cx)
+                        .flat_map(|worktree| {
+                            let root = worktree.read(cx).abs_path();
+                            DEV_CONTAINER_CONFIG_PATHS
+                                .iter()
+                                .map(move |path| root.join(path))
+                        })
+                        .collect::<Vec<_>>()
+                });
+                let listening_ports = read_listening_ports(fs.as_ref()).await;
+                let configured_ports = read_configured_ports(fs.as_ref(), &config_paths).await;
+                if this
+                    .update(cx, |this, cx| {
+                        this.set_ports(listening_ports, configured_ports, cx)
+                    })
+                    .is_err()
+                {
+                    break;
+                }
+                cx.background_executor()
+                    .timer(PORT_DETECTION_INTERVAL)
+                    .await;
+            }
+        })
+    }
+
+    async fn handle_update_listening_ports(
+        this: Entity<Self>,
+        envelope: TypedEnvelope<proto::UpdateListeningPorts>,
+        mut cx: AsyncApp,
+    ) -> Result<()> {
+        let listening_ports = envelope
+            .payload
+            .listening_ports
+            .into_iter()
+            .filter_map(|port| u16::try_from(port).ok())
+            .collect();
+        let configured_ports = envelope
+            .payload
+            .configured_ports
+            .into_iter()
+            .filter_map(|port| {
+                Some(ConfiguredPort {
+                    port: u16::try_from(port.port).ok()?,
+                    label: port.label.map(SharedString::from),
+                })
+            })
+            .collect();
+        this.update(&mut cx, |this, cx| {
+            this.set_ports(listening_ports, configured_ports, cx)
+        });
+        Ok(())
+    }
+
+    fn set_ports(
+        &mut self,
+        listening_ports: BTreeSet<u16>,
+        configured_ports: Vec<ConfiguredPort>,
+        cx: &mut Context<Self>,
+    ) {
+        let is_first_scan = !self.ports_detected;
+        self.ports_detected = true;
+        if is_first_scan && let PortStoreMode::Remote(remote) = &mut self.mode {
+            remote.baseline_ports = listening_ports.clone();
+        }
+        if !is_first_scan
+            && listening_ports == self.listening_ports
+            && configured_ports == self.configured_ports
+        {
+            return;
+        }
+        self.listening_ports = listening_ports;
+        self.configured_ports = configured_ports;
+        self.send_update();
+        self.update_forwards(cx);
+        cx.notify();
+    }
+
+    fn send_update(&self) {
+        // An empty list before the first scan would make the client treat
+        // every port that's listened on by then as newly opened.
+        if !self.ports_detected {
+            return;
+        }
+        let Some((client, project_id)) = &self.downstream_client else {
+            return;
+        };
+        client
+            .send(proto::UpdateListeningPorts {
+                project_id: *project_id,
+                listening_ports: self
+                    .listening_ports
+                    .iter()
+                    .copied()
+                    .map(u32::from)
+                    .collect(),
+                configured_ports: self
+                    .configured_ports
+                    .iter()
+                    .map(|port| proto::ConfiguredPort {
+                        port: u32::from(port.port),
+                        label: port.label.as_ref().map(|label| label.to_string()),
+                    })
+                    .collect(),
+            })
+            .log_err();
+    }
+
+    /// Starts forwarding the configured ports and, if enabled, the ports that
+    /// started being listened on after the first scan, and stops forwarding
+    /// detected ports that are no longer listened on.
+    fn update_forwards(&mut self, cx: &mut Context<Self>) {
+        let PortStoreMode::Remote(remote) = &mut self.mode else {
+            return;
+        };
+        let auto_forward_ports = PortForwardingSettings::get_global(cx).auto_forward_ports;
+        let listening_ports = &self.listening_ports;
+        let configured_ports = &self.configured_ports;
+
+        remote.stopped_ports.retain(|port| {
+            listening_ports.contains(port)
+                || configured_ports
+                    .iter()
+                    .any(|configured| configured.port == *port)
+        });
+        remote
+            .baseline_ports
+            .retain(|port| listening_ports.contains(port));
+        let forwards_before = remote.forwards.len();
+        remote.forwards.retain(|port, forward| {
+            forward.source != PortForwardSource::Detected
+                || (auto_forward_ports && listening_ports.contains(port))
+        });
+        let mut changed = remote.forwards.len() != forwards_before;
+
+        let new_forwards = ports_to_forward(
+            listening_ports,
+            configured_ports,
+            &remote.baseline_ports,
+            |port| remote.forwards.contains_key(&port) || remote.stopped_ports.contains(&port),
+            auto_forward_ports,
+        );
+        for (port, label, source) in new_forwards {
+            self.start_forwarding(port, label, source, cx);
+            changed = true;
+        }
+        if changed {
+            cx.notify();
+        }
+    }
+
+    fn start_forwarding(
+        &mut self,
+        remote_port: u16,
+        label: Option<SharedString>,
+        source: PortForwardSource,
+        cx: &mut Context<Self>,
+    ) {
+        let PortStoreMode::Remote(remote) = &mut self.mode else {
+            return;
+        };
+        let connection = remote.remote_client.read(cx).forward_port(remote_port, cx);
+        let task = cx.spawn(async move |this, cx| {
+            let result = connection.await;
+            this.update(cx, |this, cx| {
+                let PortStoreMode::Remote(remote) = &mut this.mode else {
+                    return;
+                };
+                let Some(forward) = remote.forwards.get_mut(&remote_port) else {
+                    return;
+                };
+                match result {
+                    Ok(connection) => {
+                        forward.status = PortForwardStatus::Forwarded {
+                            local_port: connection.local_port,
+                        };
+                        forward._connection = Some(connection);
+                    }
+                    Err(error) => {
+                        log::error!("failed to forward port {remote_port}: {error:#}");
+                        forward.status = PortForwardStatus::Failed(format!("{error:#}").into());
+                    }
+                }
+                cx.notify();
+            })
+            .ok();
+        });
+        remote.forwards.insert(
+            remote_port,
+            PortForward {
+                remote_port,
+                label,
+                source,
+                status: PortForwardStatus::Starting,
+                _connection: None,
+                _task: task,
+            },
+        );
+        cx.notify();
+    }
+}
+
+/// Returns the ports to start forwarding, with their labels: the configured
+/// ports and, if `auto_forward_ports` is enabled, the listening ports that
+/// aren't in `baseline_ports`. Ports for which `is_skipped` returns true, as
+/// they're already forwarded or the user stopped forwarding them, are left out.
+fn ports_to_forward(
+    listening_ports: &BTreeSet<u16>,
+    configured_ports: &[ConfiguredPort],
+    baseline_ports: &BTreeSet<u16>,
+    is_skipped: impl Fn(u16) -> bool,
+    auto_forward_ports: bool,
+) -> Vec<(u16, Option<SharedString>, PortForwardSource)> {
+    let mut new_forwards = Vec::new();
+    for configured in configured_ports {
+        if !is_skipped(configured.port) {
+            new_forwards.push((
+                configured.port,
+                configured.label.clone(),
+                PortForwardSource::Configured,
+            ));
+        }
+    }
+    if auto_forward_ports {
+        for &port in listening_ports {
+            if !is_skipped(port)
+                && !baseline_ports.contains(&port)
+                && !new_forwards
+                    .iter()
+                    .any(|(new_port, _, _)| *new_port == port)
+            {
+                new_forwards.push((port, None, PortForwardSource::Detected));
+            }
+        }
+    }
+    new_forwards
+}
+
+async fn read_listening_ports(fs: &dyn Fs) -> BTreeSet<u16> {
+    let mut ports = BTreeSet::default();
+    for path in ["/proc/net/tcp", "/proc/net/tcp6"] {
+        if let Ok(contents) = fs.load(Path::new(path)).await {
+            ports.extend(parse_listening_ports(&contents));
+        }
+    }
+    ports
+}
+
+/// Parses the ports in the `LISTEN` state from the contents of `/proc/net/tcp`
+/// or `/proc/net/tcp6`, skipping sockets bound to an address that can't be
+/// reached through the loopback interface.
+fn parse_listening_ports(contents: &str) -> impl Iterator<Item = u16> + '_ {
+    contents.lines().skip(1).filter_map(|line| {
+        let mut fields = line.split_whitespace();
+        let local_address = fields.nth(1)?;
+        let state = fields.nth(1)?;
+        if state != "0A" {
+            return None;
+        }
+        let (address, port) = local_address.split_once(':')?;
+        // Addresses are written as hexadecimal 32-bit words in host byte order.
+        let is_reachable = matches!(
+            address,
+            "00000000"
+                | "0100007F"
+                | "00000000000000000000000000000000"
+                | "00000000000000000000000001000000"
+                | "0000000000000000FFFF00000100007F"
+        );
+        is_reachable
+            .then(|| u16::from_str_radix(port, 16).ok())
+            .flatten()
+    })
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DevContainerPorts {
+    #[serde(default)]
+    forward_ports: Vec<serde_json::Value>,
+    #[serde(default)]
+    ports_attributes: HashMap<String, PortAttributes>,
+}
+
+#[derive(Deserialize)]
+struct PortAttributes {
+    label: Option<String>,
+}
+
+async fn read_configured_ports(fs: &dyn Fs, config_paths: &[PathBuf]) -> Vec<ConfiguredPort> {
+    let mut ports = Vec::<ConfiguredPort>::new();
+    for config_path in config_paths {
+        let Ok(contents) = fs.load(config_path).await else {
+            continue;
+        };
+        let Some(config) =
+            settings::parse_json_with_comments::<DevContainerPorts>(&contents).log_err()
+        else {
+            continue;
+        };
+        for value in &config.forward_ports {
+            let Some(port) = parse_forward_port(value) else {
+                continue;
+            };
+            if ports.iter().any(|configured| configured.port == port) {
+                continue;
+            }
+            let label = config
+                .ports_attributes
+                .get(&port.to_string())
+                .and_then(|attributes| attributes.label.clone())
+                .map(SharedString::from);
+            ports.push(ConfiguredPort { port, label });
+        }
+    }
+    ports
+}
+
+/// Parses an entry of `forwardPorts`, which is either a port number or a
+/// `"host:port"` string. Only ports on the remote host itself can be forwarded.
+fn parse_forward_port(value: &serde_json::Value) -> Option<u16> {
+    match value {
+        serde_json::Value::Number(port) => u16::try_from(port.as_u64()?).ok(),
+        serde_json::Value::String(port) => {
+            let (host, port) = port.split_once(':')?;
+            matches!(host, "localhost" | "127.0.0.1")
+                .then(|| port.parse().ok())
+                .flatten()
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fs::FakeFs;
+    use gpui::TestAppContext;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_listening_ports() {
+        let contents = "\
+  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:0BB8 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 1 1
+   1: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 2 1
+   2: 0100007F:1F91 0100007F:0BB8 01 00000000:00000000 00:00000000 00000000  1000        0 3 1
+   3: 0200A8C0:1F92 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 4 1
+";
+        assert_eq!(
+            parse_listening_ports(contents).collect::<Vec<_>>(),
+            [3000, 8080]
+        );
+
+        let contents = "\
+  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000001000000:1388 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 5 1
+   1: 000080FE00000000FF005450B6AC17FE:1389 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 6 1
+";
+        assert_eq!(parse_listening_ports(contents).collect::<Vec<_>>(), [5000]);
+    }
+
+    #[test]
+    fn test_parse_forward_port() {
+        assert_eq!(parse_forward_port(&json!(3000)), Some(3000));
+        assert_eq!(parse_forward_port(&json!("localhost:3000")), Some(3000));
+        assert_eq!(parse_forward_port(&json!("127.0.0.1:3000")), Some(3000));
+        assert_eq!(parse_forward_port(&json!("db:5432")), None);
+        assert_eq!(parse_forward_port(&json!(70000)), None);
+        assert_eq!(parse_forward_port(&json!("3000")), None);
+    }
+
+    #[test]
+    fn test_ports_to_forward() {
+        let configured_ports = [ConfiguredPort {
+            port: 5432,
+            label: Some("Database".into()),
+        }];
+        let baseline_ports = BTreeSet::from_iter([22, 5432]);
+        let listening_ports = BTreeSet::from_iter([22, 3000, 5432, 8080]);
+
+        // Ports that were listened on in the first scan aren't forwarded
+        // automatically, but configured ports always are.
+        assert_eq!(
+            ports_to_forward(
+                &listening_ports,
+                &configured_ports,
+                &baseline_ports,
+                |_| false,
+                true,
+            ),
+            [
+                (
+                    5432,
+                    Some(SharedString::from("Database")),
+                    PortForwardSource::Configured,
+                ),
+                (3000, None, PortForwardSource::Detected),
+                (8080, None, PortForwardSource::Detected),
+            ]
+        );
+
+        // Ports that are already forwarded or that the user stopped forwarding
+        // are skipped.
+        assert_eq!(
+            ports_to_forward(
+                &listening_ports,
+                &configured_ports,
+                &baseline_ports,
+                |port| port == 3000 || port == 5432,
+                true,
+            ),
+            [(8080, None, PortForwardSource::Detected)]
+        );
+
+        // Only configured ports are forwarded when automatic forwarding is off.
+        assert_eq!(
+            ports_to_forward(
+                &listening_ports,
+                &configured_ports,
+                &baseline_ports,
+                |_| false,
+                false,
+            ),
+            [(
+                5432,
+                Some(SharedString::from("Database")),
+                PortForwardSource::Configured,
+            )]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_read_configured_ports(cx: &mut TestAppContext) {
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            "/project",
+            json!({
+                ".devcontainer": {
+                    "devcontainer.json": r#"{
+                        // Comments are allowed.
+                        "forwardPorts": [3000, "localhost:8080", "db:5432", 3000],
+                        "portsAttributes": {
+                            "3000": { "label": "Web" }
+                        }
+                    }"#,
+                },
+                ".devcontainer.json": r#"{ "forwardPorts": [8080, 9000] }"#,
+            }),
+        )
+        .await;
+
+        let config_paths = DEV_CONTAINER_CONFIG_PATHS
+            .iter()
+            .map(|path| Path::new("/project").join(path))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            read_configured_ports(fs.as_ref(), &config_paths).await,
+            [
+                ConfiguredPort {
+                    port: 3000,
+                    label: Some("Web".into()),
+                },
+                ConfiguredPort {
+                    port: 8080,
+                    label: None,
+                },
+                ConfiguredPort {
+                    port: 9000,
+                    label: None,
+                },
+            ]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 pub mod agent_registry_store;
 pub mod agent_server_store;
+pub mod bookmark_store;
 pub mod buffer_store;
 pub mod color_extractor;
 pub mod connection_manager;
@@ -11,10 +12,13 @@ pub mod image_store;
 pub mod lsp_command;
 pub mod lsp_store;
 pub mod manifest_tree;
+pub mod port_store;
 pub mod prettier_store;
 pub mod project_search;
 pub mod project_settings;
+pub mod review_thread_store;
 pub mod search;
+pub mod shared_terminal_store;
 pub mod task_inventory;
 pub mod task_store;
 pub mod telemetry_snapshot;
@@ -53,6 +57,7 @@ pub use manifest_tree::ManifestTree;
 pub use project_search::{Search, SearchResults};
 
 use anyhow::{Context as _, Result, anyhow};
+use bookmark_store::BookmarkStore;
 use buffer_store::{BufferStore, BufferStoreEvent};
 use client::{
     Client, Collaborator, PendingEntitySubscription, ProjectId, TypedEnvelope, UserStore, proto,
@@ -88,7 +93,8 @@ use gpui::{
 use language::{
     Buffer, BufferEvent, Capability, CodeLabel, CursorShape, DiskState, Language, LanguageName,
     LanguageRegistry, PointUtf16, ToOffset, ToPointUtf16, Toolchain, ToolchainMetadata,
-    ToolchainScope, Transaction, Unclipped, language_settings::InlayHintKind,
+    ToolchainScope, Transaction, Unclipped,
+    language_settings::{FormatOnSave, InlayHintKind, LanguageSettings},
     proto::split_operations,
 };
 use lsp::{
@@ -101,11 +107,13 @@ use lsp_store::{CompletionDocumentation, LspFormatTarget, OpenLspBufferHandle};
 pub use manifest_tree::ManifestProvidersStore;
 use node_runtime::NodeRuntime;
 use parking_lot::Mutex;
+use port_store::PortStore;
 pub use prettier_store::PrettierStore;
-use project_settings::{ProjectSettings, SettingsObserver, SettingsObserverEvent};
+use project_settings::{LocalKeymap, ProjectSettings, SettingsObserver, SettingsObserverEvent};
 #[cfg(target_os = "windows")]
 use remote::wsl_path_to_windows_path;
 use remote::{RemoteClient, RemoteConnectionOptions};
+use review_thread_store::ReviewThreadStore;
 use rpc::{
     AnyProtoClient, ErrorCode,
     proto::{LanguageServerPromptResponse, REMOTE_SERVER_PROJECT_ID},
@@ -113,6 +121,7 @@ use rpc::{
 use search::{SearchInputKind, SearchQuery, SearchResult};
 use search_history::SearchHistory;
 use settings::{InvalidSettingsError, RegisterSetting, Settings, SettingsLocation, SettingsStore};
+use shared_terminal_store::SharedTerminalStore;
 use snippet::Snippet;
 pub use snippet_provider;
 use snippet_provider::SnippetProvider;
@@ -212,6 +221,10 @@ pub struct Project {
     agent_server_store: Entity<AgentServerStore>,
 
     breakpoint_store: Entity<BreakpointStore>,
+    bookmark_store: Entity<BookmarkStore>,
+    port_store: Entity<PortStore>,
+    shared_terminal_store: Entity<SharedTerminalStore>,
+    review_thread_store: Entity<ReviewThreadStore>,
     collab_client: Arc<client::Client>,
     join_project_response_message_id: u32,
     task_store: Entity<TaskStore>,
@@ -398,7 +411,11 @@ pub enum Event {
     EntryRenamed(ProjectTransaction, ProjectPath, PathBuf),
     WorkspaceEditApplied(ProjectTransaction),
     AgentLocationChanged,
+    /// The agent is about to edit the buffer, which still has its previous contents.
+    AgentWillEditBuffer(Entity<Buffer>),
     BufferEdited,
+    /// The project's `.zed/keymap.json` files, allowed to be loaded, changed.
+    LocalKeymapsUpdated,
 }
 
 pub struct AgentLocationChanged;
@@ -464,6 +481,7 @@ pub enum InlayId {
     Hint(usize),
     Color(usize),
     ReplResult(usize),
+    ReplacementPreview(usize),
 }
 
 impl InlayId {
@@ -474,6 +492,7 @@ impl InlayId {
             Self::Hint(id) => *id,
             Self::Color(id) => *id,
             Self::ReplResult(id) => *id,
+            Self::ReplacementPreview(id) => *id,
         }
     }
 }
@@ -881,6 +900,9 @@ enum EntitySubscription {
     SettingsObserver(PendingEntitySubscription<SettingsObserver>),
     DapStore(PendingEntitySubscription<DapStore>),
     BreakpointStore(PendingEntitySubscription<BreakpointStore>),
+    BookmarkStore(PendingEntitySubscription<BookmarkStore>),
+    SharedTerminalStore(PendingEntitySubscription<SharedTerminalStore>),
+    ReviewThreadStore(PendingEntitySubscription<ReviewThreadStore>),
 }
 
 #[derive(Debug, Clone)]
@@ -1134,6 +1156,9 @@ impl Project {
         ToolchainStore::init(&client);
         DapStore::init(&client, cx);
         BreakpointStore::init(&client);
+        BookmarkStore::init(&client);
+        SharedTerminalStore::init(&client);
+        ReviewThreadStore::init(&client);
         context_server_store::init(cx);
     }
 
@@ -1197,6 +1222,11 @@ impl Project {
 
             let breakpoint_store =
                 cx.new(|_| BreakpointStore::local(worktree_store.clone(), buffer_store.clone()));
+            let bookmark_store =
+                cx.new(|_| BookmarkStore::local(worktree_store.clone(), buffer_store.clone()));
+            let port_store = cx.new(|_| PortStore::local(fs.clone(), worktree_store.clone()));
+            let shared_terminal_store = cx.new(|_| SharedTerminalStore::local());
+            let review_thread_store = cx.new(|_| ReviewThreadStore::local());
 
             let dap_store = cx.new(|cx| {
                 DapStore::new_local(
@@ -1313,6 +1343,10 @@ impl Project {
                 fs,
                 remote_client: None,
                 breakpoint_store,
+                bookmark_store,
+                port_store,
+                shared_terminal_store,
+                review_thread_store,
                 dap_store,
                 agent_server_store,
 
@@ -1476,6 +1510,17 @@ impl Project {
                     worktree_store.clone(),
                 )
             });
+            let bookmark_store = cx.new(|_| {
+                BookmarkStore::remote(
+                    REMOTE_SERVER_PROJECT_ID,
+                    remote_proto.clone(),
+                    buffer_store.clone(),
+                    worktree_store.clone(),
+                )
+            });
+            let port_store = cx.new(|cx| PortStore::remote(remote.clone(), cx));
+            let shared_terminal_store = cx.new(|_| SharedTerminalStore::local());
+            let review_thread_store = cx.new(|_| ReviewThreadStore::local());
 
             let dap_store = cx.new(|cx| {
                 DapStore::new_remote(
@@ -1519,6 +1564,10 @@ impl Project {
                 lsp_store,
                 context_server_store,
                 breakpoint_store,
+                bookmark_store,
+                port_store,
+                shared_terminal_store,
+                review_thread_store,
                 dap_store,
                 join_project_response_message_id: 0,
                 client_state: ProjectClientState::Local,
@@ -1578,6 +1627,8 @@ impl Project {
             remote_proto.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &this.lsp_store);
             remote_proto.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &this.dap_store);
             remote_proto.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &this.breakpoint_store);
+            remote_proto.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &this.bookmark_store);
+            remote_proto.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &this.port_store);
             remote_proto.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &this.settings_observer);
             remote_proto.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &this.git_store);
             remote_proto.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &this.agent_server_store);
@@ -1604,6 +1655,8 @@ impl Project {
             ToolchainStore::init(&remote_proto);
             DapStore::init(&remote_proto, cx);
             BreakpointStore::init(&remote_proto);
+            BookmarkStore::init(&remote_proto);
+            PortStore::init(&remote_proto);
             GitStore::init(&remote_proto);
             AgentServerStore::init_remote(&remote_proto);
 
@@ -1636,6 +1689,15 @@ impl Project {
             EntitySubscription::BreakpointStore(
                 client.subscribe_to_entity::<BreakpointStore>(remote_id)?,
             ),
+            EntitySubscription::BookmarkStore(
+                client.subscribe_to_entity::<BookmarkStore>(remote_id)?,
+            ),
+            EntitySubscription::SharedTerminalStore(
+                client.subscribe_to_entity::<SharedTerminalStore>(remote_id)?,
+            ),
+            EntitySubscription::ReviewThreadStore(
+                client.subscribe_to_entity::<ReviewThreadStore>(remote_id)?,
+            ),
         ];
         let committer = get_git_committer(&cx).await;
         let response = client
@@ -1660,7 +1722,7 @@ impl Project {
 
     async fn from_join_project_response(
         response: TypedEnvelope<proto::JoinProjectResponse>,
-        subscriptions: [EntitySubscription; 8],
+        subscriptions: [EntitySubscription; 10],
         client: Arc<Client>,
         run_tasks: bool,
         user_store: Entity<UserStore>,
@@ -1703,6 +1765,19 @@ impl Project {
                 worktree_store.clone(),
             )
         });
+        let bookmark_store = cx.new(|_| {
+            BookmarkStore::remote(
+                remote_id,
+                client.clone().into(),
+                buffer_store.clone(),
+                worktree_store.clone(),
+            )
+        });
+        let port_store = cx.new(|_| PortStore::local(fs.clone(), worktree_store.clone()));
+        let shared_terminal_store =
+            cx.new(|_| SharedTerminalStore::remote(remote_id, client.clone().into(), path_style));
+        let review_thread_store =
+            cx.new(|cx| ReviewThreadStore::remote(remote_id, client.clone().into(), cx));
         let dap_store = cx.new(|cx| {
             DapStore::new_collab(
                 remote_id,
@@ -1828,6 +1903,10 @@ impl Project {
                     replica_id,
                 },
                 breakpoint_store: breakpoint_store.clone(),
+                bookmark_store: bookmark_store.clone(),
+                port_store,
+                shared_terminal_store: shared_terminal_store.clone(),
+                review_thread_store: review_thread_store.clone(),
                 dap_store: dap_store.clone(),
                 git_store: git_store.clone(),
                 agent_server_store,
@@ -1888,6 +1967,15 @@ impl Project {
                 EntitySubscription::BreakpointStore(subscription) => {
                     subscription.set_entity(&breakpoint_store, &cx)
                 }
+                EntitySubscription::BookmarkStore(subscription) => {
+                    subscription.set_entity(&bookmark_store, &cx)
+                }
+                EntitySubscription::SharedTerminalStore(subscription) => {
+                    subscription.set_entity(&shared_terminal_store, &cx)
+                }
+                EntitySubscription::ReviewThreadStore(subscription) => {
+                    subscription.set_entity(&review_thread_store, &cx)
+                }
             })
             .collect::<Vec<_>>();
 
@@ -2065,6 +2153,25 @@ impl Project {
         self.breakpoint_store.clone()
     }
 
+    #[inline]
+    pub fn bookmark_store(&self) -> Entity<BookmarkStore> {
+        self.bookmark_store.clone()
+    }
+
+    #[inline]
+    pub fn port_store(&self) -> Entity<PortStore> {
+        self.port_store.clone()
+    }
+
+    #[inline]
+    pub fn shared_terminal_store(&self) -> Entity<SharedTerminalStore> {
+        self.shared_terminal_store.clone()
+    }
+
+    pub fn review_thread_store(&self) -> Entity<ReviewThreadStore> {
+        self.review_thread_store.clone()
+    }
+
     pub fn active_debug_session(&self, cx: &App) -> Option<(Entity<Session>, ActiveStackFrame)> {
         let active_position = self.breakpoint_store.read(cx).active_position()?;
         let session = self
@@ -2241,6 +2348,15 @@ impl Project {
         &self.task_store
     }
 
+    /// The `.zed/keymap.json` files of the project's local worktrees, allowed to be loaded.
+    pub fn local_keymaps(&self, cx: &App) -> Vec<LocalKeymap> {
+        self.settings_observer
+            .read(cx)
+            .local_keymaps()
+            .cloned()
+            .collect()
+    }
+
     #[inline]
     pub fn snippets(&self) -> &Entity<SnippetProvider> {
         &self.snippets
@@ -2615,6 +2731,15 @@ impl Project {
             self.collab_client
                 .subscribe_to_entity(project_id)?
                 .set_entity(&self.breakpoint_store, &cx.to_async()),
+            self.collab_client
+                .subscribe_to_entity(project_id)?
+                .set_entity(&self.bookmark_store, &cx.to_async()),
+            self.collab_client
+                .subscribe_to_entity(project_id)?
+                .set_entity(&self.shared_terminal_store, &cx.to_async()),
+            self.collab_client
+                .subscribe_to_entity(project_id)?
+                .set_entity(&self.review_thread_store, &cx.to_async()),
             self.collab_client
                 .subscribe_to_entity(project_id)?
                 .set_entity(&self.git_store, &cx.to_async()),
@@ -2632,6 +2757,17 @@ impl Project {
         self.breakpoint_store.update(cx, |breakpoint_store, _| {
             breakpoint_store.shared(project_id, self.collab_client.clone().into())
         });
+        self.bookmark_store.update(cx, |bookmark_store, _| {
+            bookmark_store.shared(project_id, self.collab_client.clone().into())
+        });
+        self.shared_terminal_store
+            .update(cx, |shared_terminal_store, _| {
+                shared_terminal_store.shared(project_id, self.collab_client.clone().into())
+            });
+        self.review_thread_store
+            .update(cx, |review_thread_store, _| {
+                review_thread_store.shared(project_id, self.collab_client.clone().into())
+            });
         self.dap_store.update(cx, |dap_store, cx| {
             dap_store.shared(project_id, self.collab_client.clone().into(), cx);
         });
@@ -2737,6 +2873,17 @@ impl Project {
             self.breakpoint_store.update(cx, |breakpoint_store, cx| {
                 breakpoint_store.unshared(cx);
             });
+            self.bookmark_store.update(cx, |bookmark_store, cx| {
+                bookmark_store.unshared(cx);
+            });
+            self.shared_terminal_store
+                .update(cx, |shared_terminal_store, cx| {
+                    shared_terminal_store.unshared(cx);
+                });
+            self.review_thread_store
+                .update(cx, |review_thread_store, cx| {
+                    review_thread_store.unshared(cx);
+                });
             self.dap_store.update(cx, |dap_store, cx| {
                 dap_store.unshared(cx);
             });
@@ -2802,6 +2949,14 @@ impl Project {
             });
             self.lsp_store
                 .update(cx, |lsp_store, _cx| lsp_store.disconnected_from_host());
+            self.shared_terminal_store
+                .update(cx, |shared_terminal_store, cx| {
+                    shared_terminal_store.disconnected_from_host(cx)
+                });
+            self.review_thread_store
+                .update(cx, |review_thread_store, cx| {
+                    review_thread_store.disconnected_from_host(cx)
+                });
         }
     }
 
@@ -3621,6 +3776,7 @@ impl Project {
                 }),
                 Err(_) => {}
             },
+            SettingsObserverEvent::LocalKeymapsUpdated => cx.emit(Event::LocalKeymapsUpdated),
         }
     }
 
@@ -4027,11 +4183,99 @@ impl Project {
         trigger: lsp_store::FormatTrigger,
         cx: &mut Context<Project>,
     ) -> Task<anyhow::Result<ProjectTransaction>> {
+        if trigger == lsp_store::FormatTrigger::Save && matches!(target, LspFormatTarget::Buffers) {
+            let (modified_only, full): (HashSet<_>, HashSet<_>) =
+                buffers.into_iter().partition(|buffer| {
+                    LanguageSettings::for_buffer(buffer.read(cx), cx).format_on_save
+                        == FormatOnSave::Modified
+                });
+            if !modified_only.is_empty() {
+                return self.format_modified_lines(modified_only, full, push_to_history, cx);
+            }
+            return self.lsp_store.update(cx, |lsp_store, cx| {
+                lsp_store.format(full, target, push_to_history, trigger, cx)
+            });
+        }
+
         self.lsp_store.update(cx, |lsp_store, cx| {
             lsp_store.format(buffers, target, push_to_history, trigger, cx)
         })
     }
 
+    /// Formats the lines of `modified_only` that changed since the last commit,
+    /// along with all of `full`. Buffers that aren't in a git repository are
+    /// formatted in full.
+    fn format_modified_lines(
+        &mut self,
+        modified_only: HashSet<Entity<Buffer>>,
+        mut full: HashSet<Entity<Buffer>>,
+        push_to_history: bool,
+        cx: &mut Context<Project>,
+    ) -> Task<anyhow::Result<ProjectTransaction>> {
+        let diffs = modified_only
+            .into_iter()
+            .map(|buffer| {
+                let diff = self.open_uncommitted_diff(buffer.clone(), cx);
+                (buffer, diff)
+            })
+            .collect::<Vec<_>>();
+        let lsp_store = self.lsp_store.clone();
+        cx.spawn(async move |_, cx| {
+            let mut ranged = HashSet::default();
+            let mut ranges = BTreeMap::new();
+            for (buffer, diff) in diffs {
+                match diff.await {
+                    Ok(diff) => {
+                        let (buffer_id, buffer_ranges) = cx.update(|cx| {
+                            let buffer = buffer.read(cx);
+                            (
+                                buffer.remote_id(),
+                                modified_line_ranges(buffer, diff.read(cx), cx),
+                            )
+                        });
+                        ranges.insert(buffer_id, buffer_ranges);
+                        ranged.insert(buffer);
+                    }
+                    Err(error) => {
+                        log::debug!(
+                            "formatting whole buffer, no diff to find modified lines: {error:#}"
+                        );
+                        full.insert(buffer);
+                    }
+                }
+            }
+
+            let tasks = lsp_store.update(cx, |lsp_store, cx| {
+                let mut tasks = Vec::new();
+                if !full.is_empty() {
+                    tasks.push(lsp_store.format(
+                        full,
+                        LspFormatTarget::Buffers,
+                        push_to_history,
+                        lsp_store::FormatTrigger::Save,
+                        cx,
+                    ));
+                }
+                if !ranged.is_empty() {
+                    tasks.push(lsp_store.format(
+                        ranged,
+                        LspFormatTarget::Ranges(ranges),
+                        push_to_history,
+                        lsp_store::FormatTrigger::Save,
+                        cx,
+                    ));
+                }
+                tasks
+            });
+
+            let mut project_transaction = ProjectTransaction::default();
+            for task in tasks {
+                project_transaction.0.extend(task.await?.0);
+            }
+            Ok(project_transaction)
+        })
+    }
+
     pub fn definitions<T: ToPointUtf16>(
         &mut self,
         buffer: &Entity<Buffer>,
@@ -4430,6 +4674,7 @@ impl Project {
                 ),
                 None => project_search::Search::local(
                     self.fs.clone(),
+                    self.languages.clone(),
                     self.buffer_store.clone(),
                     self.worktree_store.clone(),
                     project_search::Search::MAX_SEARCH_RESULT_FILES + 1,
@@ -4971,6 +5216,8 @@ impl Project {
                 buffer_store.forget_shared_buffers_for(&collaborator.peer_id);
             });
             this.breakpoint_store.read(cx).broadcast();
+            this.bookmark_store.read(cx).broadcast();
+            this.shared_terminal_store.read(cx).broadcast(cx);
             cx.emit(Event::CollaboratorJoined(collaborator.peer_id));
             this.collaborators
                 .insert(collaborator.peer_id, collaborator);
@@ -5991,6 +6238,12 @@ impl Project {
         self.agent_location.clone()
     }
 
+    /// Notifies observers that the agent is about to edit `buffer`. Called once
+    /// per tool call, before any of its edits are applied.
+    pub fn agent_will_edit_buffer(&mut self, buffer: &Entity<Buffer>, cx: &mut Context<Self>) {
+        cx.emit(Event::AgentWillEditBuffer(buffer.clone()));
+    }
+
     pub fn path_style(&self, cx: &App) -> PathStyle {
         self.worktree_store.read(cx).path_style()
     }
@@ -6262,6 +6515,32 @@ impl Completion {
     }
 }
 
+/// Returns the ranges of whole lines in the buffer that differ from the diff's base text.
+/// Deletions are represented by the line following them.
+fn modified_line_ranges(buffer: &Buffer, diff: &BufferDiff, cx: &App) -> Vec<Range<Anchor>> {
+    let snapshot = buffer.text_snapshot();
+    let mut rows: Vec<Range<u32>> = Vec::new();
+    for hunk in diff.snapshot(cx).hunks(&snapshot) {
+        let start = hunk.range.start.row;
+        let end = if hunk.range.end.column == 0 && hunk.range.end.row > start {
+            hunk.range.end.row - 1
+        } else {
+            hunk.range.end.row.min(snapshot.max_point().row)
+        };
+        match rows.last_mut() {
+            Some(last) if start <= last.end + 1 => last.end = last.end.max(end),
+            _ => rows.push(start..end),
+        }
+    }
+    rows.into_iter()
+        .map(|rows| {
+            let start = Point::new(rows.start, 0);
+            let end = Point::new(rows.end, snapshot.line_len(rows.end));
+            snapshot.anchor_before(start)..snapshot.anchor_after(end)
+        })
+        .collect()
+}
+
 fn proto_to_prompt(level: proto::language_server_prompt_request::Level) -> gpui::PromptLevel {
     match level {
         proto::language_server_prompt_request::Level::Info(_) => gpui::PromptLevel::Info,
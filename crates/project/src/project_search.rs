@@ -15,7 +15,7 @@ use fs::Fs;
 use futures::FutureExt as _;
 use futures::{SinkExt, StreamExt, select_biased, stream::FuturesOrdered};
 use gpui::{App, AppContext, AsyncApp, BackgroundExecutor, Entity, Priority, Task};
-use language::{Buffer, BufferSnapshot};
+use language::{Buffer, BufferSnapshot, LanguageRegistry, StructuralPattern};
 use parking_lot::Mutex;
 use postage::oneshot;
 use rpc::{AnyProtoClient, proto};
@@ -43,6 +43,7 @@ enum SearchKind {
     /// Search for candidates by inspecting file contents on file system, avoiding loading the buffer unless we know that a given file contains a match.
     Local {
         fs: Arc<dyn Fs>,
+        languages: Arc<LanguageRegistry>,
         worktrees: Vec<Entity<Worktree>>,
     },
     /// Query remote host for candidates. As of writing, the host runs a local search in "buffers with matches only" mode.
@@ -88,6 +89,9 @@ impl SearchResultsHandle {
 enum FindSearchCandidates {
     Local {
         fs: Arc<dyn Fs>,
+        /// Used to rule out files whose language can't contain matches of a
+        /// structural query.
+        languages: Arc<LanguageRegistry>,
         /// Start off with all paths in project and filter them based on:
         /// - Include filters
         /// - Exclude filters
@@ -107,6 +111,7 @@ enum FindSearchCandidates {
 impl Search {
     pub fn local(
         fs: Arc<dyn Fs>,
+        languages: Arc<LanguageRegistry>,
         buffer_store: Entity<BufferStore>,
         worktree_store: Entity<WorktreeStore>,
         limit: usize,
@@ -114,7 +119,11 @@ impl Search {
     ) -> Self {
         let worktrees = worktree_store.read(cx).visible_worktrees(cx).collect();
         Self {
-            kind: SearchKind::Local { fs, worktrees },
+            kind: SearchKind::Local {
+                fs,
+                languages,
+                worktrees,
+            },
             buffer_store,
             worktree_store,
             limit,
@@ -206,6 +215,7 @@ impl Search {
                     }
                     SearchKind::Local {
                         fs,
+                        languages,
                         ref mut worktrees,
                     } => {
                         let (get_buffer_for_full_scan_tx, get_buffer_for_full_scan_rx) =
@@ -240,6 +250,7 @@ impl Search {
                         (
                             FindSearchCandidates::Local {
                                 fs,
+                                languages,
                                 confirm_contents_will_match_tx,
                                 confirm_contents_will_match_rx,
                                 input_paths_rx,
@@ -370,6 +381,7 @@ impl Search {
                             grab_buffer_snapshot_rx,
                             find_all_matches_tx,
                             sorted_matches_tx,
+                            query.is_structural(),
                             cx.clone(),
                         )
                         .boxed_local(),
@@ -538,10 +550,14 @@ impl Search {
             oneshot::Sender<(Entity<Buffer>, Vec<Range<language::Anchor>>)>,
         )>,
         results: Sender<oneshot::Receiver<(Entity<Buffer>, Vec<Range<language::Anchor>>)>>,
+        wait_for_syntax: bool,
         mut cx: AsyncApp,
     ) {
         _ = maybe!(async move {
             while let Ok(buffer) = rx.recv().await {
+                if wait_for_syntax {
+                    Self::wait_for_syntax(&buffer, &mut cx).await;
+                }
                 let snapshot = buffer.read_with(&mut cx, |this, _| this.snapshot());
                 let (tx, rx) = oneshot::channel();
                 find_all_matches_tx.send((buffer, snapshot, tx)).await?;
@@ -553,6 +569,43 @@ impl Search {
         .await;
     }
 
+    /// Structural queries are matched against syntax trees, so buffers that
+    /// were just opened need their language to be assigned and parsed first.
+    async fn wait_for_syntax(buffer: &Entity<Buffer>, cx: &mut AsyncApp) {
+        const LANGUAGE_DETECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+        let (language_changed_tx, language_changed_rx) = futures::channel::oneshot::channel();
+        let subscription = cx.update(|cx| {
+            let buffer_ref = buffer.read(cx);
+            let expects_language = buffer_ref.language().is_none()
+                && buffer_ref
+                    .language_registry()
+                    .zip(buffer_ref.file())
+                    .is_some_and(|(registry, file)| {
+                        registry
+                            .language_for_file(file, Some(buffer_ref.as_rope()), cx)
+                            .is_some()
+                    });
+            expects_language.then(|| {
+                let mut language_changed_tx = Some(language_changed_tx);
+                cx.subscribe(buffer, move |_, event, _| {
+                    if let language::BufferEvent::LanguageChanged(_) = event
+                        && let Some(tx) = language_changed_tx.take()
+                    {
+                        tx.send(()).ok();
+                    }
+                })
+            })
+        });
+        if subscription.is_some() {
+            let timeout = cx.background_executor().timer(LANGUAGE_DETECTION_TIMEOUT);
+            futures::future::select(language_changed_rx, timeout).await;
+        }
+
+        let parsing_idle = buffer.read_with(cx, |buffer, _| buffer.parsing_idle());
+        parsing_idle.await;
+    }
+
     async fn ensure_matched_ranges_are_reported_in_order(
         rx: Receiver<oneshot::Receiver<(Entity<Buffer>, Vec<Range<language::Anchor>>)>>,
         tx: Sender<SearchResult>,
@@ -565,6 +618,10 @@ impl Search {
                 let Some((buffer, ranges)) = next_buffer_matches.recv().await else {
                     continue;
                 };
+                // Structural queries can't rule out every non-matching file up front.
+                if ranges.is_empty() {
+                    continue;
+                }
 
                 if matched_buffers > Search::MAX_SEARCH_RESULT_FILES
                     || matches > Search::MAX_SEARCH_RESULT_RANGES
@@ -645,9 +702,11 @@ impl Worker {
             confirm_contents_will_match_rx,
             mut confirm_contents_will_match_tx,
             fs,
+            languages,
         ) = match self.candidates {
             FindSearchCandidates::Local {
                 fs,
+                languages,
                 input_paths_rx,
                 confirm_contents_will_match_rx,
                 confirm_contents_will_match_tx,
@@ -656,9 +715,10 @@ impl Worker {
                 confirm_contents_will_match_rx,
                 confirm_contents_will_match_tx,
                 Some(fs),
+                Some(languages),
             ),
             FindSearchCandidates::Remote | FindSearchCandidates::OpenBuffersOnly => {
-                (unbounded().1, unbounded().1, unbounded().0, None)
+                (unbounded().1, unbounded().1, unbounded().0, None, None)
             }
         };
         // WorkerA: grabs a request for "find all matches in file/a" <- takes 5 minutes
@@ -672,6 +732,7 @@ impl Worker {
                 query: &self.query,
                 open_entries: &self.open_buffers,
                 fs: fs.as_deref(),
+                languages: languages.as_ref(),
                 confirm_contents_will_match_tx: &confirm_contents_will_match_tx,
             };
             // Whenever we notice that some step of a pipeline is closed, we don't want to close subsequent
@@ -711,6 +772,7 @@ impl Worker {
 struct RequestHandler<'worker> {
     query: &'worker SearchQuery,
     fs: Option<&'worker dyn Fs>,
+    languages: Option<&'worker Arc<LanguageRegistry>>,
     open_entries: &'worker HashSet<ProjectEntryId>,
     confirm_contents_will_match_tx: &'worker Sender<MatchingEntry>,
 }
@@ -798,6 +860,12 @@ impl RequestHandler<'_> {
                 }
             }
 
+            if let Some(pattern) = self.query.structural_pattern()
+                && !self.has_grammar_for_pattern(pattern, &entry.path).await
+            {
+                return Ok(());
+            }
+
             if self.open_entries.contains(&entry.id) {
                 // The buffer is already in memory and that's the version we want to scan;
                 // hence skip the dilly-dally and look for all matches straight away.
@@ -826,6 +894,26 @@ impl RequestHandler<'_> {
     }
 }
 
+impl RequestHandler<'_> {
+    /// Whether the language of the file at `path` has a grammar that the
+    /// structural `pattern` compiles against. Files in other languages can't
+    /// contain matches, so they're skipped without being opened.
+    async fn has_grammar_for_pattern(&self, pattern: &StructuralPattern, path: &RelPath) -> bool {
+        let Some(languages) = self.languages else {
+            return true;
+        };
+        let Ok(language) = languages
+            .load_language_for_file_path(path.as_std_path())
+            .await
+        else {
+            return false;
+        };
+        language
+            .grammar()
+            .is_some_and(|grammar| pattern.compiles_for(grammar))
+    }
+}
+
 struct InputPath {
     entry: Entry,
     snapshot: Snapshot,
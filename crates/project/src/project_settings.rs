@@ -1,5 +1,5 @@
 use anyhow::Context as _;
-use collections::HashMap;
+use collections::{HashMap, HashSet};
 use context_server::ContextServerCommand;
 use dap::adapters::DebugAdapterName;
 use fs::Fs;
@@ -8,9 +8,9 @@ use git::repository::DEFAULT_WORKTREE_DIRECTORY;
 use gpui::{AsyncApp, BorrowAppContext, Context, Entity, EventEmitter, Subscription, Task};
 use lsp::{DEFAULT_LSP_REQUEST_TIMEOUT_SECS, LanguageServerName};
 use paths::{
-    EDITORCONFIG_NAME, local_debug_file_relative_path, local_settings_file_relative_path,
-    local_tasks_file_relative_path, local_vscode_launch_file_relative_path,
-    local_vscode_tasks_file_relative_path, task_file_name,
+    EDITORCONFIG_NAME, local_debug_file_relative_path, local_keymap_file_relative_path,
+    local_settings_file_relative_path, local_tasks_file_relative_path,
+    local_vscode_launch_file_relative_path, local_vscode_tasks_file_relative_path, task_file_name,
 };
 use rpc::{
     AnyProtoClient, TypedEnvelope,
@@ -26,14 +26,22 @@ use settings::{
     LocalSettingsPath, RegisterSetting, SemanticTokenRules, Settings, SettingsLocation,
     SettingsStore, parse_json_with_comments, watch_config_file,
 };
-use std::{cell::OnceCell, collections::BTreeMap, path::PathBuf, sync::Arc, time::Duration};
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 use task::{DebugTaskFile, TaskTemplates, VsCodeDebugTaskFile, VsCodeTaskFile};
 use util::{ResultExt, rel_path::RelPath, serde::default_true};
 use worktree::{PathChange, UpdatedEntriesSet, Worktree, WorktreeId};
 
 use crate::{
     task_store::{TaskSettingsLocation, TaskStore},
-    trusted_worktrees::{PathTrust, TrustedWorktrees, TrustedWorktreesEvent},
+    trusted_worktrees::{
+        PathTrust, TrustCapability, TrustedConfigFile, TrustedWorktrees, TrustedWorktreesEvent,
+        TrustedWorktreesStore,
+    },
     worktree_store::{WorktreeStore, WorktreeStoreEvent},
 };
 
@@ -744,10 +752,18 @@ pub enum SettingsObserverEvent {
     LocalSettingsUpdated(Result<PathBuf, InvalidSettingsError>),
     LocalTasksUpdated(Result<PathBuf, InvalidSettingsError>),
     LocalDebugScenariosUpdated(Result<PathBuf, InvalidSettingsError>),
+    LocalKeymapsUpdated,
 }
 
 impl EventEmitter<SettingsObserverEvent> for SettingsObserver {}
 
+/// A `.zed/keymap.json` file of a local worktree, allowed to be loaded.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LocalKeymap {
+    pub abs_path: Arc<Path>,
+    pub content: String,
+}
+
 pub struct SettingsObserver {
     mode: SettingsObserverMode,
     downstream_client: Option<AnyProtoClient>,
@@ -756,6 +772,13 @@ pub struct SettingsObserver {
     task_store: Entity<TaskStore>,
     pending_local_settings:
         HashMap<PathTrust, BTreeMap<(WorktreeId, Arc<RelPath>), Option<String>>>,
+    pending_local_tasks: HashMap<PathTrust, BTreeMap<(WorktreeId, Arc<RelPath>), Option<String>>>,
+    /// Worktree settings, applied since their worktree was allowed to use them, so they can be unapplied once it is not anymore.
+    applied_local_settings: BTreeMap<(WorktreeId, Arc<RelPath>), String>,
+    /// Worktree tasks, applied since their worktree was allowed to use them, so they can be unapplied once it is not anymore.
+    applied_local_tasks: BTreeMap<(WorktreeId, Arc<RelPath>), String>,
+    local_keymaps: BTreeMap<WorktreeId, LocalKeymap>,
+    pending_local_keymaps: HashMap<PathTrust, Option<String>>,
     _trusted_worktrees_watcher: Option<Subscription>,
     _user_settings_watcher: Option<Subscription>,
     _editorconfig_watcher: Option<Subscription>,
@@ -786,53 +809,79 @@ impl SettingsObserver {
 
         let _trusted_worktrees_watcher =
             TrustedWorktrees::try_get_global(cx).map(|trusted_worktrees| {
-                cx.subscribe(
+                let trust_subscription = cx.subscribe(
                     &trusted_worktrees,
-                    move |settings_observer, _, e, cx| match e {
+                    move |settings_observer, trusted_worktrees, e, cx| match e {
                         TrustedWorktreesEvent::Trusted(_, trusted_paths) => {
                             for trusted_path in trusted_paths {
-                                if let Some(pending_local_settings) = settings_observer
-                                    .pending_local_settings
-                                    .remove(trusted_path)
-                                {
-                                    for ((worktree_id, directory_path), settings_contents) in
-                                        pending_local_settings
-                                    {
-                                        let path =
-                                            LocalSettingsPath::InWorktree(directory_path.clone());
-                                        apply_local_settings(
-                                            worktree_id,
-                                            path.clone(),
-                                            LocalSettingsKind::Settings,
-                                            &settings_contents,
-                                            cx,
-                                        );
-                                        if let Some(downstream_client) =
-                                            &settings_observer.downstream_client
-                                        {
-                                            downstream_client
-                                                .send(proto::UpdateWorktreeSettings {
-                                                    project_id: settings_observer.project_id,
-                                                    worktree_id: worktree_id.to_proto(),
-                                                    path: path.to_proto(),
-                                                    content: settings_contents,
-                                                    kind: Some(
-                                                        local_settings_kind_to_proto(
-                                                            LocalSettingsKind::Settings,
-                                                        )
-                                                        .into(),
-                                                    ),
-                                                    outside_worktree: Some(false),
-                                                })
-                                                .log_err();
-                                        }
-                                    }
-                                }
+                                settings_observer.apply_pending_local_settings(trusted_path, cx);
+                                settings_observer.apply_pending_local_tasks(trusted_path, cx);
+                                settings_observer.apply_pending_local_keymap(trusted_path, cx);
+                            }
+                        }
+                        TrustedWorktreesEvent::Restricted(worktree_store, restricted_paths) => {
+                            if *worktree_store == settings_observer.worktree_store.downgrade() {
+                                settings_observer.unapply_restricted_configs(
+                                    &trusted_worktrees,
+                                    restricted_paths,
+                                    cx,
+                                );
                             }
                         }
-                        TrustedWorktreesEvent::Restricted(..) => {}
                     },
-                )
+                );
+                let grants_subscription = cx.observe(
+                    &trusted_worktrees,
+                    |settings_observer, trusted_worktrees, cx| {
+                        let trusted_worktrees = trusted_worktrees.read(cx);
+                        let worktree_store = &settings_observer.worktree_store;
+                        let is_granted = |path_trust: &PathTrust, capability: TrustCapability| {
+                            match path_trust {
+                                PathTrust::Worktree(worktree_id) => trusted_worktrees.is_granted(
+                                    worktree_store,
+                                    *worktree_id,
+                                    &capability,
+                                    cx,
+                                ),
+                                PathTrust::AbsPath(..) => false,
+                            }
+                        };
+                        let granted_settings = settings_observer
+                            .pending_local_settings
+                            .keys()
+                            .filter(|path_trust| {
+                                is_granted(path_trust, TrustCapability::ProjectSettings)
+                            })
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        let granted_tasks = settings_observer
+                            .pending_local_tasks
+                            .keys()
+                            .filter(|path_trust| {
+                                is_granted(path_trust, TrustCapability::ProjectTasks)
+                            })
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        let granted_keymaps = settings_observer
+                            .pending_local_keymaps
+                            .keys()
+                            .filter(|path_trust| {
+                                is_granted(path_trust, TrustCapability::ProjectKeymap)
+                            })
+                            .cloned()
+                            .collect::<Vec<_>>();
+                        for path_trust in granted_settings {
+                            settings_observer.apply_pending_local_settings(&path_trust, cx);
+                        }
+                        for path_trust in granted_tasks {
+                            settings_observer.apply_pending_local_tasks(&path_trust, cx);
+                        }
+                        for path_trust in granted_keymaps {
+                            settings_observer.apply_pending_local_keymap(&path_trust, cx);
+                        }
+                    },
+                );
+                Subscription::join(trust_subscription, grants_subscription)
             });
 
         let editorconfig_store = cx.global::<SettingsStore>().editorconfig_store.clone();
@@ -872,6 +921,11 @@ impl SettingsObserver {
             downstream_client: None,
             _trusted_worktrees_watcher,
             pending_local_settings: HashMap::default(),
+            pending_local_tasks: HashMap::default(),
+            applied_local_settings: BTreeMap::new(),
+            applied_local_tasks: BTreeMap::new(),
+            local_keymaps: BTreeMap::new(),
+            pending_local_keymaps: HashMap::default(),
             _user_settings_watcher: None,
             _editorconfig_watcher: Some(_editorconfig_watcher),
             project_id: REMOTE_SERVER_PROJECT_ID,
@@ -936,6 +990,11 @@ impl SettingsObserver {
             project_id: REMOTE_SERVER_PROJECT_ID,
             _trusted_worktrees_watcher: None,
             pending_local_settings: HashMap::default(),
+            pending_local_tasks: HashMap::default(),
+            applied_local_settings: BTreeMap::new(),
+            applied_local_tasks: BTreeMap::new(),
+            local_keymaps: BTreeMap::new(),
+            pending_local_keymaps: HashMap::default(),
             _user_settings_watcher: user_settings_watcher,
             _editorconfig_watcher: None,
             _global_task_config_watcher: Self::subscribe_to_global_task_file_changes(
@@ -1080,6 +1139,15 @@ impl SettingsObserver {
                 cx.update_global::<SettingsStore, _>(|store, cx| {
                     store.clear_local_settings(*worktree_id, cx).log_err();
                 });
+                self.pending_local_keymaps
+                    .remove(&PathTrust::Worktree(*worktree_id));
+                self.applied_local_settings
+                    .retain(|(id, _), _| id != worktree_id);
+                self.applied_local_tasks
+                    .retain(|(id, _), _| id != worktree_id);
+                if self.local_keymaps.remove(worktree_id).is_some() {
+                    cx.emit(SettingsObserverEvent::LocalKeymapsUpdated);
+                }
             }
             _ => {}
         }
@@ -1096,7 +1164,13 @@ impl SettingsObserver {
         };
 
         let mut settings_contents = Vec::new();
+        let mut keymap_change = None;
         for (path, _, change) in changes.iter() {
+            // Unlike settings and tasks, only the keymap at the root of the worktree is loaded.
+            if &**path == local_keymap_file_relative_path() {
+                keymap_change = Some(change != &PathChange::Removed);
+                continue;
+            }
             let (settings_dir, kind) = if path.ends_with(local_settings_file_relative_path()) {
                 let settings_dir = path
                     .ancestors()
@@ -1236,6 +1310,26 @@ impl SettingsObserver {
             });
         }
 
+        if let Some(exists) = keymap_change {
+            let fs = fs.clone();
+            let abs_path = worktree
+                .read(cx)
+                .absolutize(local_keymap_file_relative_path());
+            let worktree_id = worktree.read(cx).id();
+            cx.spawn(async move |this, cx| {
+                let content = if exists {
+                    fs.load(&abs_path).await.log_err()
+                } else {
+                    None
+                };
+                this.update(cx, |this, cx| {
+                    this.update_local_keymap(worktree_id, content, cx)
+                })
+                .ok();
+            })
+            .detach();
+        }
+
         if settings_contents.is_empty() {
             return;
         }
@@ -1274,33 +1368,56 @@ impl SettingsObserver {
         cx: &mut Context<Self>,
     ) {
         let worktree_id = worktree.read(cx).id();
-        let remote_worktree_id = worktree.read(cx).id();
         let task_store = self.task_store.clone();
-        let can_trust_worktree = if is_via_collab {
-            OnceCell::from(true)
-        } else {
-            OnceCell::new()
-        };
+        let settings_contents = settings_contents.into_iter().collect::<Vec<_>>();
+        if !is_via_collab {
+            if let Some(trusted_worktrees) = TrustedWorktrees::try_get_global(cx) {
+                trusted_worktrees.update(cx, |trusted_worktrees, cx| {
+                    trusted_worktrees.update_configs(
+                        &self.worktree_store,
+                        worktree_id,
+                        settings_contents.iter().filter_map(
+                            |(directory_path, kind, file_content)| {
+                                let LocalSettingsPath::InWorktree(directory) = directory_path
+                                else {
+                                    return None;
+                                };
+                                let config_file = match kind {
+                                    LocalSettingsKind::Settings => TrustedConfigFile::Settings,
+                                    LocalSettingsKind::Tasks => TrustedConfigFile::Tasks,
+                                    _ => return None,
+                                };
+                                Some((config_file, directory.clone(), file_content.as_deref()))
+                            },
+                        ),
+                        cx,
+                    )
+                });
+            }
+        }
+
         for (directory_path, kind, file_content) in settings_contents {
             let mut applied = true;
             match (&directory_path, kind) {
                 (LocalSettingsPath::InWorktree(directory), LocalSettingsKind::Settings) => {
-                    if *can_trust_worktree.get_or_init(|| {
-                        if let Some(trusted_worktrees) = TrustedWorktrees::try_get_global(cx) {
-                            trusted_worktrees.update(cx, |trusted_worktrees, cx| {
-                                trusted_worktrees.can_trust(&self.worktree_store, worktree_id, cx)
-                            })
-                        } else {
-                            true
-                        }
-                    }) {
+                    if self.can_use(
+                        worktree_id,
+                        &TrustCapability::ProjectSettings,
+                        is_via_collab,
+                        cx,
+                    ) {
                         apply_local_settings(
                             worktree_id,
                             LocalSettingsPath::InWorktree(directory.clone()),
                             kind,
                             &file_content,
                             cx,
-                        )
+                        );
+                        track_applied_config(
+                            &mut self.applied_local_settings,
+                            (worktree_id, directory.clone()),
+                            file_content.clone(),
+                        );
                     } else {
                         applied = false;
                         self.pending_local_settings
@@ -1310,32 +1427,24 @@ impl SettingsObserver {
                     }
                 }
                 (LocalSettingsPath::InWorktree(directory), LocalSettingsKind::Tasks) => {
-                    let result = task_store.update(cx, |task_store, cx| {
-                        task_store.update_user_tasks(
-                            TaskSettingsLocation::Worktree(SettingsLocation {
-                                worktree_id,
-                                path: directory.as_ref(),
-                            }),
-                            file_content.as_deref(),
-                            cx,
-                        )
-                    });
-
-                    match result {
-                        Err(InvalidSettingsError::Tasks { path, message }) => {
-                            log::error!("Failed to set local tasks in {path:?}: {message:?}");
-                            cx.emit(SettingsObserverEvent::LocalTasksUpdated(Err(
-                                InvalidSettingsError::Tasks { path, message },
-                            )));
-                        }
-                        Err(e) => {
-                            log::error!("Failed to set local tasks: {e}");
-                        }
-                        Ok(()) => {
-                            cx.emit(SettingsObserverEvent::LocalTasksUpdated(Ok(directory
-                                .as_std_path()
-                                .join(task_file_name()))));
-                        }
+                    if self.can_use(
+                        worktree_id,
+                        &TrustCapability::ProjectTasks,
+                        is_via_collab,
+                        cx,
+                    ) {
+                        self.apply_local_tasks(worktree_id, directory, file_content.as_deref(), cx);
+                        track_applied_config(
+                            &mut self.applied_local_tasks,
+                            (worktree_id, directory.clone()),
+                            file_content.clone(),
+                        );
+                    } else {
+                        applied = false;
+                        self.pending_local_tasks
+                            .entry(PathTrust::Worktree(worktree_id))
+                            .or_default()
+                            .insert((worktree_id, directory.clone()), file_content.clone());
                     }
                 }
                 (LocalSettingsPath::InWorktree(directory), LocalSettingsKind::Debug) => {
@@ -1383,19 +1492,293 @@ impl SettingsObserver {
             };
 
             if applied {
-                if let Some(downstream_client) = &self.downstream_client {
-                    downstream_client
-                        .send(proto::UpdateWorktreeSettings {
-                            project_id: self.project_id,
-                            worktree_id: remote_worktree_id.to_proto(),
-                            path: directory_path.to_proto(),
-                            content: file_content.clone(),
-                            kind: Some(local_settings_kind_to_proto(kind).into()),
-                            outside_worktree: Some(directory_path.is_outside_worktree()),
-                        })
-                        .log_err();
+                self.send_local_settings_downstream(
+                    worktree_id,
+                    &directory_path,
+                    kind,
+                    file_content,
+                );
+            }
+        }
+    }
+
+    fn update_local_keymap(
+        &mut self,
+        worktree_id: WorktreeId,
+        content: Option<String>,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(trusted_worktrees) = TrustedWorktrees::try_get_global(cx) {
+            trusted_worktrees.update(cx, |trusted_worktrees, cx| {
+                trusted_worktrees.update_configs(
+                    &self.worktree_store,
+                    worktree_id,
+                    [(
+                        TrustedConfigFile::Keymap,
+                        RelPath::empty().into_arc(),
+                        content.as_deref(),
+                    )],
+                    cx,
+                )
+            });
+        }
+
+        if self.can_use(worktree_id, &TrustCapability::ProjectKeymap, false, cx) {
+            self.apply_local_keymap(worktree_id, content, cx);
+        } else {
+            self.pending_local_keymaps
+                .insert(PathTrust::Worktree(worktree_id), content);
+            // Bindings, loaded before the worktree got restricted, should not stay around.
+            self.apply_local_keymap(worktree_id, None, cx);
+        }
+    }
+
+    fn apply_pending_local_keymap(&mut self, path_trust: &PathTrust, cx: &mut Context<Self>) {
+        let PathTrust::Worktree(worktree_id) = path_trust else {
+            return;
+        };
+        if let Some(content) = self.pending_local_keymaps.remove(path_trust) {
+            self.apply_local_keymap(*worktree_id, content, cx);
+        }
+    }
+
+    fn apply_local_keymap(
+        &mut self,
+        worktree_id: WorktreeId,
+        content: Option<String>,
+        cx: &mut Context<Self>,
+    ) {
+        let local_keymap = content.and_then(|content| {
+            let worktree = self
+                .worktree_store
+                .read(cx)
+                .worktree_for_id(worktree_id, cx)?;
+            let abs_path = worktree
+                .read(cx)
+                .absolutize(local_keymap_file_relative_path());
+            Some(LocalKeymap {
+                abs_path: abs_path.into(),
+                content,
+            })
+        });
+        let changed = match local_keymap {
+            Some(local_keymap) => {
+                self.local_keymaps.insert(worktree_id, local_keymap.clone()) != Some(local_keymap)
+            }
+            None => self.local_keymaps.remove(&worktree_id).is_some(),
+        };
+        if changed {
+            cx.emit(SettingsObserverEvent::LocalKeymapsUpdated);
+        }
+    }
+
+    /// The `.zed/keymap.json` files of the local worktrees, allowed to be loaded.
+    pub fn local_keymaps(&self) -> impl Iterator<Item = &LocalKeymap> {
+        self.local_keymaps.values()
+    }
+
+    /// Whether the worktree may use a capability, see [`TrustedWorktreesStore::can_use`].
+    ///
+    /// [`TrustedWorktreesStore::can_use`]: crate::trusted_worktrees::TrustedWorktreesStore::can_use
+    fn can_use(
+        &self,
+        worktree_id: WorktreeId,
+        capability: &TrustCapability,
+        is_via_collab: bool,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        if is_via_collab {
+            return true;
+        }
+        match TrustedWorktrees::try_get_global(cx) {
+            Some(trusted_worktrees) => trusted_worktrees.update(cx, |trusted_worktrees, cx| {
+                trusted_worktrees.can_use(&self.worktree_store, worktree_id, capability, cx)
+            }),
+            None => true,
+        }
+    }
+
+    fn apply_pending_local_settings(&mut self, path_trust: &PathTrust, cx: &mut Context<Self>) {
+        let Some(pending_local_settings) = self.pending_local_settings.remove(path_trust) else {
+            return;
+        };
+        for ((worktree_id, directory_path), settings_contents) in pending_local_settings {
+            track_applied_config(
+                &mut self.applied_local_settings,
+                (worktree_id, directory_path.clone()),
+                settings_contents.clone(),
+            );
+            let path = LocalSettingsPath::InWorktree(directory_path);
+            apply_local_settings(
+                worktree_id,
+                path.clone(),
+                LocalSettingsKind::Settings,
+                &settings_contents,
+                cx,
+            );
+            self.send_local_settings_downstream(
+                worktree_id,
+                &path,
+                LocalSettingsKind::Settings,
+                settings_contents,
+            );
+        }
+    }
+
+    /// Unapplies the settings, tasks and keymaps of the worktrees that just got restricted, unless they were granted to use them.
+    /// They are kept pending, so they are applied again once the worktree is trusted again or granted the capability.
+    fn unapply_restricted_configs(
+        &mut self,
+        trusted_worktrees: &Entity<TrustedWorktreesStore>,
+        restricted_paths: &HashSet<PathTrust>,
+        cx: &mut Context<Self>,
+    ) {
+        for restricted_path in restricted_paths {
+            let PathTrust::Worktree(worktree_id) = restricted_path else {
+                continue;
+            };
+            let worktree_id = *worktree_id;
+            let [keep_settings, keep_tasks, keep_keymap] = [
+                TrustCapability::ProjectSettings,
+                TrustCapability::ProjectTasks,
+                TrustCapability::ProjectKeymap,
+            ]
+            .map(|capability| {
+                trusted_worktrees.read(cx).is_granted(
+                    &self.worktree_store,
+                    worktree_id,
+                    &capability,
+                    cx,
+                )
+            });
+
+            if !keep_settings {
+                for ((_, directory), content) in
+                    take_applied_configs(&mut self.applied_local_settings, worktree_id)
+                {
+                    // Newer contents might be pending already, when a config change caused the restriction.
+                    self.pending_local_settings
+                        .entry(restricted_path.clone())
+                        .or_default()
+                        .entry((worktree_id, directory.clone()))
+                        .or_insert(Some(content));
+                    let path = LocalSettingsPath::InWorktree(directory);
+                    apply_local_settings(
+                        worktree_id,
+                        path.clone(),
+                        LocalSettingsKind::Settings,
+                        &None,
+                        cx,
+                    );
+                    self.send_local_settings_downstream(
+                        worktree_id,
+                        &path,
+                        LocalSettingsKind::Settings,
+                        None,
+                    );
+                }
+            }
+            if !keep_tasks {
+                for ((_, directory), content) in
+                    take_applied_configs(&mut self.applied_local_tasks, worktree_id)
+                {
+                    self.pending_local_tasks
+                        .entry(restricted_path.clone())
+                        .or_default()
+                        .entry((worktree_id, directory.clone()))
+                        .or_insert(Some(content));
+                    self.apply_local_tasks(worktree_id, &directory, None, cx);
+                    self.send_local_settings_downstream(
+                        worktree_id,
+                        &LocalSettingsPath::InWorktree(directory),
+                        LocalSettingsKind::Tasks,
+                        None,
+                    );
                 }
             }
+            if !keep_keymap && let Some(local_keymap) = self.local_keymaps.get(&worktree_id) {
+                self.pending_local_keymaps
+                    .entry(restricted_path.clone())
+                    .or_insert(Some(local_keymap.content.clone()));
+                self.apply_local_keymap(worktree_id, None, cx);
+            }
+        }
+    }
+
+    fn apply_pending_local_tasks(&mut self, path_trust: &PathTrust, cx: &mut Context<Self>) {
+        let Some(pending_local_tasks) = self.pending_local_tasks.remove(path_trust) else {
+            return;
+        };
+        for ((worktree_id, directory_path), tasks_contents) in pending_local_tasks {
+            track_applied_config(
+                &mut self.applied_local_tasks,
+                (worktree_id, directory_path.clone()),
+                tasks_contents.clone(),
+            );
+            self.apply_local_tasks(worktree_id, &directory_path, tasks_contents.as_deref(), cx);
+            self.send_local_settings_downstream(
+                worktree_id,
+                &LocalSettingsPath::InWorktree(directory_path),
+                LocalSettingsKind::Tasks,
+                tasks_contents,
+            );
+        }
+    }
+
+    fn apply_local_tasks(
+        &self,
+        worktree_id: WorktreeId,
+        directory: &Arc<RelPath>,
+        tasks_content: Option<&str>,
+        cx: &mut Context<Self>,
+    ) {
+        let result = self.task_store.update(cx, |task_store, cx| {
+            task_store.update_user_tasks(
+                TaskSettingsLocation::Worktree(SettingsLocation {
+                    worktree_id,
+                    path: directory.as_ref(),
+                }),
+                tasks_content,
+                cx,
+            )
+        });
+
+        match result {
+            Err(InvalidSettingsError::Tasks { path, message }) => {
+                log::error!("Failed to set local tasks in {path:?}: {message:?}");
+                cx.emit(SettingsObserverEvent::LocalTasksUpdated(Err(
+                    InvalidSettingsError::Tasks { path, message },
+                )));
+            }
+            Err(e) => {
+                log::error!("Failed to set local tasks: {e}");
+            }
+            Ok(()) => {
+                cx.emit(SettingsObserverEvent::LocalTasksUpdated(Ok(directory
+                    .as_std_path()
+                    .join(task_file_name()))));
+            }
+        }
+    }
+
+    fn send_local_settings_downstream(
+        &self,
+        worktree_id: WorktreeId,
+        path: &LocalSettingsPath,
+        kind: LocalSettingsKind,
+        content: Option<String>,
+    ) {
+        if let Some(downstream_client) = &self.downstream_client {
+            downstream_client
+                .send(proto::UpdateWorktreeSettings {
+                    project_id: self.project_id,
+                    worktree_id: worktree_id.to_proto(),
+                    path: path.to_proto(),
+                    content,
+                    kind: Some(local_settings_kind_to_proto(kind).into()),
+                    outside_worktree: Some(path.is_outside_worktree()),
+                })
+                .log_err();
         }
     }
 
@@ -1511,6 +1894,39 @@ impl SettingsObserver {
     }
 }
 
+/// Remembers the contents of a config file that were just applied, or forgets them if the file was removed.
+fn track_applied_config(
+    applied_configs: &mut BTreeMap<(WorktreeId, Arc<RelPath>), String>,
+    key: (WorktreeId, Arc<RelPath>),
+    content: Option<String>,
+) {
+    match content {
+        Some(content) => {
+            applied_configs.insert(key, content);
+        }
+        None => {
+            applied_configs.remove(&key);
+        }
+    }
+}
+
+fn take_applied_configs(
+    applied_configs: &mut BTreeMap<(WorktreeId, Arc<RelPath>), String>,
+    worktree_id: WorktreeId,
+) -> Vec<((WorktreeId, Arc<RelPath>), String)> {
+    let keys = applied_configs
+        .keys()
+        .filter(|(id, _)| *id == worktree_id)
+        .cloned()
+        .collect::<Vec<_>>();
+    keys.into_iter()
+        .filter_map(|key| {
+            let content = applied_configs.remove(&key)?;
+            Some((key, content))
+        })
+        .collect()
+}
+
 fn apply_local_settings(
     worktree_id: WorktreeId,
     path: LocalSettingsPath,
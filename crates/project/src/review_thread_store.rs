@@ -0,0 +1,276 @@
+//! Module for review comment threads in shared projects.
+//!
+//! Threads are anchored to the code they comment on, so they follow it as the buffer is
+//! edited. The collab server stores them alongside the shared project, which means they
+//! last as long as the project is shared. Every collaborator sends changes to the server,
+//! which sends them on to everyone else in the project.
+use anyhow::{Context as _, Result};
+use collections::BTreeMap;
+use gpui::{AsyncApp, Context, Entity, EventEmitter, Task};
+use language::{
+    Buffer,
+    proto::{deserialize_anchor, serialize_anchor},
+};
+use rpc::{AnyProtoClient, TypedEnvelope, proto};
+use std::ops::Range;
+use text::BufferId;
+use util::{ResultExt as _, rel_path::RelPath};
+
+use crate::{ProjectPath, WorktreeId};
+
+#[derive(Clone, Debug)]
+pub struct ReviewThread {
+    pub id: u64,
+    pub project_path: ProjectPath,
+    pub buffer_id: BufferId,
+    pub range: Range<text::Anchor>,
+    /// The commented code when the thread was started.
+    pub original_text: String,
+    pub resolved: bool,
+    /// The comments in the thread, the first of which started it.
+    pub comments: Vec<ReviewComment>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ReviewComment {
+    pub id: u64,
+    pub user_id: u64,
+    pub body: String,
+    /// When the comment was made, in seconds since the Unix epoch.
+    pub timestamp: u64,
+}
+
+impl ReviewThread {
+    fn from_proto(thread: proto::ReviewThread) -> Result<Self> {
+        Ok(Self {
+            id: thread.id,
+            project_path: ProjectPath {
+                worktree_id: WorktreeId::from_proto(thread.worktree_id),
+                path: RelPath::from_proto(&thread.path)?,
+            },
+            buffer_id: BufferId::new(thread.buffer_id)?,
+            range: thread
+                .start
+                .and_then(deserialize_anchor)
+                .context("invalid start anchor")?
+                ..thread
+                    .end
+                    .and_then(deserialize_anchor)
+                    .context("invalid end anchor")?,
+            original_text: thread.original_text,
+            resolved: thread.resolved,
+            comments: thread
+                .comments
+                .into_iter()
+                .map(|comment| ReviewComment {
+                    id: comment.id,
+                    user_id: comment.user_id,
+                    body: comment.body,
+                    timestamp: comment.timestamp,
+                })
+                .collect(),
+        })
+    }
+
+    /// Returns whether the commented code changed since the thread was started.
+    pub fn is_outdated(&self, buffer: &text::BufferSnapshot) -> bool {
+        if buffer.remote_id() != self.buffer_id
+            || !buffer.can_resolve(&self.range.start)
+            || !buffer.can_resolve(&self.range.end)
+        {
+            return true;
+        }
+        buffer
+            .text_for_range(self.range.clone())
+            .collect::<String>()
+            != self.original_text
+    }
+}
+
+pub struct ReviewThreadStore {
+    threads: BTreeMap<u64, ReviewThread>,
+    /// The client for the collab server, and the project's ID there, while the project is
+    /// shared.
+    collab: Option<(AnyProtoClient, u64)>,
+    _load_threads: Option<Task<()>>,
+}
+
+pub enum ReviewThreadStoreEvent {
+    /// A thread was started, replied to, resolved or reopened.
+    ThreadUpdated(u64),
+}
+
+impl EventEmitter<ReviewThreadStoreEvent> for ReviewThreadStore {}
+
+impl ReviewThreadStore {
+    pub fn init(client: &AnyProtoClient) {
+        client.add_entity_message_handler(Self::handle_update_review_thread);
+    }
+
+    pub fn local() -> Self {
+        Self {
+            threads: BTreeMap::default(),
+            collab: None,
+            _load_threads: None,
+        }
+    }
+
+    pub(crate) fn remote(project_id: u64, client: AnyProtoClient, cx: &mut Context<Self>) -> Self {
+        let load_threads = cx.spawn({
+            let request = client.request(proto::GetReviewThreads { project_id });
+            async move |this, cx| {
+                let Some(response) = request.await.log_err() else {
+                    return;
+                };
+                this.update(cx, |this, cx| {
+                    for thread in response.threads {
+                        this.insert_thread(thread, cx).log_err();
+                    }
+                })
+                .ok();
+            }
+        });
+        Self {
+            threads: BTreeMap::default(),
+            collab: Some((client, project_id)),
+            _load_threads: Some(load_threads),
+        }
+    }
+
+    pub fn shared(&mut self, project_id: u64, client: AnyProtoClient) {
+        self.collab = Some((client, project_id));
+    }
+
+    pub(crate) fn unshared(&mut self, cx: &mut Context<Self>) {
+        self.collab.take();
+        self.threads.clear();
+        cx.notify();
+    }
+
+    pub(crate) fn disconnected_from_host(&mut self, cx: &mut Context<Self>) {
+        self.collab.take();
+        cx.notify();
+    }
+
+    /// Returns whether threads can be started and replied to, which is only while the
+    /// project is shared.
+    pub fn is_available(&self) -> bool {
+        self.collab.is_some()
+    }
+
+    pub fn threads(&self) -> impl Iterator<Item = &ReviewThread> {
+        self.threads.values()
+    }
+
+    pub fn threads_for_buffer(&self, buffer_id: BufferId) -> impl Iterator<Item = &ReviewThread> {
+        self.threads
+            .values()
+            .filter(move |thread| thread.buffer_id == buffer_id)
+    }
+
+    pub fn thread(&self, thread_id: u64) -> Option<&ReviewThread> {
+        self.threads.get(&thread_id)
+    }
+
+    /// Starts a thread on a range of a buffer, returning its ID.
+    pub fn create_thread(
+        &mut self,
+        buffer: &Entity<Buffer>,
+        range: Range<text::Anchor>,
+        body: String,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<u64>> {
+        let Some((client, project_id)) = self.collab.clone() else {
+            return Task::ready(Err(anyhow::anyhow!("the project isn't shared")));
+        };
+        let buffer = buffer.read(cx);
+        let Some(file) = buffer.file() else {
+            return Task::ready(Err(anyhow::anyhow!("the buffer has no file")));
+        };
+        let project_path = ProjectPath::from_file(file.as_ref(), cx);
+        let request = client.request(proto::CreateReviewThread {
+            project_id,
+            worktree_id: project_path.worktree_id.to_proto(),
+            path: project_path.path.to_proto(),
+            buffer_id: buffer.remote_id().into(),
+            start: Some(serialize_anchor(&range.start)),
+            end: Some(serialize_anchor(&range.end)),
+            original_text: buffer.text_for_range(range).collect(),
+            body,
+        });
+        cx.spawn(async move |this, cx| {
+            let response = request.await?;
+            let thread = response.thread.context("missing review thread")?;
+            this.update(cx, |this, cx| this.insert_thread(thread, cx))?
+        })
+    }
+
+    /// Adds a comment to a thread.
+    pub fn reply(
+        &mut self,
+        thread_id: u64,
+        body: String,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let Some((client, project_id)) = self.collab.clone() else {
+            return Task::ready(Err(anyhow::anyhow!("the project isn't shared")));
+        };
+        let request = client.request(proto::ReplyToReviewThread {
+            project_id,
+            thread_id,
+            body,
+        });
+        cx.spawn(async move |this, cx| {
+            let response = request.await?;
+            let thread = response.thread.context("missing review thread")?;
+            this.update(cx, |this, cx| this.insert_thread(thread, cx))??;
+            Ok(())
+        })
+    }
+
+    /// Resolves a thread, or reopens it if `resolved` is false.
+    pub fn set_resolved(
+        &mut self,
+        thread_id: u64,
+        resolved: bool,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let Some((client, project_id)) = self.collab.clone() else {
+            return Task::ready(Err(anyhow::anyhow!("the project isn't shared")));
+        };
+        let request = client.request(proto::ResolveReviewThread {
+            project_id,
+            thread_id,
+            resolved,
+        });
+        cx.spawn(async move |this, cx| {
+            let response = request.await?;
+            let thread = response.thread.context("missing review thread")?;
+            this.update(cx, |this, cx| this.insert_thread(thread, cx))??;
+            Ok(())
+        })
+    }
+
+    fn insert_thread(
+        &mut self,
+        thread: proto::ReviewThread,
+        cx: &mut Context<Self>,
+    ) -> Result<u64> {
+        let thread = ReviewThread::from_proto(thread)?;
+        let thread_id = thread.id;
+        self.threads.insert(thread_id, thread);
+        cx.emit(ReviewThreadStoreEvent::ThreadUpdated(thread_id));
+        cx.notify();
+        Ok(thread_id)
+    }
+
+    async fn handle_update_review_thread(
+        this: Entity<Self>,
+        message: TypedEnvelope<proto::UpdateReviewThread>,
+        mut cx: AsyncApp,
+    ) -> Result<()> {
+        let thread = message.payload.thread.context("missing review thread")?;
+        this.update(&mut cx, |this, cx| this.insert_thread(thread, cx))?;
+        Ok(())
+    }
+}
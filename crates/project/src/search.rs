@@ -4,7 +4,7 @@ use client::proto;
 use fancy_regex::{Captures, Regex, RegexBuilder};
 use gpui::Entity;
 use itertools::Itertools as _;
-use language::{Buffer, BufferSnapshot, CharKind};
+use language::{Buffer, BufferSnapshot, CharKind, StructuralPattern};
 use smol::future::yield_now;
 use std::{
     borrow::Cow,
@@ -77,6 +77,12 @@ pub enum SearchQuery {
         one_match_per_line: bool,
         inner: SearchInputs,
     },
+    Structural {
+        pattern: Arc<StructuralPattern>,
+        replacement: Option<String>,
+        include_ignored: bool,
+        inner: SearchInputs,
+    },
 }
 
 static WORD_MATCH_TEST: LazyLock<Regex> = LazyLock::new(|| {
@@ -206,6 +212,37 @@ impl SearchQuery {
         })
     }
 
+    /// Create a structural query, which matches a code pattern or Tree-sitter
+    /// query against the syntax tree of each buffer. See [`StructuralPattern`]
+    /// for the pattern syntax.
+    ///
+    /// If `match_full_paths` is true, include/exclude patterns will be matched against fully qualified project paths
+    /// beginning with a project root name. If false, they will be matched against project-relative paths.
+    pub fn structural(
+        query: impl ToString,
+        include_ignored: bool,
+        files_to_include: PathMatcher,
+        files_to_exclude: PathMatcher,
+        match_full_paths: bool,
+        buffers: Option<Vec<Entity<Buffer>>>,
+    ) -> Result<Self> {
+        let query = query.to_string();
+        let pattern = StructuralPattern::new(&query)?;
+        let inner = SearchInputs {
+            query: query.into(),
+            files_to_exclude,
+            files_to_include,
+            match_full_paths,
+            buffers,
+        };
+        Ok(Self::Structural {
+            pattern: Arc::new(pattern),
+            replacement: None,
+            include_ignored,
+            inner,
+        })
+    }
+
     /// Extracts case sensitivity settings from pattern items in the provided
     /// query and returns the same query, with the pattern items removed.
     ///
@@ -270,7 +307,16 @@ impl SearchQuery {
             message.files_to_exclude
         };
 
-        if message.regex {
+        if message.structural {
+            Self::structural(
+                message.query,
+                message.include_ignored,
+                PathMatcher::new(files_to_include, path_style)?,
+                PathMatcher::new(files_to_exclude, path_style)?,
+                message.match_full_paths,
+                None, // search opened only don't need search remote
+            )
+        } else if message.regex {
             Self::regex(
                 message.query,
                 message.whole_word,
@@ -305,6 +351,10 @@ impl SearchQuery {
             | Self::Regex {
                 ref mut replacement,
                 ..
+            }
+            | Self::Structural {
+                ref mut replacement,
+                ..
             } => {
                 *replacement = Some(new_replacement);
                 self
@@ -318,6 +368,7 @@ impl SearchQuery {
         proto::SearchQuery {
             query: self.as_str().to_string(),
             regex: self.is_regex(),
+            structural: self.is_structural(),
             whole_word: self.whole_word(),
             case_sensitive: self.case_sensitive(),
             include_ignored: self.include_ignored(),
@@ -387,19 +438,37 @@ impl SearchQuery {
                     Ok(false)
                 }
             }
+            Self::Structural { pattern, .. } => {
+                // Files are only detected after their language was checked
+                // against the pattern, so a pattern without literals can't
+                // rule out anything more here.
+                if pattern.literals().is_empty() {
+                    return Ok(true);
+                }
+                let mut text = String::new();
+                reader.read_to_string(&mut text)?;
+                Ok(pattern
+                    .literals()
+                    .iter()
+                    .all(|literal| text.contains(literal.as_str())))
+            }
         }
     }
     /// Returns the replacement text for this `SearchQuery`.
     pub fn replacement(&self) -> Option<&str> {
         match self {
-            SearchQuery::Text { replacement, .. } | SearchQuery::Regex { replacement, .. } => {
-                replacement.as_deref()
-            }
+            SearchQuery::Text { replacement, .. }
+            | SearchQuery::Regex { replacement, .. }
+            | SearchQuery::Structural { replacement, .. } => replacement.as_deref(),
         }
     }
     /// Replaces search hits if replacement is set. `text` is assumed to be a string that matches this `SearchQuery` exactly, without any leftovers on either side.
+    ///
+    /// Structural queries need the surrounding syntax tree to resolve their
+    /// metavariables, and are replaced with [`Self::structural_replacement_for`] instead.
     pub fn replacement_for<'a>(&self, text: &'a str) -> Option<Cow<'a, str>> {
         match self {
+            SearchQuery::Structural { .. } => None,
             SearchQuery::Text { replacement, .. } => replacement.clone().map(Cow::from),
             SearchQuery::Regex {
                 regex, replacement, ..
@@ -424,6 +493,26 @@ impl SearchQuery {
         }
     }
 
+    /// Replaces a structural search hit if replacement is set, substituting the
+    /// text captured by each metavariable. `range` is assumed to be the range
+    /// of a match in `buffer`.
+    pub fn structural_replacement_for(
+        &self,
+        buffer: &BufferSnapshot,
+        range: Range<usize>,
+    ) -> Option<String> {
+        let SearchQuery::Structural {
+            pattern,
+            replacement: Some(replacement),
+            ..
+        } = self
+        else {
+            return None;
+        };
+        let mat = pattern.match_at(buffer, range)?;
+        Some(mat.expand_replacement(replacement, buffer))
+    }
+
     pub async fn search(
         &self,
         buffer: &BufferSnapshot,
@@ -435,6 +524,15 @@ impl SearchQuery {
             return Default::default();
         }
 
+        if let Self::Structural { pattern, .. } = self {
+            let range = subrange.unwrap_or(0..buffer.len());
+            return pattern
+                .matches(buffer, range.clone())
+                .into_iter()
+                .map(|mat| mat.range.start - range.start..mat.range.end - range.start)
+                .collect();
+        }
+
         let range_offset = subrange.as_ref().map(|r| r.start).unwrap_or(0);
         let rope = if let Some(range) = subrange {
             buffer.as_rope().slice(range)
@@ -519,6 +617,7 @@ impl SearchQuery {
                     }
                 }
             }
+            Self::Structural { .. } => unreachable!("structural queries are matched above"),
         }
 
         matches
@@ -536,6 +635,7 @@ impl SearchQuery {
         match self {
             Self::Text { whole_word, .. } => *whole_word,
             Self::Regex { whole_word, .. } => *whole_word,
+            Self::Structural { .. } => false,
         }
     }
 
@@ -543,6 +643,7 @@ impl SearchQuery {
         match self {
             Self::Text { case_sensitive, .. } => *case_sensitive,
             Self::Regex { case_sensitive, .. } => *case_sensitive,
+            Self::Structural { .. } => true,
         }
     }
 
@@ -553,6 +654,9 @@ impl SearchQuery {
             } => *include_ignored,
             Self::Regex {
                 include_ignored, ..
+            }
+            | Self::Structural {
+                include_ignored, ..
             } => *include_ignored,
         }
     }
@@ -561,6 +665,17 @@ impl SearchQuery {
         matches!(self, Self::Regex { .. })
     }
 
+    pub fn is_structural(&self) -> bool {
+        matches!(self, Self::Structural { .. })
+    }
+
+    pub fn structural_pattern(&self) -> Option<&StructuralPattern> {
+        match self {
+            Self::Structural { pattern, .. } => Some(pattern.as_ref()),
+            Self::Text { .. } | Self::Regex { .. } => None,
+        }
+    }
+
     pub fn files_to_include(&self) -> &PathMatcher {
         self.as_inner().files_to_include()
     }
@@ -604,7 +719,9 @@ impl SearchQuery {
     }
     pub fn as_inner(&self) -> &SearchInputs {
         match self {
-            Self::Regex { inner, .. } | Self::Text { inner, .. } => inner,
+            Self::Regex { inner, .. }
+            | Self::Text { inner, .. }
+            | Self::Structural { inner, .. } => inner,
         }
     }
 
@@ -617,7 +734,7 @@ impl SearchQuery {
             Self::Regex {
                 one_match_per_line, ..
             } => Some(*one_match_per_line),
-            Self::Text { .. } => None,
+            Self::Text { .. } | Self::Structural { .. } => None,
         }
     }
 }
@@ -0,0 +1,390 @@
+//! Module for sharing terminals with collaborators.
+//!
+//! Hosts share terminals with the guests of a shared project, either read-only
+//! or read-write. Guests see them in display-only terminals that are kept up to
+//! date with snapshots of the host's screen, and their input is only written to
+//! the host's terminal when it was shared read-write.
+use anyhow::{Context as _, Result};
+use collections::BTreeMap;
+use futures::{StreamExt as _, channel::mpsc};
+use gpui::{
+    App, AppContext as _, AsyncApp, Context, Entity, EventEmitter, Subscription, Task, WeakEntity,
+};
+use rpc::{AnyProtoClient, TypedEnvelope, proto};
+use std::time::Duration;
+use terminal::{
+    Terminal, TerminalBuilder,
+    terminal_settings::{AlternateScroll, CursorShape},
+};
+use util::{ResultExt as _, paths::PathStyle};
+
+/// How often the screen of a busy shared terminal is sent to guests.
+const SCREEN_UPDATE_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TerminalAccess {
+    /// Guests can only watch the terminal.
+    ReadOnly,
+    /// Guests can also type in the terminal.
+    ReadWrite,
+}
+
+/// A terminal the host is sharing.
+struct HostedTerminal {
+    terminal: WeakEntity<Terminal>,
+    access: TerminalAccess,
+    pending_update: Option<Task<()>>,
+    _subscriptions: [Subscription; 2],
+}
+
+/// A terminal a guest is watching.
+struct JoinedTerminal {
+    terminal: Entity<Terminal>,
+    access: TerminalAccess,
+    _forward_input: Task<()>,
+}
+
+#[derive(Clone)]
+struct RemoteSharedTerminalStore {
+    upstream_client: AnyProtoClient,
+    upstream_project_id: u64,
+    path_style: PathStyle,
+}
+
+#[derive(Clone)]
+enum SharedTerminalStoreMode {
+    Local,
+    Remote(RemoteSharedTerminalStore),
+}
+
+pub struct SharedTerminalStore {
+    hosted: BTreeMap<u64, HostedTerminal>,
+    joined: BTreeMap<u64, JoinedTerminal>,
+    next_terminal_id: u64,
+    downstream_client: Option<(AnyProtoClient, u64)>,
+    mode: SharedTerminalStoreMode,
+}
+
+pub enum SharedTerminalStoreEvent {
+    /// The host shared a terminal, which guests can show.
+    TerminalShared(Entity<Terminal>),
+}
+
+impl EventEmitter<SharedTerminalStoreEvent> for SharedTerminalStore {}
+
+impl SharedTerminalStore {
+    pub fn init(client: &AnyProtoClient) {
+        client.add_entity_message_handler(Self::handle_update_shared_terminal);
+        client.add_entity_message_handler(Self::handle_unshare_terminal);
+        client.add_entity_request_handler(Self::handle_shared_terminal_input);
+    }
+
+    pub fn local() -> Self {
+        Self {
+            hosted: BTreeMap::default(),
+            joined: BTreeMap::default(),
+            next_terminal_id: 0,
+            downstream_client: None,
+            mode: SharedTerminalStoreMode::Local,
+        }
+    }
+
+    pub(crate) fn remote(
+        upstream_project_id: u64,
+        upstream_client: AnyProtoClient,
+        path_style: PathStyle,
+    ) -> Self {
+        Self {
+            hosted: BTreeMap::default(),
+            joined: BTreeMap::default(),
+            next_terminal_id: 0,
+            downstream_client: None,
+            mode: SharedTerminalStoreMode::Remote(RemoteSharedTerminalStore {
+                upstream_client,
+                upstream_project_id,
+                path_style,
+            }),
+        }
+    }
+
+    pub fn shared(&mut self, project_id: u64, downstream_client: AnyProtoClient) {
+        self.downstream_client = Some((downstream_client, project_id));
+    }
+
+    pub(crate) fn unshared(&mut self, cx: &mut Context<Self>) {
+        self.downstream_client.take();
+        self.hosted.clear();
+        cx.notify();
+    }
+
+    pub(crate) fn disconnected_from_host(&mut self, cx: &mut Context<Self>) {
+        for (_, joined) in std::mem::take(&mut self.joined) {
+            joined
+                .terminal
+                .update(cx, |_, cx| cx.emit(terminal::Event::CloseTerminal));
+        }
+        cx.notify();
+    }
+
+    /// Shares a terminal with the project's guests, or changes what they can do with it.
+    pub fn share_terminal(
+        &mut self,
+        terminal: &Entity<Terminal>,
+        access: TerminalAccess,
+        cx: &mut Context<Self>,
+    ) -> Result<()> {
+        anyhow::ensure!(
+            matches!(self.mode, SharedTerminalStoreMode::Local),
+            "only the host can share terminals"
+        );
+        anyhow::ensure!(self.downstream_client.is_some(), "the project isn't shared");
+
+        let terminal_id = match self.terminal_id(terminal) {
+            Some(terminal_id) => terminal_id,
+            None => {
+                let terminal_id = self.next_terminal_id;
+                self.next_terminal_id += 1;
+                let subscriptions = [
+                    cx.subscribe(terminal, move |this, _, event, cx| match event {
+                        terminal::Event::Wakeup | terminal::Event::TitleChanged => {
+                            this.schedule_update(terminal_id, cx);
+                        }
+                        terminal::Event::CloseTerminal => this.unshare(terminal_id, cx),
+                        _ => {}
+                    }),
+                    cx.observe_release(terminal, move |this, _, cx| this.unshare(terminal_id, cx)),
+                ];
+                self.hosted.insert(
+                    terminal_id,
+                    HostedTerminal {
+                        terminal: terminal.downgrade(),
+                        access,
+                        pending_update: None,
+                        _subscriptions: subscriptions,
+                    },
+                );
+                terminal_id
+            }
+        };
+        if let Some(hosted) = self.hosted.get_mut(&terminal_id) {
+            hosted.access = access;
+        }
+        self.send_update(terminal_id, cx);
+        cx.notify();
+        Ok(())
+    }
+
+    /// Stops sharing a terminal, closing it for the project's guests.
+    pub fn unshare_terminal(&mut self, terminal: &Entity<Terminal>, cx: &mut Context<Self>) {
+        if let Some(terminal_id) = self.terminal_id(terminal) {
+            self.unshare(terminal_id, cx);
+        }
+    }
+
+    /// Returns what guests can do with a terminal the host is sharing.
+    pub fn terminal_access(&self, terminal: &Entity<Terminal>) -> Option<TerminalAccess> {
+        let terminal_id = self.terminal_id(terminal)?;
+        Some(self.hosted.get(&terminal_id)?.access)
+    }
+
+    /// Returns the terminals the host shared, for guests.
+    pub fn joined_terminals(&self) -> impl Iterator<Item = (&Entity<Terminal>, TerminalAccess)> {
+        self.joined
+            .values()
+            .map(|joined| (&joined.terminal, joined.access))
+    }
+
+    fn terminal_id(&self, terminal: &Entity<Terminal>) -> Option<u64> {
+        self.hosted
+            .iter()
+            .find(|(_, hosted)| hosted.terminal.entity_id() == terminal.entity_id())
+            .map(|(terminal_id, _)| *terminal_id)
+    }
+
+    fn unshare(&mut self, terminal_id: u64, cx: &mut Context<Self>) {
+        if self.hosted.remove(&terminal_id).is_none() {
+            return;
+        }
+        if let Some((client, project_id)) = &self.downstream_client {
+            client
+                .send(proto::UnshareTerminal {
+                    project_id: *project_id,
+                    terminal_id,
+                })
+                .log_err();
+        }
+        cx.notify();
+    }
+
+    /// Sends the terminal's screen to guests soon, so that busy terminals don't send every
+    /// change.
+    fn schedule_update(&mut self, terminal_id: u64, cx: &mut Context<Self>) {
+        let Some(hosted) = self.hosted.get_mut(&terminal_id) else {
+            return;
+        };
+        if hosted.pending_update.is_some() {
+            return;
+        }
+        hosted.pending_update = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(SCREEN_UPDATE_INTERVAL).await;
+            this.update(cx, |this, cx| {
+                if let Some(hosted) = this.hosted.get_mut(&terminal_id) {
+                    hosted.pending_update = None;
+                }
+                this.send_update(terminal_id, cx);
+            })
+            .ok();
+        }));
+    }
+
+    fn send_update(&self, terminal_id: u64, cx: &App) {
+        let Some((client, project_id)) = &self.downstream_client else {
+            return;
+        };
+        let Some(hosted) = self.hosted.get(&terminal_id) else {
+            return;
+        };
+        let Some(terminal) = hosted.terminal.upgrade() else {
+            return;
+        };
+        let terminal = terminal.read(cx);
+        client
+            .send(proto::UpdateSharedTerminal {
+                project_id: *project_id,
+                terminal_id,
+                title: terminal.title(true),
+                writable: hosted.access == TerminalAccess::ReadWrite,
+                screen: terminal.screen_snapshot(),
+            })
+            .log_err();
+    }
+
+    pub(crate) fn broadcast(&self, cx: &App) {
+        for terminal_id in self.hosted.keys() {
+            self.send_update(*terminal_id, cx);
+        }
+    }
+
+    async fn handle_update_shared_terminal(
+        this: Entity<Self>,
+        message: TypedEnvelope<proto::UpdateSharedTerminal>,
+        mut cx: AsyncApp,
+    ) -> Result<()> {
+        this.update(&mut cx, |this, cx| {
+            let SharedTerminalStoreMode::Remote(remote) = &this.mode else {
+                return Ok(());
+            };
+            let path_style = remote.path_style;
+            let terminal_id = message.payload.terminal_id;
+            let access = if message.payload.writable {
+                TerminalAccess::ReadWrite
+            } else {
+                TerminalAccess::ReadOnly
+            };
+            let terminal = match this.joined.get_mut(&terminal_id) {
+                Some(joined) => {
+                    joined.access = access;
+                    joined.terminal.clone()
+                }
+                None => {
+                    let builder = TerminalBuilder::new_display_only(
+                        CursorShape::default(),
+                        AlternateScroll::On,
+                        None,
+                        0,
+                        cx.background_executor(),
+                        path_style,
+                    )?;
+                    let terminal = cx.new(|cx| builder.subscribe(cx));
+                    let (input_tx, input_rx) = mpsc::unbounded();
+                    terminal.update(cx, |terminal, _| terminal.forward_input(input_tx));
+                    this.joined.insert(
+                        terminal_id,
+                        JoinedTerminal {
+                            terminal: terminal.clone(),
+                            access,
+                            _forward_input: Self::forward_input(terminal_id, input_rx, cx),
+                        },
+                    );
+                    cx.emit(SharedTerminalStoreEvent::TerminalShared(terminal.clone()));
+                    terminal
+                }
+            };
+            terminal.update(cx, |terminal, cx| {
+                terminal.set_title_override(Some(message.payload.title), cx);
+                terminal.apply_screen_snapshot(&message.payload.screen, cx);
+            });
+            cx.notify();
+            Ok(())
+        })
+    }
+
+    /// Sends what the guest types in a shared terminal to the host, in order.
+    fn forward_input(
+        terminal_id: u64,
+        mut input_rx: mpsc::UnboundedReceiver<Vec<u8>>,
+        cx: &mut Context<Self>,
+    ) -> Task<()> {
+        cx.spawn(async move |this, cx| {
+            while let Some(input) = input_rx.next().await {
+                let Ok(request) = this.update(cx, |this, _| {
+                    let SharedTerminalStoreMode::Remote(remote) = &this.mode else {
+                        return None;
+                    };
+                    let joined = this.joined.get(&terminal_id)?;
+                    // Input to read-only terminals is dropped, like a display-only terminal's.
+                    (joined.access == TerminalAccess::ReadWrite).then(|| {
+                        remote.upstream_client.request(proto::SharedTerminalInput {
+                            project_id: remote.upstream_project_id,
+                            terminal_id,
+                            input,
+                        })
+                    })
+                }) else {
+                    break;
+                };
+                if let Some(request) = request {
+                    request.await.log_err();
+                }
+            }
+        })
+    }
+
+    async fn handle_unshare_terminal(
+        this: Entity<Self>,
+        message: TypedEnvelope<proto::UnshareTerminal>,
+        mut cx: AsyncApp,
+    ) -> Result<()> {
+        this.update(&mut cx, |this, cx| {
+            if let Some(joined) = this.joined.remove(&message.payload.terminal_id) {
+                joined
+                    .terminal
+                    .update(cx, |_, cx| cx.emit(terminal::Event::CloseTerminal));
+                cx.notify();
+            }
+        });
+        Ok(())
+    }
+
+    async fn handle_shared_terminal_input(
+        this: Entity<Self>,
+        message: TypedEnvelope<proto::SharedTerminalInput>,
+        mut cx: AsyncApp,
+    ) -> Result<proto::Ack> {
+        let terminal = this.update(&mut cx, |this, _| {
+            let hosted = this
+                .hosted
+                .get(&message.payload.terminal_id)
+                .context("terminal isn't shared")?;
+            anyhow::ensure!(
+                hosted.access == TerminalAccess::ReadWrite,
+                "terminal is shared read-only"
+            );
+            hosted.terminal.upgrade().context("terminal was closed")
+        })?;
+        terminal.update(&mut cx, |terminal, _| {
+            terminal.input(message.payload.input);
+        });
+        Ok(proto::Ack {})
+    }
+}
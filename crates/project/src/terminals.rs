@@ -250,6 +250,8 @@ impl Project {
                         settings.max_scroll_history_lines,
                         settings.path_hyperlink_regexes,
                         settings.path_hyperlink_timeout_ms,
+                        settings.shell_integration,
+                        settings.inline_images,
                         is_via_remote,
                         cx.entity_id().as_u64(),
                         Some(completion_tx),
@@ -415,6 +417,8 @@ impl Project {
                         settings.max_scroll_history_lines,
                         settings.path_hyperlink_regexes,
                         settings.path_hyperlink_timeout_ms,
+                        settings.shell_integration,
+                        settings.inline_images,
                         is_via_remote,
                         cx.entity_id().as_u64(),
                         None,
@@ -38,6 +38,18 @@
 //!
 //! To ease trusting multiple directory worktrees at once, it's possible to trust a parent directory of a certain directory worktree opened in Zed.
 //! Trusting a directory means trusting all its subdirectories as well, including all current and potential directory worktrees.
+//!
+//!
+//! Capability grants.
+//!
+//! Instead of trusting a worktree as a whole, it's possible to grant it separate [`TrustCapability`] entries: e.g. allow language servers, but not project tasks.
+//! Each grant is persisted per host and worktree path, together with the hash of the configuration files the capability relies on (see [`TrustCapability::config_file`]).
+//! Once any of these files change, the grant stops applying and the worktree gets restricted again, so the user is asked to review it.
+//!
+//! Trusting a worktree as a whole is bound to its config files the same way: their hashes are persisted when the worktree gets trusted (see [`TrustedConfigs`]).
+//! Once any of these files change, get added or removed, the worktree is not trusted as a whole anymore, until the user reviews it again.
+//!
+//! Grants only loosen restrictions: a trusted worktree may use every capability.
 
 use client::ProjectId;
 use collections::{HashMap, HashSet};
@@ -46,12 +58,15 @@ use gpui::{
 };
 use remote::RemoteConnectionOptions;
 use rpc::{AnyProtoClient, proto};
-use settings::{Settings as _, WorktreeId};
+use serde::{Deserialize, de::IgnoredAny};
+use settings::{Settings as _, WorktreeId, parse_json_with_comments};
+use sha2::{Digest, Sha256};
 use std::{
+    collections::{BTreeMap, BTreeSet},
     path::{Path, PathBuf},
     sync::Arc,
 };
-use util::debug_panic;
+use util::{debug_panic, rel_path::RelPath};
 
 use crate::{project_settings::ProjectSettings, worktree_store::WorktreeStore};
 
@@ -127,6 +142,12 @@ pub struct TrustedWorktreesStore {
     trusted_paths: TrustedPaths,
     restricted: HashMap<WeakEntity<WorktreeStore>, HashSet<WorktreeId>>,
     worktree_trust_serialization: Task<()>,
+    grants: DbTrustGrants,
+    configs: HashMap<WeakEntity<WorktreeStore>, HashMap<WorktreeId, WorktreeConfigs>>,
+    /// Worktrees which lost some of their grants due to a config change, and were not reviewed since.
+    changed_configs: HashMap<WeakEntity<WorktreeStore>, HashSet<WorktreeId>>,
+    trusted_configs: DbTrustedConfigs,
+    grants_serialization: Task<()>,
 }
 
 #[derive(Debug, Default)]
@@ -211,6 +232,136 @@ impl PathTrust {
     }
 }
 
+/// A part of a worktree that can be allowed on its own, without trusting the whole worktree.
+/// See module-level documentation on capability grants.
+#[derive(Debug, PartialEq, Eq, Clone, Hash, PartialOrd, Ord)]
+pub enum TrustCapability {
+    /// Starting language servers and linters for the worktree.
+    LanguageServers,
+    /// Applying the worktree's `.zed/settings.json` files.
+    ProjectSettings,
+    /// Loading tasks from the worktree's `.zed/tasks.json` and `.vscode/tasks.json` files.
+    ProjectTasks,
+    /// Loading key bindings from the worktree's `.zed/keymap.json` file.
+    ProjectKeymap,
+    /// Starting a particular MCP server, defined in the worktree's settings.
+    ContextServer(SharedString),
+}
+
+impl TrustCapability {
+    /// The kind of config files a grant for this capability is bound to.
+    pub fn config_file(&self) -> TrustedConfigFile {
+        match self {
+            Self::LanguageServers | Self::ProjectSettings | Self::ContextServer(_) => {
+                TrustedConfigFile::Settings
+            }
+            Self::ProjectTasks => TrustedConfigFile::Tasks,
+            Self::ProjectKeymap => TrustedConfigFile::Keymap,
+        }
+    }
+
+    pub fn to_db_key(&self) -> String {
+        match self {
+            Self::LanguageServers => "language_servers".to_string(),
+            Self::ProjectSettings => "project_settings".to_string(),
+            Self::ProjectTasks => "project_tasks".to_string(),
+            Self::ProjectKeymap => "project_keymap".to_string(),
+            Self::ContextServer(name) => format!("context_server:{name}"),
+        }
+    }
+
+    pub fn from_db_key(key: &str) -> Option<Self> {
+        Some(match key {
+            "language_servers" => Self::LanguageServers,
+            "project_settings" => Self::ProjectSettings,
+            "project_tasks" => Self::ProjectTasks,
+            "project_keymap" => Self::ProjectKeymap,
+            _ => Self::ContextServer(SharedString::new(key.strip_prefix("context_server:")?)),
+        })
+    }
+}
+
+/// A kind of worktree config files, which contents the capability grants are bound to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash, PartialOrd, Ord)]
+pub enum TrustedConfigFile {
+    /// `.zed/settings.json` files.
+    Settings,
+    /// `.zed/tasks.json` and `.vscode/tasks.json` files.
+    Tasks,
+    /// The `.zed/keymap.json` file.
+    Keymap,
+}
+
+impl TrustedConfigFile {
+    pub fn to_db_key(self) -> &'static str {
+        match self {
+            Self::Settings => "settings",
+            Self::Tasks => "tasks",
+            Self::Keymap => "keymap",
+        }
+    }
+
+    pub fn from_db_key(key: &str) -> Option<Self> {
+        Some(match key {
+            "settings" => Self::Settings,
+            "tasks" => Self::Tasks,
+            "keymap" => Self::Keymap,
+            _ => return None,
+        })
+    }
+}
+
+/// The config files of a worktree trusted as a whole, as of when it was trusted.
+/// See module-level documentation on capability grants.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct TrustedConfigs {
+    /// Hashes of the worktree's config files, by their kind and directory.
+    pub hashes: BTreeMap<(TrustedConfigFile, Arc<RelPath>), String>,
+    /// Whether any config file changed since the worktree was trusted, so it is not trusted as a whole anymore.
+    pub changed: bool,
+}
+
+impl TrustedConfigs {
+    /// Serializes the config file hashes, one `kind\thash\tdirectory` line per file.
+    pub fn hashes_to_db_value(&self) -> String {
+        self.hashes
+            .iter()
+            .map(|((config_file, directory), hash)| {
+                format!(
+                    "{}\t{hash}\t{}\n",
+                    config_file.to_db_key(),
+                    directory.as_unix_str()
+                )
+            })
+            .collect()
+    }
+
+    pub fn from_db_value(hashes: &str, changed: bool) -> Option<Self> {
+        let hashes = hashes
+            .lines()
+            .map(|line| {
+                let mut parts = line.splitn(3, '\t');
+                let config_file = TrustedConfigFile::from_db_key(parts.next()?)?;
+                let hash = parts.next()?.to_string();
+                let directory = RelPath::unix(parts.next()?).ok()?.into_arc();
+                Some(((config_file, directory), hash))
+            })
+            .collect::<Option<_>>()?;
+        Some(Self { hashes, changed })
+    }
+}
+
+#[derive(Debug, Default)]
+struct WorktreeConfigs {
+    /// Hashes of the worktree's config files, by their kind and directory.
+    hashes: BTreeMap<(TrustedConfigFile, Arc<RelPath>), String>,
+    /// MCP servers defined by the worktree's settings files, by their directory.
+    context_servers: BTreeMap<Arc<RelPath>, BTreeSet<SharedString>>,
+    /// Whether config files, loaded for the first time, are added to the worktree's [`TrustedConfigs`] instead of revoking its trust.
+    /// Happens for worktrees that were trusted before their config files got tracked.
+    record_new_trusted_configs: bool,
+}
+
 /// A change of trust on a certain host.
 #[derive(Debug)]
 pub enum TrustedWorktreesEvent {
@@ -222,6 +373,12 @@ impl EventEmitter<TrustedWorktreesEvent> for TrustedWorktreesStore {}
 
 type TrustedPaths = HashMap<WeakEntity<WorktreeStore>, HashSet<PathTrust>>;
 pub type DbTrustedPaths = HashMap<Option<RemoteHostLocation>, HashSet<PathBuf>>;
+/// Capabilities granted to worktrees on each host, by the worktree path,
+/// with the hash of the config each capability was granted for.
+pub type DbTrustGrants =
+    HashMap<Option<RemoteHostLocation>, HashMap<PathBuf, HashMap<TrustCapability, String>>>;
+/// Config files of the worktrees trusted as a whole on each host, by the worktree path.
+pub type DbTrustedConfigs = HashMap<Option<RemoteHostLocation>, HashMap<PathBuf, TrustedConfigs>>;
 
 impl TrustedWorktreesStore {
     fn new(db_trusted_paths: DbTrustedPaths) -> Self {
@@ -231,6 +388,11 @@ impl TrustedWorktreesStore {
             worktree_stores: HashMap::default(),
             restricted: HashMap::default(),
             worktree_trust_serialization: Task::ready(()),
+            grants: HashMap::default(),
+            configs: HashMap::default(),
+            changed_configs: HashMap::default(),
+            trusted_configs: HashMap::default(),
+            grants_serialization: Task::ready(()),
         }
     }
 
@@ -273,6 +435,26 @@ impl TrustedWorktreesStore {
         cx: &mut Context<Self>,
     ) {
         let weak_worktree_store = worktree_store.downgrade();
+        // Trusting is a review: bind the trust of the requested worktrees to their current config files.
+        let reviewed_worktrees = worktree_store
+            .read(cx)
+            .worktrees()
+            .filter_map(|worktree| {
+                let worktree = worktree.read(cx);
+                let abs_path = worktree.abs_path();
+                trusted_paths
+                    .iter()
+                    .any(|trusted_path| match trusted_path {
+                        PathTrust::Worktree(worktree_id) => *worktree_id == worktree.id(),
+                        PathTrust::AbsPath(trusted_path) => abs_path.starts_with(trusted_path),
+                    })
+                    .then_some(worktree.id())
+            })
+            .collect::<HashSet<_>>();
+        for worktree_id in &reviewed_worktrees {
+            self.record_trusted_configs(worktree_store, *worktree_id, cx);
+        }
+
         let mut new_trusted_single_file_worktrees = HashSet::default();
         let mut new_trusted_other_worktrees = HashSet::default();
         let mut new_trusted_abs_paths = HashSet::default();
@@ -284,6 +466,9 @@ impl TrustedWorktreesStore {
         ) {
             match trusted_path {
                 PathTrust::Worktree(worktree_id) => {
+                    if self.trusted_configs_changed(worktree_store, *worktree_id, cx) {
+                        continue;
+                    }
                     if let Some(restricted_worktrees) =
                         self.restricted.get_mut(&weak_worktree_store)
                     {
@@ -347,6 +532,9 @@ impl TrustedWorktreesStore {
                     else {
                         return false;
                     };
+                    if self.trusted_configs_changed(worktree_store, *restricted_worktree, cx) {
+                        return true;
+                    }
                     let is_file = worktree.read(cx).is_single_file();
 
                     // When trusting an abs path on the host, we transitively trust all single file worktrees on this host too.
@@ -404,6 +592,18 @@ impl TrustedWorktreesStore {
                 }
             }
         }
+        for trusted_path in &trusted_paths {
+            if let PathTrust::Worktree(worktree_id) = trusted_path
+                && !reviewed_worktrees.contains(worktree_id)
+            {
+                self.record_trusted_configs(worktree_store, *worktree_id, cx);
+            }
+        }
+        if let Some(changed_configs) = self.changed_configs.get_mut(&weak_worktree_store) {
+            changed_configs
+                .retain(|worktree_id| !trusted_paths.contains(&PathTrust::Worktree(*worktree_id)));
+        }
+        cx.notify();
         cx.emit(TrustedWorktreesEvent::Trusted(
             weak_worktree_store,
             trusted_paths,
@@ -443,6 +643,9 @@ impl TrustedWorktreesStore {
     pub fn clear_trusted_paths(&mut self) {
         self.trusted_paths.clear();
         self.db_trusted_paths.clear();
+        self.grants.clear();
+        self.changed_configs.clear();
+        self.trusted_configs.clear();
     }
 
     /// Checks whether a certain worktree is trusted (or on a larger trust level).
@@ -470,7 +673,6 @@ impl TrustedWorktreesStore {
             return true;
         }
 
-        let is_file = worktree.read(cx).is_single_file();
         if self
             .restricted
             .get(&weak_worktree_store)
@@ -479,38 +681,12 @@ impl TrustedWorktreesStore {
             return false;
         }
 
-        if self
-            .trusted_paths
-            .get(&weak_worktree_store)
-            .is_some_and(|trusted_paths| trusted_paths.contains(&PathTrust::Worktree(worktree_id)))
+        if self.is_trusted_path(worktree_store, worktree_id, cx)
+            && !self.trusted_configs_changed(worktree_store, worktree_id, cx)
         {
             return true;
         }
 
-        // * Single files are auto-approved when something else (not a single file) was approved on this host already.
-        // * If parent path is trusted already, this worktree is stusted also.
-        //
-        // See module documentation for details on trust level.
-        if let Some(trusted_paths) = self.trusted_paths.get(&weak_worktree_store) {
-            let auto_trusted = worktree_store.read_with(cx, |worktree_store, cx| {
-                trusted_paths.iter().any(|trusted_path| match trusted_path {
-                    PathTrust::Worktree(worktree_id) => worktree_store
-                        .worktree_for_id(*worktree_id, cx)
-                        .is_some_and(|worktree| {
-                            let worktree = worktree.read(cx);
-                            worktree_path.starts_with(&worktree.abs_path())
-                                || (is_file && !worktree.is_single_file())
-                        }),
-                    PathTrust::AbsPath(trusted_path) => {
-                        is_file || worktree_path.starts_with(trusted_path)
-                    }
-                })
-            });
-            if auto_trusted {
-                return true;
-            }
-        }
-
         self.restricted
             .entry(weak_worktree_store.clone())
             .or_default()
@@ -542,6 +718,433 @@ impl TrustedWorktreesStore {
         false
     }
 
+    /// Checks whether a certain worktree is covered by the trusted paths, directly or via the trust hierarchy.
+    fn is_trusted_path(
+        &self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        cx: &App,
+    ) -> bool {
+        let Some(trusted_paths) = self.trusted_paths.get(&worktree_store.downgrade()) else {
+            return false;
+        };
+        if trusted_paths.contains(&PathTrust::Worktree(worktree_id)) {
+            return true;
+        }
+        let Some(worktree) = worktree_store.read(cx).worktree_for_id(worktree_id, cx) else {
+            return false;
+        };
+        let worktree_path = worktree.read(cx).abs_path();
+        let is_file = worktree.read(cx).is_single_file();
+
+        // * Single files are auto-approved when something else (not a single file) was approved on this host already.
+        // * If parent path is trusted already, this worktree is stusted also.
+        //
+        // See module documentation for details on trust level.
+        trusted_paths.iter().any(|trusted_path| match trusted_path {
+            PathTrust::Worktree(worktree_id) => worktree_store
+                .read(cx)
+                .worktree_for_id(*worktree_id, cx)
+                .is_some_and(|worktree| {
+                    let worktree = worktree.read(cx);
+                    worktree_path.starts_with(&worktree.abs_path())
+                        || (is_file && !worktree.is_single_file())
+                }),
+            PathTrust::AbsPath(trusted_path) => is_file || worktree_path.starts_with(trusted_path),
+        })
+    }
+
+    /// Checks whether a certain worktree is trusted as a whole, without restricting anything,
+    /// see [`TrustedWorktreesStore::can_trust`].
+    fn is_trusted_as_whole(
+        &self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        cx: &App,
+    ) -> bool {
+        if ProjectSettings::get_global(cx).session.trust_all_worktrees {
+            return false;
+        }
+        if !worktree_store
+            .read(cx)
+            .worktree_for_id(worktree_id, cx)
+            .is_some_and(|worktree| worktree.read(cx).is_visible())
+        {
+            return false;
+        }
+        if self
+            .restricted
+            .get(&worktree_store.downgrade())
+            .is_some_and(|restricted_worktrees| restricted_worktrees.contains(&worktree_id))
+        {
+            return false;
+        }
+        self.is_trusted_path(worktree_store, worktree_id, cx)
+            && !self.trusted_configs_changed(worktree_store, worktree_id, cx)
+    }
+
+    /// Whether any config file of a certain worktree changed since the worktree was trusted as a whole.
+    fn trusted_configs_changed(
+        &self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        cx: &App,
+    ) -> bool {
+        self.worktree_location(worktree_store, worktree_id, cx)
+            .and_then(|(host, abs_path)| self.trusted_configs.get(&host)?.get(&abs_path))
+            .is_some_and(|trusted_configs| trusted_configs.changed)
+    }
+
+    /// Binds the trust of a certain worktree as a whole to the current contents of its config files.
+    fn record_trusted_configs(
+        &mut self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        cx: &App,
+    ) {
+        let Some((host, abs_path)) = self.worktree_location(worktree_store, worktree_id, cx) else {
+            return;
+        };
+        let hashes = self
+            .configs
+            .get(&worktree_store.downgrade())
+            .and_then(|configs| configs.get(&worktree_id))
+            .map(|worktree_configs| worktree_configs.hashes.clone())
+            .unwrap_or_default();
+        self.trusted_configs.entry(host).or_default().insert(
+            abs_path,
+            TrustedConfigs {
+                hashes,
+                changed: false,
+            },
+        );
+    }
+
+    /// Checks the config files of a worktree trusted as a whole that were just updated against the ones it was trusted with.
+    /// Returns whether any of them changed, so the worktree should not be trusted as a whole anymore.
+    fn check_trusted_configs(
+        &mut self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        updated_configs: Vec<((TrustedConfigFile, Arc<RelPath>), Option<String>)>,
+        cx: &App,
+    ) -> bool {
+        let Some((host, abs_path)) = self.worktree_location(worktree_store, worktree_id, cx) else {
+            return false;
+        };
+        let Some(worktree_configs) = self
+            .configs
+            .get_mut(&worktree_store.downgrade())
+            .and_then(|configs| configs.get_mut(&worktree_id))
+        else {
+            return false;
+        };
+        let host_trusted_configs = self.trusted_configs.entry(host).or_default();
+        let Some(trusted_configs) = host_trusted_configs.get_mut(&abs_path) else {
+            worktree_configs.record_new_trusted_configs = true;
+            host_trusted_configs.insert(
+                abs_path,
+                TrustedConfigs {
+                    hashes: worktree_configs.hashes.clone(),
+                    changed: false,
+                },
+            );
+            return false;
+        };
+
+        for (key, new_hash) in updated_configs {
+            let old_hash = trusted_configs.hashes.get(&key);
+            if old_hash == new_hash.as_ref() {
+                continue;
+            }
+            match new_hash {
+                Some(new_hash)
+                    if old_hash.is_none() && worktree_configs.record_new_trusted_configs =>
+                {
+                    trusted_configs.hashes.insert(key, new_hash);
+                }
+                _ => trusted_configs.changed = true,
+            }
+        }
+        trusted_configs.changed
+    }
+
+    /// Checks whether a certain worktree may use a capability: either it was granted one for its current config,
+    /// or the worktree is trusted as a whole, see [`TrustedWorktreesStore::can_trust`].
+    pub fn can_use(
+        &mut self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        capability: &TrustCapability,
+        cx: &mut Context<Self>,
+    ) -> bool {
+        self.is_granted(worktree_store, worktree_id, capability, cx)
+            || self.can_trust(worktree_store, worktree_id, cx)
+    }
+
+    /// Checks whether a certain worktree was granted a capability for its current config.
+    /// Unlike [`TrustedWorktreesStore::can_use`], does not consider whole worktree trust and never restricts anything.
+    pub fn is_granted(
+        &self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        capability: &TrustCapability,
+        cx: &App,
+    ) -> bool {
+        self.worktree_grants(worktree_store, worktree_id, cx)
+            .and_then(|grants| grants.get(capability))
+            .is_some_and(|granted_hash| {
+                *granted_hash
+                    == self.config_hash(worktree_store, worktree_id, capability.config_file())
+            })
+    }
+
+    /// Lists the capabilities a certain worktree was granted for its current config.
+    pub fn granted_capabilities(
+        &self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        cx: &App,
+    ) -> BTreeSet<TrustCapability> {
+        self.worktree_grants(worktree_store, worktree_id, cx)
+            .into_iter()
+            .flatten()
+            .filter(|(capability, granted_hash)| {
+                **granted_hash
+                    == self.config_hash(worktree_store, worktree_id, capability.config_file())
+            })
+            .map(|(capability, _)| capability.clone())
+            .collect()
+    }
+
+    /// Lists the config files of a certain worktree that changed since some capability was granted for them.
+    pub fn changed_config_files(
+        &self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        cx: &App,
+    ) -> BTreeSet<TrustedConfigFile> {
+        self.worktree_grants(worktree_store, worktree_id, cx)
+            .into_iter()
+            .flatten()
+            .map(|(capability, granted_hash)| (capability.config_file(), granted_hash))
+            .filter(|(config_file, granted_hash)| {
+                **granted_hash != self.config_hash(worktree_store, worktree_id, *config_file)
+            })
+            .map(|(config_file, _)| config_file)
+            .collect()
+    }
+
+    /// Lists MCP servers, defined by a certain worktree's settings.
+    pub fn context_servers(
+        &self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+    ) -> BTreeSet<SharedString> {
+        self.configs
+            .get(&worktree_store.downgrade())
+            .and_then(|configs| configs.get(&worktree_id))
+            .into_iter()
+            .flat_map(|configs| configs.context_servers.values().flatten().cloned())
+            .collect()
+    }
+
+    /// Replaces the capabilities granted to a certain worktree, binding each of them to the current contents of the corresponding config files.
+    pub fn set_grants(
+        &mut self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        capabilities: impl IntoIterator<Item = TrustCapability>,
+        cx: &mut Context<Self>,
+    ) {
+        let weak_worktree_store = worktree_store.downgrade();
+        let Some(host) = self
+            .worktree_stores
+            .get(&weak_worktree_store)
+            .map(|store_data| store_data.host.clone())
+        else {
+            return;
+        };
+        let Some(worktree) = worktree_store.read(cx).worktree_for_id(worktree_id, cx) else {
+            return;
+        };
+        let abs_path = worktree.read(cx).abs_path().to_path_buf();
+
+        let worktree_grants = capabilities
+            .into_iter()
+            .map(|capability| {
+                let config_hash =
+                    self.config_hash(worktree_store, worktree_id, capability.config_file());
+                (capability, config_hash)
+            })
+            .collect::<HashMap<_, _>>();
+        let host_grants = self.grants.entry(host).or_default();
+        if worktree_grants.is_empty() {
+            host_grants.remove(&abs_path);
+        } else {
+            host_grants.insert(abs_path, worktree_grants);
+        }
+        if let Some(changed_configs) = self.changed_configs.get_mut(&weak_worktree_store) {
+            changed_configs.remove(&worktree_id);
+        }
+        cx.notify();
+    }
+
+    /// Records the contents of the config files of a certain worktree, which the capability grants and the trust as a whole are bound to.
+    /// When that revokes the trust or any grant the worktree had, the worktree is restricted and [`TrustedWorktreesEvent::Restricted`] is emitted.
+    pub fn update_configs<'a>(
+        &mut self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        configs: impl IntoIterator<Item = (TrustedConfigFile, Arc<RelPath>, Option<&'a str>)>,
+        cx: &mut Context<Self>,
+    ) {
+        let granted_before = self.granted_capabilities(worktree_store, worktree_id, cx);
+        let trusted_as_whole = self.is_trusted_as_whole(worktree_store, worktree_id, cx);
+
+        let weak_worktree_store = worktree_store.downgrade();
+        let worktree_configs = self
+            .configs
+            .entry(weak_worktree_store.clone())
+            .or_default()
+            .entry(worktree_id)
+            .or_default();
+        let mut updated_configs = Vec::new();
+        for (config_file, directory, content) in configs {
+            let new_hash = content.map(|content| format!("{:x}", Sha256::digest(content)));
+            let key = (config_file, directory.clone());
+            let old_hash = match new_hash.clone() {
+                Some(new_hash) => worktree_configs.hashes.insert(key.clone(), new_hash),
+                None => worktree_configs.hashes.remove(&key),
+            };
+            if old_hash != new_hash {
+                updated_configs.push((key, new_hash));
+            }
+
+            if config_file == TrustedConfigFile::Settings {
+                let context_servers = content.map(context_server_names).unwrap_or_default();
+                if context_servers.is_empty() {
+                    worktree_configs.context_servers.remove(&directory);
+                } else {
+                    worktree_configs
+                        .context_servers
+                        .insert(directory, context_servers);
+                }
+            }
+        }
+        if updated_configs.is_empty() {
+            return;
+        }
+
+        let trust_revoked = trusted_as_whole
+            && self.check_trusted_configs(worktree_store, worktree_id, updated_configs, cx);
+        if trust_revoked {
+            log::info!("Worktree {worktree_id:?} config changed, revoking its trust");
+            if let Some(trusted_paths) = self.trusted_paths.get_mut(&weak_worktree_store) {
+                trusted_paths.remove(&PathTrust::Worktree(worktree_id));
+            }
+        }
+
+        let granted_after = self.granted_capabilities(worktree_store, worktree_id, cx);
+        let grants_revoked = !granted_before.is_subset(&granted_after);
+        if grants_revoked {
+            log::info!(
+                "Worktree {worktree_id:?} config changed, revoking its grants: {:?}",
+                granted_before
+                    .difference(&granted_after)
+                    .collect::<Vec<_>>()
+            );
+        }
+        // Grants do not matter for the worktrees trusted as a whole.
+        if (trust_revoked || grants_revoked) && !self.can_trust(worktree_store, worktree_id, cx) {
+            self.changed_configs
+                .entry(weak_worktree_store.clone())
+                .or_default()
+                .insert(worktree_id);
+            self.restrict(
+                weak_worktree_store,
+                HashSet::from_iter([PathTrust::Worktree(worktree_id)]),
+                cx,
+            );
+        }
+        cx.notify();
+    }
+
+    /// Whether any worktree of the worktree store had lost its trust or grants due to a config change since the last call.
+    pub fn take_changed_configs(&mut self, worktree_store: &Entity<WorktreeStore>) -> bool {
+        self.changed_configs
+            .remove(&worktree_store.downgrade())
+            .is_some_and(|changed_configs| !changed_configs.is_empty())
+    }
+
+    /// Restores the capability grants and the config files of the worktrees trusted as a whole, persisted in the previous sessions.
+    pub fn restore_grants(&mut self, grants: DbTrustGrants, trusted_configs: DbTrustedConfigs) {
+        self.grants = grants;
+        self.trusted_configs = trusted_configs;
+    }
+
+    pub fn schedule_grants_serialization<S>(&mut self, cx: &mut Context<Self>, serialize: S)
+    where
+        S: FnOnce(DbTrustGrants, DbTrustedConfigs, &App) -> Task<()> + 'static,
+    {
+        self.grants_serialization =
+            serialize(self.grants.clone(), self.trusted_configs.clone(), cx);
+    }
+
+    fn worktree_grants(
+        &self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        cx: &App,
+    ) -> Option<&HashMap<TrustCapability, String>> {
+        let (host, abs_path) = self.worktree_location(worktree_store, worktree_id, cx)?;
+        self.grants.get(&host)?.get(&abs_path)
+    }
+
+    /// The host and the path of a certain worktree, which its grants and trusted configs are persisted by.
+    fn worktree_location(
+        &self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        cx: &App,
+    ) -> Option<(Option<RemoteHostLocation>, PathBuf)> {
+        let host = self
+            .worktree_stores
+            .get(&worktree_store.downgrade())?
+            .host
+            .clone();
+        let worktree = worktree_store.read(cx).worktree_for_id(worktree_id, cx)?;
+        let abs_path = worktree.read(cx).abs_path().to_path_buf();
+        Some((host, abs_path))
+    }
+
+    /// A hash of all config files of a certain kind in the worktree, as last recorded with [`TrustedWorktreesStore::update_configs`].
+    fn config_hash(
+        &self,
+        worktree_store: &Entity<WorktreeStore>,
+        worktree_id: WorktreeId,
+        config_file: TrustedConfigFile,
+    ) -> String {
+        let mut hasher = Sha256::new();
+        if let Some(worktree_configs) = self
+            .configs
+            .get(&worktree_store.downgrade())
+            .and_then(|configs| configs.get(&worktree_id))
+        {
+            for ((_, directory), file_hash) in worktree_configs
+                .hashes
+                .iter()
+                .filter(|((file, _), _)| *file == config_file)
+            {
+                hasher.update(directory.as_unix_str());
+                hasher.update([0]);
+                hasher.update(file_hash);
+                hasher.update([0]);
+            }
+        }
+        format!("{:x}", hasher.finalize())
+    }
+
     /// Lists all explicitly restricted worktrees (via [`TrustedWorktreesStore::can_trust`] method calls) for a particular worktree store on a particular host.
     pub fn restricted_worktrees(
         &self,
@@ -684,6 +1287,24 @@ impl TrustedWorktreesStore {
     }
 }
 
+fn context_server_names(settings_content: &str) -> BTreeSet<SharedString> {
+    #[derive(Deserialize)]
+    struct ContextServers {
+        #[serde(default)]
+        context_servers: HashMap<String, IgnoredAny>,
+    }
+
+    parse_json_with_comments::<ContextServers>(settings_content)
+        .map(|settings| {
+            settings
+                .context_servers
+                .into_keys()
+                .map(SharedString::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn find_worktree_in_store(
     worktree_store: &WorktreeStore,
     abs_path: &Path,
@@ -6854,6 +6854,82 @@ async fn test_search(cx: &mut gpui::TestAppContext) {
     );
 }
 
+#[gpui::test]
+async fn test_structural_search(cx: &mut gpui::TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        path!("/dir"),
+        json!({
+            "one.rs": "const ONE: usize = 1;",
+            "two.rs": "const TWO: usize = one::ONE + one::ONE;",
+            "three.rs": "const THREE: usize = one::ONE + two::TWO;",
+            "notes.txt": "one::ONE + one::ONE",
+        }),
+    )
+    .await;
+    let project = Project::test(fs.clone(), [path!("/dir").as_ref()], cx).await;
+    let language_registry = project.read_with(cx, |project, _| project.languages().clone());
+    language_registry.add(rust_lang());
+
+    assert_eq!(
+        search(
+            &project,
+            SearchQuery::structural(
+                "$A + $A",
+                false,
+                Default::default(),
+                Default::default(),
+                false,
+                None,
+            )
+            .unwrap(),
+            cx
+        )
+        .await
+        .unwrap(),
+        HashMap::from_iter([(path!("dir/two.rs").to_string(), vec![19..38])])
+    );
+
+    let query = SearchQuery::structural(
+        "one::ONE + $B",
+        false,
+        Default::default(),
+        Default::default(),
+        false,
+        None,
+    )
+    .unwrap()
+    .with_replacement("$B + one::ONE".to_string());
+    assert_eq!(
+        search(&project, query.clone(), cx).await.unwrap(),
+        HashMap::from_iter([
+            (path!("dir/two.rs").to_string(), vec![19..38]),
+            (path!("dir/three.rs").to_string(), vec![21..40]),
+        ])
+    );
+
+    let buffer = project
+        .update(cx, |project, cx| {
+            project.open_local_buffer(path!("/dir/three.rs"), cx)
+        })
+        .await
+        .unwrap();
+    buffer.read_with(cx, |buffer, _| {
+        let snapshot = buffer.snapshot();
+        assert_eq!(
+            query.structural_replacement_for(&snapshot, 21..40),
+            Some("two::TWO + one::ONE".to_string())
+        );
+        assert_eq!(query.structural_replacement_for(&snapshot, 20..40), None);
+    });
+
+    let proto_query = SearchQuery::from_proto(query.to_proto(), PathStyle::local()).unwrap();
+    assert!(proto_query.is_structural());
+    assert_eq!(proto_query.as_str(), "one::ONE + $B");
+}
+
 #[gpui::test]
 async fn test_search_with_inclusions(cx: &mut gpui::TestAppContext) {
     init_test(cx);
@@ -1,10 +1,10 @@
-use std::{cell::RefCell, path::PathBuf, rc::Rc};
+use std::{cell::RefCell, collections::BTreeMap, path::PathBuf, rc::Rc};
 
-use collections::HashSet;
-use gpui::{Entity, TestAppContext};
+use collections::{HashMap, HashSet};
+use gpui::{Entity, SharedString, TestAppContext};
 use serde_json::json;
 use settings::SettingsStore;
-use util::path;
+use util::{path, rel_path::RelPath};
 
 use crate::{FakeFs, Project};
 
@@ -955,3 +955,383 @@ async fn test_invisible_worktree_stores_do_not_affect_trust(cx: &mut TestAppCont
         "only visible worktrees should be restricted"
     );
 }
+
+#[gpui::test]
+async fn test_capability_grants(cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/root"), json!({ "main.rs": "fn main() {}" }))
+        .await;
+
+    let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+    let worktree_store = project.read_with(cx, |project, _| project.worktree_store());
+    let worktree_id = worktree_store.read_with(cx, |store, cx| {
+        store.worktrees().next().unwrap().read(cx).id()
+    });
+
+    let trusted_worktrees = init_trust_global(worktree_store.clone(), cx);
+
+    let settings_dir = RelPath::empty().into_arc();
+    let settings = r#"{ "context_servers": { "my-server": { "command": "server" } } }"#;
+    trusted_worktrees.update(cx, |store, cx| {
+        store.update_configs(
+            &worktree_store,
+            worktree_id,
+            [
+                (
+                    TrustedConfigFile::Settings,
+                    settings_dir.clone(),
+                    Some(settings),
+                ),
+                (TrustedConfigFile::Tasks, settings_dir.clone(), Some("[]")),
+            ],
+            cx,
+        );
+    });
+    let context_servers = trusted_worktrees.read_with(cx, |store, _| {
+        store.context_servers(&worktree_store, worktree_id)
+    });
+    assert_eq!(
+        context_servers.into_iter().collect::<Vec<_>>(),
+        vec![SharedString::from("my-server")]
+    );
+
+    let can_use = |capability: TrustCapability, cx: &mut TestAppContext| {
+        trusted_worktrees.update(cx, |store, cx| {
+            store.can_use(&worktree_store, worktree_id, &capability, cx)
+        })
+    };
+    assert!(!can_use(TrustCapability::LanguageServers, cx));
+    assert!(!can_use(TrustCapability::ProjectTasks, cx));
+
+    trusted_worktrees.update(cx, |store, cx| {
+        store.set_grants(
+            &worktree_store,
+            worktree_id,
+            [
+                TrustCapability::LanguageServers,
+                TrustCapability::ContextServer("my-server".into()),
+            ],
+            cx,
+        );
+    });
+    assert!(can_use(TrustCapability::LanguageServers, cx));
+    assert!(can_use(
+        TrustCapability::ContextServer("my-server".into()),
+        cx
+    ));
+    assert!(!can_use(TrustCapability::ProjectTasks, cx));
+    assert!(!can_use(TrustCapability::ProjectSettings, cx));
+
+    trusted_worktrees.update(cx, |store, cx| {
+        store.update_configs(
+            &worktree_store,
+            worktree_id,
+            [(TrustedConfigFile::Tasks, settings_dir.clone(), Some("[{}]"))],
+            cx,
+        );
+    });
+    assert!(
+        can_use(TrustCapability::LanguageServers, cx),
+        "tasks changes should not affect the grants bound to settings"
+    );
+    let changed =
+        trusted_worktrees.update(cx, |store, _| store.take_changed_configs(&worktree_store));
+    assert!(!changed);
+
+    trusted_worktrees.update(cx, |store, cx| {
+        store.update_configs(
+            &worktree_store,
+            worktree_id,
+            [(
+                TrustedConfigFile::Settings,
+                settings_dir.clone(),
+                Some("{}"),
+            )],
+            cx,
+        );
+    });
+    assert!(
+        !can_use(TrustCapability::LanguageServers, cx),
+        "settings changes should revoke the grants bound to settings"
+    );
+    let changed_files = trusted_worktrees.read_with(cx, |store, cx| {
+        store.changed_config_files(&worktree_store, worktree_id, cx)
+    });
+    assert_eq!(
+        changed_files.into_iter().collect::<Vec<_>>(),
+        vec![TrustedConfigFile::Settings]
+    );
+    let changed =
+        trusted_worktrees.update(cx, |store, _| store.take_changed_configs(&worktree_store));
+    assert!(changed, "revoked grants should ask to re-prompt");
+    let changed =
+        trusted_worktrees.update(cx, |store, _| store.take_changed_configs(&worktree_store));
+    assert!(!changed, "re-prompt should only be requested once");
+
+    trusted_worktrees.update(cx, |store, cx| {
+        store.update_configs(
+            &worktree_store,
+            worktree_id,
+            [(
+                TrustedConfigFile::Settings,
+                settings_dir.clone(),
+                Some(settings),
+            )],
+            cx,
+        );
+    });
+    assert!(
+        can_use(TrustCapability::LanguageServers, cx),
+        "grants should apply again once the config is reverted"
+    );
+}
+
+#[gpui::test]
+async fn test_project_keymap_grant(cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/root"), json!({ "main.rs": "fn main() {}" }))
+        .await;
+
+    let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+    let worktree_store = project.read_with(cx, |project, _| project.worktree_store());
+    let worktree_id = worktree_store.read_with(cx, |store, cx| {
+        store.worktrees().next().unwrap().read(cx).id()
+    });
+
+    let trusted_worktrees = init_trust_global(worktree_store.clone(), cx);
+
+    let root = RelPath::empty().into_arc();
+    let keymap = r#"[{ "bindings": { "ctrl-t": "task::Spawn" } }]"#;
+    trusted_worktrees.update(cx, |store, cx| {
+        store.update_configs(
+            &worktree_store,
+            worktree_id,
+            [
+                (TrustedConfigFile::Settings, root.clone(), Some("{}")),
+                (TrustedConfigFile::Keymap, root.clone(), Some(keymap)),
+            ],
+            cx,
+        );
+    });
+
+    let can_use = |capability: TrustCapability, cx: &mut TestAppContext| {
+        trusted_worktrees.update(cx, |store, cx| {
+            store.can_use(&worktree_store, worktree_id, &capability, cx)
+        })
+    };
+    assert!(!can_use(TrustCapability::ProjectKeymap, cx));
+
+    trusted_worktrees.update(cx, |store, cx| {
+        store.set_grants(
+            &worktree_store,
+            worktree_id,
+            [TrustCapability::ProjectKeymap],
+            cx,
+        );
+    });
+    assert!(can_use(TrustCapability::ProjectKeymap, cx));
+    assert!(!can_use(TrustCapability::ProjectSettings, cx));
+
+    trusted_worktrees.update(cx, |store, cx| {
+        store.update_configs(
+            &worktree_store,
+            worktree_id,
+            [(TrustedConfigFile::Settings, root.clone(), Some("{ }"))],
+            cx,
+        );
+    });
+    assert!(
+        can_use(TrustCapability::ProjectKeymap, cx),
+        "settings changes should not affect the keymap grant"
+    );
+
+    trusted_worktrees.update(cx, |store, cx| {
+        store.update_configs(
+            &worktree_store,
+            worktree_id,
+            [(TrustedConfigFile::Keymap, root.clone(), Some("[]"))],
+            cx,
+        );
+    });
+    assert!(
+        !can_use(TrustCapability::ProjectKeymap, cx),
+        "keymap changes should revoke the keymap grant"
+    );
+    let changed_files = trusted_worktrees.read_with(cx, |store, cx| {
+        store.changed_config_files(&worktree_store, worktree_id, cx)
+    });
+    assert_eq!(
+        changed_files.into_iter().collect::<Vec<_>>(),
+        vec![TrustedConfigFile::Keymap]
+    );
+}
+
+#[gpui::test]
+async fn test_whole_trust_bound_to_configs(cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/root"), json!({ "main.rs": "fn main() {}" }))
+        .await;
+
+    let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+    let worktree_store = project.read_with(cx, |project, _| project.worktree_store());
+    let worktree_id = worktree_store.read_with(cx, |store, cx| {
+        store.worktrees().next().unwrap().read(cx).id()
+    });
+
+    let trusted_worktrees = init_trust_global(worktree_store.clone(), cx);
+
+    let root = RelPath::empty().into_arc();
+    let update_configs =
+        |config_file: TrustedConfigFile, content: Option<&str>, cx: &mut TestAppContext| {
+            trusted_worktrees.update(cx, |store, cx| {
+                store.update_configs(
+                    &worktree_store,
+                    worktree_id,
+                    [(config_file, root.clone(), content)],
+                    cx,
+                );
+            });
+        };
+    let can_trust = |cx: &mut TestAppContext| {
+        trusted_worktrees.update(cx, |store, cx| {
+            store.can_trust(&worktree_store, worktree_id, cx)
+        })
+    };
+    let trust = |cx: &mut TestAppContext| {
+        trusted_worktrees.update(cx, |store, cx| {
+            store.trust(
+                &worktree_store,
+                HashSet::from_iter([PathTrust::Worktree(worktree_id)]),
+                cx,
+            );
+        });
+    };
+    let take_changed_configs = |cx: &mut TestAppContext| {
+        trusted_worktrees.update(cx, |store, _| store.take_changed_configs(&worktree_store))
+    };
+
+    update_configs(TrustedConfigFile::Settings, Some("{}"), cx);
+    update_configs(TrustedConfigFile::Tasks, Some("[]"), cx);
+    assert!(!can_trust(cx));
+    trust(cx);
+    assert!(can_trust(cx));
+
+    update_configs(TrustedConfigFile::Tasks, Some("[]"), cx);
+    assert!(can_trust(cx), "unchanged configs should keep the trust");
+    assert!(!take_changed_configs(cx));
+
+    update_configs(TrustedConfigFile::Tasks, Some("[{}]"), cx);
+    assert!(!can_trust(cx), "changed configs should revoke the trust");
+    assert!(
+        take_changed_configs(cx),
+        "revoked trust should ask to re-prompt"
+    );
+
+    update_configs(TrustedConfigFile::Tasks, Some("[]"), cx);
+    assert!(
+        !can_trust(cx),
+        "reverting the config should not bring the trust back without a review"
+    );
+
+    trust(cx);
+    assert!(can_trust(cx));
+    update_configs(TrustedConfigFile::Keymap, Some("[]"), cx);
+    assert!(!can_trust(cx), "added configs should revoke the trust");
+
+    trust(cx);
+    assert!(can_trust(cx));
+    update_configs(TrustedConfigFile::Tasks, None, cx);
+    assert!(!can_trust(cx), "removed configs should revoke the trust");
+}
+
+#[gpui::test]
+async fn test_persisted_trusted_configs(cx: &mut TestAppContext) {
+    init_test(cx);
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(path!("/root"), json!({ "main.rs": "fn main() {}" }))
+        .await;
+
+    let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+    let worktree_store = project.read_with(cx, |project, _| project.worktree_store());
+    let worktree_id = worktree_store.read_with(cx, |store, cx| {
+        store.worktrees().next().unwrap().read(cx).id()
+    });
+
+    let root_path = PathBuf::from(path!("/root"));
+    let restore_trust = |trusted_configs: DbTrustedConfigs, cx: &mut TestAppContext| {
+        init_test(cx);
+        cx.update(|cx| {
+            init(
+                DbTrustedPaths::from_iter([(None, HashSet::from_iter([root_path.clone()]))]),
+                cx,
+            );
+            let trusted_worktrees =
+                TrustedWorktrees::try_get_global(cx).expect("global should be set");
+            trusted_worktrees.update(cx, |store, _| {
+                store.restore_grants(DbTrustGrants::default(), trusted_configs)
+            });
+            track_worktree_trust(worktree_store.clone(), None, None, None, cx);
+            trusted_worktrees
+        })
+    };
+
+    // Worktrees trusted before their configs got tracked record them when loaded.
+    let trusted_worktrees = restore_trust(DbTrustedConfigs::default(), cx);
+    let root = RelPath::empty().into_arc();
+    let update_configs =
+        |config_file: TrustedConfigFile, content: Option<&str>, cx: &mut TestAppContext| {
+            trusted_worktrees.update(cx, |store, cx| {
+                store.update_configs(
+                    &worktree_store,
+                    worktree_id,
+                    [(config_file, root.clone(), content)],
+                    cx,
+                );
+            });
+        };
+    let can_trust = |cx: &mut TestAppContext| {
+        trusted_worktrees.update(cx, |store, cx| {
+            store.can_trust(&worktree_store, worktree_id, cx)
+        })
+    };
+    update_configs(TrustedConfigFile::Settings, Some("{}"), cx);
+    assert!(can_trust(cx));
+    update_configs(TrustedConfigFile::Tasks, Some("[]"), cx);
+    assert!(can_trust(cx), "configs loaded later should be recorded too");
+    update_configs(TrustedConfigFile::Settings, Some("{ }"), cx);
+    assert!(!can_trust(cx), "recorded configs should not change");
+
+    let trusted_configs = TrustedConfigs {
+        hashes: BTreeMap::from_iter([(
+            (
+                TrustedConfigFile::Tasks,
+                RelPath::unix("nested/dir").unwrap().into_arc(),
+            ),
+            "tasks-hash".to_string(),
+        )]),
+        changed: true,
+    };
+    assert_eq!(
+        TrustedConfigs::from_db_value(&trusted_configs.hashes_to_db_value(), true),
+        Some(trusted_configs.clone())
+    );
+
+    // The trust, revoked in a previous session, stays revoked.
+    let trusted_worktrees = restore_trust(
+        DbTrustedConfigs::from_iter([(
+            None,
+            HashMap::from_iter([(root_path.clone(), trusted_configs)]),
+        )]),
+        cx,
+    );
+    assert!(!trusted_worktrees.update(cx, |store, cx| {
+        store.can_trust(&worktree_store, worktree_id, cx)
+    }));
+}
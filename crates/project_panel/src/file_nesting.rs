@@ -0,0 +1,338 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use collections::HashMap;
+use project::{GitEntry, ProjectEntryId};
+use util::rel_path::RelPath;
+
+/// Patterns for nesting related files, like generated files or lockfiles,
+/// under a parent file in the same directory.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct FileNestingRules {
+    rules: Vec<NestingRule>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct NestingRule {
+    /// The text before the `*` in the parent pattern, or the whole pattern if
+    /// it has no `*`.
+    prefix: String,
+    /// The text after the `*` in the parent pattern, or `None` if it has no
+    /// `*` and must match a file name exactly.
+    suffix: Option<String>,
+    children: Vec<String>,
+}
+
+impl FileNestingRules {
+    /// Compiles the `file_nesting.patterns` setting. Parent patterns with more
+    /// than one `*` are ignored.
+    pub(crate) fn new(patterns: &BTreeMap<String, Vec<String>>) -> Self {
+        let rules = patterns
+            .iter()
+            .filter(|(_, children)| !children.is_empty())
+            .filter_map(|(parent, children)| {
+                let (prefix, suffix) = match parent.split_once('*') {
+                    Some((_, suffix)) if suffix.contains('*') => return None,
+                    Some((prefix, suffix)) => (prefix.to_string(), Some(suffix.to_string())),
+                    None => (parent.clone(), None),
+                };
+                Some(NestingRule {
+                    prefix,
+                    suffix,
+                    children: children.clone(),
+                })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Given the names of the files in one directory, returns the index of
+    /// the file each nested file is shown under.
+    ///
+    /// Nesting is a single level deep: when a file would be nested under a
+    /// file that is itself nested, it's shown under the outermost parent
+    /// instead. Files are never nested under themselves or under a file
+    /// nested beneath them.
+    pub(crate) fn nest(&self, file_names: &[&str]) -> HashMap<usize, usize> {
+        let mut parents = HashMap::default();
+        if self.rules.is_empty() || file_names.len() < 2 {
+            return parents;
+        }
+
+        let indices_by_name = file_names
+            .iter()
+            .enumerate()
+            .map(|(ix, name)| (*name, ix))
+            .collect::<HashMap<_, _>>();
+        for (parent_ix, parent_name) in file_names.iter().enumerate() {
+            for rule in &self.rules {
+                let Some(capture) = rule.capture(parent_name) else {
+                    continue;
+                };
+                for child in &rule.children {
+                    let child = child.replace("${capture}", capture);
+                    let mut nest = |child_ix: usize| {
+                        if child_ix != parent_ix
+                            && !parents.contains_key(&child_ix)
+                            && !is_nested_under(&parents, parent_ix, child_ix)
+                        {
+                            parents.insert(child_ix, parent_ix);
+                        }
+                    };
+                    if child.contains('*') {
+                        for (child_ix, child_name) in file_names.iter().enumerate() {
+                            if glob_matches(&child, child_name) {
+                                nest(child_ix);
+                            }
+                        }
+                    } else if let Some(child_ix) = indices_by_name.get(child.as_str()) {
+                        nest(*child_ix);
+                    }
+                }
+            }
+        }
+
+        parents
+            .keys()
+            .map(|child_ix| {
+                let mut parent_ix = parents[child_ix];
+                while let Some(grandparent_ix) = parents.get(&parent_ix) {
+                    parent_ix = *grandparent_ix;
+                }
+                (*child_ix, parent_ix)
+            })
+            .collect()
+    }
+}
+
+impl NestingRule {
+    /// Returns the text matched by the `*` if the parent pattern matches the
+    /// file name.
+    fn capture<'a>(&self, file_name: &'a str) -> Option<&'a str> {
+        let rest = file_name.strip_prefix(self.prefix.as_str())?;
+        match &self.suffix {
+            Some(suffix) => rest.strip_suffix(suffix.as_str()),
+            None => rest.is_empty().then_some(rest),
+        }
+    }
+}
+
+fn is_nested_under(parents: &HashMap<usize, usize>, mut ix: usize, ancestor_ix: usize) -> bool {
+    while let Some(parent_ix) = parents.get(&ix) {
+        if *parent_ix == ancestor_ix {
+            return true;
+        }
+        ix = *parent_ix;
+    }
+    false
+}
+
+/// Matches a file name against a pattern where `*` matches any text.
+fn glob_matches(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let Some(mut rest) = name.strip_prefix(first) else {
+        return false;
+    };
+    let mut parts = parts.peekable();
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return rest.ends_with(part);
+        }
+        match rest.find(part) {
+            Some(ix) => rest = &rest[ix + part.len()..],
+            None => return false,
+        }
+    }
+    rest.is_empty()
+}
+
+/// The files nested under other files in a worktree's visible entries.
+#[derive(Debug, Default)]
+pub(crate) struct NestedFiles {
+    /// The file each nested file is shown under.
+    parents: HashMap<Arc<RelPath>, Arc<RelPath>>,
+    /// The files nested under each parent file, in display order.
+    children: HashMap<Arc<RelPath>, Vec<Arc<RelPath>>>,
+}
+
+impl NestedFiles {
+    pub(crate) fn parent_of(&self, path: &RelPath) -> Option<&Arc<RelPath>> {
+        self.parents.get(path)
+    }
+
+    pub(crate) fn children_of(&self, path: &RelPath) -> &[Arc<RelPath>] {
+        self.children.get(path).map_or(&[], Vec::as_slice)
+    }
+
+    pub(crate) fn is_nest_parent(&self, path: &RelPath) -> bool {
+        self.children.contains_key(path)
+    }
+
+    /// Nests the files in sorted visible entries according to `rules`.
+    ///
+    /// Nested files are moved right after their parent file, and removed
+    /// unless the parent is in `expanded_ids`. Parent files take on the git
+    /// status of the files nested under them, like directories do.
+    pub(crate) fn nest_entries(
+        entries: &mut Vec<GitEntry>,
+        rules: &FileNestingRules,
+        expanded_ids: &[ProjectEntryId],
+        skip_id: ProjectEntryId,
+    ) -> Self {
+        let mut nested_files = Self::default();
+        if rules.is_empty() {
+            return nested_files;
+        }
+
+        let mut files_by_dir = HashMap::<&RelPath, Vec<usize>>::default();
+        for (ix, entry) in entries.iter().enumerate() {
+            if entry.is_file()
+                && entry.id != skip_id
+                && let Some(parent) = entry.path.parent()
+            {
+                files_by_dir.entry(parent).or_default().push(ix);
+            }
+        }
+        let mut parent_indices = HashMap::<usize, usize>::default();
+        for files in files_by_dir.values() {
+            let names = files
+                .iter()
+                .map(|ix| entries[*ix].path.file_name().unwrap_or_default())
+                .collect::<Vec<_>>();
+            for (child, parent) in rules.nest(&names) {
+                parent_indices.insert(files[child], files[parent]);
+            }
+        }
+        if parent_indices.is_empty() {
+            return nested_files;
+        }
+
+        let mut child_indices = HashMap::<usize, Vec<usize>>::default();
+        let mut sorted_parent_indices = parent_indices.iter().collect::<Vec<_>>();
+        sorted_parent_indices.sort_unstable();
+        for (child_ix, parent_ix) in sorted_parent_indices {
+            let git_summary = entries[*child_ix].git_summary;
+            entries[*parent_ix].git_summary += git_summary;
+            child_indices.entry(*parent_ix).or_default().push(*child_ix);
+            nested_files.parents.insert(
+                entries[*child_ix].path.clone(),
+                entries[*parent_ix].path.clone(),
+            );
+            nested_files
+                .children
+                .entry(entries[*parent_ix].path.clone())
+                .or_default()
+                .push(entries[*child_ix].path.clone());
+        }
+
+        let mut old_entries = std::mem::take(entries)
+            .into_iter()
+            .map(Some)
+            .collect::<Vec<_>>();
+        for ix in 0..old_entries.len() {
+            if parent_indices.contains_key(&ix) {
+                continue;
+            }
+            let Some(entry) = old_entries[ix].take() else {
+                continue;
+            };
+            let is_expanded = expanded_ids.binary_search(&entry.id).is_ok();
+            entries.push(entry);
+            if is_expanded && let Some(children) = child_indices.get(&ix) {
+                entries.extend(children.iter().filter_map(|ix| old_entries[*ix].take()));
+            }
+        }
+        nested_files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(patterns: &[(&str, &[&str])]) -> FileNestingRules {
+        FileNestingRules::new(
+            &patterns
+                .iter()
+                .map(|(parent, children)| {
+                    (
+                        parent.to_string(),
+                        children.iter().map(|child| child.to_string()).collect(),
+                    )
+                })
+                .collect(),
+        )
+    }
+
+    fn nest<'a>(rules: &FileNestingRules, names: &[&'a str]) -> Vec<(&'a str, &'a str)> {
+        let mut nested = rules
+            .nest(names)
+            .into_iter()
+            .map(|(child, parent)| (names[child], names[parent]))
+            .collect::<Vec<_>>();
+        nested.sort_unstable();
+        nested
+    }
+
+    #[test]
+    fn test_nest_by_capture() {
+        let rules = rules(&[
+            (
+                "*.ts",
+                &["${capture}.js", "${capture}.d.ts", "${capture}.*.ts"],
+            ),
+            ("Cargo.toml", &["Cargo.lock"]),
+        ]);
+        assert_eq!(
+            nest(
+                &rules,
+                &[
+                    "Cargo.lock",
+                    "Cargo.toml",
+                    "main.d.ts",
+                    "main.js",
+                    "main.test.ts",
+                    "main.ts",
+                    "other.js",
+                ]
+            ),
+            [
+                ("Cargo.lock", "Cargo.toml"),
+                ("main.d.ts", "main.ts"),
+                ("main.js", "main.ts"),
+                ("main.test.ts", "main.ts"),
+            ]
+        );
+        assert_eq!(nest(&rules, &["Cargo.lock"]), []);
+    }
+
+    #[test]
+    fn test_nesting_is_one_level_deep() {
+        let rules = rules(&[
+            ("*.ts", &["${capture}.js"]),
+            ("*.js", &["${capture}.js.map"]),
+        ]);
+        assert_eq!(
+            nest(&rules, &["a.js", "a.js.map", "a.ts"]),
+            [("a.js", "a.ts"), ("a.js.map", "a.ts")]
+        );
+
+        let cyclic_rules = rules(&[("*.ts", &["${capture}.js"]), ("*.js", &["${capture}.ts"])]);
+        assert_eq!(nest(&cyclic_rules, &["a.js", "a.ts"]), [("a.ts", "a.js")]);
+    }
+
+    #[test]
+    fn test_glob_matches() {
+        assert!(glob_matches("*.ts", "a.ts"));
+        assert!(glob_matches("a.*.ts", "a.test.ts"));
+        assert!(glob_matches("*", "anything"));
+        assert!(glob_matches("a*b*c", "abc"));
+        assert!(!glob_matches("a*b*c", "acb"));
+        assert!(!glob_matches("a.*.ts", "a.ts"));
+        assert!(!glob_matches("exact", "exactly"));
+    }
+}
@@ -1,3 +1,4 @@
+mod file_nesting;
 pub mod project_panel_settings;
 mod undo;
 mod utils;
@@ -38,7 +39,7 @@ use project::{
     git_store::{GitStoreEvent, RepositoryEvent, git_traversal::ChildEntriesGitIter},
     project_settings::GoToDiagnosticSeverityFilter,
 };
-use project_panel_settings::ProjectPanelSettings;
+use project_panel_settings::{FileNestingSettings, ProjectPanelSettings};
 use rayon::slice::ParallelSliceMut;
 use schemars::JsonSchema;
 use serde::Deserialize;
@@ -53,6 +54,7 @@ use std::{
     cell::OnceCell,
     cmp,
     collections::HashSet,
+    iter,
     ops::Range,
     path::{Path, PathBuf},
     sync::Arc,
@@ -82,7 +84,10 @@ use zed_actions::{
     workspace::OpenWithSystem,
 };
 
-use crate::undo::{ProjectPanelOperation, UndoManager};
+use crate::{
+    file_nesting::{FileNestingRules, NestedFiles},
+    undo::{ProjectPanelOperation, UndoManager},
+};
 
 const PROJECT_PANEL_KEY: &str = "ProjectPanel";
 const NEW_ENTRY_ID: ProjectEntryId = ProjectEntryId::MAX;
@@ -91,6 +96,14 @@ struct VisibleEntriesForWorktree {
     worktree_id: WorktreeId,
     entries: Vec<GitEntry>,
     index: OnceCell<HashSet<Arc<RelPath>>>,
+    nested_files: NestedFiles,
+}
+
+impl VisibleEntriesForWorktree {
+    fn paths(&self) -> &HashSet<Arc<RelPath>> {
+        self.index
+            .get_or_init(|| self.entries.iter().map(|e| e.path.clone()).collect())
+    }
 }
 
 struct State {
@@ -269,6 +282,8 @@ struct EntryDetails {
     kind: EntryKind,
     is_ignored: bool,
     is_expanded: bool,
+    /// Whether other files are nested under this file.
+    is_nest_parent: bool,
     is_selected: bool,
     is_marked: bool,
     is_editing: bool,
@@ -829,7 +844,13 @@ impl ProjectPanel {
             .detach();
 
             let mut project_panel_settings = *ProjectPanelSettings::get_global(cx);
+            let mut file_nesting_settings = FileNestingSettings::get_global(cx).clone();
             cx.observe_global_in::<SettingsStore>(window, move |this, window, cx| {
+                let new_file_nesting_settings = FileNestingSettings::get_global(cx);
+                if &file_nesting_settings != new_file_nesting_settings {
+                    file_nesting_settings = new_file_nesting_settings.clone();
+                    this.update_visible_entries(None, false, false, window, cx);
+                }
                 let new_settings = *ProjectPanelSettings::get_global(cx);
                 if project_panel_settings != new_settings {
                     if project_panel_settings.hide_gitignore != new_settings.hide_gitignore {
@@ -1299,7 +1320,8 @@ impl ProjectPanel {
                 cx.notify();
                 return;
             }
-            if entry.is_dir() {
+            let is_dir = entry.is_dir();
+            if is_dir || self.is_nest_parent(worktree.id(), entry) {
                 let worktree_id = worktree.id();
                 let entry_id = entry.id;
                 let expanded_dir_ids = if let Some(expanded_dir_ids) =
@@ -1313,9 +1335,11 @@ impl ProjectPanel {
                 match expanded_dir_ids.binary_search(&entry_id) {
                     Ok(_) => self.select_next(&SelectNext, window, cx),
                     Err(ix) => {
-                        self.project.update(cx, |project, cx| {
-                            project.expand_entry(worktree_id, entry_id, cx);
-                        });
+                        if is_dir {
+                            self.project.update(cx, |project, cx| {
+                                project.expand_entry(worktree_id, entry_id, cx);
+                            });
+                        }
 
                         expanded_dir_ids.insert(ix, entry_id);
                         self.update_visible_entries(None, false, false, window, cx);
@@ -1354,6 +1378,7 @@ impl ProjectPanel {
             return;
         }
         let worktree_id = worktree.id();
+        let nest_parent = self.nest_parent_entry(worktree, &entry, cx);
         let expanded_dir_ids =
             if let Some(expanded_dir_ids) = self.state.expanded_dir_ids.get_mut(&worktree_id) {
                 expanded_dir_ids
@@ -1361,7 +1386,8 @@ impl ProjectPanel {
                 return;
             };
 
-        let mut entry = &entry;
+        // Collapsing a nested file collapses the file it's nested under.
+        let mut entry = nest_parent.unwrap_or(&entry);
         loop {
             let entry_id = entry.id;
             match expanded_dir_ids.binary_search(&entry_id) {
@@ -1489,7 +1515,14 @@ impl ProjectPanel {
                         expanded_dir_ids.remove(ix);
                     }
                     Err(ix) => {
-                        project.expand_entry(worktree_id, entry_id, cx);
+                        // Files are only expanded to show the files nested under them.
+                        let is_dir = project
+                            .worktree_for_id(worktree_id, cx)
+                            .and_then(|worktree| worktree.read(cx).entry_for_id(entry_id))
+                            .is_some_and(|entry| entry.is_dir());
+                        if is_dir {
+                            project.expand_entry(worktree_id, entry_id, cx);
+                        }
                         expanded_dir_ids.insert(ix, entry_id);
                     }
                 }
@@ -3937,6 +3970,12 @@ impl ProjectPanel {
             .collect();
         let hide_root = settings.hide_root && visible_worktrees.len() == 1;
         let hide_hidden = settings.hide_hidden;
+        let file_nesting = FileNestingSettings::get_global(cx);
+        let file_nesting_rules = if file_nesting.enabled {
+            FileNestingRules::new(&file_nesting.patterns)
+        } else {
+            FileNestingRules::default()
+        };
 
         let visible_entries_task = cx.spawn_in(window, async move |this, cx| {
             let new_state = cx
@@ -4153,10 +4192,20 @@ impl ProjectPanel {
                             &mut visible_worktree_entries,
                             sort_mode,
                         );
+                        let nested_files = NestedFiles::nest_entries(
+                            &mut visible_worktree_entries,
+                            &file_nesting_rules,
+                            new_state
+                                .expanded_dir_ids
+                                .get(&worktree_id)
+                                .map_or(&[], Vec::as_slice),
+                            NEW_ENTRY_ID,
+                        );
                         new_state.visible_entries.push(VisibleEntriesForWorktree {
                             worktree_id,
                             entries: visible_worktree_entries,
                             index: OnceCell::new(),
+                            nested_files,
                         })
                     }
                     if let Some((project_entry_id, worktree_id, _)) = max_width_item {
@@ -4232,6 +4281,15 @@ impl ProjectPanel {
         entry_id: ProjectEntryId,
         cx: &mut Context<Self>,
     ) {
+        let nest_parent_id = self
+            .project
+            .read(cx)
+            .worktree_for_id(worktree_id, cx)
+            .and_then(|worktree| {
+                let worktree = worktree.read(cx);
+                let entry = worktree.entry_for_id(entry_id)?;
+                Some(self.nest_parent_entry(worktree, entry, cx)?.id)
+            });
         self.project.update(cx, |project, cx| {
             if let Some((worktree, expanded_dir_ids)) = project
                 .worktree_for_id(worktree_id, cx)
@@ -4240,9 +4298,16 @@ impl ProjectPanel {
                 project.expand_entry(worktree_id, entry_id, cx);
                 let worktree = worktree.read(cx);
 
+                if let Some(nest_parent_id) = nest_parent_id
+                    && let Err(ix) = expanded_dir_ids.binary_search(&nest_parent_id)
+                {
+                    expanded_dir_ids.insert(ix, nest_parent_id);
+                }
                 if let Some(mut entry) = worktree.entry_for_id(entry_id) {
                     loop {
-                        if let Err(ix) = expanded_dir_ids.binary_search(&entry.id) {
+                        if entry.is_dir()
+                            && let Err(ix) = expanded_dir_ids.binary_search(&entry.id)
+                        {
                             expanded_dir_ids.insert(ix, entry.id);
                         }
 
@@ -4259,6 +4324,94 @@ impl ProjectPanel {
         });
     }
 
+    /// Returns the file that the given file is nested under, if any. This is
+    /// also found when the file's directory hasn't been expanded yet.
+    fn nest_parent_entry<'a>(
+        &self,
+        worktree: &'a Worktree,
+        entry: &Entry,
+        cx: &App,
+    ) -> Option<&'a Entry> {
+        if !entry.is_file() {
+            return None;
+        }
+        let nested_files = self
+            .state
+            .visible_entries
+            .iter()
+            .find(|visible| visible.worktree_id == worktree.id())
+            .map(|visible| &visible.nested_files);
+        if let Some(nest_parent) = nested_files.and_then(|files| files.parent_of(&entry.path)) {
+            return worktree.entry_for_path(nest_parent);
+        }
+
+        let settings = FileNestingSettings::get_global(cx);
+        if !settings.enabled {
+            return None;
+        }
+        let siblings = worktree
+            .child_entries(entry.path.parent()?)
+            .filter(|sibling| sibling.is_file())
+            .collect::<Vec<_>>();
+        let names = siblings
+            .iter()
+            .map(|sibling| sibling.path.file_name().unwrap_or_default())
+            .collect::<Vec<_>>();
+        let ix = siblings.iter().position(|sibling| sibling.id == entry.id)?;
+        let nest_parent_ix = *FileNestingRules::new(&settings.patterns)
+            .nest(&names)
+            .get(&ix)?;
+        Some(siblings[nest_parent_ix])
+    }
+
+    fn is_nest_parent(&self, worktree_id: WorktreeId, entry: &Entry) -> bool {
+        entry.is_file()
+            && self
+                .state
+                .visible_entries
+                .iter()
+                .find(|visible| visible.worktree_id == worktree_id)
+                .is_some_and(|visible| visible.nested_files.is_nest_parent(&entry.path))
+    }
+
+    /// Adds the files nested under any selected file, so they move along with
+    /// the file they're shown under.
+    fn with_nested_files(
+        &self,
+        mut entries: BTreeSet<SelectedEntry>,
+        cx: &App,
+    ) -> BTreeSet<SelectedEntry> {
+        let project = self.project.read(cx);
+        let mut nested_entries = Vec::new();
+        for selection in &entries {
+            let Some(visible) = self
+                .state
+                .visible_entries
+                .iter()
+                .find(|visible| visible.worktree_id == selection.worktree_id)
+            else {
+                continue;
+            };
+            let Some(worktree) = project.worktree_for_id(selection.worktree_id, cx) else {
+                continue;
+            };
+            let worktree = worktree.read(cx);
+            let Some(entry) = worktree.entry_for_id(selection.entry_id) else {
+                continue;
+            };
+            for child_path in visible.nested_files.children_of(&entry.path) {
+                if let Some(child) = worktree.entry_for_path(child_path) {
+                    nested_entries.push(SelectedEntry {
+                        worktree_id: selection.worktree_id,
+                        entry_id: child.id,
+                    });
+                }
+            }
+        }
+        entries.extend(nested_entries);
+        entries
+    }
+
     fn drop_external_files(
         &mut self,
         paths: &[PathBuf],
@@ -4408,7 +4561,7 @@ impl ProjectPanel {
                 worktree_id: entry.worktree_id,
             })
             .collect::<BTreeSet<SelectedEntry>>();
-        let entries = self.disjoint_entries(resolved_selections, cx);
+        let entries = self.disjoint_entries(self.with_nested_files(resolved_selections, cx), cx);
 
         if Self::is_copy_modifier_set(&window.modifiers()) {
             let _ = maybe!({
@@ -4694,7 +4847,7 @@ impl ProjectPanel {
         callback: &mut dyn FnMut(
             &Entry,
             usize,
-            &HashSet<Arc<RelPath>>,
+            &VisibleEntriesForWorktree,
             &mut Window,
             &mut Context<ProjectPanel>,
         ),
@@ -4712,13 +4865,10 @@ impl ProjectPanel {
 
             let end_ix = range.end.min(ix + visible.entries.len());
             let entry_range = range.start.saturating_sub(ix)..end_ix - ix;
-            let entries = visible
-                .index
-                .get_or_init(|| visible.entries.iter().map(|e| e.path.clone()).collect());
             let base_index = ix + entry_range.start;
             for (i, entry) in visible.entries[entry_range].iter().enumerate() {
                 let global_index = base_index + i;
-                callback(entry, global_index, entries, window, cx);
+                callback(entry, global_index, visible, window, cx);
             }
             ix = end_ix;
         }
@@ -4761,9 +4911,6 @@ impl ProjectPanel {
                 let root_name = snapshot.root_name();
 
                 let entry_range = range.start.saturating_sub(ix)..end_ix - ix;
-                let entries = visible
-                    .index
-                    .get_or_init(|| visible.entries.iter().map(|e| e.path.clone()).collect());
                 for entry in visible.entries[entry_range].iter() {
                     let status = git_status_setting
                         .then_some(entry.git_summary)
@@ -4773,7 +4920,7 @@ impl ProjectPanel {
                         entry,
                         visible.worktree_id,
                         root_name,
-                        entries,
+                        visible,
                         status,
                         None,
                         window,
@@ -5096,8 +5243,9 @@ impl ProjectPanel {
 
     fn calculate_depth_and_difference(
         entry: &Entry,
-        visible_worktree_entries: &HashSet<Arc<RelPath>>,
+        visible: &VisibleEntriesForWorktree,
     ) -> (usize, usize) {
+        let visible_worktree_entries = visible.paths();
         let (depth, difference) = entry
             .path
             .ancestors()
@@ -5119,7 +5267,12 @@ impl ProjectPanel {
             })
             .unwrap_or_else(|| (0, entry.path.components().count()));
 
-        (depth, difference)
+        // Files nested under another file are indented beneath it.
+        if visible.nested_files.parent_of(&entry.path).is_some() {
+            (depth + 1, difference)
+        } else {
+            (depth, difference)
+        }
     }
 
     fn highlight_entry_for_external_drag(
@@ -5269,6 +5422,8 @@ impl ProjectPanel {
         let path_for_dragged_selection = path.clone();
 
         let depth = details.depth;
+        let is_nest_parent = details.is_nest_parent;
+        let is_expanded = details.is_expanded;
         let worktree_id = details.worktree_id;
         let dragged_selection = DraggedSelection {
             active_selection: SelectedEntry {
@@ -5694,6 +5849,13 @@ impl ProjectPanel {
                 ListItem::new(id)
                     .indent_level(depth)
                     .indent_step_size(px(settings.indent_size))
+                    .when(is_nest_parent, |this| {
+                        this.toggle(is_expanded)
+                            .always_show_disclosure_icon(true)
+                            .on_toggle(cx.listener(move |this, _, window, cx| {
+                                this.toggle_expanded(entry_id, window, cx);
+                            }))
+                    })
                     .spacing(match settings.entry_spacing {
                         ProjectPanelEntrySpacing::Comfortable => ListItemSpacing::Dense,
                         ProjectPanelEntrySpacing::Standard => ListItemSpacing::ExtraDense,
@@ -6106,7 +6268,7 @@ impl ProjectPanel {
         entry: &Entry,
         worktree_id: WorktreeId,
         root_name: &RelPath,
-        entries_paths: &HashSet<Arc<RelPath>>,
+        visible: &VisibleEntriesForWorktree,
         git_status: GitSummary,
         sticky: Option<StickyDetails>,
         _window: &mut Window,
@@ -6143,8 +6305,7 @@ impl ProjectPanel {
         };
 
         let path_style = self.project.read(cx).path_style(cx);
-        let (depth, difference) =
-            ProjectPanel::calculate_depth_and_difference(entry, entries_paths);
+        let (depth, difference) = ProjectPanel::calculate_depth_and_difference(entry, visible);
 
         let filename = if difference > 1 {
             entry
@@ -6168,9 +6329,12 @@ impl ProjectPanel {
         let is_marked = self.marked_entries.contains(&selection);
         let is_selected = self.selection == Some(selection);
 
-        let diagnostic_severity = self
-            .diagnostics
-            .get(&(worktree_id, entry.path.clone()))
+        let is_nest_parent = visible.nested_files.is_nest_parent(&entry.path);
+        // Like directories, files show the strongest diagnostic of the files nested under them.
+        let diagnostic_severity = iter::once(&entry.path)
+            .chain(visible.nested_files.children_of(&entry.path))
+            .filter_map(|path| self.diagnostics.get(&(worktree_id, path.clone())))
+            .min()
             .cloned();
 
         let diagnostic_count = self
@@ -6194,6 +6358,7 @@ impl ProjectPanel {
             kind: entry.kind,
             is_ignored: entry.is_ignored,
             is_expanded,
+            is_nest_parent,
             is_selected,
             is_marked,
             is_editing: false,
@@ -6288,50 +6453,55 @@ impl ProjectPanel {
     ) -> Option<usize> {
         let (worktree, entry) = self.selected_entry(cx)?;
 
+        let nested_files = self
+            .state
+            .visible_entries
+            .iter()
+            .find(|visible| visible.worktree_id == worktree.id())
+            .map(|visible| &visible.nested_files)?;
+        let is_expanded = |entry: &Entry| {
+            self.state
+                .expanded_dir_ids
+                .get(&worktree.id())
+                .is_some_and(|ids| ids.binary_search(&entry.id).is_ok())
+        };
+
         // Find the parent entry of the indent guide, this will either be the
-        // expanded folder we have selected, or the parent of the currently
-        // selected file/collapsed directory
+        // expanded folder or nest parent file we have selected, or the parent
+        // of the currently selected file/collapsed directory
         let mut entry = entry;
         loop {
-            let is_expanded_dir = entry.is_dir()
-                && self
-                    .state
-                    .expanded_dir_ids
-                    .get(&worktree.id())
-                    .map(|ids| ids.binary_search(&entry.id).is_ok())
-                    .unwrap_or(false);
-            if is_expanded_dir {
+            if (entry.is_dir() || nested_files.is_nest_parent(&entry.path)) && is_expanded(entry) {
                 break;
             }
-            entry = worktree.entry_for_path(&entry.path.parent()?)?;
+            entry = match nested_files.parent_of(&entry.path) {
+                Some(nest_parent) => worktree.entry_for_path(nest_parent)?,
+                None => worktree.entry_for_path(&entry.path.parent()?)?,
+            };
         }
 
         let (active_indent_range, depth) = {
             let (worktree_ix, child_offset, ix) = self.index_for_entry(entry.id, worktree.id())?;
-            let child_paths = &self.state.visible_entries[worktree_ix].entries;
+            let visible_worktree = &self.state.visible_entries[worktree_ix];
+            let child_paths = &visible_worktree.entries;
             let mut child_count = 0;
-            let depth = entry.path.ancestors().count();
-            while let Some(entry) = child_paths.get(child_offset + child_count + 1) {
-                if entry.path.ancestors().count() <= depth {
-                    break;
+            if entry.is_dir() {
+                let depth = entry.path.ancestors().count();
+                while let Some(entry) = child_paths.get(child_offset + child_count + 1) {
+                    if entry.path.ancestors().count() <= depth {
+                        break;
+                    }
+                    child_count += 1;
                 }
-                child_count += 1;
+            } else {
+                child_count = nested_files.children_of(&entry.path).len();
             }
 
             let start = ix + 1;
             let end = start + child_count;
 
-            let visible_worktree = &self.state.visible_entries[worktree_ix];
-            let visible_worktree_entries = visible_worktree.index.get_or_init(|| {
-                visible_worktree
-                    .entries
-                    .iter()
-                    .map(|e| e.path.clone())
-                    .collect()
-            });
-
             // Calculate the actual depth of the entry, taking into account that directories can be auto-folded.
-            let (depth, _) = Self::calculate_depth_and_difference(entry, visible_worktree_entries);
+            let (depth, _) = Self::calculate_depth_and_difference(entry, visible_worktree);
             (start..end, depth)
         };
 
@@ -6377,12 +6547,18 @@ impl ProjectPanel {
         };
         let worktree = worktree.read(cx).snapshot();
 
-        let paths = visible
-            .index
-            .get_or_init(|| visible.entries.iter().map(|e| e.path.clone()).collect());
+        let paths = visible.paths();
 
         let mut sticky_parents = Vec::new();
         let mut current_path = entry_ref.path.clone();
+        if let Some(nest_parent) = visible
+            .nested_files
+            .parent_of(&current_path)
+            .and_then(|path| worktree.entry_for_path(path))
+        {
+            sticky_parents.push(nest_parent.clone());
+            current_path = nest_parent.path.clone();
+        }
 
         'outer: loop {
             if let Some(parent_path) = current_path.parent() {
@@ -6436,7 +6612,7 @@ impl ProjectPanel {
                     entry,
                     worktree_id,
                     root_name,
-                    paths,
+                    visible,
                     git_status,
                     sticky_details,
                     window,
@@ -6692,10 +6868,10 @@ impl Render for ProjectPanel {
                                                 range,
                                                 window,
                                                 cx,
-                                                &mut |entry, _, entries, _, _| {
+                                                &mut |entry, _, visible, _, _| {
                                                     let (depth, _) =
                                                         Self::calculate_depth_and_difference(
-                                                            entry, entries,
+                                                            entry, visible,
                                                         );
                                                     items.push(depth);
                                                 },
@@ -6805,10 +6981,10 @@ impl Render for ProjectPanel {
                                             range,
                                             window,
                                             cx,
-                                            &mut |entry, index, entries, _, _| {
+                                            &mut |entry, index, visible, _, _| {
                                                 let (depth, _) =
                                                     Self::calculate_depth_and_difference(
-                                                        entry, entries,
+                                                        entry, visible,
                                                     );
                                                 let candidate =
                                                     StickyProjectPanelCandidate { index, depth };
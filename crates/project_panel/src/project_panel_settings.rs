@@ -1,3 +1,5 @@
+use std::collections::BTreeMap;
+
 use editor::EditorSettings;
 use gpui::Pixels;
 use schemars::JsonSchema;
@@ -39,6 +41,17 @@ pub struct ProjectPanelSettings {
     pub git_status_indicator: bool,
 }
 
+/// The `project_panel.file_nesting` settings. They're registered separately
+/// from [`ProjectPanelSettings`] so that it can stay `Copy`.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq, RegisterSetting)]
+pub struct FileNestingSettings {
+    /// Whether to nest related files under a parent file.
+    pub enabled: bool,
+    /// Patterns that nest files under a parent file, keyed by the name of the
+    /// parent file.
+    pub patterns: BTreeMap<String, Vec<String>>,
+}
+
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
 pub struct IndentGuidesSettings {
     pub show: ShowIndentGuides,
@@ -142,3 +155,19 @@ impl Settings for ProjectPanelSettings {
         }
     }
 }
+
+impl Settings for FileNestingSettings {
+    fn from_settings(content: &settings::SettingsContent) -> Self {
+        let file_nesting = content
+            .project_panel
+            .as_ref()
+            .unwrap()
+            .file_nesting
+            .clone()
+            .unwrap();
+        Self {
+            enabled: file_nesting.enabled.unwrap(),
+            patterns: file_nesting.patterns.unwrap(),
+        }
+    }
+}
@@ -7220,6 +7220,117 @@ fn toggle_expand_dir(panel: &Entity<ProjectPanel>, path: &str, cx: &mut VisualTe
     cx.run_until_parked();
 }
 
+#[gpui::test]
+async fn test_file_nesting(cx: &mut gpui::TestAppContext) {
+    init_test_with_editor(cx);
+    cx.update(|cx| {
+        cx.update_global::<SettingsStore, _>(|store, cx| {
+            store.update_user_settings(cx, |settings| {
+                settings.project_panel.get_or_insert_default().file_nesting =
+                    Some(settings::ProjectPanelFileNestingSettings {
+                        enabled: Some(true),
+                        patterns: Some(
+                            [
+                                (
+                                    "*.ts".to_string(),
+                                    vec![
+                                        "${capture}.js".to_string(),
+                                        "${capture}.d.ts".to_string(),
+                                    ],
+                                ),
+                                ("Cargo.toml".to_string(), vec!["Cargo.lock".to_string()]),
+                            ]
+                            .into_iter()
+                            .collect(),
+                        ),
+                    });
+            });
+        })
+    });
+
+    let fs = FakeFs::new(cx.executor());
+    fs.insert_tree(
+        "/root",
+        json!({
+            "src": {
+                "main.d.ts": "",
+                "main.js": "",
+                "main.ts": "",
+                "util.ts": "",
+            },
+            "Cargo.lock": "",
+            "Cargo.toml": "",
+        }),
+    )
+    .await;
+
+    let project = Project::test(fs.clone(), ["/root".as_ref()], cx).await;
+    let window = cx.add_window(|window, cx| MultiWorkspace::test_new(project.clone(), window, cx));
+    let workspace = window
+        .read_with(cx, |mw, _| mw.workspace().clone())
+        .unwrap();
+    let cx = &mut VisualTestContext::from_window(window.into(), cx);
+    let panel = workspace.update_in(cx, ProjectPanel::new);
+    cx.run_until_parked();
+
+    assert_eq!(
+        visible_entries_as_strings(&panel, 0..20, cx),
+        &["v root", "    > src", "    > Cargo.toml"],
+        "Nested files should be hidden under their collapsed parent"
+    );
+
+    toggle_expand_dir(&panel, "root/Cargo.toml", cx);
+    assert_eq!(
+        visible_entries_as_strings(&panel, 0..20, cx),
+        &[
+            "v root",
+            "    > src",
+            "    v Cargo.toml",
+            "          Cargo.lock",
+        ],
+        "Expanding a parent file should show the files nested under it"
+    );
+
+    let main_js = find_project_entry(&panel, "root/src/main.js", cx).unwrap();
+    panel.update(cx, |panel, cx| {
+        panel.project.update(cx, |_, cx| {
+            cx.emit(project::Event::RevealInProjectPanel(main_js))
+        })
+    });
+    cx.run_until_parked();
+    assert_eq!(
+        visible_entries_as_strings(&panel, 0..20, cx),
+        &[
+            "v root",
+            "    v src",
+            "        v main.ts",
+            "              main.d.ts",
+            "              main.js  <== selected  <== marked",
+            "          util.ts",
+            "    v Cargo.toml",
+            "          Cargo.lock",
+        ],
+        "Revealing a nested file should expand the file it's nested under"
+    );
+
+    panel.update_in(cx, |panel, window, cx| {
+        panel.collapse_selected_entry(&CollapseSelectedEntry, window, cx)
+    });
+    cx.run_until_parked();
+    assert_eq!(
+        visible_entries_as_strings(&panel, 0..20, cx),
+        &[
+            "v root",
+            "    v src",
+            "        > main.ts  <== selected",
+            "          util.ts",
+            "    v Cargo.toml",
+            "          Cargo.lock",
+        ],
+        "Collapsing a nested file should collapse the file it's nested under"
+    );
+}
+
 #[gpui::test]
 async fn test_expand_all_for_entry(cx: &mut gpui::TestAppContext) {
     init_test_with_editor(cx);
@@ -9732,7 +9843,7 @@ fn visible_entries_as_strings(
             }
 
             let indent = "    ".repeat(details.depth);
-            let icon = if details.kind.is_dir() {
+            let icon = if details.kind.is_dir() || details.is_nest_parent {
                 if details.is_expanded { "v " } else { "> " }
             } else {
                 "  "
@@ -256,6 +256,19 @@ messages!(
     (SyncExtensionsResponse, Background),
     (BreakpointsForFile, Background),
     (ToggleBreakpoint, Foreground),
+    (BookmarksForFile, Background),
+    (EditBookmark, Foreground),
+    (UpdateListeningPorts, Background),
+    (UpdateSharedTerminal, Background),
+    (UnshareTerminal, Foreground),
+    (SharedTerminalInput, Foreground),
+    (CreateReviewThread, Foreground),
+    (ReplyToReviewThread, Foreground),
+    (ResolveReviewThread, Foreground),
+    (ReviewThreadResponse, Foreground),
+    (UpdateReviewThread, Foreground),
+    (GetReviewThreads, Foreground),
+    (GetReviewThreadsResponse, Foreground),
     (SynchronizeBuffers, Foreground),
     (SynchronizeBuffersResponse, Foreground),
     (SynchronizeContexts, Foreground),
@@ -543,6 +556,12 @@ request_messages!(
     (GitDiff, GitDiffResponse),
     (GitInit, Ack),
     (ToggleBreakpoint, Ack),
+    (EditBookmark, Ack),
+    (SharedTerminalInput, Ack),
+    (CreateReviewThread, ReviewThreadResponse),
+    (ReplyToReviewThread, ReviewThreadResponse),
+    (ResolveReviewThread, ReviewThreadResponse),
+    (GetReviewThreads, GetReviewThreadsResponse),
     (GetDebugAdapterBinary, DebugAdapterBinary),
     (RunDebugLocators, DebugRequest),
     (GetDocumentDiagnostics, GetDocumentDiagnosticsResponse),
@@ -734,6 +753,13 @@ entity_messages!(
     GitInit,
     BreakpointsForFile,
     ToggleBreakpoint,
+    BookmarksForFile,
+    EditBookmark,
+    UpdateListeningPorts,
+    UpdateSharedTerminal,
+    UnshareTerminal,
+    SharedTerminalInput,
+    UpdateReviewThread,
     RunDebugLocators,
     GetDebugAdapterBinary,
     LogToDebugConsole,
@@ -0,0 +1,133 @@
+use crate::{CommandTemplate, RemoteConnection};
+use anyhow::{Context as _, Result};
+use futures::{AsyncWriteExt as _, future, io};
+use gpui::{App, BackgroundExecutor, Task};
+use smol::net::{TcpListener, TcpStream};
+use std::{net::Ipv4Addr, process::Stdio, sync::Arc};
+use util::command::{Child, new_command};
+
+/// A port on the remote host that can be reached on the local machine.
+///
+/// The port stops being forwarded when this is dropped.
+pub struct ForwardedPort {
+    pub local_port: u16,
+    _tunnel: Tunnel,
+}
+
+enum Tunnel {
+    /// The remote host shares the local network interface, so there's nothing to forward.
+    SharedNetwork,
+    /// A long-running process, such as `ssh -N -L`, that forwards the port.
+    Process { _child: Child },
+    /// A local listener that runs the connection's connect-port command for
+    /// each incoming connection.
+    Listener { _task: Task<()> },
+}
+
+pub(crate) fn forward_port(
+    connection: Arc<dyn RemoteConnection>,
+    remote_port: u16,
+    cx: &App,
+) -> Task<Result<ForwardedPort>> {
+    if connection.shares_network_interface() {
+        return Task::ready(Ok(ForwardedPort {
+            local_port: remote_port,
+            _tunnel: Tunnel::SharedNetwork,
+        }));
+    }
+
+    let executor = cx.background_executor().clone();
+    match connection.build_connect_port_command(remote_port) {
+        Ok(command) => cx.background_spawn(async move {
+            let listener = bind_local_listener(remote_port).await?;
+            let local_port = listener.local_addr()?.port();
+            let task = executor.spawn(accept_connections(listener, command, executor.clone()));
+            Ok(ForwardedPort {
+                local_port,
+                _tunnel: Tunnel::Listener { _task: task },
+            })
+        }),
+        Err(_) => cx.background_spawn(async move {
+            let local_port = bind_local_listener(remote_port).await?.local_addr()?.port();
+            let command = connection.build_forward_ports_command(vec![(
+                local_port,
+                "localhost".to_owned(),
+                remote_port,
+            )])?;
+            let child = new_command(command.program)
+                .args(command.args)
+                .envs(command.env)
+                .stdin(Stdio::null())
+                .kill_on_drop(true)
+                .spawn()
+                .context("spawning port forwarding process")?;
+            Ok(ForwardedPort {
+                local_port,
+                _tunnel: Tunnel::Process { _child: child },
+            })
+        }),
+    }
+}
+
+/// Binds a listener to the same port as on the remote host when it's free, so
+/// that URLs printed by remote processes keep working, and to any port otherwise.
+async fn bind_local_listener(preferred_port: u16) -> Result<TcpListener> {
+    if let Ok(listener) = TcpListener::bind((Ipv4Addr::LOCALHOST, preferred_port)).await {
+        return Ok(listener);
+    }
+    TcpListener::bind((Ipv4Addr::LOCALHOST, 0))
+        .await
+        .context("binding local port")
+}
+
+async fn accept_connections(
+    listener: TcpListener,
+    command: CommandTemplate,
+    executor: BackgroundExecutor,
+) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(error) => {
+                log::error!("failed to accept forwarded connection: {error}");
+                break;
+            }
+        };
+        let command = command.clone();
+        executor
+            .spawn(async move {
+                if let Err(error) = tunnel_connection(stream, command).await {
+                    log::debug!("forwarded connection failed: {error:#}");
+                }
+            })
+            .detach();
+    }
+}
+
+async fn tunnel_connection(stream: TcpStream, command: CommandTemplate) -> Result<()> {
+    let mut child = new_command(command.program)
+        .args(command.args)
+        .envs(command.env)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .context("spawning port connection process")?;
+    let mut stdin = child.stdin.take().context("missing stdin")?;
+    let mut stdout = child.stdout.take().context("missing stdout")?;
+
+    let mut reader = stream.clone();
+    let mut writer = stream;
+    let upload = async {
+        io::copy(&mut reader, &mut stdin).await.ok();
+        stdin.close().await.ok();
+    };
+    let download = async {
+        io::copy(&mut stdout, &mut writer).await.ok();
+        writer.close().await.ok();
+    };
+    future::join(upload, download).await;
+    child.status().await?;
+    Ok(())
+}
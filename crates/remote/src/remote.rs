@@ -1,9 +1,11 @@
 pub mod json_log;
+mod port_forwarding;
 pub mod protocol;
 pub mod proxy;
 pub mod remote_client;
 mod transport;
 
+pub use port_forwarding::ForwardedPort;
 #[cfg(target_os = "windows")]
 pub use remote_client::OpenWslPath;
 pub use remote_client::{
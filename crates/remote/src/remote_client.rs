@@ -2,6 +2,7 @@
 use crate::transport::mock::ConnectGuard;
 use crate::{
     SshConnectionOptions,
+    port_forwarding::{self, ForwardedPort},
     protocol::MessageId,
     proxy::ProxyLaunchError,
     transport::{
@@ -951,6 +952,15 @@ impl RemoteClient {
         connection.build_forward_ports_command(forwards)
     }
 
+    /// Makes a port on the remote host reachable on the local machine, for as
+    /// long as the returned [`ForwardedPort`] is kept alive.
+    pub fn forward_port(&self, remote_port: u16, cx: &App) -> Task<Result<ForwardedPort>> {
+        let Some(connection) = self.remote_connection() else {
+            return Task::ready(Err(anyhow!("no remote connection")));
+        };
+        port_forwarding::forward_port(connection, remote_port, cx)
+    }
+
     pub fn upload_directory(
         &self,
         src_path: PathBuf,
@@ -1364,6 +1374,13 @@ pub trait RemoteConnection: Send + Sync {
         &self,
         forwards: Vec<(u16, String, u16)>,
     ) -> Result<CommandTemplate>;
+    /// Builds a command whose stdin and stdout are connected to a TCP port on
+    /// the remote host, for connections that can't forward ports on their own.
+    fn build_connect_port_command(&self, _port: u16) -> Result<CommandTemplate> {
+        Err(anyhow!(
+            "connecting to ports isn't supported for this connection"
+        ))
+    }
     fn connection_options(&self) -> RemoteConnectionOptions;
     fn path_style(&self) -> PathStyle;
     fn shell(&self) -> String;
@@ -796,6 +796,31 @@ impl RemoteConnection for DockerExecConnection {
         Err(anyhow::anyhow!("Not currently supported for docker_exec"))
     }
 
+    fn build_connect_port_command(&self, port: u16) -> Result<CommandTemplate> {
+        let Some(remote_binary_relpath) = self.remote_binary_relpath.as_ref() else {
+            return Err(anyhow!("Remote binary path not set"));
+        };
+        Ok(CommandTemplate {
+            program: self.docker_cli().to_string(),
+            args: vec![
+                "exec".to_string(),
+                "-u".to_string(),
+                self.connection_options.remote_user.to_string(),
+                "-w".to_string(),
+                self.remote_dir_for_server.clone(),
+                "-i".to_string(),
+                self.connection_options.container_id.to_string(),
+                remote_binary_relpath
+                    .display(self.path_style())
+                    .into_owned(),
+                "connect-port".to_string(),
+                "--port".to_string(),
+                port.to_string(),
+            ],
+            env: Default::default(),
+        })
+    }
+
     fn connection_options(&self) -> RemoteConnectionOptions {
         RemoteConnectionOptions::Docker(self.connection_options.clone())
     }
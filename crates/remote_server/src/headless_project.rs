@@ -16,12 +16,14 @@ use project::{
     AgentRegistryStore, LspStore, LspStoreEvent, ManifestTree, PrettierStore, ProjectEnvironment,
     ProjectPath, ToolchainStore, WorktreeId,
     agent_server_store::AgentServerStore,
+    bookmark_store::BookmarkStore,
     buffer_store::{BufferStore, BufferStoreEvent},
     context_server_store::ContextServerStore,
     debugger::{breakpoint_store::BreakpointStore, dap_store::DapStore},
     git_store::GitStore,
     image_store::ImageId,
     lsp_store::log_store::{self, GlobalLogStore, LanguageServerKind, LogKind},
+    port_store::PortStore,
     project_settings::SettingsObserver,
     search::SearchQuery,
     task_store::TaskStore,
@@ -57,6 +59,8 @@ pub struct HeadlessProject {
     pub task_store: Entity<TaskStore>,
     pub dap_store: Entity<DapStore>,
     pub breakpoint_store: Entity<BreakpointStore>,
+    pub bookmark_store: Entity<BookmarkStore>,
+    pub port_store: Entity<PortStore>,
     pub agent_server_store: Entity<AgentServerStore>,
     pub context_server_store: Entity<ContextServerStore>,
     pub settings_observer: Entity<SettingsObserver>,
@@ -148,6 +152,21 @@ impl HeadlessProject {
             breakpoint_store
         });
 
+        let bookmark_store = cx.new(|_| {
+            let mut bookmark_store =
+                BookmarkStore::local(worktree_store.clone(), buffer_store.clone());
+            bookmark_store.shared(REMOTE_SERVER_PROJECT_ID, session.clone());
+
+            bookmark_store
+        });
+
+        let port_store = cx.new(|cx| {
+            let mut port_store = PortStore::local(fs.clone(), worktree_store.clone());
+            port_store.shared(REMOTE_SERVER_PROJECT_ID, session.clone(), cx);
+
+            port_store
+        });
+
         let dap_store = cx.new(|cx| {
             let mut dap_store = DapStore::new_local(
                 http_client.clone(),
@@ -283,6 +302,7 @@ impl HeadlessProject {
         session.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &toolchain_store);
         session.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &dap_store);
         session.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &breakpoint_store);
+        session.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &bookmark_store);
         session.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &settings_observer);
         session.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &git_store);
         session.subscribe_to_entity(REMOTE_SERVER_PROJECT_ID, &agent_server_store);
@@ -334,6 +354,7 @@ impl HeadlessProject {
         DapStore::init(&session, cx);
         // todo(debugger): Re init breakpoint store when we set it up for collab
         BreakpointStore::init(&session);
+        BookmarkStore::init(&session);
         GitStore::init(&session);
         AgentServerStore::init_headless(&session);
         ContextServerStore::init_headless(&session);
@@ -349,6 +370,8 @@ impl HeadlessProject {
             task_store,
             dap_store,
             breakpoint_store,
+            bookmark_store,
+            port_store,
             agent_server_store,
             context_server_store,
             languages,
@@ -1050,6 +1073,7 @@ impl HeadlessProject {
             let results = this.update(cx, |this, cx| {
                 project::Search::local(
                     this.fs.clone(),
+                    this.languages.clone(),
                     this.buffer_store.clone(),
                     this.worktree_store.clone(),
                     message.limit as _,
@@ -56,7 +56,7 @@ fn main() -> anyhow::Result<()> {
         res
     } else {
         std::io::stderr()
-            .write_all(b"usage: remote <run|proxy|version>\n")
+            .write_all(b"usage: remote <run|proxy|connect-port|version>\n")
             .ok();
         std::process::exit(1);
     }
@@ -25,6 +25,7 @@ use node_runtime::NodeRuntime;
 use project::{
     ProgressToken, Project,
     agent_server_store::AgentServerCommand,
+    port_store::PortForwardSource,
     search::{SearchQuery, SearchResult},
 };
 use remote::RemoteClient;
@@ -34,6 +35,7 @@ use smol::stream::StreamExt;
 use std::{
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 use unindent::Unindent as _;
 use util::{path, paths::PathMatcher, rel_path::rel_path};
@@ -2049,6 +2051,135 @@ async fn test_remote_external_agent_server(
     );
 }
 
+// The remote server detects listening ports through `/proc/net/tcp`, which only exists on Linux.
+#[cfg(not(windows))]
+#[gpui::test]
+async fn test_remote_port_forwarding(cx: &mut TestAppContext, server_cx: &mut TestAppContext) {
+    const TCP_HEADER: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n";
+    // 127.0.0.1:8080 listening, an established connection from it, and 172.17.0.2:80 listening.
+    let tcp_with_8080 = format!(
+        "{TCP_HEADER}\
+         0: 0100007F:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000 0 1 1\n\
+         1: 0100007F:1F90 0100007F:C350 01 00000000:00000000 00:00000000 00000000  1000 0 2 1\n\
+         2: 020011AC:0050 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000 0 3 1\n"
+    );
+    let tcp_without_8080 = format!(
+        "{TCP_HEADER}\
+         0: 020011AC:0050 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000 0 3 1\n"
+    );
+
+    let fs = FakeFs::new(server_cx.executor());
+    fs.insert_tree(
+        "/code",
+        json!({
+            "project1": {
+                ".devcontainer": {
+                    "devcontainer.json": r#"{
+                        // Forwarded even when nothing listens on them.
+                        "forwardPorts": [3000, "localhost:5432", "db:6379"],
+                        "portsAttributes": { "3000": { "label": "App" } },
+                    }"#,
+                },
+            },
+        }),
+    )
+    .await;
+    fs.insert_tree(
+        "/proc/net",
+        json!({
+            "tcp": tcp_with_8080.clone(),
+            // [::]:3000 listening.
+            "tcp6": format!(
+                "{TCP_HEADER}   0: 00000000000000000000000000000000:0BB8 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000 0 4 1\n"
+            ),
+        }),
+    )
+    .await;
+
+    let (project, _headless) = init_test(&fs, cx, server_cx).await;
+    project
+        .update(cx, |project, cx| {
+            project.find_or_create_worktree("/code/project1", true, cx)
+        })
+        .await
+        .unwrap();
+    cx.executor().advance_clock(Duration::from_secs(2));
+    cx.run_until_parked();
+
+    let port_store = project.read_with(cx, |project, _| project.port_store());
+    let forwards = |cx: &mut TestAppContext| {
+        port_store.read_with(cx, |port_store, _| {
+            port_store
+                .forwards()
+                .map(|forward| (forward.remote_port, forward.source, forward.label.clone()))
+                .collect::<Vec<_>>()
+        })
+    };
+    port_store.read_with(cx, |port_store, _| {
+        assert_eq!(
+            port_store
+                .listening_ports()
+                .iter()
+                .copied()
+                .collect::<Vec<_>>(),
+            [3000, 8080]
+        );
+    });
+    assert_eq!(
+        forwards(cx),
+        [
+            (
+                3000,
+                PortForwardSource::Configured,
+                Some(SharedString::from("App"))
+            ),
+            (5432, PortForwardSource::Configured, None),
+            (8080, PortForwardSource::Detected, None),
+        ]
+    );
+
+    // A stopped port isn't forwarded again while it's still listened on.
+    port_store.update(cx, |port_store, cx| port_store.stop_forwarding(8080, cx));
+    cx.executor().advance_clock(Duration::from_secs(2));
+    cx.run_until_parked();
+    assert_eq!(
+        forwards(cx)
+            .iter()
+            .map(|(port, _, _)| *port)
+            .collect::<Vec<_>>(),
+        [3000, 5432]
+    );
+
+    // Once it's listened on anew, it's forwarded again.
+    fs.insert_file("/proc/net/tcp", tcp_without_8080.into_bytes())
+        .await;
+    cx.executor().advance_clock(Duration::from_secs(2));
+    cx.run_until_parked();
+    fs.insert_file("/proc/net/tcp", tcp_with_8080.into_bytes())
+        .await;
+    cx.executor().advance_clock(Duration::from_secs(2));
+    cx.run_until_parked();
+    assert_eq!(
+        forwards(cx)
+            .iter()
+            .map(|(port, _, _)| *port)
+            .collect::<Vec<_>>(),
+        [3000, 5432, 8080]
+    );
+
+    // Stopping a configured port keeps it stopped.
+    port_store.update(cx, |port_store, cx| port_store.stop_forwarding(5432, cx));
+    cx.executor().advance_clock(Duration::from_secs(2));
+    cx.run_until_parked();
+    assert_eq!(
+        forwards(cx)
+            .iter()
+            .map(|(port, _, _)| *port)
+            .collect::<Vec<_>>(),
+        [3000, 8080]
+    );
+}
+
 pub async fn init_test(
     server_fs: &Arc<FakeFs>,
     cx: &mut TestAppContext,
@@ -79,6 +79,12 @@ pub enum Commands {
         #[arg(long)]
         identifier: String,
     },
+    /// Connects stdin and stdout to a TCP port on this host, so that the client
+    /// can forward ports over connections that can't do so on their own.
+    ConnectPort {
+        #[arg(long)]
+        port: u16,
+    },
     Version,
 }
 
@@ -104,6 +110,9 @@ pub fn run(command: Commands) -> anyhow::Result<()> {
             identifier,
             reconnect,
         } => execute_proxy(identifier, reconnect).context("running proxy on the remote server"),
+        Commands::ConnectPort { port } => {
+            execute_connect_port(port).with_context(|| format!("connecting to port {port}"))
+        }
         Commands::Version => {
             let release_channel = *RELEASE_CHANNEL;
             match release_channel {
@@ -846,6 +855,24 @@ pub(crate) fn execute_proxy(
     Ok(())
 }
 
+fn execute_connect_port(port: u16) -> Result<()> {
+    // Processes may listen only on the IPv6 loopback address, which
+    // `/proc/net/tcp6` reports alongside the IPv4 ones.
+    let stream = std::net::TcpStream::connect((std::net::Ipv4Addr::LOCALHOST, port))
+        .or_else(|_| std::net::TcpStream::connect((std::net::Ipv6Addr::LOCALHOST, port)))?;
+    let mut reader = stream.try_clone()?;
+    let mut writer = stream;
+
+    // Not joined, since stdin stays open after the remote end closes the connection.
+    std::thread::spawn(move || {
+        std::io::copy(&mut std::io::stdin().lock(), &mut writer).ok();
+        writer.shutdown(std::net::Shutdown::Write).ok();
+    });
+    std::io::copy(&mut reader, &mut std::io::stdout().lock())?;
+    std::io::stdout().flush()?;
+    Ok(())
+}
+
 fn kill_running_server(pid: u32, paths: &ServerPaths) -> Result<(), ExecuteProxyError> {
     log::info!("killing existing server with PID {}", pid);
     let system = sysinfo::System::new_with_specifics(
@@ -0,0 +1,447 @@
+mod review_threads_panel;
+mod review_threads_panel_settings;
+
+use client::UserStore;
+use collections::{HashMap, HashSet};
+use editor::{
+    Editor, EditorEvent,
+    display_map::{BlockContext, BlockPlacement, BlockProperties, BlockStyle, CustomBlockId},
+};
+use gpui::{
+    Action, AnyElement, App, Context, DismissEvent, Empty, Entity, EventEmitter, FocusHandle,
+    Focusable, Render, Subscription, Window, actions, rems,
+};
+use language::{Anchor, Buffer, Point};
+use project::review_thread_store::{ReviewComment, ReviewThreadStore};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use std::{ops::Range, sync::Arc};
+use time::{OffsetDateTime, UtcOffset};
+use ui::{Avatar, prelude::*};
+use workspace::{ModalView, Workspace};
+
+pub use review_threads_panel::ReviewThreadsPanel;
+pub use review_threads_panel_settings::ReviewThreadsPanelSettings;
+
+actions!(
+    review_threads,
+    [
+        /// Toggles focus on the review threads panel.
+        ToggleFocus,
+        /// Starts a review thread on the selected lines.
+        StartThread,
+    ]
+);
+
+/// Replies to a review thread.
+#[derive(PartialEq, Clone, Deserialize, JsonSchema, Action)]
+#[action(namespace = review_threads)]
+#[serde(deny_unknown_fields)]
+pub struct ReplyToThread {
+    pub thread_id: u64,
+}
+
+pub fn init(cx: &mut App) {
+    cx.observe_new(|workspace: &mut Workspace, _, _| {
+        workspace
+            .register_action(|workspace, _: &ToggleFocus, window, cx| {
+                workspace.toggle_panel_focus::<ReviewThreadsPanel>(window, cx);
+            })
+            .register_action(start_thread)
+            .register_action(|workspace, action: &ReplyToThread, window, cx| {
+                let store = workspace.project().read(cx).review_thread_store();
+                let target = CommentTarget::Reply(action.thread_id);
+                workspace.toggle_modal(window, cx, move |window, cx| {
+                    CommentModal::new(store, target, window, cx)
+                });
+            });
+    })
+    .detach();
+
+    cx.observe_new(|editor: &mut Editor, _, cx| register_editor(editor, cx))
+        .detach();
+}
+
+/// Shows the unresolved review threads of an editor's buffers below the code they're on.
+struct ThreadBlocks {
+    store: Entity<ReviewThreadStore>,
+    user_store: Entity<UserStore>,
+    blocks: HashMap<u64, CustomBlockId>,
+    _subscriptions: [Subscription; 2],
+}
+
+impl editor::Addon for ThreadBlocks {
+    fn to_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
+    fn to_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(self)
+    }
+}
+
+fn register_editor(editor: &mut Editor, cx: &mut Context<Editor>) {
+    if !editor.mode().is_full() {
+        return;
+    }
+    let Some(project) = editor.project() else {
+        return;
+    };
+    let store = project.read(cx).review_thread_store();
+    let user_store = project.read(cx).user_store();
+
+    let subscriptions = [
+        cx.observe(&store, |editor, _, cx| sync_thread_blocks(editor, cx)),
+        cx.subscribe(&cx.entity(), |editor, _, event: &EditorEvent, cx| {
+            if let EditorEvent::ExcerptsAdded { .. } = event {
+                sync_thread_blocks(editor, cx);
+            }
+        }),
+    ];
+    editor.register_addon(ThreadBlocks {
+        store,
+        user_store,
+        blocks: HashMap::default(),
+        _subscriptions: subscriptions,
+    });
+    sync_thread_blocks(editor, cx);
+}
+
+fn sync_thread_blocks(editor: &mut Editor, cx: &mut Context<Editor>) {
+    let Some(addon) = editor.addon::<ThreadBlocks>() else {
+        return;
+    };
+    let store = addon.store.clone();
+    let user_store = addon.user_store.clone();
+
+    let multibuffer = editor.buffer().read(cx);
+    let mut positions = HashMap::default();
+    for thread in store.read(cx).threads() {
+        if thread.resolved {
+            continue;
+        }
+        let Some(buffer) = multibuffer.buffer(thread.buffer_id) else {
+            continue;
+        };
+        if !buffer.read(cx).can_resolve(&thread.range.end) {
+            continue;
+        }
+        if let Some(anchor) = multibuffer.buffer_anchor_to_anchor(&buffer, thread.range.end, cx) {
+            positions.insert(thread.id, (buffer, anchor));
+        }
+    }
+
+    let addon = editor.addon_mut::<ThreadBlocks>().unwrap();
+    let mut removed_blocks = HashSet::default();
+    addon.blocks.retain(|thread_id, block_id| {
+        if positions.remove(thread_id).is_some() {
+            true
+        } else {
+            removed_blocks.insert(*block_id);
+            false
+        }
+    });
+    if !removed_blocks.is_empty() {
+        editor.remove_blocks(removed_blocks, None, cx);
+    }
+    if positions.is_empty() {
+        return;
+    }
+
+    let (thread_ids, blocks): (Vec<_>, Vec<_>) = positions
+        .into_iter()
+        .map(|(thread_id, (buffer, anchor))| {
+            let store = store.clone();
+            let user_store = user_store.clone();
+            let block = BlockProperties {
+                placement: BlockPlacement::Below(anchor),
+                height: Some(1),
+                style: BlockStyle::Flex,
+                render: Arc::new(move |cx| {
+                    render_thread_block(thread_id, &store, &user_store, &buffer, cx)
+                }),
+                priority: 0,
+            };
+            (thread_id, block)
+        })
+        .unzip();
+    let block_ids = editor.insert_blocks(blocks, None, cx);
+    editor
+        .addon_mut::<ThreadBlocks>()
+        .unwrap()
+        .blocks
+        .extend(thread_ids.into_iter().zip(block_ids));
+}
+
+fn render_thread_block(
+    thread_id: u64,
+    store: &Entity<ReviewThreadStore>,
+    user_store: &Entity<UserStore>,
+    buffer: &Entity<Buffer>,
+    cx: &mut BlockContext,
+) -> AnyElement {
+    let store_state = store.read(cx);
+    let Some(thread) = store_state.thread(thread_id) else {
+        return Empty.into_any_element();
+    };
+    let is_outdated = thread.is_outdated(buffer.read(cx));
+    let is_available = store_state.is_available();
+    let comments = thread
+        .comments
+        .iter()
+        .map(|comment| render_comment(comment, user_store, cx).into_any_element())
+        .collect::<Vec<_>>();
+
+    h_flex()
+        .id(cx.block_id)
+        .w_full()
+        .pl(cx.margins.gutter.full_width())
+        .pr_4()
+        .py_1()
+        .child(
+            v_flex()
+                .w_full()
+                .max_w(rems(40.))
+                .p_2()
+                .gap_2()
+                .rounded_md()
+                .border_1()
+                .border_color(cx.theme().colors().border)
+                .bg(cx.theme().colors().editor_subheader_background)
+                .children(comments)
+                .child(
+                    h_flex()
+                        .gap_1()
+                        .when(is_outdated, |this| {
+                            this.child(
+                                Label::new("Outdated")
+                                    .size(LabelSize::Small)
+                                    .color(Color::Warning),
+                            )
+                        })
+                        .child(div().flex_1())
+                        .child(
+                            Button::new("reply", "Reply")
+                                .label_size(LabelSize::Small)
+                                .disabled(!is_available)
+                                .on_click(move |_, window, cx| {
+                                    window.dispatch_action(
+                                        ReplyToThread { thread_id }.boxed_clone(),
+                                        cx,
+                                    )
+                                }),
+                        )
+                        .child(
+                            Button::new("resolve", "Resolve")
+                                .label_size(LabelSize::Small)
+                                .disabled(!is_available)
+                                .on_click({
+                                    let store = store.clone();
+                                    move |_, _, cx| {
+                                        store
+                                            .update(cx, |store, cx| {
+                                                store.set_resolved(thread_id, true, cx)
+                                            })
+                                            .detach_and_log_err(cx)
+                                    }
+                                }),
+                        ),
+                ),
+        )
+        .into_any_element()
+}
+
+pub(crate) fn render_comment(
+    comment: &ReviewComment,
+    user_store: &Entity<UserStore>,
+    cx: &App,
+) -> impl IntoElement + use<> {
+    let user = user_store.read(cx).get_cached_user(comment.user_id);
+    let timestamp = OffsetDateTime::from_unix_timestamp(comment.timestamp as i64)
+        .unwrap_or(OffsetDateTime::UNIX_EPOCH);
+    let timestamp = time_format::format_localized_timestamp(
+        timestamp,
+        OffsetDateTime::now_utc(),
+        UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC),
+        time_format::TimestampFormat::Relative,
+    );
+
+    v_flex()
+        .gap_0p5()
+        .child(
+            h_flex()
+                .gap_1()
+                .children(
+                    user.as_ref()
+                        .map(|user| Avatar::new(user.avatar_uri.clone())),
+                )
+                .child(
+                    Label::new(
+                        user.map_or_else(
+                            || "Unknown user".into(),
+                            |user| user.github_login.clone(),
+                        ),
+                    )
+                    .size(LabelSize::Small),
+                )
+                .child(
+                    Label::new(timestamp)
+                        .size(LabelSize::Small)
+                        .color(Color::Muted),
+                ),
+        )
+        .child(Label::new(comment.body.clone()))
+}
+
+fn start_thread(
+    workspace: &mut Workspace,
+    _: &StartThread,
+    window: &mut Window,
+    cx: &mut Context<Workspace>,
+) {
+    let store = workspace.project().read(cx).review_thread_store();
+    if !store.read(cx).is_available() {
+        workspace.show_error(
+            &anyhow::anyhow!("review threads can only be started in shared projects"),
+            cx,
+        );
+        return;
+    }
+    let Some(editor) = workspace.active_item_as::<Editor>(cx) else {
+        return;
+    };
+    let Some((buffer, range)) = editor.update(cx, |editor, cx| selected_lines(editor, cx)) else {
+        return;
+    };
+    workspace.toggle_modal(window, cx, move |window, cx| {
+        CommentModal::new(
+            store,
+            CommentTarget::NewThread { buffer, range },
+            window,
+            cx,
+        )
+    });
+}
+
+/// Returns the whole lines covered by the newest selection, in the buffer it starts in.
+fn selected_lines(
+    editor: &mut Editor,
+    cx: &mut Context<Editor>,
+) -> Option<(Entity<Buffer>, Range<Anchor>)> {
+    let selection = editor
+        .selections
+        .newest::<Point>(&editor.display_snapshot(cx));
+    let multibuffer = editor.buffer().read(cx);
+    let (buffer, start, _) = multibuffer.point_to_buffer_point(selection.start, cx)?;
+    let mut end = multibuffer
+        .point_to_buffer_point(selection.end, cx)
+        .filter(|(end_buffer, _, _)| *end_buffer == buffer)
+        .map_or(start, |(_, end, _)| end);
+    // A selection ending at the start of a line doesn't include that line.
+    if end.row > start.row && end.column == 0 {
+        end.row -= 1;
+    }
+
+    let snapshot = buffer.read(cx);
+    let range = snapshot.anchor_before(Point::new(start.row, 0))
+        ..snapshot.anchor_after(Point::new(end.row, snapshot.line_len(end.row)));
+    Some((buffer, range))
+}
+
+enum CommentTarget {
+    NewThread {
+        buffer: Entity<Buffer>,
+        range: Range<Anchor>,
+    },
+    Reply(u64),
+}
+
+struct CommentModal {
+    store: Entity<ReviewThreadStore>,
+    target: CommentTarget,
+    editor: Entity<Editor>,
+}
+
+impl ModalView for CommentModal {}
+
+impl EventEmitter<DismissEvent> for CommentModal {}
+
+impl Focusable for CommentModal {
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.editor.focus_handle(cx)
+    }
+}
+
+impl CommentModal {
+    fn new(
+        store: Entity<ReviewThreadStore>,
+        target: CommentTarget,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let editor = cx.new(|cx| {
+            let mut editor = Editor::single_line(window, cx);
+            editor.set_placeholder_text("Leave a comment", window, cx);
+            editor
+        });
+        Self {
+            store,
+            target,
+            editor,
+        }
+    }
+
+    fn cancel(&mut self, _: &menu::Cancel, _: &mut Window, cx: &mut Context<Self>) {
+        cx.emit(DismissEvent);
+    }
+
+    fn confirm(&mut self, _: &menu::Confirm, _: &mut Window, cx: &mut Context<Self>) {
+        let body = self.editor.read(cx).text(cx).trim().to_string();
+        if body.is_empty() {
+            return;
+        }
+        match &self.target {
+            CommentTarget::NewThread { buffer, range } => self
+                .store
+                .update(cx, |store, cx| {
+                    store.create_thread(buffer, range.clone(), body, cx)
+                })
+                .detach_and_log_err(cx),
+            CommentTarget::Reply(thread_id) => self
+                .store
+                .update(cx, |store, cx| store.reply(*thread_id, body, cx))
+                .detach_and_log_err(cx),
+        }
+        cx.emit(DismissEvent);
+    }
+}
+
+impl Render for CommentModal {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let hint = match self.target {
+            CommentTarget::NewThread { .. } => "Start a review thread on the selected lines",
+            CommentTarget::Reply(_) => "Reply to the review thread",
+        };
+
+        v_flex()
+            .w(rems(34.))
+            .elevation_2(cx)
+            .key_context("ReviewComment")
+            .on_action(cx.listener(Self::cancel))
+            .on_action(cx.listener(Self::confirm))
+            .child(
+                div()
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border_variant)
+                    .px_2()
+                    .py_1()
+                    .child(self.editor.clone()),
+            )
+            .child(
+                h_flex()
+                    .px_2()
+                    .py_1()
+                    .child(Label::new(hint).size(LabelSize::Small).color(Color::Muted)),
+            )
+    }
+}
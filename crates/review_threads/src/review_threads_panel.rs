@@ -0,0 +1,358 @@
+use anyhow::Result;
+use client::UserStore;
+use editor::{Editor, SelectionEffects, scroll::Autoscroll};
+use gpui::{
+    Action, App, AsyncWindowContext, Context, Entity, EventEmitter, FocusHandle, Focusable, Pixels,
+    Render, Subscription, Task, WeakEntity, Window,
+};
+use language::ToPoint as _;
+use project::{
+    Fs, Project,
+    review_thread_store::{ReviewThread, ReviewThreadStore},
+};
+use settings::{Settings, SettingsStore};
+use std::sync::Arc;
+use ui::{ListItem, ListItemSpacing, Tab, Tooltip, prelude::*};
+use workspace::{
+    Workspace,
+    dock::{DockPosition, Panel, PanelEvent},
+};
+
+use crate::{ReplyToThread, ReviewThreadsPanelSettings, StartThread, ToggleFocus, render_comment};
+
+const REVIEW_THREADS_PANEL_KEY: &str = "ReviewThreadsPanel";
+
+pub struct ReviewThreadsPanel {
+    workspace: WeakEntity<Workspace>,
+    project: Entity<Project>,
+    store: Entity<ReviewThreadStore>,
+    user_store: Entity<UserStore>,
+    fs: Arc<dyn Fs>,
+    focus_handle: FocusHandle,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl ReviewThreadsPanel {
+    pub fn new(workspace: &mut Workspace, cx: &mut Context<Workspace>) -> Entity<Self> {
+        let fs = workspace.app_state().fs.clone();
+        let project = workspace.project().clone();
+        let store = project.read(cx).review_thread_store();
+        let user_store = project.read(cx).user_store();
+        let workspace_handle = workspace.weak_handle();
+
+        cx.new(|cx| Self {
+            _subscriptions: vec![
+                cx.observe(&store, |_, _, cx| cx.notify()),
+                cx.observe_global::<SettingsStore>(|_, cx| cx.notify()),
+            ],
+            workspace: workspace_handle,
+            project,
+            store,
+            user_store,
+            fs,
+            focus_handle: cx.focus_handle(),
+        })
+    }
+
+    pub fn load(
+        workspace: WeakEntity<Workspace>,
+        cx: AsyncWindowContext,
+    ) -> Task<Result<Entity<Self>>> {
+        cx.spawn(async move |cx| workspace.update(cx, |workspace, cx| Self::new(workspace, cx)))
+    }
+
+    fn open_thread(&self, thread: &ReviewThread, window: &mut Window, cx: &mut Context<Self>) {
+        let buffer_id = thread.buffer_id;
+        let position = thread.range.start;
+        let Some(open) = self
+            .workspace
+            .update(cx, |workspace, cx| {
+                workspace.open_path(thread.project_path.clone(), None, true, window, cx)
+            })
+            .ok()
+        else {
+            return;
+        };
+
+        cx.spawn_in(window, async move |_, cx| {
+            let item = open.await?;
+            let Some(editor) = item.downcast::<Editor>() else {
+                return Ok(());
+            };
+            editor.update_in(cx, |editor, window, cx| {
+                let multibuffer = editor.buffer().read(cx);
+                let Some(position) = multibuffer
+                    .buffer(buffer_id)
+                    .filter(|buffer| buffer.read(cx).can_resolve(&position))
+                    .and_then(|buffer| multibuffer.buffer_anchor_to_anchor(&buffer, position, cx))
+                else {
+                    return;
+                };
+                editor.change_selections(
+                    SelectionEffects::scroll(Autoscroll::center()),
+                    window,
+                    cx,
+                    |s| s.select_anchor_ranges([position..position]),
+                );
+            })
+        })
+        .detach_and_log_err(cx);
+    }
+
+    fn render_thread(&self, thread: &ReviewThread, cx: &mut Context<Self>) -> impl IntoElement {
+        let thread_id = thread.id;
+        let is_outdated = self
+            .project
+            .read(cx)
+            .buffer_for_id(thread.buffer_id, cx)
+            .is_some_and(|buffer| thread.is_outdated(buffer.read(cx)));
+        let (status, status_color) = if thread.resolved {
+            (Some("Resolved"), Color::Muted)
+        } else if is_outdated {
+            (Some("Outdated"), Color::Warning)
+        } else {
+            (None, Color::Default)
+        };
+        let line = self
+            .project
+            .read(cx)
+            .buffer_for_id(thread.buffer_id, cx)
+            .filter(|buffer| buffer.read(cx).can_resolve(&thread.range.start))
+            .map(|buffer| {
+                let buffer = buffer.read(cx);
+                thread.range.start.to_point(buffer).row + 1
+            });
+        let path = thread
+            .project_path
+            .path
+            .display(self.project.read(cx).path_style(cx))
+            .to_string();
+        let location = match line {
+            Some(line) => format!("{path}:{line}"),
+            None => path,
+        };
+        let is_available = self.store.read(cx).is_available();
+
+        ListItem::new(("review-thread", thread_id as usize))
+            .spacing(ListItemSpacing::Sparse)
+            .start_slot(
+                Icon::new(IconName::Chat)
+                    .size(IconSize::Small)
+                    .color(if thread.resolved {
+                        Color::Disabled
+                    } else {
+                        Color::Muted
+                    }),
+            )
+            .child(
+                v_flex()
+                    .gap_1()
+                    .child(
+                        h_flex()
+                            .gap_2()
+                            .child(Label::new(location).size(LabelSize::Small).truncate())
+                            .when_some(status, |this, status| {
+                                this.child(
+                                    Label::new(status)
+                                        .size(LabelSize::Small)
+                                        .color(status_color),
+                                )
+                            }),
+                    )
+                    .children(
+                        thread
+                            .comments
+                            .first()
+                            .map(|comment| render_comment(comment, &self.user_store, cx)),
+                    )
+                    .when(thread.comments.len() > 1, |this| {
+                        this.child(
+                            Label::new(match thread.comments.len() - 1 {
+                                1 => "1 reply".to_string(),
+                                replies => format!("{replies} replies"),
+                            })
+                            .size(LabelSize::Small)
+                            .color(Color::Muted),
+                        )
+                    }),
+            )
+            .on_click({
+                let thread = thread.clone();
+                cx.listener(move |this, _, window, cx| this.open_thread(&thread, window, cx))
+            })
+            .when(is_available, |this| {
+                this.end_hover_slot(
+                    h_flex()
+                        .gap_0p5()
+                        .child(
+                            IconButton::new(("reply-thread", thread_id as usize), IconName::Chat)
+                                .icon_size(IconSize::Small)
+                                .tooltip(Tooltip::text("Reply"))
+                                .on_click(move |_, window, cx| {
+                                    window.dispatch_action(
+                                        ReplyToThread { thread_id }.boxed_clone(),
+                                        cx,
+                                    )
+                                }),
+                        )
+                        .child({
+                            let resolved = thread.resolved;
+                            let (icon, tooltip) = if resolved {
+                                (IconName::RotateCcw, "Reopen")
+                            } else {
+                                (IconName::Check, "Resolve")
+                            };
+                            IconButton::new(("resolve-thread", thread_id as usize), icon)
+                                .icon_size(IconSize::Small)
+                                .tooltip(Tooltip::text(tooltip))
+                                .on_click({
+                                    let store = self.store.clone();
+                                    move |_, _, cx| {
+                                        store
+                                            .update(cx, |store, cx| {
+                                                store.set_resolved(thread_id, !resolved, cx)
+                                            })
+                                            .detach_and_log_err(cx)
+                                    }
+                                })
+                        }),
+                )
+            })
+    }
+}
+
+impl Render for ReviewThreadsPanel {
+    fn render(&mut self, _: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let threads = self.store.read(cx).threads().cloned().collect::<Vec<_>>();
+        // Open threads come first, in the order they were started.
+        let threads = threads
+            .iter()
+            .filter(|thread| !thread.resolved)
+            .chain(threads.iter().filter(|thread| thread.resolved))
+            .map(|thread| self.render_thread(thread, cx).into_any_element())
+            .collect::<Vec<_>>();
+        let is_available = self.store.read(cx).is_available();
+
+        v_flex()
+            .key_context("ReviewThreadsPanel")
+            .track_focus(&self.focus_handle)
+            .size_full()
+            .child(
+                h_flex()
+                    .justify_between()
+                    .px_2()
+                    .py_1()
+                    // Match the height of the tab bar so they line up.
+                    .h(Tab::container_height(cx))
+                    .border_b_1()
+                    .border_color(cx.theme().colors().border)
+                    .child(Label::new("Review Threads"))
+                    .child(
+                        IconButton::new("start-thread", IconName::Plus)
+                            .icon_size(IconSize::Small)
+                            .disabled(!is_available)
+                            .tooltip(Tooltip::for_action_title(
+                                "Start a Thread on the Selected Lines",
+                                &StartThread,
+                            ))
+                            .on_click(|_, window, cx| {
+                                window.dispatch_action(StartThread.boxed_clone(), cx)
+                            }),
+                    ),
+            )
+            .map(|this| {
+                if threads.is_empty() {
+                    this.child(
+                        v_flex()
+                            .gap_1()
+                            .p_4()
+                            .child(Label::new("No review threads"))
+                            .child(
+                                Label::new(
+                                    "Select some lines in a shared project and start a thread \
+                                     to comment on them.",
+                                )
+                                .size(LabelSize::Small)
+                                .color(Color::Muted),
+                            ),
+                    )
+                } else {
+                    this.child(
+                        v_flex()
+                            .id("review-threads")
+                            .p_1()
+                            .overflow_y_scroll()
+                            .children(threads),
+                    )
+                }
+            })
+    }
+}
+
+impl Focusable for ReviewThreadsPanel {
+    fn focus_handle(&self, _: &App) -> FocusHandle {
+        self.focus_handle.clone()
+    }
+}
+
+impl EventEmitter<PanelEvent> for ReviewThreadsPanel {}
+
+impl Panel for ReviewThreadsPanel {
+    fn persistent_name() -> &'static str {
+        "ReviewThreadsPanel"
+    }
+
+    fn panel_key() -> &'static str {
+        REVIEW_THREADS_PANEL_KEY
+    }
+
+    fn position(&self, _: &Window, cx: &App) -> DockPosition {
+        ReviewThreadsPanelSettings::get_global(cx).dock
+    }
+
+    fn position_is_valid(&self, _: DockPosition) -> bool {
+        true
+    }
+
+    fn set_position(&mut self, position: DockPosition, _: &mut Window, cx: &mut Context<Self>) {
+        settings::update_settings_file(self.fs.clone(), cx, move |settings, _| {
+            settings.review_threads_panel.get_or_insert_default().dock = Some(position.into())
+        });
+    }
+
+    fn default_size(&self, _: &Window, cx: &App) -> Pixels {
+        ReviewThreadsPanelSettings::get_global(cx).default_width
+    }
+
+    fn icon(&self, _: &Window, cx: &App) -> Option<IconName> {
+        (ReviewThreadsPanelSettings::get_global(cx).button && self.enabled(cx))
+            .then_some(IconName::Chat)
+    }
+
+    fn icon_tooltip(&self, _: &Window, _: &App) -> Option<&'static str> {
+        Some("Review Threads Panel")
+    }
+
+    fn icon_label(&self, _: &Window, cx: &App) -> Option<String> {
+        let count = self
+            .store
+            .read(cx)
+            .threads()
+            .filter(|thread| !thread.resolved)
+            .count();
+        (count > 0).then(|| count.to_string())
+    }
+
+    fn toggle_action(&self) -> Box<dyn Action> {
+        Box::new(ToggleFocus)
+    }
+
+    fn activation_priority(&self) -> u32 {
+        10
+    }
+
+    fn enabled(&self, cx: &App) -> bool {
+        let store = self.store.read(cx);
+        store.is_available() || store.threads().next().is_some()
+    }
+}
@@ -0,0 +1,410 @@
+use crate::{BlockedOperation, SandboxPolicy};
+use anyhow::{Context as _, Result};
+use std::ffi::{CString, OsString};
+use std::fs::File;
+use std::io::Write as _;
+use std::os::fd::{AsRawFd as _, FromRawFd as _, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt as _;
+use std::os::unix::process::ExitStatusExt as _;
+use std::path::Path;
+
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+
+const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+const ACCESS_FS_REFER: u64 = 1 << 13;
+const ACCESS_FS_TRUNCATE: u64 = 1 << 14;
+/// Every access right in Landlock ABI version 1.
+const ACCESS_FS_V1: u64 = (1 << 13) - 1;
+const ACCESS_FS_READ: u64 = ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+
+#[repr(C)]
+struct LandlockRulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct LandlockPathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+const SECCOMP_SET_MODE_FILTER: libc::c_ulong = 1;
+const SECCOMP_FILTER_FLAG_NEW_LISTENER: libc::c_ulong = 1 << 3;
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_USER_NOTIF: u32 = 0x7fc0_0000;
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xc000_003e;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xc000_00b7;
+/// Set in the numbers of x32 syscalls, which share the x86-64 audit arch.
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+/// `_IOWR('!', 0, struct seccomp_notif)`
+const SECCOMP_IOCTL_NOTIF_RECV: libc::c_ulong = 0xc050_2100;
+/// `_IOWR('!', 1, struct seccomp_notif_resp)`
+const SECCOMP_IOCTL_NOTIF_SEND: libc::c_ulong = 0xc018_2101;
+
+#[repr(C)]
+#[derive(Default)]
+struct SeccompData {
+    nr: i32,
+    arch: u32,
+    instruction_pointer: u64,
+    args: [u64; 6],
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct SeccompNotif {
+    id: u64,
+    pid: u32,
+    flags: u32,
+    data: SeccompData,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct SeccompNotifResp {
+    id: u64,
+    val: i64,
+    error: i32,
+    flags: u32,
+}
+
+/// Returns the Landlock ABI version supported by the running kernel, if any.
+pub(crate) fn landlock_abi_version() -> Option<u32> {
+    // SAFETY: querying the ABI version takes no pointers.
+    let version = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            std::ptr::null::<LandlockRulesetAttr>(),
+            0usize,
+            LANDLOCK_CREATE_RULESET_VERSION,
+        )
+    };
+    (version > 0).then_some(version as u32)
+}
+
+/// Restricts the current process, spawns the command, and waits for it to exit.
+pub(crate) fn run(
+    policy: &SandboxPolicy,
+    report_path: Option<&Path>,
+    program: &OsString,
+    args: &[OsString],
+) -> Result<i32> {
+    // Open the report before restricting ourselves, so it can live anywhere.
+    let report = report_path
+        .map(|path| File::create(path).context("failed to create sandbox report"))
+        .transpose()?;
+
+    // SAFETY: `prctl` with these arguments has no memory-safety requirements.
+    if unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("failed to set no_new_privs");
+    }
+
+    restrict_filesystem(policy)?;
+    let listener = if policy.allow_network {
+        None
+    } else {
+        Some(install_network_filter()?)
+    };
+
+    #[allow(
+        clippy::disallowed_methods,
+        reason = "the helper has no event loop to block"
+    )]
+    let mut child = std::process::Command::new(program)
+        .args(args)
+        .spawn()
+        .with_context(|| format!("failed to spawn {program:?}"))?;
+
+    if let Some(listener) = listener {
+        std::thread::spawn(move || supervise(listener, report));
+    }
+
+    let status = child
+        .wait()
+        .context("failed to wait for sandboxed command")?;
+    Ok(status
+        .code()
+        .unwrap_or_else(|| 128 + status.signal().unwrap_or_default()))
+}
+
+fn restrict_filesystem(policy: &SandboxPolicy) -> Result<()> {
+    let abi = landlock_abi_version().context("Landlock is not supported by this kernel")?;
+    let mut handled_access = ACCESS_FS_V1;
+    if abi >= 2 {
+        handled_access |= ACCESS_FS_REFER;
+    }
+    if abi >= 3 {
+        handled_access |= ACCESS_FS_TRUNCATE;
+    }
+
+    let attr = LandlockRulesetAttr {
+        handled_access_fs: handled_access,
+    };
+    // SAFETY: `attr` outlives the call and its size is passed alongside it.
+    let ruleset_fd = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &attr as *const LandlockRulesetAttr,
+            std::mem::size_of::<LandlockRulesetAttr>(),
+            0u32,
+        )
+    };
+    if ruleset_fd < 0 {
+        return Err(std::io::Error::last_os_error()).context("failed to create Landlock ruleset");
+    }
+    // SAFETY: the syscall returned a new file descriptor that we now own.
+    let ruleset = unsafe { OwnedFd::from_raw_fd(ruleset_fd as RawFd) };
+
+    add_path_rule(&ruleset, Path::new("/"), ACCESS_FS_READ)?;
+    // Commands expect to be able to write to `/dev/null` and their terminal.
+    add_path_rule(&ruleset, Path::new("/dev"), handled_access)?;
+    for path in &policy.writable_paths {
+        if let Err(error) = add_path_rule(&ruleset, path, handled_access) {
+            log::warn!("not allowing writes to {path:?}: {error:#}");
+        }
+    }
+
+    // SAFETY: `ruleset` is a valid Landlock ruleset file descriptor.
+    if unsafe { libc::syscall(libc::SYS_landlock_restrict_self, ruleset.as_raw_fd(), 0u32) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("failed to enforce Landlock ruleset");
+    }
+    Ok(())
+}
+
+fn add_path_rule(ruleset: &OwnedFd, path: &Path, access: u64) -> Result<()> {
+    let c_path = CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: `c_path` is a valid NUL-terminated string.
+    let fd = unsafe { libc::open(c_path.as_ptr(), libc::O_PATH | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("failed to open {path:?}"));
+    }
+    // SAFETY: `open` returned a new file descriptor that we now own.
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+    // Directory-only rights can't be granted on files.
+    let access = if path.is_dir() {
+        access
+    } else {
+        access
+            & (ACCESS_FS_EXECUTE | ACCESS_FS_WRITE_FILE | ACCESS_FS_READ_FILE | ACCESS_FS_TRUNCATE)
+    };
+    let attr = LandlockPathBeneathAttr {
+        allowed_access: access,
+        parent_fd: fd.as_raw_fd(),
+    };
+    // SAFETY: `attr` outlives the call and both file descriptors are valid.
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_landlock_add_rule,
+            ruleset.as_raw_fd(),
+            LANDLOCK_RULE_PATH_BENEATH,
+            &attr as *const LandlockPathBeneathAttr,
+            0u32,
+        )
+    };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("failed to add Landlock rule for {path:?}"));
+    }
+    Ok(())
+}
+
+fn bpf_stmt(code: u16, k: u32) -> libc::sock_filter {
+    libc::sock_filter {
+        code,
+        jt: 0,
+        jf: 0,
+        k,
+    }
+}
+
+fn bpf_jump(code: u16, k: u32, jt: u8, jf: u8) -> libc::sock_filter {
+    libc::sock_filter { code, jt, jf, k }
+}
+
+/// Installs a seccomp filter that hands IPv4 and IPv6 socket creation to a
+/// supervisor, returning the listener the supervisor reads notifications from.
+fn install_network_filter() -> Result<OwnedFd> {
+    const LOAD: u16 = (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16;
+    const JEQ: u16 = (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16;
+    const JGE: u16 = (libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K) as u16;
+    const RET: u16 = (libc::BPF_RET | libc::BPF_K) as u16;
+    const OFFSET_NR: u32 = 0;
+    const OFFSET_ARCH: u32 = 4;
+    const OFFSET_ARG0: u32 = 16;
+
+    let mut filter = [
+        bpf_stmt(LOAD, OFFSET_ARCH),
+        // Syscalls of other arches (e.g. i386 `int 0x80`) have other numbers, so they could reach
+        // `socket(2)` unchecked.
+        bpf_jump(JEQ, AUDIT_ARCH, 1, 0),
+        bpf_stmt(RET, SECCOMP_RET_KILL_PROCESS),
+        bpf_stmt(LOAD, OFFSET_NR),
+        bpf_jump(JGE, X32_SYSCALL_BIT, 0, 1),
+        bpf_stmt(RET, SECCOMP_RET_ERRNO | libc::EPERM as u32),
+        // io_uring can create sockets without going through `socket(2)`.
+        bpf_jump(JEQ, libc::SYS_io_uring_setup as u32, 0, 1),
+        bpf_stmt(RET, SECCOMP_RET_ERRNO | libc::EPERM as u32),
+        bpf_jump(JEQ, libc::SYS_socket as u32, 1, 0),
+        bpf_stmt(RET, SECCOMP_RET_ALLOW),
+        bpf_stmt(LOAD, OFFSET_ARG0),
+        bpf_jump(JEQ, libc::AF_INET as u32, 2, 0),
+        bpf_jump(JEQ, libc::AF_INET6 as u32, 1, 0),
+        bpf_stmt(RET, SECCOMP_RET_ALLOW),
+        bpf_stmt(RET, SECCOMP_RET_USER_NOTIF),
+    ];
+    let program = libc::sock_fprog {
+        len: filter.len() as u16,
+        filter: filter.as_mut_ptr(),
+    };
+
+    // SAFETY: `program` points to `filter`, which outlives the call.
+    let listener = unsafe {
+        libc::syscall(
+            libc::SYS_seccomp,
+            SECCOMP_SET_MODE_FILTER,
+            SECCOMP_FILTER_FLAG_NEW_LISTENER,
+            &program as *const libc::sock_fprog,
+        )
+    };
+    if listener < 0 {
+        return Err(std::io::Error::last_os_error()).context("failed to install seccomp filter");
+    }
+    // SAFETY: the syscall returned a new file descriptor that we now own.
+    Ok(unsafe { OwnedFd::from_raw_fd(listener as RawFd) })
+}
+
+/// Denies every notification received on `listener`, recording each one in the report.
+fn supervise(listener: OwnedFd, mut report: Option<File>) {
+    loop {
+        let mut notification = SeccompNotif::default();
+        // SAFETY: `notification` is a zeroed `struct seccomp_notif`, as the kernel requires.
+        let result = unsafe {
+            libc::ioctl(
+                listener.as_raw_fd(),
+                SECCOMP_IOCTL_NOTIF_RECV as _,
+                &mut notification as *mut SeccompNotif,
+            )
+        };
+        if result != 0 {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() == Some(libc::EINTR) {
+                continue;
+            }
+            if error.raw_os_error() != Some(libc::ENOENT) {
+                log::debug!("stopped supervising sandbox: {error}");
+                return;
+            }
+            continue;
+        }
+
+        let family = match notification.data.args[0] as i32 {
+            libc::AF_INET => "AF_INET",
+            libc::AF_INET6 => "AF_INET6",
+            _ => "unknown family",
+        };
+        let operation = BlockedOperation {
+            pid: notification.pid,
+            process: std::fs::read_to_string(format!("/proc/{}/comm", notification.pid))
+                .ok()
+                .map(|comm| comm.trim().to_string()),
+            operation: format!("network socket ({family})"),
+        };
+        if let Some(report) = report.as_mut()
+            && let Err(error) =
+                writeln!(report, "{}", operation.to_report_line()).and_then(|()| report.flush())
+        {
+            log::debug!("failed to write sandbox report: {error}");
+        }
+
+        // Respond only once the operation is reported: the command is blocked until then, so the
+        // report can't be lost by the helper exiting as soon as the command does.
+        let response = SeccompNotifResp {
+            id: notification.id,
+            val: 0,
+            error: -libc::EPERM,
+            flags: 0,
+        };
+        // SAFETY: `response` is a valid `struct seccomp_notif_resp`.
+        unsafe {
+            libc::ioctl(
+                listener.as_raw_fd(),
+                SECCOMP_IOCTL_NOTIF_SEND as _,
+                &response as *const SeccompNotifResp,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SandboxReport;
+
+    #[test]
+    fn test_run_denies_writes_and_network() {
+        if !crate::is_supported() {
+            return;
+        }
+        let allowed = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        let report_dir = tempfile::tempdir().unwrap();
+        let report_path = report_dir.path().join("report");
+        let policy = SandboxPolicy {
+            writable_paths: vec![allowed.path().to_path_buf()],
+            allow_network: false,
+        };
+        let args = [
+            "-c",
+            r#"echo ok > "$1/file"; echo ok > "$2/file"; : 3<>/dev/tcp/127.0.0.1/9"#,
+            "bash",
+        ]
+        .into_iter()
+        .map(OsString::from)
+        .chain([
+            allowed.path().as_os_str().to_owned(),
+            outside.path().as_os_str().to_owned(),
+        ])
+        .collect::<Vec<_>>();
+
+        // Landlock and seccomp restrict only the calling thread and the processes it spawns, so
+        // restricting a separate thread leaves the rest of the test process unaffected.
+        let exit_code = std::thread::spawn({
+            let report_path = report_path.clone();
+            move || {
+                run(
+                    &policy,
+                    Some(report_path.as_path()),
+                    &OsString::from("bash"),
+                    &args,
+                )
+                .unwrap()
+            }
+        })
+        .join()
+        .unwrap();
+
+        assert_ne!(exit_code, 0);
+        assert!(allowed.path().join("file").exists());
+        assert!(!outside.path().join("file").exists());
+        let report = SandboxReport::read(&report_path).unwrap();
+        assert!(
+            report
+                .blocked
+                .iter()
+                .any(|operation| operation.operation == "network socket (AF_INET)"),
+            "got: {report:?}"
+        );
+    }
+}
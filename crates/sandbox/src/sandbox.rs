@@ -0,0 +1,347 @@
+//! OS-level sandboxing for commands that Zed runs on behalf of the agent.
+//!
+//! Sandboxed commands are run through a helper: Zed re-executes itself with
+//! [`HELPER_FLAG`], the helper restricts its own process according to a
+//! [`SandboxPolicy`], and then spawns the command, which inherits the
+//! restrictions. Operations the helper blocks are written to a report file
+//! that can be read back with [`SandboxReport::read`] once the command exits.
+//!
+//! Only Linux is supported. Filesystem writes are restricted with Landlock,
+//! and network access is blocked with a seccomp filter whose denials are
+//! observed by the helper through seccomp user notifications.
+
+#[cfg(all(
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+mod linux;
+
+use anyhow::{Context as _, Result, anyhow, bail};
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// When Zed is started with this as its first argument, it runs as the sandbox helper.
+pub const HELPER_FLAG: &str = "--agent-sandbox";
+
+const WRITABLE_FLAG: &str = "--writable";
+const ALLOW_NETWORK_FLAG: &str = "--allow-network";
+const REPORT_FLAG: &str = "--report";
+
+/// What a sandboxed command is allowed to do.
+///
+/// Reading and executing files is always allowed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SandboxPolicy {
+    /// Directories (and everything beneath them) that the command may modify.
+    pub writable_paths: Vec<PathBuf>,
+    /// Whether the command may create IPv4 and IPv6 sockets.
+    pub allow_network: bool,
+}
+
+impl SandboxPolicy {
+    /// Returns whether a sandboxed command could modify `path`.
+    pub fn is_writable(&self, path: &Path) -> bool {
+        let canonicalize =
+            |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        let path = canonicalize(path);
+        self.writable_paths
+            .iter()
+            .any(|writable_path| path.starts_with(canonicalize(writable_path)))
+    }
+
+    /// Returns the arguments to pass to the helper executable in order to
+    /// run `program` with `args` under this policy.
+    pub fn helper_args(&self, report_path: &Path, program: &str, args: &[String]) -> Vec<String> {
+        let mut helper_args = vec![HELPER_FLAG.to_string()];
+        for path in &self.writable_paths {
+            helper_args.push(WRITABLE_FLAG.to_string());
+            helper_args.push(path.to_string_lossy().into_owned());
+        }
+        if self.allow_network {
+            helper_args.push(ALLOW_NETWORK_FLAG.to_string());
+        }
+        helper_args.push(REPORT_FLAG.to_string());
+        helper_args.push(report_path.to_string_lossy().into_owned());
+        helper_args.push("--".to_string());
+        helper_args.push(program.to_string());
+        helper_args.extend(args.iter().cloned());
+        helper_args
+    }
+
+    /// Describes the policy for inclusion in a tool result.
+    pub fn describe(&self) -> String {
+        let mut description =
+            String::from("The command ran in a sandbox. Writes are only allowed in: ");
+        for (ix, path) in self.writable_paths.iter().enumerate() {
+            if ix > 0 {
+                description.push_str(", ");
+            }
+            description.push_str(&path.to_string_lossy());
+        }
+        description.push('.');
+        if !self.allow_network {
+            description.push_str(" Network access is blocked.");
+        }
+        description
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct HelperInvocation {
+    policy: SandboxPolicy,
+    report_path: Option<PathBuf>,
+    program: OsString,
+    args: Vec<OsString>,
+}
+
+fn parse_helper_args(args: impl IntoIterator<Item = OsString>) -> Result<HelperInvocation> {
+    let mut args = args.into_iter();
+    let mut policy = SandboxPolicy::default();
+    let mut report_path = None;
+    loop {
+        let arg = args
+            .next()
+            .context("missing `--` before the sandboxed command")?;
+        match arg.to_str() {
+            Some("--") => break,
+            Some(HELPER_FLAG) => {}
+            Some(WRITABLE_FLAG) => {
+                let path = args.next().context("missing path after --writable")?;
+                policy.writable_paths.push(PathBuf::from(path));
+            }
+            Some(ALLOW_NETWORK_FLAG) => policy.allow_network = true,
+            Some(REPORT_FLAG) => {
+                let path = args.next().context("missing path after --report")?;
+                report_path = Some(PathBuf::from(path));
+            }
+            _ => bail!("unexpected sandbox argument {arg:?}"),
+        }
+    }
+    let program = args.next().context("missing sandboxed command")?;
+    Ok(HelperInvocation {
+        policy,
+        report_path,
+        program,
+        args: args.collect(),
+    })
+}
+
+/// Returns whether commands can be sandboxed on this system.
+pub fn is_supported() -> bool {
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    {
+        linux::landlock_abi_version().is_some()
+    }
+    #[cfg(not(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )))]
+    {
+        false
+    }
+}
+
+/// The main function for when Zed is running as the sandbox helper.
+///
+/// `args` are the process arguments following the executable path. Exits
+/// with the sandboxed command's exit status.
+pub fn main(args: impl IntoIterator<Item = OsString>) -> ! {
+    let invocation = match parse_helper_args(args) {
+        Ok(invocation) => invocation,
+        Err(error) => {
+            eprintln!("zed sandbox: {error:#}");
+            std::process::exit(126);
+        }
+    };
+
+    #[cfg(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    let result = linux::run(
+        &invocation.policy,
+        invocation.report_path.as_deref(),
+        &invocation.program,
+        &invocation.args,
+    );
+    #[cfg(not(all(
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    )))]
+    let result: Result<i32> = Err(anyhow!("sandboxing is only supported on Linux"));
+
+    match result {
+        Ok(code) => std::process::exit(code),
+        Err(error) => {
+            eprintln!("zed sandbox: {error:#}");
+            std::process::exit(126);
+        }
+    }
+}
+
+/// An operation the sandbox prevented a command from performing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockedOperation {
+    pub pid: u32,
+    /// The name of the process that attempted the operation, if known.
+    pub process: Option<String>,
+    pub operation: String,
+}
+
+impl fmt::Display for BlockedOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.process {
+            Some(process) => write!(f, "{} (`{}`, pid {})", self.operation, process, self.pid),
+            None => write!(f, "{} (pid {})", self.operation, self.pid),
+        }
+    }
+}
+
+impl BlockedOperation {
+    fn to_report_line(&self) -> String {
+        format!(
+            "{}\t{}\t{}",
+            self.pid,
+            self.process.as_deref().unwrap_or_default(),
+            self.operation
+        )
+    }
+
+    fn from_report_line(line: &str) -> Result<Self> {
+        let mut fields = line.splitn(3, '\t');
+        let pid = fields
+            .next()
+            .and_then(|pid| pid.parse().ok())
+            .ok_or_else(|| anyhow!("invalid pid in sandbox report line {line:?}"))?;
+        let process = fields
+            .next()
+            .filter(|process| !process.is_empty())
+            .map(ToString::to_string);
+        let operation = fields
+            .next()
+            .ok_or_else(|| anyhow!("missing operation in sandbox report line {line:?}"))?
+            .to_string();
+        Ok(Self {
+            pid,
+            process,
+            operation,
+        })
+    }
+}
+
+/// The operations blocked while running a sandboxed command.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SandboxReport {
+    pub blocked: Vec<BlockedOperation>,
+}
+
+impl SandboxReport {
+    pub fn read(path: &Path) -> Result<Self> {
+        let contents = match std::fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(error) => return Err(error).context("failed to read sandbox report"),
+        };
+        Self::parse(&contents)
+    }
+
+    fn parse(contents: &str) -> Result<Self> {
+        let blocked = contents
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(BlockedOperation::from_report_line)
+            .collect::<Result<_>>()?;
+        Ok(Self { blocked })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_helper_args_round_trip() {
+        let policy = SandboxPolicy {
+            writable_paths: vec![PathBuf::from("/work/project"), PathBuf::from("/tmp")],
+            allow_network: false,
+        };
+        let args = policy.helper_args(
+            Path::new("/tmp/report"),
+            "/bin/bash",
+            &["-c".to_string(), "cargo test --offline".to_string()],
+        );
+        assert_eq!(args[0], HELPER_FLAG);
+
+        let invocation = parse_helper_args(args.into_iter().map(OsString::from)).unwrap();
+        assert_eq!(
+            invocation,
+            HelperInvocation {
+                policy,
+                report_path: Some(PathBuf::from("/tmp/report")),
+                program: "/bin/bash".into(),
+                args: vec!["-c".into(), "cargo test --offline".into()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_helper_args_errors() {
+        let parse = |args: &[&str]| parse_helper_args(args.iter().map(OsString::from));
+        assert!(parse(&["--writable"]).is_err());
+        assert!(parse(&["--allow-network", "ls"]).is_err());
+        assert!(parse(&["--allow-network", "--"]).is_err());
+        assert!(parse(&["--bogus", "--", "ls"]).is_err());
+        assert!(
+            parse(&["--", "ls", "--writable"])
+                .unwrap()
+                .policy
+                .writable_paths
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_is_writable() {
+        let policy = SandboxPolicy {
+            writable_paths: vec![PathBuf::from("/work/project")],
+            allow_network: false,
+        };
+        assert!(policy.is_writable(Path::new("/work/project")));
+        assert!(policy.is_writable(Path::new("/work/project/target/report")));
+        assert!(!policy.is_writable(Path::new("/work/project-other")));
+        assert!(!policy.is_writable(Path::new("/work")));
+    }
+
+    #[test]
+    fn test_report_round_trip() {
+        let blocked = vec![
+            BlockedOperation {
+                pid: 42,
+                process: Some("curl".into()),
+                operation: "network socket (AF_INET)".into(),
+            },
+            BlockedOperation {
+                pid: 43,
+                process: None,
+                operation: "network socket (AF_INET6)".into(),
+            },
+        ];
+        let contents = blocked
+            .iter()
+            .map(|operation| operation.to_report_line() + "\n")
+            .collect::<String>();
+        assert_eq!(
+            SandboxReport::parse(&contents).unwrap(),
+            SandboxReport { blocked }
+        );
+        assert!(SandboxReport::parse("not-a-pid\tcurl\tsocket").is_err());
+        assert_eq!(
+            SandboxReport::read(Path::new("/nonexistent/zed-sandbox-report")).unwrap(),
+            SandboxReport::default()
+        );
+    }
+}
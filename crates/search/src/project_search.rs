@@ -1,7 +1,8 @@
 use crate::{
     BufferSearchBar, FocusSearch, HighlightKey, NextHistoryQuery, PreviousHistoryQuery, ReplaceAll,
     ReplaceNext, SearchOption, SearchOptions, SearchSource, SelectNextMatch, SelectPreviousMatch,
-    ToggleCaseSensitive, ToggleIncludeIgnored, ToggleRegex, ToggleReplace, ToggleWholeWord,
+    ToggleCaseSensitive, ToggleIncludeIgnored, ToggleRegex, ToggleReplace, ToggleStructural,
+    ToggleWholeWord,
     buffer_search::Deploy,
     search_bar::{
         ActionButtonState, HistoryNavigationDirection, alignment_element, input_base_styles,
@@ -11,10 +12,10 @@ use crate::{
 use anyhow::Context as _;
 use collections::HashMap;
 use editor::{
-    Anchor, Editor, EditorEvent, EditorSettings, ExcerptId, MAX_TAB_TITLE_LEN, MultiBuffer,
+    Anchor, Editor, EditorEvent, EditorSettings, ExcerptId, Inlay, MAX_TAB_TITLE_LEN, MultiBuffer,
     PathKey, SelectionEffects,
     actions::{Backtab, FoldAll, SelectAll, Tab, UnfoldAll},
-    items::active_match_index,
+    items::{active_match_index, structural_replacement_for},
     multibuffer_context_lines,
     scroll::Autoscroll,
 };
@@ -29,7 +30,7 @@ use language::{Buffer, Language};
 use menu::Confirm;
 use multi_buffer;
 use project::{
-    Project, ProjectPath, SearchResults,
+    InlayId, Project, ProjectPath, SearchResults,
     search::{SearchInputKind, SearchQuery},
     search_history::SearchHistoryCursor,
 };
@@ -130,6 +131,12 @@ pub fn init(cx: &mut App) {
         register_workspace_action(workspace, move |search_bar, _: &ToggleRegex, window, cx| {
             search_bar.toggle_search_option(SearchOptions::REGEX, window, cx);
         });
+        register_workspace_action(
+            workspace,
+            move |search_bar, _: &ToggleStructural, window, cx| {
+                search_bar.toggle_search_option(SearchOptions::STRUCTURAL, window, cx);
+            },
+        );
         register_workspace_action(
             workspace,
             move |search_bar, action: &ToggleReplace, window, cx| {
@@ -269,6 +276,7 @@ pub struct ProjectSearchView {
     pending_replace_all: bool,
     included_opened_only: bool,
     regex_language: Option<Arc<Language>>,
+    replacement_previews: Vec<InlayId>,
     _subscriptions: Vec<Subscription>,
 }
 
@@ -780,6 +788,14 @@ impl ProjectSearchView {
 
     fn toggle_search_option(&mut self, option: SearchOptions, cx: &mut Context<Self>) {
         self.search_options.toggle(option);
+        // Structural patterns aren't regexes, so only one of the two modes can be active.
+        if self.search_options.contains(option) {
+            if option.contains(SearchOptions::STRUCTURAL) {
+                self.search_options.remove(SearchOptions::REGEX);
+            } else if option.contains(SearchOptions::REGEX) {
+                self.search_options.remove(SearchOptions::STRUCTURAL);
+            }
+        }
         ActiveSettings::update_global(cx, |settings, cx| {
             settings.0.insert(
                 self.entity.read(cx).project.downgrade(),
@@ -825,6 +841,7 @@ impl ProjectSearchView {
                     editor.replace(mat, &query, SearchToken::default(), window, cx);
                 }
             });
+            self.refresh_replacement_previews(cx);
             self.select_match(Direction::Next, window, cx)
         }
     }
@@ -874,6 +891,7 @@ impl ProjectSearchView {
         self.entity.update(cx, |model, _cx| {
             model.match_ranges = match_ranges;
         });
+        self.refresh_replacement_previews(cx);
     }
 
     fn toggle_all_search_results(
@@ -964,6 +982,14 @@ impl ProjectSearchView {
             }
             editor
         });
+        subscriptions.push(cx.subscribe(
+            &replacement_editor,
+            |this, _, event: &EditorEvent, cx| {
+                if let EditorEvent::Edited { .. } = event {
+                    this.refresh_replacement_previews(cx);
+                }
+            },
+        ));
         let results_editor = cx.new(|cx| {
             let mut editor = Editor::for_multibuffer(excerpts, Some(project.clone()), window, cx);
             editor.set_searchable(false);
@@ -1060,6 +1086,7 @@ impl ProjectSearchView {
             pending_replace_all: false,
             included_opened_only: false,
             regex_language: None,
+            replacement_previews: Vec::new(),
             _subscriptions: subscriptions,
         };
 
@@ -1385,7 +1412,35 @@ impl ProjectSearchView {
             .count()
             > 1;
 
-        let query = if self.search_options.contains(SearchOptions::REGEX) {
+        let query = if self.search_options.contains(SearchOptions::STRUCTURAL) {
+            match SearchQuery::structural(
+                text,
+                self.search_options.contains(SearchOptions::INCLUDE_IGNORED),
+                included_files,
+                excluded_files,
+                match_full_paths,
+                open_buffers,
+            ) {
+                Ok(query) => {
+                    let should_unmark_error = self.panels_with_errors.remove(&InputPanel::Query);
+                    if should_unmark_error.is_some() {
+                        cx.notify();
+                    }
+
+                    Some(query)
+                }
+                Err(e) => {
+                    let should_mark_error = self
+                        .panels_with_errors
+                        .insert(InputPanel::Query, e.to_string());
+                    if should_mark_error.is_none() {
+                        cx.notify();
+                    }
+
+                    None
+                }
+            }
+        } else if self.search_options.contains(SearchOptions::REGEX) {
             match SearchQuery::regex(
                 text,
                 self.search_options.contains(SearchOptions::WHOLE_WORD),
@@ -1571,6 +1626,7 @@ impl ProjectSearchView {
     fn entity_changed(&mut self, window: &mut Window, cx: &mut Context<Self>) {
         let match_ranges = self.entity.read(cx).match_ranges.clone();
 
+        self.refresh_replacement_previews(cx);
         if match_ranges.is_empty() {
             self.active_match_index = None;
             self.results_editor.update(cx, |editor, cx| {
@@ -1650,6 +1706,38 @@ impl ProjectSearchView {
         });
     }
 
+    /// Shows what each match of a structural search will be replaced with,
+    /// since the replacement depends on the text its metavariables capture.
+    fn refresh_replacement_previews(&mut self, cx: &mut Context<Self>) {
+        let search = self.entity.read(cx);
+        let query = search
+            .active_query
+            .as_ref()
+            .filter(|query| self.replace_enabled && query.is_structural())
+            .map(|query| query.clone().with_replacement(self.replacement(cx)));
+        let match_ranges = search.match_ranges.clone();
+        let to_remove = mem::take(&mut self.replacement_previews);
+        self.replacement_previews = self.results_editor.update(cx, |editor, cx| {
+            let mut to_insert = Vec::new();
+            if let Some(query) = query {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                for (ix, range) in match_ranges.iter().enumerate() {
+                    if let Some(replacement) = structural_replacement_for(&snapshot, range, &query)
+                    {
+                        to_insert.push(Inlay::replacement_preview(
+                            ix,
+                            range.end,
+                            format!(" → {replacement}"),
+                        ));
+                    }
+                }
+            }
+            let previews = to_insert.iter().map(|inlay| inlay.id).collect();
+            editor.splice_inlays(&to_remove, to_insert, cx);
+            previews
+        });
+    }
+
     pub fn has_matches(&self) -> bool {
         self.active_match_index.is_some()
     }
@@ -1687,6 +1775,18 @@ impl ProjectSearchView {
                         window.dispatch_action(ToggleRegex.boxed_clone(), cx)
                     }),
             )
+            .child(
+                Button::new("structural", "Match syntax structure")
+                    .start_icon(Icon::new(IconName::ListTree).size(IconSize::Small))
+                    .key_binding(KeyBinding::for_action_in(
+                        &ToggleStructural,
+                        &focus_handle,
+                        cx,
+                    ))
+                    .on_click(|_event, window, cx| {
+                        window.dispatch_action(ToggleStructural.boxed_clone(), cx)
+                    }),
+            )
             .child(
                 Button::new("match-case", "Match case")
                     .start_icon(Icon::new(IconName::CaseSensitive).size(IconSize::Small))
@@ -1900,6 +2000,7 @@ impl ProjectSearchBar {
         if let Some(search) = &self.active_project_search {
             search.update(cx, |this, cx| {
                 this.replace_enabled = !this.replace_enabled;
+                this.refresh_replacement_previews(cx);
                 let editor_to_focus = if this.replace_enabled {
                     this.replacement_editor.focus_handle(cx)
                 } else {
@@ -2196,6 +2297,11 @@ impl Render for ProjectSearchBar {
                         search.search_options,
                         SearchSource::Project(cx),
                         focus_handle.clone(),
+                    ))
+                    .child(SearchOption::Structural.as_button(
+                        search.search_options,
+                        SearchSource::Project(cx),
+                        focus_handle.clone(),
                     )),
             );
 
@@ -2,7 +2,7 @@ use anyhow::{Context as _, Result};
 use collections::{BTreeMap, HashMap, IndexMap};
 use fs::Fs;
 use gpui::{
-    Action, ActionBuildError, App, InvalidKeystrokeError, KEYSTROKE_PARSE_EXPECTED_MESSAGE,
+    Action, ActionBuildError, App, Global, InvalidKeystrokeError, KEYSTROKE_PARSE_EXPECTED_MESSAGE,
     KeyBinding, KeyBindingContextPredicate, KeyBindingMetaIndex, KeybindingKeystroke, Keystroke,
     NoAction, SharedString, Unbind, generate_list_of_all_registered_actions, register_action,
 };
@@ -10,7 +10,7 @@ use schemars::{JsonSchema, json_schema};
 use serde::Deserialize;
 use serde_json::{Value, json};
 use std::borrow::Cow;
-use std::{any::TypeId, fmt::Write, rc::Rc, sync::Arc, sync::LazyLock};
+use std::{any::TypeId, fmt::Write, path::Path, rc::Rc, sync::Arc, sync::LazyLock};
 use util::ResultExt as _;
 use util::{
     asset_str,
@@ -234,6 +234,11 @@ impl KeymapFile {
     }
 
     pub fn load(content: &str, cx: &App) -> KeymapFileLoadResult {
+        Self::load_named(content, "user keymap file", cx)
+    }
+
+    /// Like [`KeymapFile::load`], but refers to the file by the given name in the error messages.
+    pub fn load_named(content: &str, file_name: &str, cx: &App) -> KeymapFileLoadResult {
         let keymap_file = match Self::parse(content) {
             Ok(keymap_file) => keymap_file,
             Err(error) => {
@@ -354,7 +359,7 @@ impl KeymapFile {
         if errors.is_empty() {
             KeymapFileLoadResult::Success { key_bindings }
         } else {
-            let mut error_message = "Errors in user keymap file.".to_owned();
+            let mut error_message = format!("Errors in {file_name}.");
 
             for (context, section_errors) in errors {
                 if context.is_empty() {
@@ -504,6 +509,12 @@ impl KeymapFile {
         Ok(name_and_input)
     }
 
+    /// Builds an action written as in the keymap: either its name, or an
+    /// array of its name and its input.
+    pub fn build_action(action: &Value, cx: &App) -> std::result::Result<Box<dyn Action>, String> {
+        Self::build_keymap_action_value(action, cx).map(|(action, _)| action)
+    }
+
     fn build_keymap_action(
         action: &KeymapAction,
         cx: &App,
@@ -1315,7 +1326,10 @@ impl<'a> KeybindUpdateTarget<'a> {
     }
 }
 
-#[derive(Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug)]
+/// Where a key binding comes from.
+///
+/// Sources are ordered by their precedence: the ones which override the others come first.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
 pub enum KeybindSource {
     User,
     Vim,
@@ -1323,6 +1337,8 @@ pub enum KeybindSource {
     #[default]
     Default,
     Unknown,
+    /// A `.zed/keymap.json` file of the project in the focused window, see [`ProjectKeymapFiles`].
+    Project,
 }
 
 impl KeybindSource {
@@ -1330,6 +1346,8 @@ impl KeybindSource {
     const DEFAULT: KeyBindingMetaIndex = KeyBindingMetaIndex(KeybindSource::Default as u32);
     const VIM: KeyBindingMetaIndex = KeyBindingMetaIndex(KeybindSource::Vim as u32);
     const USER: KeyBindingMetaIndex = KeyBindingMetaIndex(KeybindSource::User as u32);
+    /// Project bindings get a separate meta index per file, starting from this one.
+    const PROJECT: KeyBindingMetaIndex = KeyBindingMetaIndex(KeybindSource::Project as u32);
 
     pub fn name(&self) -> &'static str {
         match self {
@@ -1338,6 +1356,7 @@ impl KeybindSource {
             KeybindSource::Base => "Base",
             KeybindSource::Vim => "Vim",
             KeybindSource::Unknown => "Unknown",
+            KeybindSource::Project => "Project",
         }
     }
 
@@ -1347,6 +1366,7 @@ impl KeybindSource {
             KeybindSource::Default => Self::DEFAULT,
             KeybindSource::Base => Self::BASE,
             KeybindSource::Vim => Self::VIM,
+            KeybindSource::Project => Self::PROJECT,
             KeybindSource::Unknown => KeyBindingMetaIndex(*self as u32),
         }
     }
@@ -1357,11 +1377,56 @@ impl KeybindSource {
             Self::BASE => KeybindSource::Base,
             Self::DEFAULT => KeybindSource::Default,
             Self::VIM => KeybindSource::Vim,
+            _ if index.0 >= Self::PROJECT.0 => KeybindSource::Project,
             _ => KeybindSource::Unknown,
         }
     }
+
+    /// The meta index of the bindings from a project keymap file at `file_ix` in [`ProjectKeymapFiles`].
+    pub fn project_meta(file_ix: usize) -> KeyBindingMetaIndex {
+        KeyBindingMetaIndex(Self::PROJECT.0 + file_ix as u32)
+    }
+
+    /// The project keymap file a binding with this meta index comes from.
+    pub fn project_keymap_file(index: KeyBindingMetaIndex, cx: &App) -> Option<Arc<Path>> {
+        let file_ix = index.0.checked_sub(Self::PROJECT.0)?;
+        cx.try_global::<ProjectKeymapFiles>()?
+            .0
+            .get(file_ix as usize)
+            .cloned()
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            KeybindSource::Project => 0,
+            KeybindSource::User => 1,
+            KeybindSource::Vim => 2,
+            KeybindSource::Base => 3,
+            KeybindSource::Default => 4,
+            KeybindSource::Unknown => 5,
+        }
+    }
 }
 
+impl PartialOrd for KeybindSource {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for KeybindSource {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.precedence().cmp(&other.precedence())
+    }
+}
+
+/// The project keymap files, whose bindings are loaded above the user keymap, in the order of
+/// their meta indices (see [`KeybindSource::project_meta`]).
+#[derive(Default)]
+pub struct ProjectKeymapFiles(pub Vec<Arc<Path>>);
+
+impl Global for ProjectKeymapFiles {}
+
 impl From<KeyBindingMetaIndex> for KeybindSource {
     fn from(index: KeyBindingMetaIndex) -> Self {
         Self::from_meta(index)
@@ -1515,6 +1580,24 @@ mod tests {
         KeymapFile::parse(json).unwrap();
     }
 
+    #[test]
+    fn project_keybind_sources_take_precedence_over_user() {
+        for file_ix in 0..3 {
+            let meta = KeybindSource::project_meta(file_ix);
+            assert_eq!(KeybindSource::from_meta(meta), KeybindSource::Project);
+        }
+        for source in [
+            KeybindSource::User,
+            KeybindSource::Vim,
+            KeybindSource::Base,
+            KeybindSource::Default,
+            KeybindSource::Unknown,
+        ] {
+            assert_eq!(KeybindSource::from_meta(source.meta()), source);
+            assert!(KeybindSource::Project < source);
+        }
+    }
+
     #[gpui::test]
     fn keymap_section_unbinds_are_loaded_before_bindings(cx: &mut App) {
         let key_bindings = match KeymapFile::load(
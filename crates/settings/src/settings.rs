@@ -39,7 +39,7 @@ pub use editorconfig_store::{
 };
 pub use keymap_file::{
     KeyBindingValidator, KeyBindingValidatorRegistration, KeybindSource, KeybindUpdateOperation,
-    KeybindUpdateTarget, KeymapFile, KeymapFileLoadResult,
+    KeybindUpdateTarget, KeymapFile, KeymapFileLoadResult, ProjectKeymapFiles,
 };
 pub use settings_file::*;
 pub use settings_json::*;
@@ -171,6 +171,10 @@ pub fn initial_keymap_content() -> Cow<'static, str> {
     asset_str::<SettingsAssets>("keymaps/initial.json")
 }
 
+pub fn initial_project_keymap_content() -> Cow<'static, str> {
+    asset_str::<SettingsAssets>("keymaps/initial_local.json")
+}
+
 pub fn initial_tasks_content() -> Cow<'static, str> {
     asset_str::<SettingsAssets>("settings/initial_tasks.json")
 }
@@ -1,6 +1,6 @@
 use crate::*;
 use anyhow::{Context as _, Result, anyhow};
-use collections::HashMap;
+use collections::{BTreeMap, HashMap};
 use fs::Fs;
 use gpui::Rgba;
 use paths::{cursor_settings_file_paths, vscode_settings_file_paths};
@@ -184,6 +184,7 @@ impl VsCodeSettings {
             editor: self.editor_settings_content(),
             extension: ExtensionSettingsContent::default(),
             file_finder: None,
+            file_history: self.file_history_settings_content(),
             git: self.git_settings_content(),
             git_panel: self.git_panel_settings_content(),
             global_lsp_settings: skip_default(GlobalLspSettingsContent {
@@ -191,21 +192,26 @@ impl VsCodeSettings {
                 ..GlobalLspSettingsContent::default()
             }),
             helix_mode: None,
+            hex_editor: None,
             image_viewer: None,
             journal: None,
+            keyboard_macros: None,
             language_models: None,
+            large_file_viewer: self.large_file_viewer_settings_content(),
             line_indicator_format: None,
             log: None,
             message_editor: None,
             node: self.node_binary_settings(),
             notification_panel: None,
             outline_panel: self.outline_panel_settings_content(),
+            ports_panel: None,
             preview_tabs: self.preview_tabs_settings_content(),
             project: self.project_settings_content(),
             project_panel: self.project_panel_settings_content(),
             proxy: self.read_string("http.proxy"),
             remote: RemoteSettingsContent::default(),
             repl: None,
+            review_threads_panel: None,
             server_url: None,
             session: None,
             status_bar: self.status_bar_settings_content(),
@@ -539,13 +545,20 @@ impl VsCodeSettings {
             extend_list_on_newline: None,
             indent_list_on_tab: None,
             format_on_save: self.read_bool("editor.guides.formatOnSave").map(|b| {
-                if b {
-                    FormatOnSave::On
-                } else {
+                if !b {
                     FormatOnSave::Off
+                } else if matches!(
+                    self.read_str("editor.formatOnSaveMode"),
+                    Some("modifications" | "modificationsIfAvailable")
+                ) {
+                    FormatOnSave::Modified
+                } else {
+                    FormatOnSave::On
                 }
             }),
             formatter: None,
+            linters: None,
+            spell_check: None,
             hard_tabs: self.read_bool("editor.insertSpaces").map(|v| !v),
             indent_guides: skip_default(IndentGuideSettingsContent {
                 enabled: self.read_bool("editor.guides.indentation"),
@@ -651,6 +664,22 @@ impl VsCodeSettings {
         })
     }
 
+    fn file_history_settings_content(&self) -> Option<FileHistorySettingsContent> {
+        skip_default(FileHistorySettingsContent {
+            enabled: self.read_bool("workbench.localHistory.enabled"),
+            max_entries_per_file: self.read_usize("workbench.localHistory.maxFileEntries"),
+            max_file_size_kb: self.read_u64("workbench.localHistory.maxFileSize"),
+            ..Default::default()
+        })
+    }
+
+    fn large_file_viewer_settings_content(&self) -> Option<LargeFileViewerSettingsContent> {
+        skip_default(LargeFileViewerSettingsContent {
+            threshold_mb: self.read_u64("workbench.editorLargeFileConfirmation"),
+            ..Default::default()
+        })
+    }
+
     fn node_binary_settings(&self) -> Option<NodeBinarySettings> {
         // this just sets the binary name instead of a full path so it relies on path lookup
         // resolving to the one you want
@@ -809,6 +838,10 @@ impl VsCodeSettings {
             auto_open: None,
             diagnostic_badges: None,
             git_status_indicator: None,
+            file_nesting: skip_default(ProjectPanelFileNestingSettings {
+                enabled: self.read_bool("explorer.fileNesting.enabled"),
+                patterns: self.file_nesting_patterns(),
+            }),
         };
 
         if let (Some(false), Some(false)) = (
@@ -822,6 +855,25 @@ impl VsCodeSettings {
         skip_default(project_panel_settings)
     }
 
+    fn file_nesting_patterns(&self) -> Option<BTreeMap<String, Vec<String>>> {
+        let patterns = self
+            .read_value("explorer.fileNesting.patterns")?
+            .as_object()?
+            .iter()
+            .filter_map(|(parent, children)| {
+                let children = children
+                    .as_str()?
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|child| !child.is_empty())
+                    .map(ToOwned::to_owned)
+                    .collect();
+                Some((parent.clone(), children))
+            })
+            .collect();
+        Some(patterns)
+    }
+
     fn telemetry_settings_content(&self) -> Option<TelemetrySettingsContent> {
         self.read_enum("telemetry.telemetryLevel", |level| {
             let (metrics, diagnostics) = match level {
@@ -880,6 +932,8 @@ impl VsCodeSettings {
             scroll_multiplier: None,
             toolbar: None,
             show_count_badge: None,
+            shell_integration: self.read_bool("terminal.integrated.shellIntegration.enabled"),
+            inline_images: self.read_bool("terminal.integrated.enableImages"),
         })
     }
 
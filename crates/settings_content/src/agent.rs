@@ -150,6 +150,22 @@ pub struct AgentSettingsContent {
     /// `always_confirm`) match against the tool's text input (command, path,
     /// URL, etc.).
     pub tool_permissions: Option<ToolPermissionsContent>,
+    /// Commands to run at points in the native agent's lifecycle.
+    ///
+    /// Each hook receives a JSON description of the event on stdin and may
+    /// print a JSON response on stdout to block a tool call, rewrite its
+    /// input, or add context for the model.
+    pub hooks: Option<AgentHooksContent>,
+    /// Limits on how many tokens, or how much estimated cost, the native agent
+    /// may spend per thread and per day.
+    pub budgets: Option<AgentBudgetsContent>,
+    /// Prices used to estimate the cost of language model requests. When
+    /// estimating the cost of a request, prices will be taken from the last
+    /// entry in this list that matches the model's provider and name.
+    ///
+    /// Default: []
+    #[serde(default)]
+    pub model_pricing: Vec<LanguageModelPricing>,
 }
 
 impl AgentSettingsContent {
@@ -240,6 +256,29 @@ pub struct AgentProfileContent {
     pub context_servers: IndexMap<Arc<str>, ContextServerPresetContent>,
     /// The default language model selected when using this profile.
     pub default_model: Option<LanguageModelSelection>,
+    /// Restrictions applied to commands run by the terminal tool.
+    pub terminal_sandbox: Option<TerminalSandboxContent>,
+    /// Budget shared by all threads using this profile, reset at local midnight.
+    pub budget: Option<AgentBudgetContent>,
+}
+
+#[with_fallible_options]
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct TerminalSandboxContent {
+    /// Whether to run terminal tool commands in an OS-level sandbox that only
+    /// allows writes inside the project's worktrees. Only supported on Linux.
+    ///
+    /// Default: false
+    pub enabled: Option<bool>,
+    /// Whether sandboxed commands may open network connections.
+    ///
+    /// Default: false
+    pub allow_network: Option<bool>,
+    /// Directories, in addition to the project's worktrees and the system's
+    /// temporary directory, that sandboxed commands may write to.
+    ///
+    /// Default: []
+    pub additional_writable_paths: Option<Vec<PathBuf>>,
 }
 
 #[with_fallible_options]
@@ -296,6 +335,25 @@ pub struct LanguageModelParameters {
     pub temperature: Option<f32>,
 }
 
+#[with_fallible_options]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, MergeFrom, PartialEq)]
+pub struct LanguageModelPricing {
+    pub provider: Option<LanguageModelProviderSetting>,
+    pub model: Option<String>,
+    /// Cost of one million input tokens, in US dollars.
+    pub input_cost_per_million: Option<f64>,
+    /// Cost of one million output tokens, in US dollars.
+    pub output_cost_per_million: Option<f64>,
+    /// Cost of one million tokens read from the prompt cache, in US dollars.
+    ///
+    /// Default: the input token cost
+    pub cache_read_cost_per_million: Option<f64>,
+    /// Cost of one million tokens written to the prompt cache, in US dollars.
+    ///
+    /// Default: the input token cost
+    pub cache_write_cost_per_million: Option<f64>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, MergeFrom)]
 pub struct LanguageModelProviderSetting(pub String);
 
@@ -559,6 +617,102 @@ pub struct ToolRegexRule {
     pub case_sensitive: Option<bool>,
 }
 
+#[with_fallible_options]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct AgentBudgetsContent {
+    /// Budget for each thread, across all of its turns.
+    pub thread: Option<AgentBudgetContent>,
+    /// Budget for all threads combined, reset at local midnight.
+    pub daily: Option<AgentBudgetContent>,
+}
+
+#[with_fallible_options]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct AgentBudgetContent {
+    /// Maximum number of tokens, including cached tokens, that may be used.
+    pub max_tokens: Option<u64>,
+    /// Maximum estimated cost in US dollars. Costs are estimated from
+    /// `agent.model_pricing`, or from the prices reported by the provider.
+    pub max_cost: Option<f64>,
+    /// Fraction of the budget at which to warn that it is running out.
+    ///
+    /// Default: 0.8
+    pub warn_at: Option<f32>,
+    /// What to do once the budget is used up.
+    ///
+    /// Default: pause
+    pub action: Option<AgentBudgetAction>,
+}
+
+#[derive(
+    Copy,
+    Clone,
+    Default,
+    Debug,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    MergeFrom,
+    PartialEq,
+    Eq,
+    strum::VariantArray,
+    strum::VariantNames,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum AgentBudgetAction {
+    /// Keep going, showing a warning.
+    Warn,
+    /// Stop before the next request until the user chooses to continue.
+    #[default]
+    Pause,
+    /// Stop, refusing to make further requests until the budget resets.
+    Stop,
+}
+
+#[with_fallible_options]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct AgentHooksContent {
+    /// Hooks run after the model requests a tool call, before the tool runs.
+    /// These can deny the call or replace its input.
+    /// Default: []
+    pub pre_tool_use: Option<Vec<AgentHookContent>>,
+
+    /// Hooks run after a tool call finishes, with its output.
+    /// These can add context for the model to see alongside the tool result.
+    /// Default: []
+    pub post_tool_use: Option<Vec<AgentHookContent>>,
+
+    /// Hooks run when the agent finishes its turn.
+    /// Default: []
+    pub stop: Option<Vec<AgentHookContent>>,
+
+    /// Hooks run when a user message is submitted, before it is sent to the model.
+    /// These can block the message or add context to it.
+    /// Default: []
+    pub user_prompt_submit: Option<Vec<AgentHookContent>>,
+}
+
+#[with_fallible_options]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct AgentHookContent {
+    /// The command to run.
+    #[serde(default)]
+    pub command: String,
+
+    /// Arguments to pass to the command.
+    /// Default: []
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+
+    /// Regex matched against the tool name. Only used by `pre_tool_use` and
+    /// `post_tool_use` hooks; when unset, the hook runs for every tool.
+    pub matcher: Option<String>,
+
+    /// How long to wait for the command before giving up on it, in seconds.
+    /// Default: 60
+    pub timeout: Option<u64>,
+}
+
 #[derive(
     Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema, MergeFrom,
 )]
@@ -457,6 +457,13 @@ pub struct LanguageSettingsContent {
     ///
     /// Default: auto
     pub formatter: Option<FormatterList>,
+    /// External commands whose output is reported as diagnostics, for linters
+    /// that don't have a language server.
+    ///
+    /// Default: []
+    pub linters: Option<Vec<Linter>>,
+    /// Spell checking of comments, strings, and prose.
+    pub spell_check: Option<SpellCheckSettingsContent>,
     /// Zed's Prettier integration settings.
     /// Allows to enable/disable formatting with Prettier
     /// and configure default Prettier, used when no project-level Prettier installation is found.
@@ -693,6 +700,20 @@ pub struct JsxTagAutoCloseSettingsContent {
     pub enabled: Option<bool>,
 }
 
+#[with_fallible_options]
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, MergeFrom)]
+pub struct SpellCheckSettingsContent {
+    /// Whether to check the spelling of comments, strings, and prose.
+    ///
+    /// Default: false
+    pub enabled: Option<bool>,
+    /// The Hunspell dictionaries to check against, either by name (such as
+    /// `en_US`) or as a path to a `.dic` file.
+    ///
+    /// Default: ["en_US"]
+    pub dictionaries: Option<Vec<String>>,
+}
+
 /// The settings for inlay hints.
 #[with_fallible_options]
 #[derive(Clone, Default, Debug, Serialize, Deserialize, JsonSchema, MergeFrom, PartialEq, Eq)]
@@ -880,7 +901,6 @@ pub struct PrettierSettingsContent {
     pub options: Option<HashMap<String, serde_json::Value>>,
 }
 
-/// TODO: this should just be a bool
 /// Controls the behavior of formatting files when they are saved.
 #[derive(
     Debug,
@@ -901,6 +921,11 @@ pub enum FormatOnSave {
     On,
     /// Files should not be formatted on save.
     Off,
+    /// Only the lines changed since the last git commit should be formatted on save.
+    ///
+    /// Formatters without range support are skipped. Files outside of a git
+    /// repository are formatted in full.
+    Modified,
 }
 
 /// Controls which formatters should be used when formatting code.
@@ -926,6 +951,93 @@ impl AsRef<[Formatter]> for FormatterList {
     }
 }
 
+/// An external command that checks a buffer and reports problems as diagnostics.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+pub struct Linter {
+    /// The name of the linter, shown as the source of its diagnostics.
+    pub name: String,
+    /// The program to run.
+    pub command: String,
+    /// The arguments to pass to the program. `{buffer_path}` is replaced with
+    /// the path of the buffer, and `{temp_path}` with the path of the
+    /// temporary file when `input` is `temp_file`.
+    #[serde(default)]
+    pub arguments: Vec<String>,
+    /// When to run the linter.
+    ///
+    /// Default: save
+    #[serde(default)]
+    pub run_on: LinterTrigger,
+    /// How the buffer's contents are passed to the linter.
+    ///
+    /// Default: stdin
+    #[serde(default)]
+    pub input: LinterInput,
+    /// How to parse the linter's output into diagnostics.
+    pub output: LinterOutput,
+    /// How long to wait after the last edit before running the linter, in
+    /// milliseconds, when `run_on` is `change`.
+    ///
+    /// Default: 500
+    #[serde(default = "default_linter_debounce_ms")]
+    pub debounce_ms: u64,
+    /// How long to let the linter run before killing it, in milliseconds.
+    ///
+    /// Default: 30000
+    #[serde(default = "default_linter_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_linter_debounce_ms() -> u64 {
+    500
+}
+
+fn default_linter_timeout_ms() -> u64 {
+    30_000
+}
+
+/// When to run a linter.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LinterTrigger {
+    /// Run the linter when the buffer is opened and saved.
+    #[default]
+    Save,
+    /// Run the linter when the buffer is opened and after it's edited.
+    Change,
+}
+
+/// How a linter receives the buffer's contents.
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LinterInput {
+    /// Write the contents to the linter's standard input.
+    #[default]
+    Stdin,
+    /// Write the contents to a temporary file with the same name as the buffer's
+    /// file. Its path replaces `{temp_path}` in the arguments, or is appended
+    /// to them if there's no `{temp_path}`.
+    TempFile,
+}
+
+/// How a linter's standard output is parsed into diagnostics.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LinterOutput {
+    /// A JSON array of objects with `line`, `column`, `end_line`, `end_column`,
+    /// `severity`, `code`, and `message` fields, or an object containing such
+    /// an array in a `diagnostics` or `comments` field. Lines and columns are
+    /// 1-based. Camel-cased field names and `level` in place of `severity` are
+    /// also accepted, which covers tools like shellcheck and hadolint.
+    Json,
+    /// A SARIF log.
+    Sarif,
+    /// A regular expression matched against each line of output, with named
+    /// capture groups `line`, `message`, and optionally `column`, `end_line`,
+    /// `end_column`, `severity`, and `code`.
+    Regex(String),
+}
+
 /// Controls which formatter should be used when formatting code. If there are multiple formatters, they are executed in the order of declaration.
 #[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema, MergeFrom)]
 #[serde(rename_all = "snake_case")]
@@ -101,6 +101,9 @@ pub struct SettingsContent {
     /// Settings related to the file finder.
     pub file_finder: Option<FileFinderSettingsContent>,
 
+    /// Settings related to the local history of file contents.
+    pub file_history: Option<FileHistorySettingsContent>,
+
     pub git_panel: Option<GitPanelSettingsContent>,
 
     pub tabs: Option<ItemSettingsContent>,
@@ -141,9 +144,15 @@ pub struct SettingsContent {
     /// Common language server settings.
     pub global_lsp_settings: Option<GlobalLspSettingsContent>,
 
+    /// The settings for the hex editor.
+    pub hex_editor: Option<HexEditorSettingsContent>,
+
     /// The settings for the image viewer.
     pub image_viewer: Option<ImageViewerSettingsContent>,
 
+    /// The settings for viewing files too large to open in an editor.
+    pub large_file_viewer: Option<LargeFileViewerSettingsContent>,
+
     pub repl: Option<ReplSettingsContent>,
 
     /// Whether or not to enable Helix mode.
@@ -153,6 +162,12 @@ pub struct SettingsContent {
 
     pub journal: Option<JournalSettingsContent>,
 
+    /// Keyboard macros by name. Each can be bound in the keymap with
+    /// `["keyboard_macros::Run", { "name": "..." }]`.
+    ///
+    /// Example: {"keyboard_macros": {"quote-line": ["editor::MoveToBeginningOfLine", {"text": "> "}]}}
+    pub keyboard_macros: Option<HashMap<String, Vec<KeyboardMacroStep>>>,
+
     /// A map of log scopes to the desired log level.
     /// Useful for filtering out noisy logs or enabling more verbose logging.
     ///
@@ -176,6 +191,12 @@ pub struct SettingsContent {
     /// Configuration for the Notification Panel
     pub notification_panel: Option<NotificationPanelSettingsContent>,
 
+    /// Configuration for the panel listing forwarded ports of remote projects.
+    pub ports_panel: Option<PanelSettingsContent>,
+
+    /// Configuration for the panel listing review threads of shared projects.
+    pub review_threads_panel: Option<PanelSettingsContent>,
+
     pub proxy: Option<String>,
 
     /// The URL of the Zed server to connect to.
@@ -867,6 +888,46 @@ pub struct JournalSettingsContent {
     pub hour_format: Option<HourFormat>,
 }
 
+/// A step of a keyboard macro.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+#[serde(untagged)]
+pub enum KeyboardMacroStep {
+    /// Text typed into the editor.
+    Text { text: String },
+    /// An action, written as in the keymap: either its name, or an array of
+    /// its name and its arguments.
+    Action(serde_json::Value),
+}
+
+/// Settings for the local history of file contents, which is kept
+/// independently of version control.
+#[with_fallible_options]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, MergeFrom, PartialEq)]
+pub struct FileHistorySettingsContent {
+    /// Whether to record snapshots of files when they are saved, reloaded
+    /// from disk, or edited by the agent.
+    ///
+    /// Default: true
+    pub enabled: Option<bool>,
+    /// The maximum number of snapshots kept for each file.
+    ///
+    /// Default: 50
+    pub max_entries_per_file: Option<usize>,
+    /// Files larger than this many kilobytes are not recorded.
+    ///
+    /// Default: 1024
+    pub max_file_size_kb: Option<u64>,
+    /// Snapshots older than this many days are removed.
+    ///
+    /// Default: 30
+    pub max_age_days: Option<u64>,
+    /// The maximum size in megabytes of the stored history across all files.
+    /// The oldest snapshots are removed first when it is exceeded.
+    ///
+    /// Default: 256
+    pub max_total_size_mb: Option<u64>,
+}
+
 #[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, MergeFrom, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum HourFormat {
@@ -997,6 +1058,34 @@ pub struct ImageViewerSettingsContent {
     pub unit: Option<ImageFileSizeUnit>,
 }
 
+/// The settings for the hex editor.
+#[with_fallible_options]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, MergeFrom, Default, PartialEq)]
+pub struct HexEditorSettingsContent {
+    /// Extensions of files that are opened in the hex editor instead of an
+    /// editor, matched case-insensitively. Other binary files can be opened
+    /// in it with `hex_editor: open in hex editor`.
+    ///
+    /// Default: ["bin", "exe", "dll", "so", "dylib", "o", "obj", "a", "lib", "class", "wasm", "iso", "img", "dat"]
+    pub file_types: Option<Vec<String>>,
+}
+
+/// The settings for the large file viewer.
+#[with_fallible_options]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, MergeFrom, Default, PartialEq)]
+pub struct LargeFileViewerSettingsContent {
+    /// Files at least this large, in megabytes, are opened read-only in the
+    /// large file viewer instead of an editor. Set to 0 to always use an editor.
+    ///
+    /// Default: 256
+    pub threshold_mb: Option<u64>,
+    /// Whether the large file viewer follows content appended to the file,
+    /// scrolling to the end as it grows.
+    ///
+    /// Default: false
+    pub follow: Option<bool>,
+}
+
 #[with_fallible_options]
 #[derive(
     Clone,
@@ -1028,6 +1117,11 @@ pub struct RemoteSettingsContent {
     pub dev_container_connections: Option<Vec<DevContainerConnection>>,
     pub read_ssh_config: Option<bool>,
     pub use_podman: Option<bool>,
+    /// Whether to forward ports on the remote host when a process starts listening on them.
+    /// Ports listed in `forwardPorts` of a dev container configuration are always forwarded.
+    ///
+    /// Default: true
+    pub auto_forward_ports: Option<bool>,
 }
 
 #[with_fallible_options]
@@ -175,6 +175,16 @@ pub struct TerminalSettingsContent {
     ///
     /// Default: false
     pub show_count_badge: Option<bool>,
+    /// Whether to set up shell integration for bash, zsh, fish and nushell, so that
+    /// the terminal knows where each command and its output are, and how it exited.
+    ///
+    /// Default: true
+    pub shell_integration: Option<bool>,
+    /// Whether to display images that programs print with the Kitty graphics protocol or
+    /// as Sixel images.
+    ///
+    /// Default: true
+    pub inline_images: Option<bool>,
 }
 
 /// Shell configuration to open the terminal with.
@@ -1,6 +1,6 @@
 use std::num::NonZeroUsize;
 
-use collections::HashMap;
+use collections::{BTreeMap, HashMap};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings_macros::{MergeFrom, with_fallible_options};
@@ -747,6 +747,24 @@ pub struct ProjectPanelSettingsContent {
     ///
     /// Default: false
     pub git_status_indicator: Option<bool>,
+    /// Settings for nesting related files under a parent file in the project panel.
+    pub file_nesting: Option<ProjectPanelFileNestingSettings>,
+}
+
+#[with_fallible_options]
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, MergeFrom, PartialEq, Eq, Default)]
+pub struct ProjectPanelFileNestingSettings {
+    /// Whether to nest related files under a parent file in the project panel.
+    ///
+    /// Default: false
+    pub enabled: Option<bool>,
+    /// Patterns that nest files under a parent file in the same directory, keyed
+    /// by the name of the parent file. A `*` in the key matches any text, which
+    /// the nested file patterns can refer to as `${capture}`. Nested file patterns
+    /// can also use `*` to match any text. Set a pattern to `[]` to disable it.
+    ///
+    /// Default: {"*.ts": ["${capture}.js", "${capture}.d.ts", ...], "Cargo.toml": ["Cargo.lock"], ...}
+    pub patterns: Option<BTreeMap<String, Vec<String>>>,
 }
 
 #[derive(
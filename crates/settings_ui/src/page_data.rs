@@ -6506,7 +6506,7 @@ fn terminal_page() -> SettingsPage {
         ]
     }
 
-    fn advanced_settings_section() -> [SettingsPageItem; 3] {
+    fn advanced_settings_section() -> [SettingsPageItem; 5] {
         [
             SettingsPageItem::SectionHeader("Advanced Settings"),
             SettingsPageItem::SettingItem(SettingItem {
@@ -6553,6 +6553,46 @@ fn terminal_page() -> SettingsPage {
                 metadata: None,
                 files: USER,
             }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Shell Integration",
+                description: "Whether to set up shell integration so the terminal knows where each command and its output are, and how it exited.",
+                field: Box::new(SettingField {
+                    json_path: Some("terminal.shell_integration"),
+                    pick: |settings_content| {
+                        settings_content
+                            .terminal
+                            .as_ref()?
+                            .shell_integration
+                            .as_ref()
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .terminal
+                            .get_or_insert_default()
+                            .shell_integration = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
+            SettingsPageItem::SettingItem(SettingItem {
+                title: "Inline Images",
+                description: "Whether to display images that programs print with the Kitty graphics protocol or as Sixel images.",
+                field: Box::new(SettingField {
+                    json_path: Some("terminal.inline_images"),
+                    pick: |settings_content| {
+                        settings_content.terminal.as_ref()?.inline_images.as_ref()
+                    },
+                    write: |settings_content, value| {
+                        settings_content
+                            .terminal
+                            .get_or_insert_default()
+                            .inline_images = value;
+                    },
+                }),
+                metadata: None,
+                files: USER,
+            }),
         ]
     }
 
@@ -0,0 +1,670 @@
+//! A reader for Hunspell dictionaries.
+//!
+//! A Hunspell dictionary is a pair of files: a `.dic` file listing word stems
+//! and the affix flags that apply to them, and an `.aff` file defining those
+//! affixes. Stems are expanded with their prefixes and suffixes up front, so
+//! checking a word is a set lookup. Compounding rules are not supported.
+
+use anyhow::{Context as _, Result, anyhow};
+use collections::{HashMap, HashSet};
+
+const MAX_SUGGESTIONS: usize = 5;
+const DEFAULT_TRY_CHARS: &str = "esianrtolcdugmphbyfvkwzxjq";
+
+type Flag = u32;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum FlagFormat {
+    #[default]
+    Single,
+    Long,
+    Numeric,
+}
+
+impl FlagFormat {
+    fn parse(self, flags: &str) -> Vec<Flag> {
+        match self {
+            FlagFormat::Single => flags.chars().map(|c| c as Flag).collect(),
+            FlagFormat::Long => {
+                let chars = flags.chars().collect::<Vec<_>>();
+                chars
+                    .chunks(2)
+                    .map(|pair| {
+                        let high = pair[0] as Flag;
+                        let low = pair.get(1).map_or(0, |c| *c as Flag);
+                        (high << 16) | low
+                    })
+                    .collect()
+            }
+            FlagFormat::Numeric => flags
+                .split(',')
+                .filter_map(|flag| flag.trim().parse().ok())
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum CharClass {
+    Any,
+    Char(char),
+    OneOf(Vec<char>),
+    NoneOf(Vec<char>),
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharClass::Any => true,
+            CharClass::Char(expected) => c == *expected,
+            CharClass::OneOf(chars) => chars.contains(&c),
+            CharClass::NoneOf(chars) => !chars.contains(&c),
+        }
+    }
+}
+
+/// The condition a stem must satisfy for an affix to apply, such as `[^aeiou]y`.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Condition(Vec<CharClass>);
+
+impl Condition {
+    fn parse(pattern: &str) -> Self {
+        if pattern == "." {
+            return Self::default();
+        }
+        let mut classes = Vec::new();
+        let mut chars = pattern.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '.' => classes.push(CharClass::Any),
+                '[' => {
+                    let mut members = Vec::new();
+                    let mut negated = false;
+                    for (ix, c) in chars.by_ref().enumerate() {
+                        match c {
+                            '^' if ix == 0 => negated = true,
+                            ']' => break,
+                            c => members.push(c),
+                        }
+                    }
+                    classes.push(if negated {
+                        CharClass::NoneOf(members)
+                    } else {
+                        CharClass::OneOf(members)
+                    });
+                }
+                c => classes.push(CharClass::Char(c)),
+            }
+        }
+        Self(classes)
+    }
+
+    fn matches_start(&self, word: &[char]) -> bool {
+        word.len() >= self.0.len() && self.0.iter().zip(word).all(|(class, c)| class.matches(*c))
+    }
+
+    fn matches_end(&self, word: &[char]) -> bool {
+        word.len() >= self.0.len()
+            && self
+                .0
+                .iter()
+                .rev()
+                .zip(word.iter().rev())
+                .all(|(class, c)| class.matches(*c))
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AffixKind {
+    Prefix,
+    Suffix,
+}
+
+#[derive(Clone, Debug)]
+struct AffixRule {
+    strip: String,
+    add: String,
+    continuation: Vec<Flag>,
+    condition: Condition,
+}
+
+#[derive(Clone, Debug)]
+struct AffixClass {
+    kind: AffixKind,
+    cross_product: bool,
+    rules: Vec<AffixRule>,
+}
+
+impl AffixClass {
+    fn apply<'a>(&'a self, word: &'a str) -> impl Iterator<Item = (String, &'a [Flag])> + 'a {
+        let chars = word.chars().collect::<Vec<_>>();
+        self.rules.iter().filter_map(move |rule| {
+            let form = match self.kind {
+                AffixKind::Prefix => {
+                    if !rule.condition.matches_start(&chars) {
+                        return None;
+                    }
+                    let rest = word.strip_prefix(rule.strip.as_str())?;
+                    format!("{}{}", rule.add, rest)
+                }
+                AffixKind::Suffix => {
+                    if !rule.condition.matches_end(&chars) {
+                        return None;
+                    }
+                    let rest = word.strip_suffix(rule.strip.as_str())?;
+                    format!("{}{}", rest, rule.add)
+                }
+            };
+            (!form.is_empty()).then_some((form, rule.continuation.as_slice()))
+        })
+    }
+}
+
+/// The parts of an `.aff` file that are needed to expand and check words.
+#[derive(Default)]
+struct AffixFile {
+    flag_format: FlagFormat,
+    flag_aliases: Vec<Vec<Flag>>,
+    try_chars: Vec<char>,
+    replacements: Vec<(String, String)>,
+    affixes: HashMap<Flag, AffixClass>,
+    forbidden_flag: Option<Flag>,
+    no_suggest_flag: Option<Flag>,
+    need_affix_flag: Option<Flag>,
+    only_in_compound_flag: Option<Flag>,
+}
+
+impl AffixFile {
+    fn parse(source: &str) -> Result<Self> {
+        let mut aff = Self::default();
+        let mut seen_alias_count = false;
+        let mut lines = source.lines();
+        while let Some(line) = lines.next() {
+            let mut fields = line.split_whitespace();
+            let Some(keyword) = fields.next() else {
+                continue;
+            };
+            match keyword {
+                "FLAG" => {
+                    aff.flag_format = match fields.next() {
+                        Some("long") => FlagFormat::Long,
+                        Some("num") => FlagFormat::Numeric,
+                        _ => FlagFormat::Single,
+                    }
+                }
+                "AF" => {
+                    // The first `AF` line holds the number of aliases.
+                    if !seen_alias_count {
+                        seen_alias_count = true;
+                    } else if let Some(flags) = fields.next() {
+                        aff.flag_aliases.push(aff.flag_format.parse(flags));
+                    }
+                }
+                "TRY" => aff.try_chars = fields.next().unwrap_or_default().chars().collect(),
+                "REP" => {
+                    if let (Some(from), Some(to)) = (fields.next(), fields.next()) {
+                        aff.replacements
+                            .push((from.replace('_', " "), to.replace('_', " ")));
+                    }
+                }
+                "FORBIDDENWORD" => aff.forbidden_flag = aff.single_flag(fields.next()),
+                "NOSUGGEST" => aff.no_suggest_flag = aff.single_flag(fields.next()),
+                "NEEDAFFIX" | "PSEUDOROOT" => aff.need_affix_flag = aff.single_flag(fields.next()),
+                "ONLYINCOMPOUND" => aff.only_in_compound_flag = aff.single_flag(fields.next()),
+                "PFX" | "SFX" => {
+                    let kind = if keyword == "PFX" {
+                        AffixKind::Prefix
+                    } else {
+                        AffixKind::Suffix
+                    };
+                    let flag = aff
+                        .single_flag(fields.next())
+                        .with_context(|| format!("missing affix flag in {line:?}"))?;
+                    let cross_product = fields.next() == Some("Y");
+                    let count = fields
+                        .next()
+                        .and_then(|count| count.parse::<usize>().ok())
+                        .with_context(|| format!("invalid affix header {line:?}"))?;
+                    // The count comes from the file, so it's only trusted as far as
+                    // there are lines to read.
+                    let mut rules = Vec::new();
+                    for _ in 0..count {
+                        let line = lines
+                            .next()
+                            .with_context(|| format!("missing rules for affix {keyword}"))?;
+                        if let Some(rule) = aff.parse_affix_rule(line) {
+                            rules.push(rule);
+                        }
+                    }
+                    aff.affixes.insert(
+                        flag,
+                        AffixClass {
+                            kind,
+                            cross_product,
+                            rules,
+                        },
+                    );
+                }
+                _ => {}
+            }
+        }
+        Ok(aff)
+    }
+
+    fn parse_affix_rule(&self, line: &str) -> Option<AffixRule> {
+        let mut fields = line.split_whitespace().skip(2);
+        let strip = fields.next()?;
+        let add = fields.next()?;
+        let condition = fields.next().unwrap_or(".");
+        let (add, continuation) = match add.split_once('/') {
+            Some((add, flags)) => (add, self.parse_flags(flags)),
+            None => (add, Vec::new()),
+        };
+        let unless_zero = |s: &str| {
+            if s == "0" {
+                String::new()
+            } else {
+                s.to_owned()
+            }
+        };
+        Some(AffixRule {
+            strip: unless_zero(strip),
+            add: unless_zero(add),
+            continuation,
+            condition: Condition::parse(condition),
+        })
+    }
+
+    fn single_flag(&self, flag: Option<&str>) -> Option<Flag> {
+        self.flag_format.parse(flag?).first().copied()
+    }
+
+    fn parse_flags(&self, flags: &str) -> Vec<Flag> {
+        if !self.flag_aliases.is_empty()
+            && let Ok(alias) = flags.parse::<usize>()
+        {
+            return self
+                .flag_aliases
+                .get(alias.wrapping_sub(1))
+                .cloned()
+                .unwrap_or_default();
+        }
+        self.flag_format.parse(flags)
+    }
+}
+
+/// A set of correctly spelled words, loaded from a Hunspell dictionary.
+#[derive(Default)]
+pub struct Dictionary {
+    words: HashSet<String>,
+    forbidden: HashSet<String>,
+    no_suggest: HashSet<String>,
+    try_chars: Vec<char>,
+    replacements: Vec<(String, String)>,
+}
+
+impl Dictionary {
+    /// Loads a dictionary from the contents of its `.aff` and `.dic` files.
+    pub fn load(aff: &[u8], dic: &[u8]) -> Result<Self> {
+        let encoding = String::from_utf8_lossy(aff).lines().find_map(|line| {
+            line.strip_prefix("SET ")
+                .map(|encoding| encoding.trim().to_owned())
+        });
+        let aff = AffixFile::parse(&decode(aff, encoding.as_deref()))?;
+        let dic = decode(dic, encoding.as_deref());
+
+        let mut dictionary = Dictionary {
+            try_chars: if aff.try_chars.is_empty() {
+                DEFAULT_TRY_CHARS.chars().collect()
+            } else {
+                aff.try_chars.clone()
+            },
+            replacements: aff.replacements.clone(),
+            ..Default::default()
+        };
+
+        let mut lines = dic.lines();
+        lines
+            .next()
+            .filter(|count| count.trim().parse::<usize>().is_ok())
+            .ok_or_else(|| anyhow!("dictionary is missing its word count"))?;
+        for line in lines {
+            let Some((stem, flags)) = parse_dic_line(line) else {
+                continue;
+            };
+            let flags = flags.map_or_else(Vec::new, |flags| aff.parse_flags(flags));
+            dictionary.add_stem(&aff, &stem, &flags);
+        }
+        Ok(dictionary)
+    }
+
+    fn add_stem(&mut self, aff: &AffixFile, stem: &str, flags: &[Flag]) {
+        let has_flag = |flag: Option<Flag>| flag.is_some_and(|flag| flags.contains(&flag));
+        if has_flag(aff.only_in_compound_flag) {
+            return;
+        }
+        if has_flag(aff.forbidden_flag) {
+            self.forbidden.insert(stem.to_owned());
+            return;
+        }
+        let target = if has_flag(aff.no_suggest_flag) {
+            &mut self.no_suggest
+        } else {
+            &mut self.words
+        };
+        if !has_flag(aff.need_affix_flag) {
+            target.insert(stem.to_owned());
+        }
+
+        let mut prefixed = Vec::new();
+        for flag in flags {
+            let Some(class) = aff.affixes.get(flag) else {
+                continue;
+            };
+            for (form, continuation) in class.apply(stem) {
+                if class.kind == AffixKind::Prefix && class.cross_product {
+                    prefixed.push(form.clone());
+                }
+                // Support a single level of continuation classes, which
+                // covers suffixes like `-ness` followed by `-es`.
+                for flag in continuation {
+                    if let Some(next) = aff.affixes.get(flag) {
+                        target.extend(next.apply(&form).map(|(form, _)| form));
+                    }
+                }
+                target.insert(form);
+            }
+        }
+
+        // Cross-product prefixes combine with cross-product suffixes.
+        for prefixed in prefixed {
+            for flag in flags {
+                if let Some(class) = aff.affixes.get(flag)
+                    && class.kind == AffixKind::Suffix
+                    && class.cross_product
+                {
+                    target.extend(class.apply(&prefixed).map(|(form, _)| form));
+                }
+            }
+        }
+    }
+
+    /// Adds a word, such as one from a user's personal dictionary.
+    pub fn add_word(&mut self, word: &str) {
+        self.words.insert(word.to_owned());
+    }
+
+    /// Returns whether the word is spelled correctly.
+    ///
+    /// Capitalized and upper case words are also checked in lower case, so
+    /// that words at the start of a sentence and in headings are accepted,
+    /// but proper nouns aren't accepted in lower case.
+    pub fn check(&self, word: &str) -> bool {
+        if self.forbidden.contains(word) {
+            return false;
+        }
+        if self.contains(word) {
+            return true;
+        }
+        match Case::of(word) {
+            Case::Lower | Case::Mixed => false,
+            Case::Capitalized => self.contains(&word.to_lowercase()),
+            Case::Upper => {
+                let lower = word.to_lowercase();
+                self.contains(&lower) || self.contains(&Case::Capitalized.apply(&lower))
+            }
+        }
+    }
+
+    fn contains(&self, word: &str) -> bool {
+        self.words.contains(word) || self.no_suggest.contains(word)
+    }
+
+    fn suggestable(&self, word: &str) -> bool {
+        !self.forbidden.contains(word)
+            && (self.words.contains(word)
+                || self.words.contains(&word.to_lowercase())
+                || (Case::of(word) == Case::Upper
+                    && self
+                        .words
+                        .contains(&Case::Capitalized.apply(&word.to_lowercase()))))
+    }
+
+    /// Returns up to five correctly spelled words that are close to the given
+    /// misspelled word, most likely first.
+    pub fn suggest(&self, word: &str) -> Vec<String> {
+        let case = Case::of(word);
+        let base = match case {
+            Case::Capitalized | Case::Upper => word.to_lowercase(),
+            Case::Lower | Case::Mixed => word.to_owned(),
+        };
+
+        let mut suggestions = Vec::new();
+        let mut push = |candidate: String| {
+            if suggestions.len() >= MAX_SUGGESTIONS {
+                return;
+            }
+            let candidate = case.apply(&candidate);
+            let valid = if let Some((first, second)) = candidate.split_once(' ') {
+                self.suggestable(first) && self.suggestable(second)
+            } else {
+                self.suggestable(&candidate)
+            };
+            if valid && candidate != word && !suggestions.contains(&candidate) {
+                suggestions.push(candidate);
+            }
+        };
+
+        for (from, to) in &self.replacements {
+            for (ix, _) in base.match_indices(from.as_str()) {
+                push(format!("{}{}{}", &base[..ix], to, &base[ix + from.len()..]));
+            }
+        }
+
+        let chars = base.chars().collect::<Vec<_>>();
+        let with = |f: &dyn Fn(&mut Vec<char>)| {
+            let mut chars = chars.clone();
+            f(&mut chars);
+            chars.into_iter().collect::<String>()
+        };
+        for ix in 0..chars.len().saturating_sub(1) {
+            push(with(&|chars| chars.swap(ix, ix + 1)));
+        }
+        for ix in 0..chars.len() {
+            push(with(&|chars| {
+                chars.remove(ix);
+            }));
+        }
+        for &c in &self.try_chars {
+            for ix in 0..chars.len() {
+                if chars[ix] != c {
+                    push(with(&|chars| chars[ix] = c));
+                }
+            }
+        }
+        for &c in &self.try_chars {
+            for ix in 0..=chars.len() {
+                push(with(&|chars| chars.insert(ix, c)));
+            }
+        }
+        for ix in 1..chars.len() {
+            push(with(&|chars| chars.insert(ix, ' ')));
+        }
+
+        suggestions
+    }
+}
+
+/// Splits a `.dic` line into its stem and flags, dropping any morphological
+/// fields. Slashes in the stem may be escaped with a backslash.
+fn parse_dic_line(line: &str) -> Option<(String, Option<&str>)> {
+    let entry = line.split(['\t', ' ']).next()?.trim();
+    if entry.is_empty() || entry.starts_with('#') {
+        return None;
+    }
+    let mut stem = String::new();
+    let mut chars = entry.char_indices();
+    while let Some((ix, c)) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some((_, escaped)) = chars.next() {
+                    stem.push(escaped);
+                }
+            }
+            '/' => return Some((stem, Some(&entry[ix + 1..]))),
+            c => stem.push(c),
+        }
+    }
+    Some((stem, None))
+}
+
+/// Decodes dictionary files, which may use a legacy encoding declared by
+/// the `SET` directive.
+fn decode(bytes: &[u8], encoding: Option<&str>) -> String {
+    match encoding.map(|encoding| encoding.to_ascii_uppercase()) {
+        Some(encoding) if encoding == "ISO8859-1" || encoding == "ISO-8859-1" => {
+            bytes.iter().map(|byte| *byte as char).collect()
+        }
+        _ => String::from_utf8_lossy(bytes)
+            .trim_start_matches('\u{feff}')
+            .to_owned(),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Case {
+    Lower,
+    Capitalized,
+    Upper,
+    Mixed,
+}
+
+impl Case {
+    fn of(word: &str) -> Self {
+        let mut letters = word.chars().filter(|c| c.is_alphabetic());
+        let Some(first) = letters.next() else {
+            return Case::Lower;
+        };
+        let (mut upper, mut lower) = (0, 0);
+        for c in letters {
+            if c.is_uppercase() {
+                upper += 1;
+            } else {
+                lower += 1;
+            }
+        }
+        match (first.is_uppercase(), upper, lower) {
+            (false, 0, _) => Case::Lower,
+            (true, 0, _) => Case::Capitalized,
+            (true, _, 0) => Case::Upper,
+            _ => Case::Mixed,
+        }
+    }
+
+    fn apply(self, word: &str) -> String {
+        match self {
+            Case::Lower | Case::Mixed => word.to_owned(),
+            Case::Upper => word.to_uppercase(),
+            Case::Capitalized => {
+                let mut chars = word.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AFF: &str = "\
+SET UTF-8
+TRY esianrtolcdugmphbyfvkwzxjq
+REP 1
+REP f ph
+NOSUGGEST !
+FORBIDDENWORD *
+
+PFX U Y 1
+PFX U 0 un .
+
+SFX S Y 3
+SFX S y ies [^aeiou]y
+SFX S 0 s [aeiou]y
+SFX S 0 s [^y]
+
+SFX N Y 1
+SFX N 0 ness/E .
+
+SFX E Y 1
+SFX E 0 es s
+";
+
+    const DIC: &str = "\
+7
+happy/UN
+day/S
+fly/S
+graph/S
+Paris
+and\\/or
+darn/!
+";
+
+    fn dictionary() -> Dictionary {
+        Dictionary::load(AFF.as_bytes(), DIC.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_affix_expansion() {
+        let dictionary = dictionary();
+        for word in [
+            "happy",
+            "unhappy",
+            "happyness",
+            "unhappyness",
+            "happynesses",
+            "days",
+            "flies",
+            "graphs",
+            "and/or",
+        ] {
+            assert!(dictionary.check(word), "{word} should be accepted");
+        }
+        for word in ["flys", "dayies", "unday", "happys"] {
+            assert!(!dictionary.check(word), "{word} should be rejected");
+        }
+    }
+
+    #[test]
+    fn test_malformed_affix_count() {
+        assert!(AffixFile::parse("PFX A Y 18446744073709551615\nPFX A 0 re .\n").is_err());
+        assert!(AffixFile::parse("SFX A Y many\n").is_err());
+    }
+
+    #[test]
+    fn test_case() {
+        let dictionary = dictionary();
+        assert!(dictionary.check("Happy"));
+        assert!(dictionary.check("HAPPY"));
+        assert!(dictionary.check("Paris"));
+        assert!(dictionary.check("PARIS"));
+        assert!(!dictionary.check("paris"));
+        assert!(!dictionary.check("hAppy"));
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let dictionary = dictionary();
+        assert_eq!(dictionary.suggest("hapy"), vec!["happy"]);
+        assert_eq!(dictionary.suggest("Dya"), vec!["Day"]);
+        assert_eq!(dictionary.suggest("grafs"), vec!["graphs"]);
+        assert_eq!(dictionary.suggest("flyday"), vec!["fly day"]);
+        // Words marked as not suggestable are accepted but never suggested.
+        assert!(dictionary.check("darn"));
+        assert!(dictionary.suggest("darm").is_empty());
+    }
+}
@@ -0,0 +1,609 @@
+//! Spell checking for comments, strings, and prose.
+//!
+//! Misspelled words are reported as diagnostics of their own source, and
+//! editors offer code actions to correct them or to add them to the user's or
+//! the project's dictionary.
+
+mod hunspell;
+mod words;
+
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{Context as _, Result, anyhow};
+use collections::{HashMap, HashSet};
+use editor::{CodeActionProvider, Editor, ExcerptId};
+use fs::Fs;
+use futures::{
+    FutureExt as _,
+    future::{Shared, join_all},
+};
+use gpui::{App, AppContext as _, Context, Entity, Global, Subscription, Task, WeakEntity, Window};
+use language::{
+    Buffer, BufferEvent, BufferSnapshot, DiagnosticSourceKind, LanguageServerId, ToOffset as _,
+    language_settings::LanguageSettings,
+};
+use project::{
+    CodeAction, File, LspAction, Project, ProjectTransaction, buffer_store::BufferStoreEvent,
+};
+use serde::{Deserialize, Serialize};
+use settings::SettingsStore;
+use text::BufferId;
+use util::ResultExt as _;
+use workspace::Workspace;
+
+pub use hunspell::Dictionary;
+
+const CODE_ACTION_PROVIDER_ID: &str = "spell_check";
+const DIAGNOSTIC_SOURCE: &str = "spell check";
+const CHECK_DEBOUNCE: Duration = Duration::from_millis(500);
+/// Buffers with more misspellings than this are probably not in the
+/// configured language, so reporting every one of them isn't useful.
+const MAX_MISSPELLINGS: usize = 1000;
+
+pub fn init(cx: &mut App) {
+    cx.set_global(LoadedDictionaries::default());
+
+    cx.observe_new(|workspace: &mut Workspace, window, cx| {
+        let Some(window) = window else {
+            return;
+        };
+        let project = workspace.project().clone();
+        if !project.read(cx).is_local() {
+            return;
+        }
+        let spell_checker = cx.new(|cx| SpellChecker::new(project, cx));
+        window
+            .subscribe(&cx.entity(), cx, move |_, event, window, cx| {
+                if let workspace::Event::ItemAdded { item } = event
+                    && let Some(editor) = item.act_as::<Editor>(cx)
+                {
+                    let provider = Rc::new(SpellCheckCodeActionProvider {
+                        spell_checker: spell_checker.downgrade(),
+                    });
+                    editor.update(cx, |editor, cx| {
+                        editor.add_code_action_provider(provider, window, cx)
+                    });
+                }
+            })
+            .detach();
+    })
+    .detach();
+}
+
+/// Dictionaries by name, shared between projects because they're large.
+#[derive(Default)]
+struct LoadedDictionaries(HashMap<String, Shared<Task<Option<Arc<Dictionary>>>>>);
+
+impl Global for LoadedDictionaries {}
+
+fn load_dictionary(
+    name: &str,
+    fs: &Arc<dyn Fs>,
+    cx: &mut App,
+) -> Shared<Task<Option<Arc<Dictionary>>>> {
+    if let Some(dictionary) = cx.global::<LoadedDictionaries>().0.get(name) {
+        return dictionary.clone();
+    }
+    let fs = fs.clone();
+    let name_to_load = name.to_owned();
+    let task = cx
+        .background_spawn(async move {
+            let load = async {
+                let dic_path = find_dictionary(&name_to_load, fs.as_ref())
+                    .await
+                    .with_context(|| {
+                        format!("spell checking dictionary {name_to_load} not found")
+                    })?;
+                let aff = fs.load_bytes(&dic_path.with_extension("aff")).await?;
+                let dic = fs.load_bytes(&dic_path).await?;
+                let dictionary = Dictionary::load(&aff, &dic)
+                    .with_context(|| format!("loading dictionary {}", dic_path.display()))?;
+                anyhow::Ok(Arc::new(dictionary))
+            };
+            load.await.log_err()
+        })
+        .shared();
+    cx.global_mut::<LoadedDictionaries>()
+        .0
+        .insert(name.to_owned(), task.clone());
+    task
+}
+
+async fn find_dictionary(name: &str, fs: &dyn Fs) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.is_absolute() {
+        return fs.is_file(path).await.then(|| path.to_path_buf());
+    }
+    for dir in dictionary_dirs() {
+        let path = dir.join(format!("{name}.dic"));
+        if fs.is_file(&path).await {
+            return Some(path);
+        }
+    }
+    None
+}
+
+fn dictionary_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![paths::dictionaries_dir().clone()];
+    if cfg!(target_os = "macos") {
+        dirs.push(util::paths::home_dir().join("Library/Spelling"));
+        dirs.push(PathBuf::from("/Library/Spelling"));
+    } else if !cfg!(target_os = "windows") {
+        dirs.extend(
+            [
+                "/usr/share/hunspell",
+                "/usr/local/share/hunspell",
+                "/usr/share/myspell",
+                "/usr/share/myspell/dicts",
+            ]
+            .map(PathBuf::from),
+        );
+    }
+    dirs
+}
+
+/// Reads words from the user's or a project's dictionary file, one per line.
+async fn load_word_list(path: &Path, fs: &dyn Fs) -> HashSet<String> {
+    let Ok(contents) = fs.load(path).await else {
+        return HashSet::default();
+    };
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Where to add a word that the spell checker doesn't know.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+enum WordListScope {
+    User,
+    Project,
+}
+
+/// The action taken by a spell checking code action, stored in its data.
+#[derive(Debug, Serialize, Deserialize)]
+enum SpellCheckAction {
+    Replace { replacement: String },
+    AddWord { word: String, scope: WordListScope },
+}
+
+/// Checks the spelling of a project's buffers and reports misspellings as
+/// diagnostics.
+struct SpellChecker {
+    project: WeakEntity<Project>,
+    fs: Arc<dyn Fs>,
+    server_id: LanguageServerId,
+    checks: HashMap<BufferId, Task<()>>,
+    reported: HashSet<BufferId>,
+    buffer_subscriptions: HashMap<BufferId, Subscription>,
+    _subscriptions: Vec<Subscription>,
+}
+
+impl SpellChecker {
+    fn new(project: Entity<Project>, cx: &mut Context<Self>) -> Self {
+        let buffer_store = project.read(cx).buffer_store().clone();
+        let server_id = project.read(cx).languages().next_language_server_id();
+        let fs = project.read(cx).fs().clone();
+        let subscriptions = vec![
+            cx.subscribe(&buffer_store, |this, _, event, cx| match event {
+                BufferStoreEvent::BufferAdded(buffer) => {
+                    this.register_buffer(buffer, cx);
+                }
+                BufferStoreEvent::BufferDropped(buffer_id) => {
+                    this.checks.remove(buffer_id);
+                    this.reported.remove(buffer_id);
+                    this.buffer_subscriptions.remove(buffer_id);
+                }
+                BufferStoreEvent::BufferChangedFilePath { buffer, .. } => {
+                    this.schedule_check(buffer.clone(), Duration::ZERO, cx);
+                }
+                BufferStoreEvent::SharedBufferClosed(..) => {}
+            }),
+            cx.observe_global::<SettingsStore>(|this, cx| this.check_all(cx)),
+        ];
+        let mut this = Self {
+            project: project.downgrade(),
+            fs,
+            server_id,
+            checks: HashMap::default(),
+            reported: HashSet::default(),
+            buffer_subscriptions: HashMap::default(),
+            _subscriptions: subscriptions,
+        };
+        for buffer in buffer_store.read(cx).buffers().collect::<Vec<_>>() {
+            this.register_buffer(&buffer, cx);
+        }
+        this
+    }
+
+    fn register_buffer(&mut self, buffer: &Entity<Buffer>, cx: &mut Context<Self>) {
+        let buffer_id = buffer.read(cx).remote_id();
+        let subscription = cx.subscribe(buffer, |this, buffer, event, cx| match event {
+            BufferEvent::Edited { .. } | BufferEvent::Reparsed => {
+                this.schedule_check(buffer, CHECK_DEBOUNCE, cx)
+            }
+            BufferEvent::LanguageChanged(_)
+            | BufferEvent::FileHandleChanged
+            | BufferEvent::Reloaded => this.schedule_check(buffer, Duration::ZERO, cx),
+            _ => {}
+        });
+        self.buffer_subscriptions.insert(buffer_id, subscription);
+        self.schedule_check(buffer.clone(), Duration::ZERO, cx);
+    }
+
+    fn check_all(&mut self, cx: &mut Context<Self>) {
+        let Some(project) = self.project.upgrade() else {
+            return;
+        };
+        let buffers = project
+            .read(cx)
+            .buffer_store()
+            .read(cx)
+            .buffers()
+            .collect::<Vec<_>>();
+        for buffer in buffers {
+            self.schedule_check(buffer, Duration::ZERO, cx);
+        }
+    }
+
+    fn schedule_check(&mut self, buffer: Entity<Buffer>, delay: Duration, cx: &mut Context<Self>) {
+        let buffer_id = buffer.read(cx).remote_id();
+        let task = cx.spawn(async move |this, cx| {
+            if !delay.is_zero() {
+                cx.background_executor().timer(delay).await;
+            }
+            let Ok(Some(check)) = this.update(cx, |this, cx| this.check(&buffer, cx)) else {
+                return;
+            };
+            let (abs_path, misspellings) = check.await;
+            this.update(cx, |this, cx| {
+                this.report(buffer_id, &abs_path, misspellings, cx)
+                    .log_err();
+            })
+            .ok();
+        });
+        self.checks.insert(buffer_id, task);
+    }
+
+    /// Returns a task that finds the misspellings in the buffer's current
+    /// contents, or `None` if there is nothing to report.
+    fn check(
+        &mut self,
+        buffer: &Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) -> Option<Task<(PathBuf, Vec<lsp::Diagnostic>)>> {
+        let buffer_ref = buffer.read(cx);
+        let buffer_id = buffer_ref.remote_id();
+        let abs_path = File::from_dyn(buffer_ref.file())?.abs_path(cx);
+        let settings = LanguageSettings::for_buffer(buffer_ref, cx)
+            .spell_check
+            .clone();
+        if !settings.enabled {
+            // Clear the misspellings reported before spell checking was disabled.
+            return self
+                .reported
+                .contains(&buffer_id)
+                .then(|| Task::ready((abs_path, Vec::new())));
+        }
+
+        let snapshot = buffer_ref.snapshot();
+        let word_list_paths = [
+            Some(paths::user_dictionary_file().clone()),
+            project_word_list_path(buffer, cx),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>();
+        let dictionaries = settings
+            .dictionaries
+            .iter()
+            .map(|name| load_dictionary(name, &self.fs, cx))
+            .collect::<Vec<_>>();
+        let fs = self.fs.clone();
+        Some(cx.background_spawn(async move {
+            let dictionaries = join_all(dictionaries)
+                .await
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            // Without a dictionary, every word would be reported.
+            if dictionaries.is_empty() {
+                return (abs_path, Vec::new());
+            }
+            let mut known_words = HashSet::default();
+            for path in word_list_paths {
+                known_words.extend(load_word_list(&path, fs.as_ref()).await);
+            }
+            let diagnostics = misspellings(&snapshot, &dictionaries, &known_words)
+                .into_iter()
+                .map(|(range, word)| {
+                    let start = snapshot.offset_to_point_utf16(range.start);
+                    let end = snapshot.offset_to_point_utf16(range.end);
+                    lsp::Diagnostic {
+                        range: lsp::Range::new(
+                            lsp::Position::new(start.row, start.column),
+                            lsp::Position::new(end.row, end.column),
+                        ),
+                        severity: Some(lsp::DiagnosticSeverity::INFORMATION),
+                        source: Some(DIAGNOSTIC_SOURCE.to_owned()),
+                        message: format!("Unknown word \"{word}\""),
+                        ..Default::default()
+                    }
+                })
+                .collect();
+            (abs_path, diagnostics)
+        }))
+    }
+
+    fn report(
+        &mut self,
+        buffer_id: BufferId,
+        abs_path: &Path,
+        diagnostics: Vec<lsp::Diagnostic>,
+        cx: &mut Context<Self>,
+    ) -> Result<()> {
+        let project = self.project.upgrade().context("project was dropped")?;
+        let uri = lsp::Uri::from_file_path(abs_path)
+            .ok()
+            .with_context(|| format!("invalid buffer path {}", abs_path.display()))?;
+        if diagnostics.is_empty() {
+            self.reported.remove(&buffer_id);
+        } else {
+            self.reported.insert(buffer_id);
+        }
+        let lsp_store = project.read(cx).lsp_store();
+        lsp_store.update(cx, |lsp_store, cx| {
+            lsp_store.update_diagnostics(
+                self.server_id,
+                lsp::PublishDiagnosticsParams {
+                    uri,
+                    diagnostics,
+                    version: None,
+                },
+                None,
+                DiagnosticSourceKind::SpellCheck,
+                &[],
+                cx,
+            )
+        })
+    }
+
+    fn code_actions(
+        &mut self,
+        buffer: &Entity<Buffer>,
+        range: Range<text::Anchor>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<Vec<CodeAction>>> {
+        let buffer_ref = buffer.read(cx);
+        let snapshot = buffer_ref.snapshot();
+        let misspelled = snapshot
+            .diagnostics_in_range::<_, usize>(
+                range.start.to_offset(&snapshot)..range.end.to_offset(&snapshot),
+                false,
+            )
+            .filter(|entry| entry.diagnostic.source_kind == DiagnosticSourceKind::SpellCheck)
+            .map(|entry| {
+                let word = snapshot
+                    .text_for_range(entry.range.clone())
+                    .collect::<String>();
+                let range = snapshot.anchor_before(entry.range.start)
+                    ..snapshot.anchor_after(entry.range.end);
+                (range, word)
+            })
+            .collect::<Vec<_>>();
+        if misspelled.is_empty() {
+            return Task::ready(Ok(Vec::new()));
+        }
+
+        let has_project_word_list = project_word_list_path(buffer, cx).is_some();
+        let dictionary_names = LanguageSettings::for_buffer(buffer_ref, cx)
+            .spell_check
+            .dictionaries
+            .clone();
+        let dictionaries = dictionary_names
+            .iter()
+            .map(|name| load_dictionary(name, &self.fs, cx))
+            .collect::<Vec<_>>();
+        let server_id = self.server_id;
+        cx.background_spawn(async move {
+            let dictionaries = join_all(dictionaries)
+                .await
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            let mut actions = Vec::new();
+            for (range, word) in misspelled {
+                let mut suggestions = Vec::new();
+                for suggestion in dictionaries
+                    .iter()
+                    .flat_map(|dictionary| dictionary.suggest(&word))
+                {
+                    if !suggestions.contains(&suggestion) {
+                        suggestions.push(suggestion);
+                    }
+                }
+                let code_action = |title: String, action: SpellCheckAction| CodeAction {
+                    server_id,
+                    range: range.clone(),
+                    lsp_action: LspAction::Action(Box::new(lsp::CodeAction {
+                        title,
+                        kind: Some(lsp::CodeActionKind::QUICKFIX),
+                        data: serde_json::to_value(action).ok(),
+                        ..Default::default()
+                    })),
+                    resolved: true,
+                };
+                for replacement in suggestions.into_iter().take(5) {
+                    actions.push(code_action(
+                        format!("Change to \"{replacement}\""),
+                        SpellCheckAction::Replace { replacement },
+                    ));
+                }
+                if has_project_word_list {
+                    actions.push(code_action(
+                        format!("Add \"{word}\" to project dictionary"),
+                        SpellCheckAction::AddWord {
+                            word: word.clone(),
+                            scope: WordListScope::Project,
+                        },
+                    ));
+                }
+                actions.push(code_action(
+                    format!("Add \"{word}\" to user dictionary"),
+                    SpellCheckAction::AddWord {
+                        word,
+                        scope: WordListScope::User,
+                    },
+                ));
+            }
+            Ok(actions)
+        })
+    }
+
+    fn add_word(
+        &mut self,
+        word: String,
+        scope: WordListScope,
+        buffer: &Entity<Buffer>,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let path = match scope {
+            WordListScope::User => paths::user_dictionary_file().clone(),
+            WordListScope::Project => match project_word_list_path(buffer, cx) {
+                Some(path) => path,
+                None => return Task::ready(Err(anyhow!("buffer is not part of a project"))),
+            },
+        };
+        let fs = self.fs.clone();
+        cx.spawn(async move |this, cx| {
+            if let Some(parent) = path.parent() {
+                fs.create_dir(parent).await?;
+            }
+            let mut contents = fs.load(&path).await.unwrap_or_default();
+            if !contents.is_empty() && !contents.ends_with('\n') {
+                contents.push('\n');
+            }
+            contents.push_str(&word);
+            contents.push('\n');
+            fs.atomic_write(path, contents).await?;
+            this.update(cx, |this, cx| this.check_all(cx))
+        })
+    }
+}
+
+/// Returns the path of the dictionary file for the project containing the buffer.
+fn project_word_list_path(buffer: &Entity<Buffer>, cx: &App) -> Option<PathBuf> {
+    let file = File::from_dyn(buffer.read(cx).file())?;
+    let worktree = file.worktree.read(cx);
+    if !worktree.is_visible() || worktree.root_entry().is_some_and(|entry| entry.is_file()) {
+        return None;
+    }
+    Some(
+        worktree
+            .abs_path()
+            .join(paths::local_dictionary_file_relative_path().as_std_path()),
+    )
+}
+
+fn misspellings(
+    snapshot: &BufferSnapshot,
+    dictionaries: &[Arc<Dictionary>],
+    known_words: &HashSet<String>,
+) -> Vec<(Range<usize>, String)> {
+    let mut misspellings = Vec::new();
+    for range in words::prose_ranges(snapshot) {
+        let text = snapshot.text_for_range(range.clone()).collect::<String>();
+        for (word_range, word) in words::words(&text) {
+            if known_words.contains(&word)
+                || known_words.contains(&word.to_lowercase())
+                || dictionaries
+                    .iter()
+                    .any(|dictionary| dictionary.check(&word))
+            {
+                continue;
+            }
+            misspellings.push((
+                range.start + word_range.start..range.start + word_range.end,
+                word,
+            ));
+            if misspellings.len() >= MAX_MISSPELLINGS {
+                return misspellings;
+            }
+        }
+    }
+    misspellings
+}
+
+struct SpellCheckCodeActionProvider {
+    spell_checker: WeakEntity<SpellChecker>,
+}
+
+impl CodeActionProvider for SpellCheckCodeActionProvider {
+    fn id(&self) -> Arc<str> {
+        CODE_ACTION_PROVIDER_ID.into()
+    }
+
+    fn code_actions(
+        &self,
+        buffer: &Entity<Buffer>,
+        range: Range<text::Anchor>,
+        _: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<Vec<CodeAction>>> {
+        let Some(spell_checker) = self.spell_checker.upgrade() else {
+            return Task::ready(Ok(Vec::new()));
+        };
+        spell_checker.update(cx, |spell_checker, cx| {
+            spell_checker.code_actions(buffer, range, cx)
+        })
+    }
+
+    fn apply_code_action(
+        &self,
+        buffer: Entity<Buffer>,
+        action: CodeAction,
+        _excerpt_id: ExcerptId,
+        _push_to_history: bool,
+        _: &mut Window,
+        cx: &mut App,
+    ) -> Task<Result<ProjectTransaction>> {
+        let LspAction::Action(lsp_action) = &action.lsp_action else {
+            return Task::ready(Err(anyhow!("unexpected spell check action")));
+        };
+        let Some(spell_check_action) = lsp_action
+            .data
+            .clone()
+            .and_then(|data| serde_json::from_value::<SpellCheckAction>(data).ok())
+        else {
+            return Task::ready(Err(anyhow!("unexpected spell check action")));
+        };
+        match spell_check_action {
+            SpellCheckAction::Replace { replacement } => {
+                buffer.update(cx, |buffer, cx| {
+                    buffer.edit([(action.range, replacement)], None, cx);
+                });
+                Task::ready(Ok(ProjectTransaction::default()))
+            }
+            SpellCheckAction::AddWord { word, scope } => {
+                let Some(spell_checker) = self.spell_checker.upgrade() else {
+                    return Task::ready(Err(anyhow!("spell checker was dropped")));
+                };
+                let task = spell_checker.update(cx, |spell_checker, cx| {
+                    spell_checker.add_word(word, scope, &buffer, cx)
+                });
+                cx.spawn(async move |_| {
+                    task.await?;
+                    Ok(ProjectTransaction::default())
+                })
+            }
+        }
+    }
+}
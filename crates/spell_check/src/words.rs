@@ -0,0 +1,233 @@
+use std::ops::Range;
+
+use language::BufferSnapshot;
+
+/// The smallest word that is spell checked. Shorter words are mostly
+/// abbreviations and variable names.
+const MIN_WORD_LEN: usize = 3;
+
+/// Returns the byte ranges of the buffer that contain natural language: the
+/// comments and strings of code, and the text of prose languages like Markdown.
+///
+/// Buffers without syntax highlighting are treated as prose throughout.
+pub fn prose_ranges(snapshot: &BufferSnapshot) -> Vec<Range<usize>> {
+    if snapshot
+        .language()
+        .is_none_or(|language| language.grammar().is_none())
+    {
+        return vec![0..snapshot.len()];
+    }
+
+    let mut included = Vec::new();
+    let mut excluded = Vec::new();
+    let captures = snapshot.captures(0..snapshot.len(), |grammar| {
+        grammar
+            .highlights_config
+            .as_ref()
+            .map(|config| &config.query)
+    });
+    let grammars = captures.grammars().to_vec();
+    for capture in captures {
+        let Some(query) = grammars[capture.grammar_index]
+            .highlights_config
+            .as_ref()
+            .map(|config| &config.query)
+        else {
+            continue;
+        };
+        let name = query.capture_names()[capture.index as usize];
+        let range = capture.node.byte_range();
+        if is_excluded_capture(name) {
+            excluded.push(range);
+        } else if is_prose_capture(name) {
+            included.push(range);
+        }
+    }
+
+    subtract(merge(included), merge(excluded))
+}
+
+fn is_prose_capture(name: &str) -> bool {
+    let kind = name.split('.').next().unwrap_or(name);
+    matches!(
+        kind,
+        "comment" | "string" | "text" | "title" | "emphasis" | "link_text"
+    )
+}
+
+fn is_excluded_capture(name: &str) -> bool {
+    name.starts_with("string.escape")
+        || name.starts_with("string.regex")
+        || name.starts_with("string.special")
+        || name.starts_with("text.literal")
+        || name.starts_with("link_uri")
+}
+
+fn merge(mut ranges: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    ranges.sort_unstable_by_key(|range| range.start);
+    let mut merged: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match merged.last_mut() {
+            Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+            _ => merged.push(range),
+        }
+    }
+    merged
+}
+
+/// Removes `excluded` from `included`. Both must be sorted and disjoint.
+fn subtract(included: Vec<Range<usize>>, excluded: Vec<Range<usize>>) -> Vec<Range<usize>> {
+    let mut result = Vec::with_capacity(included.len());
+    let mut excluded = excluded.into_iter().peekable();
+    for mut range in included {
+        while let Some(next) = excluded.peek() {
+            if next.end <= range.start {
+                excluded.next();
+            } else if next.start >= range.end {
+                break;
+            } else {
+                if next.start > range.start {
+                    result.push(range.start..next.start);
+                }
+                if next.end >= range.end {
+                    range.start = range.end;
+                    break;
+                }
+                range.start = next.end;
+                excluded.next();
+            }
+        }
+        if !range.is_empty() {
+            result.push(range);
+        }
+    }
+    result
+}
+
+/// Splits text into the words to spell check, returning each word's byte
+/// range within the text and its normalized spelling.
+///
+/// Identifiers are split into their parts, so `parseHTTPRequest` and
+/// `parse_http_request` are checked as `parse`, `HTTP`, and `Request`. Paths,
+/// URLs, email addresses, words containing digits, acronyms, and very short
+/// words are skipped.
+pub fn words(text: &str) -> Vec<(Range<usize>, String)> {
+    let mut words = Vec::new();
+    for (chunk_start, chunk) in split_with_offsets(text, char::is_whitespace) {
+        if chunk.contains("://") || chunk.contains(['@', '/', '\\']) {
+            continue;
+        }
+        for (token_start, token) in split_with_offsets(chunk, |c| !is_word_char(c)) {
+            let trimmed = token.trim_start_matches(is_apostrophe);
+            let token_start = token_start + token.len() - trimmed.len();
+            let token = trimmed.trim_end_matches(is_apostrophe);
+            if token.is_empty() || token.chars().any(|c| c.is_numeric()) {
+                continue;
+            }
+            for part in identifier_parts(token) {
+                let word = &token[part.clone()];
+                if word.chars().count() < MIN_WORD_LEN
+                    || word.chars().all(|c| c.is_uppercase() || is_apostrophe(c))
+                {
+                    continue;
+                }
+                let start = chunk_start + token_start + part.start;
+                words.push((start..start + word.len(), word.replace('\u{2019}', "'")));
+            }
+        }
+    }
+    words
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || is_apostrophe(c)
+}
+
+fn is_apostrophe(c: char) -> bool {
+    c == '\'' || c == '\u{2019}'
+}
+
+/// Returns the non-empty runs of `text` between separators, with their offsets.
+fn split_with_offsets(
+    text: &str,
+    is_separator: impl Fn(char) -> bool,
+) -> impl Iterator<Item = (usize, &str)> {
+    let mut offset = 0;
+    text.split(is_separator).filter_map(move |part| {
+        let start = offset;
+        offset += part.len();
+        // Account for the separator that followed this part.
+        if let Some(separator) = text[offset..].chars().next() {
+            offset += separator.len_utf8();
+        }
+        (!part.is_empty()).then_some((start, part))
+    })
+}
+
+/// Splits a token on underscores and at camel case boundaries.
+fn identifier_parts(token: &str) -> Vec<Range<usize>> {
+    let mut parts = Vec::new();
+    let chars = token.char_indices().collect::<Vec<_>>();
+    let mut start = 0;
+    for (ix, &(offset, c)) in chars.iter().enumerate() {
+        if c == '_' {
+            if start < offset {
+                parts.push(start..offset);
+            }
+            start = offset + c.len_utf8();
+            continue;
+        }
+        let Some(&(_, prev)) = ix.checked_sub(1).and_then(|ix| chars.get(ix)) else {
+            continue;
+        };
+        let next = chars.get(ix + 1).map(|(_, c)| *c);
+        let boundary = c.is_uppercase()
+            && (prev.is_lowercase()
+                || (prev.is_uppercase() && next.is_some_and(|next| next.is_lowercase())));
+        if boundary && start < offset {
+            parts.push(start..offset);
+            start = offset;
+        }
+    }
+    if start < token.len() {
+        parts.push(start..token.len());
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words_in(text: &str) -> Vec<String> {
+        words(text)
+            .into_iter()
+            .map(|(range, word)| {
+                assert_eq!(text[range].replace('\u{2019}', "'"), word);
+                word
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_words() {
+        assert_eq!(
+            words_in("Don’t parse the 'quoted' text, see https://zed.dev or me@zed.dev."),
+            ["Don't", "parse", "the", "quoted", "text", "see"]
+        );
+        assert_eq!(
+            words_in("parseHTTPRequest parse_json_value utf8 HTML src/main.rs"),
+            ["parse", "Request", "parse", "json", "value"]
+        );
+        assert_eq!(words_in("  an  über-fast  "), ["über", "fast"]);
+    }
+
+    #[test]
+    fn test_subtract() {
+        assert_eq!(
+            subtract(vec![0..10, 20..30], vec![2..4, 8..22, 25..26]),
+            vec![0..2, 4..8, 22..25, 26..30]
+        );
+        assert_eq!(subtract(vec![0..10], vec![0..10]), vec![]);
+    }
+}
@@ -0,0 +1,957 @@
+//! Inline images that programs send with the Kitty graphics protocol or as Sixel images, and
+//! the places in the terminal's output where they're displayed.
+
+mod kitty;
+mod sixel;
+
+use crate::ZedListener;
+use alacritty_terminal::{
+    Term,
+    event::WindowSize,
+    grid::Dimensions,
+    index::{Column, Line, Point as AlacPoint},
+    term::TermMode,
+};
+use gpui::RenderImage;
+use image::{Frame, RgbaImage};
+use kitty::{Action, Command, Error};
+use std::sync::Arc;
+
+/// The longest image sequence we keep, encoded; the images in longer ones are dropped.
+pub(crate) const MAX_ENCODED_LEN: usize = 128 * 1024 * 1024;
+/// The widest and tallest an image can be, in pixels.
+pub(crate) const MAX_IMAGE_DIMENSION: u32 = 10_000;
+/// The most memory a single decoded image can take.
+pub(crate) const MAX_IMAGE_BYTES: usize = 128 * 1024 * 1024;
+/// The most memory the images of a terminal can take, like Kitty's storage quota. The least
+/// recently used images are dropped to stay below it.
+const MAX_STORED_BYTES: usize = 320 * 1024 * 1024;
+/// The longest control data of a Kitty graphics command we look at.
+const MAX_CONTROL_LEN: usize = 1024;
+
+/// An image sequence in the output of a program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum GraphicsSequence {
+    /// `ESC _ G <control> ; <payload> ESC \`, without the payload when it was too long.
+    Kitty {
+        control: Vec<u8>,
+        payload: Option<Vec<u8>>,
+    },
+    /// `ESC P <params> q <data> ESC \`.
+    Sixel(Vec<u8>),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ParserState {
+    #[default]
+    Ground,
+    Escape,
+    /// After `ESC _`, where a `G` starts a graphics command.
+    ApcStart,
+    KittyControl,
+    KittyPayload,
+    /// After `ESC P`, where parameters and a `q` start a Sixel image.
+    DcsParams,
+    Sixel,
+    /// After an `ESC` in a sequence, which ends it when followed by `\`.
+    StringEscape,
+}
+
+/// Finds image sequences in the output of a program, which may be split across reads at
+/// any byte.
+#[derive(Default)]
+pub(crate) struct GraphicsParser {
+    state: ParserState,
+    /// The sequence an `ESC` was found in.
+    string: ParserState,
+    control: Vec<u8>,
+    payload: Vec<u8>,
+    too_long: bool,
+}
+
+impl GraphicsParser {
+    /// Returns the image sequences in `bytes`, along with the offset right after each of them.
+    pub(crate) fn parse(&mut self, bytes: &[u8]) -> Vec<(usize, GraphicsSequence)> {
+        let mut sequences = Vec::new();
+        for (ix, &byte) in bytes.iter().enumerate() {
+            self.state = match (self.state, byte) {
+                (ParserState::Ground, 0x1b) => ParserState::Escape,
+                (ParserState::Ground, _) => ParserState::Ground,
+                (ParserState::Escape, _) => escape(byte),
+                (ParserState::StringEscape, b'\\') => {
+                    if let Some(sequence) = self.finish() {
+                        sequences.push((ix + 1, sequence));
+                    }
+                    ParserState::Ground
+                }
+                (ParserState::StringEscape, _) => escape(byte),
+                (_, 0x18 | 0x1a) => ParserState::Ground,
+                (state, 0x1b) => {
+                    self.string = state;
+                    ParserState::StringEscape
+                }
+                (ParserState::ApcStart, b'G') => {
+                    self.start();
+                    ParserState::KittyControl
+                }
+                (ParserState::KittyControl, b';') => ParserState::KittyPayload,
+                (ParserState::KittyControl, _) => {
+                    if self.control.len() < MAX_CONTROL_LEN {
+                        self.control.push(byte);
+                    }
+                    ParserState::KittyControl
+                }
+                (ParserState::DcsParams, b'0'..=b'9' | b';') => ParserState::DcsParams,
+                (ParserState::DcsParams, b'q') => {
+                    self.start();
+                    ParserState::Sixel
+                }
+                (state @ (ParserState::KittyPayload | ParserState::Sixel), _) => {
+                    if self.payload.len() < MAX_ENCODED_LEN {
+                        self.payload.push(byte);
+                    } else {
+                        self.too_long = true;
+                    }
+                    state
+                }
+                // Other sequences can't contain an `ESC`, so there's no need to skip them.
+                (ParserState::ApcStart | ParserState::DcsParams, _) => ParserState::Ground,
+            };
+        }
+        sequences
+    }
+
+    fn start(&mut self) {
+        self.control.clear();
+        self.payload = Vec::new();
+        self.too_long = false;
+    }
+
+    fn finish(&mut self) -> Option<GraphicsSequence> {
+        let payload = std::mem::take(&mut self.payload);
+        match self.string {
+            ParserState::KittyControl | ParserState::KittyPayload => {
+                Some(GraphicsSequence::Kitty {
+                    control: std::mem::take(&mut self.control),
+                    payload: (!self.too_long).then_some(payload),
+                })
+            }
+            ParserState::Sixel if !self.too_long => Some(GraphicsSequence::Sixel(payload)),
+            _ => None,
+        }
+    }
+}
+
+fn escape(byte: u8) -> ParserState {
+    match byte {
+        b'_' => ParserState::ApcStart,
+        b'P' => ParserState::DcsParams,
+        0x1b => ParserState::Escape,
+        _ => ParserState::Ground,
+    }
+}
+
+/// The size of the terminal's cells in pixels, as reported to programs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct CellSize {
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+}
+
+impl From<WindowSize> for CellSize {
+    fn from(size: WindowSize) -> Self {
+        Self {
+            width: size.cell_width.max(1) as u32,
+            height: size.cell_height.max(1) as u32,
+        }
+    }
+}
+
+/// What an image sequence asks of the terminal, besides displaying an image.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct GraphicsEffects {
+    /// A response to write back to the program.
+    pub(crate) response: Option<String>,
+    /// Output that moves the cursor past an image that was displayed.
+    pub(crate) cursor_movement: Option<String>,
+}
+
+/// An image in the visible part of the terminal.
+#[derive(Clone)]
+pub struct TerminalImage {
+    pub image: Arc<RenderImage>,
+    /// The cell at the top left of the image.
+    pub point: AlacPoint,
+    /// How many columns and lines the image spans, which may end partway through a cell.
+    pub columns: f32,
+    pub lines: f32,
+}
+
+struct StoredImage {
+    key: u64,
+    image: Arc<RenderImage>,
+    /// The id the program refers to the image by, or 0 when it can't refer to it.
+    id: u32,
+    number: u32,
+    width: u32,
+    height: u32,
+    last_used: u64,
+}
+
+impl StoredImage {
+    fn bytes(&self) -> usize {
+        self.width as usize * self.height as usize * 4
+    }
+}
+
+/// Where an image is displayed.
+#[derive(Clone, Debug, PartialEq)]
+struct Placement {
+    image: u64,
+    /// The id the program refers to the placement by, or 0.
+    id: u32,
+    /// The image's top line, counting the lines that scrolled off the screen above it when
+    /// it's on the primary screen, which keeps its history.
+    line: i64,
+    column: usize,
+    columns: f32,
+    lines: f32,
+    alt_screen: bool,
+}
+
+impl Placement {
+    fn cell_lines(&self) -> i64 {
+        (self.lines.ceil() as i64).max(1)
+    }
+
+    fn cell_columns(&self) -> usize {
+        (self.columns.ceil() as usize).max(1)
+    }
+
+    fn contains(&self, line: i64, column: Option<usize>) -> bool {
+        (self.line..self.line + self.cell_lines()).contains(&line)
+            && column.is_none_or(|column| {
+                (self.column..self.column + self.cell_columns()).contains(&column)
+            })
+    }
+}
+
+/// The images programs sent to a terminal, and where they're displayed.
+pub(crate) struct TerminalImages {
+    images: Vec<StoredImage>,
+    placements: Vec<Placement>,
+    /// A Kitty image whose payload is being sent in chunks.
+    transmission: Option<(Command, Vec<u8>)>,
+    scrolled_lines: i64,
+    stored_bytes: usize,
+    max_stored_bytes: usize,
+    /// Increases with every image that's stored or used.
+    clock: u64,
+    /// How many ids were chosen for images that programs only gave a number.
+    chosen_ids: u32,
+    /// Images that are no longer displayed, to be dropped from the window.
+    dropped: Vec<Arc<RenderImage>>,
+}
+
+impl Default for TerminalImages {
+    fn default() -> Self {
+        Self {
+            images: Vec::new(),
+            placements: Vec::new(),
+            transmission: None,
+            scrolled_lines: 0,
+            stored_bytes: 0,
+            max_stored_bytes: MAX_STORED_BYTES,
+            clock: 0,
+            chosen_ids: 0,
+            dropped: Vec::new(),
+        }
+    }
+}
+
+impl TerminalImages {
+    /// Handles an image sequence that was printed with the cursor at `cursor`.
+    pub(crate) fn apply(
+        &mut self,
+        sequence: GraphicsSequence,
+        cursor: AlacPoint,
+        alt_screen: bool,
+        cell_size: CellSize,
+    ) -> GraphicsEffects {
+        match sequence {
+            GraphicsSequence::Kitty { control, payload } => {
+                self.apply_kitty(&control, payload, cursor, alt_screen, cell_size)
+            }
+            GraphicsSequence::Sixel(data) => {
+                let Some(image) = sixel::decode(&data) else {
+                    return GraphicsEffects::default();
+                };
+                let (width, height) = image.dimensions();
+                let line = self.line(cursor, alt_screen);
+                // A Sixel image drawn over another one hides it, which programs that redraw
+                // their images rely on.
+                self.placements.retain(|placement| {
+                    placement.id != 0
+                        || placement.alt_screen != alt_screen
+                        || (placement.line, placement.column) != (line, cursor.column.0)
+                });
+                let image = self.store(image, 0, 0);
+                let placement = Placement {
+                    image,
+                    id: 0,
+                    line,
+                    column: cursor.column.0,
+                    columns: width as f32 / cell_size.width as f32,
+                    lines: height as f32 / cell_size.height as f32,
+                    alt_screen,
+                };
+                // The cursor moves to the start of the line below the image.
+                let cursor_movement = format!("\r{}", "\n".repeat(placement.cell_lines() as usize));
+                self.placements.push(placement);
+                self.free_unplaced();
+                GraphicsEffects {
+                    response: None,
+                    cursor_movement: Some(cursor_movement),
+                }
+            }
+        }
+    }
+
+    fn apply_kitty(
+        &mut self,
+        control: &[u8],
+        payload: Option<Vec<u8>>,
+        cursor: AlacPoint,
+        alt_screen: bool,
+        cell_size: CellSize,
+    ) -> GraphicsEffects {
+        let Some(command) = Command::parse(control) else {
+            return GraphicsEffects::default();
+        };
+        let respond = |command: &Command, result: Result<(), Error>| GraphicsEffects {
+            response: command.response(&result),
+            cursor_movement: None,
+        };
+
+        // Only the first chunk of a payload has the command's control data.
+        let (mut command, payload) = match self.transmission.take() {
+            Some((first, mut received)) => {
+                let result = payload.ok_or(Error::TooBig).and_then(|payload| {
+                    received.extend_from_slice(&payload);
+                    kitty::check_payload_len(received.len())
+                });
+                if let Err(error) = result {
+                    return respond(&first, Err(error));
+                }
+                if command.more {
+                    self.transmission = Some((first, received));
+                    return GraphicsEffects::default();
+                }
+                (first, received)
+            }
+            None => {
+                let Some(payload) = payload else {
+                    return respond(&command, Err(Error::TooBig));
+                };
+                if command.more
+                    && matches!(
+                        command.action,
+                        Action::Transmit | Action::TransmitAndDisplay | Action::Query
+                    )
+                {
+                    self.transmission = Some((command, payload));
+                    return GraphicsEffects::default();
+                }
+                (command, payload)
+            }
+        };
+
+        let mut cursor_movement = None;
+        let result = match command.action {
+            Action::Query => command.decode_image(&payload).map(|_| ()),
+            Action::Transmit | Action::TransmitAndDisplay => {
+                command.decode_image(&payload).map(|image| {
+                    if command.image_id == 0 && command.image_number != 0 {
+                        command.image_id = self.choose_id();
+                    }
+                    let key = self.store(image, command.image_id, command.image_number);
+                    if command.action == Action::TransmitAndDisplay {
+                        cursor_movement =
+                            self.display(&command, key, cursor, alt_screen, cell_size);
+                    }
+                })
+            }
+            Action::Display => self
+                .find(&command)
+                .map(|key| {
+                    cursor_movement = self.display(&command, key, cursor, alt_screen, cell_size);
+                })
+                .ok_or(Error::NotFound),
+            Action::Delete => {
+                self.delete(&command, cursor, alt_screen);
+                return GraphicsEffects::default();
+            }
+        };
+        self.free_unplaced();
+
+        GraphicsEffects {
+            response: command.response(&result),
+            cursor_movement,
+        }
+    }
+
+    fn line(&self, point: AlacPoint, alt_screen: bool) -> i64 {
+        if alt_screen {
+            point.line.0 as i64
+        } else {
+            self.scrolled_lines + point.line.0 as i64
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+
+    fn choose_id(&mut self) -> u32 {
+        loop {
+            self.chosen_ids = self.chosen_ids.wrapping_add(1);
+            let id = u32::MAX - self.chosen_ids;
+            if id != 0 && self.images.iter().all(|image| image.id != id) {
+                return id;
+            }
+        }
+    }
+
+    fn find(&self, command: &Command) -> Option<u64> {
+        if command.image_id != 0 {
+            self.images
+                .iter()
+                .find(|image| image.id == command.image_id)
+                .map(|image| image.key)
+        } else if command.image_number != 0 {
+            // The newest image with that number.
+            self.images
+                .iter()
+                .rev()
+                .find(|image| image.number == command.image_number)
+                .map(|image| image.key)
+        } else {
+            None
+        }
+    }
+
+    fn store(&mut self, image: RgbaImage, id: u32, number: u32) -> u64 {
+        // Sending an image with the id of another one replaces it.
+        if id != 0 {
+            self.remove_images(|image| image.id == id);
+        }
+
+        let (width, height) = image.dimensions();
+        let bytes = width as usize * height as usize * 4;
+        while self.stored_bytes + bytes > self.max_stored_bytes {
+            let Some(least_recently_used) = self
+                .images
+                .iter()
+                .min_by_key(|image| image.last_used)
+                .map(|image| image.key)
+            else {
+                break;
+            };
+            self.remove_images(|image| image.key == least_recently_used);
+        }
+
+        // GPUI expects images in BGRA.
+        let mut data = image;
+        for pixel in data.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+        let key = self.tick();
+        self.images.push(StoredImage {
+            key,
+            image: Arc::new(RenderImage::new(vec![Frame::new(data)])),
+            id,
+            number,
+            width,
+            height,
+            last_used: key,
+        });
+        self.stored_bytes += bytes;
+        key
+    }
+
+    /// Displays a stored Kitty image at the cursor, returning the output that moves the
+    /// cursor past it, unless the program asked for the cursor to stay.
+    fn display(
+        &mut self,
+        command: &Command,
+        key: u64,
+        cursor: AlacPoint,
+        alt_screen: bool,
+        cell_size: CellSize,
+    ) -> Option<String> {
+        let last_used = self.tick();
+        let image = self.images.iter_mut().find(|image| image.key == key)?;
+        image.last_used = last_used;
+
+        // Without a size in cells, images are displayed at their size in pixels. With only
+        // one of the two, the image keeps its aspect ratio.
+        let (width, height) = (image.width as f32, image.height as f32);
+        let cell_width = cell_size.width as f32;
+        let cell_height = cell_size.height as f32;
+        let (columns, lines) = match (command.columns, command.lines) {
+            (0, 0) => (width / cell_width, height / cell_height),
+            (0, lines) => {
+                let lines = lines as f32;
+                (lines * cell_height * width / height / cell_width, lines)
+            }
+            (columns, 0) => {
+                let columns = columns as f32;
+                (columns, columns * cell_width * height / width / cell_height)
+            }
+            (columns, lines) => (columns as f32, lines as f32),
+        };
+        // The size comes from the program, and the cursor is moved past the image, so it's
+        // bounded like the size of images.
+        let max_cells = MAX_IMAGE_DIMENSION as f32;
+        let (columns, lines) = (columns.min(max_cells), lines.min(max_cells));
+
+        if command.placement_id != 0 {
+            self.placements
+                .retain(|placement| placement.image != key || placement.id != command.placement_id);
+        }
+        let placement = Placement {
+            image: key,
+            id: command.placement_id,
+            line: self.line(cursor, alt_screen),
+            column: cursor.column.0,
+            columns,
+            lines,
+            alt_screen,
+        };
+        // The cursor moves to the cell after the image, on its last line.
+        let cursor_movement = format!(
+            "{}\x1b[{}C",
+            "\n".repeat(placement.cell_lines() as usize - 1),
+            placement.cell_columns()
+        );
+        self.placements.push(placement);
+        (!command.keep_cursor).then_some(cursor_movement)
+    }
+
+    fn delete(&mut self, command: &Command, cursor: AlacPoint, alt_screen: bool) {
+        let what = command.delete.to_ascii_lowercase();
+        let image = match what {
+            b'i' => self.find(&Command {
+                image_number: 0,
+                ..command.clone()
+            }),
+            b'n' => self.find(&Command {
+                image_id: 0,
+                ..command.clone()
+            }),
+            _ => None,
+        };
+        // `x` and `y` are the 1-based column and line on the screen.
+        let column = (command.x as usize).saturating_sub(1);
+        let line = self.line(
+            AlacPoint::new(
+                Line(i32::try_from(command.y).unwrap_or(i32::MAX) - 1),
+                Column(0),
+            ),
+            alt_screen,
+        );
+        let cursor_line = self.line(cursor, alt_screen);
+
+        self.placements.retain(|placement| {
+            if placement.alt_screen != alt_screen {
+                return true;
+            }
+            let deleted = match what {
+                b'a' => true,
+                b'i' | b'n' => {
+                    image == Some(placement.image)
+                        && (command.placement_id == 0 || placement.id == command.placement_id)
+                }
+                b'c' => placement.contains(cursor_line, Some(cursor.column.0)),
+                b'p' => placement.contains(line, Some(column)),
+                b'x' => (placement.column..placement.column + placement.cell_columns())
+                    .contains(&column),
+                b'y' => placement.contains(line, None),
+                _ => false,
+            };
+            !deleted
+        });
+
+        // Uppercase keys also delete the images that are no longer displayed.
+        self.remove_unplaced(command.delete.is_ascii_lowercase());
+    }
+
+    fn remove_images(&mut self, mut predicate: impl FnMut(&StoredImage) -> bool) {
+        let mut removed = Vec::new();
+        self.images.retain(|image| {
+            let remove = predicate(image);
+            if remove {
+                removed.push((image.key, image.bytes(), image.image.clone()));
+            }
+            !remove
+        });
+        for (key, bytes, image) in removed {
+            self.placements.retain(|placement| placement.image != key);
+            self.stored_bytes -= bytes;
+            self.dropped.push(image);
+        }
+    }
+
+    /// Removes images the program can't refer to once they're no longer displayed.
+    fn free_unplaced(&mut self) {
+        self.remove_unplaced(true);
+    }
+
+    fn remove_unplaced(&mut self, only_anonymous: bool) {
+        let unplaced = self
+            .images
+            .iter()
+            .filter(|image| {
+                (!only_anonymous || image.id == 0)
+                    && self
+                        .placements
+                        .iter()
+                        .all(|placement| placement.image != image.key)
+            })
+            .map(|image| image.key)
+            .collect::<Vec<_>>();
+        if !unplaced.is_empty() {
+            self.remove_images(|image| unplaced.contains(&image.key));
+        }
+    }
+
+    /// Accounts for lines that scrolled into the terminal's history, or out of it when the
+    /// terminal got taller.
+    pub(crate) fn scroll(&mut self, lines: i64) {
+        self.scrolled_lines += lines;
+    }
+
+    /// Forgets about the images above `line`, which is no longer in the terminal.
+    pub(crate) fn forget_lines_above(&mut self, line: Line) {
+        let top = self.scrolled_lines + line.0 as i64;
+        self.placements.retain(|placement| {
+            placement.alt_screen || placement.line + placement.cell_lines() > top
+        });
+        self.free_unplaced();
+    }
+
+    /// Forgets about the images on the primary screen, which was cleared.
+    pub(crate) fn clear(&mut self) {
+        self.placements.retain(|placement| placement.alt_screen);
+        self.free_unplaced();
+    }
+
+    /// Forgets about the images on the screen, which was cleared.
+    pub(crate) fn clear_screen(&mut self, alt_screen: bool) {
+        let top = if alt_screen { 0 } else { self.scrolled_lines };
+        self.placements.retain(|placement| {
+            placement.alt_screen != alt_screen || placement.line + placement.cell_lines() <= top
+        });
+        self.free_unplaced();
+    }
+
+    /// Forgets about the images on the alternate screen, which the terminal left.
+    pub(crate) fn exit_alt_screen(&mut self) {
+        self.placements.retain(|placement| !placement.alt_screen);
+        self.transmission = None;
+        self.free_unplaced();
+    }
+
+    pub(crate) fn visible(&self, term: &Term<ZedListener>) -> Vec<TerminalImage> {
+        let alt_screen = term.mode().contains(TermMode::ALT_SCREEN);
+        let top = -(term.grid().display_offset() as i64);
+        let bottom = top + term.screen_lines() as i64;
+        self.placements
+            .iter()
+            .filter(|placement| placement.alt_screen == alt_screen)
+            .filter_map(|placement| {
+                let line = if alt_screen {
+                    placement.line
+                } else {
+                    placement.line - self.scrolled_lines
+                };
+                if line >= bottom || line + placement.cell_lines() <= top {
+                    return None;
+                }
+                let image = self
+                    .images
+                    .iter()
+                    .find(|image| image.key == placement.image)?;
+                Some(TerminalImage {
+                    image: image.image.clone(),
+                    point: AlacPoint::new(Line(line as i32), Column(placement.column)),
+                    columns: placement.columns,
+                    lines: placement.lines,
+                })
+            })
+            .collect()
+    }
+
+    /// Takes the images that are no longer displayed, for the window to drop.
+    pub(crate) fn take_dropped(&mut self) -> Vec<Arc<RenderImage>> {
+        std::mem::take(&mut self.dropped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TerminalBounds;
+    use crate::shell_integration::MarkedOutputProcessor;
+    use alacritty_terminal::term::Config;
+    use futures::channel::mpsc::unbounded;
+    use parking_lot::Mutex;
+
+    const CELL_SIZE: CellSize = CellSize {
+        width: 2,
+        height: 4,
+    };
+
+    fn parse_all(chunks: &[&[u8]]) -> Vec<GraphicsSequence> {
+        let mut parser = GraphicsParser::default();
+        chunks
+            .iter()
+            .flat_map(|chunk| parser.parse(chunk))
+            .map(|(_, sequence)| sequence)
+            .collect()
+    }
+
+    fn kitty(control: &str, payload: &str) -> GraphicsSequence {
+        GraphicsSequence::Kitty {
+            control: control.as_bytes().to_vec(),
+            payload: Some(payload.as_bytes().to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_parse_graphics_sequences() {
+        let bytes =
+            b"a\x1b_Ga=T,f=24;AAAA\x1b\\b\x1bP0;1;0q#0!4~\x1b\\\x1b]0;title\x07\x1bPzz\x1b\\";
+        for split in 0..bytes.len() {
+            let (first, second) = bytes.split_at(split);
+            assert_eq!(
+                parse_all(&[first, second]),
+                vec![
+                    kitty("a=T,f=24", "AAAA"),
+                    GraphicsSequence::Sixel(b"#0!4~".to_vec())
+                ],
+                "split at {split}"
+            );
+        }
+
+        let mut parser = GraphicsParser::default();
+        let sequences = parser.parse(b"ab\x1b_Gi=1\x1b\\cd");
+        assert_eq!(
+            sequences,
+            vec![(
+                10,
+                GraphicsSequence::Kitty {
+                    control: b"i=1".to_vec(),
+                    payload: Some(Vec::new())
+                }
+            )]
+        );
+
+        // Cancelled sequences, and APCs that aren't graphics commands.
+        assert_eq!(
+            parse_all(&[b"\x1b_Ga=T;AA\x18\x1b\\\x1b_Ha=T\x1b\\\x1bPq#0\x1b[m"]),
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn test_kitty_images() {
+        let mut images = TerminalImages::default();
+        let cursor = AlacPoint::new(Line(1), Column(3));
+
+        // A 2x1 RGB image, displayed at the cursor, which moves past it.
+        let effects = images.apply(
+            kitty("a=T,f=24,s=2,v=1,i=7", "/wAAAP8A"),
+            cursor,
+            false,
+            CELL_SIZE,
+        );
+        assert_eq!(
+            effects,
+            GraphicsEffects {
+                response: Some("\x1b_Gi=7;OK\x1b\\".to_string()),
+                cursor_movement: Some("\x1b[1C".to_string()),
+            }
+        );
+        assert_eq!(images.placements.len(), 1);
+
+        // Displaying it again in more cells, in chunks, without moving the cursor.
+        let effects = images.apply(
+            kitty("a=p,i=7,p=1,c=4,r=3,C=1", ""),
+            cursor,
+            false,
+            CELL_SIZE,
+        );
+        assert_eq!(effects.cursor_movement, None);
+        assert_eq!(images.placements.len(), 2);
+        assert_eq!(
+            images
+                .apply(kitty("a=p,i=8", ""), cursor, false, CELL_SIZE)
+                .response,
+            Some("\x1b_Gi=8;ENOENT:image not found\x1b\\".to_string())
+        );
+
+        let effects = images.apply(
+            kitty("a=T,f=24,s=2,v=1,I=3,m=1", "/wAA"),
+            cursor,
+            false,
+            CELL_SIZE,
+        );
+        assert_eq!(effects, GraphicsEffects::default());
+        let effects = images.apply(kitty("m=0", "AP8A"), cursor, false, CELL_SIZE);
+        let id = u32::MAX - 1;
+        assert_eq!(effects.response, Some(format!("\x1b_Gi={id},I=3;OK\x1b\\")));
+        assert_eq!(images.images.len(), 2);
+
+        // Deleting placements keeps the images that have an id, unless asked otherwise.
+        images.apply(kitty("a=d,d=i,i=7,p=1", ""), cursor, false, CELL_SIZE);
+        assert_eq!(images.placements.len(), 2);
+        images.apply(kitty("a=d,d=c", ""), cursor, false, CELL_SIZE);
+        assert!(images.placements.is_empty());
+        assert_eq!(images.images.len(), 2);
+        images.apply(kitty("a=d,d=A", ""), cursor, false, CELL_SIZE);
+        assert!(images.images.is_empty());
+        assert_eq!(images.stored_bytes, 0);
+        assert_eq!(images.take_dropped().len(), 2);
+    }
+
+    #[test]
+    fn test_images_memory_limit() {
+        let mut images = TerminalImages {
+            max_stored_bytes: 3 * 16 * 16 * 4,
+            ..TerminalImages::default()
+        };
+        let cursor = AlacPoint::new(Line(0), Column(0));
+        for id in 1..=4 {
+            images.store(RgbaImage::new(16, 16), id, 0);
+        }
+        // Displaying an image makes it the most recently used one.
+        images.apply(kitty("a=p,i=2", ""), cursor, false, CELL_SIZE);
+        images.store(RgbaImage::new(16, 16), 5, 0);
+        assert_eq!(images.stored_bytes, images.max_stored_bytes);
+        assert_eq!(
+            images
+                .images
+                .iter()
+                .map(|image| image.id)
+                .collect::<Vec<_>>(),
+            vec![2, 4, 5]
+        );
+
+        let effects = images.apply(
+            GraphicsSequence::Kitty {
+                control: b"a=T,i=9".to_vec(),
+                payload: None,
+            },
+            cursor,
+            false,
+            CELL_SIZE,
+        );
+        assert_eq!(
+            effects.response,
+            Some("\x1b_Gi=9;EFBIG:image too big\x1b\\".to_string())
+        );
+    }
+
+    #[test]
+    fn test_kitty_placement_size_is_bounded() {
+        let mut images = TerminalImages::default();
+        let cursor = AlacPoint::new(Line(0), Column(0));
+        images.apply(
+            kitty("a=t,f=24,s=2,v=1,i=7", "/wAAAP8A"),
+            cursor,
+            false,
+            CELL_SIZE,
+        );
+
+        let max_cells = MAX_IMAGE_DIMENSION as usize;
+        let effects = images.apply(
+            kitty("a=p,i=7,c=4294967295,r=4294967295", ""),
+            cursor,
+            false,
+            CELL_SIZE,
+        );
+        assert_eq!(
+            effects.cursor_movement,
+            Some(format!("{}\x1b[{max_cells}C", "\n".repeat(max_cells - 1)))
+        );
+
+        images.apply(
+            kitty("a=d,d=p,x=1,y=4294967295", ""),
+            cursor,
+            false,
+            CELL_SIZE,
+        );
+        assert_eq!(images.placements.len(), 1);
+    }
+
+    #[test]
+    fn test_images_scroll_with_output() {
+        let history_limit = 5;
+        let (events_tx, mut events_rx) = unbounded();
+        let bounds = TerminalBounds::default();
+        let mut term = Term::new(
+            Config {
+                scrolling_history: history_limit,
+                ..Config::default()
+            },
+            &bounds,
+            ZedListener(events_tx.clone()),
+        );
+        let images = Arc::new(Mutex::new(TerminalImages::default()));
+        let mut processor = MarkedOutputProcessor::new(Arc::default(), history_limit).with_images(
+            images.clone(),
+            ZedListener(events_tx),
+            bounds.into(),
+        );
+
+        // A Sixel image that's 2 cells wide and 2 lines tall, followed by some output.
+        processor.advance(&mut term, b"ab\x1bPq#0;2;0;0;100!10~-!10~\x1b\\cd");
+        let visible = images.lock().visible(&term);
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].point, AlacPoint::new(Line(0), Column(2)));
+        assert_eq!((visible[0].columns, visible[0].lines), (2., 2.4));
+        assert_eq!(term.grid().cursor.point, AlacPoint::new(Line(3), Column(2)));
+
+        // A Kitty query is answered.
+        processor.advance(&mut term, b"\x1b_Ga=q,i=31,s=1,v=1,f=24;AAAA\x1b\\");
+        let mut responses = Vec::new();
+        while let Ok(Some(event)) = events_rx.try_next() {
+            if let alacritty_terminal::event::Event::PtyWrite(response) = event {
+                responses.push(response);
+            }
+        }
+        assert_eq!(responses, vec!["\x1b_Gi=31;OK\x1b\\".to_string()]);
+
+        // The image scrolls with the output, and is forgotten once it leaves the history.
+        processor.advance(&mut term, "\r\n".repeat(term.screen_lines()).as_bytes());
+        let visible = images.lock().visible(&term);
+        assert!(visible.is_empty());
+        term.scroll_display(alacritty_terminal::grid::Scroll::Top);
+        let visible = images.lock().visible(&term);
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].point.line, Line(-4));
+
+        processor.advance(&mut term, "\r\n".repeat(history_limit).as_bytes());
+        assert!(images.lock().placements.is_empty());
+        assert!(images.lock().images.is_empty());
+
+        // Clearing the screen like `clear` does removes the images on it.
+        term.scroll_display(alacritty_terminal::grid::Scroll::Bottom);
+        processor.advance(&mut term, b"\x1bPq!10~\x1b\\");
+        assert_eq!(images.lock().visible(&term).len(), 1);
+        processor.advance(&mut term, b"\x1b[H\x1b[2J\x1b[3J");
+        assert!(images.lock().visible(&term).is_empty());
+    }
+}
@@ -0,0 +1,299 @@
+//! The commands of the Kitty graphics protocol, as sent in `ESC _ G <control> ; <payload> ESC \`.
+//!
+//! See <https://sw.kovidgoyal.net/kitty/graphics-protocol/>.
+
+use super::{MAX_ENCODED_LEN, MAX_IMAGE_BYTES, MAX_IMAGE_DIMENSION};
+use base64::Engine as _;
+use image::{ImageFormat, ImageReader, Limits, RgbaImage};
+use std::io::{Cursor, Read as _};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) enum Action {
+    /// `a=t`: store an image.
+    #[default]
+    Transmit,
+    /// `a=T`: store an image and display it at the cursor.
+    TransmitAndDisplay,
+    /// `a=p`: display a stored image at the cursor.
+    Display,
+    /// `a=d`: delete images, or where they're displayed.
+    Delete,
+    /// `a=q`: check whether an image could be stored, without storing it.
+    Query,
+}
+
+/// The control data of a graphics command. Keys that aren't supported are ignored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Command {
+    pub(crate) action: Action,
+    /// `f`: 24 for RGB, 32 for RGBA, or 100 for PNG.
+    pub(crate) format: u32,
+    /// `t`: where the image data is; only `d`, in the payload itself, is supported.
+    pub(crate) medium: u8,
+    /// `o=z`: whether the image data is compressed with zlib.
+    pub(crate) compressed: bool,
+    /// `s` and `v`: the size of RGB and RGBA images, in pixels.
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    /// `i`: the image's id, chosen by the client.
+    pub(crate) image_id: u32,
+    /// `I`: the image's number, for clients that let the terminal choose ids.
+    pub(crate) image_number: u32,
+    /// `p`: the placement's id, to replace or delete a placement later.
+    pub(crate) placement_id: u32,
+    /// `m=1`: more chunks of the payload follow.
+    pub(crate) more: bool,
+    /// `q`: 1 to only report errors, 2 to report nothing.
+    pub(crate) quiet: u32,
+    /// `c` and `r`: how many columns and lines to display the image in.
+    pub(crate) columns: u32,
+    pub(crate) lines: u32,
+    /// `C=1`: leave the cursor where it is when displaying the image.
+    pub(crate) keep_cursor: bool,
+    /// `d`: what to delete.
+    pub(crate) delete: u8,
+    /// `x` and `y`: the 1-based cell to delete placements at.
+    pub(crate) x: u32,
+    pub(crate) y: u32,
+}
+
+impl Default for Command {
+    fn default() -> Self {
+        Self {
+            action: Action::default(),
+            format: 32,
+            medium: b'd',
+            compressed: false,
+            width: 0,
+            height: 0,
+            image_id: 0,
+            image_number: 0,
+            placement_id: 0,
+            more: false,
+            quiet: 0,
+            columns: 0,
+            lines: 0,
+            keep_cursor: false,
+            delete: b'a',
+            x: 0,
+            y: 0,
+        }
+    }
+}
+
+impl Command {
+    pub(crate) fn parse(control: &[u8]) -> Option<Self> {
+        let mut command = Command::default();
+        for pair in control.split(|&byte| byte == b',') {
+            if pair.is_empty() {
+                continue;
+            }
+            let [key, b'=', value @ ..] = pair else {
+                return None;
+            };
+            let number = || std::str::from_utf8(value).ok()?.parse::<u32>().ok();
+            let byte = || match value {
+                [value] => Some(*value),
+                _ => None,
+            };
+            match *key {
+                b'a' => {
+                    command.action = match byte()? {
+                        b't' => Action::Transmit,
+                        b'T' => Action::TransmitAndDisplay,
+                        b'p' => Action::Display,
+                        b'd' => Action::Delete,
+                        b'q' => Action::Query,
+                        _ => return None,
+                    }
+                }
+                b'f' => command.format = number()?,
+                b't' => command.medium = byte()?,
+                b'o' => command.compressed = byte()? == b'z',
+                b's' => command.width = number()?,
+                b'v' => command.height = number()?,
+                b'i' => command.image_id = number()?,
+                b'I' => command.image_number = number()?,
+                b'p' => command.placement_id = number()?,
+                b'm' => command.more = number()? == 1,
+                b'q' => command.quiet = number()?,
+                b'c' => command.columns = number()?,
+                b'r' => command.lines = number()?,
+                b'C' => command.keep_cursor = number()? == 1,
+                b'd' => command.delete = byte()?,
+                b'x' => command.x = number()?,
+                b'y' => command.y = number()?,
+                _ => {}
+            }
+        }
+        Some(command)
+    }
+
+    /// Returns the response to send to the client, if it asked for one.
+    pub(crate) fn response(&self, result: &Result<(), Error>) -> Option<String> {
+        if self.image_id == 0 && self.image_number == 0 {
+            return None;
+        }
+        let message = match result {
+            Ok(()) if self.quiet == 0 => "OK".to_string(),
+            Err(error) if self.quiet < 2 => error.to_string(),
+            _ => return None,
+        };
+        let mut keys = format!("i={}", self.image_id);
+        if self.image_number != 0 {
+            keys.push_str(&format!(",I={}", self.image_number));
+        }
+        if self.placement_id != 0 {
+            keys.push_str(&format!(",p={}", self.placement_id));
+        }
+        Some(format!("\x1b_G{keys};{message}\x1b\\"))
+    }
+
+    /// Decodes the image in a payload that was encoded with base64.
+    pub(crate) fn decode_image(&self, payload: &[u8]) -> Result<RgbaImage, Error> {
+        if self.medium != b'd' {
+            return Err(Error::Invalid("unsupported transmission medium"));
+        }
+        let data = base64::engine::general_purpose::STANDARD
+            .decode(payload.trim_ascii_end())
+            .map_err(|_| Error::Invalid("invalid base64 data"))?;
+        let data = if self.compressed {
+            let mut decompressed = Vec::new();
+            flate2::read::ZlibDecoder::new(data.as_slice())
+                .take(MAX_IMAGE_BYTES as u64 + 1)
+                .read_to_end(&mut decompressed)
+                .map_err(|_| Error::Invalid("invalid compressed data"))?;
+            if decompressed.len() > MAX_IMAGE_BYTES {
+                return Err(Error::TooBig);
+            }
+            decompressed
+        } else {
+            data
+        };
+
+        match self.format {
+            24 | 32 => {
+                if self.width == 0 || self.height == 0 {
+                    return Err(Error::Invalid("missing image size"));
+                }
+                let pixels = self.width as usize * self.height as usize;
+                if self.width > MAX_IMAGE_DIMENSION
+                    || self.height > MAX_IMAGE_DIMENSION
+                    || pixels * 4 > MAX_IMAGE_BYTES
+                {
+                    return Err(Error::TooBig);
+                }
+                let bytes_per_pixel = self.format as usize / 8;
+                if data.len() < pixels * bytes_per_pixel {
+                    return Err(Error::NoData);
+                }
+                let buffer = if bytes_per_pixel == 4 {
+                    data[..pixels * 4].to_vec()
+                } else {
+                    data[..pixels * 3]
+                        .chunks_exact(3)
+                        .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xff])
+                        .collect()
+                };
+                RgbaImage::from_raw(self.width, self.height, buffer).ok_or(Error::NoData)
+            }
+            100 => {
+                let mut limits = Limits::default();
+                limits.max_image_width = Some(MAX_IMAGE_DIMENSION);
+                limits.max_image_height = Some(MAX_IMAGE_DIMENSION);
+                limits.max_alloc = Some(MAX_IMAGE_BYTES as u64);
+                let mut reader = ImageReader::with_format(Cursor::new(data), ImageFormat::Png);
+                reader.limits(limits);
+                let image = reader.decode().map_err(|error| match error {
+                    image::ImageError::Limits(_) => Error::TooBig,
+                    _ => Error::Invalid("invalid PNG data"),
+                })?;
+                Ok(image.into_rgba8())
+            }
+            _ => Err(Error::Invalid("unsupported format")),
+        }
+    }
+}
+
+/// Checks that a payload that's still being received isn't getting too long.
+pub(crate) fn check_payload_len(len: usize) -> Result<(), Error> {
+    if len > MAX_ENCODED_LEN {
+        Err(Error::TooBig)
+    } else {
+        Ok(())
+    }
+}
+
+/// An error reported to the client, prefixed with the POSIX error name Kitty uses for it.
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub(crate) enum Error {
+    #[error("EINVAL:{0}")]
+    Invalid(&'static str),
+    #[error("ENOENT:image not found")]
+    NotFound,
+    #[error("ENODATA:insufficient image data")]
+    NoData,
+    #[error("EFBIG:image too big")]
+    TooBig,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            Command::parse(b"a=T,f=24,s=2,v=1,i=7,p=3,c=4,r=2,C=1,m=1,q=2"),
+            Some(Command {
+                action: Action::TransmitAndDisplay,
+                format: 24,
+                width: 2,
+                height: 1,
+                image_id: 7,
+                placement_id: 3,
+                columns: 4,
+                lines: 2,
+                keep_cursor: true,
+                more: true,
+                quiet: 2,
+                ..Command::default()
+            })
+        );
+        assert_eq!(
+            Command::parse(b"a=d,d=I,i=1,z=-1"),
+            Some(Command {
+                action: Action::Delete,
+                delete: b'I',
+                image_id: 1,
+                ..Command::default()
+            })
+        );
+        assert_eq!(Command::parse(b"a=x"), None);
+        assert_eq!(Command::parse(b"i=one"), None);
+    }
+
+    #[test]
+    fn test_decode_image() {
+        let command = Command::parse(b"f=24,s=2,v=1").unwrap();
+        let image = command.decode_image(b"/wAAAP8A").unwrap();
+        assert_eq!(image.into_raw(), vec![0xff, 0, 0, 0xff, 0, 0xff, 0, 0xff]);
+        assert_eq!(command.decode_image(b"/wAA"), Err(Error::NoData));
+
+        let command = Command::parse(b"f=32,s=20000,v=1").unwrap();
+        assert_eq!(command.decode_image(b""), Err(Error::TooBig));
+
+        let command = Command::parse(b"f=100,t=f").unwrap();
+        assert_eq!(
+            command.decode_image(b"L3RtcC9pbWFnZS5wbmc="),
+            Err(Error::Invalid("unsupported transmission medium"))
+        );
+        assert_eq!(
+            Command::parse(b"i=4,p=2")
+                .unwrap()
+                .response(&Err(Error::NotFound))
+                .as_deref(),
+            Some("\x1b_Gi=4,p=2;ENOENT:image not found\x1b\\")
+        );
+    }
+}
@@ -0,0 +1,278 @@
+//! A decoder for Sixel images, as sent in `ESC P <params> q <data> ESC \`.
+
+use super::{MAX_IMAGE_BYTES, MAX_IMAGE_DIMENSION};
+use image::RgbaImage;
+
+/// The number of color registers, like xterm's default.
+const PALETTE_SIZE: usize = 1024;
+
+/// The VT340's default palette, in percentages of red, green and blue.
+const DEFAULT_PALETTE: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (20, 20, 80),
+    (80, 13, 13),
+    (20, 80, 20),
+    (80, 20, 80),
+    (20, 80, 80),
+    (80, 80, 20),
+    (53, 53, 53),
+    (26, 26, 26),
+    (33, 33, 60),
+    (60, 26, 26),
+    (33, 60, 33),
+    (60, 33, 60),
+    (33, 60, 60),
+    (60, 60, 33),
+    (80, 80, 80),
+];
+
+/// Decodes the data of a Sixel image, returning `None` when it doesn't draw anything.
+///
+/// Pixels that are never drawn stay transparent, so the terminal's background shows through.
+pub(crate) fn decode(data: &[u8]) -> Option<RgbaImage> {
+    let mut decoder = Decoder::new();
+    let mut bytes = data.iter().copied().peekable();
+    while let Some(byte) = bytes.next() {
+        match byte {
+            b'"' => {
+                let params = parse_params(&mut bytes);
+                // Raster attributes: pixel aspect ratio, then the size of the image.
+                if let (Some(&width), Some(&height)) = (params.get(2), params.get(3)) {
+                    decoder.reserve(width as usize, height as usize);
+                }
+            }
+            b'#' => {
+                let params = parse_params(&mut bytes);
+                decoder.set_color(&params);
+            }
+            b'!' => {
+                let count = parse_params(&mut bytes)
+                    .first()
+                    .copied()
+                    .unwrap_or(1)
+                    .max(1);
+                if let Some(sixel @ 0x3f..=0x7e) = bytes.next() {
+                    decoder.draw(sixel - 0x3f, count as usize);
+                }
+            }
+            b'$' => decoder.x = 0,
+            b'-' => {
+                decoder.x = 0;
+                decoder.y += 6;
+            }
+            0x3f..=0x7e => decoder.draw(byte - 0x3f, 1),
+            _ => {}
+        }
+    }
+    decoder.finish()
+}
+
+fn parse_params(bytes: &mut std::iter::Peekable<impl Iterator<Item = u8>>) -> Vec<u32> {
+    let mut params = vec![0u32];
+    while let Some(&byte) = bytes.peek() {
+        match byte {
+            b'0'..=b'9' => {
+                let param = params.last_mut().unwrap();
+                *param = param
+                    .saturating_mul(10)
+                    .saturating_add((byte - b'0') as u32);
+            }
+            b';' => params.push(0),
+            _ => break,
+        }
+        bytes.next();
+    }
+    params
+}
+
+struct Decoder {
+    palette: Vec<[u8; 4]>,
+    color: [u8; 4],
+    x: usize,
+    y: usize,
+    /// The pixels drawn so far, in rows of `stride` pixels.
+    pixels: Vec<[u8; 4]>,
+    stride: usize,
+    width: usize,
+    height: usize,
+}
+
+impl Decoder {
+    fn new() -> Self {
+        let mut palette = vec![[0, 0, 0, 0xff]; PALETTE_SIZE];
+        for (color, &(red, green, blue)) in palette.iter_mut().zip(&DEFAULT_PALETTE) {
+            *color = [percent(red), percent(green), percent(blue), 0xff];
+        }
+        Self {
+            color: palette[0],
+            palette,
+            x: 0,
+            y: 0,
+            pixels: Vec::new(),
+            stride: 0,
+            width: 0,
+            height: 0,
+        }
+    }
+
+    fn set_color(&mut self, params: &[u32]) {
+        let register = params[0] as usize % PALETTE_SIZE;
+        if let [_, space, a, b, c] = *params {
+            let (a, b, c) = (a.min(360), b.min(100), c.min(100));
+            self.palette[register] = match space {
+                1 => hls_to_rgb(a, b, c),
+                2 => [percent(a as u8), percent(b as u8), percent(c as u8), 0xff],
+                _ => return,
+            };
+        }
+        self.color = self.palette[register];
+    }
+
+    /// Makes room for an image of the given size, when it's not too big.
+    fn reserve(&mut self, width: usize, height: usize) {
+        let max = MAX_IMAGE_DIMENSION as usize;
+        let (width, height) = (width.min(max), height.min(max));
+        let rows = self.rows();
+        if width > self.stride || height > rows {
+            self.resize(width.max(self.stride), height.max(rows));
+        }
+    }
+
+    fn rows(&self) -> usize {
+        self.pixels.len().checked_div(self.stride).unwrap_or(0)
+    }
+
+    /// Grows the canvas, returning whether it fits within the memory allowed for an image.
+    fn resize(&mut self, stride: usize, rows: usize) -> bool {
+        if stride * rows * 4 > MAX_IMAGE_BYTES {
+            return false;
+        }
+        let mut pixels = vec![[0; 4]; stride * rows];
+        if self.stride > 0 {
+            for (old_row, new_row) in self
+                .pixels
+                .chunks_exact(self.stride)
+                .zip(pixels.chunks_exact_mut(stride))
+            {
+                new_row[..self.stride].copy_from_slice(old_row);
+            }
+        }
+        self.pixels = pixels;
+        self.stride = stride;
+        true
+    }
+
+    /// Draws a column of six pixels `count` times, where the low bit of `sixel` is the top.
+    fn draw(&mut self, sixel: u8, count: usize) {
+        let max = MAX_IMAGE_DIMENSION as usize;
+        let count = count.min(max.saturating_sub(self.x));
+        if count == 0 || self.y >= max {
+            return;
+        }
+        let end_x = self.x + count;
+        let end_y = (self.y + 6).min(max);
+        let rows = self.rows();
+        if end_x > self.stride || end_y > rows {
+            // Grow geometrically, so that images without raster attributes stay cheap to draw.
+            let stride = if end_x > self.stride {
+                end_x.max(self.stride * 2).min(max)
+            } else {
+                self.stride
+            };
+            let rows = if end_y > rows {
+                end_y.max(rows * 2).min(max)
+            } else {
+                rows
+            };
+            if !self.resize(stride, rows)
+                && !self.resize(end_x.max(self.stride), end_y.max(self.rows()))
+            {
+                return;
+            }
+        }
+        if sixel != 0 {
+            for bit in 0..end_y - self.y {
+                if sixel & (1 << bit) != 0 {
+                    let row = (self.y + bit) * self.stride;
+                    self.pixels[row + self.x..row + end_x].fill(self.color);
+                    self.height = self.height.max(self.y + bit + 1);
+                }
+            }
+            self.width = self.width.max(end_x);
+        }
+        self.x = end_x;
+    }
+
+    fn finish(self) -> Option<RgbaImage> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        let mut buffer = Vec::with_capacity(self.width * self.height * 4);
+        for row in self.pixels.chunks_exact(self.stride).take(self.height) {
+            buffer.extend(row[..self.width].iter().flatten());
+        }
+        RgbaImage::from_raw(self.width as u32, self.height as u32, buffer)
+    }
+}
+
+fn percent(value: u8) -> u8 {
+    ((value.min(100) as u32 * 255 + 50) / 100) as u8
+}
+
+/// Converts a Sixel HLS color, where hue 0 is blue rather than red, to RGB.
+fn hls_to_rgb(hue: u32, lightness: u32, saturation: u32) -> [u8; 4] {
+    let hue = ((hue + 240) % 360) as f32 / 360.;
+    let lightness = lightness as f32 / 100.;
+    let saturation = saturation as f32 / 100.;
+    if saturation == 0. {
+        let value = (lightness * 255.).round() as u8;
+        return [value, value, value, 0xff];
+    }
+    let q = if lightness < 0.5 {
+        lightness * (1. + saturation)
+    } else {
+        lightness + saturation - lightness * saturation
+    };
+    let p = 2. * lightness - q;
+    let channel = |mut t: f32| {
+        t = t.rem_euclid(1.);
+        let value = if t < 1. / 6. {
+            p + (q - p) * 6. * t
+        } else if t < 1. / 2. {
+            q
+        } else if t < 2. / 3. {
+            p + (q - p) * (2. / 3. - t) * 6.
+        } else {
+            p
+        };
+        (value * 255.).round() as u8
+    };
+    [
+        channel(hue + 1. / 3.),
+        channel(hue),
+        channel(hue - 1. / 3.),
+        0xff,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_sixel() {
+        // Two columns of red in the first band, then a blue pixel at the top of the second.
+        let image = decode(b"#1;2;100;0;0#1!2~-#2;2;0;0;100@").unwrap();
+        assert_eq!(image.dimensions(), (2, 7));
+        assert_eq!(image.get_pixel(1, 5).0, [0xff, 0, 0, 0xff]);
+        assert_eq!(image.get_pixel(0, 6).0, [0, 0, 0xff, 0xff]);
+        assert_eq!(image.get_pixel(1, 6).0, [0, 0, 0, 0]);
+
+        // Raster attributes don't make the image bigger than what was drawn.
+        let image = decode(b"\"1;1;100;100#0;1;120;50;100$$??A").unwrap();
+        assert_eq!(image.dimensions(), (3, 2));
+        assert_eq!(image.get_pixel(2, 1).0, [0xff, 0, 0, 0xff]);
+
+        assert_eq!(decode(b"\"1;1;10;10#0;2;0;0;0"), None);
+    }
+}
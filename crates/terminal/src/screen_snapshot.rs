@@ -0,0 +1,196 @@
+//! Snapshots of a terminal's screen, for showing it to collaborators it's shared with.
+//!
+//! A snapshot is output that draws the screen as it is, so it can be applied to a
+//! display-only terminal like any other output.
+
+use alacritty_terminal::{
+    grid::Dimensions as _,
+    index::{Column, Line},
+    term::{
+        Term, TermMode,
+        cell::{Cell, Flags},
+    },
+    vte::ansi::{Color, NamedColor},
+};
+use std::io::Write as _;
+
+use crate::ZedListener;
+
+const STYLE_FLAGS: [(Flags, u8); 7] = [
+    (Flags::BOLD, 1),
+    (Flags::DIM, 2),
+    (Flags::ITALIC, 3),
+    (Flags::ALL_UNDERLINES, 4),
+    (Flags::INVERSE, 7),
+    (Flags::HIDDEN, 8),
+    (Flags::STRIKEOUT, 9),
+];
+
+/// Renders the screen of `term` with its styles and cursor.
+///
+/// The screen is erased without scrolling it into the history and lines are drawn in place,
+/// so applying snapshots over and over doesn't fill the history up.
+pub(crate) fn screen_snapshot(term: &Term<ZedListener>) -> Vec<u8> {
+    let grid = term.grid();
+    // Lines longer than the terminal the snapshot is applied to are cut off, not wrapped.
+    let mut output = b"\x1b[?7l\x1b[H\x1b[0m\x1b[J".to_vec();
+    for line in 0..term.screen_lines() {
+        let row = &grid[Line(line as i32)];
+        let Some(end) = (0..term.columns())
+            .rev()
+            .find(|&column| !is_blank(&row[Column(column)]))
+        else {
+            continue;
+        };
+
+        write!(output, "\x1b[{}H", line + 1).ok();
+        let mut style = None;
+        for column in 0..=end {
+            let cell = &row[Column(column)];
+            if cell
+                .flags
+                .intersects(Flags::WIDE_CHAR_SPACER | Flags::LEADING_WIDE_CHAR_SPACER)
+            {
+                continue;
+            }
+            let cell_style = (cell.fg, cell.bg, style_flags(cell.flags));
+            if style != Some(cell_style) {
+                write_style(&mut output, cell);
+                style = Some(cell_style);
+            }
+            write!(output, "{}", cell.c).ok();
+            for character in cell.zerowidth().into_iter().flatten() {
+                write!(output, "{character}").ok();
+            }
+        }
+        output.extend_from_slice(b"\x1b[0m");
+    }
+
+    let cursor = grid.cursor.point;
+    write!(
+        output,
+        "\x1b[{};{}H",
+        cursor.line.0.max(0) + 1,
+        cursor.column.0 + 1
+    )
+    .ok();
+    if term.mode().contains(TermMode::SHOW_CURSOR) {
+        output.extend_from_slice(b"\x1b[?25h");
+    } else {
+        output.extend_from_slice(b"\x1b[?25l");
+    }
+    output
+}
+
+fn is_blank(cell: &Cell) -> bool {
+    cell.c == ' '
+        && cell.zerowidth().is_none()
+        && cell.bg == Color::Named(NamedColor::Background)
+        && !cell
+            .flags
+            .intersects(Flags::INVERSE | Flags::ALL_UNDERLINES)
+}
+
+fn style_flags(flags: Flags) -> Flags {
+    STYLE_FLAGS
+        .iter()
+        .filter(|(flag, _)| flags.intersects(*flag))
+        .fold(Flags::empty(), |style, (flag, _)| style | *flag)
+}
+
+fn write_style(output: &mut Vec<u8>, cell: &Cell) {
+    output.extend_from_slice(b"\x1b[0");
+    for (flag, parameter) in STYLE_FLAGS {
+        if cell.flags.intersects(flag) {
+            write!(output, ";{parameter}").ok();
+        }
+    }
+    write_color(output, cell.fg, 30);
+    write_color(output, cell.bg, 40);
+    output.push(b'm');
+}
+
+/// Writes the SGR parameters for a color, where `base` is 30 for the foreground and 40 for the
+/// background. Colors that aren't part of the palette, like the default ones, are left out.
+fn write_color(output: &mut Vec<u8>, color: Color, base: u8) {
+    match color {
+        Color::Named(named) => match named as usize {
+            index @ 0..8 => {
+                write!(output, ";{}", base as usize + index).ok();
+            }
+            index @ 8..16 => {
+                write!(output, ";{}", base as usize + 60 + index - 8).ok();
+            }
+            _ => {}
+        },
+        Color::Indexed(index) => {
+            write!(output, ";{};5;{index}", base + 8).ok();
+        }
+        Color::Spec(rgb) => {
+            write!(output, ";{};2;{};{};{}", base + 8, rgb.r, rgb.g, rgb.b).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TerminalBounds;
+    use alacritty_terminal::{
+        term::Config,
+        vte::ansi::{Processor, StdSyncHandler},
+    };
+    use futures::channel::mpsc::unbounded;
+
+    fn new_term() -> Term<ZedListener> {
+        let (events_tx, _events_rx) = unbounded();
+        Term::new(
+            Config::default(),
+            &TerminalBounds::default(),
+            ZedListener(events_tx),
+        )
+    }
+
+    fn screen_text(term: &Term<ZedListener>) -> Vec<String> {
+        (0..term.screen_lines())
+            .map(|line| {
+                let row = &term.grid()[Line(line as i32)];
+                (0..term.columns())
+                    .map(|column| row[Column(column)].c)
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_screen_snapshot() {
+        let mut processor = Processor::<StdSyncHandler>::new();
+        let mut term = new_term();
+        processor.advance(
+            &mut term,
+            b"one\r\n\x1b[1;31mtwo\x1b[0m \x1b[38;2;1;2;3mthree\r\n\r\nfour\x1b[2D",
+        );
+        let snapshot = screen_snapshot(&term);
+
+        let mut shared = new_term();
+        processor.advance(&mut shared, b"stale\r\n\r\n\r\nstale output");
+        processor.advance(&mut shared, &snapshot);
+        assert_eq!(screen_text(&shared), screen_text(&term));
+        assert_eq!(shared.grid().cursor.point, term.grid().cursor.point);
+        assert_eq!(shared.history_size(), 0);
+
+        let red = &shared.grid()[Line(1)][Column(0)];
+        assert!(red.flags.contains(Flags::BOLD));
+        assert_eq!(red.fg, Color::Named(NamedColor::Red));
+        let rgb = &shared.grid()[Line(1)][Column(4)];
+        assert!(!rgb.flags.contains(Flags::BOLD));
+        assert_eq!(rgb.fg, term.grid()[Line(1)][Column(4)].fg);
+
+        // Applying it again leaves the screen as it was.
+        processor.advance(&mut shared, &snapshot);
+        assert_eq!(screen_text(&shared), screen_text(&term));
+        assert_eq!(shared.history_size(), 0);
+    }
+}
@@ -0,0 +1,991 @@
+//! Shell integration: scripts that make shells report their prompts and commands with the
+//! OSC 133 and OSC 633 escape sequences, and the tracking of those marks in the terminal's
+//! output, so that we know where each command and its output are, and how it exited.
+
+use crate::{
+    ZedListener,
+    graphics::{CellSize, GraphicsParser, GraphicsSequence, TerminalImages},
+};
+use alacritty_terminal::{
+    Term,
+    event::{Event as AlacTermEvent, EventListener, OnResize, WindowSize},
+    grid::Dimensions,
+    index::{Column, Line, Point as AlacPoint},
+    sync::FairMutex,
+    term::TermMode,
+    tty::{self, ChildEvent, EventedPty, EventedReadWrite},
+    vte::ansi::{Processor, StdSyncHandler},
+};
+use anyhow::{Context as _, Result};
+use collections::{HashMap, VecDeque};
+use parking_lot::Mutex;
+use polling::{Event as PollingEvent, PollMode, Poller};
+use std::{
+    io::{self, Read as _},
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+    time::Instant,
+};
+use util::ResultExt as _;
+
+/// The longest OSC payload we look at; marks are much shorter, except for long command lines.
+const MAX_PAYLOAD_LEN: usize = 4096;
+/// How much output to process while holding the terminal lock, like Alacritty's event loop.
+const MAX_LOCKED_READ: usize = u16::MAX as usize;
+const READ_BUFFER_SIZE: usize = 0x1_0000;
+const CLEAR_SCREEN: &[u8] = b"\x1b[2J";
+const CLEAR_HISTORY: &[u8] = b"\x1b[3J";
+
+const SCRIPTS: &[(&str, &str)] = &[
+    ("zed.bash", include_str!("shell_integration/zed.bash")),
+    (
+        "zsh/.zshenv",
+        include_str!("shell_integration/zsh/zshenv.zsh"),
+    ),
+    (
+        "zsh/.zprofile",
+        include_str!("shell_integration/zsh/zprofile.zsh"),
+    ),
+    (
+        "zsh/.zshrc",
+        include_str!("shell_integration/zsh/zshrc.zsh"),
+    ),
+    (
+        "fish/vendor_conf.d/zed.fish",
+        include_str!("shell_integration/fish/zed.fish"),
+    ),
+    ("zed.nu", include_str!("shell_integration/zed.nu")),
+];
+
+/// A mark the shell put in its output to tell where prompts and commands are.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum ShellMark {
+    /// `133;A`: the shell starts drawing the prompt.
+    PromptStart,
+    /// `133;B`: the prompt ends, and the command line starts.
+    CommandStart,
+    /// `133;C`: the command is executed, and its output starts.
+    CommandExecuted,
+    /// `133;D[;<exit code>]`: the command finished.
+    CommandFinished { exit_code: Option<i32> },
+    /// `633;E;<command line>`: the command line that's being executed.
+    CommandLine(String),
+    /// `633;P;Cwd=<path>` or `7;file://<host>/<path>`: the shell's working directory.
+    WorkingDirectory(PathBuf),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum ParserState {
+    #[default]
+    Ground,
+    Escape,
+    Osc,
+    OscEscape,
+}
+
+/// Finds shell marks in the output of a shell, which may be split across reads at any byte.
+#[derive(Default)]
+pub(crate) struct MarkParser {
+    state: ParserState,
+    payload: Vec<u8>,
+}
+
+impl MarkParser {
+    /// Returns the marks in `bytes`, along with the offset right after each of them.
+    pub(crate) fn parse(&mut self, bytes: &[u8]) -> Vec<(usize, ShellMark)> {
+        let mut marks = Vec::new();
+        for (ix, &byte) in bytes.iter().enumerate() {
+            self.state = match (self.state, byte) {
+                (ParserState::Ground, 0x1b) => ParserState::Escape,
+                (ParserState::Ground, _) => ParserState::Ground,
+                (ParserState::Escape | ParserState::OscEscape, b']') => {
+                    self.payload.clear();
+                    ParserState::Osc
+                }
+                (ParserState::Escape, 0x1b) => ParserState::Escape,
+                (ParserState::Escape, _) => ParserState::Ground,
+                (ParserState::Osc, 0x07) | (ParserState::OscEscape, b'\\') => {
+                    if let Some(mark) = parse_mark(&self.payload) {
+                        marks.push((ix + 1, mark));
+                    }
+                    ParserState::Ground
+                }
+                (ParserState::Osc, 0x1b) => ParserState::OscEscape,
+                (ParserState::Osc, 0x18 | 0x1a) => ParserState::Ground,
+                (ParserState::Osc, _) => {
+                    if self.payload.len() < MAX_PAYLOAD_LEN {
+                        self.payload.push(byte);
+                    }
+                    ParserState::Osc
+                }
+                (ParserState::OscEscape, 0x1b) => ParserState::Escape,
+                (ParserState::OscEscape, _) => ParserState::Ground,
+            };
+        }
+        marks
+    }
+}
+
+fn parse_mark(payload: &[u8]) -> Option<ShellMark> {
+    let payload = std::str::from_utf8(payload).ok()?;
+    let (code, rest) = payload.split_once(';').unwrap_or((payload, ""));
+    match code {
+        "133" | "633" => {
+            let mut params = rest.split(';');
+            match params.next()? {
+                "A" => Some(ShellMark::PromptStart),
+                "B" => Some(ShellMark::CommandStart),
+                "C" => Some(ShellMark::CommandExecuted),
+                "D" => Some(ShellMark::CommandFinished {
+                    exit_code: params.next().and_then(|code| code.parse().ok()),
+                }),
+                "E" if code == "633" => Some(ShellMark::CommandLine(unescape(params.next()?))),
+                "P" if code == "633" => {
+                    let (key, value) = params.next()?.split_once('=')?;
+                    (key == "Cwd").then(|| ShellMark::WorkingDirectory(unescape(value).into()))
+                }
+                _ => None,
+            }
+        }
+        "7" => {
+            let url = url::Url::parse(rest).ok()?;
+            if url.scheme() != "file" {
+                return None;
+            }
+            let path = urlencoding::decode(url.path()).ok()?;
+            Some(ShellMark::WorkingDirectory(PathBuf::from(path.as_ref())))
+        }
+        _ => None,
+    }
+}
+
+/// Reverses the escaping of OSC 633 values, where backslashes are doubled and other
+/// characters may be written as `\xAB`.
+fn unescape(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut ix = 0;
+    while ix < bytes.len() {
+        if bytes[ix] == b'\\' {
+            if bytes.get(ix + 1) == Some(&b'\\') {
+                unescaped.push(b'\\');
+                ix += 2;
+                continue;
+            }
+            if bytes.get(ix + 1) == Some(&b'x')
+                && let Some(hex) = value.get(ix + 2..ix + 4)
+                && let Ok(byte) = u8::from_str_radix(hex, 16)
+            {
+                unescaped.push(byte);
+                ix += 4;
+                continue;
+            }
+        }
+        unescaped.push(bytes[ix]);
+        ix += 1;
+    }
+    String::from_utf8_lossy(&unescaped).into_owned()
+}
+
+/// A command the shell ran, as reported through shell integration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CommandBlock {
+    /// Where the command's prompt starts, or the command itself when there was no prompt.
+    pub start: AlacPoint,
+    /// Where the command's output starts, once it was executed.
+    pub output_start: Option<AlacPoint>,
+    /// Where the command's output ends, once it finished.
+    pub output_end: Option<AlacPoint>,
+    /// The command line, when the shell reported it.
+    pub command: Option<String>,
+    /// The working directory the command ran in, when the shell reported it.
+    pub working_directory: Option<PathBuf>,
+    /// The exit code of the finished command, when the shell reported it.
+    pub exit_code: Option<i32>,
+}
+
+/// A point in the terminal's output that stays put as lines scroll into the history.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct OutputPoint {
+    /// The number of lines that scrolled off the screen above the point, plus its line.
+    line: i64,
+    column: usize,
+}
+
+#[derive(Clone, Debug, Default)]
+struct TrackedCommand {
+    prompt_start: Option<OutputPoint>,
+    command_start: Option<OutputPoint>,
+    output_start: Option<OutputPoint>,
+    output_end: Option<OutputPoint>,
+    command: Option<String>,
+    working_directory: Option<PathBuf>,
+    exit_code: Option<i32>,
+}
+
+impl TrackedCommand {
+    fn start(&self) -> Option<OutputPoint> {
+        self.prompt_start
+            .or(self.command_start)
+            .or(self.output_start)
+    }
+
+    fn end(&self) -> Option<OutputPoint> {
+        self.output_end
+            .or(self.output_start)
+            .or(self.command_start)
+            .or(self.prompt_start)
+    }
+
+    fn points_mut(&mut self) -> impl Iterator<Item = &mut OutputPoint> {
+        [
+            &mut self.prompt_start,
+            &mut self.command_start,
+            &mut self.output_start,
+            &mut self.output_end,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+/// The commands the shell in a terminal reported, in the order they ran.
+#[derive(Default)]
+pub(crate) struct CommandHistory {
+    finished: VecDeque<TrackedCommand>,
+    current: Option<TrackedCommand>,
+    scrolled_lines: i64,
+    working_directory: Option<PathBuf>,
+}
+
+impl CommandHistory {
+    fn output_point(&self, point: AlacPoint) -> OutputPoint {
+        OutputPoint {
+            line: self.scrolled_lines + point.line.0 as i64,
+            column: point.column.0,
+        }
+    }
+
+    fn grid_point(&self, point: OutputPoint, term: &Term<ZedListener>) -> Option<AlacPoint> {
+        let line = point.line - self.scrolled_lines;
+        (line >= term.topmost_line().0 as i64 && line <= term.bottommost_line().0 as i64)
+            .then(|| AlacPoint::new(Line(line as i32), Column(point.column)))
+    }
+
+    /// Records a mark the shell printed with the cursor at `cursor`.
+    pub(crate) fn apply(&mut self, mark: ShellMark, cursor: AlacPoint) {
+        let point = self.output_point(cursor);
+        match mark {
+            ShellMark::PromptStart => {
+                if let Some(mut previous) = self.current.take()
+                    && previous.output_start.is_some()
+                {
+                    // The shell didn't report that the previous command finished.
+                    previous.output_end.get_or_insert(point);
+                    self.finished.push_back(previous);
+                }
+                self.current = Some(TrackedCommand {
+                    prompt_start: Some(point),
+                    working_directory: self.working_directory.clone(),
+                    ..TrackedCommand::default()
+                });
+            }
+            ShellMark::CommandStart => {
+                self.current_mut().command_start = Some(point);
+            }
+            ShellMark::CommandExecuted => {
+                let working_directory = self.working_directory.clone();
+                let current = self.current_mut();
+                current.output_start = Some(point);
+                if current.working_directory.is_none() {
+                    current.working_directory = working_directory;
+                }
+            }
+            ShellMark::CommandFinished { exit_code } => {
+                // Shells report a finished command before every prompt, even when the user
+                // didn't run anything.
+                if let Some(mut current) = self.current.take_if(|current| {
+                    current.output_start.is_some() && current.output_end.is_none()
+                }) {
+                    current.output_end = Some(point);
+                    current.exit_code = exit_code;
+                    self.finished.push_back(current);
+                }
+            }
+            ShellMark::CommandLine(command) => {
+                self.current_mut().command = Some(command);
+            }
+            ShellMark::WorkingDirectory(path) => {
+                if let Some(current) = &mut self.current
+                    && current.output_start.is_none()
+                {
+                    current.working_directory = Some(path.clone());
+                }
+                self.working_directory = Some(path);
+            }
+        }
+    }
+
+    fn current_mut(&mut self) -> &mut TrackedCommand {
+        self.current.get_or_insert_with(|| TrackedCommand {
+            working_directory: self.working_directory.clone(),
+            ..TrackedCommand::default()
+        })
+    }
+
+    /// Accounts for lines that scrolled into the terminal's history, or out of it when the
+    /// terminal got taller.
+    pub(crate) fn scroll(&mut self, lines: i64) {
+        self.scrolled_lines += lines;
+    }
+
+    /// Forgets about the output above `line`, which is no longer in the terminal.
+    pub(crate) fn forget_lines_above(&mut self, line: Line) {
+        let top = OutputPoint {
+            line: self.scrolled_lines + line.0 as i64,
+            column: 0,
+        };
+        while self
+            .finished
+            .front()
+            .is_some_and(|command| command.end().is_none_or(|end| end.line < top.line))
+        {
+            self.finished.pop_front();
+        }
+        for command in self.finished.iter_mut().chain(self.current.as_mut()) {
+            for point in command.points_mut() {
+                *point = (*point).max(top);
+            }
+        }
+    }
+
+    /// Forgets about all the output above the cursor, which moved to the top of the screen.
+    pub(crate) fn clear(&mut self) {
+        self.finished.clear();
+        let top_line = self.scrolled_lines;
+        if let Some(current) = &mut self.current {
+            for point in current.points_mut() {
+                point.line = top_line;
+            }
+        }
+    }
+
+    pub(crate) fn blocks(&self, term: &Term<ZedListener>) -> Vec<CommandBlock> {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return Vec::new();
+        }
+        self.finished
+            .iter()
+            .chain(self.current.as_ref())
+            .filter_map(|command| {
+                Some(CommandBlock {
+                    start: self.grid_point(command.start()?, term)?,
+                    output_start: command
+                        .output_start
+                        .and_then(|point| self.grid_point(point, term)),
+                    output_end: command
+                        .output_end
+                        .and_then(|point| self.grid_point(point, term)),
+                    command: command.command.clone(),
+                    working_directory: command.working_directory.clone(),
+                    exit_code: command.exit_code,
+                })
+            })
+            .collect()
+    }
+
+    /// Returns the output of the last command that was executed, which may still be running.
+    pub(crate) fn last_output(&self, term: &Term<ZedListener>) -> Option<String> {
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            return None;
+        }
+        let command = self
+            .current
+            .iter()
+            .chain(self.finished.iter().rev())
+            .find(|command| command.output_start.is_some())?;
+        let clamp = |point: OutputPoint| {
+            let line = (point.line - self.scrolled_lines).clamp(
+                term.topmost_line().0 as i64,
+                term.bottommost_line().0 as i64,
+            );
+            AlacPoint::new(Line(line as i32), Column(point.column))
+        };
+        let start = clamp(command.output_start?);
+        // The end is where the cursor was when the command finished, so it's exclusive.
+        let end = match command.output_end {
+            Some(end) => clamp(end),
+            None => term.grid().cursor.point,
+        };
+        let end = if end.column.0 > 0 {
+            AlacPoint::new(end.line, end.column - 1)
+        } else if end.line > term.topmost_line() {
+            AlacPoint::new(end.line - 1, term.last_column())
+        } else {
+            return Some(String::new());
+        };
+        if end < start {
+            return Some(String::new());
+        }
+        let output = term.bounds_to_string(start, end);
+        Some(output.trim_end_matches('\n').to_string())
+    }
+
+    /// Returns the lines where the commands in the terminal start.
+    pub(crate) fn start_lines(&self, term: &Term<ZedListener>) -> Vec<Line> {
+        self.blocks(term)
+            .into_iter()
+            .map(|block| block.start.line)
+            .collect()
+    }
+}
+
+enum OutputMark {
+    Shell(ShellMark),
+    Graphics(GraphicsSequence),
+    /// `CSI 2 J`, which clears the screen, and the images on it.
+    ClearScreen,
+    /// `CSI 3 J`, which clears the history. Programs like `clear` move the screen's lines
+    /// into the history right before, which must be counted first.
+    ClearHistory,
+}
+
+/// Programs that display images in the terminal, and where to send their responses.
+struct Graphics {
+    parser: GraphicsParser,
+    images: Arc<Mutex<TerminalImages>>,
+    listener: ZedListener,
+    cell_size: CellSize,
+}
+
+/// Feeds the output of a shell to its terminal, tracking the shell marks and the images in it.
+pub(crate) struct MarkedOutputProcessor {
+    processor: Processor<StdSyncHandler>,
+    parser: MarkParser,
+    commands: Arc<Mutex<CommandHistory>>,
+    graphics: Option<Graphics>,
+    history_limit: usize,
+    alt_screen: bool,
+}
+
+impl MarkedOutputProcessor {
+    pub(crate) fn new(commands: Arc<Mutex<CommandHistory>>, history_limit: usize) -> Self {
+        Self {
+            processor: Processor::new(),
+            parser: MarkParser::default(),
+            commands,
+            graphics: None,
+            history_limit,
+            alt_screen: false,
+        }
+    }
+
+    /// Displays the images in the output, answering the programs that send them through
+    /// `listener`.
+    pub(crate) fn with_images(
+        mut self,
+        images: Arc<Mutex<TerminalImages>>,
+        listener: ZedListener,
+        window_size: WindowSize,
+    ) -> Self {
+        self.graphics = Some(Graphics {
+            parser: GraphicsParser::default(),
+            images,
+            listener,
+            cell_size: window_size.into(),
+        });
+        self
+    }
+
+    pub(crate) fn resize(&mut self, window_size: WindowSize) {
+        if let Some(graphics) = &mut self.graphics {
+            graphics.cell_size = window_size.into();
+        }
+    }
+
+    pub(crate) fn advance(&mut self, term: &mut Term<ZedListener>, bytes: &[u8]) {
+        let mut marks = self
+            .parser
+            .parse(bytes)
+            .into_iter()
+            .map(|(end, mark)| (end, OutputMark::Shell(mark)))
+            .collect::<Vec<_>>();
+        if let Some(graphics) = &mut self.graphics {
+            marks.extend(
+                graphics
+                    .parser
+                    .parse(bytes)
+                    .into_iter()
+                    .map(|(end, sequence)| (end, OutputMark::Graphics(sequence))),
+            );
+        }
+        marks.extend(
+            find_all(bytes, CLEAR_SCREEN)
+                .map(|start| (start + CLEAR_SCREEN.len(), OutputMark::ClearScreen)),
+        );
+        marks.extend(find_all(bytes, CLEAR_HISTORY).map(|start| (start, OutputMark::ClearHistory)));
+        marks.sort_by_key(|(end, _)| *end);
+
+        let mut start = 0;
+        for (end, mark) in marks {
+            self.advance_to_mark(term, &bytes[start..end]);
+            start = end;
+            // Output held back for a synchronized update hasn't moved the cursor yet.
+            if self.processor.sync_bytes_count() > 0 {
+                self.processor.stop_sync(term);
+            }
+            let alt_screen = term.mode().contains(TermMode::ALT_SCREEN);
+            match mark {
+                OutputMark::Shell(mark) => {
+                    if !alt_screen {
+                        self.commands.lock().apply(mark, term.grid().cursor.point);
+                    }
+                }
+                OutputMark::ClearScreen => {
+                    if let Some(graphics) = &self.graphics {
+                        graphics.images.lock().clear_screen(alt_screen);
+                    }
+                }
+                OutputMark::ClearHistory => {}
+                OutputMark::Graphics(sequence) => {
+                    let Some(graphics) = &self.graphics else {
+                        continue;
+                    };
+                    let effects = graphics.images.lock().apply(
+                        sequence,
+                        term.grid().cursor.point,
+                        alt_screen,
+                        graphics.cell_size,
+                    );
+                    if let Some(response) = effects.response {
+                        graphics
+                            .listener
+                            .send_event(AlacTermEvent::PtyWrite(response));
+                    }
+                    if let Some(cursor_movement) = effects.cursor_movement {
+                        self.advance_to_mark(term, cursor_movement.as_bytes());
+                    }
+                }
+            }
+        }
+        self.advance_to_mark(term, &bytes[start..]);
+
+        // Nothing wakes us up when a synchronized update times out, so it's ended with the
+        // next output instead.
+        if self
+            .processor
+            .sync_timeout()
+            .sync_timeout()
+            .is_some_and(|timeout| timeout <= Instant::now())
+        {
+            self.processor.stop_sync(term);
+        }
+    }
+
+    fn advance_to_mark(&mut self, term: &mut Term<ZedListener>, bytes: &[u8]) {
+        if bytes.is_empty() {
+            return;
+        }
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            self.processor.advance(term, bytes);
+            self.update_alt_screen(term);
+            return;
+        }
+
+        // Lines that scroll out of a full history can't be counted, so make room for all the
+        // lines these bytes could scroll before trimming the history back to its limit.
+        let history_size = term.history_size();
+        let room = self.history_limit + bytes.len() + term.screen_lines();
+        term.grid_mut().update_history(room);
+        self.processor.advance(term, bytes);
+        self.update_alt_screen(term);
+        if term.mode().contains(TermMode::ALT_SCREEN) {
+            // The primary screen's history is trimmed the next time it's written to.
+            return;
+        }
+        let new_history_size = term.history_size();
+        term.grid_mut().update_history(self.history_limit);
+
+        let mut commands = self.commands.lock();
+        let mut images = self
+            .graphics
+            .as_ref()
+            .map(|graphics| graphics.images.lock());
+        if new_history_size >= history_size {
+            let scrolled_lines = (new_history_size - history_size) as i64;
+            commands.scroll(scrolled_lines);
+            commands.forget_lines_above(term.topmost_line());
+            if let Some(images) = &mut images {
+                images.scroll(scrolled_lines);
+                images.forget_lines_above(term.topmost_line());
+            }
+        } else {
+            // The history was cleared.
+            commands.forget_lines_above(Line(0));
+            if let Some(images) = &mut images {
+                images.forget_lines_above(Line(0));
+            }
+        }
+    }
+
+    fn update_alt_screen(&mut self, term: &Term<ZedListener>) {
+        let alt_screen = term.mode().contains(TermMode::ALT_SCREEN);
+        if alt_screen != self.alt_screen {
+            self.alt_screen = alt_screen;
+            if !alt_screen && let Some(graphics) = &self.graphics {
+                graphics.images.lock().exit_alt_screen();
+            }
+        }
+    }
+}
+
+fn find_all<'a>(bytes: &'a [u8], sequence: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    bytes
+        .windows(sequence.len())
+        .enumerate()
+        .filter(move |(_, window)| *window == sequence)
+        .map(|(start, _)| start)
+}
+
+/// Wraps the PTY of a terminal to process its output with a [`MarkedOutputProcessor`].
+///
+/// Alacritty's event loop processes all the output it reads at once, without a chance to
+/// look at the terminal in between, so reading processes the output right away and reports
+/// to the event loop that there was nothing to read.
+pub(crate) struct ShellIntegrationPty {
+    pty: tty::Pty,
+    term: Arc<FairMutex<Term<ZedListener>>>,
+    listener: ZedListener,
+    processor: MarkedOutputProcessor,
+    buffer: Box<[u8]>,
+}
+
+impl ShellIntegrationPty {
+    pub(crate) fn new(
+        pty: tty::Pty,
+        term: Arc<FairMutex<Term<ZedListener>>>,
+        listener: ZedListener,
+        processor: MarkedOutputProcessor,
+    ) -> Self {
+        Self {
+            pty,
+            term,
+            listener,
+            processor,
+            buffer: vec![0; READ_BUFFER_SIZE].into_boxed_slice(),
+        }
+    }
+}
+
+impl io::Read for ShellIntegrationPty {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        let mut processed = 0;
+        while processed < MAX_LOCKED_READ {
+            match self.pty.reader().read(&mut self.buffer) {
+                Ok(0) => break,
+                Ok(count) => {
+                    // The event loop holds a lease on the terminal while reading.
+                    let mut term = self.term.lock_unfair();
+                    self.processor.advance(&mut term, &self.buffer[..count]);
+                    processed += count;
+                }
+                Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => break,
+                Err(error) if processed == 0 => return Err(error),
+                Err(_) => break,
+            }
+        }
+        if processed > 0 {
+            self.listener.send_event(AlacTermEvent::Wakeup);
+        }
+        Ok(0)
+    }
+}
+
+impl EventedReadWrite for ShellIntegrationPty {
+    type Reader = Self;
+    type Writer = <tty::Pty as EventedReadWrite>::Writer;
+
+    unsafe fn register(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: PollingEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        unsafe { self.pty.register(poll, interest, mode) }
+    }
+
+    fn reregister(
+        &mut self,
+        poll: &Arc<Poller>,
+        interest: PollingEvent,
+        mode: PollMode,
+    ) -> io::Result<()> {
+        self.pty.reregister(poll, interest, mode)
+    }
+
+    fn deregister(&mut self, poll: &Arc<Poller>) -> io::Result<()> {
+        self.pty.deregister(poll)
+    }
+
+    fn reader(&mut self) -> &mut Self::Reader {
+        self
+    }
+
+    fn writer(&mut self) -> &mut Self::Writer {
+        self.pty.writer()
+    }
+}
+
+impl EventedPty for ShellIntegrationPty {
+    fn next_child_event(&mut self) -> Option<ChildEvent> {
+        self.pty.next_child_event()
+    }
+}
+
+impl OnResize for ShellIntegrationPty {
+    fn on_resize(&mut self, window_size: WindowSize) {
+        self.processor.resize(window_size);
+        self.pty.on_resize(window_size)
+    }
+}
+
+/// Sets up the environment for the shell to load Zed's shell integration script, returning
+/// the program and arguments to start the shell with when those need to change.
+///
+/// Arguments are only added when the shell has none, so that a shell configured with its own
+/// arguments starts the way the user asked for.
+pub(crate) fn inject(
+    program: Option<&str>,
+    args: Option<&[String]>,
+    login: bool,
+    env: &mut HashMap<String, String>,
+) -> Option<(String, Vec<String>)> {
+    if cfg!(windows) {
+        return None;
+    }
+    let program = program
+        .map(ToOwned::to_owned)
+        .unwrap_or_else(util::shell::get_system_shell);
+    let name = Path::new(&program).file_name()?.to_str()?;
+    let has_args = args.is_some_and(|args| !args.is_empty());
+    let variable = |env: &HashMap<String, String>, name: &str| {
+        env.get(name).cloned().or_else(|| std::env::var(name).ok())
+    };
+
+    match name {
+        "zsh" => {
+            let scripts = install_scripts()?;
+            if let Some(zdotdir) = variable(env, "ZDOTDIR") {
+                env.insert("ZED_USER_ZDOTDIR".to_string(), zdotdir);
+            }
+            env.insert(
+                "ZDOTDIR".to_string(),
+                scripts.join("zsh").to_string_lossy().into_owned(),
+            );
+            None
+        }
+        "fish" => {
+            let scripts = install_scripts()?;
+            let data_dirs = variable(env, "XDG_DATA_DIRS");
+            env.insert(
+                "XDG_DATA_DIRS".to_string(),
+                format!(
+                    "{}:{}",
+                    scripts.display(),
+                    data_dirs
+                        .as_deref()
+                        .unwrap_or("/usr/local/share:/usr/share")
+                ),
+            );
+            if let Some(data_dirs) = data_dirs {
+                env.insert("ZED_XDG_DATA_DIRS".to_string(), data_dirs);
+            }
+            None
+        }
+        "bash" if !has_args => {
+            let scripts = install_scripts()?;
+            if login {
+                env.insert("ZED_SHELL_LOGIN".to_string(), "1".to_string());
+            }
+            let script = scripts.join("zed.bash").to_string_lossy().into_owned();
+            Some((program, vec!["--init-file".to_string(), script]))
+        }
+        "nu" if !has_args => {
+            let scripts = install_scripts()?;
+            let mut args = Vec::new();
+            if login {
+                args.push("--login".to_string());
+            }
+            args.push("--execute".to_string());
+            args.push(format!("source '{}'", scripts.join("zed.nu").display()));
+            Some((program, args))
+        }
+        _ => None,
+    }
+}
+
+fn install_scripts() -> Option<&'static Path> {
+    static SCRIPTS_DIR: OnceLock<Option<PathBuf>> = OnceLock::new();
+    SCRIPTS_DIR
+        .get_or_init(|| {
+            let dir = paths::temp_dir().join("shell_integration");
+            write_scripts(&dir)
+                .context("installing shell integration scripts")
+                .log_err()?;
+            Some(dir)
+        })
+        .as_deref()
+}
+
+fn write_scripts(dir: &Path) -> Result<()> {
+    for (path, contents) in SCRIPTS {
+        let path = dir.join(path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, contents)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alacritty_terminal::term::Config;
+    use futures::channel::mpsc::unbounded;
+
+    fn parse_all(chunks: &[&[u8]]) -> Vec<ShellMark> {
+        let mut parser = MarkParser::default();
+        chunks
+            .iter()
+            .flat_map(|chunk| parser.parse(chunk))
+            .map(|(_, mark)| mark)
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_marks() {
+        assert_eq!(
+            parse_all(&[b"\x1b]133;A\x07$ \x1b]133;B\x1b\\ls\r\n\x1b]133;C\x07"]),
+            vec![
+                ShellMark::PromptStart,
+                ShellMark::CommandStart,
+                ShellMark::CommandExecuted
+            ]
+        );
+        assert_eq!(
+            parse_all(&[b"\x1b]133;D;127\x07\x1b]133;D\x07\x1b]133;A;click_events=1\x07"]),
+            vec![
+                ShellMark::CommandFinished {
+                    exit_code: Some(127)
+                },
+                ShellMark::CommandFinished { exit_code: None },
+                ShellMark::PromptStart,
+            ]
+        );
+        assert_eq!(
+            parse_all(&[
+                b"\x1b]633;E;echo a\\x3b b \\\\ c\x07",
+                b"\x1b]633;P;Cwd=/tmp/a\\x3bb\x07",
+                b"\x1b]7;file://host/tmp/with%20space\x07",
+            ]),
+            vec![
+                ShellMark::CommandLine("echo a; b \\ c".to_string()),
+                ShellMark::WorkingDirectory(PathBuf::from("/tmp/a;b")),
+                ShellMark::WorkingDirectory(PathBuf::from("/tmp/with space")),
+            ]
+        );
+        assert_eq!(
+            parse_all(&[b"\x1b]0;title\x07\x1b]133;Z\x07\x1b]633;P;Other=1\x07"]),
+            Vec::new()
+        );
+    }
+
+    #[test]
+    fn test_parse_marks_split_across_reads() {
+        let bytes = b"out\x1b]133;D;1\x07\x1b]133;A\x1b\\";
+        for split in 0..bytes.len() {
+            let (first, second) = bytes.split_at(split);
+            assert_eq!(
+                parse_all(&[first, second]),
+                vec![
+                    ShellMark::CommandFinished { exit_code: Some(1) },
+                    ShellMark::PromptStart
+                ],
+                "split at {split}"
+            );
+        }
+
+        let mut parser = MarkParser::default();
+        let marks = parser.parse(b"ab\x1b]133;C\x07cd");
+        assert_eq!(marks, vec![(10, ShellMark::CommandExecuted)]);
+    }
+
+    #[test]
+    fn test_track_commands() {
+        let history_limit = 5;
+        let (events_tx, _events_rx) = unbounded();
+        let mut term = Term::new(
+            Config {
+                scrolling_history: history_limit,
+                ..Config::default()
+            },
+            &crate::TerminalBounds::default(),
+            ZedListener(events_tx),
+        );
+        let commands = Arc::new(Mutex::new(CommandHistory::default()));
+        let mut processor = MarkedOutputProcessor::new(commands.clone(), history_limit);
+        let mut run = |term: &mut Term<ZedListener>,
+                       command: &str,
+                       output: &str,
+                       exit_code: i32| {
+            let bytes = format!(
+                "\x1b]133;A\x07$ \x1b]133;B\x07{command}\r\n\x1b]133;C\x07{output}\x1b]133;D;{exit_code}\x07"
+            );
+            processor.advance(term, bytes.as_bytes());
+        };
+
+        run(&mut term, "true", "", 0);
+        run(&mut term, "printf 'a\\nb'", "a\r\nb\r\n", 0);
+        run(&mut term, "false", "failed\r\n", 1);
+
+        let blocks = commands.lock().blocks(&term);
+        assert_eq!(
+            blocks
+                .iter()
+                .map(|block| block.exit_code)
+                .collect::<Vec<_>>(),
+            vec![Some(0), Some(0), Some(1)]
+        );
+        assert_eq!(
+            commands.lock().last_output(&term).as_deref(),
+            Some("failed")
+        );
+
+        // Scroll the first commands out of the history.
+        run(
+            &mut term,
+            "seq 20",
+            &(1..=20).map(|n| format!("{n}\r\n")).collect::<String>(),
+            0,
+        );
+        let blocks = commands.lock().blocks(&term);
+        assert_eq!(blocks.len(), 1);
+        assert_eq!(blocks[0].exit_code, Some(0));
+        assert_eq!(
+            commands.lock().last_output(&term).unwrap().lines().last(),
+            Some("20")
+        );
+        assert_eq!(term.history_size(), history_limit);
+
+        // A command that runs without a prompt, like the ones the agent runs.
+        processor.advance(&mut term, b"\x1b]133;C\x07running\r\n\x1b]133;D;2\x07$ ");
+        let blocks = commands.lock().blocks(&term);
+        assert_eq!(blocks.last().unwrap().exit_code, Some(2));
+        assert_eq!(
+            commands.lock().last_output(&term).as_deref(),
+            Some("running")
+        );
+    }
+}
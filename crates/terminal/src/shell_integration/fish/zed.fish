@@ -0,0 +1,45 @@
+# Zed's shell integration for fish. Zed adds this file's data directory to XDG_DATA_DIRS so
+# that fish loads it from vendor_conf.d, then it reports prompts, commands, their exit
+# status and the working directory to the terminal with OSC 133 and OSC 633 escape sequences.
+
+if set -q ZED_XDG_DATA_DIRS
+    set -gx XDG_DATA_DIRS $ZED_XDG_DATA_DIRS
+    set -e ZED_XDG_DATA_DIRS
+else
+    set -e XDG_DATA_DIRS
+end
+
+status is-interactive; or exit
+
+# Escapes a value the way OSC 633 expects: backslashes are doubled, and semicolons and
+# newlines are written as \xAB.
+function __zed_escape
+    set -l value (string replace -a -- '\\' '\\\\' $argv[1] | string collect)
+    set value (string replace -a -- ';' '\\x3b' $value | string collect)
+    string replace -a -- \n '\\x0a' $value | string collect
+end
+
+function __zed_preexec --on-event fish_preexec
+    printf '\e]633;E;%s\a' (__zed_escape $argv[1])
+    printf '\e]133;C\a'
+end
+
+function __zed_postexec --on-event fish_postexec
+    printf '\e]133;D;%s\a' $status
+end
+
+function __zed_prompt_start --on-event fish_prompt
+    printf '\e]633;P;Cwd=%s\a' (__zed_escape $PWD)
+    printf '\e]133;A\a'
+
+    # The prompt is usually defined after vendor configuration runs, so it's wrapped the
+    # first time it's shown.
+    if not set -q __zed_prompt_wrapped; and functions -q fish_prompt
+        set -g __zed_prompt_wrapped
+        functions -c fish_prompt __zed_user_fish_prompt
+        function fish_prompt
+            __zed_user_fish_prompt
+            printf '\e]133;B\a'
+        end
+    end
+end
@@ -0,0 +1,85 @@
+# Zed's shell integration for bash, loaded with `bash --init-file` in place of ~/.bashrc.
+#
+# Reports prompts, commands, their exit status and the working directory to the terminal
+# with OSC 133 and OSC 633 escape sequences.
+
+if [[ -n "${ZED_SHELL_LOGIN-}" ]]; then
+    builtin unset ZED_SHELL_LOGIN
+    # Zed would have started a login shell, so source the files a login shell would have.
+    if [[ -r /etc/profile ]]; then
+        builtin source /etc/profile
+    fi
+    for __zed_profile in ~/.bash_profile ~/.bash_login ~/.profile; do
+        if [[ -r "$__zed_profile" ]]; then
+            builtin source "$__zed_profile"
+            break
+        fi
+    done
+    builtin unset __zed_profile
+else
+    if [[ -r /etc/bash.bashrc ]]; then
+        builtin source /etc/bash.bashrc
+    fi
+    if [[ -r ~/.bashrc ]]; then
+        builtin source ~/.bashrc
+    fi
+fi
+
+if [[ -n "${__zed_shell_integration-}" ]]; then
+    builtin return
+fi
+__zed_shell_integration=1
+
+# Escapes a value the way OSC 633 expects: backslashes are doubled, and semicolons and
+# control characters are written as \xAB.
+__zed_escape() {
+    local value="$1" escaped="" char
+    local -i i
+    for ((i = 0; i < ${#value}; i++)); do
+        char="${value:i:1}"
+        if [[ "$char" == '\' ]]; then
+            escaped+='\\'
+        elif [[ "$char" == ';' || "$char" == [[:cntrl:]] ]]; then
+            builtin printf -v char '\\x%02x' "'$char"
+            escaped+="$char"
+        else
+            escaped+="$char"
+        fi
+    done
+    builtin printf '%s' "$escaped"
+}
+
+__zed_capture_status() {
+    __zed_status=$?
+}
+
+__zed_precmd() {
+    # The terminal ignores this when no command was executed since the last prompt.
+    builtin printf '\e]133;D;%s\a' "$__zed_status"
+    builtin printf '\e]633;P;Cwd=%s\a' "$(__zed_escape "$PWD")"
+    # Other prompt commands may have replaced the prompt.
+    if [[ "$PS1" != *'\[\e]133;A\a\]'* ]]; then
+        PS1='\[\e]133;A\a\]'"$PS1"'\[\e]133;B\a\]'
+    fi
+}
+
+# Runs in a subshell through PS0, right before the command is executed.
+__zed_preexec() {
+    local command
+    command="$(HISTTIMEFORMAT='' builtin history 1)"
+    if [[ "$command" =~ ^\ *[0-9]+\*?\ +(.*)$ ]]; then
+        builtin printf '\e]633;E;%s\a' "$(__zed_escape "${BASH_REMATCH[1]}")"
+    fi
+    builtin printf '\e]133;C\a'
+}
+
+# The exit status has to be captured before any other prompt command runs, and the prompt
+# wrapped after all of them did.
+if [[ "$(builtin declare -p PROMPT_COMMAND 2>/dev/null)" == "declare -a"* ]]; then
+    PROMPT_COMMAND=(__zed_capture_status "${PROMPT_COMMAND[@]}" __zed_precmd)
+else
+    PROMPT_COMMAND="__zed_capture_status
+${PROMPT_COMMAND:+$PROMPT_COMMAND
+}__zed_precmd"
+fi
+PS0="${PS0-}"'$(__zed_preexec)'
@@ -2,7 +2,10 @@ pub mod mappings;
 
 pub use alacritty_terminal;
 
+mod graphics;
 mod pty_info;
+mod screen_snapshot;
+mod shell_integration;
 mod terminal_hyperlinks;
 pub mod terminal_settings;
 
@@ -41,9 +44,12 @@ use mappings::mouse::{
 
 use collections::{HashMap, VecDeque};
 use futures::StreamExt;
+use graphics::TerminalImages;
+use parking_lot::Mutex;
 use pty_info::{ProcessIdGetter, PtyProcessInfo};
 use serde::{Deserialize, Serialize};
 use settings::Settings;
+use shell_integration::{CommandHistory, MarkedOutputProcessor, ShellIntegrationPty};
 use smol::channel::{Receiver, Sender};
 use task::{HideStrategy, Shell, SpawnInTerminal};
 use terminal_hyperlinks::RegexSearches;
@@ -74,6 +80,9 @@ use gpui::{
 
 use crate::mappings::{colors::to_alac_rgb, keys::to_esc_str};
 
+pub use graphics::TerminalImage;
+pub use shell_integration::CommandBlock;
+
 actions!(
     terminal,
     [
@@ -103,6 +112,12 @@ actions!(
         ScrollToTop,
         /// Scrolls to the bottom of the terminal buffer.
         ScrollToBottom,
+        /// Scrolls to the previous command reported by the shell.
+        ScrollToPreviousCommand,
+        /// Scrolls to the next command reported by the shell.
+        ScrollToNextCommand,
+        /// Copies the output of the last command to the clipboard.
+        CopyLastCommandOutput,
         /// Toggles vi mode in the terminal.
         ToggleViMode,
         /// Selects all text in the terminal.
@@ -168,6 +183,8 @@ enum InternalEvent {
     // FocusNextMatch,
     Scroll(AlacScroll),
     ScrollToAlacPoint(AlacPoint),
+    ScrollToPreviousCommand,
+    ScrollToNextCommand,
     SetSelection(Option<(Selection, AlacPoint)>),
     UpdateSelection(Point<Pixels>),
     FindHyperlink(Point<Pixels>, bool),
@@ -403,6 +420,8 @@ impl TerminalBuilder {
             last_mouse_move_time: Instant::now(),
             last_hyperlink_search_position: None,
             mouse_down_hyperlink: None,
+            commands: Arc::default(),
+            images: Arc::default(),
             #[cfg(windows)]
             shell_program: None,
             activation_script: Vec::new(),
@@ -414,12 +433,15 @@ impl TerminalBuilder {
                 max_scroll_history_lines,
                 path_hyperlink_regexes: Vec::default(),
                 path_hyperlink_timeout_ms: 0,
+                shell_integration: false,
+                inline_images: false,
                 window_id,
             },
             child_exited: None,
             event_loop_task: Task::ready(Ok(())),
             background_executor: background_executor.clone(),
             path_style,
+            input_tx: None,
             #[cfg(any(test, feature = "test-support"))]
             input_log: Vec::new(),
         };
@@ -440,6 +462,8 @@ impl TerminalBuilder {
         max_scroll_history_lines: Option<usize>,
         path_hyperlink_regexes: Vec<String>,
         path_hyperlink_timeout_ms: u64,
+        shell_integration: bool,
+        inline_images: bool,
         is_remote_terminal: bool,
         window_id: u64,
         completion_tx: Option<Sender<Option<ExitStatus>>>,
@@ -487,7 +511,7 @@ impl TerminalBuilder {
                 }
             }
 
-            let shell_params = match shell.clone() {
+            let mut shell_params = match shell.clone() {
                 Shell::System => {
                     if cfg!(windows) {
                         Some(ShellParams::new(
@@ -506,6 +530,25 @@ impl TerminalBuilder {
                     title_override,
                 } => Some(ShellParams::new(program, Some(args), title_override)),
             };
+
+            // The shell integration scripts are only set up for the user's own shells, which
+            // run on this machine.
+            let mut shell_env = env.clone();
+            if shell_integration
+                && task.is_none()
+                && !is_remote_terminal
+                && let Some((program, args)) = shell_integration::inject(
+                    shell_params.as_ref().map(|params| params.program.as_str()),
+                    shell_params
+                        .as_ref()
+                        .and_then(|params| params.args.as_deref()),
+                    cfg!(target_os = "macos") && matches!(shell, Shell::System),
+                    &mut shell_env,
+                )
+            {
+                let title_override = shell_params.and_then(|params| params.title_override);
+                shell_params = Some(ShellParams::new(program, Some(args), title_override));
+            }
             let terminal_title_override =
                 shell_params.as_ref().and_then(|e| e.title_override.clone());
 
@@ -537,7 +580,7 @@ impl TerminalBuilder {
                     shell: alac_shell,
                     working_directory: working_directory.clone(),
                     drain_on_exit: true,
-                    env: env.clone().into_iter().collect(),
+                    env: shell_env.into_iter().collect(),
                     #[cfg(windows)]
                     escape_args: shell_kind.tty_escape_args(),
                 }
@@ -593,6 +636,23 @@ impl TerminalBuilder {
 
             let pty_info = PtyProcessInfo::new(&pty);
 
+            let commands = Arc::new(Mutex::new(CommandHistory::default()));
+            let images = Arc::new(Mutex::new(TerminalImages::default()));
+            let mut processor = MarkedOutputProcessor::new(commands.clone(), scrolling_history);
+            if inline_images {
+                processor = processor.with_images(
+                    images.clone(),
+                    ZedListener(events_tx.clone()),
+                    TerminalBounds::default().into(),
+                );
+            }
+            let pty = ShellIntegrationPty::new(
+                pty,
+                term.clone(),
+                ZedListener(events_tx.clone()),
+                processor,
+            );
+
             //And connect them together
             let event_loop = EventLoop::new(
                 term.clone(),
@@ -636,6 +696,8 @@ impl TerminalBuilder {
                 last_mouse_move_time: Instant::now(),
                 last_hyperlink_search_position: None,
                 mouse_down_hyperlink: None,
+                commands,
+                images,
                 #[cfg(windows)]
                 shell_program,
                 activation_script: activation_script.clone(),
@@ -647,12 +709,15 @@ impl TerminalBuilder {
                     max_scroll_history_lines,
                     path_hyperlink_regexes,
                     path_hyperlink_timeout_ms,
+                    shell_integration,
+                    inline_images,
                     window_id,
                 },
                 child_exited: None,
                 event_loop_task: Task::ready(Ok(())),
                 background_executor,
                 path_style,
+                input_tx: None,
                 #[cfg(any(test, feature = "test-support"))]
                 input_log: Vec::new(),
             };
@@ -803,6 +868,15 @@ pub struct TerminalContent {
     pub last_hovered_word: Option<HoveredWord>,
     pub scrolled_to_top: bool,
     pub scrolled_to_bottom: bool,
+    pub command_markers: Vec<CommandMarker>,
+    pub images: Vec<TerminalImage>,
+}
+
+/// The start of a finished command in the visible part of the terminal.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct CommandMarker {
+    pub line: Line,
+    pub exit_code: Option<i32>,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -829,6 +903,8 @@ impl Default for TerminalContent {
             last_hovered_word: None,
             scrolled_to_top: false,
             scrolled_to_bottom: false,
+            command_markers: Vec::new(),
+            images: Vec::new(),
         }
     }
 }
@@ -871,6 +947,8 @@ pub struct Terminal {
     last_mouse_move_time: Instant,
     last_hyperlink_search_position: Option<Point<Pixels>>,
     mouse_down_hyperlink: Option<(String, bool, Match)>,
+    commands: Arc<Mutex<CommandHistory>>,
+    images: Arc<Mutex<TerminalImages>>,
     #[cfg(windows)]
     shell_program: Option<String>,
     template: CopyTemplate,
@@ -879,6 +957,8 @@ pub struct Terminal {
     event_loop_task: Task<Result<(), anyhow::Error>>,
     background_executor: BackgroundExecutor,
     path_style: PathStyle,
+    /// Where the input of a display-only terminal goes, when it's shared by a collaborator.
+    input_tx: Option<UnboundedSender<Vec<u8>>>,
     #[cfg(any(test, feature = "test-support"))]
     input_log: Vec<Vec<u8>>,
 }
@@ -891,6 +971,8 @@ struct CopyTemplate {
     max_scroll_history_lines: Option<usize>,
     path_hyperlink_regexes: Vec<String>,
     path_hyperlink_timeout_ms: u64,
+    shell_integration: bool,
+    inline_images: bool,
     window_id: u64,
 }
 
@@ -1033,7 +1115,11 @@ impl Terminal {
                     pty_tx.0.send(Msg::Resize(new_bounds.into())).ok();
                 }
 
+                let history_size = term.history_size();
                 term.resize(new_bounds);
+                let scrolled_lines = term.history_size() as i64 - history_size as i64;
+                self.commands.lock().scroll(scrolled_lines);
+                self.images.lock().scroll(scrolled_lines);
                 // If there are matches we need to emit a wake up event to
                 // invalidate the matches and recalculate their locations
                 // in the new terminal layout
@@ -1045,6 +1131,8 @@ impl Terminal {
                 trace!("Clearing");
                 // Clear back buffer
                 term.clear_screen(ClearMode::Saved);
+                self.commands.lock().clear();
+                self.images.lock().clear();
 
                 let cursor = term.grid().cursor.point;
 
@@ -1074,6 +1162,29 @@ impl Terminal {
 
                 cx.emit(Event::Wakeup);
             }
+            InternalEvent::ScrollToPreviousCommand | InternalEvent::ScrollToNextCommand => {
+                let viewport_top = Line(-(term.grid().display_offset() as i32));
+                let start_lines = self.commands.lock().start_lines(term);
+                let target = if matches!(event, InternalEvent::ScrollToPreviousCommand) {
+                    start_lines
+                        .into_iter()
+                        .rev()
+                        .find(|line| *line < viewport_top)
+                } else {
+                    start_lines.into_iter().find(|line| *line > viewport_top)
+                };
+                let scroll = match target {
+                    Some(line) => Some(AlacScroll::Delta(viewport_top.0 - line.0)),
+                    None if matches!(event, InternalEvent::ScrollToNextCommand) => {
+                        Some(AlacScroll::Bottom)
+                    }
+                    None => None,
+                };
+                if let Some(scroll) = scroll {
+                    term.scroll_display(scroll);
+                    self.refresh_hovered_word(window);
+                }
+            }
             InternalEvent::Scroll(scroll) => {
                 trace!("Scrolling: scroll={scroll:?}");
                 term.scroll_display(*scroll);
@@ -1325,6 +1436,35 @@ impl Terminal {
         cx.emit(Event::Wakeup);
     }
 
+    /// Renders the screen as output that reproduces it, for display-only terminals that show
+    /// this terminal to collaborators.
+    pub fn screen_snapshot(&self) -> Vec<u8> {
+        screen_snapshot::screen_snapshot(&self.term.lock())
+    }
+
+    /// Replaces the screen of a display-only terminal with a [`Terminal::screen_snapshot`].
+    pub fn apply_screen_snapshot(&mut self, snapshot: &[u8], cx: &mut Context<Self>) {
+        let mut processor = alacritty_terminal::vte::ansi::Processor::<
+            alacritty_terminal::vte::ansi::StdSyncHandler,
+        >::new();
+        processor.advance(&mut *self.term.lock(), snapshot);
+        cx.emit(Event::Wakeup);
+    }
+
+    /// Sends the input of a display-only terminal to `input_tx` instead of dropping it.
+    pub fn forward_input(&mut self, input_tx: UnboundedSender<Vec<u8>>) {
+        if let TerminalType::DisplayOnly = self.terminal_type {
+            self.input_tx = Some(input_tx);
+        }
+    }
+
+    pub fn set_title_override(&mut self, title: Option<String>, cx: &mut Context<Self>) {
+        if self.title_override != title {
+            self.title_override = title;
+            cx.emit(Event::TitleChanged);
+        }
+    }
+
     pub fn total_lines(&self) -> usize {
         self.term.lock_unfair().total_lines()
     }
@@ -1426,6 +1566,15 @@ impl Terminal {
             .push_back(InternalEvent::Scroll(AlacScroll::Bottom));
     }
 
+    pub fn scroll_to_previous_command(&mut self) {
+        self.events
+            .push_back(InternalEvent::ScrollToPreviousCommand);
+    }
+
+    pub fn scroll_to_next_command(&mut self) {
+        self.events.push_back(InternalEvent::ScrollToNextCommand);
+    }
+
     pub fn scrolled_to_top(&self) -> bool {
         self.last_content.scrolled_to_top
     }
@@ -1442,9 +1591,11 @@ impl Terminal {
     }
 
     /// Write the Input payload to the PTY, if applicable.
-    /// (This is a no-op for display-only terminals.)
+    /// (Display-only terminals drop it, unless it's forwarded with [`Terminal::forward_input`].)
     fn write_to_pty(&self, input: impl Into<Cow<'static, [u8]>>) {
-        if let TerminalType::Pty { pty_tx, .. } = &self.terminal_type {
+        if let Some(input_tx) = &self.input_tx {
+            input_tx.unbounded_send(input.into().into_owned()).ok();
+        } else if let TerminalType::Pty { pty_tx, .. } = &self.terminal_type {
             let input = input.into();
             if log::log_enabled!(log::Level::Debug) {
                 if let Ok(str) = str::from_utf8(&input) {
@@ -1622,10 +1773,24 @@ impl Terminal {
             self.process_terminal_event(&e, &mut terminal, window, cx)
         }
 
-        self.last_content = Self::make_content(&terminal, &self.last_content);
+        let mut images = self.images.lock();
+        for image in images.take_dropped() {
+            cx.drop_image(image, Some(window));
+        }
+        self.last_content = Self::make_content(
+            &terminal,
+            &self.last_content,
+            &self.commands.lock(),
+            &images,
+        );
     }
 
-    fn make_content(term: &Term<ZedListener>, last_content: &TerminalContent) -> TerminalContent {
+    fn make_content(
+        term: &Term<ZedListener>,
+        last_content: &TerminalContent,
+        commands: &CommandHistory,
+        images: &TerminalImages,
+    ) -> TerminalContent {
         let content = term.renderable_content();
 
         // Pre-allocate with estimated size to reduce reallocations
@@ -1655,9 +1820,36 @@ impl Terminal {
             last_hovered_word: last_content.last_hovered_word.clone(),
             scrolled_to_top: content.display_offset == term.history_size(),
             scrolled_to_bottom: content.display_offset == 0,
+            command_markers: commands
+                .blocks(term)
+                .into_iter()
+                .filter(|block| {
+                    let top = -(content.display_offset as i32);
+                    block.output_end.is_some()
+                        && (top..top + term.screen_lines() as i32).contains(&block.start.line.0)
+                })
+                .map(|block| CommandMarker {
+                    line: block.start.line,
+                    exit_code: block.exit_code,
+                })
+                .collect(),
+            images: images.visible(term),
         }
     }
 
+    /// Returns the commands the shell reported through shell integration, oldest first.
+    pub fn command_blocks(&self) -> Vec<CommandBlock> {
+        let term = self.term.lock_unfair();
+        self.commands.lock().blocks(&term)
+    }
+
+    /// Returns the output of the last command the shell reported through shell integration,
+    /// which may still be running.
+    pub fn last_command_output(&self) -> Option<String> {
+        let term = self.term.lock_unfair();
+        self.commands.lock().last_output(&term)
+    }
+
     pub fn get_content(&self) -> String {
         let term = self.term.lock_unfair();
         let start = AlacPoint::new(term.topmost_line(), Column(0));
@@ -2309,6 +2501,8 @@ impl Terminal {
             self.template.max_scroll_history_lines,
             self.template.path_hyperlink_regexes.clone(),
             self.template.path_hyperlink_timeout_ms,
+            self.template.shell_integration,
+            self.template.inline_images,
             self.is_remote_terminal,
             self.template.window_id,
             None,
@@ -2597,6 +2791,8 @@ mod tests {
                     vec![],
                     0,
                     false,
+                    false,
+                    false,
                     0,
                     Some(completion_tx),
                     cx,
@@ -2637,7 +2833,12 @@ mod tests {
 
         terminal.update(cx, |terminal, _cx| {
             let term_lock = terminal.term.lock();
-            terminal.last_content = Terminal::make_content(&term_lock, &terminal.last_content);
+            terminal.last_content = Terminal::make_content(
+                &term_lock,
+                &terminal.last_content,
+                &terminal.commands.lock(),
+                &terminal.images.lock(),
+            );
             drop(term_lock);
 
             let terminal_bounds = TerminalBounds::new(
@@ -2743,6 +2944,8 @@ mod tests {
                     vec![],
                     0,
                     false,
+                    false,
+                    false,
                     0,
                     Some(completion_tx),
                     cx,
@@ -2819,6 +3022,8 @@ mod tests {
                     Vec::new(),
                     0,
                     false,
+                    false,
+                    false,
                     0,
                     Some(completion_tx),
                     cx,
@@ -3021,7 +3226,12 @@ mod tests {
         // Get the content by directly accessing the term
         let content = terminal.update(cx, |terminal, _cx| {
             let term = terminal.term.lock_unfair();
-            Terminal::make_content(&term, &terminal.last_content)
+            Terminal::make_content(
+                &term,
+                &terminal.last_content,
+                &terminal.commands.lock(),
+                &terminal.images.lock(),
+            )
         });
 
         // If LF is properly converted to CRLF, each line should start at column 0
@@ -3069,7 +3279,12 @@ mod tests {
         // Get the content by directly accessing the term
         let content = terminal.update(cx, |terminal, _cx| {
             let term = terminal.term.lock_unfair();
-            Terminal::make_content(&term, &terminal.last_content)
+            Terminal::make_content(
+                &term,
+                &terminal.last_content,
+                &terminal.commands.lock(),
+                &terminal.images.lock(),
+            )
         });
 
         let cells = &content.cells;
@@ -3111,7 +3326,12 @@ mod tests {
         // Get the content by directly accessing the term
         let content = terminal.update(cx, |terminal, _cx| {
             let term = terminal.term.lock_unfair();
-            Terminal::make_content(&term, &terminal.last_content)
+            Terminal::make_content(
+                &term,
+                &terminal.last_content,
+                &terminal.commands.lock(),
+                &terminal.images.lock(),
+            )
         });
 
         let cells = &content.cells;
@@ -3304,6 +3524,8 @@ mod tests {
                         settings.path_hyperlink_regexes.clone(),
                         test_path_hyperlink_timeout_ms,
                         false,
+                        false,
+                        false,
                         window.window_handle().window_id().as_u64(),
                         None,
                         cx,
@@ -1,19 +1,19 @@
 use editor::{CursorLayout, EditorSettings, HighlightedRange, HighlightedRangeLine};
 use gpui::{
-    AbsoluteLength, AnyElement, App, AvailableSpace, Bounds, ContentMask, Context, DispatchPhase,
-    Element, ElementId, Entity, FocusHandle, Font, FontFeatures, FontStyle, FontWeight,
-    GlobalElementId, HighlightStyle, Hitbox, Hsla, InputHandler, InteractiveElement, Interactivity,
-    IntoElement, LayoutId, Length, ModifiersChangedEvent, MouseButton, MouseMoveEvent, Pixels,
-    Point, StatefulInteractiveElement, StrikethroughStyle, Styled, TextRun, TextStyle,
-    UTF16Selection, UnderlineStyle, WeakEntity, WhiteSpace, Window, div, fill, point, px, relative,
-    size,
+    AbsoluteLength, AnyElement, App, AvailableSpace, Bounds, ContentMask, Context, Corners,
+    DispatchPhase, Element, ElementId, Entity, FocusHandle, Font, FontFeatures, FontStyle,
+    FontWeight, GlobalElementId, HighlightStyle, Hitbox, Hsla, InputHandler, InteractiveElement,
+    Interactivity, IntoElement, LayoutId, Length, ModifiersChangedEvent, MouseButton,
+    MouseMoveEvent, Pixels, Point, StatefulInteractiveElement, StrikethroughStyle, Styled, TextRun,
+    TextStyle, UTF16Selection, UnderlineStyle, WeakEntity, WhiteSpace, Window, div, fill, point,
+    px, relative, size,
 };
 use itertools::Itertools;
 use language::CursorShape;
 use settings::Settings;
 use std::time::Instant;
 use terminal::{
-    IndexedCell, Terminal, TerminalBounds, TerminalContent,
+    IndexedCell, Terminal, TerminalBounds, TerminalContent, TerminalImage,
     alacritty_terminal::{
         grid::Dimensions,
         index::Point as AlacPoint,
@@ -50,6 +50,8 @@ pub struct LayoutState {
     display_offset: usize,
     hyperlink_tooltip: Option<AnyElement>,
     gutter: Pixels,
+    failed_command_lines: Vec<i32>,
+    images: Vec<TerminalImage>,
     block_below_cursor_element: Option<AnyElement>,
     base_text_style: TextStyle,
     content_mode: ContentMode,
@@ -1030,10 +1032,25 @@ impl Element for TerminalElement {
                     cursor_char,
                     selection,
                     cursor,
+                    command_markers,
+                    images,
                     ..
                 } = &self.terminal.read(cx).last_content;
                 let mode = *mode;
                 let display_offset = *display_offset;
+                let failed_command_lines = command_markers
+                    .iter()
+                    .filter(|marker| marker.exit_code.is_some_and(|code| code != 0))
+                    .map(|marker| marker.line.0 + display_offset as i32)
+                    .collect();
+                let images = images
+                    .iter()
+                    .cloned()
+                    .map(|mut image| {
+                        image.point.line += display_offset as i32;
+                        image
+                    })
+                    .collect();
 
                 // searches, highlights to a single range representations
                 let mut relative_highlighted_ranges = Vec::new();
@@ -1219,6 +1236,8 @@ impl Element for TerminalElement {
                     display_offset,
                     hyperlink_tooltip,
                     gutter,
+                    failed_command_lines,
+                    images,
                     block_below_cursor_element,
                     base_text_style: text_style,
                     content_mode,
@@ -1302,6 +1321,22 @@ impl Element for TerminalElement {
                         rect.paint(origin, &layout.dimensions, window);
                     }
 
+                    // Mark the commands that failed in the gutter.
+                    let marker_width = layout.gutter / 4.;
+                    for line in &layout.failed_command_lines {
+                        let marker_origin = point(
+                            bounds.origin.x + marker_width,
+                            origin.y + *line as f32 * layout.dimensions.line_height,
+                        );
+                        window.paint_quad(fill(
+                            Bounds::new(
+                                marker_origin,
+                                size(marker_width, layout.dimensions.line_height),
+                            ),
+                            cx.theme().status().error,
+                        ));
+                    }
+
                     for (relative_highlighted_range, color) in &layout.relative_highlighted_ranges {
                         if let Some((start_y, highlighted_range_lines)) =
                             to_highlighted_range_lines(relative_highlighted_range, layout, origin)
@@ -1330,6 +1365,27 @@ impl Element for TerminalElement {
                     }
                     let text_paint_time = text_paint_start.elapsed();
 
+                    for image in &layout.images {
+                        let image_origin = origin
+                            + point(
+                                image.point.column.0 as f32 * layout.dimensions.cell_width,
+                                image.point.line.0 as f32 * layout.dimensions.line_height,
+                            );
+                        let image_size = size(
+                            image.columns * layout.dimensions.cell_width,
+                            image.lines * layout.dimensions.line_height,
+                        );
+                        window
+                            .paint_image(
+                                Bounds::new(image_origin, image_size),
+                                Corners::default(),
+                                image.image.clone(),
+                                0,
+                                false,
+                            )
+                            .log_err();
+                    }
+
                     if let Some(text_to_mark) = &marked_text_cloned
                         && !text_to_mark.is_empty()
                         && let Some(ime_bounds) = layout.ime_cursor_bounds
@@ -12,11 +12,11 @@ use db::kvp::KeyValueStore;
 use futures::{channel::oneshot, future::join_all};
 use gpui::{
     Action, AnyView, App, AsyncApp, AsyncWindowContext, Context, Corner, Entity, EventEmitter,
-    FocusHandle, Focusable, IntoElement, ParentElement, Pixels, Render, Styled, Task, WeakEntity,
-    Window, actions,
+    FocusHandle, Focusable, IntoElement, ParentElement, Pixels, Render, Styled, Subscription, Task,
+    WeakEntity, Window, actions,
 };
 use itertools::Itertools;
-use project::{Fs, Project};
+use project::{Fs, Project, shared_terminal_store::SharedTerminalStoreEvent};
 
 use settings::{Settings, TerminalDockPosition};
 use task::{RevealStrategy, RevealTarget, Shell, ShellBuilder, SpawnInTerminal, TaskId};
@@ -58,12 +58,12 @@ pub fn init(cx: &mut App) {
             workspace.register_action(TerminalPanel::new_terminal);
             workspace.register_action(TerminalPanel::open_terminal);
             workspace.register_action(|workspace, _: &ToggleFocus, window, cx| {
-                if is_enabled_in_workspace(workspace, cx) {
+                if is_enabled_in_workspace(workspace, cx) || has_shared_terminals(workspace, cx) {
                     workspace.toggle_panel_focus::<TerminalPanel>(window, cx);
                 }
             });
             workspace.register_action(|workspace, _: &Toggle, window, cx| {
-                if is_enabled_in_workspace(workspace, cx) {
+                if is_enabled_in_workspace(workspace, cx) || has_shared_terminals(workspace, cx) {
                     if !workspace.toggle_panel_focus::<TerminalPanel>(window, cx) {
                         workspace.close_panel::<TerminalPanel>(window, cx);
                     }
@@ -85,6 +85,7 @@ pub struct TerminalPanel {
     assistant_enabled: bool,
     assistant_tab_bar_button: Option<AnyView>,
     active: bool,
+    _shared_terminals_subscription: Subscription,
 }
 
 impl TerminalPanel {
@@ -92,6 +93,17 @@ impl TerminalPanel {
         let project = workspace.project();
         let pane = new_terminal_pane(workspace.weak_handle(), project.clone(), false, window, cx);
         let center = PaneGroup::new(pane.clone());
+        // Guests of a collaborative project see the terminals the host shares with them.
+        let shared_terminal_store = project.read(cx).shared_terminal_store();
+        let shared_terminals_subscription = cx.subscribe_in(
+            &shared_terminal_store,
+            window,
+            |terminal_panel, _, event, window, cx| match event {
+                SharedTerminalStoreEvent::TerminalShared(terminal) => {
+                    terminal_panel.add_shared_terminal(terminal.clone(), window, cx);
+                }
+            },
+        );
         let terminal_panel = Self {
             center,
             active_pane: pane,
@@ -103,6 +115,7 @@ impl TerminalPanel {
             assistant_enabled: false,
             assistant_tab_bar_button: None,
             active: false,
+            _shared_terminals_subscription: shared_terminals_subscription,
         };
         terminal_panel.apply_tab_bar_buttons(&terminal_panel.active_pane, cx);
         terminal_panel
@@ -294,6 +307,27 @@ impl TerminalPanel {
             });
         }
 
+        // Terminals the host shared before the panel was loaded.
+        terminal_panel.update_in(&mut cx, |terminal_panel, window, cx| {
+            let Some(project) = terminal_panel
+                .workspace
+                .read_with(cx, |workspace, _| workspace.project().clone())
+                .ok()
+            else {
+                return;
+            };
+            let shared_terminals = project
+                .read(cx)
+                .shared_terminal_store()
+                .read(cx)
+                .joined_terminals()
+                .map(|(terminal, _)| terminal.clone())
+                .collect::<Vec<_>>();
+            for terminal in shared_terminals {
+                terminal_panel.add_shared_terminal(terminal, window, cx);
+            }
+        })?;
+
         // Since panels/docks are loaded outside from the workspace, we cleanup here, instead of through the workspace.
         if let Some(workspace) = workspace.upgrade() {
             let cleanup_task = workspace.update_in(&mut cx, |workspace, window, cx| {
@@ -843,6 +877,42 @@ impl TerminalPanel {
         })
     }
 
+    /// Shows a terminal the host of a collaborative project shared, unless it's shown already.
+    fn add_shared_terminal(
+        &mut self,
+        terminal: Entity<Terminal>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let already_shown = self.center.panes().into_iter().any(|pane| {
+            pane.read(cx).items().any(|item| {
+                item.downcast::<TerminalView>()
+                    .is_some_and(|terminal_view| terminal_view.read(cx).terminal() == &terminal)
+            })
+        });
+        if already_shown {
+            return;
+        }
+        let Some(workspace) = self.workspace.upgrade() else {
+            return;
+        };
+        let workspace_id = workspace.read(cx).database_id();
+        let project = workspace.read(cx).project().downgrade();
+        let terminal_view = Box::new(cx.new(|cx| {
+            TerminalView::new(
+                terminal,
+                workspace.downgrade(),
+                workspace_id,
+                project,
+                window,
+                cx,
+            )
+        }));
+        self.active_pane.update(cx, |pane, cx| {
+            pane.add_item(terminal_view, true, false, None, window, cx);
+        });
+    }
+
     fn add_terminal_shell(
         &mut self,
         cwd: Option<PathBuf>,
@@ -1127,9 +1197,10 @@ impl TerminalPanel {
     }
 
     fn is_enabled(&self, cx: &App) -> bool {
-        self.workspace
-            .upgrade()
-            .is_some_and(|workspace| is_enabled_in_workspace(workspace.read(cx), cx))
+        self.workspace.upgrade().is_some_and(|workspace| {
+            let workspace = workspace.read(cx);
+            is_enabled_in_workspace(workspace, cx) || has_shared_terminals(workspace, cx)
+        })
     }
 
     fn activate_pane_in_direction(
@@ -1198,6 +1269,17 @@ fn is_enabled_in_workspace(workspace: &Workspace, cx: &App) -> bool {
     workspace.project().read(cx).supports_terminal(cx)
 }
 
+fn has_shared_terminals(workspace: &Workspace, cx: &App) -> bool {
+    workspace
+        .project()
+        .read(cx)
+        .shared_terminal_store()
+        .read(cx)
+        .joined_terminals()
+        .next()
+        .is_some()
+}
+
 pub fn new_terminal_pane(
     workspace: WeakEntity<Workspace>,
     project: Entity<Project>,
@@ -8,15 +8,17 @@ mod terminal_slash_command;
 use assistant_slash_command::SlashCommandRegistry;
 use editor::{Editor, EditorSettings, actions::SelectAll, blink_manager::BlinkManager};
 use gpui::{
-    Action, AnyElement, App, ClipboardEntry, DismissEvent, Entity, EventEmitter, ExternalPaths,
-    FocusHandle, Focusable, Font, KeyContext, KeyDownEvent, Keystroke, MouseButton, MouseDownEvent,
-    Pixels, Point, Render, ScrollWheelEvent, Styled, Subscription, Task, WeakEntity, actions,
-    anchored, deferred, div,
+    Action, AnyElement, App, ClipboardEntry, ClipboardItem, DismissEvent, Entity, EventEmitter,
+    ExternalPaths, FocusHandle, Focusable, Font, KeyContext, KeyDownEvent, Keystroke, MouseButton,
+    MouseDownEvent, Pixels, Point, Render, ScrollWheelEvent, Styled, Subscription, Task,
+    WeakEntity, actions, anchored, deferred, div,
 };
 use itertools::Itertools;
 use menu;
 use persistence::TerminalDb;
-use project::{Project, ProjectEntryId, search::SearchQuery};
+use project::{
+    Project, ProjectEntryId, search::SearchQuery, shared_terminal_store::TerminalAccess,
+};
 use schemars::JsonSchema;
 use serde::Deserialize;
 use settings::{Settings, SettingsStore, TerminalBlink, WorkingDirectory};
@@ -31,8 +33,9 @@ use std::{
 };
 use task::TaskId;
 use terminal::{
-    Clear, Copy, Event, HoveredWord, MaybeNavigationTarget, Paste, ScrollLineDown, ScrollLineUp,
-    ScrollPageDown, ScrollPageUp, ScrollToBottom, ScrollToTop, ShowCharacterPalette, TaskState,
+    Clear, Copy, CopyLastCommandOutput, Event, HoveredWord, MaybeNavigationTarget, Paste,
+    ScrollLineDown, ScrollLineUp, ScrollPageDown, ScrollPageUp, ScrollToBottom,
+    ScrollToNextCommand, ScrollToPreviousCommand, ScrollToTop, ShowCharacterPalette, TaskState,
     TaskStatus, Terminal, TerminalBounds, ToggleViMode,
     alacritty_terminal::{
         index::Point as AlacPoint,
@@ -89,6 +92,12 @@ actions!(
     [
         /// Reruns the last executed task in the terminal.
         RerunTask,
+        /// Shares the terminal with the project's collaborators, who can type in it.
+        ShareTerminal,
+        /// Shares the terminal with the project's collaborators, who can only watch it.
+        ShareTerminalReadOnly,
+        /// Stops sharing the terminal with the project's collaborators.
+        UnshareTerminal,
     ]
 );
 
@@ -506,6 +515,11 @@ impl TerminalView {
             .selection_text
             .as_ref()
             .is_some_and(|text| !text.is_empty());
+        let can_share = self.project.upgrade().is_some_and(|project| {
+            let project = project.read(cx);
+            project.is_shared() && !project.is_via_collab()
+        });
+        let terminal_access = self.terminal_access(cx);
         let context_menu = ContextMenu::build(window, cx, |menu, _, _| {
             menu.context(self.focus_handle.clone())
                 .action("New Terminal", Box::new(NewTerminal::default()))
@@ -521,6 +535,21 @@ impl TerminalView {
                             menu.action("Add to Agent Thread", Box::new(AddSelectionToThread))
                         })
                 })
+                .when(can_share, |menu| {
+                    menu.separator()
+                        .when(terminal_access != Some(TerminalAccess::ReadWrite), |menu| {
+                            menu.action("Share with Collaborators", Box::new(ShareTerminal))
+                        })
+                        .when(terminal_access != Some(TerminalAccess::ReadOnly), |menu| {
+                            menu.action(
+                                "Share Read-Only with Collaborators",
+                                Box::new(ShareTerminalReadOnly),
+                            )
+                        })
+                        .when(terminal_access.is_some(), |menu| {
+                            menu.action("Stop Sharing", Box::new(UnshareTerminal))
+                        })
+                })
                 .separator()
                 .action(
                     "Close Terminal Tab",
@@ -622,6 +651,54 @@ impl TerminalView {
         window.dispatch_action(Box::new(task), cx);
     }
 
+    fn share_terminal(&mut self, _: &ShareTerminal, _: &mut Window, cx: &mut Context<Self>) {
+        self.set_terminal_access(Some(TerminalAccess::ReadWrite), cx);
+    }
+
+    fn share_terminal_read_only(
+        &mut self,
+        _: &ShareTerminalReadOnly,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.set_terminal_access(Some(TerminalAccess::ReadOnly), cx);
+    }
+
+    fn unshare_terminal(&mut self, _: &UnshareTerminal, _: &mut Window, cx: &mut Context<Self>) {
+        self.set_terminal_access(None, cx);
+    }
+
+    /// Shares the terminal with the project's collaborators, or stops sharing it.
+    fn set_terminal_access(&mut self, access: Option<TerminalAccess>, cx: &mut Context<Self>) {
+        let Some(project) = self.project.upgrade() else {
+            return;
+        };
+        let terminal = self.terminal.clone();
+        project.read(cx).shared_terminal_store().update(
+            cx,
+            |shared_terminal_store, cx| match access {
+                Some(access) => shared_terminal_store
+                    .share_terminal(&terminal, access, cx)
+                    .log_err(),
+                None => {
+                    shared_terminal_store.unshare_terminal(&terminal, cx);
+                    Some(())
+                }
+            },
+        );
+        cx.notify();
+    }
+
+    /// Returns what the project's collaborators can do with the terminal, if it's shared.
+    fn terminal_access(&self, cx: &App) -> Option<TerminalAccess> {
+        let project = self.project.upgrade()?;
+        project
+            .read(cx)
+            .shared_terminal_store()
+            .read(cx)
+            .terminal_access(&self.terminal)
+    }
+
     fn clear(&mut self, _: &Clear, _: &mut Window, cx: &mut Context<Self>) {
         self.scroll_top = px(0.);
         self.terminal.update(cx, |term, _| term.clear());
@@ -748,6 +825,28 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn scroll_to_previous_command(
+        &mut self,
+        _: &ScrollToPreviousCommand,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_previous_command());
+        cx.notify();
+    }
+
+    fn scroll_to_next_command(
+        &mut self,
+        _: &ScrollToNextCommand,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.terminal
+            .update(cx, |term, _| term.scroll_to_next_command());
+        cx.notify();
+    }
+
     fn toggle_vi_mode(&mut self, _: &ToggleViMode, _: &mut Window, cx: &mut Context<Self>) {
         self.terminal.update(cx, |term, _| term.toggle_vi_mode());
         cx.notify();
@@ -807,6 +906,17 @@ impl TerminalView {
         cx.notify();
     }
 
+    fn copy_last_command_output(
+        &mut self,
+        _: &CopyLastCommandOutput,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if let Some(output) = self.terminal.read(cx).last_command_output() {
+            cx.write_to_clipboard(ClipboardItem::new_string(output));
+        }
+    }
+
     ///Attempt to paste the clipboard into the terminal
     fn paste(&mut self, _: &Paste, _: &mut Window, cx: &mut Context<Self>) {
         let Some(clipboard) = cx.read_from_clipboard() else {
@@ -1230,11 +1340,17 @@ impl Render for TerminalView {
             .on_action(cx.listener(TerminalView::scroll_page_down))
             .on_action(cx.listener(TerminalView::scroll_to_top))
             .on_action(cx.listener(TerminalView::scroll_to_bottom))
+            .on_action(cx.listener(TerminalView::scroll_to_previous_command))
+            .on_action(cx.listener(TerminalView::scroll_to_next_command))
+            .on_action(cx.listener(TerminalView::copy_last_command_output))
             .on_action(cx.listener(TerminalView::toggle_vi_mode))
             .on_action(cx.listener(TerminalView::show_character_palette))
             .on_action(cx.listener(TerminalView::select_all))
             .on_action(cx.listener(TerminalView::rerun_task))
             .on_action(cx.listener(TerminalView::rename_terminal))
+            .on_action(cx.listener(TerminalView::share_terminal))
+            .on_action(cx.listener(TerminalView::share_terminal_read_only))
+            .on_action(cx.listener(TerminalView::unshare_terminal))
             .on_key_down(cx.listener(Self::key_down))
             .on_mouse_down(
                 MouseButton::Right,
@@ -1347,6 +1463,9 @@ impl Item for TerminalView {
                     }
                 }
             },
+            None if self.terminal_access(cx).is_some() => {
+                (IconName::UserGroup, Color::Accent, None)
+            }
             None => (IconName::Terminal, Color::Muted, None),
         };
 
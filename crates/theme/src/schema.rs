@@ -5,7 +5,7 @@ use palette::FromColor;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use settings::IntoGpui;
-pub use settings::{FontWeightContent, WindowBackgroundContent};
+pub use settings::{FontWeightContent, PlayerColorContent, WindowBackgroundContent};
 
 use crate::{StatusColorsRefinement, ThemeColorsRefinement};
 
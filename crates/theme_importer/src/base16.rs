@@ -0,0 +1,187 @@
+use anyhow::{Context as _, Result};
+use collections::{HashMap, IndexMap};
+use theme::HighlightStyleContent;
+
+use crate::base_colors::BaseColors;
+use crate::color::parse_hex_color;
+use crate::{ImportedTheme, MappingReport};
+
+/// The Zed syntax tokens colored by each base16 color.
+const SYNTAX_COLORS: &[(&str, &[&str])] = &[
+    ("base03", &["comment", "comment.doc"]),
+    ("base05", &["operator", "punctuation", "variable"]),
+    (
+        "base08",
+        &["variable.special", "tag", "property", "link_uri"],
+    ),
+    ("base09", &["number", "boolean", "constant", "attribute"]),
+    ("base0A", &["type", "enum", "constructor"]),
+    ("base0B", &["string", "text.literal"]),
+    (
+        "base0C",
+        &["string.escape", "string.regex", "string.special"],
+    ),
+    ("base0D", &["function", "title", "link_text"]),
+    ("base0E", &["keyword", "preproc", "label"]),
+    ("base0F", &["embedded", "punctuation.special"]),
+];
+
+/// The base16 colors used for the 16 ANSI colors. base24 schemes define brighter colors in
+/// `base12` to `base17`.
+const ANSI_COLORS: [&[&str]; 16] = [
+    &["base00"],
+    &["base08"],
+    &["base0B"],
+    &["base0A"],
+    &["base0D"],
+    &["base0E"],
+    &["base0C"],
+    &["base05"],
+    &["base03"],
+    &["base12", "base08"],
+    &["base14", "base0B"],
+    &["base13", "base0A"],
+    &["base16", "base0D"],
+    &["base17", "base0E"],
+    &["base15", "base0C"],
+    &["base07"],
+];
+
+/// Imports a base16 or base24 scheme.
+///
+/// Both the original format, where the colors are top-level keys, and the newer one, where
+/// they're nested under `palette`, are supported.
+pub(crate) fn import_base16_scheme(default_name: &str, contents: &[u8]) -> Result<ImportedTheme> {
+    let contents = std::str::from_utf8(contents).context("base16 scheme isn't UTF-8")?;
+
+    let mut report = MappingReport::default();
+    let mut name = None;
+    let mut author = None;
+    let mut colors = HashMap::default();
+    for line in contents.lines() {
+        let line = line.split(" #").next().unwrap_or_default();
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let key = key.trim();
+        let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+        match key {
+            "scheme" | "name" => name = Some(value.to_string()),
+            "author" => author = Some(value.to_string()),
+            "system" | "variant" | "slug" | "description" | "palette" => {}
+            _ if is_base16_key(key) => {
+                let color = parse_hex_color(value)
+                    .with_context(|| format!("invalid color for {key}: {value:?}"))?;
+                colors.insert(key.to_string(), color);
+            }
+            _ => report.add_unmapped_color(key),
+        }
+    }
+
+    let color = |key: &str| colors.get(key).cloned();
+    let base_colors = BaseColors {
+        background: color("base00").context("scheme has no base00 color")?,
+        foreground: color("base05").context("scheme has no base05 color")?,
+        cursor: color("base05"),
+        selection: color("base02"),
+        line_highlight: color("base01"),
+        line_number: color("base03"),
+        invisible: color("base03"),
+        search_match: None,
+        ansi: ANSI_COLORS.map(|keys| keys.iter().find_map(|&key| color(key))),
+    };
+
+    let mut syntax = IndexMap::default();
+    for &(key, tokens) in SYNTAX_COLORS {
+        let Some(color) = color(key) else {
+            continue;
+        };
+        for token in tokens {
+            syntax.insert(
+                token.to_string(),
+                HighlightStyleContent {
+                    color: Some(color.clone()),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+
+    let name = name.unwrap_or_else(|| default_name.to_string());
+    let theme = base_colors.into_theme(name, syntax);
+    Ok(ImportedTheme::new(theme, author, report))
+}
+
+/// Returns whether the key is one of `base00` to `base0F`, or one of base24's `base10` to
+/// `base17`.
+fn is_base16_key(key: &str) -> bool {
+    key.strip_prefix("base").is_some_and(|index| {
+        index.len() == 2 && u8::from_str_radix(index, 16).is_ok_and(|index| index <= 0x17)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_base16_scheme() {
+        let contents = r##"
+# A base24 scheme in the newer format.
+system: "base24"
+name: "Test Dark"
+author: "Someone"
+variant: "dark"
+palette:
+  base00: "#1d1f21" # background
+  base01: "#282a2e"
+  base02: "#373b41"
+  base03: "#969896"
+  base04: "#b4b7b4"
+  base05: "#c5c8c6"
+  base06: "#e0e0e0"
+  base07: "#ffffff"
+  base08: "#cc6666"
+  base09: "#de935f"
+  base0A: "#f0c674"
+  base0B: "#b5bd68"
+  base0C: "#8abeb7"
+  base0D: "#81a2be"
+  base0E: "#b294bb"
+  base0F: "#a3685a"
+  base12: "#ff7777"
+"##;
+
+        let imported = import_base16_scheme("fallback", contents.as_bytes()).unwrap();
+        assert_eq!(imported.family.name, "Test Dark");
+        assert_eq!(imported.family.author, "Someone");
+        let theme = &imported.family.themes[0];
+        assert_eq!(theme.appearance, theme::AppearanceContent::Dark);
+        let colors = &theme.style.colors;
+        assert_eq!(colors.editor_background.as_deref(), Some("#1d1f21ff"));
+        assert_eq!(colors.terminal_ansi_red.as_deref(), Some("#cc6666ff"));
+        assert_eq!(
+            colors.terminal_ansi_bright_red.as_deref(),
+            Some("#ff7777ff")
+        );
+        assert_eq!(
+            colors.terminal_ansi_bright_green.as_deref(),
+            Some("#b5bd68ff")
+        );
+        assert_eq!(
+            theme.style.syntax["keyword"].color.as_deref(),
+            Some("#b294bbff")
+        );
+        assert_eq!(theme.style.players.len(), 6);
+        assert!(imported.report.is_empty());
+    }
+
+    #[test]
+    fn test_import_base16_scheme_without_background() {
+        let contents = "scheme: \"Broken\"\nbase05: \"c5c8c6\"\n";
+        assert!(import_base16_scheme("fallback", contents.as_bytes()).is_err());
+    }
+}
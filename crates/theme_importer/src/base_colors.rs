@@ -0,0 +1,273 @@
+use collections::IndexMap;
+use theme::{
+    AppearanceContent, HighlightStyleContent, PlayerColorContent, StatusColorsContent,
+    ThemeColorsContent, ThemeContent, ThemeStyleContent, WindowBackgroundContent,
+};
+
+use crate::color::{is_light, mix_colors, with_alpha};
+
+const BLACK: usize = 0;
+const RED: usize = 1;
+const GREEN: usize = 2;
+const YELLOW: usize = 3;
+const BLUE: usize = 4;
+const MAGENTA: usize = 5;
+const CYAN: usize = 6;
+const WHITE: usize = 7;
+
+/// The handful of colors that editor color schemes and terminal palettes define, from which the
+/// rest of a Zed theme is derived.
+///
+/// All colors are in the `#rrggbbaa` form.
+#[derive(Debug, Default)]
+pub(crate) struct BaseColors {
+    pub background: String,
+    pub foreground: String,
+    pub cursor: Option<String>,
+    pub selection: Option<String>,
+    pub line_highlight: Option<String>,
+    pub line_number: Option<String>,
+    pub invisible: Option<String>,
+    pub search_match: Option<String>,
+    /// The 16 ANSI colors, with the 8 bright colors last.
+    pub ansi: [Option<String>; 16],
+}
+
+impl BaseColors {
+    pub fn appearance(&self) -> AppearanceContent {
+        if is_light(&self.background) {
+            AppearanceContent::Light
+        } else {
+            AppearanceContent::Dark
+        }
+    }
+
+    fn mix(&self, amount: f32) -> Option<String> {
+        mix_colors(&self.background, &self.foreground, amount)
+    }
+
+    fn ansi(&self, index: usize) -> Option<String> {
+        self.ansi[index].clone()
+    }
+
+    /// Returns the bright variant of an ANSI color, falling back to the normal one.
+    fn bright_ansi(&self, index: usize) -> Option<String> {
+        self.ansi(index + 8).or_else(|| self.ansi(index))
+    }
+
+    pub fn into_theme(
+        self,
+        name: String,
+        syntax: IndexMap<String, HighlightStyleContent>,
+    ) -> ThemeContent {
+        ThemeContent {
+            name,
+            appearance: self.appearance(),
+            style: ThemeStyleContent {
+                window_background_appearance: Some(WindowBackgroundContent::Opaque),
+                accents: Vec::new(),
+                colors: self.theme_colors(),
+                status: self.status_colors(),
+                players: self.players(),
+                syntax,
+            },
+        }
+    }
+
+    fn theme_colors(&self) -> ThemeColorsContent {
+        let background = Some(self.background.clone());
+        let foreground = Some(self.foreground.clone());
+        let surface = self.mix(0.04);
+        let border = self.mix(0.15);
+        let selection = self.selection.clone().or_else(|| self.mix(0.2));
+        let accent = self.ansi(BLUE).or_else(|| self.cursor.clone());
+
+        ThemeColorsContent {
+            border: border.clone(),
+            border_variant: self.mix(0.1),
+            border_focused: accent.clone(),
+            border_selected: accent.clone(),
+            border_transparent: Some("#00000000".to_string()),
+            border_disabled: self.mix(0.08),
+            elevated_surface_background: surface.clone(),
+            surface_background: surface.clone(),
+            background: surface.clone(),
+            element_background: self.mix(0.08),
+            element_hover: self.mix(0.12),
+            element_active: self.mix(0.16),
+            element_selected: self.mix(0.16),
+            element_disabled: self.mix(0.06),
+            drop_target_background: selection,
+            ghost_element_hover: self.mix(0.12),
+            ghost_element_active: self.mix(0.16),
+            ghost_element_selected: self.mix(0.16),
+            text: foreground.clone(),
+            text_muted: self.mix(0.65),
+            text_placeholder: self.mix(0.45),
+            text_disabled: self.mix(0.35),
+            text_accent: accent.clone(),
+            icon: foreground.clone(),
+            icon_muted: self.mix(0.65),
+            icon_disabled: self.mix(0.35),
+            icon_placeholder: self.mix(0.45),
+            icon_accent: accent.clone(),
+            status_bar_background: surface.clone(),
+            title_bar_background: surface.clone(),
+            title_bar_inactive_background: surface.clone(),
+            toolbar_background: background.clone(),
+            tab_bar_background: surface.clone(),
+            tab_inactive_background: surface.clone(),
+            tab_active_background: background.clone(),
+            search_match_background: self
+                .search_match
+                .clone()
+                .or_else(|| self.ansi(YELLOW).and_then(|color| with_alpha(&color, 0.3))),
+            panel_background: surface,
+            pane_group_border: border.clone(),
+            scrollbar_thumb_background: self.mix(0.2),
+            scrollbar_thumb_hover_background: self.mix(0.3),
+            scrollbar_thumb_border: self.mix(0.2),
+            scrollbar_track_background: background.clone(),
+            scrollbar_track_border: border,
+            editor_foreground: foreground.clone(),
+            editor_background: background.clone(),
+            editor_gutter_background: background.clone(),
+            editor_active_line_background: self.line_highlight.clone().or_else(|| self.mix(0.05)),
+            editor_line_number: self.line_number.clone().or_else(|| self.mix(0.4)),
+            editor_active_line_number: foreground.clone(),
+            editor_invisible: self.invisible.clone().or_else(|| self.mix(0.3)),
+            editor_wrap_guide: self.mix(0.08),
+            editor_active_wrap_guide: self.mix(0.15),
+            editor_indent_guide: self.mix(0.08),
+            editor_indent_guide_active: self.mix(0.2),
+            editor_document_highlight_read_background: self.mix(0.12),
+            editor_document_highlight_write_background: self.mix(0.18),
+            terminal_background: background,
+            terminal_foreground: foreground,
+            terminal_ansi_black: self.ansi(BLACK),
+            terminal_ansi_red: self.ansi(RED),
+            terminal_ansi_green: self.ansi(GREEN),
+            terminal_ansi_yellow: self.ansi(YELLOW),
+            terminal_ansi_blue: self.ansi(BLUE),
+            terminal_ansi_magenta: self.ansi(MAGENTA),
+            terminal_ansi_cyan: self.ansi(CYAN),
+            terminal_ansi_white: self.ansi(WHITE),
+            terminal_ansi_bright_black: self.bright_ansi(BLACK),
+            terminal_ansi_bright_red: self.bright_ansi(RED),
+            terminal_ansi_bright_green: self.bright_ansi(GREEN),
+            terminal_ansi_bright_yellow: self.bright_ansi(YELLOW),
+            terminal_ansi_bright_blue: self.bright_ansi(BLUE),
+            terminal_ansi_bright_magenta: self.bright_ansi(MAGENTA),
+            terminal_ansi_bright_cyan: self.bright_ansi(CYAN),
+            terminal_ansi_bright_white: self.bright_ansi(WHITE),
+            link_text_hover: accent,
+            ..Default::default()
+        }
+    }
+
+    fn status_colors(&self) -> StatusColorsContent {
+        let background = |color: &Option<String>| {
+            color
+                .as_deref()
+                .and_then(|color| mix_colors(&self.background, color, 0.15))
+        };
+        let red = self.ansi(RED);
+        let green = self.ansi(GREEN);
+        let yellow = self.ansi(YELLOW);
+        let blue = self.ansi(BLUE);
+
+        StatusColorsContent {
+            conflict: yellow.clone(),
+            created: green.clone(),
+            deleted: red.clone(),
+            error: red.clone(),
+            error_background: background(&red),
+            error_border: red,
+            hidden: self.mix(0.35),
+            hint: self.mix(0.5),
+            ignored: self.mix(0.45),
+            info: blue.clone(),
+            info_background: background(&blue),
+            info_border: blue,
+            modified: yellow.clone(),
+            predictive: self.mix(0.45),
+            renamed: self.ansi(CYAN),
+            success: green.clone(),
+            success_background: background(&green),
+            success_border: green,
+            unreachable: self.mix(0.45),
+            warning: yellow.clone(),
+            warning_background: background(&yellow),
+            warning_border: yellow,
+            ..Default::default()
+        }
+    }
+
+    fn players(&self) -> Vec<PlayerColorContent> {
+        let local_cursor = self
+            .cursor
+            .clone()
+            .or_else(|| self.ansi(BLUE))
+            .unwrap_or_else(|| self.foreground.clone());
+        let local_player = PlayerColorContent {
+            background: Some(local_cursor.clone()),
+            selection: self
+                .selection
+                .clone()
+                .or_else(|| with_alpha(&local_cursor, 0.24)),
+            cursor: Some(local_cursor),
+        };
+
+        let remote_players = [GREEN, MAGENTA, YELLOW, CYAN, RED]
+            .into_iter()
+            .filter_map(|index| self.ansi(index))
+            .map(|color| PlayerColorContent {
+                background: Some(color.clone()),
+                selection: with_alpha(&color, 0.24),
+                cursor: Some(color),
+            });
+
+        std::iter::once(local_player)
+            .chain(remote_players)
+            .collect()
+    }
+}
+
+/// Derives syntax highlighting from a terminal's ANSI palette, for color schemes that don't
+/// define any.
+pub(crate) fn syntax_from_ansi(colors: &BaseColors) -> IndexMap<String, HighlightStyleContent> {
+    let mut syntax = IndexMap::default();
+    let tokens = [
+        ("comment", colors.bright_ansi(BLACK)),
+        ("keyword", colors.ansi(MAGENTA)),
+        ("string", colors.ansi(GREEN)),
+        ("string.escape", colors.ansi(CYAN)),
+        ("string.regex", colors.ansi(CYAN)),
+        ("number", colors.ansi(YELLOW)),
+        ("boolean", colors.ansi(YELLOW)),
+        ("constant", colors.ansi(YELLOW)),
+        ("function", colors.ansi(BLUE)),
+        ("title", colors.ansi(BLUE)),
+        ("type", colors.ansi(CYAN)),
+        ("constructor", colors.ansi(CYAN)),
+        ("tag", colors.ansi(RED)),
+        ("attribute", colors.ansi(YELLOW)),
+        ("property", colors.ansi(RED)),
+        ("variable.special", colors.ansi(RED)),
+        ("operator", Some(colors.foreground.clone())),
+        ("punctuation", Some(colors.foreground.clone())),
+        ("variable", Some(colors.foreground.clone())),
+    ];
+    for (token, color) in tokens {
+        if let Some(color) = color {
+            syntax.insert(
+                token.to_string(),
+                HighlightStyleContent {
+                    color: Some(color),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+    syntax
+}
@@ -1,8 +1,7 @@
 use anyhow::Result;
-use gpui::Hsla;
+use gpui::{Hsla, Rgba};
 use palette::FromColor;
 
-#[allow(unused)]
 pub(crate) fn try_parse_color(color: &str) -> Result<Hsla> {
     let rgba = gpui::Rgba::try_from(color)?;
     let rgba = palette::rgb::Srgba::from_components((rgba.r, rgba.g, rgba.b, rgba.a));
@@ -27,6 +26,55 @@ pub(crate) fn pack_color(color: Hsla) -> u32 {
     u32::from(rgba)
 }
 
+/// Parses a hex color written as `#rrggbb`, `rrggbb`, or `0xrrggbb`, with or without alpha,
+/// into the `#rrggbbaa` form that Zed themes use.
+pub(crate) fn parse_hex_color(value: &str) -> Option<String> {
+    let value = value.trim();
+    let hex = value
+        .strip_prefix('#')
+        .or_else(|| value.strip_prefix("0x"))
+        .unwrap_or(value);
+    if !matches!(hex.len(), 3 | 4 | 6 | 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let rgba = Rgba::try_from(format!("#{hex}").as_str()).ok()?;
+    Some(format_color(rgba))
+}
+
+pub(crate) fn format_color(color: Rgba) -> String {
+    let channel = |value: f32| (value.clamp(0., 1.) * 255.).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}{:02x}",
+        channel(color.r),
+        channel(color.g),
+        channel(color.b),
+        channel(color.a)
+    )
+}
+
+/// Mixes `amount` of `foreground` into `background`.
+pub(crate) fn mix_colors(background: &str, foreground: &str, amount: f32) -> Option<String> {
+    let background = Rgba::try_from(background).ok()?;
+    let foreground = Rgba::try_from(foreground).ok()?;
+    let mix = |background: f32, foreground: f32| background + (foreground - background) * amount;
+    Some(format_color(Rgba {
+        r: mix(background.r, foreground.r),
+        g: mix(background.g, foreground.g),
+        b: mix(background.b, foreground.b),
+        a: mix(background.a, foreground.a),
+    }))
+}
+
+pub(crate) fn with_alpha(color: &str, alpha: f32) -> Option<String> {
+    let color = Rgba::try_from(color).ok()?;
+    Some(format_color(Rgba { a: alpha, ..color }))
+}
+
+/// Returns whether a background color calls for a light theme.
+pub(crate) fn is_light(color: &str) -> bool {
+    try_parse_color(color).is_ok_and(|color| color.l > 0.5)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -53,4 +101,25 @@ mod tests {
 
         assert_eq!(format!("#{:x}", rgba), color);
     }
+
+    #[test]
+    pub fn test_parse_hex_color() {
+        assert_eq!(parse_hex_color("#B4637A").as_deref(), Some("#b4637aff"));
+        assert_eq!(parse_hex_color("b4637a80").as_deref(), Some("#b4637a80"));
+        assert_eq!(parse_hex_color("0xb4637a").as_deref(), Some("#b4637aff"));
+        assert_eq!(parse_hex_color("#fff").as_deref(), Some("#ffffffff"));
+        assert_eq!(parse_hex_color("red"), None);
+        assert_eq!(parse_hex_color("#b4637"), None);
+    }
+
+    #[test]
+    pub fn test_mix_colors() {
+        assert_eq!(
+            mix_colors("#000000ff", "#ffffffff", 0.5).as_deref(),
+            Some("#808080ff")
+        );
+        assert_eq!(with_alpha("#b4637aff", 0.5).as_deref(), Some("#b4637a80"));
+        assert!(is_light("#fafafaff"));
+        assert!(!is_light("#1e1e1eff"));
+    }
 }
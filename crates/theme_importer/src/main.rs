@@ -1,55 +1,13 @@
-mod color;
-mod vscode;
-
 use std::fs::File;
 use std::io::{Read, Write};
 use std::path::PathBuf;
 
 use anyhow::{Context as _, Result};
 use clap::Parser;
-use collections::IndexMap;
 use log::LevelFilter;
-use serde::Deserialize;
 use simplelog::ColorChoice;
 use simplelog::{TermLogger, TerminalMode};
-use theme::{Appearance, AppearanceContent};
-
-use crate::vscode::VsCodeTheme;
-use crate::vscode::VsCodeThemeConverter;
-
-const ZED_THEME_SCHEMA_URL: &str = "https://zed.dev/schema/themes/v0.2.0.json";
-
-#[derive(Debug, Clone, Copy, Deserialize)]
-#[serde(rename_all = "snake_case")]
-pub enum ThemeAppearanceJson {
-    Light,
-    Dark,
-}
-
-impl From<ThemeAppearanceJson> for AppearanceContent {
-    fn from(value: ThemeAppearanceJson) -> Self {
-        match value {
-            ThemeAppearanceJson::Light => Self::Light,
-            ThemeAppearanceJson::Dark => Self::Dark,
-        }
-    }
-}
-
-impl From<ThemeAppearanceJson> for Appearance {
-    fn from(value: ThemeAppearanceJson) -> Self {
-        match value {
-            ThemeAppearanceJson::Light => Self::Light,
-            ThemeAppearanceJson::Dark => Self::Dark,
-        }
-    }
-}
-
-#[derive(Debug, Deserialize)]
-pub struct ThemeMetadata {
-    pub name: String,
-    pub file_name: String,
-    pub appearance: ThemeAppearanceJson,
-}
+use theme_importer::{ThemeFormat, import_theme};
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
@@ -98,24 +56,39 @@ fn main() -> Result<()> {
         }
     };
 
-    let vscode_theme: VsCodeTheme = serde_json_lenient::from_slice(&buffer)
+    let format = ThemeFormat::from_path(&theme_file_path)
+        .with_context(|| format!("unrecognized theme format {theme_file_path:?}"))?;
+    let default_name = theme_file_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let imported = import_theme(format, &default_name, &buffer)
         .context(format!("failed to parse theme {theme_file_path:?}"))?;
 
-    let theme_metadata = ThemeMetadata {
-        name: vscode_theme.name.clone().unwrap_or("".to_string()),
-        appearance: ThemeAppearanceJson::Dark,
-        file_name: "".to_string(),
-    };
-
-    let converter = VsCodeThemeConverter::new(vscode_theme, theme_metadata, IndexMap::default());
+    for scope in &imported.report.unmapped_scopes {
+        log::warn!("No Zed syntax token was mapped from scope '{scope}'");
+    }
+    for color in &imported.report.unmapped_colors {
+        log::warn!("Color '{color}' was not mapped to a Zed theme color");
+    }
 
-    let theme = converter.convert()?;
-    let mut theme = serde_json::to_value(theme).unwrap();
-    theme.as_object_mut().unwrap().insert(
-        "$schema".to_string(),
-        serde_json::Value::String(ZED_THEME_SCHEMA_URL.to_string()),
-    );
-    let theme_json = serde_json::to_string_pretty(&theme).unwrap();
+    let theme_json = match format {
+        // VS Code themes are printed as a single theme, for use in the bundled theme families.
+        ThemeFormat::VsCode => {
+            let theme = imported
+                .family
+                .themes
+                .first()
+                .with_context(|| format!("no themes found in {theme_file_path:?}"))?;
+            let mut theme = serde_json::to_value(theme)?;
+            theme.as_object_mut().unwrap().insert(
+                "$schema".to_string(),
+                serde_json::Value::String(theme_importer::ZED_THEME_SCHEMA_URL.to_string()),
+            );
+            serde_json::to_string_pretty(&theme)?
+        }
+        _ => imported.to_json()?,
+    };
 
     if let Some(output) = args.output {
         let mut file = File::create(output)?;
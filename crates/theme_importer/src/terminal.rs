@@ -0,0 +1,321 @@
+//! Importers for terminal color schemes, which only define ANSI palettes and a few editor-like
+//! colors. Syntax highlighting is derived from the palette.
+
+use std::collections::BTreeMap;
+
+use anyhow::{Context as _, Result};
+use gpui::Rgba;
+use serde::Deserialize;
+
+use crate::base_colors::{BaseColors, syntax_from_ansi};
+use crate::color::{format_color, parse_hex_color};
+use crate::{ImportedTheme, MappingReport};
+
+const ANSI_COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// The colors of a terminal color scheme, as they're read.
+#[derive(Default)]
+struct TerminalColors {
+    background: Option<String>,
+    foreground: Option<String>,
+    cursor: Option<String>,
+    selection: Option<String>,
+    ansi: [Option<String>; 16],
+}
+
+impl TerminalColors {
+    fn into_imported_theme(self, name: String, report: MappingReport) -> Result<ImportedTheme> {
+        let base_colors = BaseColors {
+            background: self
+                .background
+                .context("color scheme has no background color")?,
+            foreground: self
+                .foreground
+                .context("color scheme has no foreground color")?,
+            cursor: self.cursor,
+            selection: self.selection,
+            line_highlight: None,
+            line_number: None,
+            invisible: None,
+            search_match: None,
+            ansi: self.ansi,
+        };
+        let syntax = syntax_from_ansi(&base_colors);
+        let theme = base_colors.into_theme(name, syntax);
+        Ok(ImportedTheme::new(theme, None, report))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ITermColor {
+    #[serde(rename = "Red Component")]
+    red: f32,
+    #[serde(rename = "Green Component")]
+    green: f32,
+    #[serde(rename = "Blue Component")]
+    blue: f32,
+    #[serde(rename = "Alpha Component")]
+    alpha: Option<f32>,
+}
+
+/// Imports an iTerm2 color preset (`.itermcolors`), which is a property list of colors with
+/// components between 0 and 1.
+pub(crate) fn import_iterm2_colors(default_name: &str, contents: &[u8]) -> Result<ImportedTheme> {
+    let iterm_colors: BTreeMap<String, ITermColor> =
+        plist::from_bytes(contents).context("failed to parse iTerm2 color preset")?;
+
+    let mut report = MappingReport::default();
+    let mut colors = TerminalColors::default();
+    for (key, color) in iterm_colors {
+        let color = format_color(Rgba {
+            r: color.red,
+            g: color.green,
+            b: color.blue,
+            a: color.alpha.unwrap_or(1.),
+        });
+        let ansi_index = key
+            .strip_prefix("Ansi ")
+            .and_then(|key| key.strip_suffix(" Color"))
+            .and_then(|index| index.parse::<usize>().ok())
+            .filter(|index| *index < 16);
+        if let Some(index) = ansi_index {
+            colors.ansi[index] = Some(color);
+            continue;
+        }
+        match key.as_str() {
+            "Background Color" => colors.background = Some(color),
+            "Foreground Color" => colors.foreground = Some(color),
+            "Cursor Color" => colors.cursor = Some(color),
+            "Selection Color" => colors.selection = Some(color),
+            _ => report.add_unmapped_color(&key),
+        }
+    }
+
+    colors.into_imported_theme(default_name.to_string(), report)
+}
+
+/// Imports an Alacritty color scheme, which is the `colors` table of an Alacritty TOML config.
+pub(crate) fn import_alacritty_colors(
+    default_name: &str,
+    contents: &[u8],
+) -> Result<ImportedTheme> {
+    let contents = std::str::from_utf8(contents).context("Alacritty config isn't UTF-8")?;
+    let config: toml::Table =
+        toml::from_str(contents).context("failed to parse Alacritty config")?;
+    let sections = config
+        .get("colors")
+        .and_then(|colors| colors.as_table())
+        .context("Alacritty config has no colors")?;
+
+    let mut report = MappingReport::default();
+    let mut colors = TerminalColors::default();
+    for (section, values) in sections {
+        let Some(values) = values.as_table() else {
+            report.add_unmapped_color(section);
+            continue;
+        };
+        for (key, value) in values {
+            let Some(color) = value.as_str().and_then(parse_hex_color) else {
+                report.add_unmapped_color(&format!("{section}.{key}"));
+                continue;
+            };
+            let ansi_index = ANSI_COLOR_NAMES
+                .iter()
+                .position(|name| *name == key.as_str());
+            match (section.as_str(), key.as_str(), ansi_index) {
+                ("primary", "background", _) => colors.background = Some(color),
+                ("primary", "foreground", _) => colors.foreground = Some(color),
+                ("cursor", "cursor", _) => colors.cursor = Some(color),
+                ("selection", "background", _) => colors.selection = Some(color),
+                ("normal", _, Some(index)) => colors.ansi[index] = Some(color),
+                ("bright", _, Some(index)) => colors.ansi[index + 8] = Some(color),
+                _ => report.add_unmapped_color(&format!("{section}.{key}")),
+            }
+        }
+    }
+
+    colors.into_imported_theme(default_name.to_string(), report)
+}
+
+/// Imports a Ghostty theme, which is a config file of `key = value` lines.
+pub(crate) fn import_ghostty_colors(default_name: &str, contents: &[u8]) -> Result<ImportedTheme> {
+    let contents = std::str::from_utf8(contents).context("Ghostty theme isn't UTF-8")?;
+
+    let mut report = MappingReport::default();
+    let mut colors = TerminalColors::default();
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        let value = value.trim();
+        if key == "palette" {
+            let palette_color = value.split_once('=').and_then(|(index, color)| {
+                let index = index
+                    .trim()
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|index| *index < 16)?;
+                Some((index, parse_hex_color(color)?))
+            });
+            match palette_color {
+                Some((index, color)) => colors.ansi[index] = Some(color),
+                None => report.add_unmapped_color(&format!("palette = {value}")),
+            }
+            continue;
+        }
+        let Some(color) = parse_hex_color(value) else {
+            report.add_unmapped_color(key);
+            continue;
+        };
+        match key {
+            "background" => colors.background = Some(color),
+            "foreground" => colors.foreground = Some(color),
+            "cursor-color" => colors.cursor = Some(color),
+            "selection-background" => colors.selection = Some(color),
+            _ => report.add_unmapped_color(key),
+        }
+    }
+
+    colors.into_imported_theme(default_name.to_string(), report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_alacritty_colors() {
+        let contents = r##"
+[colors.primary]
+background = "0x282828"
+foreground = "#ebdbb2"
+
+[colors.cursor]
+text = "CellBackground"
+cursor = "#ebdbb2"
+
+[colors.normal]
+black = "#282828"
+red = "#cc241d"
+green = "#98971a"
+yellow = "#d79921"
+blue = "#458588"
+magenta = "#b16286"
+cyan = "#689d6a"
+white = "#a89984"
+
+[colors.bright]
+black = "#928374"
+red = "#fb4934"
+"##;
+
+        let imported = import_alacritty_colors("Gruvbox", contents.as_bytes()).unwrap();
+        let theme = &imported.family.themes[0];
+        assert_eq!(theme.name, "Gruvbox");
+        let colors = &theme.style.colors;
+        assert_eq!(colors.editor_background.as_deref(), Some("#282828ff"));
+        assert_eq!(
+            colors.terminal_ansi_bright_red.as_deref(),
+            Some("#fb4934ff")
+        );
+        assert_eq!(
+            colors.terminal_ansi_bright_blue.as_deref(),
+            Some("#458588ff")
+        );
+        assert_eq!(
+            theme.style.syntax["comment"].color.as_deref(),
+            Some("#928374ff")
+        );
+        assert_eq!(imported.report.unmapped_colors, ["cursor.text"]);
+    }
+
+    #[test]
+    fn test_import_ghostty_colors() {
+        let contents = "
+# A light theme.
+palette = 0=#000000
+palette = 1=#c91b00
+palette = 2=#00c200
+background = ffffff
+foreground = 000000
+cursor-color = c7c7c7
+selection-foreground = 000000
+";
+
+        let imported = import_ghostty_colors("Light", contents.as_bytes()).unwrap();
+        let theme = &imported.family.themes[0];
+        assert_eq!(theme.appearance, theme::AppearanceContent::Light);
+        assert_eq!(
+            theme.style.colors.terminal_ansi_red.as_deref(),
+            Some("#c91b00ff")
+        );
+        assert_eq!(theme.style.players[0].cursor.as_deref(), Some("#c7c7c7ff"));
+        assert_eq!(
+            theme.style.syntax["string"].color.as_deref(),
+            Some("#00c200ff")
+        );
+        assert_eq!(imported.report.unmapped_colors, ["selection-foreground"]);
+    }
+
+    #[test]
+    fn test_import_iterm2_colors() {
+        let contents = r##"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Ansi 1 Color</key>
+    <dict>
+        <key>Blue Component</key>
+        <real>0.0</real>
+        <key>Green Component</key>
+        <real>0.0</real>
+        <key>Red Component</key>
+        <real>1</real>
+    </dict>
+    <key>Background Color</key>
+    <dict>
+        <key>Blue Component</key>
+        <real>0.0</real>
+        <key>Green Component</key>
+        <real>0.0</real>
+        <key>Red Component</key>
+        <real>0.0</real>
+    </dict>
+    <key>Foreground Color</key>
+    <dict>
+        <key>Blue Component</key>
+        <real>1</real>
+        <key>Green Component</key>
+        <real>1</real>
+        <key>Red Component</key>
+        <real>1</real>
+    </dict>
+    <key>Bold Color</key>
+    <dict>
+        <key>Blue Component</key>
+        <real>1</real>
+        <key>Green Component</key>
+        <real>1</real>
+        <key>Red Component</key>
+        <real>1</real>
+    </dict>
+</dict>
+</plist>"##;
+
+        let imported = import_iterm2_colors("Basic", contents.as_bytes()).unwrap();
+        let theme = &imported.family.themes[0];
+        assert_eq!(theme.appearance, theme::AppearanceContent::Dark);
+        assert_eq!(
+            theme.style.colors.terminal_ansi_red.as_deref(),
+            Some("#ff0000ff")
+        );
+        assert_eq!(imported.report.unmapped_colors, ["Bold Color"]);
+    }
+}
@@ -0,0 +1,346 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context as _, Result};
+use collections::IndexMap;
+use gpui::Rgba;
+use serde::Deserialize;
+
+use crate::base_colors::BaseColors;
+use crate::color::{format_color, parse_hex_color, with_alpha};
+use crate::vscode::{
+    VsCodeTokenColor, VsCodeTokenColorSettings, VsCodeTokenScope, convert_token_colors,
+};
+use crate::{ImportedTheme, MappingReport};
+
+#[derive(Debug, Deserialize)]
+struct TmTheme {
+    name: Option<String>,
+    author: Option<String>,
+    settings: Vec<TmThemeRule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TmThemeRule {
+    name: Option<String>,
+    scope: Option<String>,
+    settings: BTreeMap<String, plist::Value>,
+}
+
+/// Imports a TextMate theme, which is a property list.
+pub(crate) fn import_tm_theme(default_name: &str, contents: &[u8]) -> Result<ImportedTheme> {
+    let tm_theme: TmTheme =
+        plist::from_bytes(contents).context("failed to parse TextMate theme")?;
+
+    let mut report = MappingReport::default();
+    let mut globals = Vec::new();
+    let mut token_colors = Vec::new();
+    for rule in tm_theme.settings {
+        let setting = |key: &str| {
+            rule.settings
+                .get(key)
+                .and_then(|value| value.as_string())
+                .map(|value| value.to_string())
+        };
+        match rule.scope {
+            None => globals.extend(
+                rule.settings
+                    .iter()
+                    .map(|(key, value)| (key.clone(), value.as_string().and_then(parse_hex_color))),
+            ),
+            Some(scope) => token_colors.push(VsCodeTokenColor {
+                settings: VsCodeTokenColorSettings {
+                    foreground: setting("foreground").as_deref().and_then(parse_hex_color),
+                    background: setting("background").as_deref().and_then(parse_hex_color),
+                    font_style: setting("fontStyle"),
+                },
+                name: rule.name,
+                scope: Some(VsCodeTokenScope::One(scope)),
+            }),
+        }
+    }
+
+    let base_colors = base_colors_from_globals(globals, &mut report)?;
+    let syntax = convert_token_colors(&token_colors, &IndexMap::default(), &mut report);
+    let name = tm_theme.name.unwrap_or_else(|| default_name.to_string());
+    let theme = base_colors.into_theme(name, syntax);
+    Ok(ImportedTheme::new(theme, tm_theme.author, report))
+}
+
+#[derive(Debug, Deserialize)]
+struct SublimeColorScheme {
+    name: Option<String>,
+    author: Option<String>,
+    #[serde(default)]
+    variables: BTreeMap<String, String>,
+    #[serde(default)]
+    globals: BTreeMap<String, serde_json::Value>,
+    #[serde(default)]
+    rules: Vec<SublimeColorSchemeRule>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SublimeColorSchemeRule {
+    name: Option<String>,
+    scope: Option<String>,
+    foreground: Option<serde_json::Value>,
+    background: Option<String>,
+    font_style: Option<String>,
+}
+
+/// Imports a Sublime Text color scheme, which is JSON with comments.
+pub(crate) fn import_sublime_color_scheme(
+    default_name: &str,
+    contents: &[u8],
+) -> Result<ImportedTheme> {
+    let scheme: SublimeColorScheme = serde_json_lenient::from_slice(contents)
+        .context("failed to parse Sublime Text color scheme")?;
+
+    let mut report = MappingReport::default();
+    let resolve = |value: &str| resolve_sublime_color(value, &scheme.variables, 0);
+    let globals = scheme
+        .globals
+        .iter()
+        .map(|(key, value)| (key.clone(), value.as_str().and_then(resolve)))
+        .collect();
+    let token_colors = scheme
+        .rules
+        .into_iter()
+        .filter_map(|rule| {
+            Some(VsCodeTokenColor {
+                settings: VsCodeTokenColorSettings {
+                    // Gradients, which are arrays of colors, aren't supported.
+                    foreground: rule
+                        .foreground
+                        .as_ref()
+                        .and_then(|value| value.as_str())
+                        .and_then(resolve),
+                    background: rule.background.as_deref().and_then(resolve),
+                    font_style: rule.font_style,
+                },
+                name: rule.name,
+                scope: Some(VsCodeTokenScope::One(rule.scope?)),
+            })
+        })
+        .collect::<Vec<_>>();
+
+    let base_colors = base_colors_from_globals(globals, &mut report)?;
+    let syntax = convert_token_colors(&token_colors, &IndexMap::default(), &mut report);
+    let name = scheme.name.unwrap_or_else(|| default_name.to_string());
+    let theme = base_colors.into_theme(name, syntax);
+    Ok(ImportedTheme::new(theme, scheme.author, report))
+}
+
+/// Maps the global colors of a TextMate theme or Sublime Text color scheme, which use camelCase
+/// and snake_case keys respectively.
+fn base_colors_from_globals(
+    globals: Vec<(String, Option<String>)>,
+    report: &mut MappingReport,
+) -> Result<BaseColors> {
+    let mut background = None;
+    let mut foreground = None;
+    let mut base_colors = BaseColors::default();
+    for (key, color) in globals {
+        let Some(color) = color else {
+            report.add_unmapped_color(&key);
+            continue;
+        };
+        match key.as_str() {
+            "background" => background = Some(color),
+            "foreground" => foreground = Some(color),
+            "caret" => base_colors.cursor = Some(color),
+            "selection" => base_colors.selection = Some(color),
+            "lineHighlight" | "line_highlight" => base_colors.line_highlight = Some(color),
+            "gutterForeground" | "gutter_foreground" => base_colors.line_number = Some(color),
+            "invisibles" => base_colors.invisible = Some(color),
+            "findHighlight" | "find_highlight" => base_colors.search_match = Some(color),
+            _ => report.add_unmapped_color(&key),
+        }
+    }
+    base_colors.background = background.context("color scheme has no background color")?;
+    base_colors.foreground = foreground.context("color scheme has no foreground color")?;
+    Ok(base_colors)
+}
+
+/// Resolves a color written in Sublime Text's subset of CSS colors: hex colors, `rgb()` and
+/// `rgba()`, variables, and `color()` with an `alpha()` adjuster.
+fn resolve_sublime_color(
+    value: &str,
+    variables: &BTreeMap<String, String>,
+    depth: usize,
+) -> Option<String> {
+    // Guards against variables that refer to each other.
+    if depth > 8 {
+        return None;
+    }
+    let value = value.trim();
+
+    if let Some(name) = function_arguments(value, "var") {
+        let value = variables.get(name.trim())?;
+        return resolve_sublime_color(value, variables, depth + 1);
+    }
+
+    if let Some(arguments) = function_arguments(value, "color") {
+        let (color, adjusters) = match arguments.find(" alpha(").or(arguments.find(" a(")) {
+            Some(ix) => arguments.split_at(ix),
+            None => (arguments, ""),
+        };
+        let color = resolve_sublime_color(color, variables, depth + 1)?;
+        let adjusters = adjusters.trim();
+        let alpha =
+            function_arguments(adjusters, "alpha").or_else(|| function_arguments(adjusters, "a"));
+        return match alpha {
+            Some(alpha) => with_alpha(&color, alpha.trim().parse().ok()?),
+            None => Some(color),
+        };
+    }
+
+    if let Some(arguments) =
+        function_arguments(value, "rgb").or_else(|| function_arguments(value, "rgba"))
+    {
+        let components = arguments
+            .split(',')
+            .map(|component| component.trim().parse::<f32>().ok())
+            .collect::<Option<Vec<_>>>()?;
+        let (r, g, b, a) = match components.as_slice() {
+            [r, g, b] => (*r, *g, *b, 1.),
+            [r, g, b, a] => (*r, *g, *b, *a),
+            _ => return None,
+        };
+        return Some(format_color(Rgba {
+            r: r / 255.,
+            g: g / 255.,
+            b: b / 255.,
+            a,
+        }));
+    }
+
+    if value.starts_with('#') {
+        return parse_hex_color(value);
+    }
+
+    None
+}
+
+fn function_arguments<'a>(value: &'a str, function: &str) -> Option<&'a str> {
+    value
+        .strip_prefix(function)?
+        .strip_prefix('(')?
+        .strip_suffix(')')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_tm_theme() {
+        let contents = r##"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>name</key>
+    <string>Test</string>
+    <key>settings</key>
+    <array>
+        <dict>
+            <key>settings</key>
+            <dict>
+                <key>background</key>
+                <string>#272822</string>
+                <key>foreground</key>
+                <string>#F8F8F2</string>
+                <key>caret</key>
+                <string>#F8F8F0</string>
+                <key>bracketContentsForeground</key>
+                <string>#F8F8F2A5</string>
+            </dict>
+        </dict>
+        <dict>
+            <key>name</key>
+            <string>Comment</string>
+            <key>scope</key>
+            <string>comment</string>
+            <key>settings</key>
+            <dict>
+                <key>foreground</key>
+                <string>#75715E</string>
+                <key>fontStyle</key>
+                <string>italic</string>
+            </dict>
+        </dict>
+        <dict>
+            <key>scope</key>
+            <string>markup.changed.git_gutter</string>
+            <key>settings</key>
+            <dict>
+                <key>foreground</key>
+                <string>#E6DB74</string>
+            </dict>
+        </dict>
+    </array>
+</dict>
+</plist>"##;
+
+        let imported = import_tm_theme("fallback", contents.as_bytes()).unwrap();
+        let theme = &imported.family.themes[0];
+        assert_eq!(theme.name, "Test");
+        assert_eq!(theme.appearance, theme::AppearanceContent::Dark);
+        assert_eq!(
+            theme.style.colors.editor_background.as_deref(),
+            Some("#272822ff")
+        );
+        assert_eq!(theme.style.players[0].cursor.as_deref(), Some("#f8f8f0ff"));
+        let comment = &theme.style.syntax["comment"];
+        assert_eq!(comment.color.as_deref(), Some("#75715eff"));
+        assert_eq!(comment.font_style, Some(theme::FontStyleContent::Italic));
+        assert_eq!(
+            imported.report.unmapped_scopes,
+            ["markup.changed.git_gutter"]
+        );
+        assert_eq!(
+            imported.report.unmapped_colors,
+            ["bracketContentsForeground"]
+        );
+    }
+
+    #[test]
+    fn test_import_sublime_color_scheme() {
+        let contents = r##"{
+            // Comments are allowed.
+            "name": "Test",
+            "variables": {
+                "black": "#000000",
+                "green": "rgb(0, 128, 0)",
+                "accent": "var(green)",
+            },
+            "globals": {
+                "background": "#ffffff",
+                "foreground": "var(black)",
+                "selection": "color(var(accent) alpha(0.25))",
+            },
+            "rules": [
+                { "scope": "string", "foreground": "var(green)" },
+                { "scope": "keyword, storage", "foreground": "#0000ff", "font_style": "bold" },
+                { "scope": "meta.diff.header", "foreground": "#ff0000" },
+            ],
+        }"##;
+
+        let imported = import_sublime_color_scheme("fallback", contents.as_bytes()).unwrap();
+        let theme = &imported.family.themes[0];
+        assert_eq!(theme.appearance, theme::AppearanceContent::Light);
+        assert_eq!(theme.style.colors.text.as_deref(), Some("#000000ff"));
+        assert_eq!(
+            theme.style.players[0].selection.as_deref(),
+            Some("#00800040")
+        );
+        assert_eq!(
+            theme.style.syntax["string"].color.as_deref(),
+            Some("#008000ff")
+        );
+        assert_eq!(
+            theme.style.syntax["keyword"].font_weight,
+            Some(theme::FontWeightContent::BOLD)
+        );
+        assert_eq!(imported.report.unmapped_scopes, ["meta.diff.header"]);
+    }
+}
@@ -0,0 +1,208 @@
+//! Converts themes and color schemes from other editors and terminals into Zed themes.
+//!
+//! Besides VS Code themes, this supports TextMate (`.tmTheme`) and Sublime Text
+//! (`.sublime-color-scheme`) color schemes, base16 and base24 schemes, and the color schemes of
+//! iTerm2, Alacritty, and Ghostty.
+
+mod base16;
+mod base_colors;
+mod color;
+mod terminal;
+mod textmate;
+mod vscode;
+
+use std::path::Path;
+
+use anyhow::{Context as _, Result};
+use collections::IndexMap;
+use serde::Deserialize;
+use theme::{Appearance, AppearanceContent, ThemeContent, ThemeFamilyContent};
+
+use crate::vscode::{VsCodeTheme, VsCodeThemeConverter};
+
+/// The URL of the JSON schema for Zed theme families.
+pub const ZED_THEME_SCHEMA_URL: &str = "https://zed.dev/schema/themes/v0.2.0.json";
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeAppearanceJson {
+    Light,
+    Dark,
+}
+
+impl From<ThemeAppearanceJson> for AppearanceContent {
+    fn from(value: ThemeAppearanceJson) -> Self {
+        match value {
+            ThemeAppearanceJson::Light => Self::Light,
+            ThemeAppearanceJson::Dark => Self::Dark,
+        }
+    }
+}
+
+impl From<ThemeAppearanceJson> for Appearance {
+    fn from(value: ThemeAppearanceJson) -> Self {
+        match value {
+            ThemeAppearanceJson::Light => Self::Light,
+            ThemeAppearanceJson::Dark => Self::Dark,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ThemeMetadata {
+    pub name: String,
+    pub file_name: String,
+    pub appearance: ThemeAppearanceJson,
+}
+
+/// The formats that themes can be imported from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThemeFormat {
+    VsCode,
+    TextMate,
+    SublimeColorScheme,
+    /// base16 and base24 schemes, in YAML.
+    Base16,
+    ITerm2,
+    Alacritty,
+    Ghostty,
+}
+
+impl ThemeFormat {
+    /// Guesses the format of a theme from its file extension.
+    ///
+    /// Ghostty themes are files without an extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let Some(extension) = path.extension() else {
+            return Some(Self::Ghostty);
+        };
+        let extension = extension.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "json" => Some(Self::VsCode),
+            "tmtheme" => Some(Self::TextMate),
+            "sublime-color-scheme" => Some(Self::SublimeColorScheme),
+            "yaml" | "yml" => Some(Self::Base16),
+            "itermcolors" => Some(Self::ITerm2),
+            "toml" => Some(Self::Alacritty),
+            "conf" => Some(Self::Ghostty),
+            _ => None,
+        }
+    }
+}
+
+/// What couldn't be carried over when importing a theme.
+#[derive(Debug, Default, PartialEq)]
+pub struct MappingReport {
+    /// Scopes from the theme's rules that no Zed syntax token was mapped from.
+    pub unmapped_scopes: Vec<String>,
+    /// Global or terminal color keys that weren't mapped to any Zed theme color.
+    pub unmapped_colors: Vec<String>,
+    /// Zed syntax tokens that the theme has no color for.
+    pub missing_tokens: Vec<String>,
+}
+
+impl MappingReport {
+    pub fn is_empty(&self) -> bool {
+        self.unmapped_scopes.is_empty()
+            && self.unmapped_colors.is_empty()
+            && self.missing_tokens.is_empty()
+    }
+
+    fn add_unmapped_scope(&mut self, scope: &str) {
+        if !self
+            .unmapped_scopes
+            .iter()
+            .any(|existing| existing == scope)
+        {
+            self.unmapped_scopes.push(scope.to_string());
+        }
+    }
+
+    fn add_unmapped_color(&mut self, key: &str) {
+        if !self.unmapped_colors.iter().any(|existing| existing == key) {
+            self.unmapped_colors.push(key.to_string());
+        }
+    }
+}
+
+/// A theme converted from another format.
+#[derive(Debug)]
+pub struct ImportedTheme {
+    pub family: ThemeFamilyContent,
+    pub report: MappingReport,
+}
+
+impl ImportedTheme {
+    fn new(theme: ThemeContent, author: Option<String>, report: MappingReport) -> Self {
+        Self {
+            family: ThemeFamilyContent {
+                name: theme.name.clone(),
+                author: author.unwrap_or_default(),
+                themes: vec![theme],
+            },
+            report,
+        }
+    }
+
+    /// Serializes the theme family for the user themes directory.
+    pub fn to_json(&self) -> Result<String> {
+        let mut family = serde_json::to_value(&self.family)?;
+        family
+            .as_object_mut()
+            .context("theme family isn't an object")?
+            .insert(
+                "$schema".to_string(),
+                serde_json::Value::String(ZED_THEME_SCHEMA_URL.to_string()),
+            );
+        Ok(serde_json::to_string_pretty(&family)?)
+    }
+}
+
+/// Converts the contents of a theme file to a Zed theme.
+///
+/// `default_name` names the theme when the file doesn't.
+pub fn import_theme(
+    format: ThemeFormat,
+    default_name: &str,
+    contents: &[u8],
+) -> Result<ImportedTheme> {
+    match format {
+        ThemeFormat::VsCode => import_vscode_theme(default_name, contents),
+        ThemeFormat::TextMate => textmate::import_tm_theme(default_name, contents),
+        ThemeFormat::SublimeColorScheme => {
+            textmate::import_sublime_color_scheme(default_name, contents)
+        }
+        ThemeFormat::Base16 => base16::import_base16_scheme(default_name, contents),
+        ThemeFormat::ITerm2 => terminal::import_iterm2_colors(default_name, contents),
+        ThemeFormat::Alacritty => terminal::import_alacritty_colors(default_name, contents),
+        ThemeFormat::Ghostty => terminal::import_ghostty_colors(default_name, contents),
+    }
+}
+
+fn import_vscode_theme(default_name: &str, contents: &[u8]) -> Result<ImportedTheme> {
+    let vscode_theme: VsCodeTheme =
+        serde_json_lenient::from_slice(contents).context("failed to parse VS Code theme")?;
+    let is_light = vscode_theme
+        .colors
+        .editor
+        .background
+        .as_deref()
+        .is_some_and(color::is_light);
+    let author = vscode_theme.author.clone();
+    let theme_metadata = ThemeMetadata {
+        name: vscode_theme
+            .name
+            .clone()
+            .unwrap_or_else(|| default_name.to_string()),
+        appearance: if is_light {
+            ThemeAppearanceJson::Light
+        } else {
+            ThemeAppearanceJson::Dark
+        },
+        file_name: String::new(),
+    };
+
+    let converter = VsCodeThemeConverter::new(vscode_theme, theme_metadata, IndexMap::default());
+    let (theme, report) = converter.convert()?;
+    Ok(ImportedTheme::new(theme, author, report))
+}
@@ -6,8 +6,8 @@ use theme::{
     ThemeColorsContent, ThemeContent, ThemeStyleContent, WindowBackgroundContent,
 };
 
-use crate::ThemeMetadata;
-use crate::vscode::{VsCodeTheme, VsCodeTokenScope};
+use crate::vscode::{VsCodeTheme, VsCodeTokenColor, VsCodeTokenScope};
+use crate::{MappingReport, ThemeMetadata};
 
 use super::ZedSyntaxToken;
 
@@ -45,14 +45,19 @@ impl VsCodeThemeConverter {
         }
     }
 
-    pub fn convert(self) -> Result<ThemeContent> {
+    pub fn convert(self) -> Result<(ThemeContent, MappingReport)> {
         let appearance = self.theme_metadata.appearance.into();
 
+        let mut report = MappingReport::default();
         let status_colors = self.convert_status_colors()?;
         let theme_colors = self.convert_theme_colors()?;
-        let syntax_theme = self.convert_syntax_theme()?;
+        let syntax_theme = convert_token_colors(
+            &self.theme.token_colors,
+            &self.syntax_overrides,
+            &mut report,
+        );
 
-        Ok(ThemeContent {
+        let theme = ThemeContent {
             name: self.theme_metadata.name,
             appearance,
             style: ThemeStyleContent {
@@ -63,7 +68,9 @@ impl VsCodeThemeConverter {
                 players: Vec::new(),
                 syntax: syntax_theme,
             },
-        })
+        };
+
+        Ok((theme, report))
     }
 
     fn convert_status_colors(&self) -> Result<StatusColorsContent> {
@@ -211,67 +218,99 @@ impl VsCodeThemeConverter {
             ..Default::default()
         })
     }
+}
 
-    fn convert_syntax_theme(&self) -> Result<IndexMap<String, HighlightStyleContent>> {
-        let mut highlight_styles = IndexMap::default();
-
-        for syntax_token in ZedSyntaxToken::iter() {
-            let override_match = self
-                .syntax_overrides
-                .get(&syntax_token.to_string())
-                .and_then(|scope| {
-                    self.theme.token_colors.iter().find(|token_color| {
-                        token_color.scope == Some(VsCodeTokenScope::Many(scope.clone()))
-                    })
-                });
+/// Maps the scoped rules of a VS Code or TextMate theme to Zed syntax tokens, recording the
+/// scopes and tokens that couldn't be matched in `report`.
+pub(crate) fn convert_token_colors(
+    token_colors: &[VsCodeTokenColor],
+    syntax_overrides: &IndexMap<String, Vec<String>>,
+    report: &mut MappingReport,
+) -> IndexMap<String, HighlightStyleContent> {
+    let mut highlight_styles = IndexMap::default();
+    let mut matched_token_colors = Vec::new();
 
-            let best_match = override_match
-                .or_else(|| syntax_token.find_best_token_color_match(&self.theme.token_colors))
-                .or_else(|| {
-                    syntax_token.fallbacks().iter().find_map(|fallback| {
-                        fallback.find_best_token_color_match(&self.theme.token_colors)
-                    })
-                });
+    for syntax_token in ZedSyntaxToken::iter() {
+        let override_match = syntax_overrides
+            .get(&syntax_token.to_string())
+            .and_then(|scope| {
+                token_colors.iter().find(|token_color| {
+                    token_color.scope == Some(VsCodeTokenScope::Many(scope.clone()))
+                })
+            });
 
-            let Some(token_color) = best_match else {
-                log::warn!("No matching token color found for '{syntax_token}'");
-                continue;
-            };
+        let best_match = override_match
+            .or_else(|| syntax_token.find_best_token_color_match(token_colors))
+            .or_else(|| {
+                syntax_token
+                    .fallbacks()
+                    .iter()
+                    .find_map(|fallback| fallback.find_best_token_color_match(token_colors))
+            });
 
-            log::info!(
-                "Matched '{syntax_token}' to '{}'",
-                token_color
-                    .name
-                    .clone()
-                    .or_else(|| token_color
-                        .scope
-                        .as_ref()
-                        .map(|scope| format!("{:?}", scope)))
-                    .unwrap_or_else(|| "no identifier".to_string())
-            );
+        let Some(token_color) = best_match else {
+            log::warn!("No matching token color found for '{syntax_token}'");
+            // Tokens without any VS Code scopes can't be matched by any theme.
+            if !syntax_token.to_vscode().is_empty() {
+                report.missing_tokens.push(syntax_token.to_string());
+            }
+            continue;
+        };
 
-            let highlight_style = HighlightStyleContent {
-                color: token_color.settings.foreground.clone(),
-                background_color: token_color.settings.background.clone(),
-                font_style: token_color
-                    .settings
-                    .font_style
-                    .as_ref()
-                    .and_then(|style| try_parse_font_style(style)),
-                font_weight: token_color
-                    .settings
-                    .font_style
+        log::info!(
+            "Matched '{syntax_token}' to '{}'",
+            token_color
+                .name
+                .clone()
+                .or_else(|| token_color
+                    .scope
                     .as_ref()
-                    .and_then(|style| try_parse_font_weight(style)),
-            };
+                    .map(|scope| format!("{:?}", scope)))
+                .unwrap_or_else(|| "no identifier".to_string())
+        );
+        matched_token_colors.push(token_color);
 
-            if highlight_style.is_empty() {
-                continue;
-            }
+        let highlight_style = HighlightStyleContent {
+            color: token_color.settings.foreground.clone(),
+            background_color: token_color.settings.background.clone(),
+            font_style: token_color
+                .settings
+                .font_style
+                .as_ref()
+                .and_then(|style| try_parse_font_style(style)),
+            font_weight: token_color
+                .settings
+                .font_style
+                .as_ref()
+                .and_then(|style| try_parse_font_weight(style)),
+        };
 
-            highlight_styles.insert(syntax_token.to_string(), highlight_style);
+        if highlight_style.is_empty() {
+            continue;
         }
 
-        Ok(highlight_styles)
+        highlight_styles.insert(syntax_token.to_string(), highlight_style);
     }
+
+    for token_color in token_colors {
+        if matched_token_colors
+            .iter()
+            .any(|matched| std::ptr::eq(*matched, token_color))
+        {
+            continue;
+        }
+        let scopes = match &token_color.scope {
+            Some(VsCodeTokenScope::One(scope)) => vec![scope],
+            Some(VsCodeTokenScope::Many(scopes)) => scopes.iter().collect(),
+            None => continue,
+        };
+        for scope in scopes.iter().flat_map(|scope| scope.split(',')) {
+            let scope = scope.trim();
+            if !scope.is_empty() {
+                report.add_unmapped_scope(scope);
+            }
+        }
+    }
+
+    highlight_styles
 }
@@ -1,10 +1,11 @@
 mod icon_theme_selector;
 
+use anyhow::Context as _;
 use fs::Fs;
 use fuzzy::{StringMatch, StringMatchCandidate, match_strings};
 use gpui::{
-    App, Context, DismissEvent, Entity, EventEmitter, Focusable, Render, UpdateGlobal, WeakEntity,
-    Window, actions,
+    App, Context, DismissEvent, Entity, EventEmitter, Focusable, PathPromptOptions, PromptLevel,
+    Render, UpdateGlobal, WeakEntity, Window, actions,
 };
 use picker::{Picker, PickerDelegate};
 use settings::{Settings, SettingsStore, update_settings_file};
@@ -13,9 +14,15 @@ use theme::{
     Appearance, SystemAppearance, Theme, ThemeAppearanceMode, ThemeMeta, ThemeName, ThemeRegistry,
     ThemeSelection, ThemeSettings,
 };
+use theme_importer::{MappingReport, ThemeFormat};
 use ui::{ListItem, ListItemSpacing, prelude::*, v_flex};
 use util::ResultExt;
-use workspace::{ModalView, Workspace, ui::HighlightedLabel, with_active_or_new_workspace};
+use workspace::{
+    ModalView, Toast, Workspace,
+    notifications::{DetachAndPromptErr, NotificationId},
+    ui::HighlightedLabel,
+    with_active_or_new_workspace,
+};
 use zed_actions::{ExtensionCategoryFilter, Extensions};
 
 use crate::icon_theme_selector::{IconThemeSelector, IconThemeSelectorDelegate};
@@ -24,7 +31,10 @@ actions!(
     theme_selector,
     [
         /// Reloads all themes from disk.
-        Reload
+        Reload,
+        /// Imports a theme or color scheme from another editor or terminal into the user themes
+        /// directory.
+        ImportTheme
     ]
 );
 
@@ -41,6 +51,11 @@ pub fn init(cx: &mut App) {
             toggle_icon_theme_selector(workspace, &action, window, cx);
         });
     });
+    cx.on_action(|_: &ImportTheme, cx| {
+        with_active_or_new_workspace(cx, |workspace, window, cx| {
+            import_theme(workspace, window, cx);
+        });
+    });
 }
 
 fn toggle_theme_selector(
@@ -61,6 +76,108 @@ fn toggle_theme_selector(
     });
 }
 
+fn import_theme(workspace: &mut Workspace, window: &mut Window, cx: &mut Context<Workspace>) {
+    let fs = workspace.app_state().fs.clone();
+    let path_prompt = cx.prompt_for_paths(PathPromptOptions {
+        files: true,
+        directories: false,
+        multiple: false,
+        prompt: Some("Import".into()),
+    });
+
+    cx.spawn_in(window, async move |workspace, cx| {
+        let Some(path) = path_prompt
+            .await??
+            .and_then(|paths| paths.into_iter().next())
+        else {
+            return Ok(());
+        };
+        let format = ThemeFormat::from_path(&path)
+            .with_context(|| format!("Unrecognized theme format: {}", path.display()))?;
+        let file_stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .context("Theme file has no name")?;
+        let contents = fs.load_bytes(&path).await?;
+        let imported = theme_importer::import_theme(format, &file_stem, &contents)?;
+
+        let themes_dir = paths::themes_dir();
+        fs.create_dir(themes_dir).await?;
+        let theme_path = themes_dir.join(format!("{file_stem}.json"));
+        if fs.is_file(&theme_path).await {
+            let answer = workspace.update_in(cx, |_, window, cx| {
+                window.prompt(
+                    PromptLevel::Warning,
+                    &format!("A theme file named {file_stem}.json already exists."),
+                    Some("Do you want to replace it with the imported theme?"),
+                    &["Replace", "Cancel"],
+                    cx,
+                )
+            })?;
+            if answer.await != Ok(0) {
+                return Ok(());
+            }
+        }
+        fs.atomic_write(theme_path.clone(), imported.to_json()?)
+            .await?;
+        let registry = cx.update(|_, cx| ThemeRegistry::global(cx))?;
+        registry.load_user_theme(&theme_path, fs).await?;
+
+        let theme_names = imported
+            .family
+            .themes
+            .iter()
+            .map(|theme| theme.name.clone())
+            .collect::<Vec<_>>();
+        let message = mapping_report_message(&imported.family.name, &imported.report);
+        workspace.update_in(cx, |workspace, window, cx| {
+            if let Some(message) = message {
+                workspace.show_toast(
+                    Toast::new(NotificationId::unique::<ImportTheme>(), message),
+                    cx,
+                );
+            }
+            toggle_theme_selector(
+                workspace,
+                &zed_actions::theme_selector::Toggle {
+                    themes_filter: Some(theme_names),
+                },
+                window,
+                cx,
+            );
+        })
+    })
+    .detach_and_prompt_err("Failed to import theme", window, cx, |_, _, _| None);
+}
+
+/// Summarizes what couldn't be carried over from an imported theme, logging the details.
+fn mapping_report_message(theme_name: &str, report: &MappingReport) -> Option<String> {
+    if report.unmapped_scopes.is_empty() && report.unmapped_colors.is_empty() {
+        return None;
+    }
+    log::info!(
+        "imported theme {theme_name:?} with unmapped scopes {:?} and unmapped colors {:?}",
+        report.unmapped_scopes,
+        report.unmapped_colors
+    );
+
+    let mut unmapped = Vec::new();
+    match report.unmapped_scopes.len() {
+        0 => {}
+        1 => unmapped.push("1 scope".to_string()),
+        count => unmapped.push(format!("{count} scopes")),
+    }
+    match report.unmapped_colors.len() {
+        0 => {}
+        1 => unmapped.push("1 color".to_string()),
+        count => unmapped.push(format!("{count} colors")),
+    }
+    Some(format!(
+        "Imported {theme_name}, but {} couldn't be mapped to Zed's theme",
+        unmapped.join(" and ")
+    ))
+}
+
 fn toggle_icon_theme_selector(
     workspace: &mut Workspace,
     toggle: &zed_actions::icon_theme_selector::Toggle,
@@ -12,6 +12,8 @@ pub struct ShellBuilder {
     interactive: bool,
     /// Whether to redirect stdin to /dev/null for the spawned command as a subshell.
     redirect_stdin: bool,
+    /// Whether to surround the command with shell integration marks.
+    report_command_marks: bool,
     kind: ShellKind,
 }
 
@@ -31,6 +33,7 @@ impl ShellBuilder {
             interactive: true,
             kind,
             redirect_stdin: false,
+            report_command_marks: false,
         }
     }
     pub fn non_interactive(mut self) -> Self {
@@ -73,6 +76,14 @@ impl ShellBuilder {
         self
     }
 
+    /// Prints the OSC 133 marks for a command's output starting and finishing around the
+    /// command, so that a terminal can tell its output apart from what the shell prints at
+    /// startup, and learn its exit status.
+    pub fn report_command_marks(mut self) -> Self {
+        self.report_command_marks = true;
+        self
+    }
+
     /// Returns the program and arguments to run this task in a shell.
     pub fn build(
         mut self,
@@ -122,6 +133,31 @@ impl ShellBuilder {
                     }
                 }
             }
+            if self.report_command_marks {
+                // The command goes on its own line, so that a trailing comment doesn't
+                // swallow the rest.
+                match self.kind {
+                    ShellKind::Posix => {
+                        combined_command = format!(
+                            "printf '\\033]133;C\\007'\n{combined_command}\n__zed_status=$?\nprintf '\\033]133;D;%s\\007' \"$__zed_status\"\nexit \"$__zed_status\""
+                        );
+                    }
+                    ShellKind::Fish => {
+                        combined_command = format!(
+                            "printf '\\e]133;C\\a'\n{combined_command}\nset __zed_status $status\nprintf '\\e]133;D;%s\\a' $__zed_status\nexit $__zed_status"
+                        );
+                    }
+                    ShellKind::Nushell
+                    | ShellKind::Csh
+                    | ShellKind::Tcsh
+                    | ShellKind::Rc
+                    | ShellKind::Xonsh
+                    | ShellKind::Elvish
+                    | ShellKind::PowerShell
+                    | ShellKind::Pwsh
+                    | ShellKind::Cmd => {}
+                }
+            }
 
             self.args
                 .extend(self.kind.args_for_shell(self.interactive, combined_command));
@@ -289,6 +325,33 @@ mod test {
         assert_eq!(args, vec!["-i", "-c", "begin; echo test; end </dev/null"]);
     }
 
+    #[test]
+    fn report_command_marks() {
+        let shell = Shell::Program("/bin/sh".to_owned());
+        let shell_builder = ShellBuilder::new(&shell, false);
+
+        let (program, args) = shell_builder
+            .redirect_stdin_to_dev_null()
+            .report_command_marks()
+            .build(Some("false".into()), &[]);
+
+        assert_eq!(program, "/bin/sh");
+        assert_eq!(
+            args,
+            vec![
+                "-i",
+                "-c",
+                "printf '\\033]133;C\\007'\n(false) </dev/null\n__zed_status=$?\nprintf '\\033]133;D;%s\\007' \"$__zed_status\"\nexit \"$__zed_status\""
+            ]
+        );
+
+        let shell = Shell::Program("pwsh".to_owned());
+        let (_, args) = ShellBuilder::new(&shell, false)
+            .report_command_marks()
+            .build(Some("echo".into()), &[]);
+        assert_eq!(args.last().unwrap(), "echo");
+    }
+
     #[test]
     fn does_not_quote_sole_command_only() {
         let shell = Shell::Program("fish".to_owned());
@@ -0,0 +1,676 @@
+use std::path::PathBuf;
+
+use anyhow::{Context as _, Result, anyhow};
+use collections::HashSet;
+use gpui::{App, Context, Entity, Task, Window};
+use project::Project;
+use util::ResultExt as _;
+
+use crate::{
+    Event, Member, Pane, PaneAxis, PaneGroup, Workspace, WorkspaceDb,
+    dock::{Dock, DockPosition},
+    item::ItemHandle,
+    persistence::model::{
+        LayoutDockSizes, SerializedLayout, SerializedLayoutMember, SerializedLayoutPane,
+    },
+};
+
+/// A named layout saved for a workspace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayoutEntry {
+    pub name: String,
+    /// Whether the layout is applied whenever the project is opened.
+    pub is_default: bool,
+}
+
+impl Workspace {
+    /// Captures the current arrangement of the center panes and the docks.
+    pub fn capture_layout(&self, window: &Window, cx: &App) -> SerializedLayout {
+        let dock_size = |dock: &Entity<Dock>| {
+            let dock = dock.read(cx);
+            dock.stored_panel_size_state(dock.active_panel()?.as_ref())
+        };
+
+        SerializedLayout {
+            center: self.capture_layout_member(&self.center.root, cx),
+            docks: self.capture_dock_state(window, cx),
+            dock_sizes: LayoutDockSizes {
+                left: dock_size(&self.left_dock),
+                right: dock_size(&self.right_dock),
+                bottom: dock_size(&self.bottom_dock),
+            },
+        }
+    }
+
+    fn capture_layout_member(&self, member: &Member, cx: &App) -> SerializedLayoutMember {
+        match member {
+            Member::Axis(PaneAxis {
+                axis,
+                members,
+                flexes,
+                ..
+            }) => SerializedLayoutMember::Axis {
+                axis: *axis,
+                flexes: flexes.lock().clone(),
+                members: members
+                    .iter()
+                    .map(|member| self.capture_layout_member(member, cx))
+                    .collect(),
+            },
+            Member::Pane(pane_handle) => {
+                let pane = pane_handle.read(cx);
+                let mut layout_pane = SerializedLayoutPane {
+                    active: pane_handle == &self.active_pane,
+                    zoomed: pane.is_zoomed(),
+                    ..Default::default()
+                };
+                for (ix, item) in pane.items().enumerate() {
+                    let Some(abs_path) = item_abs_path(item.as_ref(), &self.project, cx) else {
+                        continue;
+                    };
+                    if ix == pane.active_item_index() {
+                        layout_pane.active_item = Some(layout_pane.items.len());
+                    }
+                    if ix < pane.pinned_count() {
+                        layout_pane.pinned_count += 1;
+                    }
+                    layout_pane.items.push(abs_path);
+                }
+                SerializedLayoutMember::Pane(layout_pane)
+            }
+        }
+    }
+
+    /// Rearranges the center panes and the docks to match a layout, opening the files it refers
+    /// to.
+    ///
+    /// Open items that aren't part of the layout are closed, except for those with unsaved
+    /// changes and those that aren't backed by a file, which are moved to the layout's active
+    /// pane.
+    pub fn apply_layout(
+        &mut self,
+        layout: SerializedLayout,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let mut open_items = Vec::new();
+        for pane in self.center.panes() {
+            for item in pane.read(cx).items() {
+                let abs_path = item_abs_path(item.as_ref(), &self.project, cx);
+                open_items.push((pane.clone(), item.boxed_clone(), abs_path));
+            }
+        }
+
+        let mut layout_panes = Vec::new();
+        let root = self.build_layout_member(layout.center, &mut layout_panes, window, cx);
+        let active_pane = layout_panes
+            .iter()
+            .find(|(_, layout_pane)| layout_pane.active)
+            .or(layout_panes.first())
+            .map(|(pane, _)| pane.clone())
+            .expect("layouts always have at least one pane");
+
+        // Reuse the items that are already open rather than reopening them.
+        let mut paths_to_open = Vec::new();
+        for (pane, layout_pane) in &layout_panes {
+            for path in &layout_pane.items {
+                let open_item = open_items
+                    .iter()
+                    .position(|(_, _, abs_path)| abs_path.as_ref() == Some(path))
+                    .map(|ix| open_items.remove(ix));
+                if let Some((old_pane, item, _)) = open_item {
+                    move_layout_item(&old_pane, pane, item, window, cx);
+                } else {
+                    paths_to_open.push((pane.downgrade(), path.clone()));
+                }
+            }
+        }
+        for (old_pane, item, abs_path) in open_items {
+            if abs_path.is_none() || item.is_dirty(cx) {
+                move_layout_item(&old_pane, &active_pane, item, window, cx);
+            } else {
+                old_pane.update(cx, |pane, cx| {
+                    pane.remove_item(item.item_id(), false, false, window, cx)
+                });
+            }
+        }
+
+        self.remove_panes(self.center.root.clone(), window, cx);
+        self.center = PaneGroup::with_root(root);
+        self.center.set_is_center(true);
+        self.center.mark_positions(cx);
+
+        for dock in self.all_docks() {
+            if let Some(panel) = dock.read(cx).active_panel().cloned()
+                && panel.is_zoomed(window, cx)
+            {
+                panel.set_zoomed(false, window, cx);
+            }
+        }
+        self.zoomed = None;
+        self.zoomed_position = None;
+        for (pane, layout_pane) in &layout_panes {
+            let zoomed = layout_pane.zoomed && pane == &active_pane;
+            pane.update(cx, |pane, cx| pane.set_zoomed(zoomed, cx));
+        }
+        self.set_active_pane(&active_pane, window, cx);
+        window.focus(&active_pane.focus_handle(cx), cx);
+
+        let zoomed_dock = [
+            (DockPosition::Left, layout.docks.left.zoom),
+            (DockPosition::Bottom, layout.docks.bottom.zoom),
+            (DockPosition::Right, layout.docks.right.zoom),
+        ]
+        .into_iter()
+        .find_map(|(position, zoom)| zoom.then_some(position));
+        self.set_dock_structure(layout.docks, window, cx);
+        for (dock, size_state) in [
+            (&self.left_dock, layout.dock_sizes.left),
+            (&self.bottom_dock, layout.dock_sizes.bottom),
+            (&self.right_dock, layout.dock_sizes.right),
+        ] {
+            if let Some(size_state) = size_state {
+                dock.update(cx, |dock, cx| {
+                    dock.resize_active_panel(
+                        size_state.size,
+                        size_state.flexible_size_ratio,
+                        window,
+                        cx,
+                    )
+                });
+            }
+        }
+        if let Some(position) = zoomed_dock
+            && let Some(panel) = self.dock_at_position(position).read(cx).active_panel()
+        {
+            self.zoomed = Some(panel.to_any().downgrade());
+            self.zoomed_position = Some(position);
+        }
+        cx.emit(Event::ZoomChanged);
+        cx.notify();
+
+        let open_tasks = paths_to_open
+            .into_iter()
+            .filter_map(|(pane, path)| {
+                let project_path = self.project.read(cx).find_project_path(&path, cx)?;
+                Some(self.open_path_preview(
+                    project_path,
+                    Some(pane),
+                    false,
+                    false,
+                    false,
+                    window,
+                    cx,
+                ))
+            })
+            .collect::<Vec<_>>();
+        cx.spawn_in(window, async move |this, cx| {
+            for result in futures::future::join_all(open_tasks).await {
+                result.log_err();
+            }
+
+            this.update_in(cx, |this, window, cx| {
+                for (pane, layout_pane) in layout_panes {
+                    arrange_layout_pane(&pane, &layout_pane, &this.project, window, cx);
+                }
+                this.serialize_workspace(window, cx);
+            })
+        })
+    }
+
+    fn build_layout_member(
+        &mut self,
+        member: SerializedLayoutMember,
+        layout_panes: &mut Vec<(Entity<Pane>, SerializedLayoutPane)>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Member {
+        match member {
+            SerializedLayoutMember::Axis {
+                axis,
+                flexes,
+                members,
+            } => {
+                let mut members = members
+                    .into_iter()
+                    .map(|member| self.build_layout_member(member, layout_panes, window, cx))
+                    .collect::<Vec<_>>();
+                match members.len() {
+                    0 => self.build_layout_member(
+                        SerializedLayoutMember::Pane(SerializedLayoutPane::default()),
+                        layout_panes,
+                        window,
+                        cx,
+                    ),
+                    1 => members.remove(0),
+                    _ => Member::Axis(PaneAxis::load(axis, members, Some(flexes))),
+                }
+            }
+            SerializedLayoutMember::Pane(layout_pane) => {
+                let pane = self.add_pane(window, cx);
+                layout_panes.push((pane.clone(), layout_pane));
+                Member::Pane(pane)
+            }
+        }
+    }
+
+    /// Returns the layouts saved for this workspace, sorted by name.
+    pub fn layouts(&self, cx: &App) -> Result<Vec<LayoutEntry>> {
+        let workspace_id = self
+            .database_id()
+            .context("layouts can't be saved for this workspace")?;
+        let layouts = WorkspaceDb::global(cx)
+            .layout_names(workspace_id)?
+            .into_iter()
+            .map(|(name, is_default)| LayoutEntry { name, is_default })
+            .collect();
+        Ok(layouts)
+    }
+
+    /// Saves the current layout under the given name, replacing any layout with the same name.
+    pub fn save_layout(&self, name: String, window: &Window, cx: &App) -> Task<Result<()>> {
+        let Some(workspace_id) = self.database_id() else {
+            return Task::ready(Err(anyhow!("layouts can't be saved for this workspace")));
+        };
+        let layout = self.capture_layout(window, cx);
+        let db = WorkspaceDb::global(cx);
+        cx.background_spawn(async move { db.save_layout(workspace_id, name, layout).await })
+    }
+
+    pub fn switch_to_layout(
+        &mut self,
+        name: &str,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Task<Result<()>> {
+        let layout = self
+            .database_id()
+            .context("layouts can't be saved for this workspace")
+            .and_then(|workspace_id| WorkspaceDb::global(cx).layout(workspace_id, name))
+            .and_then(|layout| layout.with_context(|| format!("no layout named {name:?}")));
+        match layout {
+            Ok(layout) => self.apply_layout(layout, window, cx),
+            Err(error) => Task::ready(Err(error)),
+        }
+    }
+
+    pub fn delete_layout(&self, name: String, cx: &App) -> Task<Result<()>> {
+        let Some(workspace_id) = self.database_id() else {
+            return Task::ready(Err(anyhow!("layouts can't be saved for this workspace")));
+        };
+        let db = WorkspaceDb::global(cx);
+        cx.background_spawn(async move { db.delete_layout(workspace_id, name).await })
+    }
+
+    /// Makes the named layout the one applied whenever the project is opened, in place of the
+    /// panes and docks from the last session. Passing `None` clears the default layout.
+    pub fn set_default_layout(&self, name: Option<String>, cx: &App) -> Task<Result<()>> {
+        let Some(workspace_id) = self.database_id() else {
+            return Task::ready(Err(anyhow!("layouts can't be saved for this workspace")));
+        };
+        let db = WorkspaceDb::global(cx);
+        cx.background_spawn(async move { db.set_default_layout(workspace_id, name).await })
+    }
+}
+
+fn item_abs_path(item: &dyn ItemHandle, project: &Entity<Project>, cx: &App) -> Option<PathBuf> {
+    let project_path = item.project_path(cx)?;
+    project.read(cx).absolute_path(&project_path, cx)
+}
+
+fn move_layout_item(
+    source: &Entity<Pane>,
+    destination: &Entity<Pane>,
+    item: Box<dyn ItemHandle>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    if source != destination {
+        source.update(cx, |pane, cx| {
+            pane.remove_item(item.item_id(), false, false, window, cx)
+        });
+    }
+    destination.update(cx, |pane, cx| {
+        pane.add_item(item, false, false, None, window, cx)
+    });
+}
+
+/// Orders a pane's items as the layout lists them, and restores its active and pinned items.
+/// Items that aren't part of the layout are kept after them.
+fn arrange_layout_pane(
+    pane: &Entity<Pane>,
+    layout_pane: &SerializedLayoutPane,
+    project: &Entity<Project>,
+    window: &mut Window,
+    cx: &mut App,
+) {
+    pane.update(cx, |pane, cx| {
+        let mut arranged_items = HashSet::default();
+        let mut active_item_index = None;
+        for (layout_ix, path) in layout_pane.items.iter().enumerate() {
+            let item = pane
+                .items()
+                .find(|item| {
+                    !arranged_items.contains(&item.item_id())
+                        && item_abs_path(item.as_ref(), project, cx).as_deref() == Some(path)
+                })
+                .cloned();
+            let Some(item) = item else {
+                continue;
+            };
+            let ix = arranged_items.len();
+            arranged_items.insert(item.item_id());
+            pane.add_item(item, false, false, Some(ix), window, cx);
+            if layout_pane.active_item == Some(layout_ix) {
+                active_item_index = Some(ix);
+            }
+        }
+        if let Some(ix) = active_item_index {
+            pane.activate_item(ix, false, false, window, cx);
+        }
+        pane.set_pinned_count(layout_pane.pinned_count.min(arranged_items.len()));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use fs::FakeFs;
+    use gpui::{Axis, EntityId, TestAppContext, VisualTestContext, px};
+    use project::{ProjectEntryId, ProjectPath, WorktreeId};
+    use serde_json::json;
+    use util::{path, rel_path::rel_path};
+
+    use super::*;
+    use crate::{
+        dock::{Panel as _, PanelSizeState, test::TestPanel},
+        item::test::{TestItem, TestProjectItem},
+        tests::init_test,
+    };
+
+    async fn init_workspace(
+        cx: &mut TestAppContext,
+    ) -> (Entity<Workspace>, WorktreeId, &mut VisualTestContext) {
+        init_test(cx);
+
+        let fs = FakeFs::new(cx.executor());
+        fs.insert_tree(
+            path!("/root"),
+            json!({ "a.txt": "", "b.txt": "", "c.txt": "" }),
+        )
+        .await;
+        let project = Project::test(fs, [path!("/root").as_ref()], cx).await;
+        let worktree_id = project.update(cx, |project, cx| {
+            project.worktrees(cx).next().unwrap().read(cx).id()
+        });
+        let (workspace, cx) =
+            cx.add_window_view(|window, cx| Workspace::test_new(project.clone(), window, cx));
+        (workspace, worktree_id, cx)
+    }
+
+    fn file_item(
+        id: u64,
+        path: &str,
+        worktree_id: WorktreeId,
+        cx: &mut VisualTestContext,
+    ) -> Entity<TestItem> {
+        let project_item = cx.new(|_| TestProjectItem {
+            entry_id: Some(ProjectEntryId::from_proto(id)),
+            project_path: Some(ProjectPath {
+                worktree_id,
+                path: rel_path(path).into(),
+            }),
+            is_dirty: false,
+        });
+        cx.new(|cx| TestItem::new(cx).with_project_items(&[project_item]))
+    }
+
+    fn add_items(
+        workspace: &Entity<Workspace>,
+        items: &[&Entity<TestItem>],
+        cx: &mut VisualTestContext,
+    ) {
+        workspace.update_in(cx, |workspace, window, cx| {
+            for item in items {
+                workspace.add_item_to_active_pane(
+                    Box::new((*item).clone()),
+                    None,
+                    true,
+                    window,
+                    cx,
+                );
+            }
+        });
+    }
+
+    fn pane_item_ids(pane: &Entity<Pane>, cx: &VisualTestContext) -> Vec<EntityId> {
+        pane.read_with(cx, |pane, _| {
+            pane.items().map(|item| item.item_id()).collect()
+        })
+    }
+
+    fn layout_pane(
+        items: &[&str],
+        active_item: Option<usize>,
+        active: bool,
+    ) -> SerializedLayoutPane {
+        SerializedLayoutPane {
+            items: items
+                .iter()
+                .map(|name| PathBuf::from(path!("/root")).join(name))
+                .collect(),
+            active_item,
+            pinned_count: 0,
+            active,
+            zoomed: false,
+        }
+    }
+
+    #[gpui::test]
+    async fn test_apply_layout_restores_splits(cx: &mut TestAppContext) {
+        let (workspace, worktree_id, cx) = init_workspace(cx).await;
+        let item_a = file_item(1, "a.txt", worktree_id, cx);
+        let item_b = file_item(2, "b.txt", worktree_id, cx);
+        let item_c = file_item(3, "c.txt", worktree_id, cx);
+        add_items(&workspace, &[&item_a, &item_b, &item_c], cx);
+
+        let center = SerializedLayoutMember::Axis {
+            axis: Axis::Horizontal,
+            flexes: vec![1.5, 0.5],
+            members: vec![
+                SerializedLayoutMember::Pane(layout_pane(&["b.txt", "a.txt"], Some(1), false)),
+                SerializedLayoutMember::Pane(layout_pane(&["c.txt"], Some(0), true)),
+            ],
+        };
+        let layout = workspace.update_in(cx, |workspace, window, cx| SerializedLayout {
+            center: center.clone(),
+            ..workspace.capture_layout(window, cx)
+        });
+        workspace
+            .update_in(cx, |workspace, window, cx| {
+                workspace.apply_layout(layout, window, cx)
+            })
+            .await
+            .unwrap();
+
+        let panes = workspace.read_with(cx, |workspace, _| workspace.panes().to_vec());
+        assert_eq!(panes.len(), 2);
+        assert_eq!(
+            pane_item_ids(&panes[0], cx),
+            [item_b.entity_id(), item_a.entity_id()]
+        );
+        assert_eq!(pane_item_ids(&panes[1], cx), [item_c.entity_id()]);
+        workspace.read_with(cx, |workspace, cx| {
+            assert_eq!(workspace.active_pane(), &panes[1]);
+            assert_eq!(panes[0].read(cx).active_item_index(), 1);
+        });
+
+        // Capturing the restored layout yields the layout that was applied.
+        let captured = workspace.update_in(cx, |workspace, window, cx| {
+            workspace.capture_layout(window, cx)
+        });
+        assert_eq!(captured.center, center);
+    }
+
+    #[gpui::test]
+    async fn test_apply_layout_keeps_dirty_and_non_file_items(cx: &mut TestAppContext) {
+        let (workspace, worktree_id, cx) = init_workspace(cx).await;
+        let item_a = file_item(1, "a.txt", worktree_id, cx);
+        let item_b = file_item(2, "b.txt", worktree_id, cx);
+        item_b.update(cx, |item, _| item.is_dirty = true);
+        let item_c = file_item(3, "c.txt", worktree_id, cx);
+        let untitled_item = cx.new(TestItem::new);
+        add_items(&workspace, &[&item_a, &item_b, &item_c, &untitled_item], cx);
+
+        let layout = workspace.update_in(cx, |workspace, window, cx| SerializedLayout {
+            center: SerializedLayoutMember::Axis {
+                axis: Axis::Vertical,
+                flexes: vec![1., 1.],
+                members: vec![
+                    SerializedLayoutMember::Pane(layout_pane(&["a.txt"], Some(0), false)),
+                    SerializedLayoutMember::Pane(layout_pane(&[], None, true)),
+                ],
+            },
+            ..workspace.capture_layout(window, cx)
+        });
+        workspace
+            .update_in(cx, |workspace, window, cx| {
+                workspace.apply_layout(layout, window, cx)
+            })
+            .await
+            .unwrap();
+
+        // The clean file that isn't part of the layout is closed, while the dirty file and the
+        // item without a file are moved to the layout's active pane.
+        let panes = workspace.read_with(cx, |workspace, _| workspace.panes().to_vec());
+        assert_eq!(panes.len(), 2);
+        assert_eq!(pane_item_ids(&panes[0], cx), [item_a.entity_id()]);
+        assert_eq!(
+            pane_item_ids(&panes[1], cx),
+            [item_b.entity_id(), untitled_item.entity_id()]
+        );
+        workspace.read_with(cx, |workspace, cx| {
+            assert_eq!(workspace.active_pane(), &panes[1]);
+            assert!(
+                workspace
+                    .items(cx)
+                    .all(|item| item.item_id() != item_c.entity_id())
+            );
+        });
+    }
+
+    #[gpui::test]
+    async fn test_apply_layout_restores_zoom(cx: &mut TestAppContext) {
+        let (workspace, worktree_id, cx) = init_workspace(cx).await;
+        let item_a = file_item(1, "a.txt", worktree_id, cx);
+        let item_b = file_item(2, "b.txt", worktree_id, cx);
+        add_items(&workspace, &[&item_a, &item_b], cx);
+
+        let panel = workspace.update_in(cx, |workspace, window, cx| {
+            let panel = cx.new(|cx| TestPanel::new(DockPosition::Right, 100, cx));
+            workspace.add_panel(panel.clone(), window, cx);
+            workspace.right_dock().update(cx, |dock, cx| {
+                dock.activate_panel(0, window, cx);
+                dock.set_open(true, window, cx);
+            });
+            panel.update(cx, |panel, cx| panel.set_zoomed(true, window, cx));
+            panel
+        });
+
+        // Only the active pane of a layout can be zoomed.
+        let mut zoomed_pane = layout_pane(&["a.txt"], Some(0), true);
+        zoomed_pane.zoomed = true;
+        let mut inactive_pane = layout_pane(&["b.txt"], Some(0), false);
+        inactive_pane.zoomed = true;
+        let center = SerializedLayoutMember::Axis {
+            axis: Axis::Horizontal,
+            flexes: vec![1., 1.],
+            members: vec![
+                SerializedLayoutMember::Pane(zoomed_pane),
+                SerializedLayoutMember::Pane(inactive_pane),
+            ],
+        };
+        let layout = workspace.update_in(cx, |workspace, window, cx| SerializedLayout {
+            center,
+            ..workspace.capture_layout(window, cx)
+        });
+        workspace
+            .update_in(cx, |workspace, window, cx| {
+                workspace.apply_layout(layout, window, cx)
+            })
+            .await
+            .unwrap();
+
+        let panes = workspace.read_with(cx, |workspace, _| workspace.panes().to_vec());
+        workspace.update_in(cx, |workspace, window, cx| {
+            assert!(panes[0].read(cx).is_zoomed());
+            assert!(!panes[1].read(cx).is_zoomed());
+            assert!(!panel.read(cx).is_zoomed(window, cx));
+            assert_eq!(workspace.zoomed_position, None);
+        });
+
+        let captured = workspace.update_in(cx, |workspace, window, cx| {
+            workspace.capture_layout(window, cx)
+        });
+        let SerializedLayoutMember::Axis { members, .. } = captured.center else {
+            panic!("expected a split layout");
+        };
+        let zoomed = members
+            .iter()
+            .map(|member| match member {
+                SerializedLayoutMember::Pane(pane) => pane.zoomed,
+                SerializedLayoutMember::Axis { .. } => false,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(zoomed, [true, false]);
+        assert!(!captured.docks.right.zoom);
+    }
+
+    #[gpui::test]
+    async fn test_apply_layout_restores_docks(cx: &mut TestAppContext) {
+        let (workspace, _, cx) = init_workspace(cx).await;
+        workspace.update_in(cx, |workspace, window, cx| {
+            let panel = cx.new(|cx| TestPanel::new(DockPosition::Left, 100, cx));
+            workspace.add_panel(panel, window, cx);
+            workspace.left_dock().update(cx, |dock, cx| {
+                dock.activate_panel(0, window, cx);
+                dock.set_open(true, window, cx);
+                dock.resize_active_panel(Some(px(250.)), None, window, cx);
+            });
+        });
+
+        let layout = workspace.update_in(cx, |workspace, window, cx| {
+            workspace.capture_layout(window, cx)
+        });
+        assert!(layout.docks.left.visible);
+        assert_eq!(
+            layout.docks.left.active_panel.as_deref(),
+            Some(TestPanel::persistent_name())
+        );
+        assert_eq!(
+            layout.dock_sizes.left,
+            Some(PanelSizeState {
+                size: Some(px(250.)),
+                flexible_size_ratio: None,
+            })
+        );
+
+        workspace.update_in(cx, |workspace, window, cx| {
+            workspace.left_dock().update(cx, |dock, cx| {
+                dock.resize_active_panel(Some(px(400.)), None, window, cx);
+                dock.set_open(false, window, cx);
+            });
+        });
+        workspace
+            .update_in(cx, |workspace, window, cx| {
+                workspace.apply_layout(layout.clone(), window, cx)
+            })
+            .await
+            .unwrap();
+
+        let captured = workspace.update_in(cx, |workspace, window, cx| {
+            workspace.capture_layout(window, cx)
+        });
+        assert_eq!(captured.docks, layout.docks);
+        assert_eq!(captured.dock_sizes, layout.dock_sizes);
+    }
+}
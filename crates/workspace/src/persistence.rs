@@ -21,8 +21,12 @@ use db::{
 };
 use gpui::{Axis, Bounds, Task, WindowBounds, WindowId, point, size};
 use project::{
+    bookmark_store::SerializedBookmark,
     debugger::breakpoint_store::{BreakpointState, SourceBreakpoint},
-    trusted_worktrees::{DbTrustedPaths, RemoteHostLocation},
+    trusted_worktrees::{
+        DbTrustGrants, DbTrustedConfigs, DbTrustedPaths, RemoteHostLocation, TrustCapability,
+        TrustedConfigs,
+    },
 };
 
 use language::{LanguageName, Toolchain, ToolchainScope};
@@ -47,7 +51,7 @@ use crate::{
 };
 
 use model::{
-    GroupId, ItemId, PaneId, RemoteConnectionId, SerializedItem, SerializedPane,
+    GroupId, ItemId, PaneId, RemoteConnectionId, SerializedItem, SerializedLayout, SerializedPane,
     SerializedPaneGroup, SerializedWorkspace,
 };
 
@@ -971,6 +975,47 @@ impl Domain for WorkspaceDb {
         sql!(
             ALTER TABLE remote_connections ADD COLUMN use_podman BOOLEAN;
         ),
+        sql!(
+            CREATE TABLE bookmarks (
+                workspace_id INTEGER NOT NULL,
+                path TEXT NOT NULL,
+                row INTEGER NOT NULL,
+                label TEXT,
+                FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+                ON DELETE CASCADE
+                ON UPDATE CASCADE
+            ) STRICT;
+        ),
+        sql!(
+            CREATE TABLE workspace_layouts (
+                workspace_id INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                layout TEXT NOT NULL,
+                is_default INTEGER NOT NULL DEFAULT 0,
+                PRIMARY KEY(workspace_id, name),
+                FOREIGN KEY(workspace_id) REFERENCES workspaces(workspace_id)
+                ON DELETE CASCADE
+                ON UPDATE CASCADE
+            ) STRICT;
+        ),
+        sql!(
+            CREATE TABLE trust_grants (
+                absolute_path TEXT NOT NULL,
+                user_name TEXT,
+                host_name TEXT,
+                capability TEXT NOT NULL,
+                config_hash TEXT NOT NULL
+            ) STRICT;
+        ),
+        sql!(
+            CREATE TABLE trusted_configs (
+                absolute_path TEXT NOT NULL,
+                user_name TEXT,
+                host_name TEXT,
+                config_hashes TEXT NOT NULL,
+                changed INTEGER NOT NULL
+            ) STRICT;
+        ),
     ];
 
     // Allow recovering from bad migration that was initially shipped to nightly
@@ -1106,6 +1151,7 @@ impl WorkspaceDb {
             docks,
             session_id: None,
             breakpoints: self.breakpoints(workspace_id),
+            bookmarks: self.bookmarks(workspace_id),
             window_id,
             user_toolchains: self.user_toolchains(workspace_id, remote_connection_id),
         })
@@ -1196,6 +1242,7 @@ impl WorkspaceDb {
             docks,
             session_id: None,
             breakpoints: self.breakpoints(workspace_id),
+            bookmarks: self.bookmarks(workspace_id),
             window_id,
             user_toolchains: self.user_toolchains(workspace_id, remote_connection_id),
         })
@@ -1247,6 +1294,36 @@ impl WorkspaceDb {
         }
     }
 
+    fn bookmarks(&self, workspace_id: WorkspaceId) -> BTreeMap<Arc<Path>, Vec<SerializedBookmark>> {
+        let bookmarks: Result<Vec<(PathBuf, u32, Option<String>)>> = self
+            .select_bound(sql! {
+                SELECT path, row, label
+                FROM bookmarks
+                WHERE workspace_id = ?
+                ORDER BY path, row
+            })
+            .and_then(|mut prepared_statement| (prepared_statement)(workspace_id));
+
+        match bookmarks {
+            Ok(bookmarks) => {
+                let mut map: BTreeMap<Arc<Path>, Vec<SerializedBookmark>> = Default::default();
+                for (path, row, label) in bookmarks {
+                    map.entry(path.into())
+                        .or_default()
+                        .push(SerializedBookmark {
+                            row,
+                            label: label.map(Arc::from),
+                        });
+                }
+                map
+            }
+            Err(msg) => {
+                log::error!("Bookmarks query failed with msg: {msg}");
+                Default::default()
+            }
+        }
+    }
+
     fn user_toolchains(
         &self,
         workspace_id: WorkspaceId,
@@ -1375,6 +1452,27 @@ impl WorkspaceDb {
                     }
                 }
 
+                conn.exec_bound(
+                    sql!(
+                        DELETE FROM bookmarks WHERE workspace_id = ?1;
+                    )
+                )?(workspace.id).context("Clearing old bookmarks")?;
+
+                for (path, bookmarks) in workspace.bookmarks {
+                    for bookmark in bookmarks {
+                        conn.exec_bound(sql!(
+                            INSERT INTO bookmarks (workspace_id, path, row, label)
+                            VALUES (?1, ?2, ?3, ?4);
+                        ))?((
+                            workspace.id,
+                            path.as_ref(),
+                            bookmark.row,
+                            bookmark.label.as_deref(),
+                        ))
+                        .log_err();
+                    }
+                }
+
                 conn.exec_bound(
                     sql!(
                         DELETE FROM user_toolchains WHERE workspace_id = ?1;
@@ -2125,6 +2223,57 @@ impl WorkspaceDb {
         Ok(())
     }
 
+    query! {
+        pub async fn save_layout(workspace_id: WorkspaceId, name: String, layout: SerializedLayout) -> Result<()> {
+            INSERT INTO workspace_layouts(workspace_id, name, layout)
+            VALUES (?1, ?2, ?3)
+            ON CONFLICT DO
+            UPDATE SET layout = ?3
+        }
+    }
+
+    query! {
+        pub fn layout(workspace_id: WorkspaceId, name: &str) -> Result<Option<SerializedLayout>> {
+            SELECT layout
+            FROM workspace_layouts
+            WHERE workspace_id = ?1 AND name = ?2
+        }
+    }
+
+    // Returns the names of the workspace's layouts, and whether each one is the default.
+    query! {
+        pub fn layout_names(workspace_id: WorkspaceId) -> Result<Vec<(String, bool)>> {
+            SELECT name, is_default
+            FROM workspace_layouts
+            WHERE workspace_id = ?
+            ORDER BY name
+        }
+    }
+
+    query! {
+        pub fn default_layout(workspace_id: WorkspaceId) -> Result<Option<SerializedLayout>> {
+            SELECT layout
+            FROM workspace_layouts
+            WHERE workspace_id = ? AND is_default
+        }
+    }
+
+    // Makes the named layout the workspace's default, or clears the default if `name` is `None`.
+    query! {
+        pub async fn set_default_layout(workspace_id: WorkspaceId, name: Option<String>) -> Result<()> {
+            UPDATE workspace_layouts
+            SET is_default = (name IS ?2)
+            WHERE workspace_id = ?1
+        }
+    }
+
+    query! {
+        pub async fn delete_layout(workspace_id: WorkspaceId, name: String) -> Result<()> {
+            DELETE FROM workspace_layouts
+            WHERE workspace_id = ?1 AND name = ?2
+        }
+    }
+
     query! {
         pub async fn update_timestamp(workspace_id: WorkspaceId) -> Result<()> {
             UPDATE workspaces
@@ -2324,18 +2473,7 @@ VALUES {placeholders};"#
         Ok(trusted_worktrees
             .into_iter()
             .filter_map(|(abs_path, user_name, host_name)| {
-                let db_host = match (user_name, host_name) {
-                    (None, Some(host_name)) => Some(RemoteHostLocation {
-                        user_name: None,
-                        host_identifier: SharedString::new(host_name),
-                    }),
-                    (Some(user_name), Some(host_name)) => Some(RemoteHostLocation {
-                        user_name: Some(SharedString::new(user_name)),
-                        host_identifier: SharedString::new(host_name),
-                    }),
-                    _ => None,
-                };
-                Some((db_host, abs_path?))
+                Some((db_remote_host(user_name, host_name), abs_path?))
             })
             .fold(HashMap::default(), |mut acc, (remote_host, abs_path)| {
                 acc.entry(remote_host)
@@ -2357,6 +2495,157 @@ VALUES {placeholders};"#
             DELETE FROM trusted_worktrees
         }
     }
+
+    pub(crate) async fn save_trust_grants(&self, grants: DbTrustGrants) -> anyhow::Result<()> {
+        use anyhow::Context as _;
+
+        let mut rows = Vec::new();
+        for (host, worktrees) in grants {
+            let user_name = host
+                .as_ref()
+                .and_then(|host| Some(host.user_name.as_ref()?.to_string()));
+            let host_name = host.as_ref().map(|host| host.host_identifier.to_string());
+            for (abs_path, capabilities) in worktrees {
+                for (capability, config_hash) in capabilities {
+                    rows.push((
+                        abs_path.to_string_lossy().into_owned(),
+                        user_name.clone(),
+                        host_name.clone(),
+                        capability.to_db_key(),
+                        config_hash,
+                    ));
+                }
+            }
+        }
+
+        self.write(move |conn| {
+            conn.exec(sql!(DELETE FROM trust_grants))?()?;
+            let mut insert = conn.exec_bound(sql!(
+                INSERT INTO trust_grants(absolute_path, user_name, host_name, capability, config_hash)
+                VALUES (?, ?, ?, ?, ?)
+            ))?;
+            for row in rows {
+                insert(row)?;
+            }
+            anyhow::Ok(())
+        })
+        .await
+        .context("saving trust grants")
+    }
+
+    pub fn fetch_trust_grants(&self) -> Result<DbTrustGrants> {
+        let mut grants = DbTrustGrants::default();
+        for (abs_path, user_name, host_name, capability, config_hash) in self.trust_grants()? {
+            let Some(capability) = TrustCapability::from_db_key(&capability) else {
+                log::warn!("Skipping unknown trust grant {capability:?} for {abs_path:?}");
+                continue;
+            };
+            grants
+                .entry(db_remote_host(user_name, host_name))
+                .or_default()
+                .entry(abs_path)
+                .or_default()
+                .insert(capability, config_hash);
+        }
+        Ok(grants)
+    }
+
+    query! {
+        fn trust_grants() -> Result<Vec<(PathBuf, Option<String>, Option<String>, String, String)>> {
+            SELECT absolute_path, user_name, host_name, capability, config_hash
+            FROM trust_grants
+        }
+    }
+
+    query! {
+        pub async fn clear_trust_grants() -> Result<()> {
+            DELETE FROM trust_grants
+        }
+    }
+
+    pub(crate) async fn save_trusted_configs(
+        &self,
+        trusted_configs: DbTrustedConfigs,
+    ) -> anyhow::Result<()> {
+        use anyhow::Context as _;
+
+        let mut rows = Vec::new();
+        for (host, worktrees) in trusted_configs {
+            let user_name = host
+                .as_ref()
+                .and_then(|host| Some(host.user_name.as_ref()?.to_string()));
+            let host_name = host.as_ref().map(|host| host.host_identifier.to_string());
+            for (abs_path, worktree_configs) in worktrees {
+                rows.push((
+                    abs_path.to_string_lossy().into_owned(),
+                    user_name.clone(),
+                    host_name.clone(),
+                    worktree_configs.hashes_to_db_value(),
+                    worktree_configs.changed,
+                ));
+            }
+        }
+
+        self.write(move |conn| {
+            conn.exec(sql!(DELETE FROM trusted_configs))?()?;
+            let mut insert = conn.exec_bound(sql!(
+                INSERT INTO trusted_configs(absolute_path, user_name, host_name, config_hashes, changed)
+                VALUES (?, ?, ?, ?, ?)
+            ))?;
+            for row in rows {
+                insert(row)?;
+            }
+            anyhow::Ok(())
+        })
+        .await
+        .context("saving trusted configs")
+    }
+
+    pub fn fetch_trusted_configs(&self) -> Result<DbTrustedConfigs> {
+        let mut trusted_configs = DbTrustedConfigs::default();
+        for (abs_path, user_name, host_name, config_hashes, changed) in self.trusted_configs()? {
+            let Some(worktree_configs) = TrustedConfigs::from_db_value(&config_hashes, changed)
+            else {
+                log::warn!("Skipping malformed trusted configs for {abs_path:?}");
+                continue;
+            };
+            trusted_configs
+                .entry(db_remote_host(user_name, host_name))
+                .or_default()
+                .insert(abs_path, worktree_configs);
+        }
+        Ok(trusted_configs)
+    }
+
+    query! {
+        fn trusted_configs() -> Result<Vec<(PathBuf, Option<String>, Option<String>, String, bool)>> {
+            SELECT absolute_path, user_name, host_name, config_hashes, changed
+            FROM trusted_configs
+        }
+    }
+
+    query! {
+        pub async fn clear_trusted_configs() -> Result<()> {
+            DELETE FROM trusted_configs
+        }
+    }
+}
+
+fn db_remote_host(
+    user_name: Option<String>,
+    host_name: Option<String>,
+) -> Option<RemoteHostLocation> {
+    match (user_name, host_name) {
+        (None, Some(host_name)) => Some(RemoteHostLocation {
+            user_name: None,
+            host_identifier: SharedString::new(host_name),
+        }),
+        (Some(user_name), Some(host_name)) => Some(RemoteHostLocation {
+            user_name: Some(SharedString::new(user_name)),
+            host_identifier: SharedString::new(host_name),
+        }),
+        _ => None,
+    }
 }
 
 type WorkspaceEntry = (
@@ -2473,14 +2762,18 @@ pub fn delete_unloaded_items(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dock::PanelSizeState;
     use crate::persistence::model::{
-        SerializedItem, SerializedPane, SerializedPaneGroup, SerializedWorkspace, SessionWorkspace,
+        DockData, LayoutDockSizes, SerializedItem, SerializedLayoutMember, SerializedLayoutPane,
+        SerializedPane, SerializedPaneGroup, SerializedWorkspace, SessionWorkspace,
     };
     use gpui;
     use pretty_assertions::assert_eq;
+    use project::trusted_worktrees::TrustedConfigFile;
     use remote::SshConnectionOptions;
     use serde_json::json;
-    use std::{thread, time::Duration};
+    use std::{collections::BTreeMap, thread, time::Duration};
+    use util::rel_path::rel_path;
 
     /// Creates a unique directory in a FakeFs, returning the path.
     /// Uses a UUID suffix to avoid collisions with other tests sharing the global DB.
@@ -2663,6 +2956,7 @@ mod tests {
                 );
                 map
             },
+            bookmarks: Default::default(),
             session_id: None,
             window_id: None,
             user_toolchains: Default::default(),
@@ -2784,6 +3078,7 @@ mod tests {
                 );
                 map
             },
+            bookmarks: Default::default(),
             session_id: None,
             window_id: None,
             user_toolchains: Default::default(),
@@ -2818,6 +3113,7 @@ mod tests {
             docks: Default::default(),
             centered_layout: false,
             breakpoints: collections::BTreeMap::default(),
+            bookmarks: Default::default(),
             session_id: None,
             window_id: None,
             user_toolchains: Default::default(),
@@ -2834,6 +3130,236 @@ mod tests {
         assert!(empty_breakpoints.is_none());
     }
 
+    #[gpui::test]
+    async fn test_bookmarks() {
+        zlog::init_test();
+
+        let db = WorkspaceDb::open_test_db("test_bookmarks").await;
+        let id = db.next_id().await.unwrap();
+
+        let path = Path::new("/tmp/test_bookmarks.rs");
+        let bookmarks = vec![
+            SerializedBookmark {
+                row: 3,
+                label: None,
+            },
+            SerializedBookmark {
+                row: 42,
+                label: Some("entry point".into()),
+            },
+        ];
+
+        let mut workspace = SerializedWorkspace {
+            id,
+            paths: PathList::new(&["/tmp"]),
+            location: SerializedWorkspaceLocation::Local,
+            center_group: Default::default(),
+            window_bounds: Default::default(),
+            display: Default::default(),
+            docks: Default::default(),
+            centered_layout: false,
+            breakpoints: Default::default(),
+            bookmarks: collections::BTreeMap::from_iter([(Arc::from(path), bookmarks.clone())]),
+            session_id: None,
+            window_id: None,
+            user_toolchains: Default::default(),
+        };
+
+        db.save_workspace(workspace.clone()).await;
+        let loaded = db.workspace_for_roots(&["/tmp"]).unwrap();
+        assert_eq!(loaded.bookmarks.get(&Arc::from(path)), Some(&bookmarks));
+
+        workspace.bookmarks.clear();
+        db.save_workspace(workspace).await;
+        let loaded = db.workspace_for_roots(&["/tmp"]).unwrap();
+        assert!(loaded.bookmarks.is_empty());
+    }
+
+    #[gpui::test]
+    async fn test_layouts() {
+        zlog::init_test();
+
+        let db = WorkspaceDb::open_test_db("test_layouts").await;
+        let id = db.next_id().await.unwrap();
+
+        let review = SerializedLayout {
+            center: SerializedLayoutMember::Axis {
+                axis: Axis::Horizontal,
+                flexes: vec![0.5, 1.5],
+                members: vec![
+                    SerializedLayoutMember::Pane(SerializedLayoutPane {
+                        items: vec![PathBuf::from("/tmp/a.rs"), PathBuf::from("/tmp/b.rs")],
+                        active_item: Some(1),
+                        pinned_count: 1,
+                        active: true,
+                        zoomed: false,
+                    }),
+                    SerializedLayoutMember::Pane(SerializedLayoutPane::default()),
+                ],
+            },
+            docks: DockStructure {
+                left: DockData {
+                    visible: true,
+                    active_panel: Some("ProjectPanel".into()),
+                    zoom: false,
+                },
+                ..Default::default()
+            },
+            dock_sizes: LayoutDockSizes {
+                left: Some(PanelSizeState {
+                    size: Some(px(320.)),
+                    flexible_size_ratio: None,
+                }),
+                ..Default::default()
+            },
+        };
+        let writing = SerializedLayout {
+            center: SerializedLayoutMember::Pane(SerializedLayoutPane {
+                zoomed: true,
+                ..Default::default()
+            }),
+            docks: Default::default(),
+            dock_sizes: Default::default(),
+        };
+
+        db.save_layout(id, "review".into(), review.clone())
+            .await
+            .unwrap();
+        db.save_layout(id, "writing".into(), review.clone())
+            .await
+            .unwrap();
+        db.save_layout(id, "writing".into(), writing.clone())
+            .await
+            .unwrap();
+        assert_eq!(db.layout(id, "review").unwrap(), Some(review));
+        assert_eq!(db.layout(id, "writing").unwrap(), Some(writing.clone()));
+        assert_eq!(db.layout(id, "debugging").unwrap(), None);
+        assert_eq!(db.default_layout(id).unwrap(), None);
+
+        db.set_default_layout(id, Some("writing".into()))
+            .await
+            .unwrap();
+        assert_eq!(
+            db.layout_names(id).unwrap(),
+            [("review".to_string(), false), ("writing".to_string(), true)]
+        );
+        assert_eq!(db.default_layout(id).unwrap(), Some(writing));
+
+        db.delete_layout(id, "writing".into()).await.unwrap();
+        assert_eq!(
+            db.layout_names(id).unwrap(),
+            [("review".to_string(), false)]
+        );
+        assert_eq!(db.default_layout(id).unwrap(), None);
+
+        db.set_default_layout(id, None).await.unwrap();
+        assert_eq!(
+            db.layout_names(id).unwrap(),
+            [("review".to_string(), false)]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_trust_grants() {
+        zlog::init_test();
+
+        let db = WorkspaceDb::open_test_db("test_trust_grants").await;
+        assert_eq!(db.fetch_trust_grants().unwrap(), DbTrustGrants::default());
+
+        let remote_host = RemoteHostLocation {
+            user_name: Some("user".into()),
+            host_identifier: "example.com".into(),
+        };
+        let mut grants = DbTrustGrants::default();
+        grants.entry(None).or_default().insert(
+            PathBuf::from("/projects/client"),
+            HashMap::from_iter([
+                (
+                    TrustCapability::LanguageServers,
+                    "settings-hash".to_string(),
+                ),
+                (
+                    TrustCapability::ContextServer("docs".into()),
+                    "settings-hash".to_string(),
+                ),
+            ]),
+        );
+        grants.entry(Some(remote_host)).or_default().insert(
+            PathBuf::from("/home/user/project"),
+            HashMap::from_iter([(TrustCapability::ProjectTasks, "tasks-hash".to_string())]),
+        );
+
+        db.save_trust_grants(grants.clone()).await.unwrap();
+        assert_eq!(db.fetch_trust_grants().unwrap(), grants);
+
+        grants.remove(&None);
+        db.save_trust_grants(grants.clone()).await.unwrap();
+        assert_eq!(db.fetch_trust_grants().unwrap(), grants);
+
+        db.clear_trust_grants().await.unwrap();
+        assert_eq!(db.fetch_trust_grants().unwrap(), DbTrustGrants::default());
+    }
+
+    #[gpui::test]
+    async fn test_trusted_configs() {
+        zlog::init_test();
+
+        let db = WorkspaceDb::open_test_db("test_trusted_configs").await;
+        assert_eq!(
+            db.fetch_trusted_configs().unwrap(),
+            DbTrustedConfigs::default()
+        );
+
+        let remote_host = RemoteHostLocation {
+            user_name: Some("user".into()),
+            host_identifier: "example.com".into(),
+        };
+        let mut trusted_configs = DbTrustedConfigs::default();
+        trusted_configs.entry(None).or_default().insert(
+            PathBuf::from("/projects/client"),
+            TrustedConfigs {
+                hashes: BTreeMap::from_iter([
+                    (
+                        (TrustedConfigFile::Settings, RelPath::empty().into_arc()),
+                        "settings-hash".to_string(),
+                    ),
+                    (
+                        (TrustedConfigFile::Tasks, rel_path("nested/dir").into_arc()),
+                        "tasks-hash".to_string(),
+                    ),
+                ]),
+                changed: false,
+            },
+        );
+        trusted_configs
+            .entry(Some(remote_host))
+            .or_default()
+            .insert(
+                PathBuf::from("/home/user/project"),
+                TrustedConfigs {
+                    hashes: BTreeMap::default(),
+                    changed: true,
+                },
+            );
+
+        db.save_trusted_configs(trusted_configs.clone())
+            .await
+            .unwrap();
+        assert_eq!(db.fetch_trusted_configs().unwrap(), trusted_configs);
+
+        trusted_configs.remove(&None);
+        db.save_trusted_configs(trusted_configs.clone())
+            .await
+            .unwrap();
+        assert_eq!(db.fetch_trusted_configs().unwrap(), trusted_configs);
+
+        db.clear_trusted_configs().await.unwrap();
+        assert_eq!(
+            db.fetch_trusted_configs().unwrap(),
+            DbTrustedConfigs::default()
+        );
+    }
+
     #[gpui::test]
     async fn test_next_id_stability() {
         zlog::init_test();
@@ -2916,6 +3442,7 @@ mod tests {
             docks: Default::default(),
             centered_layout: false,
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             session_id: None,
             window_id: None,
             user_toolchains: Default::default(),
@@ -2931,6 +3458,7 @@ mod tests {
             docks: Default::default(),
             centered_layout: false,
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             session_id: None,
             window_id: None,
             user_toolchains: Default::default(),
@@ -3035,6 +3563,7 @@ mod tests {
             center_group,
             window_bounds: Default::default(),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
@@ -3069,6 +3598,7 @@ mod tests {
             center_group: Default::default(),
             window_bounds: Default::default(),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
@@ -3087,6 +3617,7 @@ mod tests {
             docks: Default::default(),
             centered_layout: false,
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             session_id: None,
             window_id: Some(2),
             user_toolchains: Default::default(),
@@ -3126,6 +3657,7 @@ mod tests {
             center_group: Default::default(),
             window_bounds: Default::default(),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
@@ -3167,6 +3699,7 @@ mod tests {
             docks: Default::default(),
             centered_layout: false,
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             session_id: Some("session-id-1".to_owned()),
             window_id: Some(10),
             user_toolchains: Default::default(),
@@ -3182,6 +3715,7 @@ mod tests {
             docks: Default::default(),
             centered_layout: false,
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             session_id: Some("session-id-1".to_owned()),
             window_id: Some(20),
             user_toolchains: Default::default(),
@@ -3197,6 +3731,7 @@ mod tests {
             docks: Default::default(),
             centered_layout: false,
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             session_id: Some("session-id-2".to_owned()),
             window_id: Some(30),
             user_toolchains: Default::default(),
@@ -3212,6 +3747,7 @@ mod tests {
             docks: Default::default(),
             centered_layout: false,
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             session_id: None,
             window_id: None,
             user_toolchains: Default::default(),
@@ -3238,6 +3774,7 @@ mod tests {
             docks: Default::default(),
             centered_layout: false,
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             session_id: Some("session-id-2".to_owned()),
             window_id: Some(50),
             user_toolchains: Default::default(),
@@ -3250,6 +3787,7 @@ mod tests {
             center_group: Default::default(),
             window_bounds: Default::default(),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             display: Default::default(),
             docks: Default::default(),
             centered_layout: false,
@@ -3309,6 +3847,7 @@ mod tests {
             display: Default::default(),
             docks: Default::default(),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             centered_layout: false,
             session_id: None,
             window_id: None,
@@ -3352,6 +3891,7 @@ mod tests {
             centered_layout: false,
             session_id: Some("one-session".to_owned()),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             window_id: Some(window_id),
             user_toolchains: Default::default(),
         })
@@ -3464,6 +4004,7 @@ mod tests {
             centered_layout: false,
             session_id: Some("one-session".to_owned()),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             window_id: Some(window_id),
             user_toolchains: Default::default(),
         })
@@ -3823,6 +4364,7 @@ mod tests {
             display: None,
             docks: Default::default(),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             centered_layout: false,
             session_id: None,
             window_id: None,
@@ -3901,6 +4443,7 @@ mod tests {
                 centered_layout: false,
                 session_id: Some("test-session".to_owned()),
                 breakpoints: Default::default(),
+                bookmarks: Default::default(),
                 window_id: Some(*window_id),
                 user_toolchains: Default::default(),
             })
@@ -4209,6 +4752,7 @@ mod tests {
             centered_layout: false,
             session_id: Some(session_id.clone()),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             window_id: Some(99),
             user_toolchains: Default::default(),
         })
@@ -4308,6 +4852,7 @@ mod tests {
             centered_layout: false,
             session_id: Some(session_id.to_owned()),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             window_id: Some(window_id_val),
             user_toolchains: Default::default(),
         })
@@ -4324,6 +4869,7 @@ mod tests {
             centered_layout: false,
             session_id: Some(session_id.to_owned()),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             window_id: Some(window_id_val),
             user_toolchains: Default::default(),
         })
@@ -4406,6 +4952,7 @@ mod tests {
             centered_layout: false,
             session_id: Some(session_id.clone()),
             breakpoints: Default::default(),
+            bookmarks: Default::default(),
             window_id: Some(88),
             user_toolchains: Default::default(),
         })
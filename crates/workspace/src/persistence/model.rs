@@ -1,7 +1,7 @@
 use super::{SerializedAxis, SerializedWindowBounds};
 use crate::{
-    Member, Pane, PaneAxis, SerializableItemRegistry, Workspace, WorkspaceId, item::ItemHandle,
-    path_list::PathList,
+    Member, Pane, PaneAxis, SerializableItemRegistry, Workspace, WorkspaceId, dock::PanelSizeState,
+    item::ItemHandle, path_list::PathList,
 };
 use anyhow::{Context, Result};
 use async_recursion::async_recursion;
@@ -10,10 +10,12 @@ use db::sqlez::{
     bindable::{Bind, Column, StaticColumnCount},
     statement::Statement,
 };
-use gpui::{AsyncWindowContext, Entity, WeakEntity, WindowId};
+use gpui::{AsyncWindowContext, Axis, Entity, WeakEntity, WindowId};
 
 use language::{Toolchain, ToolchainScope};
-use project::{Project, debugger::breakpoint_store::SourceBreakpoint};
+use project::{
+    Project, bookmark_store::SerializedBookmark, debugger::breakpoint_store::SourceBreakpoint,
+};
 use remote::RemoteConnectionOptions;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -87,6 +89,7 @@ pub(crate) struct SerializedWorkspace {
     pub(crate) docks: DockStructure,
     pub(crate) session_id: Option<String>,
     pub(crate) breakpoints: BTreeMap<Arc<Path>, Vec<SourceBreakpoint>>,
+    pub(crate) bookmarks: BTreeMap<Arc<Path>, Vec<SerializedBookmark>>,
     pub(crate) user_toolchains: BTreeMap<ToolchainScope, IndexSet<Toolchain>>,
     pub(crate) window_id: Option<u64>,
 }
@@ -172,6 +175,67 @@ impl Bind for DockData {
     }
 }
 
+/// A named arrangement of a workspace's panes and docks, which can be switched to at any time.
+///
+/// Unlike the serialized session state, which refers to items by their (unstable) ids, layouts
+/// refer to the files open in each pane by their absolute paths. Items that aren't backed by a
+/// file aren't captured.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct SerializedLayout {
+    pub center: SerializedLayoutMember,
+    pub docks: DockStructure,
+    #[serde(default)]
+    pub dock_sizes: LayoutDockSizes,
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SerializedLayoutMember {
+    Axis {
+        axis: Axis,
+        flexes: Vec<f32>,
+        members: Vec<SerializedLayoutMember>,
+    },
+    Pane(SerializedLayoutPane),
+}
+
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct SerializedLayoutPane {
+    pub items: Vec<PathBuf>,
+    pub active_item: Option<usize>,
+    #[serde(default)]
+    pub pinned_count: usize,
+    #[serde(default)]
+    pub active: bool,
+    #[serde(default)]
+    pub zoomed: bool,
+}
+
+/// The sizes of the active panel of each dock.
+#[derive(Debug, PartialEq, Clone, Default, Serialize, Deserialize)]
+pub struct LayoutDockSizes {
+    pub left: Option<PanelSizeState>,
+    pub right: Option<PanelSizeState>,
+    pub bottom: Option<PanelSizeState>,
+}
+
+impl StaticColumnCount for SerializedLayout {}
+
+impl Bind for SerializedLayout {
+    fn bind(&self, statement: &Statement, start_index: i32) -> Result<i32> {
+        let json = serde_json::to_string(self).context("serializing layout")?;
+        statement.bind(&json, start_index)
+    }
+}
+
+impl Column for SerializedLayout {
+    fn column(statement: &mut Statement, start_index: i32) -> Result<(Self, i32)> {
+        let (json, next_index) = String::column(statement, start_index)?;
+        let layout = serde_json::from_str(&json).context("deserializing layout")?;
+        Ok((layout, next_index))
+    }
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub(crate) enum SerializedPaneGroup {
     Group {
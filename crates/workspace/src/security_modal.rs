@@ -2,6 +2,7 @@
 
 use std::{
     borrow::Cow,
+    collections::BTreeSet,
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -11,7 +12,9 @@ use gpui::{DismissEvent, EventEmitter, FocusHandle, Focusable, WeakEntity};
 
 use project::{
     WorktreeId,
-    trusted_worktrees::{PathTrust, RemoteHostLocation, TrustedWorktrees},
+    trusted_worktrees::{
+        PathTrust, RemoteHostLocation, TrustCapability, TrustedConfigFile, TrustedWorktrees,
+    },
     worktree_store::WorktreeStore,
 };
 use smallvec::SmallVec;
@@ -29,7 +32,18 @@ pub struct SecurityModal {
     worktree_store: WeakEntity<WorktreeStore>,
     remote_host: Option<RemoteHostLocation>,
     focus_handle: FocusHandle,
-    trusted: Option<bool>,
+    decision: Option<Decision>,
+    /// Capabilities to grant the restricted worktrees, instead of trusting them.
+    capabilities: BTreeSet<TrustCapability>,
+    context_servers: BTreeSet<SharedString>,
+    changed_config_files: BTreeSet<TrustedConfigFile>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Decision {
+    Restricted,
+    Trusted,
+    Granted,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -53,9 +67,16 @@ impl ModalView for SecurityModal {
     }
 
     fn on_before_dismiss(&mut self, _: &mut Window, _: &mut Context<Self>) -> DismissDecision {
-        match self.trusted {
-            Some(false) => telemetry::event!("Open in Restricted", source = "Worktree Trust Modal"),
-            Some(true) => telemetry::event!("Trust and Continue", source = "Worktree Trust Modal"),
+        match self.decision {
+            Some(Decision::Restricted) => {
+                telemetry::event!("Open in Restricted", source = "Worktree Trust Modal")
+            }
+            Some(Decision::Trusted) => {
+                telemetry::event!("Trust and Continue", source = "Worktree Trust Modal")
+            }
+            Some(Decision::Granted) => {
+                telemetry::event!("Allow Selected", source = "Worktree Trust Modal")
+            }
             None => telemetry::event!("Dismissed", source = "Worktree Trust Modal"),
         }
         DismissDecision::Dismiss(true)
@@ -85,7 +106,7 @@ impl Render for SecurityModal {
                 this.trust_and_dismiss(cx);
             }))
             .on_action(cx.listener(|security_modal, _: &ToggleWorktreeSecurity, _window, cx| {
-                security_modal.trusted = Some(false);
+                security_modal.decision = Some(Decision::Restricted);
                 security_modal.dismiss(cx);
             }))
             .header(
@@ -147,13 +168,28 @@ impl Render for SecurityModal {
                                 .color(Color::Muted),
                             ),
                     )
+                    .when_some(self.changed_config_label(), |this, label| {
+                        this.child(
+                            h_flex()
+                                .gap_1()
+                                .child(
+                                    Icon::new(IconName::Warning)
+                                        .size(IconSize::Small)
+                                        .color(Color::Warning),
+                                )
+                                .child(Label::new(label).color(Color::Warning)),
+                        )
+                    })
                     .child(
                         v_flex()
                             .child(Label::new("Restricted Mode prevents:").color(Color::Muted))
                             .child(ListBulletItem::new("Project settings from being applied"))
+                            .child(ListBulletItem::new("Project tasks from being loaded"))
+                            .child(ListBulletItem::new("Project key bindings from being loaded"))
                             .child(ListBulletItem::new("Language servers from running"))
                             .child(ListBulletItem::new("MCP Server integrations from installing")),
                     )
+                    .child(self.render_capabilities(cx))
                     .map(|this| match trust_label {
                         Some(trust_label) => this.child(
                             Checkbox::new("trust-parents", ToggleState::from(self.trust_parents))
@@ -185,11 +221,19 @@ impl Render for SecurityModal {
                                 .map(|kb| kb.size(rems_from_px(12.))),
                             )
                             .on_click(cx.listener(move |security_modal, _, _, cx| {
-                                security_modal.trusted = Some(false);
+                                security_modal.decision = Some(Decision::Restricted);
                                 security_modal.dismiss(cx);
                                 cx.stop_propagation();
                             })),
                     )
+                    .child(
+                        Button::new("as", "Allow Selected").on_click(cx.listener(
+                            move |security_modal, _, _, cx| {
+                                security_modal.grant_and_dismiss(cx);
+                                cx.stop_propagation();
+                            },
+                        )),
+                    )
                     .child(
                         Button::new("tc", "Trust and Continue")
                             .style(ButtonStyle::Filled)
@@ -221,7 +265,10 @@ impl SecurityModal {
             focus_handle: cx.focus_handle(),
             trust_parents: false,
             home_dir: std::env::home_dir(),
-            trusted: None,
+            decision: None,
+            capabilities: BTreeSet::new(),
+            context_servers: BTreeSet::new(),
+            changed_config_files: BTreeSet::new(),
         };
         this.refresh_restricted_paths(cx);
 
@@ -294,10 +341,137 @@ impl SecurityModal {
             });
         }
 
-        self.trusted = Some(true);
+        self.decision = Some(Decision::Trusted);
+        self.dismiss(cx);
+    }
+
+    fn grant_and_dismiss(&mut self, cx: &mut Context<Self>) {
+        if let Some((trusted_worktrees, worktree_store)) =
+            TrustedWorktrees::try_get_global(cx).zip(self.worktree_store.upgrade())
+        {
+            trusted_worktrees.update(cx, |trusted_worktrees, cx| {
+                for &worktree_id in self.restricted_paths.keys() {
+                    let context_servers =
+                        trusted_worktrees.context_servers(&worktree_store, worktree_id);
+                    let capabilities = self
+                        .capabilities
+                        .iter()
+                        .filter(|capability| match capability {
+                            TrustCapability::ContextServer(name) => context_servers.contains(name),
+                            _ => true,
+                        })
+                        .cloned()
+                        .collect::<Vec<_>>();
+                    trusted_worktrees.set_grants(&worktree_store, worktree_id, capabilities, cx);
+                }
+            });
+        }
+
+        self.decision = Some(Decision::Granted);
         self.dismiss(cx);
     }
 
+    fn toggle_capability(&mut self, capability: TrustCapability, cx: &mut Context<Self>) {
+        if !self.capabilities.remove(&capability) {
+            self.capabilities.insert(capability);
+        }
+        // MCP servers come from the project settings, and cannot start without these applied.
+        if !self
+            .capabilities
+            .contains(&TrustCapability::ProjectSettings)
+        {
+            self.capabilities
+                .retain(|capability| !matches!(capability, TrustCapability::ContextServer(_)));
+        }
+        cx.notify();
+    }
+
+    fn render_capabilities(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let project_settings_allowed = self
+            .capabilities
+            .contains(&TrustCapability::ProjectSettings);
+        v_flex()
+            .gap_0p5()
+            .child(Label::new("Or allow only:").color(Color::Muted))
+            .child(self.render_capability_checkbox(
+                "allow-language-servers",
+                TrustCapability::LanguageServers,
+                "Language servers and linters",
+                false,
+                cx,
+            ))
+            .child(self.render_capability_checkbox(
+                "allow-project-settings",
+                TrustCapability::ProjectSettings,
+                "Project settings from .zed/settings.json",
+                false,
+                cx,
+            ))
+            .children(self.context_servers.iter().enumerate().map(|(ix, name)| {
+                div().pl_6().child(self.render_capability_checkbox(
+                    ("allow-context-server", ix),
+                    TrustCapability::ContextServer(name.clone()),
+                    format!("MCP server \u{201c}{name}\u{201d}"),
+                    !project_settings_allowed,
+                    cx,
+                ))
+            }))
+            .child(self.render_capability_checkbox(
+                "allow-project-tasks",
+                TrustCapability::ProjectTasks,
+                "Tasks from .zed/tasks.json",
+                false,
+                cx,
+            ))
+            .child(self.render_capability_checkbox(
+                "allow-project-keymap",
+                TrustCapability::ProjectKeymap,
+                "Key bindings from .zed/keymap.json",
+                false,
+                cx,
+            ))
+    }
+
+    fn render_capability_checkbox(
+        &self,
+        id: impl Into<ElementId>,
+        capability: TrustCapability,
+        label: impl Into<SharedString>,
+        disabled: bool,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        Checkbox::new(
+            id,
+            ToggleState::from(self.capabilities.contains(&capability)),
+        )
+        .label(label)
+        .disabled(disabled)
+        .on_click(cx.listener(move |security_modal, _: &ToggleState, _, cx| {
+            security_modal.toggle_capability(capability.clone(), cx);
+            cx.stop_propagation();
+        }))
+    }
+
+    fn changed_config_label(&self) -> Option<String> {
+        let files = self
+            .changed_config_files
+            .iter()
+            .map(|config_file| match config_file {
+                TrustedConfigFile::Settings => ".zed/settings.json",
+                TrustedConfigFile::Tasks => ".zed/tasks.json",
+                TrustedConfigFile::Keymap => ".zed/keymap.json",
+            })
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            None
+        } else {
+            Some(format!(
+                "{} changed since you last allowed it. Review the changes before allowing again.",
+                files.join(" and ")
+            ))
+        }
+    }
+
     pub fn dismiss(&mut self, cx: &mut Context<Self>) {
         cx.emit(DismissEvent);
     }
@@ -322,9 +496,36 @@ impl SecurityModal {
                     })
                     .collect::<HashMap<_, _>>();
 
+                let trusted_worktrees = trusted_worktrees.read(cx);
+                let mut granted_capabilities = BTreeSet::new();
+                let mut context_servers = BTreeSet::new();
+                let mut changed_config_files = BTreeSet::new();
+                for &worktree_id in new_restricted_worktrees.keys() {
+                    granted_capabilities.extend(trusted_worktrees.granted_capabilities(
+                        &worktree_store,
+                        worktree_id,
+                        cx,
+                    ));
+                    context_servers
+                        .extend(trusted_worktrees.context_servers(&worktree_store, worktree_id));
+                    changed_config_files.extend(trusted_worktrees.changed_config_files(
+                        &worktree_store,
+                        worktree_id,
+                        cx,
+                    ));
+                }
+
                 if self.restricted_paths != new_restricted_worktrees {
                     self.trust_parents = false;
                     self.restricted_paths = new_restricted_worktrees;
+                    self.capabilities = granted_capabilities;
+                    cx.notify();
+                }
+                if self.context_servers != context_servers
+                    || self.changed_config_files != changed_config_files
+                {
+                    self.context_servers = context_servers;
+                    self.changed_config_files = changed_config_files;
                     cx.notify();
                 }
             }
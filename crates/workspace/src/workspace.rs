@@ -2,6 +2,7 @@ pub mod dock;
 pub mod history_manager;
 pub mod invalid_item_view;
 pub mod item;
+mod layouts;
 mod modal_layer;
 mod multi_workspace;
 pub mod notifications;
@@ -26,6 +27,7 @@ mod workspace_settings;
 
 pub use crate::notifications::NotificationFrame;
 pub use dock::Panel;
+pub use layouts::LayoutEntry;
 pub use multi_workspace::{
     CloseWorkspaceSidebar, DraggedSidebar, FocusWorkspaceSidebar, MultiWorkspace,
     MultiWorkspaceEvent, NextWorkspace, PreviousWorkspace, Sidebar, SidebarHandle,
@@ -80,7 +82,8 @@ use persistence::{SerializedWindowBounds, model::SerializedWorkspace};
 pub use persistence::{
     WorkspaceDb, delete_unloaded_items,
     model::{
-        DockStructure, ItemId, SerializedMultiWorkspace, SerializedWorkspaceLocation,
+        DockStructure, ItemId, LayoutDockSizes, SerializedLayout, SerializedLayoutMember,
+        SerializedLayoutPane, SerializedMultiWorkspace, SerializedWorkspaceLocation,
         SessionWorkspace,
     },
     read_serialized_multi_workspaces, resolve_worktree_workspaces,
@@ -89,6 +92,7 @@ use postage::stream::Stream;
 use project::{
     DirectoryLister, Project, ProjectEntryId, ProjectPath, ResolvedPath, Worktree, WorktreeId,
     WorktreeSettings,
+    bookmark_store::BookmarkStoreEvent,
     debugger::{breakpoint_store::BreakpointStoreEvent, session::ThreadStatus},
     project_settings::ProjectSettings,
     toolchain_store::ToolchainStoreEvent,
@@ -1398,6 +1402,44 @@ impl Workspace {
             })
             .detach();
 
+            cx.observe(&trusted_worktrees, |_, trusted_worktrees, cx| {
+                trusted_worktrees.update(cx, |trusted_worktrees, cx| {
+                    trusted_worktrees.schedule_grants_serialization(
+                        cx,
+                        |grants, trusted_configs, cx| {
+                            let timeout =
+                                cx.background_executor().timer(SERIALIZATION_THROTTLE_TIME);
+                            let db = WorkspaceDb::global(cx);
+                            cx.background_spawn(async move {
+                                timeout.await;
+                                db.save_trust_grants(grants).await.log_err();
+                                db.save_trusted_configs(trusted_configs).await.log_err();
+                            })
+                        },
+                    )
+                });
+            })
+            .detach();
+
+            // Ask to review the project again, when its config changes after some capabilities were granted to it.
+            cx.subscribe_in(
+                &trusted_worktrees,
+                window,
+                |workspace, trusted_worktrees, e, window, cx| {
+                    if let TrustedWorktreesEvent::Restricted(worktree_store, _) = e {
+                        let project_worktree_store = workspace.project().read(cx).worktree_store();
+                        if *worktree_store == project_worktree_store.downgrade()
+                            && trusted_worktrees.update(cx, |trusted_worktrees, _| {
+                                trusted_worktrees.take_changed_configs(&project_worktree_store)
+                            })
+                        {
+                            workspace.show_worktree_trust_security_modal(false, window, cx);
+                        }
+                    }
+                },
+            )
+            .detach();
+
             cx.observe_global::<SettingsStore>(|_, cx| {
                 if ProjectSettings::get_global(cx).session.trust_all_worktrees {
                     if let Some(trusted_worktrees) = TrustedWorktrees::try_get_global(cx) {
@@ -1529,6 +1571,18 @@ impl Workspace {
             },
         )
         .detach();
+
+        cx.subscribe_in(
+            &project.read(cx).bookmark_store(),
+            window,
+            |workspace, _, event, window, cx| match event {
+                BookmarkStoreEvent::BookmarksUpdated(_)
+                | BookmarkStoreEvent::BookmarksCleared(_) => {
+                    workspace.serialize_workspace(window, cx);
+                }
+            },
+        )
+        .detach();
         if let Some(toolchain_store) = project.read(cx).toolchain_store() {
             cx.subscribe_in(
                 &toolchain_store,
@@ -6411,6 +6465,12 @@ impl Workspace {
                         .read(cx)
                         .all_source_breakpoints(cx)
                 });
+                let bookmarks = self.project.update(cx, |project, cx| {
+                    project
+                        .bookmark_store()
+                        .read(cx)
+                        .all_serialized_bookmarks(cx)
+                });
                 let user_toolchains = self
                     .project
                     .read(cx)
@@ -6432,6 +6492,7 @@ impl Workspace {
                     centered_layout: self.centered_layout,
                     session_id: self.session_id.clone(),
                     breakpoints,
+                    bookmarks,
                     window_id: Some(window.window_handle().window_id().as_u64()),
                     user_toolchains,
                 };
@@ -6640,6 +6701,28 @@ impl Workspace {
                 cx.notify();
             })?;
 
+            // A project's default layout takes precedence over the panes and docks of its last
+            // session. As it may close the restored items, the requested paths are left for the
+            // caller to open.
+            let default_layout = cx.update(|_, cx| {
+                WorkspaceDb::global(cx)
+                    .default_layout(serialized_workspace.id)
+                    .log_err()
+                    .flatten()
+            })?;
+            let opened_items = match default_layout {
+                Some(layout) => {
+                    workspace
+                        .update_in(cx, |workspace, window, cx| {
+                            workspace.apply_layout(layout, window, cx)
+                        })?
+                        .await
+                        .log_err();
+                    opened_items.iter().map(|_| None).collect()
+                }
+                None => opened_items,
+            };
+
             let _ = project
                 .update(cx, |project, cx| {
                     project
@@ -6651,6 +6734,14 @@ impl Workspace {
                 })
                 .await;
 
+            let _ = project
+                .update(cx, |project, cx| {
+                    project.bookmark_store().update(cx, |bookmark_store, cx| {
+                        bookmark_store.with_serialized_bookmarks(serialized_workspace.bookmarks, cx)
+                    })
+                })
+                .await;
+
             // Clean up all the items that have _not_ been loaded. Our ItemIds aren't stable. That means
             // after loading the items, we might have different items and in order to avoid
             // the database filling up, we delete items that haven't been loaded now.
@@ -6901,7 +6992,10 @@ impl Workspace {
                         });
                         let db = WorkspaceDb::global(cx);
                         cx.spawn(async move |_, cx| {
-                            if db.clear_trusted_worktrees().await.log_err().is_some() {
+                            if db.clear_trusted_worktrees().await.log_err().is_some()
+                                && db.clear_trust_grants().await.log_err().is_some()
+                                && db.clear_trusted_configs().await.log_err().is_some()
+                            {
                                 cx.update(|cx| reload(cx));
                             }
                         })
@@ -0,0 +1,678 @@
+//! Runs the agent against a project without opening a window, for `zed --agent`.
+//!
+//! Progress (assistant messages and tool calls) is streamed as the agent works,
+//! and the changes it made are printed once it stops, either as a unified diff
+//! or as a JSON transcript.
+
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::process;
+use std::rc::Rc;
+use std::str::FromStr as _;
+use std::sync::Arc;
+
+use acp_thread::{
+    AcpThread, AcpThreadEvent, AgentConnection as _, AgentThreadEntry, AssistantMessageChunk,
+    ContentBlock, PermissionOptions, SelectedPermissionOutcome, ToolCallStatus,
+};
+use agent::{NativeAgent, NativeAgentConnection, Templates, Thread, ThreadStore};
+use agent_client_protocol as acp;
+use agent_settings::{AgentProfileId, AgentSettings};
+use anyhow::{Context as _, Result, anyhow};
+use cli::{AgentOutputFormat, AgentPermissionPolicy};
+use client::{Client, ProxySettings, UserStore};
+use collections::HashMap;
+use db::AppDatabase;
+use extension::ExtensionHostProxy;
+use fs::RealFs;
+use gpui::http_client::read_proxy_from_env;
+use gpui::{App, AppContext as _, AsyncApp, Entity};
+use gpui_tokio::Tokio;
+use language::LanguageRegistry;
+use language_extension::LspAccess;
+use language_model::{LanguageModelId, LanguageModelProviderId, LanguageModelRegistry};
+use node_runtime::{NodeBinaryOptions, NodeRuntime};
+use project::Project;
+use project::project_settings::ProjectSettings;
+use prompt_store::PromptBuilder;
+use release_channel::AppVersion;
+use reqwest_client::ReqwestClient;
+use serde::Serialize;
+use settings::{Settings as _, SettingsStore};
+use util::ResultExt as _;
+use util::path_list::PathList;
+
+const EXIT_OK: i32 = 0;
+const EXIT_ERROR: i32 = 1;
+
+pub struct AgentRunOptions {
+    pub prompt: String,
+    /// The project's worktree roots. Defaults to the current directory.
+    pub paths: Vec<PathBuf>,
+    pub profile: Option<String>,
+    pub model: Option<String>,
+    pub permissions: AgentPermissionPolicy,
+    pub output: AgentOutputFormat,
+}
+
+struct AgentRunnerAppState {
+    languages: Arc<LanguageRegistry>,
+    client: Arc<Client>,
+    user_store: Entity<UserStore>,
+    fs: Arc<dyn fs::Fs>,
+    node_runtime: NodeRuntime,
+}
+
+#[derive(Serialize)]
+struct AgentRunTranscript {
+    status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    diff: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    thread: Option<serde_json::Value>,
+}
+
+pub fn run(options: AgentRunOptions, app_version: AppVersion) -> ! {
+    let http_client = Arc::new(ReqwestClient::new());
+    let app = gpui_platform::headless().with_http_client(http_client);
+
+    app.run(move |cx| {
+        let app_state = init(app_version, cx);
+        cx.spawn(async move |cx| {
+            let output = options.output;
+            let exit_code = match run_agent(&app_state, options, cx).await {
+                Ok(exit_code) => exit_code,
+                Err(error) => {
+                    match output {
+                        AgentOutputFormat::Diff => eprintln!("Error: {error:#}"),
+                        AgentOutputFormat::Json => print_transcript(&AgentRunTranscript {
+                            status: "error",
+                            error: Some(format!("{error:#}")),
+                            diff: String::new(),
+                            thread: None,
+                        }),
+                    }
+                    EXIT_ERROR
+                }
+            };
+            cx.update(|cx| cx.quit());
+            process::exit(exit_code);
+        })
+        .detach();
+    });
+
+    process::exit(EXIT_ERROR);
+}
+
+fn init(app_version: AppVersion, cx: &mut App) -> Arc<AgentRunnerAppState> {
+    release_channel::init(app_version.clone(), cx);
+    gpui_tokio::init(cx);
+
+    let mut settings_store = SettingsStore::new(cx, &settings::default_settings());
+    match std::fs::read_to_string(paths::settings_file()) {
+        Ok(user_settings) => {
+            settings_store
+                .set_user_settings(&user_settings, cx)
+                .log_err();
+        }
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+        Err(error) => eprintln!(
+            "Could not read {}: {error}",
+            paths::settings_file().display()
+        ),
+    }
+    cx.set_global(settings_store);
+
+    let user_agent = format!(
+        "Zed/{} ({}; {})",
+        app_version,
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+    let proxy_str = ProxySettings::get_global(cx).proxy.to_owned();
+    let proxy_url = proxy_str
+        .as_ref()
+        .and_then(|input| input.parse().ok())
+        .or_else(read_proxy_from_env);
+    let http = {
+        let _guard = Tokio::handle(cx).enter();
+        ReqwestClient::proxy_and_user_agent(proxy_url, &user_agent)
+            .expect("could not start HTTP client")
+    };
+    cx.set_http_client(Arc::new(http));
+
+    let client = Client::production(cx);
+    cx.set_http_client(client.http_client());
+
+    cx.set_global(AppDatabase::new());
+
+    let fs = Arc::new(RealFs::new(None, cx.background_executor().clone()));
+
+    let mut languages = LanguageRegistry::new(cx.background_executor().clone());
+    languages.set_language_server_download_dir(paths::languages_dir().clone());
+    let languages = Arc::new(languages);
+
+    let user_store = cx.new(|cx| UserStore::new(client.clone(), cx));
+
+    extension::init(cx);
+
+    let (mut node_options_tx, node_options_rx) = watch::channel(None);
+    cx.observe_global::<SettingsStore>(move |cx| {
+        let settings = &ProjectSettings::get_global(cx).node;
+        let options = NodeBinaryOptions {
+            allow_path_lookup: !settings.ignore_system_version,
+            allow_binary_download: true,
+            use_paths: settings.path.as_ref().map(|node_path| {
+                let node_path = PathBuf::from(shellexpand::tilde(node_path).as_ref());
+                let npm_path = settings
+                    .npm_path
+                    .as_ref()
+                    .map(|path| PathBuf::from(shellexpand::tilde(&path).as_ref()));
+                (
+                    node_path.clone(),
+                    npm_path.unwrap_or_else(|| {
+                        let base_path = PathBuf::new();
+                        node_path.parent().unwrap_or(&base_path).join("npm")
+                    }),
+                )
+            }),
+        };
+        node_options_tx.send(Some(options)).log_err();
+    })
+    .detach();
+    let node_runtime = NodeRuntime::new(client.http_client(), None, node_options_rx);
+
+    let extension_host_proxy = ExtensionHostProxy::global(cx);
+    debug_adapter_extension::init(extension_host_proxy.clone(), cx);
+    language_extension::init(LspAccess::Noop, extension_host_proxy, languages.clone());
+    language_model::init(user_store.clone(), client.clone(), cx);
+    language_models::init(user_store.clone(), client.clone(), cx);
+    languages::init(languages.clone(), fs.clone(), node_runtime.clone(), cx);
+    prompt_store::init(cx);
+    terminal_view::init(cx);
+
+    let prompt_builder = PromptBuilder::load(fs.clone(), false, cx);
+    agent_ui::init(
+        fs.clone(),
+        client.clone(),
+        prompt_builder,
+        languages.clone(),
+        true,
+        cx,
+    );
+
+    Arc::new(AgentRunnerAppState {
+        languages,
+        client,
+        user_store,
+        fs,
+        node_runtime,
+    })
+}
+
+async fn run_agent(
+    app_state: &AgentRunnerAppState,
+    options: AgentRunOptions,
+    cx: &mut AsyncApp,
+) -> Result<i32> {
+    let paths = if options.paths.is_empty() {
+        vec![std::env::current_dir().context("retrieving current directory")?]
+    } else {
+        options.paths.clone()
+    };
+    let paths = paths
+        .iter()
+        .map(|path| {
+            path.canonicalize()
+                .with_context(|| format!("invalid path {}", path.display()))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    let auth_tasks = cx.update(|cx| {
+        LanguageModelRegistry::global(cx).update(cx, |registry, cx| {
+            registry
+                .providers()
+                .iter()
+                .map(|provider| provider.authenticate(cx))
+                .collect::<Vec<_>>()
+        })
+    });
+    futures::future::join_all(auth_tasks).await;
+
+    let profile_id = cx.update(|cx| {
+        select_model(options.model.as_deref(), cx)?;
+        options
+            .profile
+            .as_deref()
+            .map(|profile| validate_profile(profile, cx))
+            .transpose()
+    })?;
+
+    let project = cx.update(|cx| {
+        Project::local(
+            app_state.client.clone(),
+            app_state.node_runtime.clone(),
+            app_state.user_store.clone(),
+            app_state.languages.clone(),
+            app_state.fs.clone(),
+            None,
+            project::LocalProjectFlags {
+                init_worktree_trust: false,
+                ..Default::default()
+            },
+            cx,
+        )
+    });
+    for path in &paths {
+        let worktree = project
+            .update(cx, |project, cx| project.create_worktree(path, true, cx))
+            .await
+            .with_context(|| format!("opening {}", path.display()))?;
+        let scan_complete = worktree.update(cx, |worktree, _| {
+            worktree
+                .as_local()
+                .context("expected a local worktree")
+                .map(|worktree| worktree.scan_complete())
+        })?;
+        scan_complete.await;
+    }
+
+    let agent = cx.update(|cx| {
+        let thread_store = cx.new(|cx| ThreadStore::new(cx));
+        NativeAgent::new(
+            thread_store,
+            Templates::new(),
+            None,
+            app_state.fs.clone(),
+            cx,
+        )
+    });
+    let connection = Rc::new(NativeAgentConnection(agent));
+    let acp_thread = cx
+        .update(|cx| {
+            connection
+                .clone()
+                .new_session(project, PathList::new(&paths), cx)
+        })
+        .await
+        .context("creating agent thread")?;
+    let thread = cx
+        .update(|cx| connection.thread(acp_thread.read(cx).session_id(), cx))
+        .context("agent thread not found")?;
+    if let Some(profile_id) = profile_id {
+        thread.update(cx, |thread, cx| thread.set_profile(profile_id, cx));
+    }
+
+    let mut reporter = ProgressReporter::new(options.output, options.permissions);
+    let _subscription = cx.subscribe(&acp_thread, move |acp_thread, event, cx| {
+        reporter.handle_event(acp_thread, event, cx);
+    });
+
+    let message = vec![acp::ContentBlock::Text(acp::TextContent::new(
+        options.prompt.clone(),
+    ))];
+    let response = acp_thread
+        .update(cx, |acp_thread, cx| acp_thread.send(message, cx))
+        .await;
+
+    let (status, error, exit_code) = run_status(&response);
+
+    let diff = cx.update(|cx| changes_as_diff(thread.read(cx), paths.len() > 1, cx));
+    match options.output {
+        AgentOutputFormat::Diff => {
+            if let Some(error) = &error {
+                eprintln!("Error: {error}");
+            }
+            print!("{diff}");
+        }
+        AgentOutputFormat::Json => {
+            let db_thread = thread.read_with(cx, |thread, cx| thread.to_db(cx)).await;
+            print_transcript(&AgentRunTranscript {
+                status,
+                error,
+                diff,
+                thread: serde_json::to_value(&db_thread).log_err(),
+            });
+        }
+    }
+
+    Ok(exit_code)
+}
+
+/// Returns the transcript status, error, and exit code of a run that ended with `response`.
+fn run_status(
+    response: &Result<Option<acp::PromptResponse>>,
+) -> (&'static str, Option<String>, i32) {
+    match response {
+        Ok(Some(response)) => match response.stop_reason {
+            acp::StopReason::EndTurn => ("completed", None, EXIT_OK),
+            acp::StopReason::MaxTokens => ("max_tokens", None, EXIT_ERROR),
+            acp::StopReason::MaxTurnRequests => ("max_turn_requests", None, EXIT_ERROR),
+            acp::StopReason::Refusal => ("refusal", None, EXIT_ERROR),
+            acp::StopReason::Cancelled => ("cancelled", None, EXIT_ERROR),
+            _ => ("stopped", None, EXIT_ERROR),
+        },
+        Ok(None) => ("completed", None, EXIT_OK),
+        Err(error) => ("error", Some(format!("{error:#}")), EXIT_ERROR),
+    }
+}
+
+/// Returns the option answering a permission request under `policy`.
+///
+/// Only one-shot options are picked, as the "always" ones would persist a change to the user's
+/// settings as a side effect of the run.
+fn permission_option(
+    policy: AgentPermissionPolicy,
+    options: &PermissionOptions,
+) -> Option<&acp::PermissionOption> {
+    let kind = match policy {
+        AgentPermissionPolicy::Allow => acp::PermissionOptionKind::AllowOnce,
+        AgentPermissionPolicy::Deny => acp::PermissionOptionKind::RejectOnce,
+    };
+    options.first_option_of_kind(kind)
+}
+
+fn select_model(model: Option<&str>, cx: &mut App) -> Result<()> {
+    let selected = match model {
+        Some(model) => {
+            language_model::SelectedModel::from_str(model).map_err(|e| anyhow!("{e}"))?
+        }
+        None => {
+            let selection = AgentSettings::get_global(cx)
+                .default_model
+                .clone()
+                .context("no agent model is configured; set `agent.default_model` or pass `--agent-model`")?;
+            language_model::SelectedModel {
+                provider: LanguageModelProviderId::from(selection.provider.0),
+                model: LanguageModelId::from(selection.model),
+            }
+        }
+    };
+
+    let registry = LanguageModelRegistry::global(cx);
+    let is_available = registry
+        .read(cx)
+        .available_models(cx)
+        .any(|model| model.id() == selected.model && model.provider_id() == selected.provider);
+    if !is_available {
+        let available = registry
+            .read(cx)
+            .available_models(cx)
+            .map(|model| format!("{}/{}", model.provider_id().0, model.id().0))
+            .collect::<Vec<_>>()
+            .join(", ");
+        anyhow::bail!(
+            "model {}/{} is not available. Available models: {available}",
+            selected.provider.0,
+            selected.model.0
+        );
+    }
+    registry.update(cx, |registry, cx| {
+        registry.select_default_model(Some(&selected), cx)
+    });
+    Ok(())
+}
+
+fn validate_profile(profile: &str, cx: &App) -> Result<AgentProfileId> {
+    let profiles = &AgentSettings::get_global(cx).profiles;
+    let profile_id = AgentProfileId(profile.into());
+    if !profiles.contains_key(&profile_id) {
+        let available = profiles
+            .keys()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        anyhow::bail!("unknown agent profile `{profile}`. Available profiles: {available}");
+    }
+    Ok(profile_id)
+}
+
+/// Returns the changes tracked by the thread's action log as a unified diff.
+fn changes_as_diff(thread: &Thread, include_worktree_name: bool, cx: &App) -> String {
+    let mut diff = String::new();
+    for (buffer, buffer_diff) in thread.action_log().read(cx).changed_buffers(cx) {
+        let buffer = buffer.read(cx);
+        let Some(file) = buffer.file() else {
+            continue;
+        };
+        let path = if include_worktree_name {
+            file.full_path(cx).to_string_lossy().replace('\\', "/")
+        } else {
+            file.path().as_unix_str().to_string()
+        };
+        let base_text = buffer_diff.read(cx).base_text_string(cx);
+        let is_deleted = file.disk_state().is_deleted();
+        let new_text = if is_deleted {
+            String::new()
+        } else {
+            buffer.text()
+        };
+
+        let hunks = language::unified_diff(base_text.as_deref().unwrap_or_default(), &new_text);
+        if hunks.is_empty() {
+            continue;
+        }
+        if base_text.is_some() {
+            writeln!(diff, "--- a/{path}").ok();
+        } else {
+            writeln!(diff, "--- /dev/null").ok();
+        }
+        if is_deleted {
+            writeln!(diff, "+++ /dev/null").ok();
+        } else {
+            writeln!(diff, "+++ b/{path}").ok();
+        }
+        diff.push_str(&hunks);
+        if !diff.ends_with('\n') {
+            diff.push('\n');
+        }
+    }
+    diff
+}
+
+fn print_transcript(transcript: &AgentRunTranscript) {
+    match serde_json::to_string_pretty(transcript) {
+        Ok(json) => println!("{json}"),
+        Err(error) => eprintln!("Error serializing transcript: {error:#}"),
+    }
+}
+
+/// Streams the thread's progress and answers permission requests.
+struct ProgressReporter {
+    output: AgentOutputFormat,
+    permissions: AgentPermissionPolicy,
+    /// The number of entries whose assistant messages have been printed.
+    printed_entries: usize,
+    tool_call_statuses: HashMap<acp::ToolCallId, String>,
+}
+
+impl ProgressReporter {
+    fn new(output: AgentOutputFormat, permissions: AgentPermissionPolicy) -> Self {
+        Self {
+            output,
+            permissions,
+            printed_entries: 0,
+            tool_call_statuses: HashMap::default(),
+        }
+    }
+
+    /// Progress goes to stdout, unless stdout is reserved for the JSON transcript.
+    fn print(&self, line: &str) {
+        match self.output {
+            AgentOutputFormat::Diff => println!("{line}"),
+            AgentOutputFormat::Json => eprintln!("{line}"),
+        }
+    }
+
+    fn handle_event(
+        &mut self,
+        acp_thread: Entity<AcpThread>,
+        event: &AcpThreadEvent,
+        cx: &mut App,
+    ) {
+        match event {
+            AcpThreadEvent::NewEntry => {
+                // Assistant messages are printed once they're complete, which is
+                // when a later entry is added or the thread stops.
+                let entry_count = acp_thread.read(cx).entries().len();
+                self.print_assistant_messages(&acp_thread, entry_count.saturating_sub(1), cx);
+            }
+            AcpThreadEvent::EntryUpdated(ix) => {
+                let acp_thread = acp_thread.read(cx);
+                if let Some(AgentThreadEntry::ToolCall(tool_call)) = acp_thread.entries().get(*ix) {
+                    let status = tool_call.status.to_string();
+                    if !matches!(tool_call.status, ToolCallStatus::Pending)
+                        && self.tool_call_statuses.get(&tool_call.id) != Some(&status)
+                    {
+                        self.print(&format!(
+                            "[tool] {} ({status})",
+                            tool_call.label.read(cx).source()
+                        ));
+                        self.tool_call_statuses.insert(tool_call.id.clone(), status);
+                    }
+                }
+            }
+            AcpThreadEvent::ToolAuthorizationRequested(tool_call_id) => {
+                self.answer_permission_request(acp_thread, tool_call_id, cx);
+            }
+            AcpThreadEvent::Retry(status) => {
+                self.print(&format!(
+                    "[retry] attempt {} of {}: {}",
+                    status.attempt, status.max_attempts, status.last_error
+                ));
+            }
+            AcpThreadEvent::Warning(warning) => {
+                self.print(&format!("[warning] {warning}"));
+            }
+            AcpThreadEvent::Stopped(_) | AcpThreadEvent::Error | AcpThreadEvent::Refusal => {
+                let entry_count = acp_thread.read(cx).entries().len();
+                self.print_assistant_messages(&acp_thread, entry_count, cx);
+            }
+            _ => {}
+        }
+    }
+
+    fn print_assistant_messages(&mut self, acp_thread: &Entity<AcpThread>, end: usize, cx: &App) {
+        let entries = acp_thread.read(cx).entries();
+        for entry in entries.get(self.printed_entries..end).unwrap_or_default() {
+            let AgentThreadEntry::AssistantMessage(message) = entry else {
+                continue;
+            };
+            for chunk in &message.chunks {
+                if let AssistantMessageChunk::Message {
+                    block: ContentBlock::Markdown { markdown },
+                } = chunk
+                {
+                    let text = markdown.read(cx).source().trim();
+                    if !text.is_empty() {
+                        self.print(text);
+                    }
+                }
+            }
+        }
+        self.printed_entries = self.printed_entries.max(end);
+    }
+
+    fn answer_permission_request(
+        &mut self,
+        acp_thread: Entity<AcpThread>,
+        tool_call_id: &acp::ToolCallId,
+        cx: &mut App,
+    ) {
+        let request = acp_thread.read(cx).entries().iter().find_map(|entry| {
+            let AgentThreadEntry::ToolCall(tool_call) = entry else {
+                return None;
+            };
+            let ToolCallStatus::WaitingForConfirmation { options, .. } = &tool_call.status else {
+                return None;
+            };
+            if &tool_call.id != tool_call_id {
+                return None;
+            }
+            let option = permission_option(self.permissions, options)?;
+            Some((
+                tool_call.label.read(cx).source().to_string(),
+                SelectedPermissionOutcome::new(option.option_id.clone(), option.kind),
+            ))
+        });
+
+        let Some((label, outcome)) = request else {
+            // Nothing else would answer the request, so stop rather than wait forever.
+            self.print(&format!(
+                "[permission] no option to answer tool call {}, cancelling",
+                tool_call_id.0
+            ));
+            acp_thread.update(cx, |acp_thread, cx| acp_thread.cancel(cx).detach());
+            return;
+        };
+        let verb = match self.permissions {
+            AgentPermissionPolicy::Allow => "allowed",
+            AgentPermissionPolicy::Deny => "denied",
+        };
+        self.print(&format!("[permission] {verb}: {label}"));
+        acp_thread.update(cx, |acp_thread, cx| {
+            acp_thread.authorize_tool_call(tool_call_id.clone(), outcome, cx);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option(id: &str, kind: acp::PermissionOptionKind) -> acp::PermissionOption {
+        acp::PermissionOption::new(
+            acp::PermissionOptionId::new(id.to_string()),
+            id.to_string(),
+            kind,
+        )
+    }
+
+    #[test]
+    fn test_permission_option_is_one_shot() {
+        let options = PermissionOptions::Flat(vec![
+            option("always_allow", acp::PermissionOptionKind::AllowAlways),
+            option("allow", acp::PermissionOptionKind::AllowOnce),
+            option("always_deny", acp::PermissionOptionKind::RejectAlways),
+            option("deny", acp::PermissionOptionKind::RejectOnce),
+        ]);
+        let picked = |policy| {
+            permission_option(policy, &options).map(|option| option.option_id.0.to_string())
+        };
+        assert_eq!(
+            picked(AgentPermissionPolicy::Allow).as_deref(),
+            Some("allow")
+        );
+        assert_eq!(picked(AgentPermissionPolicy::Deny).as_deref(), Some("deny"));
+
+        let always_only = PermissionOptions::Flat(vec![
+            option("always_allow", acp::PermissionOptionKind::AllowAlways),
+            option("always_deny", acp::PermissionOptionKind::RejectAlways),
+        ]);
+        assert!(permission_option(AgentPermissionPolicy::Allow, &always_only).is_none());
+        assert!(permission_option(AgentPermissionPolicy::Deny, &always_only).is_none());
+    }
+
+    #[test]
+    fn test_run_status() {
+        let stopped = |reason| run_status(&Ok(Some(acp::PromptResponse::new(reason))));
+        assert_eq!(
+            stopped(acp::StopReason::EndTurn),
+            ("completed", None, EXIT_OK)
+        );
+        assert_eq!(
+            stopped(acp::StopReason::MaxTokens),
+            ("max_tokens", None, EXIT_ERROR)
+        );
+        assert_eq!(
+            stopped(acp::StopReason::Cancelled),
+            ("cancelled", None, EXIT_ERROR)
+        );
+        assert_eq!(run_status(&Ok(None)), ("completed", None, EXIT_OK));
+        assert_eq!(
+            run_status(&Err(anyhow!("provider is down"))),
+            ("error", Some("provider is down".to_string()), EXIT_ERROR)
+        );
+    }
+}
@@ -1,6 +1,7 @@
 // Disable command line from opening on release mode
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod agent_runner;
 mod reliability;
 mod zed;
 
@@ -182,6 +183,15 @@ fn main() {
     #[cfg(unix)]
     util::prevent_root_execution();
 
+    // `zed --agent-sandbox` Makes zed operate as the sandbox helper for agent terminal commands.
+    // This is checked before parsing `Args`, as everything after `--` belongs to the sandboxed command.
+    if std::env::args_os()
+        .nth(1)
+        .is_some_and(|arg| arg == sandbox::HELPER_FLAG)
+    {
+        sandbox::main(std::env::args_os().skip(1));
+    }
+
     let args = Args::parse();
 
     // `zed --askpass` Makes zed operate in nc/netcat mode for use with askpass
@@ -274,6 +284,33 @@ fn main() {
         return;
     }
 
+    // `zed --agent` runs the agent headlessly. Logs go to the log file so that
+    // stdout only contains the agent's output.
+    if let Some(prompt) = args.agent.clone() {
+        zlog::init();
+        if let Err(err) = zlog::init_output_file(paths::log_file(), Some(paths::old_log_file())) {
+            eprintln!("Could not open log file: {}", err);
+        }
+        let app_commit_sha = option_env!("ZED_COMMIT_SHA")
+            .map(|commit_sha| AppCommitSha::new(commit_sha.to_string()));
+        let app_version = AppVersion::load(
+            env!("CARGO_PKG_VERSION"),
+            option_env!("ZED_BUILD_ID"),
+            app_commit_sha,
+        );
+        agent_runner::run(
+            agent_runner::AgentRunOptions {
+                prompt,
+                paths: args.paths_or_urls.iter().map(PathBuf::from).collect(),
+                profile: args.agent_profile.clone(),
+                model: args.agent_model.clone(),
+                permissions: args.agent_permissions,
+                output: args.agent_output,
+            },
+            app_version,
+        );
+    }
+
     zlog::init();
 
     if stdout_is_a_pty() {
@@ -464,6 +501,20 @@ fn main() {
             }
         };
         trusted_worktrees::init(db_trusted_paths, cx);
+        let db = workspace::WorkspaceDb::global(cx);
+        let grants = db.fetch_trust_grants().unwrap_or_else(|e| {
+            log::error!("Failed to do initial trust grants fetch: {e:#}");
+            HashMap::default()
+        });
+        let trusted_configs = db.fetch_trusted_configs().unwrap_or_else(|e| {
+            log::error!("Failed to do initial trusted configs fetch: {e:#}");
+            HashMap::default()
+        });
+        if let Some(trusted_worktrees) = trusted_worktrees::TrustedWorktrees::try_get_global(cx) {
+            trusted_worktrees.update(cx, |trusted_worktrees, _| {
+                trusted_worktrees.restore_grants(grants, trusted_configs)
+            });
+        }
         menu::init();
         zed_actions::init();
 
@@ -695,6 +746,8 @@ fn main() {
 
         editor::init(cx);
         image_viewer::init(cx);
+        large_file_viewer::init(cx);
+        hex_editor::init(cx);
         repl::notebook::init(cx);
         diagnostics::init(cx);
 
@@ -707,10 +760,16 @@ fn main() {
         tab_switcher::init(cx);
         outline::init(cx);
         project_symbols::init(cx);
+        bookmarks::init(cx);
+        file_history::init(cx);
+        keyboard_macros::init(cx);
         project_panel::init(cx);
         outline_panel::init(cx);
+        ports_panel::init(cx);
+        review_threads::init(cx);
         tasks_ui::init(cx);
         snippets_ui::init(cx);
+        spell_check::init(cx);
         channel::init(&app_state.client.clone(), app_state.user_store.clone(), cx);
         search::init(cx);
         cx.set_global(workspace::PaneSearchBarCallbacks {
@@ -727,6 +786,7 @@ fn main() {
         journal::init(app_state.clone(), cx);
         encoding_selector::init(cx);
         language_selector::init(cx);
+        layout_selector::init(cx);
         line_ending_selector::init(cx);
         toolchain_selector::init(cx);
         theme_selector::init(cx);
@@ -1645,6 +1705,29 @@ struct Args {
     #[arg(long)]
     system_specs: bool,
 
+    /// Run the agent with this prompt against the given paths without opening a window.
+    ///
+    /// Tool calls are streamed to stdout, followed by the changes the agent made.
+    #[arg(long, value_name = "PROMPT", allow_hyphen_values = true)]
+    agent: Option<String>,
+
+    /// The agent profile to use with `--agent`. Defaults to the `agent.default_profile` setting.
+    #[arg(long, requires = "agent", value_name = "PROFILE")]
+    agent_profile: Option<String>,
+
+    /// The language model to use with `--agent`, in `provider/model` format.
+    /// Defaults to the `agent.default_model` setting.
+    #[arg(long, requires = "agent", value_name = "PROVIDER/MODEL")]
+    agent_model: Option<String>,
+
+    /// How to answer tool calls that need confirmation when running with `--agent`.
+    #[arg(long, requires = "agent", value_enum, default_value_t)]
+    agent_permissions: cli::AgentPermissionPolicy,
+
+    /// What to print when the agent finishes when running with `--agent`.
+    #[arg(long, requires = "agent", value_enum, default_value_t)]
+    agent_output: cli::AgentOutputFormat,
+
     /// Used for the MCP Server, to remove the need for netcat as a dependency,
     /// by having Zed act like netcat communicating over a Unix socket.
     #[arg(long, hide = true)]
@@ -34,9 +34,9 @@ use git_ui::git_panel::GitPanel;
 use git_ui::project_diff::{BranchDiffToolbar, ProjectDiffToolbar};
 use gpui::{
     Action, App, AppContext as _, AsyncWindowContext, Context, DismissEvent, Element, Entity,
-    Focusable, KeyBinding, ParentElement, PathPromptOptions, PromptLevel, ReadGlobal, SharedString,
-    Task, TitlebarOptions, UpdateGlobal, WeakEntity, Window, WindowHandle, WindowKind,
-    WindowOptions, actions, image_cache, point, px, retain_all,
+    Focusable, Global, KeyBinding, ParentElement, PathPromptOptions, PromptLevel, ReadGlobal,
+    SharedString, Task, TitlebarOptions, UpdateGlobal, WeakEntity, Window, WindowHandle,
+    WindowKind, WindowOptions, actions, image_cache, point, px, retain_all,
 };
 use image_viewer::ImageInfo;
 use language::Capability;
@@ -51,22 +51,26 @@ use onboarding::multibuffer_hint::MultibufferHint;
 pub use open_listener::*;
 use outline_panel::OutlinePanel;
 use paths::{
-    local_debug_file_relative_path, local_settings_file_relative_path,
-    local_tasks_file_relative_path,
+    local_debug_file_relative_path, local_keymap_file_relative_path,
+    local_settings_file_relative_path, local_tasks_file_relative_path,
+};
+use ports_panel::PortsPanel;
+use project::{
+    DirectoryLister, DisableAiSettings, Project, ProjectItem, project_settings::LocalKeymap,
 };
-use project::{DirectoryLister, DisableAiSettings, ProjectItem};
 use project_panel::ProjectPanel;
 use prompt_store::PromptBuilder;
 use quick_action_bar::QuickActionBar;
 use recent_projects::open_remote_project;
 use release_channel::{AppCommitSha, AppVersion, ReleaseChannel};
+use review_threads::ReviewThreadsPanel;
 use rope::Rope;
 use search::project_search::ProjectSearchBar;
 use settings::{
     BaseKeymap, DEFAULT_KEYMAP_PATH, InvalidSettingsError, KeybindSource, KeymapFile,
-    KeymapFileLoadResult, MigrationStatus, Settings, SettingsStore, VIM_KEYMAP_PATH,
-    initial_local_debug_tasks_content, initial_project_settings_content, initial_tasks_content,
-    update_settings_file,
+    KeymapFileLoadResult, MigrationStatus, ProjectKeymapFiles, Settings, SettingsStore,
+    VIM_KEYMAP_PATH, initial_local_debug_tasks_content, initial_project_keymap_content,
+    initial_project_settings_content, initial_tasks_content, update_settings_file,
 };
 use sidebar::Sidebar;
 
@@ -79,7 +83,7 @@ use std::{
 use terminal_view::terminal_panel::{self, TerminalPanel};
 use theme::{ActiveTheme, GlobalTheme, SystemAppearance, ThemeRegistry, ThemeSettings};
 use ui::{PopoverMenuHandle, prelude::*};
-use util::markdown::MarkdownString;
+use util::markdown::{MarkdownEscaped, MarkdownInlineCode, MarkdownString};
 use util::rel_path::RelPath;
 use util::{ResultExt, asset_str, maybe};
 use uuid::Uuid;
@@ -87,8 +91,8 @@ use vim_mode_setting::VimModeSetting;
 use workspace::notifications::{NotificationId, dismiss_app_notification, show_app_notification};
 
 use workspace::{
-    AppState, MultiWorkspace, NewFile, NewWindow, OpenLog, Panel, Toast, Workspace,
-    WorkspaceSettings, create_and_open_local_file,
+    AppState, MultiWorkspace, MultiWorkspaceEvent, NewFile, NewWindow, OpenLog, Panel, Toast,
+    Workspace, WorkspaceSettings, create_and_open_local_file,
     notifications::simple_message_notification::MessageNotification, open_new,
 };
 use workspace::{
@@ -117,6 +121,8 @@ actions!(
         OpenProjectSettingsFile,
         /// Opens the project tasks configuration.
         OpenProjectTasks,
+        /// Opens the project-specific keymap file.
+        OpenProjectKeymap,
         /// Opens the tasks panel.
         OpenTasks,
         /// Opens debug tasks configuration.
@@ -410,8 +416,30 @@ pub fn initialize_workspace(
                 .unwrap_or(true)
         });
 
-        let window_handle = window.window_handle();
         let multi_workspace_handle = cx.entity();
+        // Project keymaps only apply while their project's window is focused.
+        cx.observe_window_activation(window, |multi_workspace, window, cx| {
+            if window.is_window_active() {
+                let project = multi_workspace.workspace().read(cx).project().clone();
+                load_project_keymaps(&project, cx);
+            }
+        })
+        .detach();
+        cx.subscribe_in(
+            &multi_workspace_handle,
+            window,
+            |multi_workspace, _, event: &MultiWorkspaceEvent, window, cx| {
+                if let MultiWorkspaceEvent::ActiveWorkspaceChanged = event
+                    && window.is_window_active()
+                {
+                    let project = multi_workspace.workspace().read(cx).project().clone();
+                    load_project_keymaps(&project, cx);
+                }
+            },
+        )
+        .detach();
+
+        let window_handle = window.window_handle();
         cx.defer(move |cx| {
             window_handle
                 .update(cx, |_, window, cx| {
@@ -450,6 +478,25 @@ pub fn initialize_workspace(
         })
         .detach();
 
+        cx.subscribe_in(
+            workspace.project(),
+            window,
+            |_, project, event, window, cx| {
+                if let project::Event::LocalKeymapsUpdated = event
+                    && window.is_window_active()
+                    && window
+                        .root::<MultiWorkspace>()
+                        .flatten()
+                        .is_some_and(|multi_workspace| {
+                            multi_workspace.read(cx).workspace() == &cx.entity()
+                        })
+                {
+                    load_project_keymaps(project, cx);
+                }
+            },
+        )
+        .detach();
+
         #[cfg(not(any(test, target_os = "macos")))]
         initialize_file_watcher(window, cx);
 
@@ -491,6 +538,8 @@ pub fn initialize_workspace(
         let active_toolchain_language =
             cx.new(|cx| toolchain_selector::ActiveToolchain::new(workspace, window, cx));
         let vim_mode_indicator = cx.new(|cx| vim::ModeIndicator::new(window, cx));
+        let macro_recording_indicator =
+            cx.new(|cx| keyboard_macros::RecordingIndicator::new(window, cx));
         let image_info = cx.new(|_cx| ImageInfo::new(workspace));
 
         let lsp_button_menu_handle = PopoverMenuHandle::default();
@@ -516,6 +565,7 @@ pub fn initialize_workspace(
             status_bar.add_right_item(active_buffer_language, window, cx);
             status_bar.add_right_item(active_toolchain_language, window, cx);
             status_bar.add_right_item(line_ending_indicator, window, cx);
+            status_bar.add_right_item(macro_recording_indicator, window, cx);
             status_bar.add_right_item(vim_mode_indicator, window, cx);
             status_bar.add_right_item(cursor_position, window, cx);
             status_bar.add_right_item(image_info, window, cx);
@@ -660,6 +710,8 @@ fn initialize_panels(
             workspace_handle.clone(),
             cx.clone(),
         );
+        let ports_panel = PortsPanel::load(workspace_handle.clone(), cx.clone());
+        let review_threads_panel = ReviewThreadsPanel::load(workspace_handle.clone(), cx.clone());
         let debug_panel = DebugPanel::load(workspace_handle.clone(), cx);
 
         async fn add_panel_when_ready(
@@ -684,6 +736,8 @@ fn initialize_panels(
             add_panel_when_ready(git_panel, workspace_handle.clone(), cx.clone()),
             add_panel_when_ready(channels_panel, workspace_handle.clone(), cx.clone()),
             add_panel_when_ready(notification_panel, workspace_handle.clone(), cx.clone()),
+            add_panel_when_ready(ports_panel, workspace_handle.clone(), cx.clone()),
+            add_panel_when_ready(review_threads_panel, workspace_handle.clone(), cx.clone()),
             add_panel_when_ready(debug_panel, workspace_handle.clone(), cx.clone()),
             initialize_agent_panel(workspace_handle, prompt_builder, cx.clone()).map(|r| r.log_err()),
         );
@@ -1025,6 +1079,7 @@ fn register_actions(
         })
         .register_action(open_project_settings_file)
         .register_action(open_project_tasks_file)
+        .register_action(open_project_keymap_file)
         .register_action(open_project_debug_tasks_file)
         .register_action(
             |workspace: &mut Workspace,
@@ -1667,6 +1722,8 @@ pub fn handle_keymap_file_changes(
 ) {
     let (base_keymap_tx, mut base_keymap_rx) = mpsc::unbounded();
     let (keyboard_layout_tx, mut keyboard_layout_rx) = mpsc::unbounded();
+    let (project_keymaps_tx, mut project_keymaps_rx) = mpsc::unbounded();
+    cx.set_global(ProjectKeymapsChannel(project_keymaps_tx));
     let mut old_base_keymap = *BaseKeymap::get_global(cx);
     let mut old_vim_enabled = VimModeSetting::get_global(cx).0;
     let mut old_helix_enabled = vim_mode_setting::HelixModeSetting::get_global(cx).0;
@@ -1723,11 +1780,20 @@ pub fn handle_keymap_file_changes(
     cx.spawn(async move |cx| {
         let _user_keymap_watcher = user_keymap_watcher;
         let mut user_keymap_content = String::new();
+        let mut project_keymaps = Vec::new();
         let mut migrating_in_memory = false;
         loop {
             select_biased! {
                 _ = base_keymap_rx.next() => {},
                 _ = keyboard_layout_rx.next() => {},
+                keymaps = project_keymaps_rx.next() => {
+                    if let Some(keymaps) = keymaps {
+                        if keymaps == project_keymaps {
+                            continue;
+                        }
+                        project_keymaps = keymaps;
+                    }
+                }
                 content = user_keymap_file_rx.next() => {
                     if let Some(content) = content {
                         if let Ok(Some(migrated_content)) = migrate_keymap(&content) {
@@ -1752,7 +1818,7 @@ pub fn handle_keymap_file_changes(
                 let load_result = KeymapFile::load(&user_keymap_content, cx);
                 match load_result {
                     KeymapFileLoadResult::Success { key_bindings } => {
-                        reload_keymaps(cx, key_bindings);
+                        reload_keymaps(cx, key_bindings, &project_keymaps);
                         dismiss_app_notification(&notification_id.clone(), cx);
                     }
                     KeymapFileLoadResult::SomeFailedToLoad {
@@ -1760,7 +1826,7 @@ pub fn handle_keymap_file_changes(
                         error_message,
                     } => {
                         if !key_bindings.is_empty() {
-                            reload_keymaps(cx, key_bindings);
+                            reload_keymaps(cx, key_bindings, &project_keymaps);
                         }
                         show_keymap_file_load_error(notification_id.clone(), error_message, cx);
                     }
@@ -1774,6 +1840,80 @@ pub fn handle_keymap_file_changes(
     .detach();
 }
 
+/// Delivers the project keymaps of the focused window to [`handle_keymap_file_changes`].
+struct ProjectKeymapsChannel(mpsc::UnboundedSender<Vec<LocalKeymap>>);
+
+impl Global for ProjectKeymapsChannel {}
+
+fn load_project_keymaps(project: &Entity<Project>, cx: &App) {
+    if let Some(channel) = cx.try_global::<ProjectKeymapsChannel>() {
+        channel
+            .0
+            .unbounded_send(project.read(cx).local_keymaps(cx))
+            .ok();
+    }
+}
+
+/// Loads the bindings of the project keymaps, reporting the errors in them.
+fn load_project_key_bindings(project_keymaps: &[LocalKeymap], cx: &mut App) -> Vec<KeyBinding> {
+    struct ProjectKeymapParseErrorNotification;
+
+    let mut project_key_bindings = Vec::new();
+    for (file_ix, project_keymap) in project_keymaps.iter().enumerate() {
+        let notification_id = NotificationId::composite::<ProjectKeymapParseErrorNotification>(
+            SharedString::from(project_keymap.abs_path.to_string_lossy().into_owned()),
+        );
+        let file_name = format!(
+            "project keymap file {}",
+            MarkdownInlineCode(&project_keymap.abs_path.to_string_lossy())
+        );
+        let mut key_bindings = match KeymapFile::load_named(&project_keymap.content, &file_name, cx)
+        {
+            KeymapFileLoadResult::Success { key_bindings } => {
+                dismiss_app_notification(&notification_id, cx);
+                key_bindings
+            }
+            KeymapFileLoadResult::SomeFailedToLoad {
+                key_bindings,
+                error_message,
+            } => {
+                show_markdown_app_notification(
+                    notification_id,
+                    error_message,
+                    "Open Project Keymap".into(),
+                    |window, cx| {
+                        window.dispatch_action(OpenProjectKeymap.boxed_clone(), cx);
+                        cx.emit(DismissEvent);
+                    },
+                    cx,
+                );
+                key_bindings
+            }
+            KeymapFileLoadResult::JsonParseFailure { error } => {
+                show_markdown_app_notification(
+                    notification_id,
+                    MarkdownString(format!(
+                        "JSON parse error in {file_name}. Its bindings are not loaded.\n\n{}",
+                        MarkdownEscaped(&error.to_string())
+                    )),
+                    "Open Project Keymap".into(),
+                    |window, cx| {
+                        window.dispatch_action(OpenProjectKeymap.boxed_clone(), cx);
+                        cx.emit(DismissEvent);
+                    },
+                    cx,
+                );
+                Vec::new()
+            }
+        };
+        for key_binding in &mut key_bindings {
+            key_binding.set_meta(KeybindSource::project_meta(file_ix));
+        }
+        project_key_bindings.extend(key_bindings);
+    }
+    project_key_bindings
+}
+
 fn show_keymap_file_json_error(
     notification_id: NotificationId,
     error: &anyhow::Error,
@@ -1844,7 +1984,13 @@ fn show_markdown_app_notification<F>(
     })
 }
 
-fn reload_keymaps(cx: &mut App, mut user_key_bindings: Vec<KeyBinding>) {
+fn reload_keymaps(
+    cx: &mut App,
+    mut user_key_bindings: Vec<KeyBinding>,
+    project_keymaps: &[LocalKeymap],
+) {
+    let project_key_bindings = load_project_key_bindings(project_keymaps, cx);
+
     cx.clear_key_bindings();
     load_default_keymap(cx);
 
@@ -1852,6 +1998,14 @@ fn reload_keymaps(cx: &mut App, mut user_key_bindings: Vec<KeyBinding>) {
         key_binding.set_meta(KeybindSource::User.meta());
     }
     cx.bind_keys(user_key_bindings);
+    // Bound last, so they take precedence over the user bindings.
+    cx.bind_keys(project_key_bindings);
+    cx.set_global(ProjectKeymapFiles(
+        project_keymaps
+            .iter()
+            .map(|project_keymap| project_keymap.abs_path.clone())
+            .collect(),
+    ));
 
     let menus = app_menus(cx);
     cx.set_menus(menus);
@@ -1942,6 +2096,21 @@ fn open_project_tasks_file(
     )
 }
 
+fn open_project_keymap_file(
+    workspace: &mut Workspace,
+    _: &OpenProjectKeymap,
+    window: &mut Window,
+    cx: &mut Context<Workspace>,
+) {
+    open_local_file(
+        workspace,
+        local_keymap_file_relative_path(),
+        initial_project_keymap_content(),
+        window,
+        cx,
+    )
+}
+
 fn open_project_debug_tasks_file(
     workspace: &mut Workspace,
     _: &zed_actions::OpenProjectDebugTasks,
@@ -4760,6 +4929,7 @@ mod tests {
                 "assistant",
                 "assistant2",
                 "auto_update",
+                "bookmarks",
                 "branch_picker",
                 "bedrock",
                 "branches",
@@ -4783,20 +4953,25 @@ mod tests {
                 "encoding_selector",
                 "feedback",
                 "file_finder",
+                "file_history",
                 "git",
                 "git_graph",
                 "git_onboarding",
                 "git_panel",
                 "git_picker",
                 "go_to_line",
+                "hex_editor",
                 "highlights_tree_view",
                 "icon_theme_selector",
                 "image_viewer",
                 "inline_assistant",
                 "journal",
+                "keyboard_macros",
                 "keymap_editor",
                 "keystroke_input",
                 "language_selector",
+                "large_file_viewer",
+                "layout_selector",
                 "welcome",
                 "line_ending_selector",
                 "lsp_tool",
@@ -4812,6 +4987,7 @@ mod tests {
                 "pane",
                 "panel",
                 "picker",
+                "ports_panel",
                 "project_panel",
                 "project_search",
                 "project_symbols",
@@ -4819,6 +4995,7 @@ mod tests {
                 "recent_projects",
                 "remote_debug",
                 "repl",
+                "review_threads",
                 "rules_library",
                 "search",
                 "settings_editor",
@@ -805,3 +805,15 @@ pub mod notebook {
         ]
     );
 }
+
+pub mod hex_editor {
+    use gpui::actions;
+
+    actions!(
+        hex_editor,
+        [
+            /// Opens the active file in the hex editor.
+            OpenInHexEditor,
+        ]
+    );
+}